@@ -0,0 +1,155 @@
+use crate::Page;
+use crate::errors::Error;
+use crate::handlers::extract::Theme;
+use crate::handlers::html::make_error;
+use crate::limits::{Limiter, Network};
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::Method;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+/// Networks allowed or denied access to a group of routes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct Rules {
+    /// If not empty, only these networks have access.
+    pub allow: Vec<Network>,
+    /// Networks without access, even if allowed.
+    pub deny: Vec<Network>,
+}
+
+/// Rules for creating pastes and for all other requests.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct Access {
+    pub create: Rules,
+    pub read: Rules,
+}
+
+/// State of the [`filter`] middleware.
+#[derive(Clone)]
+pub(crate) struct Filter {
+    pub access: Arc<Access>,
+    pub limiter: Limiter,
+    pub page: Page,
+}
+
+impl Rules {
+    /// Return `true` if `ip` is not denied and allowed if an allowlist is set.
+    pub fn permits(&self, ip: IpAddr) -> bool {
+        (self.allow.is_empty() || self.allow.iter().any(|net| net.contains(ip)))
+            && !self.deny.iter().any(|net| net.contains(ip))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+}
+
+impl Access {
+    pub fn is_empty(&self) -> bool {
+        self.create.is_empty() && self.read.is_empty()
+    }
+}
+
+/// Return `true` if `request` creates a paste via the API or the form, or otherwise writes like
+/// previewing, editing or changing the language of a paste.
+fn creates(request: &Request) -> bool {
+    let path = request.uri().path();
+
+    request.method() == Method::POST
+        && (matches!(path, "/" | "/new" | "/preview")
+            || path.starts_with("/edit/")
+            || path.starts_with("/lang/"))
+}
+
+/// Reject requests from client addresses the rules of their route group do not permit. The
+/// address is determined like for [`crate::handlers::extract::ClientIp`].
+pub(crate) async fn filter(
+    State(filter): State<Filter>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    theme: Option<Theme>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let rules = if creates(&request) {
+        &filter.access.create
+    } else {
+        &filter.access.read
+    };
+
+    let ip = filter.limiter.client(request.headers(), peer.ip());
+
+    if !rules.permits(ip) {
+        tracing::debug!("denied {} {} from {ip}", request.method(), request.uri());
+        return make_error(Error::AddressDenied, filter.page, theme).into_response();
+    }
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+
+    #[test]
+    fn rules() {
+        let ip = "10.1.2.3".parse().unwrap();
+        assert!(Rules::default().permits(ip));
+
+        let rules = Rules {
+            allow: vec!["10.0.0.0/8".parse().unwrap()],
+            deny: vec!["10.1.0.0/16".parse().unwrap()],
+        };
+        assert!(!rules.permits(ip));
+        assert!(rules.permits("10.2.0.1".parse().unwrap()));
+        assert!(!rules.permits("192.168.0.1".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn route_groups() -> Result<(), Box<dyn std::error::Error>> {
+        let access = Access {
+            create: Rules {
+                deny: vec!["127.0.0.0/8".parse()?],
+                ..Default::default()
+            },
+            read: Rules::default(),
+        };
+        let client = Client::with_access(StoreCookies(false), access).await;
+        let data = Entry {
+            text: String::from("FooBarBaz"),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert_eq!(client.get("/").send().await?.status(), StatusCode::OK);
+
+        for path in ["/preview", "/edit/AAAAAA", "/lang/AAAAAA"] {
+            let res = client.post(path).form(&data).send().await?;
+            assert_eq!(res.status(), StatusCode::FORBIDDEN);
+            assert!(res.text().await?.contains("access from your address"));
+        }
+
+        let access = Access {
+            create: Rules::default(),
+            read: Rules {
+                allow: vec!["10.0.0.0/8".parse()?],
+                ..Default::default()
+            },
+        };
+        let client = Client::with_access(StoreCookies(false), access).await;
+
+        let res = client.post_form().form(&data).send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            client.get("/").send().await?.status(),
+            StatusCode::FORBIDDEN
+        );
+
+        Ok(())
+    }
+}
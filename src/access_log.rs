@@ -0,0 +1,470 @@
+use crate::id::Id;
+use crate::limits::Limiter;
+use axum::body::{Body, Bytes, HttpBody};
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::HeaderMap;
+use axum::http::header::{HeaderName, REFERER, USER_AGENT};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::fs::{File, OpenOptions};
+use std::io::{LineWriter, Write};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+use time::macros::format_description;
+use tokio_stream::Stream;
+
+/// Number of lines buffered for the writer thread before further lines are dropped.
+const CAPACITY: usize = 4096;
+
+/// Format of access log lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Format {
+    /// Combined log format as written by Apache and nginx, followed by the latency in seconds.
+    #[default]
+    Combined,
+    /// One JSON object per request.
+    Json,
+}
+
+/// Where access log lines go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Target {
+    Stdout,
+    File(PathBuf),
+}
+
+enum Sink {
+    Stdout,
+    File(LineWriter<File>),
+}
+
+/// Writer of one line per handled request. Lines are handed to a dedicated thread, so a slow
+/// disk or terminal never blocks request handling. If that thread falls behind, lines are dropped
+/// and counted instead.
+#[derive(Clone)]
+pub(crate) struct AccessLog {
+    format: Format,
+    sender: SyncSender<String>,
+    dropped: Arc<AtomicU64>,
+}
+
+/// Middleware state.
+#[derive(Clone)]
+pub(crate) struct Logger {
+    pub log: Option<AccessLog>,
+    pub limiter: Limiter,
+}
+
+/// Request details written to the log.
+struct Line {
+    time: OffsetDateTime,
+    client: IpAddr,
+    method: String,
+    path: String,
+    version: String,
+    status: u16,
+    bytes: Option<u64>,
+    latency: Duration,
+    referer: Option<String>,
+    user_agent: Option<String>,
+}
+
+impl Sink {
+    fn write(&mut self, line: &str) -> std::io::Result<()> {
+        match self {
+            Self::Stdout => writeln!(std::io::stdout().lock(), "{line}"),
+            Self::File(file) => writeln!(file, "{line}"),
+        }
+    }
+}
+
+impl AccessLog {
+    /// Open the log writing `format` lines to `target`, appending to an existing file.
+    pub fn open(target: &Target, format: Format) -> std::io::Result<Self> {
+        let mut sink = match target {
+            Target::Stdout => Sink::Stdout,
+            Target::File(path) => Sink::File(open(path)?),
+        };
+
+        let (sender, receiver) = mpsc::sync_channel::<String>(CAPACITY);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&dropped);
+
+        std::thread::Builder::new()
+            .name(String::from("access-log"))
+            .spawn(move || {
+                for line in receiver {
+                    if let Err(err) = sink.write(&line) {
+                        tracing::warn!("failed to write access log: {err}");
+                    }
+
+                    let dropped = counter.swap(0, Ordering::Relaxed);
+
+                    if dropped > 0 {
+                        tracing::warn!(
+                            "dropped {dropped} access log lines while writing fell behind"
+                        );
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            format,
+            sender,
+            dropped,
+        })
+    }
+
+    fn write(&self, line: &Line) {
+        let line = match self.format {
+            Format::Combined => line.combined(),
+            Format::Json => line.json(),
+        };
+
+        match self.sender.try_send(line) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                tracing::warn!("failed to write access log: writer thread stopped");
+            }
+        }
+    }
+}
+
+fn open(path: &Path) -> std::io::Result<LineWriter<File>> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    Ok(LineWriter::new(file))
+}
+
+/// Hide the host part of `ip` by zeroing the last octet of IPv4 and all but the first 48 bits of
+/// IPv6 addresses.
+fn anonymize(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            IpAddr::from([a, b, c, 0])
+        }
+        IpAddr::V6(ip) => {
+            let [a, b, c, ..] = ip.segments();
+            IpAddr::from([a, b, c, 0, 0, 0, 0, 0])
+        }
+    }
+}
+
+/// `path` with the capability tokens of secret pastes, the part after the `_` of segments like
+/// `<id>_<token>.rs`, replaced, so that the log does not store working secret URLs.
+pub(crate) fn redact(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.split_once('_') {
+            Some((id, rest)) if id.parse::<Id>().is_ok() => match rest.split_once('.') {
+                Some((_, ext)) => format!("{id}_redacted.{ext}"),
+                None => format!("{id}_redacted"),
+            },
+            _ => segment.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `referer` without query and fragment and with tokens in its path redacted, or `None` if it is
+/// not a URL.
+fn redact_referer(referer: &str) -> Option<String> {
+    let mut url = url::Url::parse(referer).ok()?;
+    url.set_query(None);
+    url.set_fragment(None);
+    let path = redact(url.path());
+    url.set_path(&path);
+
+    Some(url.to_string())
+}
+
+/// Quote `value` for the combined log format.
+fn quoted(value: Option<&str>) -> String {
+    value.map_or_else(
+        || String::from("\"-\""),
+        |value| format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")),
+    )
+}
+
+impl Line {
+    fn combined(&self) -> String {
+        let time = self
+            .time
+            .format(format_description!(
+                "[day]/[month repr:short]/[year]:[hour]:[minute]:[second] [offset_hour sign:mandatory][offset_minute]"
+            ))
+            .unwrap_or_default();
+
+        let bytes = self
+            .bytes
+            .map_or_else(|| String::from("-"), |bytes| bytes.to_string());
+
+        format!(
+            "{client} - - [{time}] \"{method} {path} {version}\" {status} {bytes} {referer} {user_agent} {latency:.6}",
+            client = self.client,
+            method = self.method,
+            path = self.path,
+            version = self.version,
+            status = self.status,
+            referer = quoted(self.referer.as_deref()),
+            user_agent = quoted(self.user_agent.as_deref()),
+            latency = self.latency.as_secs_f64(),
+        )
+    }
+
+    fn json(&self) -> String {
+        let time = self
+            .time
+            .format(&time::format_description::well_known::Rfc3339)
+            .unwrap_or_default();
+
+        serde_json::json!({
+            "timestamp": time,
+            "client": self.client.to_string(),
+            "method": self.method,
+            "path": self.path,
+            "version": self.version,
+            "status": self.status,
+            "bytes": self.bytes,
+            "latency": self.latency.as_secs_f64(),
+            "referer": self.referer,
+            "user_agent": self.user_agent,
+        })
+        .to_string()
+    }
+}
+
+fn header(headers: &HeaderMap, name: &HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
+/// Response body stream counting the bytes sent, which writes the line once the body is done or
+/// dropped, as streamed bodies do not know their size beforehand.
+struct Counted {
+    inner: axum::body::BodyDataStream,
+    log: AccessLog,
+    line: Option<Line>,
+    start: Instant,
+    bytes: u64,
+}
+
+impl Counted {
+    fn finish(&mut self) {
+        if let Some(mut line) = self.line.take() {
+            line.bytes = Some(self.bytes);
+            line.latency = self.start.elapsed();
+            self.log.write(&line);
+        }
+    }
+}
+
+impl Stream for Counted {
+    type Item = Result<Bytes, axum::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let polled = Pin::new(&mut self.inner).poll_next(cx);
+
+        match &polled {
+            Poll::Ready(Some(Ok(chunk))) => self.bytes += chunk.len() as u64,
+            Poll::Ready(None | Some(Err(_))) => self.finish(),
+            Poll::Pending => {}
+        }
+
+        polled
+    }
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Write a line to the access log, if any, after handling the request. Only the path is logged
+/// because query strings might carry tokens, and tokens in the path and referer are redacted.
+/// Lines of streamed responses are written once the body is sent, with the bytes actually sent.
+pub(crate) async fn record(
+    State(logger): State<Logger>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(log) = logger.log else {
+        return next.run(request).await;
+    };
+
+    let start = Instant::now();
+    let time = OffsetDateTime::now_utc();
+    let client = anonymize(logger.limiter.client(request.headers(), peer.ip()));
+    let method = request.method().to_string();
+    let path = redact(request.uri().path());
+    let version = format!("{:?}", request.version());
+    let headers = request.headers();
+    let referer = header(headers, &REFERER).and_then(|referer| redact_referer(&referer));
+    let user_agent = header(headers, &USER_AGENT);
+
+    let response = next.run(request).await;
+
+    let line = Line {
+        time,
+        client,
+        method,
+        path,
+        version,
+        status: response.status().as_u16(),
+        bytes: response.body().size_hint().exact(),
+        latency: start.elapsed(),
+        referer,
+        user_agent,
+    };
+
+    if line.bytes.is_some() {
+        log.write(&line);
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = Body::from_stream(Counted {
+        inner: body.into_data_stream(),
+        log,
+        line: Some(line),
+        start,
+        bytes: 0,
+    });
+
+    Response::from_parts(parts, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{Client, StoreCookies};
+
+    #[test]
+    fn anonymized_addresses() {
+        assert_eq!(
+            anonymize("192.168.12.34".parse().unwrap()),
+            "192.168.12.0".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(
+            anonymize("2001:db8:1234:5678::1".parse().unwrap()),
+            "2001:db8:1234::".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn redacted_tokens() {
+        assert_eq!(redact("/sIiFec_0123abcd.rs"), "/sIiFec_redacted.rs");
+        assert_eq!(redact("/burn/sIiFec_0123abcd"), "/burn/sIiFec_redacted");
+        assert_eq!(redact("/raw/sIiFec.rs"), "/raw/sIiFec.rs");
+        assert_eq!(redact("/assets/not_an_id.css"), "/assets/not_an_id.css");
+        assert_eq!(
+            redact_referer("https://paste.example.com/sIiFec_0123abcd?from=x#L3").as_deref(),
+            Some("https://paste.example.com/sIiFec_redacted")
+        );
+        assert_eq!(redact_referer("not a url"), None);
+    }
+
+    #[test]
+    fn formats() {
+        let line = Line {
+            time: time::macros::datetime!(2024-03-05 06:07:08 UTC),
+            client: "10.0.0.0".parse().unwrap(),
+            method: String::from("GET"),
+            path: String::from("/abc"),
+            version: String::from("HTTP/1.1"),
+            status: 200,
+            bytes: Some(123),
+            latency: Duration::from_millis(5),
+            referer: None,
+            user_agent: Some(String::from("curl \"8\"")),
+        };
+
+        assert_eq!(
+            line.combined(),
+            r#"10.0.0.0 - - [05/Mar/2024:06:07:08 +0000] "GET /abc HTTP/1.1" 200 123 "-" "curl \"8\"" 0.005000"#
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&line.json()).unwrap();
+        assert_eq!(json["timestamp"], "2024-03-05T06:07:08Z");
+        assert_eq!(json["status"], 200);
+        assert_eq!(json["bytes"], 123);
+        assert_eq!(json["referer"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn access_log() -> Result<(), Box<dyn std::error::Error>> {
+        let path = std::env::temp_dir().join(format!("wastebin-access-log-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let log = AccessLog::open(&Target::File(path.clone()), Format::Json)?;
+        let client = Client::with_access_log(StoreCookies(false), log).await;
+
+        let data = Entry {
+            text: String::from("FooBarBaz"),
+            secret: Some(String::from("on")),
+            ..Default::default()
+        };
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?.to_string();
+        let (id, token) = location.trim_start_matches('/').split_once('_').unwrap();
+        client
+            .get(&format!("{location}?token=secret"))
+            .header(REFERER, format!("https://localhost{location}"))
+            .send()
+            .await?;
+        let res = client.get(&format!("/raw{location}")).send().await?;
+        assert_eq!(res.text().await?, "FooBarBaz");
+
+        // Lines are written by another thread.
+        let mut content = String::new();
+
+        for _ in 0..100 {
+            content = std::fs::read_to_string(&path)?;
+
+            if content.lines().count() == 3 {
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let lines = content
+            .lines()
+            .map(serde_json::from_str::<serde_json::Value>)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["method"], "POST");
+        assert_eq!(lines[0]["path"], "/new");
+        assert_eq!(lines[0]["status"], 303);
+        assert_eq!(lines[0]["client"], "127.0.0.0");
+        assert_eq!(lines[1]["path"], format!("/{id}_redacted"));
+        assert_eq!(
+            lines[1]["referer"],
+            format!("https://localhost/{id}_redacted")
+        );
+        assert_eq!(lines[1]["status"], 200);
+        assert_eq!(lines[2]["path"], format!("/raw/{id}_redacted"));
+        assert_eq!(lines[2]["bytes"], 9);
+        assert!(!content.contains("secret"));
+        assert!(!content.contains(token));
+
+        std::fs::remove_file(&path)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,351 @@
+use crate::db::read::Usage;
+use crate::errors::Error;
+use rand::Rng;
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::LazyLock;
+use tokio::task::spawn_blocking;
+
+/// Name of the signed cookie carrying the session token.
+pub const SESSION_COOKIE: &str = "session";
+
+/// Number of days a session stays valid after logging in.
+pub const SESSION_DAYS: i64 = 30;
+
+/// Prefix of API tokens, so they can be told apart from session tokens and found by secret
+/// scanners.
+pub const API_TOKEN_PREFIX: &str = "wb_";
+
+/// Maximum length of API token labels.
+const MAX_LABEL_LENGTH: usize = 64;
+
+/// Number of minutes to enter the second factor after logging in with a password.
+pub const PENDING_MINUTES: i64 = 5;
+
+/// Number of wrong second factors after which a pending login is cancelled.
+pub const MAX_ATTEMPTS: u32 = 5;
+
+/// Hash checked in place of a missing one, so that logins of unknown users take as long as the
+/// ones of existing users and do not tell which names exist.
+static MISSING_HASH: LazyLock<String> = LazyLock::new(|| {
+    argon2::hash_encoded(b"missing", &[0; 16], &argon2::Config::default())
+        .expect("hashing with the default configuration")
+});
+
+/// Maximum length of user names.
+const MAX_NAME_LENGTH: usize = 32;
+
+/// Minimum length of account passwords.
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Permissions of an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Roles {
+    /// May create pastes if creation is restricted.
+    pub create: bool,
+    /// May delete any paste.
+    pub admin: bool,
+}
+
+impl Default for Roles {
+    fn default() -> Self {
+        Self {
+            create: true,
+            admin: false,
+        }
+    }
+}
+
+/// What an API token may be used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Create pastes.
+    Create,
+    /// Create, edit and delete own pastes.
+    Manage,
+}
+
+impl Scope {
+    /// Name as stored in the database and submitted by the settings form.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::Create => "create",
+            Scope::Manage => "manage",
+        }
+    }
+}
+
+impl std::str::FromStr for Scope {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create" => Ok(Scope::Create),
+            "manage" => Ok(Scope::Manage),
+            _ => Err(()),
+        }
+    }
+}
+
+impl ToSql for Scope {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+impl FromSql for Scope {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_str()?
+            .parse()
+            .map_err(|()| FromSqlError::InvalidType)
+    }
+}
+
+/// Limits on what a single account may store, each unlimited if unset.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Quota {
+    /// Maximum number of unexpired pastes.
+    pub pastes: Option<usize>,
+    /// Maximum size of all unexpired pastes as stored in bytes.
+    pub bytes: Option<usize>,
+    /// Maximum size of a single paste in bytes.
+    pub paste_bytes: Option<usize>,
+}
+
+impl Quota {
+    /// Check if a paste of `size` bytes is within the limit for single pastes.
+    pub fn check_size(&self, size: usize) -> Result<(), Error> {
+        match self.paste_bytes {
+            Some(max) if size > max => Err(Error::PasteTooLarge(max)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check if another paste of `size` bytes fits into the quota given the current `usage`.
+    pub fn check(&self, usage: Usage, size: usize) -> Result<(), Error> {
+        self.check_size(size)?;
+
+        if let Some(max) = self.pastes.filter(|max| usage.pastes >= *max) {
+            return Err(Error::PasteQuota(max));
+        }
+
+        if let Some(max) = self.bytes.filter(|max| usage.bytes + size > *max) {
+            return Err(Error::ByteQuota(max));
+        }
+
+        Ok(())
+    }
+
+    /// Check if a paste grown to `size` bytes still fits into the quota given the `usage` of all
+    /// other pastes of the account.
+    pub fn check_edit(&self, usage: Usage, size: usize) -> Result<(), Error> {
+        self.check_size(size)?;
+
+        if let Some(max) = self.bytes.filter(|max| usage.bytes + size > *max) {
+            return Err(Error::ByteQuota(max));
+        }
+
+        Ok(())
+    }
+}
+
+/// Account names derived from the `name` of an external identity in order of preference, so a
+/// taken name can be suffixed with a number.
+#[cfg(any(feature = "ldap", feature = "oidc"))]
+pub fn names(name: &str) -> Vec<String> {
+    let name = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .take(MAX_NAME_LENGTH - 8)
+        .collect::<String>();
+
+    let name = if name.is_empty() {
+        String::from("user")
+    } else {
+        name
+    };
+
+    std::iter::once(name.clone())
+        .chain((2..10).map(|n| format!("{name}-{n}")))
+        .collect()
+}
+
+/// Check that `name` consists of up to [`MAX_NAME_LENGTH`] ASCII letters, digits, `-`, `_` and
+/// `.` and that `password` has at least [`MIN_PASSWORD_LENGTH`] characters.
+pub fn validate(name: &str, password: &str) -> Result<(), Error> {
+    if name.is_empty()
+        || name.len() > MAX_NAME_LENGTH
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(Error::UserName);
+    }
+
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(Error::WeakPassword);
+    }
+
+    Ok(())
+}
+
+/// Hash `password` with a random salt into an encoded argon2 string that carries its parameters.
+pub async fn hash(password: String) -> Result<String, Error> {
+    let hash = spawn_blocking(move || {
+        let salt = rand::rng().random::<[u8; 16]>();
+        argon2::hash_encoded(password.as_bytes(), &salt, &argon2::Config::default())
+    })
+    .await??;
+
+    Ok(hash)
+}
+
+/// Check `password` against the encoded argon2 `hash`.
+pub async fn verify(hash: String, password: String) -> Result<bool, Error> {
+    let valid =
+        spawn_blocking(move || argon2::verify_encoded(&hash, password.as_bytes())).await??;
+
+    Ok(valid)
+}
+
+/// Check `password` against the encoded argon2 `hash` or, if there is none, spend as long as that
+/// would take and return `false`.
+pub async fn verify_or_waste(hash: Option<String>, password: String) -> Result<bool, Error> {
+    if let Some(hash) = hash {
+        return verify(hash, password).await;
+    }
+
+    spawn_blocking(move || argon2::verify_encoded(&MISSING_HASH, password.as_bytes())).await??;
+
+    Ok(false)
+}
+
+/// Generate a new random session token.
+pub fn new_token() -> String {
+    hex::encode(rand::rng().random::<[u8; 32]>())
+}
+
+/// Generate a new random API token.
+pub fn new_api_token() -> String {
+    format!("{API_TOKEN_PREFIX}{}", new_token())
+}
+
+/// Check that the API token `label` is not blank and has at most [`MAX_LABEL_LENGTH`]
+/// characters.
+pub fn validate_label(label: &str) -> Result<(), Error> {
+    if label.trim().is_empty() || label.chars().count() > MAX_LABEL_LENGTH {
+        return Err(Error::TokenLabel);
+    }
+
+    Ok(())
+}
+
+/// Generate a new random invite code, short enough to be shared by hand.
+pub fn new_invite() -> String {
+    hex::encode(rand::rng().random::<[u8; 8]>())
+}
+
+/// Hash of `token` as stored in the database, so a leaked database does not leak sessions.
+pub fn token_hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_credentials() {
+        assert!(validate("alice", "correct horse").is_ok());
+        assert!(validate("a.b_c-1", "12345678").is_ok());
+        assert!(matches!(validate("", "12345678"), Err(Error::UserName)));
+        assert!(matches!(validate("a b", "12345678"), Err(Error::UserName)));
+        assert!(matches!(
+            validate(&"a".repeat(33), "12345678"),
+            Err(Error::UserName)
+        ));
+        assert!(matches!(
+            validate("alice", "1234567"),
+            Err(Error::WeakPassword)
+        ));
+    }
+
+    #[test]
+    fn quota() {
+        let quota = Quota {
+            pastes: Some(2),
+            bytes: Some(100),
+            paste_bytes: Some(60),
+        };
+        let usage = |pastes, bytes| Usage { pastes, bytes };
+
+        assert!(quota.check(usage(0, 0), 60).is_ok());
+        assert!(quota.check(usage(1, 40), 60).is_ok());
+        assert!(matches!(
+            quota.check(usage(0, 0), 61),
+            Err(Error::PasteTooLarge(60))
+        ));
+        assert!(matches!(
+            quota.check(usage(2, 0), 1),
+            Err(Error::PasteQuota(2))
+        ));
+        assert!(matches!(
+            quota.check(usage(1, 50), 51),
+            Err(Error::ByteQuota(100))
+        ));
+        assert!(
+            Quota::default()
+                .check(usage(1000, 1 << 30), 1 << 20)
+                .is_ok()
+        );
+
+        assert!(quota.check_edit(usage(2, 40), 60).is_ok());
+        assert!(matches!(
+            quota.check_edit(usage(2, 50), 51),
+            Err(Error::ByteQuota(100))
+        ));
+    }
+
+    #[tokio::test]
+    async fn hash_and_verify() -> Result<(), Box<dyn std::error::Error>> {
+        let hash = hash(String::from("correct horse")).await?;
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(verify(hash.clone(), String::from("correct horse")).await?);
+        assert!(!verify(hash, String::from("battery staple")).await?);
+
+        Ok(())
+    }
+
+    #[cfg(any(feature = "ldap", feature = "oidc"))]
+    #[test]
+    fn external_names() {
+        let names = names("Alice Smith");
+        assert_eq!(names[0], "AliceSmith");
+        assert_eq!(names[1], "AliceSmith-2");
+        assert!(names.iter().all(|name| validate(name, "12345678").is_ok()));
+        assert_eq!(super::names("日本")[0], "user");
+    }
+
+    #[test]
+    fn tokens() {
+        let token = new_token();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, new_token());
+        assert_eq!(token_hash(&token), token_hash(&token));
+        assert_ne!(token_hash(&token), token);
+
+        let token = new_api_token();
+        assert!(token.starts_with(API_TOKEN_PREFIX));
+        assert_ne!(token, new_api_token());
+
+        assert!(validate_label("ci").is_ok());
+        assert!(matches!(validate_label("  "), Err(Error::TokenLabel)));
+        assert!(matches!(
+            validate_label(&"a".repeat(65)),
+            Err(Error::TokenLabel)
+        ));
+    }
+}
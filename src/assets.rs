@@ -1,44 +1,106 @@
 use axum::response::{IntoResponse, Response};
 use axum_extra::{TypedHeader, headers};
 use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
+use syntect::LoadingError;
 use syntect::highlighting::{self, ThemeSet};
 use syntect::html::{ClassStyle, css_for_theme_with_class_style};
 use two_face::theme::EmbeddedThemeName;
 
-use crate::highlight::Theme;
+use crate::handlers::extract;
+use crate::highlight::{CustomTheme, Theme, custom};
+
+/// Errors when loading custom themes.
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum ThemeError {
+    #[error("failed to load themes: {0}")]
+    Load(#[from] LoadingError),
+    #[error("theme {0} lacks a foreground or background color")]
+    MissingColors(String),
+}
 
 /// An asset associated with a MIME type.
 #[derive(Clone)]
 pub(crate) struct Asset {
     /// Route that this will be served under.
     pub route: String,
+    /// URL pages reference this by, the route unless assets are served from elsewhere.
+    url: String,
     /// MIME type of this asset determined for the `ContentType` response header.
     mime: mime::Mime,
     /// Actual asset content.
     content: Vec<u8>,
+    /// Route contains a hash of the content, so it can be cached forever.
+    immutable: bool,
+}
+
+/// Errors when loading a custom stylesheet or logo.
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum CustomError {
+    #[error("failed to read {0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("unsupported file type of {0}, expected one of {1}")]
+    Kind(String, String),
 }
 
 /// Asset kind.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub(crate) enum Kind {
     Css,
     Js,
+    Png,
+    Svg,
+    Jpeg,
+    Gif,
+    Webp,
+    Ico,
+}
+
+/// Kinds of images usable as logo.
+pub(crate) const IMAGES: [Kind; 5] = [Kind::Png, Kind::Svg, Kind::Jpeg, Kind::Gif, Kind::Webp];
+
+/// Where a custom stylesheet or logo comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Source {
+    /// File read at startup and served like the built-in assets.
+    File(std::path::PathBuf),
+    /// URL pages reference instead.
+    Url(url::Url),
+}
+
+/// Stylesheet or logo provided by the operator.
+#[derive(Clone)]
+pub(crate) enum Custom {
+    Local(Asset),
+    Remote(url::Url),
 }
 
+/// How long browsers may cache assets under a fixed route before revalidating.
+const MAX_AGE: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// How long browsers may cache assets under a route containing their content hash.
+const IMMUTABLE_MAX_AGE: Duration = Duration::from_secs(60 * 60 * 24 * 365);
+
 impl IntoResponse for Asset {
     fn into_response(self) -> Response {
         let content_type_header = headers::ContentType::from(self.mime);
 
-        let headers = (
-            TypedHeader(content_type_header),
-            TypedHeader(
-                headers::CacheControl::new()
-                    .with_max_age(Duration::from_secs(60 * 60 * 24 * 30))
-                    .with_immutable(),
-            ),
-        );
+        let cache_control = if self.immutable {
+            headers::CacheControl::new()
+                .with_public()
+                .with_max_age(IMMUTABLE_MAX_AGE)
+                .with_immutable()
+        } else {
+            headers::CacheControl::new()
+                .with_public()
+                .with_max_age(MAX_AGE)
+        };
+
+        let headers = (TypedHeader(content_type_header), TypedHeader(cache_control));
 
         (headers, self.content).into_response()
     }
@@ -49,8 +111,10 @@ impl Asset {
     pub fn new(name: &str, mime: mime::Mime, content: Vec<u8>) -> Self {
         Self {
             route: format!("/{name}"),
+            url: format!("/{name}"),
             mime,
             content,
+            immutable: false,
         }
     }
 
@@ -59,6 +123,12 @@ impl Asset {
         let (mime, ext) = match kind {
             Kind::Css => (mime::TEXT_CSS, "css"),
             Kind::Js => (mime::TEXT_JAVASCRIPT, "js"),
+            Kind::Png => (mime::IMAGE_PNG, "png"),
+            Kind::Svg => (mime::IMAGE_SVG, "svg"),
+            Kind::Jpeg => (mime::IMAGE_JPEG, "jpg"),
+            Kind::Gif => (mime::IMAGE_GIF, "gif"),
+            Kind::Webp => ("image/webp".parse().expect("valid MIME type"), "webp"),
+            Kind::Ico => ("image/x-icon".parse().expect("valid MIME type"), "ico"),
         };
 
         let route = format!(
@@ -69,25 +139,146 @@ impl Asset {
         );
 
         Self {
+            url: route.clone(),
             route,
             mime,
             content,
+            immutable: true,
         }
     }
 
+    /// Load the file at `path` as hashed asset `name`, which must be one of the `kinds`.
+    pub fn load(name: &str, path: &Path, kinds: &[Kind]) -> Result<Self, CustomError> {
+        let display = path.display().to_string();
+
+        let kind = Kind::from_path(path)
+            .filter(|kind| kinds.contains(kind))
+            .ok_or_else(|| {
+                let expected = kinds
+                    .iter()
+                    .map(|kind| format!("{kind:?}").to_lowercase())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                CustomError::Kind(display.clone(), expected)
+            })?;
+
+        let content = std::fs::read(path).map_err(|err| CustomError::Io(display, err))?;
+
+        Ok(Self::new_hashed(name, kind, content))
+    }
+
+    /// Copy of this asset under the fixed route `/name`, for clients requesting it without a link.
+    pub fn fixed(&self, name: &str) -> Self {
+        Self::new(name, self.mime.clone(), self.content.clone())
+    }
+
     pub fn route(&self) -> &str {
         &self.route
     }
+
+    pub fn mime(&self) -> &mime::Mime {
+        &self.mime
+    }
+
+    /// Width and height if this is a PNG image, read from its header.
+    pub fn png_size(&self) -> Option<(u32, u32)> {
+        let header = self.content.get(..24)?;
+
+        if self.mime != mime::IMAGE_PNG || &header[12..16] != b"IHDR" {
+            return None;
+        }
+
+        let width = u32::from_be_bytes(header[16..20].try_into().ok()?);
+        let height = u32::from_be_bytes(header[20..24].try_into().ok()?);
+
+        Some((width, height))
+    }
+
+    /// URL to reference this asset by in pages.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Reference this asset below `base` instead of the own origin.
+    pub fn rebase(&mut self, base: &url::Url) {
+        self.url = format!("{}{}", base.as_str().trim_end_matches('/'), self.route);
+    }
 }
 
-/// Collection of light and dark CSS and main UI style CSS derived from them.
-pub(crate) struct Css {
-    /// Main UI CSS stylesheet.
-    pub style: Asset,
+impl Kind {
+    /// Determine the kind of a file by its extension.
+    fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+
+        match ext.as_str() {
+            "css" => Some(Self::Css),
+            "js" => Some(Self::Js),
+            "png" => Some(Self::Png),
+            "svg" => Some(Self::Svg),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "gif" => Some(Self::Gif),
+            "webp" => Some(Self::Webp),
+            "ico" => Some(Self::Ico),
+            _ => None,
+        }
+    }
+}
+
+impl Custom {
+    /// Load the asset `name` from `source`, which must be one of the `kinds` if it is a file.
+    pub fn load(name: &str, source: Source, kinds: &[Kind]) -> Result<Self, CustomError> {
+        match source {
+            Source::Url(url) => Ok(Self::Remote(url)),
+            Source::File(path) => Ok(Self::Local(Asset::load(name, &path, kinds)?)),
+        }
+    }
+
+    /// URL to reference this asset by in pages.
+    pub fn url(&self) -> &str {
+        match self {
+            Self::Local(asset) => asset.url(),
+            Self::Remote(url) => url.as_str(),
+        }
+    }
+
+    /// Origin the content security policy has to allow, if not this one.
+    pub fn origin(&self) -> Option<String> {
+        match self {
+            Self::Local(_) => None,
+            Self::Remote(url) => Some(url.origin().ascii_serialization()),
+        }
+    }
+
+    /// The asset to serve, if it is not referenced elsewhere.
+    pub fn local(&self) -> Option<&Asset> {
+        match self {
+            Self::Local(asset) => Some(asset),
+            Self::Remote(_) => None,
+        }
+    }
+}
+
+/// Light and dark CSS of a single theme.
+pub(crate) struct ThemeCss {
     /// Light theme colors.
     pub light: Asset,
     /// Dark theme colors.
     pub dark: Asset,
+    /// Light and dark syntect themes the CSS is generated from, used to render images.
+    pub light_theme: highlighting::Theme,
+    pub dark_theme: highlighting::Theme,
+}
+
+/// Collection of the main UI style CSS and the light and dark CSS of all selectable themes.
+pub(crate) struct Css {
+    /// Main UI CSS stylesheet.
+    pub style: Asset,
+    /// Stylesheet loaded if JavaScript is disabled, hiding controls that need it.
+    pub noscript: Asset,
+    /// Theme colors by theme name.
+    pub themes: BTreeMap<String, ThemeCss>,
+    /// Name of the theme used if the viewer did not select one.
+    pub default: String,
 }
 
 /// Generate the highlighting colors for `theme` and add main foreground and background colors
@@ -111,55 +302,198 @@ fn combined_css(theme: &highlighting::Theme) -> Vec<u8> {
     .into_bytes()
 }
 
-impl Css {
+impl ThemeCss {
     /// Create CSS assets for `theme`.
-    pub fn new(theme: Theme) -> Self {
+    fn new(theme: &Theme) -> Self {
+        let name = theme.name();
         let light_theme = light_theme(theme);
         let dark_theme = dark_theme(theme);
+        let light = Asset::new_hashed(
+            &format!("{name}-light"),
+            Kind::Css,
+            combined_css(&light_theme),
+        );
+        let dark = Asset::new_hashed(
+            &format!("{name}-dark"),
+            Kind::Css,
+            combined_css(&dark_theme),
+        );
+
+        Self {
+            light,
+            dark,
+            light_theme,
+            dark_theme,
+        }
+    }
+}
+
+impl Css {
+    /// Create CSS assets for the built-in and `custom` themes with `default` being used unless
+    /// the viewer selects another one.
+    pub fn new(default: &Theme, custom: &[Theme]) -> Self {
         let style = Asset::new_hashed("style", Kind::Css, include_str!("style.css").into());
-        let light = Asset::new_hashed("light", Kind::Css, combined_css(&light_theme));
-        let dark = Asset::new_hashed("dark", Kind::Css, combined_css(&dark_theme));
+        let noscript =
+            Asset::new_hashed("noscript", Kind::Css, include_str!("noscript.css").into());
+        let themes = Theme::builtin()
+            .iter()
+            .chain(custom)
+            .map(|theme| (theme.name().to_string(), ThemeCss::new(theme)))
+            .collect();
+
+        Self {
+            style,
+            noscript,
+            themes,
+            default: default.name().to_string(),
+        }
+    }
+
+    /// Reference all stylesheets below `base`.
+    pub fn rebase(&mut self, base: &url::Url) {
+        self.style.rebase(base);
+        self.noscript.rebase(base);
+
+        for css in self.themes.values_mut() {
+            css.light.rebase(base);
+            css.dark.rebase(base);
+        }
+    }
+
+    /// Stylesheet URLs with an optional media query to link for the viewer's `theme`
+    /// preferences. Printing always uses the light variant, as paper is white.
+    pub fn stylesheets(&self, theme: Option<&extract::Theme>) -> Vec<(&str, Option<&'static str>)> {
+        let Some(css) = self.themes.get(self.selected(theme)) else {
+            return Vec::new();
+        };
+
+        match mode(theme) {
+            Some(extract::Mode::Dark) => vec![
+                (css.dark.url(), Some("screen")),
+                (css.light.url(), Some("print")),
+            ],
+            Some(extract::Mode::Light) => vec![(css.light.url(), None)],
+            None => vec![
+                (
+                    css.dark.url(),
+                    Some("screen and (prefers-color-scheme: dark)"),
+                ),
+                (
+                    css.light.url(),
+                    Some("print, (prefers-color-scheme: light)"),
+                ),
+            ],
+        }
+    }
+
+    /// Syntect theme for the viewer's `theme` preferences, dark unless light mode is selected.
+    pub fn colors(&self, theme: Option<&extract::Theme>) -> Option<&highlighting::Theme> {
+        let css = self.themes.get(self.selected(theme))?;
+
+        match mode(theme) {
+            Some(extract::Mode::Light) => Some(&css.light_theme),
+            Some(extract::Mode::Dark) | None => Some(&css.dark_theme),
+        }
+    }
 
-        Self { style, light, dark }
+    /// Name of the theme shown to the viewer.
+    pub fn selected<'a>(&'a self, theme: Option<&'a extract::Theme>) -> &'a str {
+        theme
+            .and_then(|theme| theme.name.as_deref())
+            .filter(|name| self.themes.contains_key(*name))
+            .unwrap_or(&self.default)
     }
 }
 
-fn light_theme(theme: Theme) -> syntect::highlighting::Theme {
+/// Mode selected by the viewer's `theme` preferences. High contrast colors are always dark.
+fn mode(theme: Option<&extract::Theme>) -> Option<extract::Mode> {
+    match theme {
+        Some(theme) if theme.high_contrast => Some(extract::Mode::Dark),
+        Some(theme) => theme.mode,
+        None => None,
+    }
+}
+
+/// Parse a `.tmTheme` bundled with the binary.
+fn bundled(theme: &str) -> highlighting::Theme {
+    ThemeSet::load_from_reader(&mut Cursor::new(theme)).expect("loading theme")
+}
+
+fn light_theme(theme: &Theme) -> highlighting::Theme {
     let theme_set = two_face::theme::extra();
 
     match theme {
-        Theme::Ayu => {
-            let theme = include_str!("themes/ayu-light.tmTheme");
-            ThemeSet::load_from_reader(&mut Cursor::new(theme)).expect("loading theme")
-        }
+        Theme::Ayu => bundled(include_str!("themes/ayu-light.tmTheme")),
         Theme::Base16Ocean => theme_set.get(EmbeddedThemeName::Base16OceanLight).clone(),
+        Theme::Catppuccin => bundled(include_str!("themes/catppuccin-latte.tmTheme")),
         Theme::Coldark => theme_set.get(EmbeddedThemeName::ColdarkCold).clone(),
+        Theme::Dracula => bundled(include_str!("themes/dracula-light.tmTheme")),
         Theme::Gruvbox => theme_set.get(EmbeddedThemeName::GruvboxLight).clone(),
         Theme::Monokai => theme_set
             .get(EmbeddedThemeName::MonokaiExtendedLight)
             .clone(),
+        Theme::Nord => bundled(include_str!("themes/nord-light.tmTheme")),
         Theme::Onehalf => theme_set.get(EmbeddedThemeName::OneHalfLight).clone(),
         Theme::Solarized => theme_set.get(EmbeddedThemeName::SolarizedLight).clone(),
+        Theme::TokyoNight => bundled(include_str!("themes/tokyonight-day.tmTheme")),
+        Theme::Custom(custom) => custom.light.clone(),
     }
 }
 
-fn dark_theme(theme: Theme) -> syntect::highlighting::Theme {
+fn dark_theme(theme: &Theme) -> highlighting::Theme {
     let theme_set = two_face::theme::extra();
 
     match theme {
-        Theme::Ayu => {
-            let theme = include_str!("themes/ayu-dark.tmTheme");
-            ThemeSet::load_from_reader(&mut Cursor::new(theme)).expect("loading theme")
-        }
+        Theme::Ayu => bundled(include_str!("themes/ayu-dark.tmTheme")),
         Theme::Base16Ocean => theme_set.get(EmbeddedThemeName::Base16OceanDark).clone(),
+        Theme::Catppuccin => bundled(include_str!("themes/catppuccin-mocha.tmTheme")),
         Theme::Coldark => theme_set.get(EmbeddedThemeName::ColdarkDark).clone(),
+        Theme::Dracula => theme_set.get(EmbeddedThemeName::Dracula).clone(),
         Theme::Gruvbox => theme_set.get(EmbeddedThemeName::GruvboxDark).clone(),
         Theme::Monokai => theme_set.get(EmbeddedThemeName::MonokaiExtended).clone(),
+        Theme::Nord => theme_set.get(EmbeddedThemeName::Nord).clone(),
         Theme::Onehalf => theme_set.get(EmbeddedThemeName::OneHalfDark).clone(),
         Theme::Solarized => theme_set.get(EmbeddedThemeName::SolarizedDark).clone(),
+        Theme::TokyoNight => bundled(include_str!("themes/tokyonight-night.tmTheme")),
+        Theme::Custom(custom) => custom.dark.clone(),
     }
 }
 
+/// Load all `.tmTheme` files in `dir`, dumping the parsed themes into `dumps` if given. Files
+/// named `<name>-light.tmTheme` and `<name>-dark.tmTheme` form the light and dark variant of
+/// theme `<name>`, any other file is used for both variants.
+pub fn load_themes(dir: &Path, dumps: Option<&Path>) -> Result<Vec<Theme>, ThemeError> {
+    let mut variants: BTreeMap<String, (Option<highlighting::Theme>, Option<highlighting::Theme>)> =
+        BTreeMap::new();
+
+    for (stem, theme) in custom::load_themes(dir, dumps)?.themes {
+        if theme.settings.foreground.is_none() || theme.settings.background.is_none() {
+            return Err(ThemeError::MissingColors(stem));
+        }
+
+        if let Some(name) = stem.strip_suffix("-light") {
+            variants.entry(name.to_string()).or_default().0 = Some(theme);
+        } else if let Some(name) = stem.strip_suffix("-dark") {
+            variants.entry(name.to_string()).or_default().1 = Some(theme);
+        } else {
+            variants.insert(stem, (Some(theme.clone()), Some(theme)));
+        }
+    }
+
+    Ok(variants
+        .into_iter()
+        .filter_map(|(name, variants)| {
+            let (light, dark) = match variants {
+                (Some(light), Some(dark)) => (light, dark),
+                (Some(theme), None) | (None, Some(theme)) => (theme.clone(), theme),
+                (None, None) => return None,
+            };
+
+            Some(Theme::Custom(Arc::new(CustomTheme { name, light, dark })))
+        })
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -172,4 +506,177 @@ mod tests {
         let asset = Asset::new_hashed("main", Kind::Js, String::from("1 + 1").into_bytes());
         assert_eq!(asset.route, "/main.72fce59447a01f48.js");
     }
+
+    #[test]
+    fn cache_control() {
+        let hashed = Asset::new_hashed("style", Kind::Css, String::from("body {}").into_bytes());
+        let fixed = Asset::new("favicon.ico", mime::IMAGE_PNG, Vec::new());
+
+        let header = |asset: Asset| asset.into_response().headers()["cache-control"].clone();
+
+        assert_eq!(header(hashed), "public, immutable, max-age=31536000");
+        assert_eq!(header(fixed), "public, max-age=86400");
+    }
+
+    #[test]
+    fn load_custom() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("logo.SVG");
+        std::fs::write(&path, "<svg/>")?;
+
+        let Custom::Local(asset) = Custom::load("logo", Source::File(path), &IMAGES)? else {
+            panic!("expected local asset");
+        };
+        assert!(asset.route().starts_with("/logo."));
+        assert_eq!(asset.mime, mime::IMAGE_SVG);
+
+        assert!(matches!(
+            Custom::load("logo", Source::File(dir.path().join("logo.css")), &IMAGES),
+            Err(CustomError::Kind(_, expected)) if expected == "png, svg, jpeg, gif, webp"
+        ));
+        assert!(matches!(
+            Custom::load(
+                "custom",
+                Source::File(dir.path().join("missing.css")),
+                &[Kind::Css]
+            ),
+            Err(CustomError::Io(_, _))
+        ));
+
+        let url = url::Url::parse("https://cdn.example.com/brand/custom.css")?;
+        let remote = Custom::load("custom", Source::Url(url), &[Kind::Css])?;
+        assert_eq!(remote.url(), "https://cdn.example.com/brand/custom.css");
+        assert_eq!(remote.origin().as_deref(), Some("https://cdn.example.com"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn rebase() {
+        let mut css = Css::new(&Theme::Nord, &[]);
+        let route = css.style.route().to_string();
+
+        css.rebase(&url::Url::parse("https://cdn.example.com/wastebin/").unwrap());
+
+        assert_eq!(css.style.route(), route);
+        assert_eq!(
+            css.style.url(),
+            format!("https://cdn.example.com/wastebin{route}")
+        );
+        assert!(
+            css.stylesheets(None)
+                .iter()
+                .all(|(url, _)| url.starts_with("https://cdn.example.com/wastebin/nord-"))
+        );
+    }
+
+    #[test]
+    fn builtin_themes() {
+        for theme in Theme::builtin() {
+            let light = light_theme(&theme);
+            let dark = dark_theme(&theme);
+            assert!(light.settings.foreground.is_some() && light.settings.background.is_some());
+            assert!(dark.settings.foreground.is_some() && dark.settings.background.is_some());
+            assert_ne!(light.settings.background, dark.settings.background);
+        }
+    }
+
+    #[test]
+    fn stylesheets() {
+        let css = Css::new(&Theme::Nord, &[]);
+        let nord = &css.themes["nord"];
+        let ayu = &css.themes["ayu"];
+
+        assert_eq!(
+            css.stylesheets(None),
+            [
+                (
+                    nord.dark.route(),
+                    Some("screen and (prefers-color-scheme: dark)")
+                ),
+                (
+                    nord.light.route(),
+                    Some("print, (prefers-color-scheme: light)")
+                )
+            ]
+        );
+
+        let theme = extract::Theme {
+            mode: Some(extract::Mode::Dark),
+            ..Default::default()
+        };
+        assert_eq!(
+            css.stylesheets(Some(&theme)),
+            [
+                (nord.dark.route(), Some("screen")),
+                (nord.light.route(), Some("print"))
+            ]
+        );
+
+        let theme = extract::Theme {
+            mode: Some(extract::Mode::Light),
+            name: Some(String::from("ayu")),
+            ..Default::default()
+        };
+        assert_eq!(css.stylesheets(Some(&theme)), [(ayu.light.route(), None)]);
+        assert_eq!(css.selected(Some(&theme)), "ayu");
+
+        let theme = extract::Theme {
+            mode: None,
+            name: Some(String::from("unknown")),
+            ..Default::default()
+        };
+        assert_eq!(css.selected(Some(&theme)), "nord");
+
+        let theme = extract::Theme {
+            mode: Some(extract::Mode::Light),
+            high_contrast: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            css.stylesheets(Some(&theme)),
+            [
+                (nord.dark.route(), Some("screen")),
+                (nord.light.route(), Some("print"))
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_themes() {
+        let dir = std::env::temp_dir().join(format!("wastebin-themes-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let light = include_str!("themes/ayu-light.tmTheme");
+        let dark = include_str!("themes/ayu-dark.tmTheme");
+        std::fs::write(dir.join("brand-light.tmTheme"), light).unwrap();
+        std::fs::write(dir.join("brand-dark.tmTheme"), dark).unwrap();
+        std::fs::write(dir.join("single.tmTheme"), dark).unwrap();
+
+        let themes = load_themes(&dir, None).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let names = themes
+            .iter()
+            .filter_map(|theme| match theme {
+                Theme::Custom(custom) => Some(custom.name.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["brand", "single"]);
+
+        let Theme::Custom(brand) = &themes[0] else {
+            unreachable!()
+        };
+
+        assert_ne!(
+            brand.light.settings.background,
+            brand.dark.settings.background
+        );
+        let css = Css::new(&themes[1], &themes);
+        assert_eq!(css.default, "single");
+        assert!(css.themes.contains_key("brand"));
+        assert!(css.themes.contains_key("ayu"));
+    }
 }
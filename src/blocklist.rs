@@ -0,0 +1,157 @@
+use regex::Regex;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum Error {
+    #[error("failed to read blocklist: {0}")]
+    Read(#[from] std::io::Error),
+    #[error("blocklist line {0}: expected `<reject|quarantine> <regex|keyword> <pattern>`")]
+    Syntax(usize),
+    #[error("blocklist line {0}: {1}")]
+    Regex(usize, regex::Error),
+}
+
+/// What happens to a paste matching a rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Action {
+    /// Refuse to store the paste.
+    Reject,
+    /// Store the paste but hide it until an admin releases it.
+    Quarantine,
+}
+
+#[derive(Debug)]
+struct Rule {
+    action: Action,
+    pattern: Regex,
+    /// Rule as written in the file, recorded as the reason of a quarantine.
+    source: String,
+}
+
+/// Rules matched against the title and content of new pastes, evaluated in order.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Blocklist {
+    rules: Arc<[Rule]>,
+}
+
+impl Blocklist {
+    /// Load rules from the file at `path`, one per line. Empty lines and lines starting with `#`
+    /// are ignored. Keywords match case-insensitively anywhere in the paste.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    /// Number of rules.
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Action and source of the first rule matching the `title` or `text` of a paste.
+    pub fn check(&self, title: Option<&str>, text: &str) -> Option<(Action, &str)> {
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.pattern.is_match(text)
+                    || title.is_some_and(|title| rule.pattern.is_match(title))
+            })
+            .map(|rule| (rule.action, rule.source.as_str()))
+    }
+}
+
+impl std::str::FromStr for Blocklist {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rules = Vec::new();
+
+        for (number, line) in s
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+        {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(3, char::is_whitespace);
+
+            let action = match parts.next() {
+                Some("reject") => Action::Reject,
+                Some("quarantine") => Action::Quarantine,
+                _ => return Err(Error::Syntax(number)),
+            };
+
+            let (kind, pattern) = parts
+                .next()
+                .zip(parts.next().map(str::trim))
+                .filter(|(_, pattern)| !pattern.is_empty())
+                .ok_or(Error::Syntax(number))?;
+
+            let pattern = match kind {
+                "regex" => Regex::new(pattern),
+                "keyword" => Regex::new(&format!("(?i){}", regex::escape(pattern))),
+                _ => return Err(Error::Syntax(number)),
+            }
+            .map_err(|err| Error::Regex(number, err))?;
+
+            let source = line
+                .split_once(char::is_whitespace)
+                .map_or(line, |(_, rule)| rule.trim())
+                .to_string();
+
+            rules.push(Rule {
+                action,
+                pattern,
+                source,
+            });
+        }
+
+        Ok(Self {
+            rules: rules.into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules() {
+        let blocklist = "
+            # recurring phishing template
+            reject keyword Verify your PayPal account
+            quarantine regex https?://[a-z0-9-]+\\.example/login
+        "
+        .parse::<Blocklist>()
+        .unwrap();
+
+        assert_eq!(blocklist.len(), 2);
+        assert_eq!(
+            blocklist.check(None, "please VERIFY YOUR PAYPAL ACCOUNT now"),
+            Some((Action::Reject, "keyword Verify your PayPal account"))
+        );
+        assert_eq!(
+            blocklist.check(Some("go to http://bank.example/login"), "hi"),
+            Some((
+                Action::Quarantine,
+                "regex https?://[a-z0-9-]+\\.example/login"
+            ))
+        );
+        assert_eq!(blocklist.check(Some("title"), "fn main() {}"), None);
+
+        assert!(matches!(
+            "block keyword spam".parse::<Blocklist>(),
+            Err(Error::Syntax(1))
+        ));
+        assert!(matches!(
+            "\nreject regex".parse::<Blocklist>(),
+            Err(Error::Syntax(2))
+        ));
+        assert!(matches!(
+            "reject regex (".parse::<Blocklist>(),
+            Err(Error::Regex(1, _))
+        ));
+    }
+}
@@ -1,45 +1,208 @@
 use crate::errors::Error;
-use crate::highlight::Html;
+use crate::highlight::{Html, View};
 use crate::id::Id;
+use crate::metrics::Counter;
 use cached::{Cached, SizedCache};
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::num::NonZeroUsize;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
 
-/// Cache based on identifier and format.
+mod disk;
+mod warm;
+
+pub(crate) use disk::DiskCache;
+pub(crate) use warm::warm;
+
+/// Cache based on identifier and format. Highlighted HTML only refers to CSS classes, so the same
+/// entry serves viewers of all themes.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub(crate) struct Key {
     pub id: Id,
     pub ext: String,
+    /// Requested view, not part of the string representation.
+    pub view: View,
+    /// Capability token of secret pastes, separated from the identifier by `_`.
+    pub token: Option<String>,
 }
 
-/// Stores formatted HTML.
+/// Stores formatted HTML in memory and optionally on disk.
 #[derive(Clone)]
 pub(crate) struct Cache {
     inner: Arc<Mutex<SizedCache<Key, Html>>>,
+    disk: Option<Arc<DiskCache>>,
+    /// Renders in flight, so concurrent requests for the same key wait for the first one
+    /// instead of highlighting the paste again.
+    pending: Arc<Mutex<HashMap<Key, watch::Receiver<Option<Html>>>>>,
+    /// If new pastes are highlighted right after upload.
+    prerender: bool,
+    hits: Counter,
+    misses: Counter,
 }
 
 impl Cache {
     pub fn new(size: NonZeroUsize) -> Self {
         let inner = Arc::new(Mutex::new(SizedCache::with_size(size.into())));
 
-        Self { inner }
+        Self {
+            inner,
+            disk: None,
+            pending: Arc::default(),
+            prerender: false,
+            hits: Counter::default(),
+            misses: Counter::default(),
+        }
+    }
+
+    /// Highlight new pastes right after upload instead of on first view.
+    pub fn with_prerender(mut self, prerender: bool) -> Self {
+        self.prerender = prerender;
+        self
+    }
+
+    /// Return `true` if new pastes should be highlighted right after upload.
+    pub fn prerenders(&self) -> bool {
+        self.prerender
+    }
+
+    /// Persist entries in `disk` below the in-memory cache.
+    pub fn with_disk(mut self, disk: DiskCache) -> Self {
+        self.disk = Some(Arc::new(disk));
+        self
     }
 
     pub fn put(&self, key: Key, value: Html) {
+        if let Some(disk) = self.disk.clone() {
+            let key = key.clone();
+            let value = value.clone();
+
+            tokio::task::spawn_blocking(move || {
+                if let Err(err) = disk.put(&key, &value.into_inner()) {
+                    tracing::warn!(?key, "failed to write cached item: {err}");
+                }
+            });
+        }
+
         self.inner
             .lock()
             .expect("getting lock")
             .cache_set(key, value);
     }
 
+    /// Start rendering `key` and return the flight to finish it with, or if it is rendered
+    /// already, a receiver of the HTML once the render in flight is done. The receiver sees
+    /// `None` and is closed if that render fails.
+    pub fn start(&self, key: &Key) -> Result<Flight, watch::Receiver<Option<Html>>> {
+        let mut pending = self.pending.lock().expect("getting lock");
+
+        if let Some(receiver) = pending.get(key) {
+            return Err(receiver.clone());
+        }
+
+        let (sender, receiver) = watch::channel(None);
+        pending.insert(key.clone(), receiver);
+
+        Ok(Flight {
+            cache: self.clone(),
+            key: key.clone(),
+            sender,
+        })
+    }
+
+    /// Number of entries held in memory.
+    pub fn len(&self) -> usize {
+        self.inner.lock().expect("getting lock").cache_size()
+    }
+
+    /// Number of lookups served from memory or disk and those that were not.
+    pub fn counters(&self) -> (&Counter, &Counter) {
+        (&self.hits, &self.misses)
+    }
+
     pub fn get(&self, key: &Key) -> Option<Html> {
-        self.inner
+        let html = self.lookup(key);
+
+        if html.is_some() {
+            self.hits.inc();
+        } else {
+            self.misses.inc();
+        }
+
+        html
+    }
+
+    /// Return `true` if `key` is cached in memory or on disk, without counting a lookup.
+    pub fn contains(&self, key: &Key) -> bool {
+        self.lookup(key).is_some()
+    }
+
+    fn lookup(&self, key: &Key) -> Option<Html> {
+        if let Some(html) = self
+            .inner
             .lock()
             .expect("getting lock")
             .cache_get(key)
             .cloned()
+        {
+            return Some(html);
+        }
+
+        let html = Html::from(
+            self.disk
+                .as_ref()?
+                .get(key)
+                .inspect_err(|err| tracing::warn!(?key, "failed to read cached item: {err}"))
+                .ok()??,
+        );
+
+        self.inner
+            .lock()
+            .expect("getting lock")
+            .cache_set(key.clone(), html.clone());
+
+        Some(html)
+    }
+
+    /// Remove all entries of the paste `id`.
+    pub fn remove(&self, id: Id) {
+        self.inner
+            .lock()
+            .expect("getting lock")
+            .retain(|key, _| key.id != id);
+
+        if let Some(disk) = &self.disk {
+            if let Err(err) = disk.remove(id) {
+                tracing::warn!(%id, "failed to remove cached items: {err}");
+            }
+        }
+    }
+}
+
+/// Render of a key in flight, handed to whoever started it. Requests waiting for it are
+/// released once it is finished or dropped.
+pub(crate) struct Flight {
+    cache: Cache,
+    key: Key,
+    sender: watch::Sender<Option<Html>>,
+}
+
+impl Flight {
+    /// Cache the rendered `html` and pass it to the waiting requests.
+    pub fn finish(self, html: Html) {
+        self.cache.put(self.key.clone(), html.clone());
+        self.sender.send_replace(Some(html));
+    }
+}
+
+impl Drop for Flight {
+    fn drop(&mut self) {
+        self.cache
+            .pending
+            .lock()
+            .expect("getting lock")
+            .remove(&self.key);
     }
 }
 
@@ -48,14 +211,22 @@ impl Key {
     pub fn id(&self) -> String {
         self.id.to_string()
     }
+
+    /// Identifier followed by the token of secret pastes, for routes without extension.
+    pub fn id_with_token(&self) -> String {
+        match &self.token {
+            Some(token) => format!("{}_{token}", self.id),
+            None => self.id(),
+        }
+    }
 }
 
 impl Display for Key {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.ext.is_empty() {
-            write!(f, "{}", self.id)
+            write!(f, "{}", self.id_with_token())
         } else {
-            write!(f, "{}.{}", self.id, self.ext)
+            write!(f, "{}.{}", self.id_with_token(), self.ext)
         }
     }
 }
@@ -65,11 +236,22 @@ impl FromStr for Key {
 
     fn from_str(value: &str) -> Result<Self, Self::Err> {
         let (id, ext) = match value.split_once('.') {
-            None => (value.parse()?, "txt".to_string()),
-            Some((id, ext)) => (id.parse()?, ext.to_string()),
+            None => (value, "txt".to_string()),
+            Some((id, ext)) => (id, ext.to_string()),
         };
 
-        Ok(Self { id, ext })
+        let (id, token) = match id.split_once('_') {
+            None => (id.parse()?, None),
+            Some((_, "")) => return Err(Error::IllegalCharacters),
+            Some((id, token)) => (id.parse()?, Some(token.to_string())),
+        };
+
+        Ok(Self {
+            id,
+            ext,
+            view: View::default(),
+            token,
+        })
     }
 }
 
@@ -81,15 +263,47 @@ mod tests {
     fn cache_key() {
         let key = Key::from_str("bJZCna").unwrap();
         assert_eq!(key.id(), "bJZCna");
-        assert_eq!(key.id, 104651828u32.into());
+        assert_eq!(key.id, 104_651_828u32.into());
         assert_eq!(key.ext, "txt");
 
         let key = Key::from_str("sIiFec.rs").unwrap();
         assert_eq!(key.id(), "sIiFec");
-        assert_eq!(key.id, 1243750162u32.into());
+        assert_eq!(key.id, 1_243_750_162u32.into());
         assert_eq!(key.ext, "rs");
 
+        let key = Key::from_str("sIiFec_0123abcd.rs").unwrap();
+        assert_eq!(key.id(), "sIiFec");
+        assert_eq!(key.token.as_deref(), Some("0123abcd"));
+        assert_eq!(key.id_with_token(), "sIiFec_0123abcd");
+        assert_eq!(key.to_string(), "sIiFec_0123abcd.rs");
+
+        assert!(Key::from_str("sIiFec_").is_err());
         assert!(Key::from_str("foo").is_err());
         assert!(Key::from_str("bar.rs").is_err());
     }
+
+    #[tokio::test]
+    async fn single_flight() {
+        let cache = Cache::new(NonZeroUsize::new(8).unwrap());
+        let key = Key::from_str("sIiFec.rs").unwrap();
+
+        let flight = cache.start(&key).ok().unwrap();
+        let Err(mut pending) = cache.start(&key) else {
+            panic!("started a second render of the same key");
+        };
+
+        flight.finish(Html::from(String::from("<table></table>")));
+        let html = pending.wait_for(Option::is_some).await.unwrap().clone();
+        assert_eq!(html.unwrap().into_inner(), "<table></table>");
+        assert!(cache.contains(&key));
+
+        // A failed render releases the waiting requests and the key.
+        let flight = cache.start(&key).ok().unwrap();
+        let Err(mut pending) = cache.start(&key) else {
+            panic!("started a second render of the same key");
+        };
+        drop(flight);
+        assert!(pending.wait_for(Option::is_some).await.is_err());
+        assert!(cache.start(&key).is_ok());
+    }
 }
@@ -0,0 +1,293 @@
+use crate::cache::Key;
+use crate::id::Id;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Extension of cached files, other files in the directory are left alone.
+const EXTENSION: &str = "html";
+
+/// Rendered HTML persisted as one file per [`Key`]. File names start with the paste identifier
+/// followed by a hash of the extension, view and renderer version, so entries of deleted pastes
+/// can be found and renderings of older versions are never served. The least recently used files
+/// are evicted once the directory exceeds its size limit.
+pub(crate) struct DiskCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    version: u64,
+    index: Mutex<Index>,
+}
+
+/// Cached files by paste, so removing the entries of a paste does not scan the directory.
+#[derive(Default)]
+struct Index {
+    /// Total size of all cached files in bytes.
+    size: u64,
+    /// Size of each cached file by paste identifier and path.
+    files: HashMap<Id, HashMap<PathBuf, u64>>,
+}
+
+/// Cached file with its size and time of last access.
+struct Entry {
+    path: PathBuf,
+    len: u64,
+    modified: SystemTime,
+}
+
+/// Collect all cached files in `dir`.
+fn files(dir: &Path) -> io::Result<Vec<Entry>> {
+    let mut files = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if path.extension().is_some_and(|ext| ext == EXTENSION) {
+            let metadata = entry.metadata()?;
+
+            files.push(Entry {
+                path,
+                len: metadata.len(),
+                modified: metadata.modified()?,
+            });
+        }
+    }
+
+    Ok(files)
+}
+
+impl Index {
+    /// Index of the cached `files`, skipping those not named after a paste.
+    fn new(files: &[Entry]) -> Self {
+        let mut index = Self::default();
+
+        for file in files {
+            let id = file
+                .path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.split_once('.'))
+                .and_then(|(id, _)| id.parse::<Id>().ok());
+
+            if let Some(id) = id {
+                index.insert(id, file.path.clone(), file.len);
+            }
+        }
+
+        index
+    }
+
+    /// Record the file at `path` of paste `id` with `len` bytes, replacing a previous one.
+    fn insert(&mut self, id: Id, path: PathBuf, len: u64) {
+        let previous = self.files.entry(id).or_default().insert(path, len);
+        self.size = (self.size + len).saturating_sub(previous.unwrap_or(0));
+    }
+}
+
+impl DiskCache {
+    /// Open the cache in `dir`, creating the directory if it does not exist.
+    pub fn new(dir: PathBuf, max_bytes: u64, version: u64) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+
+        let index = Index::new(&files(&dir)?);
+
+        Ok(Self {
+            dir,
+            max_bytes,
+            version,
+            index: Mutex::new(index),
+        })
+    }
+
+    /// Path of the entry for `key`, hashed with SHA-256 so that names stay the same across
+    /// builds and toolchains.
+    fn path(&self, key: &Key) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.update(self.version.to_le_bytes());
+        hasher.update(key.ext.as_bytes());
+        hasher.update([0]);
+        hasher.update(format!("{:?}", key.view).as_bytes());
+        let hash = hex::encode(&hasher.finalize()[..8]);
+
+        self.dir.join(format!("{}.{hash}.{EXTENSION}", key.id))
+    }
+
+    /// Read the entry for `key` and mark it as recently used.
+    pub fn get(&self, key: &Key) -> io::Result<Option<String>> {
+        let path = self.path(key);
+
+        match fs::read_to_string(&path) {
+            Ok(html) => {
+                File::options()
+                    .write(true)
+                    .open(&path)?
+                    .set_modified(SystemTime::now())?;
+
+                Ok(Some(html))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Write the entry for `key` and evict old entries if the size limit is exceeded.
+    pub fn put(&self, key: &Key, html: &str) -> io::Result<()> {
+        let path = self.path(key);
+
+        // Write to a uniquely named temporary file first, so concurrent readers never see
+        // partial content and concurrent writers of the same key do not clobber each other.
+        let mut tmp = tempfile::NamedTempFile::new_in(&self.dir)?;
+        tmp.write_all(html.as_bytes())?;
+        tmp.persist(&path)?;
+
+        let mut index = self.index.lock().expect("getting lock");
+        index.insert(key.id, path, html.len() as u64);
+
+        if index.size > self.max_bytes {
+            *index = self.evict()?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove all entries of the paste `id`.
+    pub fn remove(&self, id: Id) -> io::Result<()> {
+        let mut index = self.index.lock().expect("getting lock");
+
+        for (path, len) in index.files.remove(&id).unwrap_or_default() {
+            match fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+
+            index.size = index.size.saturating_sub(len);
+        }
+
+        Ok(())
+    }
+
+    /// Remove least recently used files until the cache fits into its size limit and return the
+    /// index of the remaining ones.
+    fn evict(&self) -> io::Result<Index> {
+        let mut files = files(&self.dir)?;
+        let mut size = files.iter().map(|file| file.len).sum::<u64>();
+
+        files.sort_by_key(|file| file.modified);
+
+        let mut evicted = 0;
+
+        for file in &files {
+            if size <= self.max_bytes {
+                break;
+            }
+
+            tracing::trace!(path = ?file.path, "evict cached file");
+            fs::remove_file(&file.path)?;
+            size -= file.len;
+            evicted += 1;
+        }
+
+        Ok(Index::new(&files[evicted..]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::highlight::View;
+    use std::str::FromStr;
+
+    fn dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("wastebin-disk-cache-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn persist_entries() {
+        let dir = dir("persist");
+        let key = Key::from_str("bJZCna.rs").unwrap();
+
+        let cache = DiskCache::new(dir.clone(), 1024, 1).unwrap();
+        assert!(cache.get(&key).unwrap().is_none());
+        cache.put(&key, "<b>rust</b>").unwrap();
+
+        // Reopening the cache simulates a restart.
+        let cache = DiskCache::new(dir.clone(), 1024, 1).unwrap();
+        assert_eq!(cache.get(&key).unwrap().as_deref(), Some("<b>rust</b>"));
+
+        let pretty = Key {
+            view: View::Pretty,
+            ..key.clone()
+        };
+        assert!(cache.get(&pretty).unwrap().is_none());
+
+        // Names must not change between builds, or restarts would lose all entries.
+        let name = cache.path(&key);
+        let name = name.file_name().unwrap().to_str().unwrap();
+        assert_eq!(name, "bJZCna.cab7f5cc0b513378.html");
+
+        // Renderings of another version are not served.
+        let cache = DiskCache::new(dir.clone(), 1024, 2).unwrap();
+        assert!(cache.get(&key).unwrap().is_none());
+
+        // Files found when opening the cache are indexed for removal.
+        cache.remove(key.id).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn evict_and_remove() {
+        let dir = dir("evict");
+        let cache = DiskCache::new(dir.clone(), 10, 1).unwrap();
+        let first = Key::from_str("bJZCna.rs").unwrap();
+        let second = Key::from_str("sIiFec.rs").unwrap();
+        let third = Key::from_str("sIiFec.py").unwrap();
+
+        cache.put(&first, "123456").unwrap();
+        File::options()
+            .write(true)
+            .open(cache.path(&first))
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        cache.put(&second, "123456").unwrap();
+
+        assert!(cache.get(&first).unwrap().is_none());
+        assert!(cache.get(&second).unwrap().is_some());
+
+        cache.put(&third, "1234").unwrap();
+        cache.remove(second.id).unwrap();
+        assert!(cache.get(&second).unwrap().is_none());
+        assert!(cache.get(&third).unwrap().is_none());
+        assert_eq!(cache.index.lock().unwrap().size, 0);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn concurrent_writes() {
+        let dir = dir("concurrent");
+        let cache = DiskCache::new(dir.clone(), 1024, 1).unwrap();
+        let key = Key::from_str("bJZCna.rs").unwrap();
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| cache.put(&key, "<b>rust</b>").unwrap());
+            }
+        });
+
+        assert_eq!(cache.get(&key).unwrap().as_deref(), Some("<b>rust</b>"));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,115 @@
+use crate::cache::{Cache, Key};
+use crate::db::read::Entry;
+use crate::highlight::{Rendered, View};
+use crate::{Database, Highlighter};
+use std::sync::Arc;
+
+/// Render the `count` most viewed pastes into `cache` one after another, so that the first
+/// viewers after a restart find them highlighted. The cache and highlighting configuration is only
+/// read at startup, so this also covers changes of it: pastes cached on disk with the current
+/// renderer are only loaded into memory, the others are rendered anew.
+pub(crate) async fn warm(cache: Cache, db: Database, highlighter: Highlighter, count: usize) {
+    let pastes = match db.popular(count).await {
+        Ok(pastes) => pastes,
+        Err(err) => {
+            tracing::warn!("failed to list popular pastes: {err}");
+            return;
+        }
+    };
+
+    let mut rendered = 0;
+
+    for paste in pastes {
+        let key = Key {
+            id: paste.id,
+            ext: paste.extension.unwrap_or_else(|| String::from("txt")),
+            view: View::Source,
+            token: paste.secret,
+        };
+
+        if cache.contains(&key) {
+            continue;
+        }
+
+        let Ok(Entry::Regular(data)) = db.get(key.id, key.token.as_deref(), None).await else {
+            continue;
+        };
+
+        if highlighter.exceeds_limit(&data.text) {
+            continue;
+        }
+
+        let html = match Arc::clone(&highlighter)
+            .highlight(data, key.ext.clone(), key.view)
+            .await
+        {
+            Ok(Rendered::Done(html)) => html,
+            Ok(Rendered::Late(_, task)) => match task.await {
+                Ok(Ok(html)) => html,
+                _ => continue,
+            },
+            Err(err) => {
+                tracing::warn!(?key, "failed to warm cache: {err}");
+                continue;
+            }
+        };
+
+        cache.put(key, html);
+        rendered += 1;
+    }
+
+    tracing::debug!("warmed the cache with {rendered} pastes");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{self, write};
+    use crate::id::Id;
+    use std::num::NonZeroUsize;
+
+    #[tokio::test]
+    async fn most_viewed_first() -> Result<(), Box<dyn std::error::Error>> {
+        let db = Database::new(db::Open::Memory)?;
+        let cache = Cache::new(NonZeroUsize::new(8).unwrap());
+        let highlighter = Arc::new(crate::highlight::Highlighter::default());
+
+        for (number, views) in [(1u32, 1), (2, 3), (3, 0)] {
+            let entry = write::Entry {
+                text: String::from("fn main() {}"),
+                extension: Some(String::from("rs")),
+                ..Default::default()
+            };
+
+            let id = Id::from(number);
+            db.insert(id, entry).await?;
+
+            for _ in 0..views {
+                db.record_view(id);
+            }
+        }
+
+        let burned = write::Entry {
+            text: String::from("secret"),
+            burn_after_reading: Some(true),
+            ..Default::default()
+        };
+        db.insert(Id::from(4u32), burned).await?;
+        db.record_view(Id::from(4u32));
+
+        let pastes = db.popular(8).await?;
+        let ids = pastes.iter().map(|paste| paste.id).collect::<Vec<_>>();
+        assert_eq!(ids, [Id::from(2u32), Id::from(1u32)]);
+
+        warm(cache.clone(), db, highlighter, 1).await;
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains(&Key {
+            id: Id::from(2u32),
+            ext: String::from("rs"),
+            view: View::Source,
+            token: None,
+        }));
+
+        Ok(())
+    }
+}
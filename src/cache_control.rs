@@ -0,0 +1,246 @@
+use crate::Page;
+use crate::handlers::extract::PASSWORD_HEADER_NAME;
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::header::{AUTHORIZATION, CACHE_CONTROL, COOKIE, SET_COOKIE, VARY};
+use axum::http::{HeaderValue, Method};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::sync::Arc;
+
+/// Directives without a value accepted in configured headers.
+const FLAGS: [&str; 9] = [
+    "public",
+    "private",
+    "no-cache",
+    "no-store",
+    "no-transform",
+    "must-revalidate",
+    "proxy-revalidate",
+    "must-understand",
+    "immutable",
+];
+
+/// Directives with a number of seconds accepted in configured headers.
+const DURATIONS: [&str; 4] = [
+    "max-age",
+    "s-maxage",
+    "stale-while-revalidate",
+    "stale-if-error",
+];
+
+/// Header of responses with pastes that are gone after being read once.
+pub(crate) const NO_STORE: HeaderValue = HeaderValue::from_static("no-store");
+
+/// Configured `Cache-Control` headers by kind of response, keeping the defaults if unset.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Policy {
+    /// Formatted paste views.
+    pub paste: Option<HeaderValue>,
+    /// Raw paste bodies and downloads.
+    pub raw: Option<HeaderValue>,
+    /// Stylesheets, scripts and the favicon.
+    pub assets: Option<HeaderValue>,
+}
+
+/// State of the [`apply`] middleware.
+#[derive(Clone)]
+pub(crate) struct Headers {
+    pub policy: Arc<Policy>,
+    pub page: Page,
+}
+
+/// Parse a comma-separated list of `Cache-Control` directives such as
+/// `public, max-age=60, s-maxage=300, stale-while-revalidate=30`.
+pub(crate) fn parse(value: &str) -> Result<HeaderValue, String> {
+    let mut directives = Vec::new();
+
+    for directive in value.split(',').map(str::trim) {
+        match directive.split_once('=') {
+            Some((name, seconds)) if DURATIONS.contains(&name) => {
+                let seconds = seconds
+                    .parse::<u32>()
+                    .map_err(|err| format!("{directive}: {err}"))?;
+                directives.push(format!("{name}={seconds}"));
+            }
+            None if FLAGS.contains(&directive) => directives.push(directive.to_string()),
+            _ => return Err(format!("unknown directive `{directive}`")),
+        }
+    }
+
+    HeaderValue::try_from(directives.join(", ")).map_err(|err| err.to_string())
+}
+
+impl Policy {
+    pub fn is_empty(&self) -> bool {
+        self.paste.is_none() && self.raw.is_none() && self.assets.is_none()
+    }
+}
+
+/// Replace the `Cache-Control` header of successful `GET` responses with the one configured for
+/// their kind. Responses that set cookies or answer requests carrying a password keep their
+/// headers, as do pastes marked `no-store` because they are burned after reading. Paste views
+/// contain the CSRF token and controls of the viewer, so they keep their headers for requests
+/// carrying cookies or credentials and otherwise vary by cookie.
+pub(crate) async fn apply(
+    State(headers): State<Headers>,
+    path: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = path.as_ref().map(MatchedPath::as_str);
+
+    let configured = match route {
+        _ if request.method() != Method::GET
+            || request.headers().contains_key(PASSWORD_HEADER_NAME) =>
+        {
+            None
+        }
+        Some("/:id")
+            if request.headers().contains_key(COOKIE)
+                || request.headers().contains_key(AUTHORIZATION) =>
+        {
+            None
+        }
+        Some("/:id") => headers.policy.paste.clone(),
+        Some("/raw/:id" | "/dl/:id") => headers.policy.raw.clone(),
+        Some(route) if headers.page.assets.contains(route) => headers.policy.assets.clone(),
+        _ => None,
+    };
+
+    let mut response = next.run(request).await;
+
+    let Some(value) = configured else {
+        return response;
+    };
+
+    let no_store = response
+        .headers()
+        .get(CACHE_CONTROL)
+        .is_some_and(|value| value.as_bytes().starts_with(b"no-store"));
+
+    if response.status().is_success() && !no_store && !response.headers().contains_key(SET_COOKIE) {
+        response.headers_mut().insert(CACHE_CONTROL, value);
+
+        if route == Some("/:id") {
+            response
+                .headers_mut()
+                .append(VARY, HeaderValue::from_static("cookie"));
+        }
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+
+    #[test]
+    fn parse_directives() {
+        assert_eq!(
+            parse("public,max-age=60, s-maxage=300 , stale-while-revalidate=30").unwrap(),
+            "public, max-age=60, s-maxage=300, stale-while-revalidate=30"
+        );
+        assert_eq!(parse("no-store").unwrap(), "no-store");
+        assert!(parse("max-age=soon").is_err());
+        assert!(parse("public, forever").is_err());
+        assert!(parse("public=1").is_err());
+    }
+
+    #[tokio::test]
+    async fn configured_headers() -> Result<(), Box<dyn std::error::Error>> {
+        let policy = Policy {
+            paste: None,
+            raw: Some(parse("public, max-age=60, s-maxage=60")?),
+            assets: Some(parse("public, max-age=3600")?),
+        };
+        let client = Client::with_cache_control(StoreCookies(false), policy).await;
+        let header = |res: &reqwest::Response| {
+            res.headers()
+                .get("cache-control")
+                .map(|value| value.to_str().unwrap().to_string())
+        };
+
+        let data = Entry {
+            text: String::from("FooBarBaz"),
+            ..Default::default()
+        };
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?.to_string();
+
+        let res = client.get(&format!("/raw{location}")).send().await?;
+        assert_eq!(
+            header(&res).as_deref(),
+            Some("public, max-age=60, s-maxage=60")
+        );
+
+        let res = client.get(&location).send().await?;
+        assert_eq!(header(&res), None);
+
+        let res = client.get("/raw/abcdef").send().await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(header(&res), None);
+
+        let res = client.get("/favicon.ico").send().await?;
+        assert_eq!(header(&res).as_deref(), Some("public, max-age=3600"));
+
+        let data = Entry {
+            text: String::from("FooBarBaz"),
+            burn_after_reading: Some(String::from("on")),
+            ..Default::default()
+        };
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+        let id = location.trim_start_matches("/burn");
+
+        let res = client.get(&format!("/raw{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(header(&res).as_deref(), Some("no-store"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn paste_with_cookie() -> Result<(), Box<dyn std::error::Error>> {
+        let policy = Policy {
+            paste: Some(parse("public, s-maxage=300")?),
+            ..Default::default()
+        };
+        let client = Client::with_cache_control(StoreCookies(false), policy).await;
+
+        let data = Entry {
+            text: String::from("FooBarBaz"),
+            ..Default::default()
+        };
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?.to_string();
+
+        let res = client.get(&location).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["cache-control"], "public, s-maxage=300");
+        assert!(
+            res.headers()
+                .get_all("vary")
+                .iter()
+                .any(|value| value == "cookie")
+        );
+
+        let res = client
+            .get(&location)
+            .header("cookie", "session=abc")
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_ne!(
+            res.headers()
+                .get("cache-control")
+                .map(|value| value.to_str().unwrap()),
+            Some("public, s-maxage=300")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use crate::errors::Error;
+use crate::id::Id;
+use crate::keys::Keys;
+use axum::http::HeaderName;
+use axum_extra::extract::cookie::Key;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Header carrying a capability token for API requests.
+pub(crate) const HEADER: HeaderName = HeaderName::from_static("wastebin-token");
+
+/// Seconds an issued token stays valid.
+const VALIDITY: u64 = 30 * 24 * 60 * 60;
+
+/// Action a token grants on a single paste.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Right {
+    Delete,
+    Edit,
+}
+
+impl Right {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Delete => "delete",
+            Self::Edit => "edit",
+        }
+    }
+}
+
+/// Generate the capability token of a secret paste, which is required next to the identifier
+/// in its URLs.
+pub(crate) fn secret() -> String {
+    hex::encode(rand::random::<[u8; 32]>())
+}
+
+/// Return `true` if `given` matches the `expected` token of a secret paste or the paste is not
+/// secret. Tokens are compared in constant time to not reveal matching prefixes.
+pub(crate) fn secret_matches(expected: Option<&str>, given: Option<&str>) -> bool {
+    let Some(expected) = expected else {
+        return true;
+    };
+
+    let given = given.unwrap_or_default();
+
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+fn mac(key: &Key, id: Id, right: Right, expires: u64) -> Option<Hmac<Sha256>> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.signing()).ok()?;
+    mac.update(format!("capability:{}:{}:{expires}", right.name(), id.to_i64()).as_bytes());
+    Some(mac)
+}
+
+/// Issue a token of the form `<expires>.<signature>` granting `right` on paste `id`.
+pub(crate) fn issue(key: &Key, id: Id, right: Right) -> Result<String, Error> {
+    let expires = now() + VALIDITY;
+    let mac = mac(key, id, right, expires).ok_or(Error::Capability)?;
+    Ok(format!(
+        "{expires}.{}",
+        hex::encode(mac.finalize().into_bytes())
+    ))
+}
+
+/// Check that `token` grants `right` on paste `id`, has not expired and is signed with the
+/// current or a previous signing key.
+pub(crate) fn verify(keys: &Keys, id: Id, right: Right, token: &str) -> Result<(), Error> {
+    let (expires, signature) = token.split_once('.').ok_or(Error::Capability)?;
+    let expires = expires.parse::<u64>().map_err(|_| Error::Capability)?;
+    let signature = hex::decode(signature).map_err(|_| Error::Capability)?;
+
+    if expires < now() {
+        return Err(Error::Capability);
+    }
+
+    std::iter::once(&keys.key)
+        .chain(keys.previous.iter())
+        .filter_map(|key| mac(key, id, right, expires))
+        .any(|mac| mac.verify_slice(&signature).is_ok())
+        .then_some(())
+        .ok_or(Error::Capability)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn tokens() {
+        let old = Key::generate();
+        let keys = Keys {
+            key: Key::generate(),
+            previous: Arc::new([old.clone()]),
+        };
+        let id = Id::from_i64(1234);
+        let token = issue(&keys.key, id, Right::Delete).unwrap();
+
+        assert!(verify(&keys, id, Right::Delete, &token).is_ok());
+        assert!(verify(&keys, id, Right::Edit, &token).is_err());
+        assert!(verify(&keys, Id::from_i64(1235), Right::Delete, &token).is_err());
+        assert!(verify(&keys, id, Right::Delete, "1.00").is_err());
+
+        let token = issue(&old, id, Right::Edit).unwrap();
+        assert!(verify(&keys, id, Right::Edit, &token).is_ok());
+
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged = format!("{}.{signature}", now() + 2 * VALIDITY);
+        assert!(verify(&keys, id, Right::Edit, &forged).is_err());
+
+        let expires = now() - 1;
+        let mac = mac(&keys.key, id, Right::Edit, expires).unwrap();
+        let stale = format!("{expires}.{}", hex::encode(mac.finalize().into_bytes()));
+        assert!(verify(&keys, id, Right::Edit, &stale).is_err());
+    }
+
+    #[test]
+    fn secrets() {
+        let token = secret();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, secret());
+
+        assert!(secret_matches(None, None));
+        assert!(secret_matches(None, Some("foo")));
+        assert!(secret_matches(Some(&token), Some(&token)));
+        assert!(!secret_matches(Some(&token), None));
+        assert!(!secret_matches(Some(&token), Some(&token[1..])));
+        assert!(!secret_matches(Some(&token), Some(&secret())));
+    }
+}
@@ -0,0 +1,147 @@
+#[cfg(feature = "captcha")]
+use crate::errors::Error;
+#[cfg(feature = "captcha")]
+use std::net::IpAddr;
+
+/// Header carrying the bypass token for anonymous API requests.
+#[cfg(feature = "captcha")]
+pub(crate) const BYPASS_HEADER: axum::http::HeaderName =
+    axum::http::HeaderName::from_static("wastebin-captcha-bypass");
+
+/// Service asking anonymous users to prove they are human.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Provider {
+    HCaptcha,
+    Turnstile,
+}
+
+/// Captcha widget shown on the form of anonymous users.
+#[derive(Debug, Clone)]
+pub(crate) struct Widget {
+    pub provider: Provider,
+    /// Public key identifying the site to the provider.
+    pub site_key: String,
+}
+
+/// Verifies widget responses with the provider.
+#[cfg(feature = "captcha")]
+pub(crate) struct Verifier {
+    url: String,
+    secret: String,
+    /// Token anonymous API requests must pass instead of solving a captcha.
+    bypass: Option<String>,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "captcha")]
+#[derive(serde::Deserialize)]
+struct Verification {
+    success: bool,
+}
+
+impl std::str::FromStr for Provider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hcaptcha" => Ok(Provider::HCaptcha),
+            "turnstile" => Ok(Provider::Turnstile),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+impl Provider {
+    /// Script rendering the widget.
+    pub fn script(self) -> &'static str {
+        match self {
+            Provider::HCaptcha => "https://js.hcaptcha.com/1/api.js",
+            Provider::Turnstile => "https://challenges.cloudflare.com/turnstile/v0/api.js",
+        }
+    }
+
+    /// Class of the element the widget is rendered into.
+    pub fn class(self) -> &'static str {
+        match self {
+            Provider::HCaptcha => "h-captcha",
+            Provider::Turnstile => "cf-turnstile",
+        }
+    }
+
+    /// Origins the widget loads scripts, styles and frames from.
+    pub fn origins(self) -> &'static str {
+        match self {
+            Provider::HCaptcha => "https://hcaptcha.com https://*.hcaptcha.com",
+            Provider::Turnstile => "https://challenges.cloudflare.com",
+        }
+    }
+
+    /// Endpoint verifying widget responses.
+    #[cfg(feature = "captcha")]
+    fn verify_url(self) -> &'static str {
+        match self {
+            Provider::HCaptcha => "https://api.hcaptcha.com/siteverify",
+            Provider::Turnstile => "https://challenges.cloudflare.com/turnstile/v0/siteverify",
+        }
+    }
+}
+
+#[cfg(feature = "captcha")]
+impl Verifier {
+    /// Create a verifier for `provider` authenticating with `secret`.
+    pub fn new(provider: Provider, secret: String, bypass: Option<String>) -> Self {
+        Self {
+            url: provider.verify_url().to_string(),
+            secret,
+            bypass,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Send verification requests to `url` instead of the provider.
+    #[cfg(test)]
+    pub fn with_url(mut self, url: String) -> Self {
+        self.url = url;
+        self
+    }
+
+    /// Check that `response` of the widget was solved by the user at `ip`.
+    pub async fn verify(&self, response: &str, ip: IpAddr) -> Result<(), Error> {
+        if response.is_empty() {
+            return Err(Error::Captcha);
+        }
+
+        let provider = |err: reqwest::Error| Error::CaptchaProvider(err.to_string());
+
+        let verification = self
+            .client
+            .post(&self.url)
+            .form(&[
+                ("secret", self.secret.as_str()),
+                ("response", response),
+                ("remoteip", &ip.to_string()),
+            ])
+            .send()
+            .await
+            .map_err(provider)?
+            .error_for_status()
+            .map_err(provider)?
+            .json::<Verification>()
+            .await
+            .map_err(provider)?;
+
+        if !verification.success {
+            return Err(Error::Captcha);
+        }
+
+        Ok(())
+    }
+
+    /// Check that an anonymous API request passes the bypass `token` if one is configured.
+    pub fn check_bypass(&self, token: Option<&str>) -> Result<(), Error> {
+        match &self.bypass {
+            Some(bypass) if token != Some(bypass.as_str()) => Err(Error::Captcha),
+            _ => Ok(()),
+        }
+    }
+}
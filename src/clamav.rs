@@ -0,0 +1,207 @@
+use crate::errors::Error;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Maximum time for connecting to clamd and scanning a paste.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Size of chunks streamed to clamd, well below its default `StreamMaxLength`.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Socket clamd listens on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Address {
+    /// Local socket given by an absolute path.
+    #[cfg(unix)]
+    Unix(PathBuf),
+    /// TCP socket given as `host:port`.
+    Tcp(String),
+}
+
+/// Scans pastes with a clamd antivirus daemon using its `INSTREAM` command.
+#[derive(Debug, Clone)]
+pub(crate) struct Scanner {
+    address: Arc<Address>,
+}
+
+impl FromStr for Address {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        #[cfg(unix)]
+        if s.starts_with('/') {
+            return Ok(Self::Unix(PathBuf::from(s)));
+        }
+
+        if s.contains(':') {
+            Ok(Self::Tcp(s.to_string()))
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl Scanner {
+    pub fn new(address: Address) -> Self {
+        Self {
+            address: Arc::new(address),
+        }
+    }
+
+    /// Scan `data`, failing with [`Error::Infected`] and the signature name if clamd found
+    /// malware. Pastes are rejected as well if clamd cannot be reached, so that nothing is stored
+    /// unscanned.
+    pub async fn scan(&self, data: &[u8]) -> Result<(), Error> {
+        let reply = tokio::time::timeout(TIMEOUT, async {
+            match &*self.address {
+                #[cfg(unix)]
+                Address::Unix(path) => {
+                    instream(tokio::net::UnixStream::connect(path).await?, data).await
+                }
+                Address::Tcp(addr) => {
+                    instream(tokio::net::TcpStream::connect(addr).await?, data).await
+                }
+            }
+        })
+        .await
+        .map_err(|_| Error::Clamd(String::from("timed out")))?
+        .map_err(|err| Error::Clamd(err.to_string()))?;
+
+        match parse_reply(&reply) {
+            Reply::Clean => Ok(()),
+            Reply::Infected(signature) => {
+                tracing::warn!("rejected paste infected with {signature}");
+                Err(Error::Infected(signature.to_string()))
+            }
+            Reply::Failed(reply) => Err(Error::Clamd(reply.to_string())),
+        }
+    }
+}
+
+/// Verdict of clamd.
+#[derive(Debug, PartialEq, Eq)]
+enum Reply<'a> {
+    Clean,
+    Infected(&'a str),
+    Failed(&'a str),
+}
+
+/// Stream `data` in chunks prefixed by their length to clamd and return its reply.
+async fn instream<S>(mut stream: S, data: &[u8]) -> std::io::Result<String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(b"zINSTREAM\0").await?;
+
+    for chunk in data.chunks(CHUNK_SIZE) {
+        let len = u32::try_from(chunk.len()).expect("chunk fits into u32");
+        stream.write_all(&len.to_be_bytes()).await?;
+        stream.write_all(chunk).await?;
+    }
+
+    stream.write_all(&0u32.to_be_bytes()).await?;
+    stream.flush().await?;
+
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply).await?;
+
+    Ok(String::from_utf8_lossy(&reply)
+        .trim_end_matches(['\0', '\n'])
+        .to_string())
+}
+
+/// Parse replies like `stream: OK` or `stream: Eicar-Test-Signature FOUND`.
+fn parse_reply(reply: &str) -> Reply<'_> {
+    let verdict = reply.strip_prefix("stream: ").unwrap_or(reply);
+
+    if verdict == "OK" {
+        Reply::Clean
+    } else if let Some(signature) = verdict.strip_suffix(" FOUND") {
+        Reply::Infected(signature)
+    } else {
+        Reply::Failed(reply)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    pub(crate) const EICAR: &str =
+        r"X5O!P%@AP[4\PZX54(P^)7CC)7}$EICAR-STANDARD-ANTIVIRUS-TEST-FILE!$H+H*";
+
+    /// Spawn a fake clamd that finds the EICAR test signature and return its address.
+    pub(crate) async fn clamd() -> Address {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+
+                tokio::spawn(async move {
+                    let mut command = [0; 10];
+                    stream.read_exact(&mut command).await.unwrap();
+                    assert_eq!(&command, b"zINSTREAM\0");
+
+                    let mut data = Vec::new();
+
+                    loop {
+                        let len = stream.read_u32().await.unwrap() as usize;
+
+                        if len == 0 {
+                            break;
+                        }
+
+                        let mut chunk = vec![0; len];
+                        stream.read_exact(&mut chunk).await.unwrap();
+                        data.extend(chunk);
+                    }
+
+                    let reply: &[u8] = if String::from_utf8_lossy(&data).contains(EICAR) {
+                        b"stream: Eicar-Test-Signature FOUND\0"
+                    } else {
+                        b"stream: OK\0"
+                    };
+
+                    stream.write_all(reply).await.unwrap();
+                });
+            }
+        });
+
+        Address::Tcp(addr.to_string())
+    }
+
+    #[test]
+    fn replies() {
+        assert_eq!(parse_reply("stream: OK"), Reply::Clean);
+        assert_eq!(
+            parse_reply("stream: Win.Test.EICAR_HDB-1 FOUND"),
+            Reply::Infected("Win.Test.EICAR_HDB-1")
+        );
+        assert_eq!(
+            parse_reply("INSTREAM size limit exceeded. ERROR"),
+            Reply::Failed("INSTREAM size limit exceeded. ERROR")
+        );
+    }
+
+    #[tokio::test]
+    async fn scan() {
+        let scanner = Scanner::new(clamd().await);
+
+        assert!(scanner.scan(b"FooBarBaz").await.is_ok());
+        assert!(scanner.scan(&vec![b'a'; 3 * CHUNK_SIZE]).await.is_ok());
+        assert!(matches!(
+            scanner.scan(EICAR.as_bytes()).await,
+            Err(Error::Infected(signature)) if signature == "Eicar-Test-Signature"
+        ));
+
+        let scanner = Scanner::new(Address::Tcp(String::from("127.0.0.1:1")));
+        assert!(matches!(scanner.scan(b"").await, Err(Error::Clamd(_))));
+    }
+}
@@ -0,0 +1,406 @@
+use crate::accounts;
+use crate::cache::Key;
+use crate::db::Database;
+use crate::db::read::Listing;
+use crate::db::write::Action;
+use std::io::Write;
+
+/// Usage shown by `wastebin help` and on invalid arguments.
+pub const USAGE: &str = "\
+Usage: wastebin [COMMAND]
+
+Commands:
+  (none)                           Serve pastes
+  admin list [FILTER]              List pastes, most recent first
+  admin delete [FILTER | ID...]    Delete pastes matching FILTER or with the given identifiers
+  admin purge                      Delete all expired pastes that are not pinned
+  admin stats                      Show instance statistics
+  admin invite create [--uses <N>] Create an invite code for N registrations, 1 by default
+  admin invite list                List invite codes with their remaining uses
+  admin invite revoke <CODE>       Delete an invite code
+  help                             Show this message
+
+Filters:
+  --match <PATTERN>                Identifier, language or owner equal to or title containing PATTERN
+  --older-than <DAYS>              Created more than DAYS days ago
+
+Admin commands operate on the database at WASTEBIN_DATABASE_PATH.";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("unknown command `{0}`")]
+    UnknownCommand(String),
+    #[error("unknown option `{0}`")]
+    UnknownOption(String),
+    #[error("unexpected argument `{0}`")]
+    UnexpectedArgument(String),
+    #[error("missing value for `{0}`")]
+    MissingValue(String),
+    #[error("failed to parse `--older-than`, expected number of days: {0}")]
+    OlderThan(String),
+    #[error("failed to parse `--uses`, expected positive number: {0}")]
+    Uses(String),
+    #[error("refusing to delete all pastes, pass identifiers or a filter")]
+    Unfiltered,
+    #[error("identifiers cannot be combined with filters")]
+    IdsWithFilter,
+    #[error("WASTEBIN_DATABASE_PATH must be set to administer a database")]
+    InMemory,
+}
+
+/// Command selected by the command line arguments.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Command {
+    Serve,
+    Help,
+    Admin(Admin),
+}
+
+/// Administrative command operating on the database.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Admin {
+    List(Filter),
+    Delete(Filter, Vec<String>),
+    Purge,
+    Stats,
+    CreateInvite(u32),
+    Invites,
+    RevokeInvite(String),
+}
+
+/// Criteria pastes must match to be listed or deleted.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Filter {
+    /// Search term as used by the admin dashboard.
+    pub pattern: Option<String>,
+    /// Minimum age in days.
+    pub older_than: Option<u32>,
+}
+
+impl Filter {
+    fn is_empty(&self) -> bool {
+        self.pattern.is_none() && self.older_than.is_none()
+    }
+}
+
+/// Parse the command line `args` without the program name.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, Error> {
+    let mut args = args.into_iter();
+
+    let Some(command) = args.next() else {
+        return Ok(Command::Serve);
+    };
+
+    match command.as_str() {
+        "help" | "-h" | "--help" => return Ok(Command::Help),
+        "admin" => {}
+        _ => return Err(Error::UnknownCommand(command)),
+    }
+
+    let Some(command) = args.next() else {
+        return Ok(Command::Help);
+    };
+
+    if command == "invite" {
+        return parse_invite(args).map(Command::Admin);
+    }
+
+    let mut filter = Filter::default();
+    let mut ids = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--match" => {
+                filter.pattern = Some(args.next().ok_or(Error::MissingValue(arg))?);
+            }
+            "--older-than" => {
+                let days = args.next().ok_or(Error::MissingValue(arg))?;
+                let days = days
+                    .parse()
+                    .map_err(|err: std::num::ParseIntError| Error::OlderThan(err.to_string()))?;
+                filter.older_than = Some(days);
+            }
+            _ if arg.starts_with('-') => return Err(Error::UnknownOption(arg)),
+            _ => ids.push(arg),
+        }
+    }
+
+    if command != "delete" {
+        if let Some(id) = ids.into_iter().next() {
+            return Err(Error::UnexpectedArgument(id));
+        }
+
+        if command != "list" && !filter.is_empty() {
+            return Err(Error::UnexpectedArgument(String::from("filter")));
+        }
+
+        return match command.as_str() {
+            "list" => Ok(Command::Admin(Admin::List(filter))),
+            "purge" => Ok(Command::Admin(Admin::Purge)),
+            "stats" => Ok(Command::Admin(Admin::Stats)),
+            _ => Err(Error::UnknownCommand(command)),
+        };
+    }
+
+    if ids.is_empty() && filter.is_empty() {
+        return Err(Error::Unfiltered);
+    }
+
+    if !ids.is_empty() && !filter.is_empty() {
+        return Err(Error::IdsWithFilter);
+    }
+
+    Ok(Command::Admin(Admin::Delete(filter, ids)))
+}
+
+/// Parse the arguments of `admin invite`.
+fn parse_invite(mut args: impl Iterator<Item = String>) -> Result<Admin, Error> {
+    let command = args.next().unwrap_or_default();
+
+    let admin = match command.as_str() {
+        "create" => match args.next().as_deref() {
+            None => Admin::CreateInvite(1),
+            Some("--uses") => {
+                let uses = args
+                    .next()
+                    .ok_or(Error::MissingValue(String::from("--uses")))?;
+                let uses = uses
+                    .parse::<u32>()
+                    .map_err(|err| Error::Uses(err.to_string()))?;
+
+                if uses == 0 {
+                    return Err(Error::Uses(String::from("must not be zero")));
+                }
+
+                Admin::CreateInvite(uses)
+            }
+            Some(arg) => return Err(Error::UnknownOption(arg.to_string())),
+        },
+        "list" => Admin::Invites,
+        "revoke" => Admin::RevokeInvite(
+            args.next()
+                .ok_or(Error::MissingValue(String::from("revoke")))?,
+        ),
+        _ => return Err(Error::UnknownCommand(format!("invite {command}"))),
+    };
+
+    if let Some(arg) = args.next() {
+        return Err(Error::UnexpectedArgument(arg));
+    }
+
+    Ok(admin)
+}
+
+/// Write a tab-separated line with identifier, creation time, expiration, size in bytes, owner
+/// and title of `paste`.
+fn write_listing(
+    out: &mut impl Write,
+    paste: &Listing,
+    owner: Option<&str>,
+) -> std::io::Result<()> {
+    let expires = if paste.expired {
+        "expired"
+    } else if paste.pinned {
+        "pinned"
+    } else {
+        paste.expires.as_deref().unwrap_or("never")
+    };
+
+    writeln!(
+        out,
+        "{}\t{}\t{expires}\t{}\t{}\t{}",
+        paste.id,
+        paste.created.as_deref().unwrap_or("-"),
+        paste.size,
+        owner.unwrap_or("-"),
+        paste.title.as_deref().unwrap_or_default(),
+    )
+}
+
+/// Run the `admin` command on `db` and write the results to `out`.
+pub async fn run(
+    db: &Database,
+    admin: Admin,
+    out: &mut impl Write,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match admin {
+        Admin::List(filter) => {
+            for (paste, owner) in db
+                .list_all(filter.pattern, filter.older_than, 0, None)
+                .await?
+            {
+                write_listing(out, &paste, owner.as_deref())?;
+            }
+        }
+        Admin::Delete(filter, ids) => {
+            let ids = if ids.is_empty() {
+                db.list_all(filter.pattern, filter.older_than, 0, None)
+                    .await?
+                    .into_iter()
+                    .map(|(paste, _)| paste.id)
+                    .collect()
+            } else {
+                ids.iter()
+                    .map(|id| id.parse::<Key>().map(|key| key.id))
+                    .collect::<Result<Vec<_>, _>>()?
+            };
+
+            for id in ids {
+                db.delete(id).await?;
+                db.audit(None, Action::Delete, Some(id.to_string())).await?;
+                writeln!(out, "{id}")?;
+            }
+        }
+        Admin::Purge => {
+            let ids = db.purge().await?;
+            db.audit(None, Action::Purge, Some(ids.len().to_string()))
+                .await?;
+
+            for id in ids {
+                writeln!(out, "{id}")?;
+            }
+        }
+        Admin::Stats => {
+            let stats = db.stats().await?;
+
+            writeln!(out, "pastes\t{}", stats.pastes)?;
+            writeln!(out, "bytes\t{}", stats.bytes)?;
+            writeln!(out, "expired\t{}", stats.expired)?;
+            writeln!(out, "encrypted\t{}", stats.encrypted)?;
+            writeln!(out, "burn_after_reading\t{}", stats.burn_after_reading)?;
+            writeln!(out, "pinned\t{}", stats.pinned)?;
+            writeln!(out, "accounts\t{}", stats.users)?;
+            writeln!(out, "sessions\t{}", stats.sessions)?;
+        }
+        Admin::CreateInvite(uses) => {
+            let code = accounts::new_invite();
+            db.create_invite(code.clone(), uses).await?;
+            db.audit(None, Action::CreateInvite, Some(code.clone()))
+                .await?;
+            writeln!(out, "{code}")?;
+        }
+        Admin::Invites => {
+            for invite in db.invites().await? {
+                writeln!(out, "{}\t{}\t{}", invite.code, invite.uses, invite.created)?;
+            }
+        }
+        Admin::RevokeInvite(code) => {
+            db.delete_invite(code.clone()).await?;
+            db.audit(None, Action::RevokeInvite, Some(code)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Open, write};
+    use crate::id::Id;
+
+    fn args(args: &str) -> Result<Command, Error> {
+        parse(args.split_whitespace().map(ToString::to_string))
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(args("").unwrap(), Command::Serve);
+        assert_eq!(args("help").unwrap(), Command::Help);
+        assert_eq!(args("admin").unwrap(), Command::Help);
+        assert_eq!(args("admin stats").unwrap(), Command::Admin(Admin::Stats));
+        assert_eq!(
+            args("admin list --match spam --older-than 90").unwrap(),
+            Command::Admin(Admin::List(Filter {
+                pattern: Some(String::from("spam")),
+                older_than: Some(90),
+            }))
+        );
+        assert_eq!(
+            args("admin delete bJZCna").unwrap(),
+            Command::Admin(Admin::Delete(
+                Filter::default(),
+                vec![String::from("bJZCna")]
+            ))
+        );
+
+        assert!(matches!(args("serve"), Err(Error::UnknownCommand(_))));
+        assert!(matches!(args("admin delete"), Err(Error::Unfiltered)));
+        assert!(matches!(
+            args("admin delete bJZCna --match spam"),
+            Err(Error::IdsWithFilter)
+        ));
+        assert!(matches!(
+            args("admin list --older-than"),
+            Err(Error::MissingValue(_))
+        ));
+        assert!(matches!(
+            args("admin list --older-than soon"),
+            Err(Error::OlderThan(_))
+        ));
+        assert!(matches!(
+            args("admin purge --all"),
+            Err(Error::UnknownOption(_))
+        ));
+        assert!(matches!(
+            args("admin stats bJZCna"),
+            Err(Error::UnexpectedArgument(_))
+        ));
+        assert_eq!(
+            args("admin invite create").unwrap(),
+            Command::Admin(Admin::CreateInvite(1))
+        );
+        assert_eq!(
+            args("admin invite create --uses 5").unwrap(),
+            Command::Admin(Admin::CreateInvite(5))
+        );
+        assert!(matches!(
+            args("admin invite create --uses 0"),
+            Err(Error::Uses(_))
+        ));
+        assert!(matches!(
+            args("admin invite revoke"),
+            Err(Error::MissingValue(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn delete_matching() -> Result<(), Box<dyn std::error::Error>> {
+        let db = Database::new(Open::Memory)?;
+
+        for (id, title) in [(1u32, "spam"), (2, "notes")] {
+            let entry = write::Entry {
+                text: String::from("text"),
+                title: Some(String::from(title)),
+                ..Default::default()
+            };
+            db.insert(Id::from(id), entry).await?;
+        }
+
+        let mut out = Vec::new();
+        run(&db, Admin::List(Filter::default()), &mut out).await?;
+        assert_eq!(String::from_utf8(out)?.lines().count(), 2);
+
+        let filter = Filter {
+            pattern: Some(String::from("spam")),
+            older_than: None,
+        };
+        let mut out = Vec::new();
+        run(&db, Admin::Delete(filter, Vec::new()), &mut out).await?;
+        assert_eq!(String::from_utf8(out)?, format!("{}\n", Id::from(1u32)));
+
+        assert!(db.get(Id::from(1u32), None, None).await.is_err());
+        assert!(db.get(Id::from(2u32), None, None).await.is_ok());
+
+        let log = db.audit_log(None).await?;
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].actor, None);
+        assert_eq!(log[0].target, Some(Id::from(1u32).to_string()));
+
+        let mut out = Vec::new();
+        run(&db, Admin::Stats, &mut out).await?;
+        assert!(String::from_utf8(out)?.starts_with("pastes\t1\n"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use axum::body::HttpBody as Body;
+use http::header::CONTENT_TYPE;
+use std::sync::Arc;
+use tower_http::compression::Predicate;
+use tower_http::compression::predicate::{NotForContentType, SizeAbove};
+
+/// Minimum response size in bytes worth compressing by default.
+pub(crate) const DEFAULT_MIN_SIZE: u16 = 32;
+
+/// Decides which responses are compressed with gzip, brotli or zstd as accepted by the client.
+#[derive(Clone, Debug)]
+pub(crate) struct Filter {
+    min_size: u16,
+    /// Prefixes of compressed content types, compressing everything but images if unset.
+    types: Option<Arc<[String]>>,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::new(DEFAULT_MIN_SIZE, None)
+    }
+}
+
+impl Filter {
+    pub fn new(min_size: u16, types: Option<Vec<String>>) -> Self {
+        Self {
+            min_size,
+            types: types.map(Into::into),
+        }
+    }
+
+    fn matches_type<B>(&self, response: &http::Response<B>) -> bool
+    where
+        B: Body,
+    {
+        let Some(types) = &self.types else {
+            return NotForContentType::GRPC
+                .and(NotForContentType::IMAGES)
+                .and(NotForContentType::SSE)
+                .should_compress(response);
+        };
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+
+        types.iter().any(|prefix| content_type.starts_with(prefix))
+    }
+}
+
+impl Predicate for Filter {
+    fn should_compress<B>(&self, response: &http::Response<B>) -> bool
+    where
+        B: Body,
+    {
+        SizeAbove::new(self.min_size).should_compress(response) && self.matches_type(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(content_type: &str, body: &str) -> http::Response<String> {
+        http::Response::builder()
+            .header(CONTENT_TYPE, content_type)
+            .body(body.to_string())
+            .unwrap()
+    }
+
+    #[test]
+    fn default_skips_images_and_small_responses() {
+        let filter = Filter::default();
+        let body = &"a".repeat(64);
+
+        assert!(filter.should_compress(&response("text/html; charset=utf-8", body)));
+        assert!(filter.should_compress(&response("image/svg+xml", body)));
+        assert!(!filter.should_compress(&response("image/png", body)));
+        assert!(!filter.should_compress(&response("text/html", "small")));
+    }
+
+    #[test]
+    fn types_and_min_size() {
+        let filter = Filter::new(128, Some(vec![String::from("text/html")]));
+        let body = &"a".repeat(256);
+
+        assert!(filter.should_compress(&response("text/html; charset=utf-8", body)));
+        assert!(!filter.should_compress(&response("text/plain", body)));
+        assert!(!filter.should_compress(&response("text/html", &"a".repeat(64))));
+    }
+}
@@ -2,22 +2,25 @@ use crate::env;
 use crate::errors::Error;
 use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
 use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use std::fmt;
+use std::str::FromStr;
 use std::sync::LazyLock;
 use tokio::task::spawn_blocking;
 
-static CONFIG: LazyLock<argon2::Config> = LazyLock::new(|| argon2::Config {
-    variant: argon2::Variant::Argon2i,
-    version: argon2::Version::Version13,
-    mem_cost: 65536,
-    time_cost: 10,
-    lanes: 4,
-    secret: &[],
-    ad: &[],
-    hash_length: 32,
-});
-
+/// Salt of pastes encrypted before salts were generated per paste.
 static SALT: LazyLock<String> = LazyLock::new(env::password_hash_salt);
 
+/// Parameters of pastes encrypted before they were stored per paste.
+const LEGACY_PARAMS: Params = Params {
+    memory: 65536,
+    iterations: 10,
+    parallelism: 4,
+};
+
+/// Length of generated salts in bytes.
+const SALT_LENGTH: usize = 16;
+
 /// Encrypted data item.
 pub(crate) struct Encrypted {
     /// Encrypted ciphertext.
@@ -32,6 +35,26 @@ pub(crate) struct Password(Vec<u8>);
 /// Plaintext bytes to be encrypted.
 pub(crate) struct Plaintext(Vec<u8>);
 
+/// Argon2 cost parameters for deriving keys from passwords.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Params {
+    /// Memory in KiB.
+    pub memory: u32,
+    /// Number of passes over the memory.
+    pub iterations: u32,
+    /// Number of lanes computed in parallel.
+    pub parallelism: u32,
+}
+
+/// Key derivation of a single paste, stored next to its ciphertext as
+/// `<variant>$m=<memory>,t=<iterations>,p=<parallelism>$<hex salt>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Kdf {
+    variant: argon2::Variant,
+    params: Params,
+    salt: Vec<u8>,
+}
+
 impl From<Vec<u8>> for Password {
     fn from(value: Vec<u8>) -> Self {
         Self(value)
@@ -44,17 +67,135 @@ impl From<Vec<u8>> for Plaintext {
     }
 }
 
-fn cipher_from(password: &[u8]) -> Result<XChaCha20Poly1305, Error> {
-    let key = argon2::hash_raw(password, SALT.as_bytes(), &CONFIG)?;
-    let key = Key::from_slice(&key);
-    Ok(XChaCha20Poly1305::new(key))
+impl Default for Params {
+    fn default() -> Self {
+        LEGACY_PARAMS
+    }
+}
+
+impl Params {
+    /// Check that argon2 accepts the parameters.
+    pub fn validate(self) -> Result<Self, String> {
+        if self.iterations == 0 {
+            return Err(String::from("at least one iteration required"));
+        }
+
+        if self.parallelism == 0 || self.parallelism > 0x00ff_ffff {
+            return Err(String::from("parallelism must be between 1 and 16777215"));
+        }
+
+        if self.memory < 8 * self.parallelism {
+            return Err(String::from("memory must be at least 8 KiB per lane"));
+        }
+
+        Ok(self)
+    }
+}
+
+impl Kdf {
+    /// Derivation with a fresh random salt and `params`.
+    pub fn generate(params: Params) -> Self {
+        let mut salt = vec![0; SALT_LENGTH];
+        rand::rng().fill_bytes(&mut salt);
+
+        Self {
+            variant: argon2::Variant::Argon2id,
+            params,
+            salt,
+        }
+    }
+
+    /// Derivation of pastes encrypted with the global `WASTEBIN_PASSWORD_SALT`.
+    pub fn legacy() -> Self {
+        Self {
+            variant: argon2::Variant::Argon2i,
+            params: LEGACY_PARAMS,
+            salt: SALT.as_bytes().to_vec(),
+        }
+    }
+
+    /// Return `true` if the derivation uses a per-paste salt and matches `params`.
+    pub fn is_current(&self, params: Params) -> bool {
+        self.variant == argon2::Variant::Argon2id && self.params == params
+    }
+
+    fn cipher(&self, password: &[u8]) -> Result<XChaCha20Poly1305, Error> {
+        let config = argon2::Config {
+            variant: self.variant,
+            version: argon2::Version::Version13,
+            mem_cost: self.params.memory,
+            time_cost: self.params.iterations,
+            lanes: self.params.parallelism,
+            secret: &[],
+            ad: &[],
+            hash_length: 32,
+        };
+
+        let key = argon2::hash_raw(password, &self.salt, &config)?;
+        let key = Key::from_slice(&key);
+        Ok(XChaCha20Poly1305::new(key))
+    }
+}
+
+impl fmt::Display for Kdf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}$m={},t={},p={}${}",
+            self.variant.as_lowercase_str(),
+            self.params.memory,
+            self.params.iterations,
+            self.params.parallelism,
+            hex::encode(&self.salt)
+        )
+    }
+}
+
+impl FromStr for Kdf {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::Argon2(argon2::Error::DecodingFail);
+        let mut parts = s.split('$');
+
+        let variant = parts
+            .next()
+            .and_then(|variant| argon2::Variant::from_str(variant).ok())
+            .ok_or_else(invalid)?;
+
+        let mut params = Params::default();
+
+        for param in parts.next().ok_or_else(invalid)?.split(',') {
+            let (name, value) = param.split_once('=').ok_or_else(invalid)?;
+            let value = value.parse().map_err(|_| invalid())?;
+
+            match name {
+                "m" => params.memory = value,
+                "t" => params.iterations = value,
+                "p" => params.parallelism = value,
+                _ => return Err(invalid()),
+            }
+        }
+
+        let salt = parts
+            .next()
+            .and_then(|salt| hex::decode(salt).ok())
+            .ok_or_else(invalid)?;
+
+        Ok(Self {
+            variant,
+            params,
+            salt,
+        })
+    }
 }
 
 impl Plaintext {
-    /// Consume and encrypt plaintext into [`Encrypted`] using `password`.
-    pub async fn encrypt(self, password: Password) -> Result<Encrypted, Error> {
+    /// Consume and encrypt plaintext into [`Encrypted`] using `password` and a key derived with
+    /// `kdf`.
+    pub async fn encrypt(self, password: Password, kdf: Kdf) -> Result<Encrypted, Error> {
         spawn_blocking(move || {
-            let cipher = cipher_from(&password.0)?;
+            let cipher = kdf.cipher(&password.0)?;
             let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
             let ciphertext = cipher
                 .encrypt(&nonce, self.0.as_ref())
@@ -72,10 +213,10 @@ impl Encrypted {
         Self { ciphertext, nonce }
     }
 
-    /// Decrypt into bytes using `password`.
-    pub async fn decrypt(self, password: Password) -> Result<Vec<u8>, Error> {
+    /// Decrypt into bytes using `password` and a key derived with `kdf`.
+    pub async fn decrypt(self, password: Password, kdf: Kdf) -> Result<Vec<u8>, Error> {
         spawn_blocking(move || {
-            let cipher = cipher_from(&password.0)?;
+            let cipher = kdf.cipher(&password.0)?;
             let nonce = XNonce::from_slice(&self.nonce);
             let plaintext = cipher
                 .decrypt(nonce, self.ciphertext.as_ref())
@@ -87,21 +228,58 @@ impl Encrypted {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
+    /// Cheap parameters to keep tests fast.
+    pub(crate) const PARAMS: Params = Params {
+        memory: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
     #[tokio::test]
     async fn roundtrip() {
         let password = "secret".to_string();
         let plaintext = "encrypt me".to_string();
-        let encrypted = Plaintext::from(plaintext.as_bytes().to_vec())
-            .encrypt(Password::from(password.as_bytes().to_vec()))
-            .await
-            .unwrap();
-        let decrypted = encrypted
-            .decrypt(Password::from(password.as_bytes().to_vec()))
-            .await
-            .unwrap();
-        assert_eq!(decrypted, plaintext.as_bytes());
+
+        for kdf in [Kdf::generate(PARAMS), Kdf::legacy()] {
+            let encrypted = Plaintext::from(plaintext.as_bytes().to_vec())
+                .encrypt(Password::from(password.as_bytes().to_vec()), kdf.clone())
+                .await
+                .unwrap();
+            let decrypted = encrypted
+                .decrypt(Password::from(password.as_bytes().to_vec()), kdf)
+                .await
+                .unwrap();
+            assert_eq!(decrypted, plaintext.as_bytes());
+        }
+    }
+
+    #[test]
+    fn kdf() {
+        let kdf = Kdf::generate(PARAMS);
+        let encoded = kdf.to_string();
+        assert!(encoded.starts_with("argon2id$m=64,t=1,p=1$"));
+        assert_eq!(encoded.parse::<Kdf>().unwrap(), kdf);
+        assert_ne!(Kdf::generate(PARAMS).salt, kdf.salt);
+
+        assert!(kdf.is_current(PARAMS));
+        assert!(!kdf.is_current(Params::default()));
+        assert!(!Kdf::legacy().is_current(LEGACY_PARAMS));
+
+        assert!("argon2id$m=64,x=1$00".parse::<Kdf>().is_err());
+        assert!("argon2id$m=64$zz".parse::<Kdf>().is_err());
+
+        assert!(PARAMS.validate().is_ok());
+        assert!(
+            Params {
+                memory: 8,
+                iterations: 1,
+                parallelism: 2
+            }
+            .validate()
+            .is_err()
+        );
     }
 }
@@ -0,0 +1,236 @@
+use crate::Page;
+use crate::accounts::SESSION_COOKIE;
+use crate::errors::Error;
+use crate::handlers::extract::Theme;
+use crate::handlers::html::make_error;
+use axum::body::{Body, Bytes};
+use axum::extract::{Request, State};
+use axum::http::{HeaderName, Method, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum_extra::extract::cookie::{Key, SignedCookieJar};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tokio_stream::StreamExt;
+
+/// Name of the hidden form field carrying the token.
+pub(crate) const FIELD: &str = "csrf";
+
+/// Header carrying the token for scripted requests.
+pub(crate) const HEADER: HeaderName = HeaderName::from_static("x-csrf-token");
+
+/// Route of the web share target.
+pub(crate) const SHARE: &str = "/share";
+
+/// Number of bytes at the start of a form body searched for the [`FIELD`]. Forms send it first,
+/// so the rest of the body is passed on without being buffered.
+const PREFIX: usize = 4096;
+
+/// State of the [`verify`] middleware.
+#[derive(Clone)]
+pub(crate) struct Verifier {
+    pub key: Key,
+    pub page: Page,
+}
+
+/// Value the token of a request is bound to: the session token of a logged in user or the uid
+/// cookie owning anonymous pastes. A request carrying neither has no authority to forge.
+fn binding(jar: &SignedCookieJar) -> Option<String> {
+    jar.get(SESSION_COOKIE)
+        .or_else(|| jar.get("uid"))
+        .map(|cookie| cookie.value_trimmed().to_string())
+}
+
+fn mac(key: &Key, binding: &str) -> Option<Hmac<Sha256>> {
+    // HMAC accepts keys of any length.
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.signing()).ok()?;
+    mac.update(FIELD.as_bytes());
+    mac.update(binding.as_bytes());
+    Some(mac)
+}
+
+/// Token forms must submit along with the cookies in `jar`, empty if there are no credentials.
+pub(crate) fn token(key: &Key, jar: &SignedCookieJar) -> String {
+    binding(jar)
+        .and_then(|binding| mac(key, &binding))
+        .map(|mac| hex::encode(mac.finalize().into_bytes()))
+        .unwrap_or_default()
+}
+
+/// Return `true` if `token` was issued for `binding`.
+fn is_valid(key: &Key, binding: &str, token: &str) -> bool {
+    let Ok(token) = hex::decode(token) else {
+        return false;
+    };
+
+    mac(key, binding).is_some_and(|mac| mac.verify_slice(&token).is_ok())
+}
+
+/// Read up to [`PREFIX`] bytes of `body`. Return them, whether they are the whole body and a body
+/// yielding everything again.
+async fn peek(body: Body) -> Result<(Bytes, bool, Body), axum::Error> {
+    let mut stream = body.into_data_stream();
+    let mut prefix = Vec::new();
+    let mut complete = false;
+
+    while prefix.len() < PREFIX {
+        let Some(chunk) = stream.next().await else {
+            complete = true;
+            break;
+        };
+
+        prefix.extend_from_slice(&chunk?);
+    }
+
+    let prefix = Bytes::from(prefix);
+    let body = Body::from_stream(tokio_stream::once(Ok(prefix.clone())).chain(stream));
+
+    Ok((prefix, complete, body))
+}
+
+/// Value of the [`FIELD`] in `prefix` of a form body of `content_type`, if it is contained in it.
+fn field(content_type: &str, prefix: &[u8], complete: bool) -> Option<String> {
+    if content_type.starts_with("multipart/form-data") {
+        let text = String::from_utf8_lossy(prefix);
+        let start = text.find(&format!("name=\"{FIELD}\""))?;
+        let (_, value) = text[start..].split_once("\r\n\r\n")?;
+        let (value, _) = value.split_once("\r\n")?;
+        return Some(value.to_string());
+    }
+
+    // The last pair may be cut off unless the whole body was read.
+    let end = if complete {
+        prefix.len()
+    } else {
+        prefix.iter().rposition(|byte| *byte == b'&').unwrap_or(0)
+    };
+
+    url::form_urlencoded::parse(&prefix[..end])
+        .find(|(key, _)| key == FIELD)
+        .map(|(_, value)| value.into_owned())
+}
+
+/// Reject POST requests that carry session or uid cookies but no valid token, either in the
+/// [`HEADER`] or the [`FIELD`] of a form. Browsers do not send JSON across sites without CORS
+/// approval, so JSON requests pass as they are. Content shared by other apps only fills the form,
+/// which is then submitted with its own token, so [`SHARE`] needs none. Only the first [`PREFIX`]
+/// bytes of a form are searched for the field.
+pub(crate) async fn verify(
+    State(verifier): State<Verifier>,
+    theme: Option<Theme>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let content_type = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    let is_json = content_type.starts_with("application/json");
+
+    if request.method() != Method::POST || is_json || request.uri().path() == SHARE {
+        return next.run(request).await;
+    }
+
+    let jar = SignedCookieJar::from_headers(request.headers(), verifier.key.clone());
+
+    let Some(binding) = binding(&jar) else {
+        return next.run(request).await;
+    };
+
+    let header = request
+        .headers()
+        .get(HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(ToString::to_string);
+
+    let (parts, body) = request.into_parts();
+
+    let (token, body) = if let Some(token) = header {
+        (Some(token), body)
+    } else {
+        let Ok((prefix, complete, body)) = peek(body).await else {
+            return StatusCode::BAD_REQUEST.into_response();
+        };
+
+        (field(&content_type, &prefix, complete), body)
+    };
+
+    if !token.is_some_and(|token| is_valid(&verifier.key, &binding, &token)) {
+        return make_error(Error::Csrf, verifier.page, theme).into_response();
+    }
+
+    next.run(Request::from_parts(parts, body)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum_extra::extract::cookie::Cookie;
+
+    #[test]
+    fn tokens() {
+        let key = Key::generate();
+        let jar = SignedCookieJar::new(key.clone());
+        assert_eq!(token(&key, &jar), "");
+
+        let alice = jar.clone().add(Cookie::new("uid", "1"));
+        let bob = jar.add(Cookie::new("uid", "2"));
+        let token = token(&key, &alice);
+
+        assert!(is_valid(&key, "1", &token));
+        assert!(!is_valid(&key, "2", &token));
+        assert!(!is_valid(&Key::generate(), "1", &token));
+        assert!(!is_valid(&key, "1", "not hex"));
+        assert_ne!(super::token(&key, &bob), token);
+
+        let session = alice.add(Cookie::new(SESSION_COOKIE, "secret"));
+        assert_eq!(binding(&session).as_deref(), Some("secret"));
+    }
+
+    #[test]
+    fn fields() {
+        let form = "application/x-www-form-urlencoded";
+        assert_eq!(
+            field(form, b"csrf=abc&text=1", true).as_deref(),
+            Some("abc")
+        );
+        assert_eq!(
+            field(form, b"text=1&csrf=abc", true).as_deref(),
+            Some("abc")
+        );
+        assert_eq!(field(form, b"text=1&csrf=ab", false), None);
+        assert_eq!(field(form, b"csrf=abc&te", false).as_deref(), Some("abc"));
+
+        let multipart = "multipart/form-data; boundary=x";
+        let body = b"--x\r\nContent-Disposition: form-data; name=\"csrf\"\r\n\r\nabc\r\n--x\r\n";
+        assert_eq!(field(multipart, body, false).as_deref(), Some("abc"));
+        assert_eq!(field(multipart, &body[..body.len() - 8], false), None);
+    }
+
+    #[tokio::test]
+    async fn peeks() {
+        let text = "a".repeat(3 * PREFIX);
+        let chunks = text
+            .as_bytes()
+            .chunks(1000)
+            .map(|chunk| Ok::<_, axum::Error>(Bytes::copy_from_slice(chunk)))
+            .collect::<Vec<_>>();
+
+        let (prefix, complete, body) = peek(Body::from_stream(tokio_stream::iter(chunks)))
+            .await
+            .unwrap();
+
+        assert!(!complete);
+        assert!(prefix.len() >= PREFIX && prefix.len() < PREFIX + 1000);
+
+        let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        assert_eq!(bytes, text.as_bytes());
+
+        let (prefix, complete, _) = peek(Body::from("csrf=abc")).await.unwrap();
+        assert!(complete);
+        assert_eq!(prefix, "csrf=abc");
+    }
+}
@@ -1,13 +1,30 @@
-use crate::crypto::Password;
+use crate::accounts::{PENDING_MINUTES, Roles, SESSION_DAYS, Scope};
+use crate::capability;
+use crate::crypto::{Params, Password};
+use crate::deadline::Deadline;
 use crate::errors::Error;
 use crate::id::Id;
-use parking_lot::Mutex;
-use rusqlite::{Connection, Transaction, params};
+use crate::metrics;
+use bytes::Bytes;
+use cached::{Cached, TimedSizedCache};
+use parking_lot::{Mutex, MutexGuard};
+use rusqlite::{Connection, DatabaseName, ErrorCode, OptionalExtension, Transaction, params};
 use rusqlite_migration::{HookError, M, Migrations};
-use std::io::Cursor;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::{Cursor, Read};
+use std::num::NonZeroUsize;
+use std::ops::Deref;
 use std::path::PathBuf;
+use std::pin::Pin;
 use std::sync::{Arc, LazyLock};
-use tokio::task::spawn_blocking;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::task::{JoinHandle, spawn_blocking};
+use tokio_stream::Stream;
+
+/// Size in bytes of the decompressed chunks pastes are streamed in.
+const CHUNK_SIZE: usize = 64 * 1024;
 
 static MIGRATIONS: LazyLock<Migrations> = LazyLock::new(|| {
     Migrations::new(vec![
@@ -44,13 +61,245 @@ static MIGRATIONS: LazyLock<Migrations> = LazyLock::new(|| {
         M::up(include_str!("migrations/0005-drop-text-column.sql")),
         M::up(include_str!("migrations/0006-add-nonce-column.sql")),
         M::up(include_str!("migrations/0007-add-title-column.sql")),
+        M::up(include_str!("migrations/0008-add-extension-column.sql")),
+        M::up(include_str!("migrations/0009-add-accounts.sql")),
+        M::up(include_str!("migrations/0010-add-identities.sql")),
+        M::up(include_str!("migrations/0011-add-roles.sql")),
+        M::up(include_str!("migrations/0012-add-pinned-column.sql")),
+        M::up(include_str!("migrations/0013-add-invites.sql")),
+        M::up(include_str!("migrations/0014-add-api-tokens.sql")),
+        M::up(include_str!("migrations/0015-add-totp.sql")),
+        M::up(include_str!("migrations/0016-add-audit-log.sql")),
+        M::up(include_str!("migrations/0017-add-quarantine-column.sql")),
+        M::up(include_str!("migrations/0018-add-kdf-column.sql")),
+        M::up(include_str!("migrations/0019-add-ciphertext-column.sql")),
+        M::up(include_str!("migrations/0020-add-secret-column.sql")),
+        M::up(include_str!("migrations/0021-add-views-columns.sql")),
+        M::up(include_str!("migrations/0022-add-lookup-indexes.sql")),
+        M::up(include_str!("migrations/0023-add-noindex-column.sql")),
+        M::up(include_str!("migrations/0024-add-filename-column.sql")),
     ])
 });
 
+/// Number of prepared statements kept per connection, enough for all queries on hot paths.
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+
+/// Interval at which views counted in memory are written.
+pub(crate) const VIEWS_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Maximum number of expired pastes deleted at once.
+const PURGE_BATCH: usize = 512;
+
+/// Condition of pastes anyone can read by their identifier alone, which may be listed publicly
+/// if opted into.
+const PUBLIC: &str = "secret IS NULL AND nonce IS NULL AND NOT coalesce(ciphertext, 0) \
+    AND NOT coalesce(burn_after_reading, 0) AND NOT coalesce(noindex, 0) AND quarantine IS NULL \
+    AND (expires IS NULL OR expires > datetime('now') OR pinned)";
+
+/// Virtual machine instructions between checks whether the deadline of a request has passed.
+const PROGRESS_STEPS: i32 = 1000;
+
+/// Connection locked for a request, whose statements are interrupted once its deadline passes.
+struct Interruptible<'a>(MutexGuard<'a, Connection>);
+
+impl Deref for Interruptible<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.0
+    }
+}
+
+impl Drop for Interruptible<'_> {
+    fn drop(&mut self) {
+        self.0.progress_handler(0, None::<fn() -> bool>);
+    }
+}
+
+/// Error of statements interrupted because the request deadline passed.
+fn interrupted() -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_INTERRUPT),
+        None,
+    )
+}
+
+/// Lock `conn` to run statements until `deadline`, also giving up if waiting for the lock takes
+/// that long.
+fn lock_until(
+    conn: &Mutex<Connection>,
+    deadline: Option<Deadline>,
+) -> Result<Interruptible<'_>, rusqlite::Error> {
+    let Some(deadline) = deadline else {
+        return Ok(Interruptible(conn.lock()));
+    };
+
+    let conn = conn
+        .try_lock_until(deadline.instant())
+        .ok_or_else(interrupted)?;
+    conn.progress_handler(PROGRESS_STEPS, Some(move || deadline.passed()));
+
+    Ok(Interruptible(conn))
+}
+
+/// Schema version as maintained by the migrations.
+fn user_version(conn: &Connection) -> Result<usize, rusqlite::Error> {
+    conn.query_row(
+        "SELECT user_version FROM pragma_user_version()",
+        [],
+        |row| row.get(0),
+    )
+}
+
 /// Our main database and integrated cache.
 #[derive(Clone)]
 pub(crate) struct Database {
     conn: Arc<Mutex<Connection>>,
+    /// Parameters to derive keys of newly encrypted pastes with.
+    argon2: Params,
+    /// Counters of created and removed pastes.
+    counters: metrics::Pastes,
+    /// Schema version after applying all known migrations.
+    latest: usize,
+    /// Identifiers recently looked up in vain.
+    missing: Missing,
+    /// Views by paste counted since they were last written.
+    views: Arc<Mutex<HashMap<i64, u64>>>,
+}
+
+/// Identifiers recently looked up in vain, if remembered at all. Lookups remember and inserts
+/// forget identifiers while holding the connection lock, so that a lookup which ran before an
+/// insert cannot remember its identifier after the insert forgot it.
+#[derive(Clone, Default)]
+struct Missing(Option<Arc<Mutex<TimedSizedCache<i64, ()>>>>);
+
+impl Missing {
+    /// Return `true` if `id` was recently not found.
+    fn contains(&self, id: Id) -> bool {
+        self.0
+            .as_ref()
+            .is_some_and(|missing| missing.lock().cache_get(&id.to_i64()).is_some())
+    }
+
+    /// Remember `id` if looking it up failed with `err` because it does not exist.
+    fn remember(&self, id: Id, err: &rusqlite::Error) {
+        if let (Some(missing), rusqlite::Error::QueryReturnedNoRows) = (&self.0, err) {
+            missing.lock().cache_set(id.to_i64(), ());
+        }
+    }
+
+    /// Forget `id` after it was inserted.
+    fn forget(&self, id: Id) {
+        if let Some(missing) = &self.0 {
+            missing.lock().cache_remove(&id.to_i64());
+        }
+    }
+}
+
+/// Reads the stored data of a paste piece by piece, locking the connection only for each read so
+/// that a slow client does not hold up other requests.
+struct BlobReader {
+    conn: Arc<Mutex<Connection>>,
+    id: Id,
+    offset: usize,
+}
+
+impl Read for BlobReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let conn = self.conn.lock();
+        let blob = conn
+            .blob_open(
+                DatabaseName::Main,
+                "entries",
+                "data",
+                self.id.to_i64(),
+                true,
+            )
+            .map_err(std::io::Error::other)?;
+        let read = blob
+            .read_at(buf, self.offset)
+            .map_err(std::io::Error::other)?;
+
+        self.offset += read;
+
+        Ok(read)
+    }
+}
+
+type BlobDecoder = zstd::Decoder<'static, std::io::BufReader<BlobReader>>;
+
+/// Stream of the decompressed chunks of a paste. Each chunk is read on the blocking pool only when
+/// it is polled, so a slow client does not hold on to a blocking thread while it is not reading.
+pub(crate) struct Chunks {
+    decoder: Option<BlobDecoder>,
+    pending: Option<JoinHandle<(BlobDecoder, std::io::Result<Option<Bytes>>)>>,
+}
+
+impl Chunks {
+    fn new(reader: BlobReader) -> std::io::Result<Self> {
+        Ok(Self {
+            decoder: Some(zstd::Decoder::new(reader)?),
+            pending: None,
+        })
+    }
+}
+
+impl std::fmt::Debug for Chunks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Chunks")
+            .field("pending", &self.pending.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Read the next chunk from `decoder`, `None` at the end of the content.
+fn read_chunk(mut decoder: BlobDecoder) -> (BlobDecoder, std::io::Result<Option<Bytes>>) {
+    let mut chunk = vec![0; CHUNK_SIZE];
+
+    let result = match decoder.read(&mut chunk) {
+        Ok(0) => Ok(None),
+        Ok(read) => {
+            chunk.truncate(read);
+            Ok(Some(Bytes::from(chunk)))
+        }
+        Err(err) => Err(err),
+    };
+
+    (decoder, result)
+}
+
+impl Stream for Chunks {
+    type Item = std::io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        let mut pending = if let Some(pending) = this.pending.take() {
+            pending
+        } else {
+            let Some(decoder) = this.decoder.take() else {
+                return Poll::Ready(None);
+            };
+
+            spawn_blocking(move || read_chunk(decoder))
+        };
+
+        let Poll::Ready(result) = Pin::new(&mut pending).poll(cx) else {
+            this.pending = Some(pending);
+            return Poll::Pending;
+        };
+
+        // The stream ends after an error, as the decoder is not returned.
+        Poll::Ready(match result {
+            Ok((decoder, Ok(Some(chunk)))) => {
+                this.decoder = Some(decoder);
+                Some(Ok(chunk))
+            }
+            Ok((_, Ok(None))) => None,
+            Ok((_, Err(err))) => Some(Err(err)),
+            Err(err) => Some(Err(std::io::Error::other(err))),
+        })
+    }
 }
 
 /// Database opening modes
@@ -64,7 +313,7 @@ pub(crate) enum Open {
 
 /// Module with types for insertion.
 pub(crate) mod write {
-    use crate::crypto::{Encrypted, Password, Plaintext};
+    use crate::crypto::{Encrypted, Kdf, Params, Password, Plaintext};
     use crate::errors::Error;
     use async_compression::tokio::bufread::ZstdEncoder;
     use serde::{Deserialize, Serialize};
@@ -89,6 +338,20 @@ pub(crate) mod write {
         pub password: Option<String>,
         /// Title
         pub title: Option<String>,
+        /// File name offered for downloads
+        pub filename: Option<String>,
+        /// Blocklist rule holding the entry back until an admin releases it
+        #[serde(skip)]
+        pub quarantine: Option<String>,
+        /// Text was encrypted by the browser with a key the server never sees
+        #[serde(default)]
+        pub ciphertext: bool,
+        /// Capability token required in URLs of secret pastes
+        #[serde(skip)]
+        pub secret: Option<String>,
+        /// Ask search engines not to index the entry
+        #[serde(default)]
+        pub noindex: bool,
     }
 
     /// A compressed entry to be inserted.
@@ -107,6 +370,57 @@ pub(crate) mod write {
         pub data: Vec<u8>,
         /// Nonce for this entry
         pub nonce: Option<Vec<u8>>,
+        /// Encoded key derivation for this entry
+        pub kdf: Option<String>,
+    }
+
+    /// Action recorded in the audit log.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(crate) enum Action {
+        /// A paste was deleted by an admin, target is the paste identifier.
+        Delete,
+        /// A paste was pinned, target is the paste identifier.
+        Pin,
+        /// A paste was unpinned, target is the paste identifier.
+        Unpin,
+        /// Expired pastes were purged, target is their number.
+        Purge,
+        /// An invite was created, target is the invite code.
+        CreateInvite,
+        /// An invite was revoked, target is the invite code.
+        RevokeInvite,
+        /// An API token was created, target is its label.
+        CreateToken,
+        /// An API token was revoked, target is its label.
+        RevokeToken,
+        /// Two-factor authentication was enabled.
+        EnableTotp,
+        /// Two-factor authentication was disabled.
+        DisableTotp,
+        /// A quarantined paste was released, target is the paste identifier.
+        Release,
+        /// The log filter was changed, target are the new directives.
+        LogFilter,
+    }
+
+    impl Action {
+        /// Name as stored in the database.
+        pub fn as_str(self) -> &'static str {
+            match self {
+                Action::Delete => "delete",
+                Action::Pin => "pin",
+                Action::Unpin => "unpin",
+                Action::Purge => "purge",
+                Action::CreateInvite => "create_invite",
+                Action::RevokeInvite => "revoke_invite",
+                Action::CreateToken => "create_token",
+                Action::RevokeToken => "revoke_token",
+                Action::EnableTotp => "enable_totp",
+                Action::DisableTotp => "disable_totp",
+                Action::Release => "release",
+                Action::LogFilter => "log_filter",
+            }
+        }
     }
 
     impl Entry {
@@ -126,21 +440,24 @@ pub(crate) mod write {
     }
 
     impl CompressedEntry {
-        /// Encrypt if password is set.
-        pub async fn encrypt(self) -> Result<DatabaseEntry, Error> {
-            let (data, nonce) = if let Some(password) = &self.entry.password {
+        /// Encrypt if password is set, deriving the key with a fresh salt and `params`.
+        pub async fn encrypt(self, params: Params) -> Result<DatabaseEntry, Error> {
+            let (data, nonce, kdf) = if let Some(password) = &self.entry.password {
                 let password = Password::from(password.as_bytes().to_vec());
                 let plaintext = Plaintext::from(self.data);
-                let Encrypted { ciphertext, nonce } = plaintext.encrypt(password).await?;
-                (ciphertext, Some(nonce))
+                let kdf = Kdf::generate(params);
+                let encoded = kdf.to_string();
+                let Encrypted { ciphertext, nonce } = plaintext.encrypt(password, kdf).await?;
+                (ciphertext, Some(nonce), Some(encoded))
             } else {
-                (self.data, None)
+                (self.data, None, None)
             };
 
             Ok(DatabaseEntry {
                 entry: self.entry,
                 data,
                 nonce,
+                kdf,
             })
         }
     }
@@ -148,9 +465,12 @@ pub(crate) mod write {
 
 /// Module with types for reading from the database.
 pub(crate) mod read {
-    use crate::crypto::{Encrypted, Password};
+    use crate::accounts::Scope;
+    use crate::crypto::{Encrypted, Kdf, Params, Password, Plaintext};
     use crate::errors::Error;
+    use crate::id::Id;
     use async_compression::tokio::bufread::ZstdDecoder;
+    use serde::Serialize;
     use std::io::Cursor;
     use tokio::io::{AsyncReadExt, BufReader};
 
@@ -167,8 +487,14 @@ pub(crate) mod read {
         pub uid: Option<i64>,
         /// Nonce for this entry
         pub nonce: Option<Vec<u8>>,
+        /// Encoded key derivation for this entry, unset for pastes encrypted with the global salt
+        pub kdf: Option<String>,
         /// Title
         pub title: Option<String>,
+        /// File extension
+        pub extension: Option<String>,
+        /// Encrypted by the browser
+        pub ciphertext: bool,
     }
 
     /// Potentially decrypted but still compressed entry
@@ -182,6 +508,10 @@ pub(crate) mod read {
         uid: Option<i64>,
         /// Title
         title: Option<String>,
+        /// File extension
+        extension: Option<String>,
+        /// Encrypted by the browser
+        ciphertext: bool,
     }
 
     /// Uncompressed entry
@@ -195,17 +525,239 @@ pub(crate) mod read {
         pub uid: Option<i64>,
         /// Title
         pub title: Option<String>,
+        /// File extension
+        pub extension: Option<String>,
+        /// Encrypted by the browser
+        pub ciphertext: bool,
     }
 
     /// Uncompressed, decrypted data read from the database.
     #[derive(Debug)]
     pub(crate) struct Data {
-        /// Content
+        /// Content, opaque ciphertext if encrypted by the browser
         pub text: String,
         /// User identifier that inserted the entry
         pub uid: Option<i64>,
         /// Title
         pub title: Option<String>,
+        /// File extension
+        pub extension: Option<String>,
+        /// Encrypted by the browser
+        pub ciphertext: bool,
+        /// Search engines are asked not to index this
+        pub noindex: bool,
+        /// Unix time of expiration, `None` if the entry never expires or is pinned
+        pub expires: Option<i64>,
+    }
+
+    /// Summary of a paste shown in the list of a user's pastes.
+    #[derive(Debug)]
+    #[expect(clippy::struct_excessive_bools)]
+    pub(crate) struct Listing {
+        /// Identifier
+        pub id: Id,
+        /// Title
+        pub title: Option<String>,
+        /// File extension
+        pub extension: Option<String>,
+        /// Creation time, unknown for pastes created before accounts existed
+        pub created: Option<String>,
+        /// Expiration time
+        pub expires: Option<String>,
+        /// Delete if read
+        pub burn_after_reading: bool,
+        /// Encrypted with a password
+        pub encrypted: bool,
+        /// Size of the stored data in bytes
+        pub size: usize,
+        /// Exempt from expiration
+        pub pinned: bool,
+        /// Expired but not purged yet
+        pub expired: bool,
+        /// Capability token of secret pastes
+        pub secret: Option<String>,
+    }
+
+    impl Listing {
+        /// Identifier followed by the capability token of secret pastes for linking the paste.
+        pub fn path(&self) -> String {
+            match &self.secret {
+                Some(token) => format!("{}_{token}", self.id),
+                None => self.id.to_string(),
+            }
+        }
+
+        /// Title of the paste or else its identifier, naming it in listings.
+        pub fn name(&self) -> String {
+            self.title.clone().unwrap_or_else(|| self.id.to_string())
+        }
+
+        /// Read a listing from the `id`, `title`, `extension`, `created`, `expires`,
+        /// `burn_after_reading`, `encrypted`, `size`, `pinned` and `expired` columns of `row`
+        /// and the `secret` column by name.
+        pub(super) fn from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Self> {
+            Ok(Self {
+                id: Id::from_i64(row.get(0)?),
+                title: row.get(1)?,
+                extension: row.get(2)?,
+                created: row.get(3)?,
+                expires: row.get(4)?,
+                burn_after_reading: row.get::<_, Option<bool>>(5)?.unwrap_or(false),
+                encrypted: row.get(6)?,
+                size: row.get(7)?,
+                pinned: row.get(8)?,
+                expired: row.get(9)?,
+                secret: row.get("secret")?,
+            })
+        }
+    }
+
+    /// Number of characters of a quarantined paste shown for review.
+    pub(crate) const PREVIEW_CHARS: usize = 500;
+
+    /// Paste held back by a blocklist rule until an admin releases it.
+    #[derive(Debug)]
+    pub(crate) struct Quarantined {
+        pub listing: Listing,
+        /// Name of the owning account
+        pub owner: Option<String>,
+        /// Rule the paste matched
+        pub rule: String,
+        /// Beginning of the content, unless encrypted
+        pub preview: Option<String>,
+    }
+
+    /// Frequently viewed paste worth rendering before its next view.
+    #[derive(Debug)]
+    pub(crate) struct Popular {
+        pub id: Id,
+        pub extension: Option<String>,
+        /// Capability token of secret pastes
+        pub secret: Option<String>,
+    }
+
+    /// Invite code allowing to register while registration is closed.
+    #[derive(Debug)]
+    pub(crate) struct Invite {
+        /// Code to enter when registering
+        pub code: String,
+        /// Number of registrations left
+        pub uses: u32,
+        /// Creation time
+        pub created: String,
+    }
+
+    /// Personal API token of an account, without the secret itself.
+    #[derive(Debug)]
+    pub(crate) struct ApiToken {
+        /// Identifier to revoke the token
+        pub id: i64,
+        /// Name given by the owner
+        pub label: String,
+        /// What the token may be used for
+        pub scope: Scope,
+        /// Creation time
+        pub created: String,
+        /// Time the token was last used to authenticate, if ever
+        pub last_used: Option<String>,
+    }
+
+    /// Entry of the audit log.
+    #[derive(Debug, Serialize)]
+    pub(crate) struct AuditEntry {
+        /// Sequence number
+        pub id: i64,
+        /// Time of the action
+        pub created: String,
+        /// Name of the account that acted or `None` for the command line
+        pub actor: Option<String>,
+        /// What was done
+        pub action: String,
+        /// What it was done to, if anything
+        pub target: Option<String>,
+    }
+
+    /// Pastes stored by an account that count towards its quota.
+    #[derive(Debug, Default, Clone, Copy, Serialize)]
+    pub(crate) struct Usage {
+        /// Number of unexpired pastes
+        pub pastes: usize,
+        /// Size of all unexpired pastes as stored in bytes
+        pub bytes: usize,
+    }
+
+    /// Instance statistics.
+    #[derive(Debug, Default, Serialize)]
+    pub(crate) struct Stats {
+        /// Number of stored pastes
+        pub pastes: usize,
+        /// Size of all stored pastes in bytes
+        pub bytes: usize,
+        /// Number of expired pastes not purged yet
+        pub expired: usize,
+        /// Number of encrypted pastes
+        pub encrypted: usize,
+        /// Number of pastes deleted after reading
+        pub burn_after_reading: usize,
+        /// Number of pinned pastes
+        pub pinned: usize,
+        /// Number of accounts
+        pub users: usize,
+        /// Number of active sessions
+        pub sessions: usize,
+    }
+
+    /// Number and size of the pastes sharing a property.
+    #[derive(Debug, Serialize)]
+    pub(crate) struct Share {
+        /// Property like the extension or the time left until expiration
+        pub label: String,
+        /// Number of pastes
+        pub pastes: usize,
+        /// Size of the pastes as stored in bytes
+        pub bytes: usize,
+    }
+
+    /// Pastes created in a month by extension.
+    #[derive(Debug, Serialize)]
+    pub(crate) struct Month {
+        /// Year and month like `2024-05`
+        pub month: String,
+        /// Pastes by extension, most common first
+        pub languages: Vec<Share>,
+    }
+
+    /// Breakdown of all stored pastes.
+    #[derive(Debug, Default, Serialize)]
+    pub(crate) struct Distribution {
+        /// Pastes by time left until expiration, soonest first
+        pub expirations: Vec<Share>,
+        /// Pastes by extension, most common first
+        pub languages: Vec<Share>,
+    }
+
+    /// Content of a paste, either read as a whole or decompressed chunk by chunk.
+    #[derive(Debug)]
+    pub(crate) enum Content {
+        /// Entire content.
+        Whole(String),
+        /// Chunks of the content as they are decompressed.
+        Chunks(super::Chunks),
+    }
+
+    /// Paste whose content is streamed rather than read into memory.
+    #[derive(Debug)]
+    pub(crate) struct Streamed {
+        /// Content, opaque ciphertext if encrypted by the browser
+        pub content: Content,
+        /// Title
+        pub title: Option<String>,
+        /// File name given on creation
+        pub filename: Option<String>,
+        /// File extension
+        pub extension: Option<String>,
+        /// Deleted after this read
+        pub burned: bool,
     }
 
     /// Potentially deleted or non-existent expired entry.
@@ -220,6 +772,17 @@ pub(crate) mod read {
     }
 
     impl DatabaseEntry {
+        /// Return `true` if the entry is encrypted with a key derivation other than one with a
+        /// per-paste salt and `params`.
+        pub fn needs_rehash(&self, params: Params) -> bool {
+            self.nonce.is_some()
+                && self
+                    .kdf
+                    .as_deref()
+                    .and_then(|kdf| kdf.parse::<Kdf>().ok())
+                    .is_none_or(|kdf| !kdf.is_current(params))
+        }
+
         pub async fn decrypt(
             self,
             password: Option<Password>,
@@ -231,15 +794,23 @@ pub(crate) mod read {
                     must_be_deleted: self.must_be_deleted,
                     uid: self.uid,
                     title: self.title,
+                    extension: self.extension,
+                    ciphertext: self.ciphertext,
                 }),
                 (Some(nonce), Some(password)) => {
+                    let kdf = match &self.kdf {
+                        Some(kdf) => kdf.parse()?,
+                        None => Kdf::legacy(),
+                    };
                     let encrypted = Encrypted::new(self.data, nonce);
-                    let decrypted = encrypted.decrypt(password).await?;
+                    let decrypted = encrypted.decrypt(password, kdf).await?;
                     Ok(CompressedReadEntry {
                         data: decrypted,
                         must_be_deleted: self.must_be_deleted,
                         uid: self.uid,
                         title: self.title,
+                        extension: self.extension,
+                        ciphertext: self.ciphertext,
                     })
                 }
             }
@@ -247,6 +818,22 @@ pub(crate) mod read {
     }
 
     impl CompressedReadEntry {
+        /// Encrypt the compressed data again with `password` and a fresh key derivation with
+        /// `params`, returning the ciphertext, nonce and encoded derivation.
+        pub async fn reencrypt(
+            &self,
+            password: Password,
+            params: Params,
+        ) -> Result<(Vec<u8>, Vec<u8>, String), Error> {
+            let kdf = Kdf::generate(params);
+            let encoded = kdf.to_string();
+            let Encrypted { ciphertext, nonce } = Plaintext::from(self.data.clone())
+                .encrypt(password, kdf)
+                .await?;
+
+            Ok((ciphertext, nonce, encoded))
+        }
+
         pub async fn decompress(self) -> Result<UmcompressedEntry, Error> {
             let reader = BufReader::new(Cursor::new(self.data));
             let mut decoder = ZstdDecoder::new(reader);
@@ -262,6 +849,8 @@ pub(crate) mod read {
                 uid: self.uid,
                 must_be_deleted: self.must_be_deleted,
                 title: self.title,
+                extension: self.extension,
+                ciphertext: self.ciphertext,
             })
         }
     }
@@ -278,101 +867,403 @@ impl Database {
         };
 
         MIGRATIONS.to_latest(&mut conn)?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
 
+        let latest = user_version(&conn)?;
         let conn = Arc::new(Mutex::new(conn));
 
-        Ok(Self { conn })
+        Ok(Self {
+            conn,
+            argon2: Params::default(),
+            counters: metrics::Pastes::default(),
+            latest,
+            missing: Missing::default(),
+            views: Arc::default(),
+        })
+    }
+
+    /// Derive keys of newly encrypted pastes with `params`. Pastes encrypted differently are
+    /// encrypted again when they are read with the right password.
+    #[must_use]
+    pub fn with_argon2(mut self, params: Params) -> Self {
+        self.argon2 = params;
+        self
+    }
+
+    /// Remember up to `size` identifiers that were not found for `ttl`, so that scans over guessed
+    /// identifiers do not cost a query each.
+    #[must_use]
+    pub fn with_negative_cache(mut self, size: NonZeroUsize, ttl: Duration) -> Self {
+        let cache = TimedSizedCache::with_size_and_lifespan(size.get(), ttl.as_secs());
+        self.missing = Missing(Some(Arc::new(Mutex::new(cache))));
+        self
+    }
+
+    /// Schema version of the database, which also checks that it is reachable.
+    pub async fn schema_version(&self) -> Result<usize, Error> {
+        let conn = self.conn.clone();
+
+        Ok(spawn_blocking(move || user_version(&conn.lock())).await??)
+    }
+
+    /// Schema version this binary migrates databases to.
+    pub fn latest_schema_version(&self) -> usize {
+        self.latest
+    }
+
+    /// Counters of created and removed pastes since startup.
+    pub fn counters(&self) -> &metrics::Pastes {
+        &self.counters
     }
 
     /// Insert `entry` under `id` into the database and optionally set owner to `uid`.
+    #[tracing::instrument(level = "debug", skip_all, fields(%id))]
     pub async fn insert(&self, id: Id, entry: write::Entry) -> Result<(), Error> {
         let conn = self.conn.clone();
-        let write::DatabaseEntry { entry, data, nonce } = entry.compress().await?.encrypt().await?;
+        let write::DatabaseEntry {
+            entry,
+            data,
+            nonce,
+            kdf,
+        } = entry.compress().await?.encrypt(self.argon2).await?;
+        let missing = self.missing.clone();
 
-        spawn_blocking(move || match entry.expires {
-            None => conn.lock().execute(
-                "INSERT INTO entries (id, uid, data, burn_after_reading, nonce, title) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-                params![id.to_i64(), entry.uid, data, entry.burn_after_reading, nonce, entry.title],
-            ),
-            Some(expires) => conn.lock().execute(
-                "INSERT INTO entries (id, uid, data, burn_after_reading, nonce, expires, title) VALUES (?1, ?2, ?3, ?4, ?5, datetime('now', ?6), ?7)",
-                params![
+        spawn_blocking(move || {
+            let conn = conn.lock();
+
+            let inserted = match entry.expires {
+                None => conn.prepare_cached(
+                    "INSERT INTO entries (id, uid, data, burn_after_reading, nonce, kdf, title, extension, quarantine, ciphertext, secret, noindex, filename, created) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, datetime('now'))",
+                )?
+                .execute(params![id.to_i64(), entry.uid, data, entry.burn_after_reading, nonce, kdf, entry.title, entry.extension, entry.quarantine, entry.ciphertext, entry.secret, entry.noindex, entry.filename]),
+                Some(expires) => conn.prepare_cached(
+                    "INSERT INTO entries (id, uid, data, burn_after_reading, nonce, kdf, expires, title, extension, quarantine, ciphertext, secret, noindex, filename, created) VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now', ?7), ?8, ?9, ?10, ?11, ?12, ?13, ?14, datetime('now'))",
+                )?
+                .execute(params![
                     id.to_i64(),
                     entry.uid,
                     data,
                     entry.burn_after_reading,
                     nonce,
+                    kdf,
                     format!("{expires} seconds"),
                     entry.title,
-                ],
-            ),
+                    entry.extension,
+                    entry.quarantine,
+                    entry.ciphertext,
+                    entry.secret,
+                    entry.noindex,
+                    entry.filename,
+                ]),
+            };
+
+            missing.forget(id);
+            inserted
         })
         .await??;
 
+        self.counters.created.inc();
+
         Ok(())
     }
 
-    /// Get entire entry for `id`.
-    pub async fn get(&self, id: Id, password: Option<Password>) -> Result<read::Entry, Error> {
-        let conn = self.conn.clone();
+    /// Get entire entry for `id`. Secret pastes are only found if `token` matches their
+    /// capability token, before they might be burned.
+    #[tracing::instrument(level = "debug", skip_all, fields(%id))]
+    pub async fn get(
+        &self,
+        id: Id,
+        token: Option<&str>,
+        password: Option<Password>,
+    ) -> Result<read::Entry, Error> {
+        if self.missing.contains(id) {
+            return Err(Error::NotFound);
+        }
 
-        let entry = spawn_blocking(move || {
-            conn.lock().query_row(
-                "SELECT data, burn_after_reading, uid, nonce, expires < datetime('now'), title FROM entries WHERE id=?1",
+        let conn = self.conn.clone();
+        let deadline = Deadline::current();
+        let missing = self.missing.clone();
+
+        let (entry, quarantined, secret, noindex, expires) = spawn_blocking(move || {
+            let conn = lock_until(&conn, deadline)?;
+            conn.prepare_cached(
+                "SELECT data, burn_after_reading, uid, nonce, (expires < datetime('now') AND NOT pinned), title, extension, quarantine IS NOT NULL, kdf, ciphertext, secret, coalesce(noindex, 0), CASE WHEN pinned THEN NULL ELSE CAST(strftime('%s', expires) AS INTEGER) END FROM entries WHERE id=?1",
+            )?.query_row(
                 params![id.to_i64()],
                 |row| {
-                    Ok(read::DatabaseEntry {
+                    let entry = read::DatabaseEntry {
                         data: row.get(0)?,
                         must_be_deleted: row.get::<_, Option<bool>>(1)?.unwrap_or(false),
                         uid: row.get(2)?,
                         nonce: row.get(3)?,
+                        kdf: row.get(8)?,
                         expired: row.get::<_, Option<bool>>(4)?.unwrap_or(false),
                         title: row.get::<_, Option<String>>(5)?,
-                    })
+                        extension: row.get::<_, Option<String>>(6)?,
+                        ciphertext: row.get::<_, Option<bool>>(9)?.unwrap_or(false),
+                    };
+
+                    Ok((
+                        entry,
+                        row.get::<_, bool>(7)?,
+                        row.get::<_, Option<String>>(10)?,
+                        row.get::<_, bool>(11)?,
+                        row.get::<_, Option<i64>>(12)?,
+                    ))
                 },
             )
+                .inspect_err(|err| missing.remember(id, err))
         })
         .await??;
 
+        if !capability::secret_matches(secret.as_deref(), token) {
+            return Err(Error::NotFound);
+        }
+
         if entry.expired {
-            self.delete(id).await?;
+            self.remove(id).await?;
+            self.counters.expired.inc();
             return Ok(read::Entry::Expired);
         }
 
-        let entry = entry.decrypt(password).await?.decompress().await?;
+        if quarantined {
+            return Err(Error::Quarantined);
+        }
+
+        // Burned entries are gone after this read anyway.
+        let needs_rehash = entry.needs_rehash(self.argon2) && !entry.must_be_deleted;
+        let entry = entry.decrypt(password.clone()).await?;
+
+        // The paste is decrypted already, so failing to encrypt it again must not fail the read.
+        if let Some(password) = password.filter(|_| needs_rehash) {
+            if let Err(err) = self.rehash(id, &entry, password).await {
+                tracing::warn!(%id, "failed to encrypt paste again: {err}");
+            }
+        }
+
+        let entry = entry.decompress().await?;
 
         let data = read::Data {
             text: entry.text,
             title: entry.title,
             uid: entry.uid,
+            extension: entry.extension,
+            ciphertext: entry.ciphertext,
+            noindex,
+            expires,
         };
 
         if entry.must_be_deleted {
-            self.delete(id).await?;
+            self.remove(id).await?;
+            self.counters.burned.inc();
             return Ok(read::Entry::Burned(data));
         }
 
         Ok(read::Entry::Regular(data))
     }
 
-    /// Get title of a paste.
-    pub async fn get_title(&self, id: Id) -> Result<Option<String>, Error> {
-        let conn = self.conn.clone();
+    /// Get the content of `id` in chunks instead of reading it into memory as a whole. Encrypted
+    /// and burned pastes have to be read completely anyway and are returned in one piece. Expired
+    /// pastes are not found.
+    #[tracing::instrument(level = "debug", skip_all, fields(%id))]
+    pub async fn stream(
+        &self,
+        id: Id,
+        token: Option<&str>,
+        password: Option<Password>,
+    ) -> Result<read::Streamed, Error> {
+        if self.missing.contains(id) {
+            return Err(Error::NotFound);
+        }
 
-        let title = spawn_blocking(move || {
-            conn.lock().query_row(
-                "SELECT title FROM entries WHERE id=?1",
+        let conn = self.conn.clone();
+        let deadline = Deadline::current();
+        let missing = self.missing.clone();
+
+        let (buffered, quarantined, secret, title, extension, filename) = spawn_blocking(move || {
+            let conn = lock_until(&conn, deadline)?;
+            conn.prepare_cached(
+                "SELECT nonce IS NOT NULL OR burn_after_reading OR (expires < datetime('now') AND NOT pinned), quarantine IS NOT NULL, secret, title, extension, filename FROM entries INDEXED BY entries_lookup WHERE id=?1",
+            )?.query_row(
                 params![id.to_i64()],
-                |row| row.get(0),
+                |row| {
+                    Ok((
+                        row.get::<_, Option<bool>>(0)?.unwrap_or(false),
+                        row.get::<_, bool>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                        row.get::<_, Option<String>>(5)?,
+                    ))
+                },
+            )
+                .inspect_err(|err| missing.remember(id, err))
+        })
+        .await??;
+
+        if buffered {
+            let (data, burned) = match self.get(id, token, password).await? {
+                read::Entry::Regular(data) => (data, false),
+                read::Entry::Burned(data) => (data, true),
+                read::Entry::Expired => return Err(Error::NotFound),
+            };
+
+            return Ok(read::Streamed {
+                content: read::Content::Whole(data.text),
+                title: data.title,
+                filename,
+                extension: data.extension,
+                burned,
+            });
+        }
+
+        if !capability::secret_matches(secret.as_deref(), token) {
+            return Err(Error::NotFound);
+        }
+
+        if quarantined {
+            return Err(Error::Quarantined);
+        }
+
+        let chunks = Chunks::new(BlobReader {
+            conn: self.conn.clone(),
+            id,
+            offset: 0,
+        })
+        .map_err(|e| Error::Compression(e.to_string()))?;
+
+        Ok(read::Streamed {
+            content: read::Content::Chunks(chunks),
+            title,
+            filename,
+            extension,
+            burned: false,
+        })
+    }
+
+    /// Encrypt `entry` with `id` again with a per-paste salt and the configured parameters.
+    async fn rehash(
+        &self,
+        id: Id,
+        entry: &read::CompressedReadEntry,
+        password: Password,
+    ) -> Result<(), Error> {
+        let conn = self.conn.clone();
+        let (data, nonce, kdf) = entry.reencrypt(password, self.argon2).await?;
+
+        spawn_blocking(move || {
+            conn.lock().execute(
+                "UPDATE entries SET data=?1, nonce=?2, kdf=?3 WHERE id=?4",
+                params![data, nonce, kdf, id.to_i64()],
             )
         })
         .await??;
 
+        tracing::debug!(?id, "encrypted paste again with per-paste salt");
+
+        Ok(())
+    }
+
+    /// Get title of a paste, which requires `token` for secret pastes.
+    #[tracing::instrument(level = "debug", skip_all, fields(%id))]
+    pub async fn get_title(&self, id: Id, token: Option<&str>) -> Result<Option<String>, Error> {
+        if self.missing.contains(id) {
+            return Err(Error::NotFound);
+        }
+
+        let conn = self.conn.clone();
+        let deadline = Deadline::current();
+        let missing = self.missing.clone();
+
+        let (title, secret) = spawn_blocking(move || {
+            let conn = lock_until(&conn, deadline)?;
+            conn.prepare_cached(
+                "SELECT title, secret FROM entries INDEXED BY entries_lookup WHERE id=?1",
+            )?
+            .query_row(params![id.to_i64()], |row| {
+                Ok((row.get(0)?, row.get::<_, Option<String>>(1)?))
+            })
+            .inspect_err(|err| missing.remember(id, err))
+        })
+        .await??;
+
+        if !capability::secret_matches(secret.as_deref(), token) {
+            return Err(Error::NotFound);
+        }
+
         Ok(title)
     }
 
+    /// If the paste `id` was encrypted by the browser, which requires `token` for secret pastes.
+    /// Unlike [`Self::get`], this never burns the paste.
+    #[tracing::instrument(level = "debug", skip_all, fields(%id))]
+    pub async fn is_ciphertext(&self, id: Id, token: Option<&str>) -> Result<bool, Error> {
+        if self.missing.contains(id) {
+            return Err(Error::NotFound);
+        }
+
+        let conn = self.conn.clone();
+        let deadline = Deadline::current();
+        let missing = self.missing.clone();
+
+        let (ciphertext, secret) = spawn_blocking(move || {
+            let conn = lock_until(&conn, deadline)?;
+            conn.prepare_cached("SELECT coalesce(ciphertext, 0), secret FROM entries WHERE id=?1")?
+                .query_row(params![id.to_i64()], |row| {
+                    Ok((row.get(0)?, row.get::<_, Option<String>>(1)?))
+                })
+                .inspect_err(|err| missing.remember(id, err))
+        })
+        .await??;
+
+        if !capability::secret_matches(secret.as_deref(), token) {
+            return Err(Error::NotFound);
+        }
+
+        Ok(ciphertext)
+    }
+
+    /// If the paste `id` is burned after reading, which requires `token` for secret pastes.
+    /// Unlike [`Self::get`], this never burns the paste.
+    #[tracing::instrument(level = "debug", skip_all, fields(%id))]
+    pub async fn is_burn_after_reading(&self, id: Id, token: Option<&str>) -> Result<bool, Error> {
+        if self.missing.contains(id) {
+            return Err(Error::NotFound);
+        }
+
+        let conn = self.conn.clone();
+        let deadline = Deadline::current();
+        let missing = self.missing.clone();
+
+        let (burn_after_reading, secret) = spawn_blocking(move || {
+            let conn = lock_until(&conn, deadline)?;
+            conn.prepare_cached(
+                "SELECT coalesce(burn_after_reading, 0), secret FROM entries WHERE id=?1",
+            )?
+            .query_row(params![id.to_i64()], |row| {
+                Ok((row.get(0)?, row.get::<_, Option<String>>(1)?))
+            })
+            .inspect_err(|err| missing.remember(id, err))
+        })
+        .await??;
+
+        if !capability::secret_matches(secret.as_deref(), token) {
+            return Err(Error::NotFound);
+        }
+
+        Ok(burn_after_reading)
+    }
+
     /// Delete paste with `id`.
-    async fn delete(&self, id: Id) -> Result<(), Error> {
+    #[tracing::instrument(level = "debug", skip_all, fields(%id))]
+    pub async fn delete(&self, id: Id) -> Result<(), Error> {
+        self.remove(id).await?;
+        self.counters.deleted.inc();
+
+        Ok(())
+    }
+
+    async fn remove(&self, id: Id) -> Result<(), Error> {
         let conn = self.conn.clone();
 
         spawn_blocking(move || {
@@ -385,6 +1276,7 @@ impl Database {
     }
 
     /// Delete paste with `id` for user `uid`.
+    #[tracing::instrument(level = "debug", skip_all, fields(%id))]
     pub async fn delete_for(&self, id: Id, uid: i64) -> Result<(), Error> {
         let conn = self.conn.clone();
 
@@ -409,38 +1301,1360 @@ impl Database {
         })
         .await??;
 
-        exists.map(|_| ()).ok_or(Error::Delete)
+        exists.ok_or(Error::Delete)?;
+        self.counters.deleted.inc();
+
+        Ok(())
     }
 
-    /// Retrieve next monotonically increasing uid.
-    pub async fn next_uid(&self) -> Result<i64, Error> {
+    /// Set extension of paste with `id` to `ext` for user `uid`, or for anyone if `None`.
+    pub async fn set_extension_for(
+        &self,
+        id: Id,
+        uid: Option<i64>,
+        ext: String,
+    ) -> Result<(), Error> {
         let conn = self.conn.clone();
 
-        let uid = spawn_blocking(move || {
-            conn.lock().query_row(
-                "UPDATE uids SET n = n + 1 WHERE id = 0 RETURNING n",
-                [],
-                |row| row.get(0),
+        let updated = spawn_blocking(move || {
+            conn.lock().execute(
+                "UPDATE entries SET extension=?1 WHERE (id=?2 AND (?3 IS NULL OR uid=?3))",
+                params![ext, id.to_i64(), uid],
             )
         })
         .await??;
 
-        Ok(uid)
-    }
-}
+        if updated == 0 {
+            return Err(Error::Edit);
+        }
 
-#[cfg(test)]
-mod tests {
-    use std::num::NonZero;
+        Ok(())
+    }
 
-    use super::*;
+    /// Get unencrypted paste with `id` of user `uid`, or of anyone if `None`, for editing without
+    /// burning it.
+    #[tracing::instrument(level = "debug", skip_all, fields(%id))]
+    pub async fn get_for(&self, id: Id, uid: Option<i64>) -> Result<read::Data, Error> {
+        let conn = self.conn.clone();
 
-    impl read::Entry {
+        let entry = spawn_blocking(move || {
+            conn.lock()
+                .query_row(
+                    "SELECT data, title, extension, uid FROM entries WHERE (id=?1 AND (?2 IS NULL OR uid=?2) AND nonce IS NULL AND NOT coalesce(ciphertext, 0) AND quarantine IS NULL AND (expires IS NULL OR expires > datetime('now') OR pinned))",
+                    params![id.to_i64(), uid],
+                    |row| {
+                        Ok(read::DatabaseEntry {
+                            data: row.get(0)?,
+                            expired: false,
+                            must_be_deleted: false,
+                            uid: row.get(3)?,
+                            nonce: None,
+                            kdf: None,
+                            title: row.get(1)?,
+                            extension: row.get(2)?,
+                            ciphertext: false,
+                        })
+                    },
+                )
+                .optional()
+        })
+        .await??
+        .ok_or(Error::Edit)?;
+
+        let entry = entry.decrypt(None).await?.decompress().await?;
+
+        Ok(read::Data {
+            text: entry.text,
+            uid: entry.uid,
+            title: entry.title,
+            extension: entry.extension,
+            ciphertext: false,
+            noindex: false,
+            expires: None,
+        })
+    }
+
+    /// Replace text and title of unencrypted paste with `id` for user `uid`, or for anyone if
+    /// `None`. The quarantine reason and a shorter expiration of `entry` are applied as well.
+    #[tracing::instrument(level = "debug", skip_all, fields(%id))]
+    pub async fn update_for(
+        &self,
+        id: Id,
+        uid: Option<i64>,
+        entry: write::Entry,
+    ) -> Result<(), Error> {
+        let conn = self.conn.clone();
+        let write::DatabaseEntry { entry, data, .. } =
+            entry.compress().await?.encrypt(self.argon2).await?;
+        let expires = entry.expires.map(|expires| format!("{expires} seconds"));
+
+        let updated = spawn_blocking(move || {
+            conn.lock().execute(
+                "UPDATE entries SET data=?1, title=?2, quarantine=?3, expires=CASE WHEN ?4 IS NULL THEN expires ELSE min(coalesce(expires, datetime('now', ?4)), datetime('now', ?4)) END WHERE (id=?5 AND (?6 IS NULL OR uid=?6) AND nonce IS NULL AND NOT coalesce(ciphertext, 0))",
+                params![data, entry.title, entry.quarantine, expires, id.to_i64(), uid],
+            )
+        })
+        .await??;
+
+        if updated == 0 {
+            return Err(Error::Edit);
+        }
+
+        Ok(())
+    }
+
+    /// List all pastes of user `uid` that have not expired yet, most recent first.
+    pub async fn list_for(&self, uid: i64) -> Result<Vec<read::Listing>, Error> {
+        let conn = self.conn.clone();
+
+        let listings = spawn_blocking(move || {
+            let conn = conn.lock();
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, title, extension, created, expires, burn_after_reading, nonce IS NOT NULL, length(data), pinned, 0, secret FROM entries WHERE (uid=?1 AND (expires IS NULL OR expires > datetime('now') OR pinned)) ORDER BY created DESC",
+            )?;
+
+            stmt.query_map(params![uid], read::Listing::from_row)?
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .await??;
+
+        Ok(listings)
+    }
+
+    /// Number of pastes anyone can read by their identifier alone.
+    pub async fn public_count(&self) -> Result<usize, Error> {
+        let conn = self.conn.clone();
+
+        let count = spawn_blocking(move || {
+            conn.lock()
+                .prepare_cached(&format!("SELECT COUNT(*) FROM entries WHERE {PUBLIC}"))?
+                .query_row([], |row| row.get(0))
+        })
+        .await??;
+
+        Ok(count)
+    }
+
+    /// List up to `limit` pastes anyone can read by their identifier alone after skipping
+    /// `offset` of them, oldest first so that earlier pages stay the same while new pastes are
+    /// added. Secret, burn after reading, password protected, encrypted, noindex, quarantined and
+    /// expired pastes are skipped.
+    pub async fn public(&self, offset: usize, limit: usize) -> Result<Vec<read::Listing>, Error> {
+        let conn = self.conn.clone();
+
+        let listings = spawn_blocking(move || {
+            let conn = conn.lock();
+            let mut stmt = conn.prepare_cached(&format!(
+                "SELECT id, title, extension, created, expires, burn_after_reading, nonce IS NOT NULL, length(data), pinned, 0, secret FROM entries \
+                 WHERE {PUBLIC} ORDER BY created, id LIMIT ?1 OFFSET ?2",
+            ))?;
+
+            stmt.query_map(params![limit, offset], read::Listing::from_row)?
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .await??;
+
+        Ok(listings)
+    }
+
+    /// Number and size of unexpired pastes of user `uid`.
+    pub async fn usage(&self, uid: i64) -> Result<read::Usage, Error> {
+        let conn = self.conn.clone();
+
+        let usage = spawn_blocking(move || {
+            conn.lock().query_row(
+                "SELECT count(*), coalesce(sum(length(data)), 0) FROM entries WHERE (uid=?1 AND (expires IS NULL OR expires > datetime('now') OR pinned))",
+                params![uid],
+                |row| {
+                    Ok(read::Usage {
+                        pastes: row.get(0)?,
+                        bytes: row.get(1)?,
+                    })
+                },
+            )
+        })
+        .await??;
+
+        Ok(usage)
+    }
+
+    /// Number and stored size of unexpired pastes of the account owning paste `id` without that
+    /// paste, or `None` if it is not owned by an account.
+    pub async fn usage_besides(&self, id: Id) -> Result<Option<read::Usage>, Error> {
+        let conn = self.conn.clone();
+
+        let usage = spawn_blocking(move || {
+            let conn = conn.lock();
+            let Some(uid) = conn
+                .query_row(
+                    "SELECT users.id FROM entries JOIN users ON users.id=entries.uid WHERE entries.id=?1",
+                    params![id.to_i64()],
+                    |row| row.get::<_, i64>(0),
+                )
+                .optional()?
+            else {
+                return Ok(None);
+            };
+
+            conn.query_row(
+                "SELECT count(*), coalesce(sum(length(data)), 0) FROM entries WHERE (uid=?1 AND id<>?2 AND (expires IS NULL OR expires > datetime('now') OR pinned))",
+                params![uid, id.to_i64()],
+                |row| {
+                    Ok(Some(read::Usage {
+                        pastes: row.get(0)?,
+                        bytes: row.get(1)?,
+                    }))
+                },
+            )
+        })
+        .await??;
+
+        Ok(usage)
+    }
+
+    /// List up to `limit` pastes of all users starting at `offset` together with the owning
+    /// account's name, most recent first. If given, `query` matches the identifier, extension or
+    /// owner name exactly or the title partially and `older_than` restricts the list to pastes
+    /// created more than that many days ago, which excludes pastes of unknown age.
+    pub async fn list_all(
+        &self,
+        query: Option<String>,
+        older_than: Option<u32>,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<Vec<(read::Listing, Option<String>)>, Error> {
+        let conn = self.conn.clone();
+        let id = query
+            .as_deref()
+            .and_then(|query| query.parse::<Id>().ok())
+            .map(Id::to_i64);
+
+        let listings = spawn_blocking(move || {
+            let conn = conn.lock();
+            let mut stmt = conn.prepare_cached(
+                "SELECT entries.id, title, extension, entries.created, expires, burn_after_reading, nonce IS NOT NULL, length(data), pinned, (coalesce(expires < datetime('now'), 0) AND NOT pinned), users.name, secret \
+                 FROM entries LEFT JOIN users ON users.id=entries.uid \
+                 WHERE (?1 IS NULL OR entries.id=?2 OR extension=?1 OR users.name=?1 OR instr(lower(title), lower(?1)) > 0) \
+                 AND (?5 IS NULL OR entries.created < datetime('now', printf('-%d days', ?5))) \
+                 ORDER BY entries.created DESC LIMIT coalesce(?3, -1) OFFSET ?4",
+            )?;
+
+            stmt.query_map(params![query, id, limit, offset, older_than], |row| {
+                Ok((read::Listing::from_row(row)?, row.get(10)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .await??;
+
+        Ok(listings)
+    }
+
+    /// Exempt paste with `id` from expiration if `pinned` is set.
+    pub async fn set_pinned(&self, id: Id, pinned: bool) -> Result<(), Error> {
+        let conn = self.conn.clone();
+
+        let updated = spawn_blocking(move || {
+            conn.lock().execute(
+                "UPDATE entries SET pinned=?1 WHERE id=?2",
+                params![pinned, id.to_i64()],
+            )
+        })
+        .await??;
+
+        if updated == 0 {
+            return Err(Error::NotFound);
+        }
+
+        Ok(())
+    }
+
+    /// List pastes held back by a blocklist rule, oldest first, with their owner, the rule and
+    /// the beginning of their content unless encrypted.
+    pub async fn quarantined(&self) -> Result<Vec<read::Quarantined>, Error> {
+        let conn = self.conn.clone();
+
+        let rows = spawn_blocking(move || {
+            let conn = conn.lock();
+            let mut stmt = conn.prepare_cached(
+                "SELECT entries.id, title, extension, entries.created, expires, burn_after_reading, nonce IS NOT NULL, length(data), pinned, (coalesce(expires < datetime('now'), 0) AND NOT pinned), users.name, quarantine, data, nonce, secret \
+                 FROM entries LEFT JOIN users ON users.id=entries.uid \
+                 WHERE quarantine IS NOT NULL ORDER BY entries.created",
+            )?;
+
+            stmt.query_map([], |row| {
+                let entry = read::DatabaseEntry {
+                    data: row.get(12)?,
+                    expired: false,
+                    must_be_deleted: false,
+                    uid: None,
+                    nonce: row.get(13)?,
+                    kdf: None,
+                    title: None,
+                    extension: None,
+                    ciphertext: false,
+                };
+
+                Ok((read::Listing::from_row(row)?, row.get(10)?, row.get(11)?, entry))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .await??;
+
+        let mut quarantined = Vec::with_capacity(rows.len());
+
+        for (listing, owner, rule, entry) in rows {
+            let preview = match entry.decrypt(None).await {
+                Ok(entry) => {
+                    let text = entry.decompress().await?.text;
+                    Some(text.chars().take(read::PREVIEW_CHARS).collect())
+                }
+                Err(_) => None,
+            };
+
+            quarantined.push(read::Quarantined {
+                listing,
+                owner,
+                rule,
+                preview,
+            });
+        }
+
+        Ok(quarantined)
+    }
+
+    /// Count a view of the paste with `id` in memory. Counts are written by [`Self::flush_views`],
+    /// so that views do not compete with reads for the connection.
+    pub fn record_view(&self, id: Id) {
+        *self.views.lock().entry(id.to_i64()).or_default() += 1;
+    }
+
+    /// Add the views counted in memory since the last call to the stored counts.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn flush_views(&self) -> Result<(), Error> {
+        let views = std::mem::take(&mut *self.views.lock());
+
+        if views.is_empty() {
+            return Ok(());
+        }
+
+        let conn = self.conn.clone();
+
+        spawn_blocking(move || {
+            let mut conn = conn.lock();
+            let tx = conn.transaction()?;
+
+            {
+                let mut stmt = tx.prepare_cached(
+                    "UPDATE entries SET views=views + ?1, viewed=datetime('now') WHERE id=?2",
+                )?;
+
+                for (id, count) in views {
+                    stmt.execute(params![count, id])?;
+                }
+            }
+
+            tx.commit()
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// Write the views counted in memory every `period` until the process exits.
+    pub async fn flush_views_every(self, period: Duration) {
+        let mut interval = tokio::time::interval(period);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(err) = self.flush_views().await {
+                tracing::warn!("failed to write view counts: {err}");
+            }
+        }
+    }
+
+    /// List up to `limit` pastes that can be cached, most viewed and then most recently viewed
+    /// first. Burn after reading, encrypted, quarantined and expired pastes are skipped.
+    pub async fn popular(&self, limit: usize) -> Result<Vec<read::Popular>, Error> {
+        self.flush_views().await?;
+
+        let conn = self.conn.clone();
+
+        let pastes = spawn_blocking(move || {
+            let conn = conn.lock();
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, extension, secret FROM entries \
+                 WHERE views > 0 AND nonce IS NULL AND NOT coalesce(ciphertext, 0) \
+                 AND NOT coalesce(burn_after_reading, 0) AND quarantine IS NULL \
+                 AND (expires IS NULL OR expires > datetime('now') OR pinned) \
+                 ORDER BY views DESC, viewed DESC LIMIT ?1",
+            )?;
+
+            stmt.query_map(params![limit], |row| {
+                Ok(read::Popular {
+                    id: Id::from_i64(row.get(0)?),
+                    extension: row.get(1)?,
+                    secret: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .await??;
+
+        Ok(pastes)
+    }
+
+    /// List up to `limit` pastes anyone can read by their identifier alone, most recent first.
+    /// Secret, burn after reading, password protected, encrypted, noindex, quarantined and
+    /// expired pastes are skipped.
+    #[cfg_attr(
+        not(feature = "gopher"),
+        expect(dead_code, reason = "only used with the `gopher` feature")
+    )]
+    pub async fn recent(&self, limit: usize) -> Result<Vec<read::Listing>, Error> {
+        let conn = self.conn.clone();
+
+        let listings = spawn_blocking(move || {
+            let conn = conn.lock();
+            let mut stmt = conn.prepare_cached(&format!(
+                "SELECT id, title, extension, created, expires, burn_after_reading, nonce IS NOT NULL, length(data), pinned, 0, secret FROM entries \
+                 WHERE {PUBLIC} ORDER BY created DESC LIMIT ?1",
+            ))?;
+
+            stmt.query_map(params![limit], read::Listing::from_row)?
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .await??;
+
+        Ok(listings)
+    }
+
+    /// Release the quarantined paste with `id`.
+    pub async fn release(&self, id: Id) -> Result<(), Error> {
+        let conn = self.conn.clone();
+
+        let updated = spawn_blocking(move || {
+            conn.lock().execute(
+                "UPDATE entries SET quarantine=NULL WHERE id=?1 AND quarantine IS NOT NULL",
+                params![id.to_i64()],
+            )
+        })
+        .await??;
+
+        if updated == 0 {
+            return Err(Error::NotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Delete all expired pastes that are not pinned and return their identifiers. Pastes are
+    /// deleted in batches of [`PURGE_BATCH`], so that other requests get the connection in between.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn purge(&self) -> Result<Vec<Id>, Error> {
+        // Views of pastes about to be deleted are written in vain otherwise.
+        self.flush_views().await?;
+
+        let mut ids = Vec::new();
+
+        loop {
+            let conn = self.conn.clone();
+
+            let batch = spawn_blocking(move || {
+                let conn = conn.lock();
+                let mut stmt = conn.prepare_cached(
+                    "DELETE FROM entries WHERE id IN (SELECT id FROM entries WHERE expires < datetime('now') AND NOT pinned LIMIT ?1) RETURNING id",
+                )?;
+
+                stmt.query_map(params![PURGE_BATCH], |row| Ok(Id::from_i64(row.get(0)?)))?
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .await??;
+
+            let done = batch.len() < PURGE_BATCH;
+            self.counters.expired.add(batch.len() as u64);
+            ids.extend(batch);
+
+            if done {
+                break;
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// Gather instance statistics.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn stats(&self) -> Result<read::Stats, Error> {
+        let conn = self.conn.clone();
+
+        let stats = spawn_blocking(move || {
+            conn.lock().query_row(
+                "SELECT count(*), coalesce(sum(length(data)), 0), \
+                 count(*) FILTER (WHERE expires < datetime('now') AND NOT pinned), \
+                 count(nonce), count(*) FILTER (WHERE burn_after_reading), count(*) FILTER (WHERE pinned), \
+                 (SELECT count(*) FROM users), \
+                 (SELECT count(*) FROM sessions WHERE expires > datetime('now')) \
+                 FROM entries",
+                [],
+                |row| {
+                    Ok(read::Stats {
+                        pastes: row.get(0)?,
+                        bytes: row.get(1)?,
+                        expired: row.get(2)?,
+                        encrypted: row.get(3)?,
+                        burn_after_reading: row.get(4)?,
+                        pinned: row.get(5)?,
+                        users: row.get(6)?,
+                        sessions: row.get(7)?,
+                    })
+                },
+            )
+        })
+        .await??;
+
+        Ok(stats)
+    }
+
+    /// Break all stored pastes down by the time left until they expire and by their extension.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn distribution(&self) -> Result<read::Distribution, Error> {
+        const EXPIRATIONS: [&str; 7] = [
+            "expired",
+            "within an hour",
+            "within a day",
+            "within a week",
+            "within a month",
+            "later",
+            "never",
+        ];
+
+        let conn = self.conn.clone();
+
+        let distribution = spawn_blocking(move || {
+            let conn = conn.lock();
+            let mut stmt = conn.prepare_cached(
+                "SELECT CASE \
+                   WHEN expires IS NULL OR pinned THEN 6 \
+                   WHEN expires < datetime('now') THEN 0 \
+                   WHEN expires < datetime('now', '+1 hour') THEN 1 \
+                   WHEN expires < datetime('now', '+1 day') THEN 2 \
+                   WHEN expires < datetime('now', '+7 days') THEN 3 \
+                   WHEN expires < datetime('now', '+1 month') THEN 4 \
+                   ELSE 5 END AS bucket, \
+                 count(*), coalesce(sum(length(data)), 0) \
+                 FROM entries GROUP BY bucket ORDER BY bucket",
+            )?;
+
+            let expirations = stmt
+                .query_map([], |row| {
+                    Ok(read::Share {
+                        label: EXPIRATIONS[row.get::<_, usize>(0)?].to_string(),
+                        pastes: row.get(1)?,
+                        bytes: row.get(2)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut stmt = conn.prepare_cached(
+                "SELECT coalesce(extension, ''), count(*), coalesce(sum(length(data)), 0) \
+                 FROM entries GROUP BY 1 ORDER BY 2 DESC, 1",
+            )?;
+
+            let languages = stmt
+                .query_map([], |row| {
+                    Ok(read::Share {
+                        label: row.get(0)?,
+                        pastes: row.get(1)?,
+                        bytes: row.get(2)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok::<_, rusqlite::Error>(read::Distribution {
+                expirations,
+                languages,
+            })
+        })
+        .await??;
+
+        Ok(distribution)
+    }
+
+    /// Break the stored pastes of known creation time down by month and extension, most recent
+    /// month first. Pastes encrypted by the browser have no known language and quarantined ones
+    /// are not public, so both are left out.
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn language_history(&self) -> Result<Vec<read::Month>, Error> {
+        let conn = self.conn.clone();
+
+        let history = spawn_blocking(move || {
+            let conn = conn.lock();
+            let mut stmt = conn.prepare_cached(
+                "SELECT strftime('%Y-%m', created), coalesce(extension, ''), count(*), \
+                 coalesce(sum(length(data)), 0) \
+                 FROM entries \
+                 WHERE created IS NOT NULL AND NOT coalesce(ciphertext, 0) AND quarantine IS NULL \
+                 GROUP BY 1, 2 ORDER BY 1 DESC, 3 DESC, 2",
+            )?;
+
+            let mut history: Vec<read::Month> = Vec::new();
+            let mut rows = stmt.query([])?;
+
+            while let Some(row) = rows.next()? {
+                let month: String = row.get(0)?;
+                let share = read::Share {
+                    label: row.get(1)?,
+                    pastes: row.get(2)?,
+                    bytes: row.get(3)?,
+                };
+
+                match history.last_mut() {
+                    Some(last) if last.month == month => last.languages.push(share),
+                    _ => history.push(read::Month {
+                        month,
+                        languages: vec![share],
+                    }),
+                }
+            }
+
+            Ok::<_, rusqlite::Error>(history)
+        })
+        .await??;
+
+        Ok(history)
+    }
+
+    /// Size of the database file in bytes.
+    pub async fn size(&self) -> Result<u64, Error> {
+        let conn = self.conn.clone();
+
+        let size = spawn_blocking(move || {
+            conn.lock().query_row(
+                "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+                [],
+                |row| row.get(0),
+            )
+        })
+        .await??;
+
+        Ok(size)
+    }
+
+    /// Transfer ownership of all pastes of user `from` to user `to`.
+    pub async fn transfer(&self, from: i64, to: i64) -> Result<(), Error> {
+        let conn = self.conn.clone();
+
+        spawn_blocking(move || {
+            conn.lock()
+                .execute("UPDATE entries SET uid=?1 WHERE uid=?2", params![to, from])
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// Create account `name` for `uid` with the encoded password `hash`.
+    pub async fn create_user(&self, uid: i64, name: String, hash: String) -> Result<(), Error> {
+        let conn = self.conn.clone();
+
+        let result = spawn_blocking(move || {
+            conn.lock().execute(
+                "INSERT INTO users (id, name, password, created) VALUES (?1, ?2, ?3, datetime('now'))",
+                params![uid, name, hash],
+            )
+        })
+        .await?;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(rusqlite::Error::SqliteFailure(err, _))
+                if err.code == ErrorCode::ConstraintViolation =>
+            {
+                Err(Error::UserExists)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Create account `uid` like [`Self::create_user`] if `invite` has uses left, which is used up
+    /// by one.
+    pub async fn create_invited_user(
+        &self,
+        uid: i64,
+        name: String,
+        hash: String,
+        invite: String,
+    ) -> Result<(), Error> {
+        let conn = self.conn.clone();
+
+        spawn_blocking(move || {
+            let mut conn = conn.lock();
+            let tx = conn.transaction()?;
+
+            let redeemed = tx.execute(
+                "UPDATE invites SET uses=uses-1 WHERE code=?1 AND uses > 0",
+                params![invite],
+            )?;
+
+            if redeemed == 0 {
+                return Err(Error::Invite);
+            }
+
+            let result = tx.execute(
+                "INSERT INTO users (id, name, password, created) VALUES (?1, ?2, ?3, datetime('now'))",
+                params![uid, name, hash],
+            );
+
+            match result {
+                Ok(_) => Ok(tx.commit()?),
+                Err(rusqlite::Error::SqliteFailure(err, _))
+                    if err.code == ErrorCode::ConstraintViolation =>
+                {
+                    Err(Error::UserExists)
+                }
+                Err(err) => Err(err.into()),
+            }
+        })
+        .await?
+    }
+
+    /// Create invite `code` for `uses` registrations.
+    pub async fn create_invite(&self, code: String, uses: u32) -> Result<(), Error> {
+        let conn = self.conn.clone();
+
+        spawn_blocking(move || {
+            conn.lock().execute(
+                "INSERT INTO invites (code, uses, created) VALUES (?1, ?2, datetime('now'))",
+                params![code, uses],
+            )
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// List all invites, most recent first.
+    pub async fn invites(&self) -> Result<Vec<read::Invite>, Error> {
+        let conn = self.conn.clone();
+
+        let invites = spawn_blocking(move || {
+            let conn = conn.lock();
+            let mut stmt = conn
+                .prepare_cached("SELECT code, uses, created FROM invites ORDER BY created DESC")?;
+
+            stmt.query_map([], |row| {
+                Ok(read::Invite {
+                    code: row.get(0)?,
+                    uses: row.get(1)?,
+                    created: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .await??;
+
+        Ok(invites)
+    }
+
+    /// Delete invite `code`.
+    pub async fn delete_invite(&self, code: String) -> Result<(), Error> {
+        let conn = self.conn.clone();
+
+        let deleted = spawn_blocking(move || {
+            conn.lock()
+                .execute("DELETE FROM invites WHERE code=?1", params![code])
+        })
+        .await??;
+
+        if deleted == 0 {
+            return Err(Error::NotFound);
+        }
+
+        Ok(())
+    }
+
+    /// Get uid and encoded password hash of account `name`.
+    pub async fn user(&self, name: String) -> Result<Option<(i64, String)>, Error> {
+        let conn = self.conn.clone();
+
+        let user = spawn_blocking(move || {
+            conn.lock()
+                .query_row(
+                    "SELECT id, password FROM users WHERE name=?1",
+                    params![name],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()
+        })
+        .await??;
+
+        Ok(user)
+    }
+
+    /// Get name of the account `uid`.
+    pub async fn user_name(&self, uid: i64) -> Result<String, Error> {
+        let conn = self.conn.clone();
+
+        let name = spawn_blocking(move || {
+            conn.lock()
+                .query_row("SELECT name FROM users WHERE id=?1", params![uid], |row| {
+                    row.get(0)
+                })
+        })
+        .await??;
+
+        Ok(name)
+    }
+
+    /// Store a session identified by the `token` hash for account `uid` and remove expired ones.
+    /// A `pending` session only becomes valid once the second factor is given.
+    pub async fn create_session(
+        &self,
+        token: String,
+        uid: i64,
+        pending: bool,
+    ) -> Result<(), Error> {
+        let conn = self.conn.clone();
+        let expires = if pending {
+            format!("+{PENDING_MINUTES} minutes")
+        } else {
+            format!("+{SESSION_DAYS} days")
+        };
+
+        spawn_blocking(move || {
+            let mut conn = conn.lock();
+            let tx = conn.transaction()?;
+
+            tx.execute("DELETE FROM sessions WHERE expires < datetime('now')", [])?;
+            tx.execute(
+                "INSERT INTO sessions (token, uid, expires, pending) \
+                 VALUES (?1, ?2, datetime('now', ?3), ?4)",
+                params![token, uid, expires, pending],
+            )?;
+
+            tx.commit()
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// Get uid, name and roles of the account of the unexpired session identified by the `token`
+    /// hash and whether the account has two-factor authentication enabled. Sessions waiting for
+    /// a second factor are not returned.
+    pub async fn session(
+        &self,
+        token: String,
+    ) -> Result<Option<(i64, String, Roles, bool)>, Error> {
+        let conn = self.conn.clone();
+
+        let session = spawn_blocking(move || {
+            conn.lock()
+                .query_row(
+                    "SELECT users.id, users.name, users.can_create, users.admin, \
+                     users.totp_secret IS NOT NULL FROM sessions \
+                     JOIN users ON users.id=sessions.uid \
+                     WHERE (sessions.token=?1 AND sessions.expires > datetime('now') \
+                     AND sessions.pending=0)",
+                    params![token],
+                    |row| {
+                        let roles = Roles {
+                            create: row.get(2)?,
+                            admin: row.get(3)?,
+                        };
+
+                        Ok((row.get(0)?, row.get(1)?, roles, row.get(4)?))
+                    },
+                )
+                .optional()
+        })
+        .await??;
+
+        Ok(session)
+    }
+
+    /// Get uid and TOTP secret of the unexpired session identified by the `token` hash that waits
+    /// for a second factor.
+    pub async fn pending_session(&self, token: String) -> Result<Option<(i64, Vec<u8>)>, Error> {
+        let conn = self.conn.clone();
+
+        let session = spawn_blocking(move || {
+            conn.lock()
+                .query_row(
+                    "SELECT users.id, users.totp_secret FROM sessions \
+                     JOIN users ON users.id=sessions.uid \
+                     WHERE (sessions.token=?1 AND sessions.expires > datetime('now') \
+                     AND sessions.pending=1 AND users.totp_secret IS NOT NULL)",
+                    params![token],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()
+        })
+        .await??;
+
+        Ok(session)
+    }
+
+    /// Complete the pending session identified by the `token` hash after a second factor was
+    /// given.
+    pub async fn confirm_session(&self, token: String) -> Result<(), Error> {
+        let conn = self.conn.clone();
+
+        spawn_blocking(move || {
+            conn.lock().execute(
+                "UPDATE sessions SET pending=0, expires=datetime('now', ?2) WHERE token=?1",
+                params![token, format!("+{SESSION_DAYS} days")],
+            )
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// Count a wrong second factor for the pending session identified by the `token` hash and
+    /// return the number of failed attempts.
+    pub async fn fail_session(&self, token: String) -> Result<u32, Error> {
+        let conn = self.conn.clone();
+
+        let attempts = spawn_blocking(move || {
+            conn.lock().query_row(
+                "UPDATE sessions SET attempts=attempts+1 WHERE token=?1 RETURNING attempts",
+                params![token],
+                |row| row.get(0),
+            )
+        })
+        .await??;
+
+        Ok(attempts)
+    }
+
+    /// Get the TOTP secret of account `uid` if two-factor authentication is enabled together with
+    /// the number of unused recovery codes.
+    pub async fn totp(&self, uid: i64) -> Result<Option<(Vec<u8>, usize)>, Error> {
+        let conn = self.conn.clone();
+
+        let totp = spawn_blocking(move || {
+            conn.lock().query_row(
+                "SELECT totp_secret, (SELECT COUNT(*) FROM recovery_codes WHERE uid=?1) \
+                 FROM users WHERE id=?1",
+                params![uid],
+                |row| {
+                    let secret: Option<Vec<u8>> = row.get(0)?;
+                    Ok(secret.map(|secret| (secret, row.get(1))))
+                },
+            )
+        })
+        .await??;
+
+        totp.map(|(secret, codes)| Ok((secret, codes?))).transpose()
+    }
+
+    /// Enable two-factor authentication for account `uid` with `secret`, which was just used
+    /// with time `step`, replacing all recovery codes with the given `hashes`.
+    pub async fn enable_totp(
+        &self,
+        uid: i64,
+        secret: Vec<u8>,
+        step: u64,
+        hashes: Vec<String>,
+    ) -> Result<(), Error> {
+        let conn = self.conn.clone();
+        let step = i64::try_from(step)?;
+
+        spawn_blocking(move || {
+            let mut conn = conn.lock();
+            let tx = conn.transaction()?;
+
+            tx.execute(
+                "UPDATE users SET totp_secret=?2, totp_step=?3 WHERE id=?1",
+                params![uid, secret, step],
+            )?;
+            tx.execute("DELETE FROM recovery_codes WHERE uid=?1", params![uid])?;
+
+            for hash in hashes {
+                tx.execute(
+                    "INSERT INTO recovery_codes (uid, hash) VALUES (?1, ?2)",
+                    params![uid, hash],
+                )?;
+            }
+
+            tx.commit()
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// Disable two-factor authentication for account `uid` and delete its recovery codes.
+    pub async fn disable_totp(&self, uid: i64) -> Result<(), Error> {
+        let conn = self.conn.clone();
+
+        spawn_blocking(move || {
+            let mut conn = conn.lock();
+            let tx = conn.transaction()?;
+
+            tx.execute(
+                "UPDATE users SET totp_secret=NULL, totp_step=NULL WHERE id=?1",
+                params![uid],
+            )?;
+            tx.execute("DELETE FROM recovery_codes WHERE uid=?1", params![uid])?;
+
+            tx.commit()
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// Record that account `uid` used the TOTP code of time `step`. Return `false` if a code of
+    /// this or a later step was used before, so codes cannot be replayed.
+    pub async fn use_totp_step(&self, uid: i64, step: u64) -> Result<bool, Error> {
+        let conn = self.conn.clone();
+        let step = i64::try_from(step)?;
+
+        let updated = spawn_blocking(move || {
+            conn.lock().execute(
+                "UPDATE users SET totp_step=?2 \
+                 WHERE id=?1 AND (totp_step IS NULL OR totp_step < ?2)",
+                params![uid, step],
+            )
+        })
+        .await??;
+
+        Ok(updated > 0)
+    }
+
+    /// Delete the recovery code `hash` of account `uid` and return `true` if it existed.
+    pub async fn use_recovery_code(&self, uid: i64, hash: String) -> Result<bool, Error> {
+        let conn = self.conn.clone();
+
+        let deleted = spawn_blocking(move || {
+            conn.lock().execute(
+                "DELETE FROM recovery_codes WHERE uid=?1 AND hash=?2",
+                params![uid, hash],
+            )
+        })
+        .await??;
+
+        Ok(deleted > 0)
+    }
+
+    /// Replace the roles of account `uid`.
+    #[cfg(feature = "ldap")]
+    pub async fn set_roles(&self, uid: i64, roles: Roles) -> Result<(), Error> {
+        let conn = self.conn.clone();
+
+        spawn_blocking(move || {
+            conn.lock().execute(
+                "UPDATE users SET can_create=?1, admin=?2 WHERE id=?3",
+                params![roles.create, roles.admin, uid],
+            )
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// Store the API token `hash` labeled `label` with `scope` for account `uid`.
+    pub async fn create_api_token(
+        &self,
+        uid: i64,
+        hash: String,
+        label: String,
+        scope: Scope,
+    ) -> Result<(), Error> {
+        let conn = self.conn.clone();
+
+        spawn_blocking(move || {
+            conn.lock().execute(
+                "INSERT INTO api_tokens (uid, hash, label, scope, created) \
+                 VALUES (?1, ?2, ?3, ?4, datetime('now'))",
+                params![uid, hash, label, scope],
+            )
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// List the API tokens of account `uid`, most recent first.
+    pub async fn api_tokens(&self, uid: i64) -> Result<Vec<read::ApiToken>, Error> {
+        let conn = self.conn.clone();
+
+        let tokens = spawn_blocking(move || {
+            let conn = conn.lock();
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, label, scope, created, last_used FROM api_tokens \
+                 WHERE uid=?1 ORDER BY created DESC, id DESC",
+            )?;
+
+            stmt.query_map(params![uid], |row| {
+                Ok(read::ApiToken {
+                    id: row.get(0)?,
+                    label: row.get(1)?,
+                    scope: row.get(2)?,
+                    created: row.get(3)?,
+                    last_used: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .await??;
+
+        Ok(tokens)
+    }
+
+    /// Delete API token `id` if it belongs to account `uid` and return its label.
+    pub async fn delete_api_token(&self, id: i64, uid: i64) -> Result<String, Error> {
+        let conn = self.conn.clone();
+
+        let label = spawn_blocking(move || {
+            conn.lock()
+                .query_row(
+                    "DELETE FROM api_tokens WHERE id=?1 AND uid=?2 RETURNING label",
+                    params![id, uid],
+                    |row| row.get(0),
+                )
+                .optional()
+        })
+        .await??;
+
+        label.ok_or(Error::NotFound)
+    }
+
+    /// Append `action` on `target` by account `actor`, or the command line if `None`, to the
+    /// audit log.
+    pub async fn audit(
+        &self,
+        actor: Option<String>,
+        action: write::Action,
+        target: Option<String>,
+    ) -> Result<(), Error> {
+        let conn = self.conn.clone();
+
+        spawn_blocking(move || {
+            conn.lock().execute(
+                "INSERT INTO audit_log (created, actor, action, target) \
+                 VALUES (datetime('now'), ?1, ?2, ?3)",
+                params![actor, action.as_str(), target],
+            )
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// List audit log entries, most recent first, at most `limit` if given.
+    pub async fn audit_log(&self, limit: Option<usize>) -> Result<Vec<read::AuditEntry>, Error> {
+        let conn = self.conn.clone();
+
+        let entries = spawn_blocking(move || {
+            let conn = conn.lock();
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, created, actor, action, target FROM audit_log \
+                 ORDER BY id DESC LIMIT coalesce(?1, -1)",
+            )?;
+
+            stmt.query_map(params![limit], |row| {
+                Ok(read::AuditEntry {
+                    id: row.get(0)?,
+                    created: row.get(1)?,
+                    actor: row.get(2)?,
+                    action: row.get(3)?,
+                    target: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .await??;
+
+        Ok(entries)
+    }
+
+    /// Get up to `limit` most recent purges of expired pastes from the audit log.
+    pub async fn purges(&self, limit: usize) -> Result<Vec<read::AuditEntry>, Error> {
+        let conn = self.conn.clone();
+        let action = write::Action::Purge.as_str();
+
+        let entries = spawn_blocking(move || {
+            let conn = conn.lock();
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, created, actor, action, target FROM audit_log \
+                 WHERE action=?1 ORDER BY id DESC LIMIT ?2",
+            )?;
+
+            stmt.query_map(params![action, limit], |row| {
+                Ok(read::AuditEntry {
+                    id: row.get(0)?,
+                    created: row.get(1)?,
+                    actor: row.get(2)?,
+                    action: row.get(3)?,
+                    target: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .await??;
+
+        Ok(entries)
+    }
+
+    /// Get uid, name, roles and token scope of the account owning the API token `hash` and
+    /// record its use.
+    pub async fn api_token(
+        &self,
+        hash: String,
+    ) -> Result<Option<(i64, String, Roles, Scope)>, Error> {
+        let conn = self.conn.clone();
+
+        let token = spawn_blocking(move || {
+            let conn = conn.lock();
+
+            let token = conn
+                .query_row(
+                    "SELECT users.id, users.name, users.can_create, users.admin, \
+                     api_tokens.scope FROM api_tokens JOIN users ON users.id=api_tokens.uid \
+                     WHERE api_tokens.hash=?1",
+                    params![hash],
+                    |row| {
+                        let roles = Roles {
+                            create: row.get(2)?,
+                            admin: row.get(3)?,
+                        };
+
+                        Ok((row.get(0)?, row.get(1)?, roles, row.get(4)?))
+                    },
+                )
+                .optional()?;
+
+            if token.is_some() {
+                conn.execute(
+                    "UPDATE api_tokens SET last_used=datetime('now') WHERE hash=?1",
+                    params![hash],
+                )?;
+            }
+
+            Ok::<_, rusqlite::Error>(token)
+        })
+        .await??;
+
+        Ok(token)
+    }
+
+    /// Delete the session identified by the `token` hash.
+    pub async fn delete_session(&self, token: String) -> Result<(), Error> {
+        let conn = self.conn.clone();
+
+        spawn_blocking(move || {
+            conn.lock()
+                .execute("DELETE FROM sessions WHERE token=?1", params![token])
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// Get the account uid linked to `subject` of the external identity provider `issuer`.
+    #[cfg(any(feature = "ldap", feature = "oidc"))]
+    pub async fn identity(&self, issuer: String, subject: String) -> Result<Option<i64>, Error> {
+        let conn = self.conn.clone();
+
+        let uid = spawn_blocking(move || {
+            conn.lock()
+                .query_row(
+                    "SELECT uid FROM identities WHERE (issuer=?1 AND subject=?2)",
+                    params![issuer, subject],
+                    |row| row.get(0),
+                )
+                .optional()
+        })
+        .await??;
+
+        Ok(uid)
+    }
+
+    /// Create a password-less account for `uid` linked to `subject` of the external identity
+    /// provider `issuer`. The account is named after the first of `names` that is not taken yet.
+    #[cfg(any(feature = "ldap", feature = "oidc"))]
+    pub async fn create_identity(
+        &self,
+        uid: i64,
+        names: Vec<String>,
+        issuer: String,
+        subject: String,
+    ) -> Result<(), Error> {
+        let conn = self.conn.clone();
+
+        spawn_blocking(move || {
+            let mut conn = conn.lock();
+            let tx = conn.transaction()?;
+
+            let mut free = None;
+
+            for name in names {
+                let taken = tx
+                    .query_row("SELECT 1 FROM users WHERE name=?1", params![name], |_| {
+                        Ok(())
+                    })
+                    .optional()?
+                    .is_some();
+
+                if !taken {
+                    free = Some(name);
+                    break;
+                }
+            }
+
+            let name = free.ok_or(Error::UserExists)?;
+
+            tx.execute(
+                "INSERT INTO users (id, name, password, created) VALUES (?1, ?2, '', datetime('now'))",
+                params![uid, name],
+            )?;
+            tx.execute(
+                "INSERT INTO identities (issuer, subject, uid) VALUES (?1, ?2, ?3)",
+                params![issuer, subject, uid],
+            )?;
+
+            tx.commit()?;
+
+            Ok::<_, Error>(())
+        })
+        .await??;
+
+        Ok(())
+    }
+
+    /// Retrieve next monotonically increasing uid.
+    pub async fn next_uid(&self) -> Result<i64, Error> {
+        let conn = self.conn.clone();
+
+        let uid = spawn_blocking(move || {
+            conn.lock().query_row(
+                "UPDATE uids SET n = n + 1 WHERE id = 0 RETURNING n",
+                [],
+                |row| row.get(0),
+            )
+        })
+        .await??;
+
+        Ok(uid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use super::*;
+    use tokio_stream::StreamExt;
+
+    impl read::Entry {
         /// Unwrap inner data or panic.
         pub fn unwrap_inner(self) -> read::Data {
             match self {
-                read::Entry::Regular(data) => data,
-                read::Entry::Burned(data) => data,
+                read::Entry::Regular(data) | read::Entry::Burned(data) => data,
                 read::Entry::Expired => panic!("no data"),
             }
         }
@@ -463,17 +2677,163 @@ mod tests {
         let id = Id::from(1234u32);
         db.insert(id, entry).await?;
 
-        let entry = db.get(id, None).await?.unwrap_inner();
+        let entry = db.get(id, None, None).await?.unwrap_inner();
         assert_eq!(entry.text, "hello world");
         assert!(entry.uid.is_some());
         assert_eq!(entry.uid.unwrap(), 10);
 
-        let result = db.get(Id::from(5678u32), None).await;
+        let result = db.get(Id::from(5678u32), None, None).await;
         assert!(result.is_err());
 
         Ok(())
     }
 
+    #[tokio::test]
+    async fn negative_cache() -> Result<(), Box<dyn std::error::Error>> {
+        let db = new_db()?.with_negative_cache(NonZero::new(8).unwrap(), Duration::from_secs(60));
+        let id = Id::from(1234u32);
+
+        assert!(matches!(db.get(id, None, None).await, Err(Error::NotFound)));
+        assert!(db.missing.contains(id));
+
+        let entry = write::Entry {
+            text: "hello world".to_string(),
+            ..Default::default()
+        };
+        db.insert(id, entry).await?;
+        assert!(!db.missing.contains(id));
+        assert_eq!(
+            db.get(id, None, None).await?.unwrap_inner().text,
+            "hello world"
+        );
+
+        let secret = Id::from(5678u32);
+        let entry = write::Entry {
+            text: "hidden".to_string(),
+            secret: Some("token".to_string()),
+            ..Default::default()
+        };
+        db.insert(secret, entry).await?;
+        assert!(db.get_title(secret, None).await.is_err());
+        assert!(!db.missing.contains(secret));
+
+        Ok(())
+    }
+
+    /// Concatenate all chunks of `content`.
+    async fn collect(content: read::Content) -> Result<String, Box<dyn std::error::Error>> {
+        match content {
+            read::Content::Whole(text) => Ok(text),
+            read::Content::Chunks(mut chunks) => {
+                let mut text = Vec::new();
+
+                while let Some(chunk) = chunks.next().await {
+                    text.extend_from_slice(&chunk?);
+                }
+
+                Ok(String::from_utf8(text)?)
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn stream() -> Result<(), Box<dyn std::error::Error>> {
+        let db = new_db()?.with_argon2(crate::crypto::tests::PARAMS);
+        let text = "hello world\n".repeat(CHUNK_SIZE / 4);
+
+        let entry = write::Entry {
+            text: text.clone(),
+            ..Default::default()
+        };
+
+        let id = Id::from(1234u32);
+        db.insert(id, entry).await?;
+
+        let streamed = db.stream(id, None, None).await?;
+        assert!(matches!(streamed.content, read::Content::Chunks(_)));
+        assert_eq!(collect(streamed.content).await?, text);
+
+        let entry = write::Entry {
+            text: text.clone(),
+            password: Some("secret".to_string()),
+            ..Default::default()
+        };
+
+        let id = Id::from(5678u32);
+        db.insert(id, entry).await?;
+
+        assert!(matches!(
+            db.stream(id, None, None).await,
+            Err(Error::NoPassword)
+        ));
+
+        let password = Password::from(b"secret".to_vec());
+        let streamed = db.stream(id, None, Some(password)).await?;
+        assert!(matches!(streamed.content, read::Content::Whole(_)));
+        assert_eq!(collect(streamed.content).await?, text);
+
+        assert!(matches!(
+            db.stream(Id::from(42u32), None, None).await,
+            Err(Error::NotFound)
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rehash_legacy_encryption() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::crypto::{Kdf, Plaintext};
+
+        let db = new_db()?.with_argon2(crate::crypto::tests::PARAMS);
+        let id = Id::from(1234u32);
+        let password = || Password::from(b"secret".to_vec());
+        let kdf = |db: &Database| -> Result<Option<String>, rusqlite::Error> {
+            db.conn.lock().query_row(
+                "SELECT kdf FROM entries WHERE id=?1",
+                params![id.to_i64()],
+                |row| row.get(0),
+            )
+        };
+
+        // Encrypt like before per-paste salts.
+        let write::DatabaseEntry { data, .. } = write::Entry {
+            text: "hello world".to_string(),
+            ..Default::default()
+        }
+        .compress()
+        .await?
+        .encrypt(crate::crypto::tests::PARAMS)
+        .await?;
+        let encrypted = Plaintext::from(data)
+            .encrypt(password(), Kdf::legacy())
+            .await?;
+
+        db.conn.lock().execute(
+            "INSERT INTO entries (id, data, nonce) VALUES (?1, ?2, ?3)",
+            params![id.to_i64(), encrypted.ciphertext, encrypted.nonce],
+        )?;
+
+        assert!(matches!(
+            db.get(id, None, None).await,
+            Err(Error::NoPassword)
+        ));
+        assert_eq!(kdf(&db)?, None);
+
+        let entry = db.get(id, None, Some(password())).await?.unwrap_inner();
+        assert_eq!(entry.text, "hello world");
+        assert!(kdf(&db)?.unwrap().starts_with("argon2id$m=64,t=1,p=1$"));
+
+        let entry = db.get(id, None, Some(password())).await?.unwrap_inner();
+        assert_eq!(entry.text, "hello world");
+
+        let result = db
+            .get(id, None, Some(Password::from(b"wrong".to_vec())))
+            .await;
+        assert!(matches!(result, Err(Error::ChaCha20Poly1305Decrypt)));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn expired_does_not_exist() -> Result<(), Box<dyn std::error::Error>> {
         let db = new_db()?;
@@ -488,12 +2848,75 @@ mod tests {
 
         tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
 
-        let result = db.get(id, None).await;
+        let result = db.get(id, None, None).await;
         assert!(matches!(result, Ok(read::Entry::Expired)));
 
         Ok(())
     }
 
+    #[tokio::test]
+    async fn purge_in_batches() -> Result<(), Box<dyn std::error::Error>> {
+        let db = new_db()?;
+        let count = PURGE_BATCH + 10;
+
+        for number in 0..=count {
+            let id = Id::from(u32::try_from(number)?);
+            db.insert(id, write::Entry::default()).await?;
+        }
+
+        db.conn.lock().execute(
+            "UPDATE entries SET expires=datetime('now', '-1 day'), pinned=(id=?1)",
+            params![Id::from(0u32).to_i64()],
+        )?;
+
+        assert_eq!(db.purge().await?.len(), count);
+        assert!(db.get(Id::from(0u32), None, None).await.is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn interrupt_after_deadline() -> Result<(), Box<dyn std::error::Error>> {
+        let db = new_db()?;
+        let deadline = Deadline::after(Duration::from_millis(10));
+
+        let result = lock_until(&db.conn, Some(deadline))?.query_row(
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n) SELECT count(*) FROM n",
+            [],
+            |row| row.get::<_, i64>(0),
+        );
+        assert!(matches!(
+            result.map_err(Error::from),
+            Err(Error::DeadlineExceeded)
+        ));
+
+        let count: i64 = lock_until(&db.conn, None)?.query_row(
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 100000) SELECT count(*) FROM n",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(count, 100_000);
+
+        let _held = db.conn.lock();
+        assert!(lock_until(&db.conn, Some(deadline)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn metadata_from_index() -> Result<(), Box<dyn std::error::Error>> {
+        let db = new_db()?;
+        let plan: String = db.conn.lock().query_row(
+            "EXPLAIN QUERY PLAN SELECT title, secret, filename FROM entries INDEXED BY entries_lookup WHERE id=?1",
+            params![1],
+            |row| row.get(3),
+        )?;
+
+        assert!(plan.contains("COVERING INDEX entries_lookup"));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn delete() -> Result<(), Box<dyn std::error::Error>> {
         let db = new_db()?;
@@ -501,9 +2924,185 @@ mod tests {
         let id = Id::from(1234u32);
         db.insert(id, write::Entry::default()).await?;
 
-        assert!(db.get(id, None).await.is_ok());
+        assert!(db.get(id, None, None).await.is_ok());
         assert!(db.delete(id).await.is_ok());
-        assert!(db.get(id, None).await.is_err());
+        assert!(db.get(id, None, None).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_older_than() -> Result<(), Box<dyn std::error::Error>> {
+        let db = new_db()?;
+
+        db.insert(Id::from(1u32), write::Entry::default()).await?;
+        db.insert(Id::from(2u32), write::Entry::default()).await?;
+        db.conn.lock().execute(
+            "UPDATE entries SET created=datetime('now', '-100 days') WHERE id=1",
+            [],
+        )?;
+
+        let listings = db.list_all(None, Some(90), 0, None).await?;
+        assert_eq!(listings.len(), 1);
+        assert_eq!(listings[0].0.id, Id::from(1u32));
+
+        assert_eq!(db.list_all(None, None, 0, None).await?.len(), 2);
+        assert_eq!(db.list_all(None, None, 0, Some(1)).await?.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn distribution() -> Result<(), Box<dyn std::error::Error>> {
+        let db = new_db()?;
+
+        for (id, extension, expires) in [(1u32, "rs", None), (2, "rs", Some(600)), (3, "py", None)]
+        {
+            let entry = write::Entry {
+                extension: Some(extension.to_string()),
+                expires: expires.and_then(NonZero::new),
+                ..Default::default()
+            };
+            db.insert(Id::from(id), entry).await?;
+        }
+
+        let distribution = db.distribution().await?;
+
+        let expirations = distribution
+            .expirations
+            .iter()
+            .map(|share| (share.label.as_str(), share.pastes))
+            .collect::<Vec<_>>();
+        assert_eq!(expirations, [("within an hour", 1), ("never", 2)]);
+
+        let languages = distribution
+            .languages
+            .iter()
+            .map(|share| (share.label.as_str(), share.pastes))
+            .collect::<Vec<_>>();
+        assert_eq!(languages, [("rs", 2), ("py", 1)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn language_history() -> Result<(), Box<dyn std::error::Error>> {
+        let db = new_db()?;
+
+        for (id, extension, ciphertext) in [
+            (1u32, "rs", false),
+            (2, "rs", false),
+            (3, "py", false),
+            (4, "", true),
+        ] {
+            let entry = write::Entry {
+                extension: Some(extension.to_string()).filter(|ext| !ext.is_empty()),
+                ciphertext,
+                ..Default::default()
+            };
+            db.insert(Id::from(id), entry).await?;
+        }
+
+        db.conn.lock().execute(
+            "UPDATE entries SET created = '2024-05-01 12:00:00' WHERE id = 3",
+            [],
+        )?;
+
+        let history = db.language_history().await?;
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].month, "2024-05");
+
+        let languages = history[0]
+            .languages
+            .iter()
+            .map(|share| (share.label.as_str(), share.pastes))
+            .collect::<Vec<_>>();
+        assert_eq!(languages, [("rs", 2)]);
+        assert_eq!(history[1].languages[0].label, "py");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn audit_log() -> Result<(), Box<dyn std::error::Error>> {
+        let db = new_db()?;
+
+        db.audit(
+            Some(String::from("alice")),
+            write::Action::Pin,
+            Some(String::from("bJZCna")),
+        )
+        .await?;
+        db.audit(None, write::Action::Purge, Some(String::from("3")))
+            .await?;
+
+        let entries = db.audit_log(None).await?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].actor, None);
+        assert_eq!(entries[0].action, "purge");
+        assert_eq!(entries[1].actor.as_deref(), Some("alice"));
+        assert_eq!(entries[1].target.as_deref(), Some("bJZCna"));
+        assert_eq!(db.audit_log(Some(1)).await?.len(), 1);
+
+        assert!(db.conn.lock().execute("DELETE FROM audit_log", []).is_err());
+        assert!(
+            db.conn
+                .lock()
+                .execute("UPDATE audit_log SET actor='mallory'", [])
+                .is_err()
+        );
+        assert_eq!(db.audit_log(None).await?.len(), 2);
+
+        Ok(())
+    }
+
+    #[cfg(any(feature = "ldap", feature = "oidc"))]
+    #[tokio::test]
+    async fn identities() -> Result<(), Box<dyn std::error::Error>> {
+        let db = new_db()?;
+        let issuer = String::from("https://sso.example.com");
+
+        db.create_user(1, String::from("alice"), String::new())
+            .await?;
+        db.create_identity(
+            2,
+            vec![String::from("alice"), String::from("alice-2")],
+            issuer.clone(),
+            String::from("1234"),
+        )
+        .await?;
+
+        assert_eq!(
+            db.identity(issuer.clone(), String::from("1234")).await?,
+            Some(2)
+        );
+        assert_eq!(db.user_name(2).await?, "alice-2");
+        assert_eq!(db.identity(issuer, String::from("5678")).await?, None);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "ldap")]
+    #[tokio::test]
+    async fn roles() -> Result<(), Box<dyn std::error::Error>> {
+        let db = new_db()?;
+
+        db.create_user(1, String::from("alice"), String::new())
+            .await?;
+        db.create_session(String::from("token"), 1, false).await?;
+
+        let (uid, name, roles, _) = db.session(String::from("token")).await?.unwrap();
+        assert_eq!(uid, 1);
+        assert_eq!(name, "alice");
+        assert_eq!(roles, Roles::default());
+
+        let admin = Roles {
+            create: false,
+            admin: true,
+        };
+        db.set_roles(1, admin).await?;
+        let (_, _, roles, _) = db.session(String::from("token")).await?.unwrap();
+        assert_eq!(roles, admin);
 
         Ok(())
     }
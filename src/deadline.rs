@@ -0,0 +1,63 @@
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::time::{Duration, Instant};
+
+tokio::task_local! {
+    static DEADLINE: Deadline;
+}
+
+/// Point in time after which nobody waits for work done on behalf of a request anymore, because
+/// the timeout layer has answered it already.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Deadline(Instant);
+
+impl Deadline {
+    pub fn after(timeout: Duration) -> Self {
+        Self(Instant::now() + timeout)
+    }
+
+    /// Deadline of the request handled by the current task, if any. Blocking tasks do not inherit
+    /// it, so it has to be passed to them.
+    pub fn current() -> Option<Self> {
+        DEADLINE.try_with(|deadline| *deadline).ok()
+    }
+
+    pub fn instant(self) -> Instant {
+        self.0
+    }
+
+    /// Return `true` if the deadline has passed and the work should be abandoned.
+    pub fn passed(self) -> bool {
+        Instant::now() >= self.0
+    }
+}
+
+/// Handle the request with a deadline `timeout` from now, the same the timeout layer enforces.
+pub(crate) async fn attach(
+    State(timeout): State<Duration>,
+    request: Request,
+    next: Next,
+) -> Response {
+    DEADLINE
+        .scope(Deadline::after(timeout), next.run(request))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn scoped() {
+        assert!(Deadline::current().is_none());
+
+        DEADLINE
+            .scope(Deadline::after(Duration::ZERO), async {
+                assert!(Deadline::current().is_some_and(Deadline::passed));
+            })
+            .await;
+
+        assert!(!Deadline::after(Duration::from_secs(60)).passed());
+    }
+}
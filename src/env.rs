@@ -1,66 +1,548 @@
-use crate::{db, expiration, highlight};
+use crate::assets::Source;
+use crate::shortcuts::Shortcuts;
+use crate::timezone::Timezone;
+use crate::{
+    access, access_log, accounts, cache_control, captcha, clamav, compression, crypto, db,
+    expiration, highlight, ldap, limits, logging, mail, metrics, notify, page, secrets, shortcuts,
+};
 use axum_extra::extract::cookie::Key;
 use std::env::VarError;
 use std::net::SocketAddr;
 use std::num::{NonZeroUsize, ParseIntError};
 use std::path::PathBuf;
+use std::str::ParseBoolError;
 use std::time::Duration;
 
 pub const DEFAULT_HTTP_TIMEOUT: Duration = Duration::from_secs(5);
 
+const VAR_ACCESS_LOG: &str = "WASTEBIN_ACCESS_LOG";
+const VAR_ACCESS_LOG_FORMAT: &str = "WASTEBIN_ACCESS_LOG_FORMAT";
+const VAR_ACCOUNTS: &str = "WASTEBIN_ACCOUNTS";
+const VAR_ADMINS: &str = "WASTEBIN_ADMINS";
+const VAR_ADMIN_TOTP: &str = "WASTEBIN_ADMIN_TOTP";
 const VAR_ADDRESS_PORT: &str = "WASTEBIN_ADDRESS_PORT";
+const VAR_ANONYMOUS_ALLOWLIST: &str = "WASTEBIN_ANONYMOUS_ALLOWLIST";
+const VAR_ANONYMOUS_DAILY_BYTES: &str = "WASTEBIN_ANONYMOUS_DAILY_BYTES";
+const VAR_ANONYMOUS_DAILY_PASTES: &str = "WASTEBIN_ANONYMOUS_DAILY_PASTES";
+const VAR_ARGON2_ITERATIONS: &str = "WASTEBIN_ARGON2_ITERATIONS";
+const VAR_ARGON2_MEMORY: &str = "WASTEBIN_ARGON2_MEMORY";
+const VAR_ARGON2_PARALLELISM: &str = "WASTEBIN_ARGON2_PARALLELISM";
+const VAR_ASSET_URL: &str = "WASTEBIN_ASSET_URL";
 const VAR_BASE_URL: &str = "WASTEBIN_BASE_URL";
+const VAR_BLOCKLIST: &str = "WASTEBIN_BLOCKLIST";
+const VAR_TERMS: &str = "WASTEBIN_TERMS";
+const VAR_ABOUT: &str = "WASTEBIN_ABOUT";
+const VAR_CACHE_CONTROL_ASSETS: &str = "WASTEBIN_CACHE_CONTROL_ASSETS";
+const VAR_CACHE_CONTROL_PASTE: &str = "WASTEBIN_CACHE_CONTROL_PASTE";
+const VAR_CACHE_CONTROL_RAW: &str = "WASTEBIN_CACHE_CONTROL_RAW";
 const VAR_CACHE_SIZE: &str = "WASTEBIN_CACHE_SIZE";
+const VAR_CACHE_WARM: &str = "WASTEBIN_CACHE_WARM";
+const VAR_NEGATIVE_CACHE_SIZE: &str = "WASTEBIN_NEGATIVE_CACHE_SIZE";
+const VAR_NEGATIVE_CACHE_TTL: &str = "WASTEBIN_NEGATIVE_CACHE_TTL";
+const VAR_CAPTCHA: &str = "WASTEBIN_CAPTCHA";
+const VAR_CAPTCHA_BYPASS_TOKEN: &str = "WASTEBIN_CAPTCHA_BYPASS_TOKEN";
+const VAR_CAPTCHA_SECRET: &str = "WASTEBIN_CAPTCHA_SECRET";
+const VAR_CAPTCHA_SITE_KEY: &str = "WASTEBIN_CAPTCHA_SITE_KEY";
+const VAR_CLIENT_IP_HEADER: &str = "WASTEBIN_CLIENT_IP_HEADER";
+const VAR_CLAMD: &str = "WASTEBIN_CLAMD";
+const VAR_COMPRESSION_MIN_SIZE: &str = "WASTEBIN_COMPRESSION_MIN_SIZE";
+const VAR_COMPRESSION_TYPES: &str = "WASTEBIN_COMPRESSION_TYPES";
+const VAR_CREATE_ALLOWLIST: &str = "WASTEBIN_CREATE_ALLOWLIST";
+const VAR_CREATE_DENYLIST: &str = "WASTEBIN_CREATE_DENYLIST";
 const VAR_DATABASE_PATH: &str = "WASTEBIN_DATABASE_PATH";
+const VAR_DISK_CACHE_PATH: &str = "WASTEBIN_DISK_CACHE_PATH";
+const VAR_DISK_CACHE_SIZE: &str = "WASTEBIN_DISK_CACHE_SIZE";
+const VAR_DUMP_DIR: &str = "WASTEBIN_DUMP_DIR";
+const VAR_HIGHLIGHTER: &str = "WASTEBIN_HIGHLIGHTER";
+const VAR_HIGHLIGHT_TIMEOUT: &str = "WASTEBIN_HIGHLIGHT_TIMEOUT";
+const VAR_HIGHLIGHT_THREADS: &str = "WASTEBIN_HIGHLIGHT_THREADS";
 const VAR_HTTP_TIMEOUT: &str = "WASTEBIN_HTTP_TIMEOUT";
+const VAR_LANGUAGE_ALIASES: &str = "WASTEBIN_LANGUAGE_ALIASES";
+const VAR_LANGUAGES: &str = "WASTEBIN_LANGUAGES";
+const VAR_LDAP_ADMIN_FILTER: &str = "WASTEBIN_LDAP_ADMIN_FILTER";
+const VAR_LDAP_BIND_DN: &str = "WASTEBIN_LDAP_BIND_DN";
+const VAR_LDAP_CREATE_FILTER: &str = "WASTEBIN_LDAP_CREATE_FILTER";
+const VAR_LDAP_URL: &str = "WASTEBIN_LDAP_URL";
+const VAR_LOCALE_DIR: &str = "WASTEBIN_LOCALE_DIR";
+const VAR_LOG_FORMAT: &str = "WASTEBIN_LOG_FORMAT";
 const VAR_MAX_BODY_SIZE: &str = "WASTEBIN_MAX_BODY_SIZE";
+const VAR_MAX_HIGHLIGHT_BYTES: &str = "WASTEBIN_MAX_HIGHLIGHT_BYTES";
+const VAR_METRICS: &str = "WASTEBIN_METRICS";
+const VAR_METRICS_ADDRESS_PORT: &str = "WASTEBIN_METRICS_ADDRESS_PORT";
+const VAR_TCP_ADDRESS_PORT: &str = "WASTEBIN_TCP_ADDRESS_PORT";
+const VAR_GEMINI_ADDRESS_PORT: &str = "WASTEBIN_GEMINI_ADDRESS_PORT";
+const VAR_GEMINI_CERT: &str = "WASTEBIN_GEMINI_CERT";
+const VAR_GEMINI_KEY: &str = "WASTEBIN_GEMINI_KEY";
+const VAR_GOPHER_ADDRESS_PORT: &str = "WASTEBIN_GOPHER_ADDRESS_PORT";
+const VAR_GOPHER_LISTING: &str = "WASTEBIN_GOPHER_LISTING";
+const VAR_SMTP_ADDRESS_PORT: &str = "WASTEBIN_SMTP_ADDRESS_PORT";
+const VAR_SMTP_ALLOWED_SENDERS: &str = "WASTEBIN_SMTP_ALLOWED_SENDERS";
+const VAR_SMTP_RELAY: &str = "WASTEBIN_SMTP_RELAY";
+const VAR_MIN_FILL_TIME: &str = "WASTEBIN_MIN_FILL_TIME";
+const VAR_NOTIFY_DISCORD: &str = "WASTEBIN_NOTIFY_DISCORD";
+const VAR_NOTIFY_DISCORD_EVENTS: &str = "WASTEBIN_NOTIFY_DISCORD_EVENTS";
+const VAR_NOTIFY_MATRIX: &str = "WASTEBIN_NOTIFY_MATRIX";
+const VAR_NOTIFY_MATRIX_EVENTS: &str = "WASTEBIN_NOTIFY_MATRIX_EVENTS";
+const VAR_NOTIFY_MATRIX_ROOM: &str = "WASTEBIN_NOTIFY_MATRIX_ROOM";
+const VAR_NOTIFY_MATRIX_TOKEN: &str = "WASTEBIN_NOTIFY_MATRIX_TOKEN";
+const VAR_NOTIFY_SLACK: &str = "WASTEBIN_NOTIFY_SLACK";
+const VAR_NOTIFY_SLACK_EVENTS: &str = "WASTEBIN_NOTIFY_SLACK_EVENTS";
+const VAR_OIDC_CLIENT_ID: &str = "WASTEBIN_OIDC_CLIENT_ID";
+const VAR_OIDC_CLIENT_SECRET: &str = "WASTEBIN_OIDC_CLIENT_SECRET";
+const VAR_OIDC_ISSUER: &str = "WASTEBIN_OIDC_ISSUER";
+const VAR_OTLP_ENDPOINT: &str = "WASTEBIN_OTLP_ENDPOINT";
+const VAR_OTLP_SAMPLE_RATIO: &str = "WASTEBIN_OTLP_SAMPLE_RATIO";
 const VAR_PASTE_EXPIRATIONS: &str = "WASTEBIN_PASTE_EXPIRATIONS";
+const VAR_SECRETS: &str = "WASTEBIN_SECRETS";
+const VAR_SECRETS_EXPIRATION: &str = "WASTEBIN_SECRETS_EXPIRATION";
+const VAR_SENTRY_DSN: &str = "WASTEBIN_SENTRY_DSN";
+const VAR_SENTRY_ENVIRONMENT: &str = "WASTEBIN_SENTRY_ENVIRONMENT";
+const VAR_SHUTDOWN_DRAIN: &str = "WASTEBIN_SHUTDOWN_DRAIN";
 const VAR_SIGNING_KEY: &str = "WASTEBIN_SIGNING_KEY";
+const VAR_SYNTAX_DIR: &str = "WASTEBIN_SYNTAX_DIR";
 const VAR_THEME: &str = "WASTEBIN_THEME";
+const VAR_TEMPLATE_DIR: &str = "WASTEBIN_TEMPLATE_DIR";
+const VAR_CUSTOM_CSS: &str = "WASTEBIN_CUSTOM_CSS";
+const VAR_LOGO: &str = "WASTEBIN_LOGO";
+const VAR_FOOTER_LINKS: &str = "WASTEBIN_FOOTER_LINKS";
+const VAR_SHORTCUTS: &str = "WASTEBIN_SHORTCUTS";
+const VAR_TIMEZONE: &str = "WASTEBIN_TIMEZONE";
+const VAR_TAB_WIDTH: &str = "WASTEBIN_TAB_WIDTH";
+const VAR_FAVICON: &str = "WASTEBIN_FAVICON";
+const VAR_TOUCH_ICON: &str = "WASTEBIN_TOUCH_ICON";
+const VAR_NOINDEX: &str = "WASTEBIN_NOINDEX";
+const VAR_LANGUAGE_STATS: &str = "WASTEBIN_LANGUAGE_STATS";
+const VAR_SITEMAP: &str = "WASTEBIN_SITEMAP";
+const VAR_ROBOTS_TXT: &str = "WASTEBIN_ROBOTS_TXT";
+const VAR_THEME_DIR: &str = "WASTEBIN_THEME_DIR";
 const VAR_PASSWORD_SALT: &str = "WASTEBIN_PASSWORD_SALT";
+const VAR_POW_DIFFICULTY: &str = "WASTEBIN_POW_DIFFICULTY";
+const VAR_PRERENDER: &str = "WASTEBIN_PRERENDER";
+const VAR_MINIFY: &str = "WASTEBIN_MINIFY";
+const VAR_PRIVATE: &str = "WASTEBIN_PRIVATE";
+const VAR_QUOTA_BYTES: &str = "WASTEBIN_QUOTA_BYTES";
+const VAR_QUOTA_PASTES: &str = "WASTEBIN_QUOTA_PASTES";
+const VAR_QUOTA_PASTE_BYTES: &str = "WASTEBIN_QUOTA_PASTE_BYTES";
+const VAR_READ_ALLOWLIST: &str = "WASTEBIN_READ_ALLOWLIST";
+const VAR_READ_DENYLIST: &str = "WASTEBIN_READ_DENYLIST";
+const VAR_REGISTRATION: &str = "WASTEBIN_REGISTRATION";
 
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum Error {
+    #[error("failed to parse {0}, expected `Cache-Control` directives: {1}")]
+    CacheControl(&'static str, String),
     #[error("failed to parse {VAR_CACHE_SIZE}, expected number of elements: {0}")]
     CacheSize(ParseIntError),
+    #[error("failed to parse {VAR_CACHE_WARM}, expected number of pastes: {0}")]
+    CacheWarm(ParseIntError),
+    #[error("failed to parse {VAR_NEGATIVE_CACHE_SIZE}, expected number of identifiers: {0}")]
+    NegativeCacheSize(ParseIntError),
+    #[error("failed to parse {VAR_NEGATIVE_CACHE_TTL}, expected number of seconds: {0}")]
+    NegativeCacheTtl(ParseIntError),
     #[error("failed to parse {VAR_DATABASE_PATH}, contains non-Unicode data")]
     DatabasePath,
+    #[error("failed to parse {VAR_DISK_CACHE_PATH}, contains non-Unicode data")]
+    DiskCachePath,
+    #[error("failed to parse {VAR_DISK_CACHE_SIZE}, expected number of bytes: {0}")]
+    DiskCacheSize(ParseIntError),
+    #[error("failed to parse {VAR_DUMP_DIR}, contains non-Unicode data")]
+    DumpDir,
+    #[error("failed to parse {VAR_SYNTAX_DIR}, contains non-Unicode data")]
+    SyntaxDir,
+    #[error("failed to parse {VAR_BLOCKLIST}, contains non-Unicode data")]
+    BlocklistPath,
+    #[error("failed to parse {VAR_TERMS}, contains non-Unicode data")]
+    Terms,
+    #[error("failed to parse {VAR_ABOUT}, contains non-Unicode data")]
+    About,
+    #[error("failed to parse {VAR_THEME_DIR}, contains non-Unicode data")]
+    ThemeDir,
+    #[error("failed to parse {VAR_LOCALE_DIR}, contains non-Unicode data")]
+    LocaleDir,
+    #[error("failed to parse {VAR_TEMPLATE_DIR}, contains non-Unicode data")]
+    TemplateDir,
+    #[error("failed to parse {VAR_CUSTOM_CSS}, expected http(s) URL or path: {0}")]
+    CustomCss(String),
+    #[error("failed to parse {VAR_LOGO}, expected http(s) URL or path: {0}")]
+    Logo(String),
+    #[error("failed to parse {VAR_FAVICON}, contains non-Unicode data")]
+    Favicon,
+    #[error("failed to parse {VAR_TOUCH_ICON}, contains non-Unicode data")]
+    TouchIcon,
+    #[error("failed to parse {VAR_NOINDEX}, expected `true` or `false`: {0}")]
+    Noindex(ParseBoolError),
+    #[error("failed to parse {VAR_LANGUAGE_STATS}, expected `true` or `false`: {0}")]
+    LanguageStats(ParseBoolError),
+    #[error("failed to parse {VAR_SITEMAP}, expected `true` or `false`: {0}")]
+    Sitemap(ParseBoolError),
+    #[error("failed to parse {VAR_ROBOTS_TXT}, contains non-Unicode data")]
+    RobotsTxt,
     #[error("failed to parse {VAR_MAX_BODY_SIZE}, expected number of bytes: {0}")]
     MaxBodySize(ParseIntError),
+    #[error("failed to parse {VAR_MAX_HIGHLIGHT_BYTES}, expected number of bytes: {0}")]
+    MaxHighlightBytes(ParseIntError),
     #[error("failed to parse {VAR_ADDRESS_PORT}, expected `host:port`")]
     AddressPort,
+    #[error("failed to parse {VAR_METRICS}, expected `true` or `false`: {0}")]
+    Metrics(ParseBoolError),
+    #[error("failed to parse {VAR_METRICS_ADDRESS_PORT}, expected `host:port`")]
+    MetricsAddressPort,
+    #[error("failed to parse {VAR_TCP_ADDRESS_PORT}, expected `host:port`")]
+    TcpAddressPort,
+    #[error("failed to parse {VAR_GEMINI_ADDRESS_PORT}, expected `host:port`")]
+    GeminiAddressPort,
+    #[error("{VAR_GEMINI_ADDRESS_PORT} requires {VAR_GEMINI_CERT} and {VAR_GEMINI_KEY}")]
+    GeminiCertificate,
+    #[error("{VAR_GEMINI_ADDRESS_PORT} requires the `gemini` feature")]
+    GeminiUnavailable,
+    #[error("failed to parse {VAR_GOPHER_ADDRESS_PORT}, expected `host:port`")]
+    GopherAddressPort,
+    #[error("{VAR_GOPHER_ADDRESS_PORT} requires the `gopher` feature")]
+    GopherUnavailable,
+    #[error("failed to parse {VAR_GOPHER_LISTING}, expected `true` or `false`: {0}")]
+    GopherListing(ParseBoolError),
+    #[error("failed to parse {VAR_SMTP_ADDRESS_PORT}, expected `host:port`")]
+    SmtpAddressPort,
+    #[error(
+        "failed to parse {VAR_SMTP_ALLOWED_SENDERS}, expected comma-separated `address=secret` or `@domain=secret` with secrets of at least 16 letters, digits, `-` or `_`: {0}"
+    )]
+    SmtpAllowedSenders(String),
+    #[error("{VAR_SMTP_ADDRESS_PORT} requires {VAR_SMTP_ALLOWED_SENDERS}")]
+    SmtpSenders,
+    #[error("failed to parse {VAR_SMTP_RELAY}, expected `host:port`")]
+    SmtpRelay,
+    #[error("{VAR_SMTP_ADDRESS_PORT} requires the `smtp` feature")]
+    SmtpUnavailable,
+    #[error("failed to parse {0}, expected http(s) URL: {1}")]
+    NotifyUrl(&'static str, String),
+    #[error("failed to parse {0}, expected comma-separated `created` and `quarantined`: {1}")]
+    NotifyEvents(&'static str, String),
+    #[error("{VAR_NOTIFY_MATRIX} requires {VAR_NOTIFY_MATRIX_ROOM} and {VAR_NOTIFY_MATRIX_TOKEN}")]
+    NotifyMatrix,
+    #[error("{0} requires the `notify` feature")]
+    NotifyUnavailable(&'static str),
+    #[error("failed to parse {VAR_ASSET_URL}, expected http(s) URL: {0}")]
+    AssetUrl(String),
     #[error("failed to parse {VAR_BASE_URL}: {0}")]
     BaseUrl(String),
     #[error("failed to generate key from {VAR_SIGNING_KEY}: {0}")]
     SigningKey(String),
+    #[error("failed to parse {VAR_HIGHLIGHT_TIMEOUT}: {0}")]
+    HighlightTimeout(ParseIntError),
+    #[error("failed to parse {VAR_HIGHLIGHT_THREADS}, expected a positive number: {0}")]
+    HighlightThreads(ParseIntError),
     #[error("failed to parse {VAR_HTTP_TIMEOUT}: {0}")]
     HttpTimeout(ParseIntError),
+    #[error(
+        "failed to parse {VAR_COMPRESSION_MIN_SIZE}, expected number of bytes up to 65535: {0}"
+    )]
+    CompressionMinSize(ParseIntError),
+    #[error("failed to parse {VAR_SHUTDOWN_DRAIN}, expected number of seconds: {0}")]
+    ShutdownDrain(ParseIntError),
+    #[error("failed to parse {VAR_MIN_FILL_TIME}, expected number of seconds: {0}")]
+    MinFillTime(ParseIntError),
     #[error("failed to parse {VAR_PASTE_EXPIRATIONS}: {0}")]
     ParsePasteExpiration(#[from] expiration::Error),
+    #[error("failed to parse {VAR_PRERENDER}, expected `true` or `false`: {0}")]
+    Prerender(ParseBoolError),
+    #[error("failed to parse {VAR_MINIFY}, expected `true` or `false`: {0}")]
+    Minify(ParseBoolError),
+    #[error("failed to parse {VAR_ADMIN_TOTP}, expected `true` or `false`: {0}")]
+    AdminTotp(ParseBoolError),
+    #[error("failed to parse {VAR_PRIVATE}, expected `true` or `false`: {0}")]
+    Private(ParseBoolError),
+    #[error("failed to parse {VAR_QUOTA_BYTES}, expected number of bytes: {0}")]
+    QuotaBytes(ParseIntError),
+    #[error("failed to parse {VAR_QUOTA_PASTES}, expected number of pastes: {0}")]
+    QuotaPastes(ParseIntError),
+    #[error("failed to parse {VAR_QUOTA_PASTE_BYTES}, expected number of bytes: {0}")]
+    QuotaPasteBytes(ParseIntError),
+    #[error("failed to parse {0}, expected positive number: {1}")]
+    Argon2Param(&'static str, ParseIntError),
+    #[error("invalid argon2 parameters: {0}")]
+    Argon2(String),
+    #[error("failed to parse {VAR_ANONYMOUS_DAILY_BYTES}, expected number of bytes: {0}")]
+    AnonymousDailyBytes(ParseIntError),
+    #[error("failed to parse {VAR_ANONYMOUS_DAILY_PASTES}, expected number of pastes: {0}")]
+    AnonymousDailyPastes(ParseIntError),
+    #[error("failed to parse {0}, expected addresses or networks: {1}")]
+    Networks(&'static str, String),
+    #[error("failed to parse {VAR_ANONYMOUS_ALLOWLIST}, expected addresses or networks: {0}")]
+    AnonymousAllowlist(String),
+    #[error("failed to parse {VAR_CLIENT_IP_HEADER}, expected header name: {0}")]
+    ClientIpHeader(String),
+    #[error("failed to parse {VAR_ACCOUNTS}, expected `true` or `false`: {0}")]
+    Accounts(ParseBoolError),
+    #[error("failed to parse {VAR_REGISTRATION}, expected `true` or `false`: {0}")]
+    Registration(ParseBoolError),
+    #[error("{VAR_LDAP_URL} requires {VAR_LDAP_BIND_DN} containing `{{user}}`")]
+    LdapBindDn,
+    #[error("{VAR_LDAP_URL} requires the `ldap` feature")]
+    LdapUnavailable,
+    #[error("{VAR_OIDC_ISSUER} requires {VAR_OIDC_CLIENT_ID} and {VAR_OIDC_CLIENT_SECRET}")]
+    OidcClient,
+    #[error("{VAR_OIDC_ISSUER} requires the `oidc` feature")]
+    OidcUnavailable,
+    #[error("failed to parse {VAR_OTLP_ENDPOINT}, expected http(s) URL: {0}")]
+    OtlpEndpoint(String),
+    #[error("failed to parse {VAR_OTLP_SAMPLE_RATIO}, expected number from 0 to 1")]
+    OtlpSampleRatio,
+    #[error("{VAR_OTLP_ENDPOINT} requires the `otlp` feature")]
+    OtlpUnavailable,
+    #[error("failed to parse {VAR_SENTRY_DSN}, expected `https://<key>@<host>/<project>`: {0}")]
+    SentryDsn(String),
+    #[error("{VAR_SENTRY_DSN} requires the `sentry` feature")]
+    SentryUnavailable,
+    #[error("failed to parse {VAR_POW_DIFFICULTY}, expected number of bits from 1 to 32")]
+    PowDifficulty,
+    #[error("{VAR_CLAMD} must be an absolute socket path or `host:port`")]
+    Clamd,
+    #[error("failed to parse {VAR_SECRETS}, expected `warn`, `expire` or `reject`: {0}")]
+    Secrets(String),
+    #[error("failed to parse {VAR_SECRETS_EXPIRATION}, expected positive number of seconds: {0}")]
+    SecretsExpiration(ParseIntError),
+    #[error("unknown captcha provider {0}, expected `hcaptcha` or `turnstile`")]
+    UnknownCaptcha(String),
+    #[error("{VAR_CAPTCHA} requires {VAR_CAPTCHA_SITE_KEY} and {VAR_CAPTCHA_SECRET}")]
+    CaptchaKeys,
+    #[error("{VAR_CAPTCHA} requires the `captcha` feature")]
+    CaptchaUnavailable,
+    #[error("failed to parse RUST_LOG, expected comma-separated `target=level` directives: {0}")]
+    LogFilter(String),
+    #[error("failed to parse {VAR_ACCESS_LOG}, contains non-Unicode data")]
+    AccessLog,
+    #[error("unknown access log format {0}, expected `combined` or `json`")]
+    AccessLogFormat(String),
+    #[error("unknown log format {0}, expected `text` or `json`")]
+    LogFormat(String),
     #[error("unknown theme {0}")]
     UnknownTheme(String),
+    #[error("unknown highlighter {0}, expected `syntect` or `tree-sitter`")]
+    UnknownHighlighter(String),
+    #[error("failed to parse {VAR_LANGUAGE_ALIASES}, expected `alias=language` pairs: {0}")]
+    LanguageAliases(String),
+    #[error(
+        "failed to parse {VAR_FOOTER_LINKS}, expected `label=url` pairs with http(s), mailto or absolute path: {0}"
+    )]
+    FooterLinks(String),
+    #[error("failed to parse {VAR_SHORTCUTS}, expected `action=key` pairs: {0}")]
+    Shortcuts(#[from] shortcuts::Error),
+    #[error("failed to parse {VAR_TIMEZONE}, expected `UTC` or an offset like `+02:00`: {0}")]
+    Timezone(time::error::Parse),
+    #[error("failed to parse {VAR_TAB_WIDTH}, expected number of spaces up to 16 or 0 for tabs")]
+    TabWidth,
+}
+
+/// Log filter directives of the `RUST_LOG` variable, only logging errors by default.
+pub fn log_filter() -> Result<tracing_subscriber::filter::Targets, Error> {
+    std::env::var("RUST_LOG").map_or_else(
+        |_| {
+            Ok(tracing_subscriber::filter::Targets::new()
+                .with_default(tracing_subscriber::filter::LevelFilter::ERROR))
+        },
+        |var| {
+            var.parse()
+                .map_err(|err| Error::LogFilter(format!("{err}")))
+        },
+    )
+}
+
+/// Target and format of the access log, `-` or `stdout` meaning standard output.
+pub fn access_log() -> Result<Option<(access_log::Target, access_log::Format)>, Error> {
+    let target = match std::env::var(VAR_ACCESS_LOG) {
+        Ok(target) if target == "-" || target == "stdout" => access_log::Target::Stdout,
+        Ok(path) => access_log::Target::File(PathBuf::from(path)),
+        Err(VarError::NotUnicode(_)) => return Err(Error::AccessLog),
+        Err(VarError::NotPresent) => return Ok(None),
+    };
+
+    let format = std::env::var(VAR_ACCESS_LOG_FORMAT).map_or_else(
+        |_| Ok(access_log::Format::Combined),
+        |var| match var.as_str() {
+            "combined" => Ok(access_log::Format::Combined),
+            "json" => Ok(access_log::Format::Json),
+            _ => Err(Error::AccessLogFormat(var)),
+        },
+    )?;
+
+    Ok(Some((target, format)))
+}
+
+/// Output format of log events.
+pub fn log_format() -> Result<logging::Format, Error> {
+    std::env::var(VAR_LOG_FORMAT).map_or_else(
+        |_| Ok(logging::Format::Text),
+        |var| match var.as_str() {
+            "text" => Ok(logging::Format::Text),
+            "json" => Ok(logging::Format::Json),
+            _ => Err(Error::LogFormat(var)),
+        },
+    )
 }
 
 pub fn title() -> String {
     std::env::var("WASTEBIN_TITLE").unwrap_or_else(|_| "wastebin".to_string())
 }
 
-pub fn theme() -> Result<highlight::Theme, Error> {
-    std::env::var(VAR_THEME).map_or_else(
-        |_| Ok(highlight::Theme::Ayu),
+/// Parse the default theme, which is either a built-in one or one of the `custom` themes.
+pub fn theme(custom: &[highlight::Theme]) -> Result<highlight::Theme, Error> {
+    let Ok(var) = std::env::var(VAR_THEME) else {
+        return Ok(highlight::Theme::Ayu);
+    };
+
+    highlight::Theme::builtin()
+        .into_iter()
+        .chain(custom.iter().cloned())
+        .find(|theme| theme.name() == var)
+        .ok_or(Error::UnknownTheme(var))
+}
+
+/// Parse the preferred highlighting engine.
+pub fn highlighter() -> Result<highlight::Engine, Error> {
+    std::env::var(VAR_HIGHLIGHTER).map_or_else(
+        |_| Ok(highlight::Engine::Syntect),
         |var| match var.as_str() {
-            "ayu" => Ok(highlight::Theme::Ayu),
-            "base16ocean" => Ok(highlight::Theme::Base16Ocean),
-            "coldark" => Ok(highlight::Theme::Coldark),
-            "gruvbox" => Ok(highlight::Theme::Gruvbox),
-            "monokai" => Ok(highlight::Theme::Monokai),
-            "onehalf" => Ok(highlight::Theme::Onehalf),
-            "solarized" => Ok(highlight::Theme::Solarized),
-            _ => Err(Error::UnknownTheme(var)),
+            "syntect" => Ok(highlight::Engine::Syntect),
+            "tree-sitter" => Ok(highlight::Engine::TreeSitter),
+            _ => Err(Error::UnknownHighlighter(var)),
         },
     )
 }
 
+/// Directory with additional `.tmTheme` files.
+pub fn theme_dir() -> Result<Option<PathBuf>, Error> {
+    match std::env::var(VAR_THEME_DIR) {
+        Ok(path) => Ok(Some(PathBuf::from(path))),
+        Err(VarError::NotUnicode(_)) => Err(Error::ThemeDir),
+        Err(VarError::NotPresent) => Ok(None),
+    }
+}
+
+/// Directory with additional or overriding `<tag>.ftl` locale files.
+pub fn locale_dir() -> Result<Option<PathBuf>, Error> {
+    match std::env::var(VAR_LOCALE_DIR) {
+        Ok(path) => Ok(Some(PathBuf::from(path))),
+        Err(VarError::NotUnicode(_)) => Err(Error::LocaleDir),
+        Err(VarError::NotPresent) => Ok(None),
+    }
+}
+
+/// Directory with templates overriding the embedded ones.
+pub fn template_dir() -> Result<Option<PathBuf>, Error> {
+    match std::env::var(VAR_TEMPLATE_DIR) {
+        Ok(path) => Ok(Some(PathBuf::from(path))),
+        Err(VarError::NotUnicode(_)) => Err(Error::TemplateDir),
+        Err(VarError::NotPresent) => Ok(None),
+    }
+}
+
+/// Parse `var` as an http(s) URL to reference or else the path of a file to serve.
+fn source(var: &str) -> Result<Option<Source>, String> {
+    let value = match std::env::var(var) {
+        Ok(value) => value,
+        Err(VarError::NotUnicode(_)) => return Err(String::from("contains non-Unicode data")),
+        Err(VarError::NotPresent) => return Ok(None),
+    };
+
+    match url::Url::parse(&value) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(Some(Source::Url(url))),
+        Ok(url) if url.scheme().len() > 1 => Err(format!("unsupported scheme {}", url.scheme())),
+        _ => Ok(Some(Source::File(PathBuf::from(value)))),
+    }
+}
+
+/// Stylesheet loaded after the theme's on every page.
+pub fn custom_css() -> Result<Option<Source>, Error> {
+    source(VAR_CUSTOM_CSS).map_err(Error::CustomCss)
+}
+
+/// Image shown in the header of every page.
+pub fn logo() -> Result<Option<Source>, Error> {
+    source(VAR_LOGO).map_err(Error::Logo)
+}
+
+/// Path of the image replacing the built-in favicon.
+pub fn favicon() -> Result<Option<PathBuf>, Error> {
+    match std::env::var(VAR_FAVICON) {
+        Ok(path) => Ok(Some(PathBuf::from(path))),
+        Err(VarError::NotUnicode(_)) => Err(Error::Favicon),
+        Err(VarError::NotPresent) => Ok(None),
+    }
+}
+
+/// Path of the image home screens and installed web apps show.
+pub fn touch_icon() -> Result<Option<PathBuf>, Error> {
+    match std::env::var(VAR_TOUCH_ICON) {
+        Ok(path) => Ok(Some(PathBuf::from(path))),
+        Err(VarError::NotUnicode(_)) => Err(Error::TouchIcon),
+        Err(VarError::NotPresent) => Ok(None),
+    }
+}
+
+/// Path of the file served as `/robots.txt` instead of the built-in one.
+pub fn robots_txt() -> Result<Option<PathBuf>, Error> {
+    match std::env::var(VAR_ROBOTS_TXT) {
+        Ok(path) => Ok(Some(PathBuf::from(path))),
+        Err(VarError::NotUnicode(_)) => Err(Error::RobotsTxt),
+        Err(VarError::NotPresent) => Ok(None),
+    }
+}
+
+/// Path of the file with blocklist rules for new pastes.
+pub fn blocklist() -> Result<Option<PathBuf>, Error> {
+    match std::env::var(VAR_BLOCKLIST) {
+        Ok(path) => Ok(Some(PathBuf::from(path))),
+        Err(VarError::NotUnicode(_)) => Err(Error::BlocklistPath),
+        Err(VarError::NotPresent) => Ok(None),
+    }
+}
+
+/// Path of the Markdown file with the terms of service anonymous users accept.
+pub fn terms() -> Result<Option<PathBuf>, Error> {
+    match std::env::var(VAR_TERMS) {
+        Ok(path) => Ok(Some(PathBuf::from(path))),
+        Err(VarError::NotUnicode(_)) => Err(Error::Terms),
+        Err(VarError::NotPresent) => Ok(None),
+    }
+}
+
+/// Path of the Markdown file shown as about page.
+pub fn about() -> Result<Option<PathBuf>, Error> {
+    match std::env::var(VAR_ABOUT) {
+        Ok(path) => Ok(Some(PathBuf::from(path))),
+        Err(VarError::NotUnicode(_)) => Err(Error::About),
+        Err(VarError::NotPresent) => Ok(None),
+    }
+}
+
+/// Socket of the clamd antivirus daemon scanning new pastes.
+pub fn clamd() -> Result<Option<clamav::Address>, Error> {
+    std::env::var(VAR_CLAMD)
+        .ok()
+        .map(|addr| addr.parse().map_err(|()| Error::Clamd))
+        .transpose()
+}
+
+/// Number of most viewed pastes rendered into the cache at startup.
+pub fn cache_warm() -> Result<usize, Error> {
+    std::env::var(VAR_CACHE_WARM)
+        .map_or_else(|_| Ok(0), |s| s.parse::<usize>())
+        .map_err(Error::CacheWarm)
+}
+
+/// Number of missing paste identifiers to remember and for how long, disabled with a size of 0.
+pub fn negative_cache() -> Result<Option<(NonZeroUsize, Duration)>, Error> {
+    let size = std::env::var(VAR_NEGATIVE_CACHE_SIZE)
+        .map_or_else(|_| Ok(1024), |s| s.parse::<usize>())
+        .map_err(Error::NegativeCacheSize)?;
+
+    let ttl = std::env::var(VAR_NEGATIVE_CACHE_TTL)
+        .map_or_else(|_| Ok(30), |s| s.parse::<u64>())
+        .map_err(Error::NegativeCacheTtl)?;
+
+    Ok(NonZeroUsize::new(size).map(|size| (size, Duration::from_secs(ttl))))
+}
+
 pub fn cache_size() -> Result<NonZeroUsize, Error> {
     std::env::var(VAR_CACHE_SIZE)
         .map_or_else(
@@ -70,6 +552,21 @@ pub fn cache_size() -> Result<NonZeroUsize, Error> {
         .map_err(Error::CacheSize)
 }
 
+/// Directory and maximum size in bytes of the persistent render cache.
+pub fn disk_cache() -> Result<Option<(PathBuf, u64)>, Error> {
+    let path = match std::env::var(VAR_DISK_CACHE_PATH) {
+        Ok(path) => PathBuf::from(path),
+        Err(VarError::NotUnicode(_)) => return Err(Error::DiskCachePath),
+        Err(VarError::NotPresent) => return Ok(None),
+    };
+
+    let size = std::env::var(VAR_DISK_CACHE_SIZE)
+        .map_or_else(|_| Ok(256 * 1024 * 1024), |s| s.parse::<u64>())
+        .map_err(Error::DiskCacheSize)?;
+
+    Ok(Some((path, size)))
+}
+
 pub fn database_method() -> Result<db::Open, Error> {
     match std::env::var(VAR_DATABASE_PATH) {
         Ok(path) => Ok(db::Open::Path(PathBuf::from(path))),
@@ -78,28 +575,248 @@ pub fn database_method() -> Result<db::Open, Error> {
     }
 }
 
-pub fn signing_key() -> Result<Key, Error> {
-    std::env::var(VAR_SIGNING_KEY).map_or_else(
-        |_| Ok(Key::generate()),
-        |s| Key::try_from(s.as_bytes()).map_err(|err| Error::SigningKey(err.to_string())),
-    )
+/// Directory to keep compiled custom syntaxes and themes in instead of their own directories.
+pub fn dump_dir() -> Result<Option<PathBuf>, Error> {
+    match std::env::var(VAR_DUMP_DIR) {
+        Ok(path) => Ok(Some(PathBuf::from(path))),
+        Err(VarError::NotUnicode(_)) => Err(Error::DumpDir),
+        Err(VarError::NotPresent) => Ok(None),
+    }
+}
+
+/// Directory with additional `.sublime-syntax` definitions.
+pub fn syntax_dir() -> Result<Option<PathBuf>, Error> {
+    match std::env::var(VAR_SYNTAX_DIR) {
+        Ok(path) => Ok(Some(PathBuf::from(path))),
+        Err(VarError::NotUnicode(_)) => Err(Error::SyntaxDir),
+        Err(VarError::NotPresent) => Ok(None),
+    }
+}
+
+/// Key signing cookies, followed by previous keys still accepted for verifying them.
+pub fn signing_keys() -> Result<(Key, Vec<Key>), Error> {
+    let Ok(keys) = std::env::var(VAR_SIGNING_KEY) else {
+        return Ok((Key::generate(), Vec::new()));
+    };
+
+    let mut keys = keys
+        .split(',')
+        .map(|key| Key::try_from(key.trim().as_bytes()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| Error::SigningKey(err.to_string()))?;
+
+    let key = keys.remove(0);
+
+    Ok((key, keys))
 }
 
 pub fn addr() -> Result<SocketAddr, Error> {
     std::env::var(VAR_ADDRESS_PORT)
         .as_ref()
-        .map(String::as_str)
-        .unwrap_or("0.0.0.0:8088")
+        .map_or("0.0.0.0:8088", String::as_str)
         .parse()
         .map_err(|_| Error::AddressPort)
 }
 
+/// Address of the plain TCP listener storing what clients send as pastes, if enabled.
+pub fn tcp_addr() -> Result<Option<SocketAddr>, Error> {
+    std::env::var(VAR_TCP_ADDRESS_PORT)
+        .ok()
+        .map(|addr| addr.parse().map_err(|_| Error::TcpAddressPort))
+        .transpose()
+}
+
+/// Address of the Gemini listener serving pastes read-only and the PEM files with its
+/// certificate chain and private key, if enabled.
+pub fn gemini() -> Result<Option<(SocketAddr, PathBuf, PathBuf)>, Error> {
+    let Ok(addr) = std::env::var(VAR_GEMINI_ADDRESS_PORT) else {
+        return Ok(None);
+    };
+
+    if !cfg!(feature = "gemini") {
+        return Err(Error::GeminiUnavailable);
+    }
+
+    let addr = addr.parse().map_err(|_| Error::GeminiAddressPort)?;
+    let cert = std::env::var_os(VAR_GEMINI_CERT).ok_or(Error::GeminiCertificate)?;
+    let key = std::env::var_os(VAR_GEMINI_KEY).ok_or(Error::GeminiCertificate)?;
+
+    Ok(Some((addr, PathBuf::from(cert), PathBuf::from(key))))
+}
+
+/// Address of the Gopher listener serving pastes read-only, if enabled.
+pub fn gopher_addr() -> Result<Option<SocketAddr>, Error> {
+    let Ok(addr) = std::env::var(VAR_GOPHER_ADDRESS_PORT) else {
+        return Ok(None);
+    };
+
+    if !cfg!(feature = "gopher") {
+        return Err(Error::GopherUnavailable);
+    }
+
+    addr.parse().map(Some).map_err(|_| Error::GopherAddressPort)
+}
+
+/// If the Gopher root menu lists recent public pastes. Pastes are unlisted otherwise, so this is
+/// off by default.
+pub fn gopher_listing() -> Result<bool, Error> {
+    std::env::var(VAR_GOPHER_LISTING)
+        .map_or_else(|_| Ok(false), |s| s.parse::<bool>())
+        .map_err(Error::GopherListing)
+}
+
+/// Address of the SMTP listener storing mail as pastes, the senders it accepts mail from and the
+/// `host:port` of the server to relay replies through, if enabled.
+pub fn smtp() -> Result<Option<(SocketAddr, mail::Senders, Option<String>)>, Error> {
+    let Ok(addr) = std::env::var(VAR_SMTP_ADDRESS_PORT) else {
+        return Ok(None);
+    };
+
+    if !cfg!(feature = "smtp") {
+        return Err(Error::SmtpUnavailable);
+    }
+
+    let addr = addr.parse().map_err(|_| Error::SmtpAddressPort)?;
+    let senders = std::env::var(VAR_SMTP_ALLOWED_SENDERS)
+        .map_err(|_| Error::SmtpSenders)?
+        .parse::<mail::Senders>()
+        .map_err(Error::SmtpAllowedSenders)?;
+
+    if senders == mail::Senders::default() {
+        return Err(Error::SmtpSenders);
+    }
+
+    let relay = std::env::var(VAR_SMTP_RELAY)
+        .ok()
+        .map(|relay| {
+            relay
+                .rsplit_once(':')
+                .filter(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+                .map(|_| relay.clone())
+                .ok_or(Error::SmtpRelay)
+        })
+        .transpose()?;
+
+    Ok(Some((addr, senders, relay)))
+}
+
+/// Chat services to notify of new pastes, each with the events it is notified of.
+pub fn notify() -> Result<Vec<notify::Target>, Error> {
+    let mut targets = Vec::new();
+
+    if let Some(url) = notify_url(VAR_NOTIFY_SLACK)? {
+        targets.push(notify::Target {
+            service: notify::Service::Slack(url),
+            events: notify_events(VAR_NOTIFY_SLACK_EVENTS)?,
+        });
+    }
+
+    if let Some(url) = notify_url(VAR_NOTIFY_DISCORD)? {
+        targets.push(notify::Target {
+            service: notify::Service::Discord(url),
+            events: notify_events(VAR_NOTIFY_DISCORD_EVENTS)?,
+        });
+    }
+
+    if let Some(homeserver) = notify_url(VAR_NOTIFY_MATRIX)? {
+        let room = std::env::var(VAR_NOTIFY_MATRIX_ROOM)
+            .ok()
+            .filter(|room| !room.is_empty())
+            .ok_or(Error::NotifyMatrix)?;
+        let token = std::env::var(VAR_NOTIFY_MATRIX_TOKEN)
+            .ok()
+            .filter(|token| !token.is_empty())
+            .ok_or(Error::NotifyMatrix)?;
+
+        targets.push(notify::Target {
+            service: notify::Service::Matrix {
+                homeserver,
+                room,
+                token,
+            },
+            events: notify_events(VAR_NOTIFY_MATRIX_EVENTS)?,
+        });
+    }
+
+    Ok(targets)
+}
+
+/// Parse `var` as the http(s) URL of a chat service, if set.
+fn notify_url(var: &'static str) -> Result<Option<url::Url>, Error> {
+    let Ok(url) = std::env::var(var) else {
+        return Ok(None);
+    };
+
+    if !cfg!(feature = "notify") {
+        return Err(Error::NotifyUnavailable(var));
+    }
+
+    let url = url::Url::parse(&url).map_err(|err| Error::NotifyUrl(var, err.to_string()))?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(Error::NotifyUrl(
+            var,
+            format!("unsupported scheme {}", url.scheme()),
+        ));
+    }
+
+    Ok(Some(url))
+}
+
+/// Parse `var` as the events to notify of, by default new pastes.
+fn notify_events(var: &'static str) -> Result<notify::Events, Error> {
+    std::env::var(var)
+        .map_or_else(|_| Ok(notify::Events::default()), |s| s.parse())
+        .map_err(|err| Error::NotifyEvents(var, err))
+}
+
+/// Where to expose Prometheus metrics. A separate address implies enabling them.
+pub fn metrics() -> Result<Option<metrics::Endpoint>, Error> {
+    if let Ok(addr) = std::env::var(VAR_METRICS_ADDRESS_PORT) {
+        let addr = addr.parse().map_err(|_| Error::MetricsAddressPort)?;
+        return Ok(Some(metrics::Endpoint::Separate(addr)));
+    }
+
+    let enabled = std::env::var(VAR_METRICS)
+        .map_or_else(|_| Ok(false), |s| s.parse::<bool>())
+        .map_err(Error::Metrics)?;
+
+    Ok(enabled.then_some(metrics::Endpoint::Main))
+}
+
 pub fn max_body_size() -> Result<usize, Error> {
     std::env::var(VAR_MAX_BODY_SIZE)
         .map_or_else(|_| Ok(1024 * 1024), |s| s.parse::<usize>())
         .map_err(Error::MaxBodySize)
 }
 
+/// Size in bytes above which pastes are shown as plain text unless highlighting is requested.
+pub fn max_highlight_bytes() -> Result<Option<usize>, Error> {
+    std::env::var(VAR_MAX_HIGHLIGHT_BYTES)
+        .ok()
+        .map(|s| s.parse::<usize>())
+        .transpose()
+        .map_err(Error::MaxHighlightBytes)
+}
+
+/// Base URL of a CDN or other host static assets are referenced from instead of this one.
+pub fn asset_url() -> Result<Option<url::Url>, Error> {
+    let Ok(url) = std::env::var(VAR_ASSET_URL) else {
+        return Ok(None);
+    };
+
+    let url = url::Url::parse(&url).map_err(|err| Error::AssetUrl(err.to_string()))?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(Error::AssetUrl(format!(
+            "unsupported scheme {}",
+            url.scheme()
+        )));
+    }
+
+    Ok(Some(url))
+}
+
 /// Read base URL either from the environment variable or fallback to the hostname.
 pub fn base_url() -> Result<url::Url, Error> {
     if let Some(base_url) = std::env::var(VAR_BASE_URL).map_or_else(
@@ -126,6 +843,25 @@ pub fn base_url() -> Result<url::Url, Error> {
         .map_err(|err| Error::BaseUrl(err.to_string()))
 }
 
+/// Argon2 parameters for deriving keys of encrypted pastes.
+pub fn argon2() -> Result<crypto::Params, Error> {
+    let defaults = crypto::Params::default();
+    let param = |var, default: u32| {
+        std::env::var(var)
+            .map_or(Ok(default), |s| s.parse())
+            .map_err(|err| Error::Argon2Param(var, err))
+    };
+
+    crypto::Params {
+        memory: param(VAR_ARGON2_MEMORY, defaults.memory)?,
+        iterations: param(VAR_ARGON2_ITERATIONS, defaults.iterations)?,
+        parallelism: param(VAR_ARGON2_PARALLELISM, defaults.parallelism)?,
+    }
+    .validate()
+    .map_err(Error::Argon2)
+}
+
+/// Salt of pastes encrypted before salts were generated per paste.
 pub fn password_hash_salt() -> String {
     std::env::var(VAR_PASSWORD_SALT).unwrap_or_else(|_| "somesalt".to_string())
 }
@@ -139,6 +875,389 @@ pub fn http_timeout() -> Result<Duration, Error> {
         .map_err(Error::HttpTimeout)
 }
 
+/// Time the server reports not to be ready before shutting down.
+pub fn shutdown_drain() -> Result<Duration, Error> {
+    std::env::var(VAR_SHUTDOWN_DRAIN)
+        .map_or_else(|_| Ok(0), |s| s.parse::<u64>())
+        .map(Duration::from_secs)
+        .map_err(Error::ShutdownDrain)
+}
+
+/// `Cache-Control` headers replacing the defaults of paste views, raw pastes and static assets.
+pub fn cache_control() -> Result<cache_control::Policy, Error> {
+    let header = |var| {
+        std::env::var(var)
+            .ok()
+            .map(|value| cache_control::parse(&value).map_err(|err| Error::CacheControl(var, err)))
+            .transpose()
+    };
+
+    Ok(cache_control::Policy {
+        paste: header(VAR_CACHE_CONTROL_PASTE)?,
+        raw: header(VAR_CACHE_CONTROL_RAW)?,
+        assets: header(VAR_CACHE_CONTROL_ASSETS)?,
+    })
+}
+
+/// Which responses to compress: those of at least a minimum size and, if given, with one of the
+/// comma-separated content type prefixes.
+pub fn compression() -> Result<compression::Filter, Error> {
+    let min_size = std::env::var(VAR_COMPRESSION_MIN_SIZE)
+        .map_or_else(|_| Ok(compression::DEFAULT_MIN_SIZE), |s| s.parse::<u16>())
+        .map_err(Error::CompressionMinSize)?;
+
+    let types = std::env::var(VAR_COMPRESSION_TYPES).ok().map(|types| {
+        types
+            .split(',')
+            .map(str::trim)
+            .filter(|prefix| !prefix.is_empty())
+            .map(String::from)
+            .collect()
+    });
+
+    Ok(compression::Filter::new(min_size, types))
+}
+
+/// Minimum time anonymous users take between loading and submitting the paste form.
+pub fn min_fill_time() -> Result<Option<Duration>, Error> {
+    std::env::var(VAR_MIN_FILL_TIME)
+        .ok()
+        .map(|s| s.parse::<u64>().map(Duration::from_secs))
+        .transpose()
+        .map_err(Error::MinFillTime)
+}
+
+/// If new pastes are highlighted right after upload.
+pub fn prerender() -> Result<bool, Error> {
+    std::env::var(VAR_PRERENDER)
+        .map_or_else(|_| Ok(true), |s| s.parse::<bool>())
+        .map_err(Error::Prerender)
+}
+
+/// If HTML responses are stripped of indentation and blank lines.
+pub fn minify() -> Result<bool, Error> {
+    std::env::var(VAR_MINIFY)
+        .map_or_else(|_| Ok(true), |s| s.parse::<bool>())
+        .map_err(Error::Minify)
+}
+
+/// If search engines are asked not to index any paste.
+pub fn noindex() -> Result<bool, Error> {
+    std::env::var(VAR_NOINDEX)
+        .map_or_else(|_| Ok(false), |s| s.parse::<bool>())
+        .map_err(Error::Noindex)
+}
+
+/// If language statistics are published.
+pub fn language_stats() -> Result<bool, Error> {
+    std::env::var(VAR_LANGUAGE_STATS)
+        .map_or_else(|_| Ok(false), |s| s.parse::<bool>())
+        .map_err(Error::LanguageStats)
+}
+
+/// If public pastes are listed in a sitemap.
+pub fn sitemap() -> Result<bool, Error> {
+    std::env::var(VAR_SITEMAP)
+        .map_or_else(|_| Ok(false), |s| s.parse::<bool>())
+        .map_err(Error::Sitemap)
+}
+
+/// If admin rights require two-factor authentication.
+pub fn admin_totp() -> Result<bool, Error> {
+    std::env::var(VAR_ADMIN_TOTP)
+        .map_or_else(|_| Ok(false), |s| s.parse::<bool>())
+        .map_err(Error::AdminTotp)
+}
+
+/// If only logged in users may create pastes.
+pub fn private() -> Result<bool, Error> {
+    std::env::var(VAR_PRIVATE)
+        .map_or_else(|_| Ok(false), |s| s.parse::<bool>())
+        .map_err(Error::Private)
+}
+
+/// Limits on what each account may store.
+pub fn quota() -> Result<accounts::Quota, Error> {
+    let limit = |var, err: fn(ParseIntError) -> Error| {
+        std::env::var(var)
+            .ok()
+            .map(|s| s.parse::<usize>().map_err(err))
+            .transpose()
+    };
+
+    Ok(accounts::Quota {
+        pastes: limit(VAR_QUOTA_PASTES, Error::QuotaPastes)?,
+        bytes: limit(VAR_QUOTA_BYTES, Error::QuotaBytes)?,
+        paste_bytes: limit(VAR_QUOTA_PASTE_BYTES, Error::QuotaPasteBytes)?,
+    })
+}
+
+/// Limits on what a single anonymous address may create per day.
+pub fn daily_limit() -> Result<limits::DailyLimit, Error> {
+    let limit = |var, err: fn(ParseIntError) -> Error| {
+        std::env::var(var)
+            .ok()
+            .map(|s| s.parse::<usize>().map_err(err))
+            .transpose()
+    };
+
+    let allow = networks(VAR_ANONYMOUS_ALLOWLIST).map_err(Error::AnonymousAllowlist)?;
+
+    Ok(limits::DailyLimit {
+        pastes: limit(VAR_ANONYMOUS_DAILY_PASTES, Error::AnonymousDailyPastes)?,
+        bytes: limit(VAR_ANONYMOUS_DAILY_BYTES, Error::AnonymousDailyBytes)?,
+        allow,
+    })
+}
+
+/// Comma-separated list of addresses and networks in `var`.
+fn networks(var: &str) -> Result<Vec<limits::Network>, String> {
+    std::env::var(var)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|net| !net.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Networks allowed and denied to create pastes and to send all other requests.
+pub fn access() -> Result<access::Access, Error> {
+    let rules = |allow, deny| -> Result<access::Rules, Error> {
+        Ok(access::Rules {
+            allow: networks(allow).map_err(|err| Error::Networks(allow, err))?,
+            deny: networks(deny).map_err(|err| Error::Networks(deny, err))?,
+        })
+    };
+
+    Ok(access::Access {
+        create: rules(VAR_CREATE_ALLOWLIST, VAR_CREATE_DENYLIST)?,
+        read: rules(VAR_READ_ALLOWLIST, VAR_READ_DENYLIST)?,
+    })
+}
+
+/// Header set by a reverse proxy to pass on the client address.
+pub fn client_ip_header() -> Result<Option<http::HeaderName>, Error> {
+    std::env::var(VAR_CLIENT_IP_HEADER)
+        .ok()
+        .map(|name| {
+            http::HeaderName::try_from(name.trim())
+                .map_err(|err| Error::ClientIpHeader(err.to_string()))
+        })
+        .transpose()
+}
+
+/// Whether accounts are enabled and, if so, whether new ones can be registered.
+pub fn accounts() -> Result<Option<bool>, Error> {
+    let enabled = std::env::var(VAR_ACCOUNTS)
+        .map_or_else(|_| Ok(false), |s| s.parse::<bool>())
+        .map_err(Error::Accounts)?;
+
+    if !enabled {
+        return Ok(None);
+    }
+
+    let registration = std::env::var(VAR_REGISTRATION)
+        .map_or_else(|_| Ok(true), |s| s.parse::<bool>())
+        .map_err(Error::Registration)?;
+
+    Ok(Some(registration))
+}
+
+/// LDAP directory to authenticate accounts against.
+pub fn ldap() -> Result<Option<ldap::Directory>, Error> {
+    let Ok(url) = std::env::var(VAR_LDAP_URL) else {
+        return Ok(None);
+    };
+
+    if !cfg!(feature = "ldap") {
+        return Err(Error::LdapUnavailable);
+    }
+
+    let bind_dn = std::env::var(VAR_LDAP_BIND_DN)
+        .ok()
+        .filter(|dn| dn.contains(ldap::USER_PLACEHOLDER))
+        .ok_or(Error::LdapBindDn)?;
+
+    Ok(Some(ldap::Directory::new(
+        url,
+        bind_dn,
+        std::env::var(VAR_LDAP_CREATE_FILTER).ok(),
+        std::env::var(VAR_LDAP_ADMIN_FILTER).ok(),
+    )))
+}
+
+/// Issuer URL, client id and client secret of the OIDC provider for single sign-on.
+pub fn oidc() -> Result<Option<(String, String, String)>, Error> {
+    let Ok(issuer) = std::env::var(VAR_OIDC_ISSUER) else {
+        return Ok(None);
+    };
+
+    if !cfg!(feature = "oidc") {
+        return Err(Error::OidcUnavailable);
+    }
+
+    let client_id = std::env::var(VAR_OIDC_CLIENT_ID).map_err(|_| Error::OidcClient)?;
+    let client_secret = std::env::var(VAR_OIDC_CLIENT_SECRET).map_err(|_| Error::OidcClient)?;
+
+    Ok(Some((issuer, client_id, client_secret)))
+}
+
+/// URL to export traces to via OTLP over HTTP and the ratio of sampled requests.
+pub fn otlp() -> Result<Option<(url::Url, f64)>, Error> {
+    let Ok(endpoint) = std::env::var(VAR_OTLP_ENDPOINT) else {
+        return Ok(None);
+    };
+
+    if !cfg!(feature = "otlp") {
+        return Err(Error::OtlpUnavailable);
+    }
+
+    let mut url = url::Url::parse(&endpoint).map_err(|err| Error::OtlpEndpoint(err.to_string()))?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(Error::OtlpEndpoint(format!(
+            "unsupported scheme {}",
+            url.scheme()
+        )));
+    }
+
+    url.path_segments_mut()
+        .map_err(|()| Error::OtlpEndpoint(String::from("cannot be a base")))?
+        .pop_if_empty()
+        .extend(["v1", "traces"]);
+
+    let ratio = std::env::var(VAR_OTLP_SAMPLE_RATIO)
+        .map_or(Ok(1.0), |s| s.parse::<f64>())
+        .ok()
+        .filter(|ratio| (0.0..=1.0).contains(ratio))
+        .ok_or(Error::OtlpSampleRatio)?;
+
+    Ok(Some((url, ratio)))
+}
+
+/// DSN of the Sentry project to report errors to and the environment to tag them with.
+pub fn sentry() -> Result<Option<(url::Url, Option<String>)>, Error> {
+    let Ok(dsn) = std::env::var(VAR_SENTRY_DSN) else {
+        return Ok(None);
+    };
+
+    if !cfg!(feature = "sentry") {
+        return Err(Error::SentryUnavailable);
+    }
+
+    let dsn = url::Url::parse(&dsn).map_err(|err| Error::SentryDsn(err.to_string()))?;
+
+    if dsn.username().is_empty() {
+        return Err(Error::SentryDsn(String::from("missing key")));
+    }
+
+    if dsn
+        .path_segments()
+        .and_then(Iterator::last)
+        .is_none_or(str::is_empty)
+    {
+        return Err(Error::SentryDsn(String::from("missing project")));
+    }
+
+    Ok(Some((dsn, std::env::var(VAR_SENTRY_ENVIRONMENT).ok())))
+}
+
+/// Captcha widget shown to anonymous users along with the secret key and the optional token
+/// letting anonymous API requests bypass the captcha.
+pub fn captcha() -> Result<Option<(captcha::Widget, String, Option<String>)>, Error> {
+    let Ok(provider) = std::env::var(VAR_CAPTCHA) else {
+        return Ok(None);
+    };
+
+    let provider = provider
+        .parse::<captcha::Provider>()
+        .map_err(Error::UnknownCaptcha)?;
+
+    if !cfg!(feature = "captcha") {
+        return Err(Error::CaptchaUnavailable);
+    }
+
+    let site_key = std::env::var(VAR_CAPTCHA_SITE_KEY).map_err(|_| Error::CaptchaKeys)?;
+    let secret = std::env::var(VAR_CAPTCHA_SECRET).map_err(|_| Error::CaptchaKeys)?;
+    let bypass = std::env::var(VAR_CAPTCHA_BYPASS_TOKEN)
+        .ok()
+        .filter(|token| !token.is_empty());
+
+    Ok(Some((
+        captcha::Widget { provider, site_key },
+        secret,
+        bypass,
+    )))
+}
+
+/// Number of leading zero bits anonymous users must find to create a paste.
+pub fn pow_difficulty() -> Result<Option<u8>, Error> {
+    std::env::var(VAR_POW_DIFFICULTY)
+        .ok()
+        .map(|difficulty| {
+            difficulty
+                .parse::<u8>()
+                .ok()
+                .filter(|difficulty| (1..=32).contains(difficulty))
+                .ok_or(Error::PowDifficulty)
+        })
+        .transpose()
+}
+
+/// What happens to pastes containing credentials, nothing if unset.
+pub fn secrets() -> Result<Option<secrets::Policy>, Error> {
+    let Ok(policy) = std::env::var(VAR_SECRETS) else {
+        return Ok(None);
+    };
+
+    match policy.as_str() {
+        "warn" => Ok(Some(secrets::Policy::Warn)),
+        "reject" => Ok(Some(secrets::Policy::Reject)),
+        "expire" => {
+            let expiration = std::env::var(VAR_SECRETS_EXPIRATION)
+                .map_or_else(|_| Ok(secrets::DEFAULT_EXPIRATION), |s| s.parse())
+                .map_err(Error::SecretsExpiration)?;
+
+            Ok(Some(secrets::Policy::Expire(expiration)))
+        }
+        _ => Err(Error::Secrets(policy)),
+    }
+}
+
+/// Names of accounts with admin rights.
+pub fn admins() -> Vec<String> {
+    std::env::var(VAR_ADMINS)
+        .map(|var| {
+            var.split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(ToString::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Time a request waits for highlighting before serving plain text.
+pub fn highlight_timeout() -> Result<Duration, Error> {
+    std::env::var(VAR_HIGHLIGHT_TIMEOUT)
+        .map_or_else(
+            |_| Ok(highlight::DEFAULT_DEADLINE),
+            |s| s.parse::<u64>().map(Duration::from_secs),
+        )
+        .map_err(Error::HighlightTimeout)
+}
+
+/// Threads to highlight long pastes with in parallel, one per core if unset.
+pub fn highlight_threads() -> Result<NonZeroUsize, Error> {
+    std::env::var(VAR_HIGHLIGHT_THREADS)
+        .map_or_else(
+            |_| Ok(std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN)),
+            |s| s.parse::<NonZeroUsize>(),
+        )
+        .map_err(Error::HighlightThreads)
+}
+
 /// Parse [`expiration::ExpirationSet`] from environment or return default.
 pub fn expiration_set() -> Result<expiration::ExpirationSet, Error> {
     let set = std::env::var(VAR_PASTE_EXPIRATIONS).map_or_else(
@@ -148,3 +1267,87 @@ pub fn expiration_set() -> Result<expiration::ExpirationSet, Error> {
 
     Ok(set)
 }
+
+/// Comma-separated languages to keep syntaxes for, all if unset.
+pub fn languages() -> Option<Vec<String>> {
+    let var = std::env::var(VAR_LANGUAGES).ok()?;
+
+    Some(
+        var.split(',')
+            .map(str::trim)
+            .filter(|language| !language.is_empty())
+            .map(String::from)
+            .collect(),
+    )
+}
+
+/// Parse comma-separated `label=url` pairs of links shown at the bottom of every page.
+pub fn footer_links() -> Result<Vec<(String, String)>, Error> {
+    let Ok(var) = std::env::var(VAR_FOOTER_LINKS) else {
+        return Ok(Vec::new());
+    };
+
+    let valid = |href: &str| {
+        href.starts_with('/') && !href.starts_with("//")
+            || url::Url::parse(href)
+                .is_ok_and(|url| matches!(url.scheme(), "http" | "https" | "mailto"))
+    };
+
+    var.split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            pair.split_once('=')
+                .map(|(label, href)| (label.trim(), href.trim()))
+                .filter(|(label, href)| !label.is_empty() && valid(href))
+                .map(|(label, href)| (label.to_string(), href.to_string()))
+                .ok_or_else(|| Error::FooterLinks(pair.to_string()))
+        })
+        .collect()
+}
+
+/// Parse comma-separated `action=key` pairs rebinding keyboard shortcuts.
+pub fn shortcuts() -> Result<Shortcuts, Error> {
+    match std::env::var(VAR_SHORTCUTS) {
+        Ok(var) => Ok(var.parse()?),
+        Err(_) => Ok(Shortcuts::default()),
+    }
+}
+
+/// Time zone of timestamps shown without JavaScript, UTC by default.
+pub fn timezone() -> Result<Timezone, Error> {
+    match std::env::var(VAR_TIMEZONE) {
+        Ok(var) => var.parse().map_err(Error::Timezone),
+        Err(_) => Ok(Timezone::default()),
+    }
+}
+
+/// Number of spaces the Tab key indents by in the editor, 0 to insert tab characters.
+pub fn tab_width() -> Result<u8, Error> {
+    std::env::var(VAR_TAB_WIDTH).map_or(Ok(page::DEFAULT_TAB_WIDTH), |width| {
+        width
+            .parse::<u8>()
+            .ok()
+            .filter(|width| *width <= 16)
+            .ok_or(Error::TabWidth)
+    })
+}
+
+/// Parse comma-separated `alias=language` pairs mapping custom extensions to known syntaxes.
+pub fn language_aliases() -> Result<Vec<(String, String)>, Error> {
+    let Ok(var) = std::env::var(VAR_LANGUAGE_ALIASES) else {
+        return Ok(Vec::new());
+    };
+
+    var.split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            pair.split_once('=')
+                .map(|(alias, language)| (alias.trim(), language.trim()))
+                .filter(|(alias, language)| !alias.is_empty() && !language.is_empty())
+                .map(|(alias, language)| (alias.to_string(), language.to_string()))
+                .ok_or_else(|| Error::LanguageAliases(pair.to_string()))
+        })
+        .collect()
+}
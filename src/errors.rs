@@ -9,6 +9,8 @@ pub(crate) enum Error {
     Axum(#[from] axum::http::Error),
     #[error("not allowed to delete")]
     Delete,
+    #[error("not allowed to edit")]
+    Edit,
     #[error("compression error: {0}")]
     Compression(String),
     #[error("entry not found")]
@@ -41,6 +43,98 @@ pub(crate) enum Error {
     ChaCha20Poly1305Decrypt,
     #[error("password not given")]
     NoPassword,
+    #[error("unknown language")]
+    UnknownLanguage,
+    #[error("invalid line range")]
+    LineRange,
+    #[error("wrong user name or password")]
+    Login,
+    #[error("user name must consist of up to 32 letters, digits, `-`, `_` or `.`")]
+    UserName,
+    #[error("password must be at least 8 characters long")]
+    WeakPassword,
+    #[error("user name already taken")]
+    UserExists,
+    #[error("registration is closed")]
+    RegistrationClosed,
+    #[error("invalid or used up invite code")]
+    Invite,
+    #[error("this instance is private, log in to create pastes")]
+    LoginRequired,
+    #[error("not allowed to create pastes")]
+    CreationRestricted,
+    #[error("accept the terms of service to create pastes")]
+    Terms,
+    #[error("not logged in")]
+    NoSession,
+    #[error("wrong two-factor authentication code")]
+    SecondFactor,
+    #[error("paste exceeds the limit of {0} bytes")]
+    PasteTooLarge(usize),
+    #[error("quota of {0} pastes exceeded, delete some to create new ones")]
+    PasteQuota(usize),
+    #[error("quota of {0} bytes exceeded, delete some pastes to create new ones")]
+    ByteQuota(usize),
+    #[error("daily limit of {0} pastes reached, try again tomorrow")]
+    DailyPasteLimit(usize),
+    #[error("daily limit of {0} bytes reached, try again tomorrow")]
+    DailyByteLimit(usize),
+    #[error("too many login attempts, try again in a minute")]
+    LoginLimit,
+    #[error("too many registrations, try again in an hour")]
+    RegistrationLimit,
+    #[error("token label must consist of 1 to 64 characters")]
+    TokenLabel,
+    #[error("admin rights required")]
+    Admin,
+    #[error("invalid form token, reload the page and try again")]
+    Csrf,
+    #[error("proof of work missing or invalid, reload the page and try again")]
+    ProofOfWork,
+    #[error("access from your address is not permitted")]
+    AddressDenied,
+    #[error("capability token invalid or expired")]
+    Capability,
+    #[error("paste rejected by the content policy of this instance")]
+    Blocked,
+    #[error("paste is held for review by an administrator")]
+    Quarantined,
+    #[error("burn after reading pastes can only be viewed once in the browser")]
+    BurnAfterReading,
+    #[error("paste rejected as infected with {0}")]
+    Infected(String),
+    #[error("virus scan failed: {0}")]
+    Clamd(String),
+    #[error("paste seems to contain credentials ({0}), remove them and try again")]
+    SecretLeak(String),
+    #[error("paste rejected as spam")]
+    Spam,
+    #[error("form submitted too quickly, take at least {0} seconds to fill it in")]
+    TooFast(u64),
+    #[error("request deadline exceeded")]
+    DeadlineExceeded,
+    #[error("could not read shared content: {0}")]
+    Share(String),
+    #[error("log filter: {0}")]
+    LogFilter(#[from] crate::logging::Error),
+    #[cfg(feature = "png")]
+    #[error("could not render image: {0}")]
+    Image(String),
+    #[cfg(feature = "ldap")]
+    #[error("directory error: {0}")]
+    Ldap(#[from] ldap3::LdapError),
+    #[cfg(feature = "oidc")]
+    #[error("single sign-on failed: {0}")]
+    Oidc(String),
+    #[cfg(feature = "oidc")]
+    #[error("single sign-on expired, please try again")]
+    OidcState,
+    #[cfg(feature = "captcha")]
+    #[error("captcha not solved, please try again")]
+    Captcha,
+    #[cfg(feature = "captcha")]
+    #[error("captcha verification failed: {0}")]
+    CaptchaProvider(String),
 }
 
 #[derive(Serialize)]
@@ -53,12 +147,31 @@ pub(crate) type JsonErrorResponse = (StatusCode, Json<JsonError>);
 
 impl From<Error> for StatusCode {
     fn from(err: Error) -> Self {
-        match err {
+        let status = match err {
             Error::NotFound => StatusCode::NOT_FOUND,
             Error::IllegalCharacters
             | Error::WrongSize
             | Error::UrlParsing(_)
-            | Error::NoPassword => StatusCode::BAD_REQUEST,
+            | Error::NoPassword
+            | Error::UnknownLanguage
+            | Error::LineRange
+            | Error::UserName
+            | Error::WeakPassword
+            | Error::TokenLabel
+            | Error::Spam
+            | Error::TooFast(_)
+            | Error::LogFilter(_)
+            | Error::Share(_)
+            | Error::SecretLeak(_) => StatusCode::BAD_REQUEST,
+            Error::Login | Error::LoginRequired | Error::NoSession | Error::SecondFactor => {
+                StatusCode::UNAUTHORIZED
+            }
+            Error::PasteTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Error::UserExists => StatusCode::CONFLICT,
+            Error::DailyPasteLimit(_)
+            | Error::DailyByteLimit(_)
+            | Error::LoginLimit
+            | Error::RegistrationLimit => StatusCode::TOO_MANY_REQUESTS,
             Error::Join(_)
             | Error::QrCode(_)
             | Error::Compression(_)
@@ -70,8 +183,48 @@ impl From<Error> for StatusCode {
             | Error::Argon2(_)
             | Error::ChaCha20Poly1305Encrypt
             | Error::Axum(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            Error::Delete | Error::ChaCha20Poly1305Decrypt => StatusCode::FORBIDDEN,
+            Error::Delete
+            | Error::Edit
+            | Error::ChaCha20Poly1305Decrypt
+            | Error::RegistrationClosed
+            | Error::Invite
+            | Error::CreationRestricted
+            | Error::Terms
+            | Error::PasteQuota(_)
+            | Error::ByteQuota(_)
+            | Error::Admin
+            | Error::Csrf
+            | Error::ProofOfWork
+            | Error::AddressDenied
+            | Error::Capability
+            | Error::Blocked
+            | Error::Infected(_)
+            | Error::Quarantined
+            | Error::BurnAfterReading => StatusCode::FORBIDDEN,
+            Error::Clamd(_) => StatusCode::BAD_GATEWAY,
+            Error::DeadlineExceeded => StatusCode::REQUEST_TIMEOUT,
+            #[cfg(feature = "png")]
+            Error::Image(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            #[cfg(feature = "ldap")]
+            Error::Ldap(_) => StatusCode::BAD_GATEWAY,
+            #[cfg(feature = "oidc")]
+            Error::Oidc(_) => StatusCode::BAD_GATEWAY,
+            #[cfg(feature = "oidc")]
+            Error::OidcState => StatusCode::BAD_REQUEST,
+            #[cfg(feature = "captcha")]
+            Error::Captcha => StatusCode::FORBIDDEN,
+            #[cfg(feature = "captcha")]
+            Error::CaptchaProvider(_) => StatusCode::BAD_GATEWAY,
+        };
+
+        // Every error response passes through here, so this is where the cause of server errors
+        // is still known.
+        #[cfg(feature = "sentry")]
+        if status.is_server_error() {
+            crate::sentry::record_error(&err.to_string());
         }
+
+        status
     }
 }
 
@@ -89,7 +242,20 @@ impl From<rusqlite::Error> for Error {
     fn from(err: rusqlite::Error) -> Self {
         match err {
             rusqlite::Error::QueryReturnedNoRows => Error::NotFound,
+            // Only queries past the request deadline are interrupted.
+            rusqlite::Error::SqliteFailure(ref failure, _)
+                if failure.code == rusqlite::ErrorCode::OperationInterrupted =>
+            {
+                Error::DeadlineExceeded
+            }
             _ => Error::Sqlite(err),
         }
     }
 }
+
+#[cfg(feature = "oidc")]
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Oidc(err.to_string())
+    }
+}
@@ -53,77 +53,53 @@ impl FromStr for Expiration {
     }
 }
 
-/// Print human-readable duration in a very rough approximation.
-impl Display for Expiration {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        /// Computes `dividend` / `divisor` and returns `Some(fraction)` if > 0.
-        fn div(dividend: u64, divisor: u64) -> Option<(u64, u64)> {
-            let r = dividend / divisor;
-            (r > 0).then_some((r, dividend % divisor))
-        }
-
+/// Units of a rough human-readable duration and their length in seconds.
+const UNITS: [(&str, u64); 6] = [
+    ("year", 60 * 60 * 24 * 7 * 4 * 12),
+    ("month", 60 * 60 * 24 * 7 * 4),
+    ("week", 60 * 60 * 24 * 7),
+    ("day", 60 * 60 * 24),
+    ("hour", 60 * 60),
+    ("minute", 60),
+];
+
+impl Expiration {
+    /// Split the duration into counts of units in a very rough approximation, from years down to
+    /// seconds. Empty if the value never expires.
+    pub fn parts(&self) -> Vec<(&'static str, u64)> {
         let mut secs = self.duration.as_secs();
+        let mut parts = Vec::new();
 
-        if secs == 0 {
-            return write!(f, "never");
-        }
-
-        if let Some((years, rem)) = div(secs, 60 * 60 * 24 * 7 * 4 * 12) {
-            if years > 1 {
-                write!(f, "{years} years")?;
-            } else {
-                write!(f, "{years} year")?;
+        for (unit, length) in UNITS {
+            if secs >= length {
+                parts.push((unit, secs / length));
+                secs %= length;
             }
-            secs = rem;
         }
 
-        if let Some((months, rem)) = div(secs, 60 * 60 * 24 * 7 * 4) {
-            if months > 1 {
-                write!(f, "{months} months")?;
-            } else {
-                write!(f, "{months} month")?;
-            }
-            secs = rem;
+        if secs > 0 {
+            parts.push(("second", secs));
         }
 
-        if let Some((weeks, rem)) = div(secs, 60 * 60 * 24 * 7) {
-            if weeks > 1 {
-                write!(f, "{weeks} weeks")?;
-            } else {
-                write!(f, "{weeks} week")?;
-            }
-            secs = rem;
-        }
+        parts
+    }
+}
 
-        if let Some((days, rem)) = div(secs, 60 * 60 * 24) {
-            if days > 1 {
-                write!(f, "{days} days")?;
-            } else {
-                write!(f, "{days} day")?;
-            }
-            secs = rem;
-        }
+/// Print human-readable duration in a very rough approximation.
+impl Display for Expiration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts = self.parts();
 
-        if let Some((hours, rem)) = div(secs, 60 * 60) {
-            if hours > 1 {
-                write!(f, "{hours} hours")?;
-            } else {
-                write!(f, "{hours} hour")?;
-            }
-            secs = rem;
+        if parts.is_empty() {
+            return write!(f, "never");
         }
 
-        if let Some((minutes, rem)) = div(secs, 60) {
-            if minutes > 1 {
-                write!(f, "{minutes} minutes")?;
+        for (unit, count) in parts {
+            if count > 1 || unit == "second" {
+                write!(f, "{count} {unit}s")?;
             } else {
-                write!(f, "{minutes} minute")?;
+                write!(f, "{count} {unit}")?;
             }
-            secs = rem;
-        }
-
-        if secs > 0 {
-            write!(f, "{secs} seconds")?;
         }
 
         Ok(())
@@ -149,6 +125,25 @@ impl FromStr for ExpirationSet {
     }
 }
 
+/// Format `secs` left until expiration compactly with the two largest units, e.g. `2h 13m`. The
+/// paste page script formats the live countdown the same way.
+pub fn countdown(secs: u64) -> String {
+    let units = [("d", 60 * 60 * 24), ("h", 60 * 60), ("m", 60), ("s", 1)];
+
+    let parts = units
+        .iter()
+        .skip_while(|(_, length)| secs < *length && *length > 1)
+        .take(2)
+        .scan(secs, |left, (unit, length)| {
+            let count = *left / length;
+            *left %= length;
+            Some(format!("{count}{unit}"))
+        })
+        .collect::<Vec<_>>();
+
+    parts.join(" ")
+}
+
 impl ExpirationSet {
     /// Retrieve sorted vector of [`Expiration`] values.
     pub fn into_inner(self) -> Vec<Expiration> {
@@ -201,6 +196,15 @@ mod tests {
         assert!(!expirations[2].default);
     }
 
+    #[test]
+    fn countdown() {
+        assert_eq!(super::countdown(0), "0s");
+        assert_eq!(super::countdown(42), "42s");
+        assert_eq!(super::countdown(60 * 5 + 3), "5m 3s");
+        assert_eq!(super::countdown(60 * 60 * 2 + 60 * 13 + 59), "2h 13m");
+        assert_eq!(super::countdown(60 * 60 * 24 * 3 + 60), "3d 0h");
+    }
+
     #[test]
     fn multiple_defaults() {
         assert!("3600=d,60=d,48000".parse::<ExpirationSet>().is_err());
@@ -0,0 +1,802 @@
+use crate::accounts::{self, MAX_ATTEMPTS, Quota, SESSION_COOKIE, SESSION_DAYS};
+use crate::db::read::{Listing, Usage};
+use crate::errors::JsonErrorResponse;
+use crate::handlers::extract::{ClientIp, CsrfToken, Session, Theme};
+use crate::handlers::html::{ErrorResponse, make_error};
+use crate::handlers::is_https;
+use crate::limits::Limiter;
+use crate::{Database, Error, Page, totp};
+use askama::Template;
+use axum::Json;
+use axum::extract::{Form, Query, State};
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Redirect, Response};
+use axum_extra::extract::cookie::{Cookie, SameSite, SignedCookieJar};
+use serde::{Deserialize, Serialize};
+
+/// Login form.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Credentials {
+    pub name: String,
+    pub password: String,
+}
+
+/// Registration form, which needs an invite code while registration is closed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Registration {
+    pub name: String,
+    pub password: String,
+    #[serde(default)]
+    pub invite: String,
+}
+
+/// TOTP or recovery code form.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct SecondFactor {
+    pub code: String,
+}
+
+/// Query parameters of the registration page.
+#[derive(Debug, Deserialize)]
+pub(crate) struct RegisterQuery {
+    /// Invite code to fill in.
+    invite: Option<String>,
+}
+
+/// Page displaying the login or registration form.
+#[derive(Template)]
+#[template(path = "login.html")]
+pub(crate) struct Login {
+    page: Page,
+    theme: Option<Theme>,
+    register: bool,
+    invite: String,
+    csrf: String,
+}
+
+/// Page asking for the second factor after logging in with a password.
+#[derive(Template)]
+#[template(path = "totp.html")]
+pub(crate) struct Totp {
+    page: Page,
+    theme: Option<Theme>,
+    csrf: String,
+}
+
+/// Usage and quota of the logged in user.
+#[derive(Serialize)]
+pub(crate) struct UsageResponse {
+    #[serde(flatten)]
+    usage: Usage,
+    quota: Quota,
+}
+
+/// Page listing the pastes of the logged in user.
+#[derive(Template)]
+#[template(path = "pastes.html")]
+pub(crate) struct MyPastes {
+    page: Page,
+    theme: Option<Theme>,
+    name: String,
+    pastes: Vec<Listing>,
+    admin: bool,
+    csrf: String,
+}
+
+/// Start a session for account `uid`. If the account has two-factor authentication enabled, the
+/// session stays pending until the second factor is given, otherwise the user enters it right
+/// away.
+pub(crate) async fn start_session(
+    db: &Database,
+    jar: SignedCookieJar,
+    headers: &HeaderMap,
+    uid: i64,
+) -> Result<(SignedCookieJar, Redirect), Error> {
+    let token = accounts::new_token();
+    let pending = db.totp(uid).await?.is_some();
+    db.create_session(accounts::token_hash(&token), uid, pending)
+        .await?;
+
+    let cookie = Cookie::build((SESSION_COOKIE, token))
+        .path("/")
+        .http_only(true)
+        .secure(is_https(headers))
+        .same_site(SameSite::Strict)
+        .max_age(time::Duration::days(SESSION_DAYS))
+        .build();
+
+    let jar = jar.add(cookie);
+
+    if pending {
+        return Ok((jar, Redirect::to("/login/totp")));
+    }
+
+    enter_session(db, jar, uid).await
+}
+
+/// Move pastes created with the anonymous `uid` cookie over to account `uid` and redirect to the
+/// list of pastes.
+async fn enter_session(
+    db: &Database,
+    jar: SignedCookieJar,
+    uid: i64,
+) -> Result<(SignedCookieJar, Redirect), Error> {
+    let mut jar = jar;
+
+    if let Some(anonymous) = jar
+        .get("uid")
+        .and_then(|cookie| cookie.value_trimmed().parse::<i64>().ok())
+    {
+        db.transfer(anonymous, uid).await?;
+        jar = jar.remove(Cookie::from("uid"));
+    }
+
+    Ok((jar, Redirect::to("/pastes")))
+}
+
+/// Check `input` against the TOTP `secret` of account `uid` or else its recovery codes. Each code
+/// is accepted only once.
+pub(crate) async fn verify_code(
+    db: &Database,
+    uid: i64,
+    secret: &[u8],
+    input: &str,
+) -> Result<bool, Error> {
+    if let Some(step) = totp::verify(secret, input, totp::now()) {
+        return db.use_totp_step(uid, step).await;
+    }
+
+    let code = input.trim().to_ascii_lowercase();
+    db.use_recovery_code(uid, accounts::token_hash(&code)).await
+}
+
+/// GET handler for the login page.
+pub async fn login_form(
+    State(page): State<Page>,
+    theme: Option<Theme>,
+    CsrfToken(csrf): CsrfToken,
+) -> Login {
+    Login {
+        page,
+        theme,
+        register: false,
+        invite: String::new(),
+        csrf,
+    }
+}
+
+/// GET handler for the registration page.
+pub async fn register_form(
+    State(page): State<Page>,
+    theme: Option<Theme>,
+    CsrfToken(csrf): CsrfToken,
+    Query(query): Query<RegisterQuery>,
+) -> Result<Login, ErrorResponse> {
+    if !page.registration && !page.invites {
+        return Err(make_error(Error::RegistrationClosed, page, theme));
+    }
+
+    Ok(Login {
+        page,
+        theme,
+        register: true,
+        invite: query.invite.unwrap_or_default(),
+        csrf,
+    })
+}
+
+/// Authenticate `credentials` against `directory` and return the uid of the linked account,
+/// which is created on first login. Roles are updated from the directory on each login.
+#[cfg(feature = "ldap")]
+async fn directory_login(
+    db: &Database,
+    directory: &crate::ldap::Directory,
+    credentials: Credentials,
+) -> Result<i64, Error> {
+    let (dn, roles) = directory
+        .authenticate(&credentials.name, &credentials.password)
+        .await?
+        .ok_or(Error::Login)?;
+
+    let issuer = directory.url().to_string();
+
+    let uid = if let Some(uid) = db.identity(issuer.clone(), dn.clone()).await? {
+        uid
+    } else {
+        let uid = db.next_uid().await?;
+        db.create_identity(uid, accounts::names(&credentials.name), issuer, dn)
+            .await?;
+        uid
+    };
+
+    db.set_roles(uid, roles).await?;
+
+    Ok(uid)
+}
+
+/// POST handler to log into an existing account, either local or from the directory.
+#[expect(clippy::too_many_arguments)]
+pub async fn login(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    State(limiter): State<Limiter>,
+    #[cfg(feature = "ldap")] State(directory): State<Option<crate::Ldap>>,
+    ClientIp(ip): ClientIp,
+    jar: SignedCookieJar,
+    headers: HeaderMap,
+    theme: Option<Theme>,
+    Form(credentials): Form<Credentials>,
+) -> Result<(SignedCookieJar, Redirect), ErrorResponse> {
+    async {
+        limiter.check_login(ip, Some(&credentials.name))?;
+
+        #[cfg(feature = "ldap")]
+        if let Some(directory) = directory {
+            let uid = directory_login(&db, &directory, credentials).await?;
+            return start_session(&db, jar, &headers, uid).await;
+        }
+
+        let user = db.user(credentials.name).await?;
+
+        // Accounts created via single sign-on have no password and are treated like unknown ones.
+        let hash = user
+            .as_ref()
+            .map(|(_, hash)| hash.clone())
+            .filter(|hash| !hash.is_empty());
+
+        if !accounts::verify_or_waste(hash, credentials.password).await? {
+            return Err(Error::Login);
+        }
+
+        let (uid, _) = user.ok_or(Error::Login)?;
+        start_session(&db, jar, &headers, uid).await
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// GET handler for the page asking for the second factor.
+pub async fn totp_form(
+    State(page): State<Page>,
+    theme: Option<Theme>,
+    CsrfToken(csrf): CsrfToken,
+) -> Totp {
+    Totp { page, theme, csrf }
+}
+
+/// POST handler to complete a pending login with a TOTP or recovery code. Too many wrong codes
+/// cancel the login.
+pub async fn totp(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    State(limiter): State<Limiter>,
+    ClientIp(ip): ClientIp,
+    jar: SignedCookieJar,
+    theme: Option<Theme>,
+    Form(form): Form<SecondFactor>,
+) -> Result<(SignedCookieJar, Redirect), ErrorResponse> {
+    async {
+        limiter.check_login(ip, None)?;
+
+        let token = jar
+            .get(SESSION_COOKIE)
+            .map(|cookie| accounts::token_hash(cookie.value_trimmed()))
+            .ok_or(Error::NoSession)?;
+
+        let (uid, secret) = db
+            .pending_session(token.clone())
+            .await?
+            .ok_or(Error::NoSession)?;
+
+        if !verify_code(&db, uid, &secret, &form.code).await? {
+            if db.fail_session(token.clone()).await? >= MAX_ATTEMPTS {
+                db.delete_session(token).await?;
+            }
+
+            return Err(Error::SecondFactor);
+        }
+
+        db.confirm_session(token).await?;
+        enter_session(&db, jar, uid).await
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// POST handler to create a new account and log into it.
+#[expect(clippy::too_many_arguments)]
+pub async fn register(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    State(limiter): State<Limiter>,
+    ClientIp(ip): ClientIp,
+    jar: SignedCookieJar,
+    headers: HeaderMap,
+    theme: Option<Theme>,
+    Form(registration): Form<Registration>,
+) -> Result<(SignedCookieJar, Redirect), ErrorResponse> {
+    async {
+        let invited = !page.registration && page.invites && !registration.invite.is_empty();
+
+        if !page.registration && !invited {
+            return Err(Error::RegistrationClosed);
+        }
+
+        accounts::validate(&registration.name, &registration.password)?;
+        limiter.check_registration(ip)?;
+
+        let hash = accounts::hash(registration.password).await?;
+        let uid = db.next_uid().await?;
+
+        if invited {
+            db.create_invited_user(uid, registration.name, hash, registration.invite)
+                .await?;
+        } else {
+            db.create_user(uid, registration.name, hash).await?;
+        }
+
+        start_session(&db, jar, &headers, uid).await
+    }
+    .await
+    .map_err(|err| make_error(err, page.clone(), theme))
+}
+
+/// POST handler to end the current session.
+pub async fn logout(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    jar: SignedCookieJar,
+    session: Option<Session>,
+    theme: Option<Theme>,
+) -> Result<(SignedCookieJar, Redirect), ErrorResponse> {
+    if let Some(session) = session {
+        db.delete_session(accounts::token_hash(&session.token))
+            .await
+            .map_err(|err| make_error(err, page, theme))?;
+    }
+
+    let jar = jar.remove(Cookie::build(SESSION_COOKIE).path("/"));
+
+    Ok((jar, Redirect::to("/")))
+}
+
+/// GET handler for the list of pastes owned by the logged in user.
+pub async fn pastes(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+    CsrfToken(csrf): CsrfToken,
+) -> Result<Response, ErrorResponse> {
+    let Some(Session { uid, roles, .. }) = session else {
+        return Ok(Redirect::to("/login").into_response());
+    };
+
+    async {
+        let name = db.user_name(uid).await?;
+        let pastes = db.list_for(uid).await?;
+
+        Ok(MyPastes {
+            page: page.clone(),
+            theme: theme.clone(),
+            name,
+            pastes,
+            admin: roles.admin,
+            csrf,
+        }
+        .into_response())
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// GET handler returning the usage and quota of the logged in user as JSON.
+pub async fn usage(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    session: Option<Session>,
+) -> Result<Json<UsageResponse>, JsonErrorResponse> {
+    let session = session.ok_or(Error::NoSession)?;
+
+    Ok(Json(UsageResponse {
+        usage: db.usage(session.uid).await?,
+        quota: page.quota,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{self, Client, StoreCookies, credentials};
+    use reqwest::StatusCode;
+
+    #[tokio::test]
+    async fn register_and_list_pastes() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(true)).await;
+
+        // A paste created before registering is moved to the account.
+        let data = Entry {
+            text: String::from("before"),
+            title: String::from("anonymous paste"),
+            ..Default::default()
+        };
+        let res = client.post_form().form(&data).send().await?;
+        let before = res.headers().get("location").unwrap().to_str()?.to_string();
+
+        let res = client.get("/pastes").send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(res.headers().get("location").unwrap(), "/login");
+
+        let res = client
+            .post("/register")
+            .form(&credentials("alice"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(res.headers().get("location").unwrap(), "/pastes");
+
+        let data = Entry {
+            text: String::from("after"),
+            ..Default::default()
+        };
+        let res = client.post_form().form(&data).send().await?;
+        assert!(res.headers().get("set-cookie").is_none());
+        let after = res.headers().get("location").unwrap().to_str()?.to_string();
+
+        let res = client.get("/pastes").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let content = res.text().await?;
+        assert!(content.contains("alice"));
+        assert!(content.contains("anonymous paste"));
+        assert!(content.contains(&format!("href=\"{after}\"")));
+
+        let res = client.post("/logout").send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client.get("/pastes").send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client
+            .post("/register")
+            .form(&credentials("alice"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+
+        let mut wrong = credentials("alice");
+        wrong.password = String::from("battery staple");
+        let res = client.post("/login").form(&wrong).send().await?;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let res = client
+            .post("/login")
+            .form(&credentials("alice"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        // Ownership is bound to the account and not to the browser's `uid` cookie anymore.
+        let res = client.post(&format!("/delete{before}")).send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client.get(&before).send().await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn local_times() -> Result<(), Box<dyn std::error::Error>> {
+        let page = test_helpers::page().with_timezone("+02:00".parse()?);
+        let client = Client::with_page(StoreCookies(true), page).await;
+
+        client
+            .post("/register")
+            .form(&credentials("alice"))
+            .send()
+            .await?;
+
+        let data = Entry {
+            text: String::from("timed"),
+            expires: Some(String::from("3600")),
+            ..Default::default()
+        };
+        client.post_form().form(&data).send().await?;
+
+        let content = client.get("/pastes").send().await?.text().await?;
+        assert_eq!(content.matches(r#"class="local-time""#).count(), 2);
+        assert!(content.contains(r#"<time datetime=""#));
+        assert!(content.contains(r#"Z" class="local-time">"#));
+        assert!(content.contains(" +02:00</time>"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn invalid_registration() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        let res = client
+            .post("/register")
+            .form(&credentials("no spaces"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let res = client
+            .post("/login")
+            .form(&credentials("bob"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn restricted_creation() -> Result<(), Box<dyn std::error::Error>> {
+        let page = test_helpers::page().with_restricted_creation();
+        let client = Client::with_page(StoreCookies(true), page).await;
+
+        let res = client.post_form().form(&Entry::default()).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        client
+            .post("/register")
+            .form(&credentials("alice"))
+            .send()
+            .await?;
+
+        let res = client.post_form().form(&Entry::default()).send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn private_instance() -> Result<(), Box<dyn std::error::Error>> {
+        let page = test_helpers::page().with_private();
+        let client = Client::with_page(StoreCookies(true), page).await;
+        let entry = crate::db::write::Entry {
+            text: String::from("hello"),
+            ..Default::default()
+        };
+
+        let res = client.get("/").send().await?;
+        assert!(res.text().await?.contains("log in</a> to create pastes"));
+
+        let res = client.post_form().form(&Entry::default()).send().await?;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let res = client.post_json().json(&entry).send().await?;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let message = res.json::<serde_json::Value>().await?["message"].to_string();
+        assert!(message.contains("log in"));
+
+        client
+            .post("/register")
+            .form(&credentials("alice"))
+            .send()
+            .await?;
+
+        let res = client.get("/").send().await?;
+        assert!(res.text().await?.contains("action=\"/new\""));
+
+        let res = client.post_json().json(&entry).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let path = res.json::<serde_json::Value>().await?["path"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        client.post("/logout").send().await?;
+
+        let res = client.get(&path).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn invites() -> Result<(), Box<dyn std::error::Error>> {
+        let db = Database::new(crate::db::Open::Memory)?;
+        db.create_invite(String::from("abc"), 2).await?;
+
+        let page = test_helpers::page()
+            .with_accounts(false)
+            .with_invites()
+            .with_admins(vec![String::from("root")]);
+        let client = Client::with_database(StoreCookies(true), page, db.clone()).await;
+
+        let res = client.get("/register?invite=abc").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.text().await?.contains("value=\"abc\""));
+
+        let register = |name: &str, invite: &str| Registration {
+            name: name.to_string(),
+            password: String::from("correct horse"),
+            invite: invite.to_string(),
+        };
+
+        for invite in ["", "wrong"] {
+            let res = client
+                .post("/register")
+                .form(&register("alice", invite))
+                .send()
+                .await?;
+            assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        }
+
+        let res = client
+            .post("/register")
+            .form(&register("root", "abc"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client
+            .post("/admin/invites")
+            .form(&[("uses", "1")])
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let invites = db.invites().await?;
+        assert_eq!(invites.len(), 2);
+        let code = invites
+            .iter()
+            .find(|invite| invite.code != "abc")
+            .map(|invite| invite.code.clone())
+            .unwrap_or_default();
+
+        let res = client.post("/admin/invites/delete/abc").send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        client.post("/logout").send().await?;
+
+        let res = client
+            .post("/register")
+            .form(&register("alice", "abc"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client
+            .post("/register")
+            .form(&register("alice", &code))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        client.post("/logout").send().await?;
+
+        let res = client
+            .post("/register")
+            .form(&register("bob", &code))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn quota() -> Result<(), Box<dyn std::error::Error>> {
+        let page = test_helpers::page().with_quota(Quota {
+            pastes: Some(1),
+            bytes: None,
+            paste_bytes: Some(10),
+        });
+        let client = Client::with_page(StoreCookies(true), page).await;
+        let entry = |text: &str| crate::db::write::Entry {
+            text: text.to_string(),
+            ..Default::default()
+        };
+
+        let res = client.get("/usage").send().await?;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        client
+            .post("/register")
+            .form(&credentials("alice"))
+            .send()
+            .await?;
+
+        let res = client
+            .post_json()
+            .json(&entry("too large!!"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let res = client.post_json().json(&entry("hello")).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = client.post_json().json(&entry("hello")).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let message = res.json::<serde_json::Value>().await?["message"].to_string();
+        assert!(message.contains("quota of 1 pastes exceeded"));
+
+        let usage = client
+            .get("/usage")
+            .send()
+            .await?
+            .json::<serde_json::Value>()
+            .await?;
+        assert_eq!(usage["pastes"], 1);
+        assert_eq!(usage["quota"]["pastes"], 1);
+        assert_eq!(usage["quota"]["paste_bytes"], 10);
+        assert!(usage["quota"]["bytes"].is_null());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn daily_limit() -> Result<(), Box<dyn std::error::Error>> {
+        let limit = crate::limits::DailyLimit {
+            pastes: Some(1),
+            ..Default::default()
+        };
+        let client = Client::with_daily_limit(StoreCookies(true), limit).await;
+        let entry = Entry {
+            text: String::from("spam"),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&entry).send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client.post_form().form(&entry).send().await?;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        client
+            .post("/register")
+            .form(&credentials("alice"))
+            .send()
+            .await?;
+
+        let res = client.post_form().form(&entry).send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn login_attempts() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(true)).await;
+
+        client
+            .post("/register")
+            .form(&credentials("alice"))
+            .send()
+            .await?;
+        client.post("/logout").send().await?;
+
+        // Unknown names fail like wrong passwords.
+        let res = client
+            .post("/login")
+            .form(&credentials("bob"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        // Spread over names so that only the address limit applies.
+        for n in 1..crate::limits::MAX_LOGINS {
+            let res = client
+                .post("/login")
+                .form(&credentials(&format!("bob{n}")))
+                .send()
+                .await?;
+            assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        let res = client
+            .post("/login")
+            .form(&credentials("alice"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,755 @@
+use crate::cache::Key;
+use crate::db::read::{AuditEntry, Distribution, Invite, Listing, Quarantined, Stats};
+use crate::db::write::Action;
+use crate::handlers::extract::{CsrfToken, Session, Theme};
+use crate::handlers::html::{ErrorResponse, make_error};
+use crate::{Cache, Database, Error, Page, accounts, logging};
+use askama::Template;
+use axum::Json;
+use axum::extract::{Form, Path, Query, State};
+use axum::http::HeaderMap;
+use axum::http::header::ACCEPT;
+use axum::response::{IntoResponse, Redirect, Response};
+use serde::{Deserialize, Serialize};
+
+/// Number of pastes listed per dashboard page.
+const PAGE_SIZE: usize = 50;
+
+/// Number of most recent audit log entries shown on the dashboard.
+const AUDIT_ENTRIES: usize = 20;
+
+/// Number of most recent purges shown in the statistics.
+const PURGES: usize = 20;
+
+/// Query parameters of the dashboard.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct DashboardQuery {
+    /// Search term.
+    pub q: Option<String>,
+    /// One-based page number.
+    pub page: Option<usize>,
+    /// Number of pastes removed by the last purge.
+    pub purged: Option<usize>,
+}
+
+/// Query parameters of the statistics.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct StatsQuery {
+    /// `json` to get the statistics as JSON regardless of the `Accept` header.
+    pub format: Option<String>,
+}
+
+/// Form to create an invite.
+#[derive(Debug, Deserialize)]
+pub(crate) struct NewInvite {
+    /// Number of registrations the invite allows.
+    pub uses: u32,
+}
+
+/// Form to change the log filter.
+#[derive(Debug, Deserialize)]
+pub(crate) struct LogFilter {
+    /// Comma-separated `target=level` directives.
+    pub filter: String,
+}
+
+/// Admin dashboard listing all pastes and instance statistics.
+#[derive(Template)]
+#[template(path = "admin.html")]
+pub(crate) struct Dashboard {
+    page: Page,
+    theme: Option<Theme>,
+    stats: Stats,
+    pastes: Vec<(Listing, Option<String>)>,
+    query: String,
+    prev_page: Option<String>,
+    next_page: Option<String>,
+    purged: Option<usize>,
+    invites: Vec<Invite>,
+    quarantined: Vec<Quarantined>,
+    audit_log: Vec<AuditEntry>,
+    /// Directives of the log filter, if it can be changed.
+    log_filter: Option<String>,
+    csrf: String,
+}
+
+/// Lookups of highlighted pastes in the cache.
+#[derive(Debug, Serialize)]
+pub(crate) struct CacheStats {
+    entries: usize,
+    hits: u64,
+    misses: u64,
+    /// Share of lookups served from the cache, if there were any.
+    hit_ratio: Option<f64>,
+}
+
+impl CacheStats {
+    fn new(cache: &Cache) -> Self {
+        let (hits, misses) = cache.counters();
+        let (hits, misses) = (hits.get(), misses.get());
+        #[expect(clippy::cast_precision_loss)]
+        let hit_ratio = (hits + misses > 0).then(|| hits as f64 / (hits + misses) as f64);
+
+        Self {
+            entries: cache.len(),
+            hits,
+            misses,
+            hit_ratio,
+        }
+    }
+
+    /// Hit ratio as percentage with one decimal.
+    fn hit_percentage(&self) -> Option<String> {
+        self.hit_ratio
+            .map(|ratio| format!("{:.1} %", ratio * 100.0))
+    }
+}
+
+/// Purge of expired pastes.
+#[derive(Debug, Serialize)]
+pub(crate) struct Purge {
+    time: String,
+    /// Name of the admin or `None` for the command line.
+    actor: Option<String>,
+    pastes: usize,
+}
+
+impl From<AuditEntry> for Purge {
+    fn from(entry: AuditEntry) -> Self {
+        Self {
+            time: entry.created,
+            actor: entry.actor,
+            pastes: entry
+                .target
+                .and_then(|target| target.parse().ok())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Statistics for capacity planning.
+#[derive(Debug, Serialize)]
+pub(crate) struct Report {
+    totals: Stats,
+    #[serde(flatten)]
+    distribution: Distribution,
+    cache: CacheStats,
+    /// Most recent purges first.
+    purges: Vec<Purge>,
+}
+
+/// Page showing the [`Report`].
+#[derive(Template)]
+#[template(path = "admin_stats.html")]
+pub(crate) struct StatsPage {
+    page: Page,
+    theme: Option<Theme>,
+    report: Report,
+}
+
+/// Human readable representation of `bytes`.
+#[expect(clippy::trivially_copy_pass_by_ref)]
+pub(crate) fn size(bytes: &usize) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+
+    let bytes = *bytes;
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut tenths = bytes * 10 / 1024;
+    let mut unit = 0;
+
+    while tenths >= 10240 && unit < UNITS.len() - 1 {
+        tenths /= 1024;
+        unit += 1;
+    }
+
+    format!("{}.{} {}", tenths / 10, tenths % 10, UNITS[unit])
+}
+
+/// Return `true` if statistics are asked for as JSON by `query` or the `Accept` header.
+pub(crate) fn wants_json(query: &StatsQuery, headers: &HeaderMap) -> bool {
+    query.format.as_deref() == Some("json")
+        || headers
+            .get(ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .is_some_and(|accept| accept.contains("application/json"))
+}
+
+/// Return the session if it belongs to an admin.
+fn admin(session: Option<Session>) -> Result<Session, Error> {
+    session
+        .filter(|session| session.roles.admin)
+        .ok_or(Error::Admin)
+}
+
+/// URL of dashboard page `number` for the search `query`.
+fn page_url(query: &str, number: usize) -> String {
+    let mut url = url::form_urlencoded::Serializer::new(String::new());
+
+    if !query.is_empty() {
+        url.append_pair("q", query);
+    }
+
+    url.append_pair("page", &number.to_string());
+
+    format!("/admin?{}", url.finish())
+}
+
+/// GET handler for the admin dashboard.
+pub async fn get(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    State(log_filter): State<Option<logging::Filter>>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+    CsrfToken(csrf): CsrfToken,
+    Query(query): Query<DashboardQuery>,
+) -> Result<Response, ErrorResponse> {
+    if session.is_none() {
+        return Ok(Redirect::to("/login").into_response());
+    }
+
+    async {
+        admin(session)?;
+
+        let search = query.q.filter(|q| !q.trim().is_empty());
+        let number = query.page.unwrap_or(1).max(1);
+        let offset = (number - 1) * PAGE_SIZE;
+
+        // Fetch one more to know if there is a next page.
+        let mut pastes = db
+            .list_all(search.clone(), None, offset, Some(PAGE_SIZE + 1))
+            .await?;
+        let has_next = pastes.len() > PAGE_SIZE;
+        pastes.truncate(PAGE_SIZE);
+
+        let search = search.unwrap_or_default();
+
+        Ok(Dashboard {
+            page: page.clone(),
+            theme: theme.clone(),
+            stats: db.stats().await?,
+            invites: if page.invites {
+                db.invites().await?
+            } else {
+                Vec::new()
+            },
+            quarantined: db.quarantined().await?,
+            audit_log: db.audit_log(Some(AUDIT_ENTRIES)).await?,
+            pastes,
+            prev_page: (number > 1).then(|| page_url(&search, number - 1)),
+            next_page: has_next.then(|| page_url(&search, number + 1)),
+            query: search,
+            purged: query.purged,
+            log_filter: log_filter.map(|filter| filter.current()),
+            csrf,
+        }
+        .into_response())
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// GET handler for the statistics, as JSON if asked for with `Accept: application/json` or
+/// `?format=json`.
+pub async fn stats(
+    State(db): State<Database>,
+    State(cache): State<Cache>,
+    State(page): State<Page>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+    headers: HeaderMap,
+    Query(query): Query<StatsQuery>,
+) -> Result<Response, ErrorResponse> {
+    let json = wants_json(&query, &headers);
+
+    if session.is_none() && !json {
+        return Ok(Redirect::to("/login").into_response());
+    }
+
+    async {
+        admin(session)?;
+
+        let report = Report {
+            totals: db.stats().await?,
+            distribution: db.distribution().await?,
+            cache: CacheStats::new(&cache),
+            purges: db
+                .purges(PURGES)
+                .await?
+                .into_iter()
+                .map(Purge::from)
+                .collect(),
+        };
+
+        if json {
+            return Ok(Json(report).into_response());
+        }
+
+        Ok(StatsPage {
+            page: page.clone(),
+            theme: theme.clone(),
+            report,
+        }
+        .into_response())
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// POST handler to delete any paste.
+pub async fn delete(
+    Path(id): Path<String>,
+    State(db): State<Database>,
+    State(cache): State<Cache>,
+    State(page): State<Page>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+) -> Result<Redirect, ErrorResponse> {
+    async {
+        let session = admin(session)?;
+        let key: Key = id.parse()?;
+        db.delete(key.id).await?;
+        cache.remove(key.id);
+        db.audit(Some(session.name), Action::Delete, Some(key.id.to_string()))
+            .await?;
+
+        Ok(Redirect::to("/admin"))
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// POST handler to exempt a paste from expiration.
+pub async fn pin(
+    Path(id): Path<String>,
+    State(db): State<Database>,
+    State(page): State<Page>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+) -> Result<Redirect, ErrorResponse> {
+    set_pinned(id, db, page, session, theme, true).await
+}
+
+/// POST handler to let a pinned paste expire again.
+pub async fn unpin(
+    Path(id): Path<String>,
+    State(db): State<Database>,
+    State(page): State<Page>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+) -> Result<Redirect, ErrorResponse> {
+    set_pinned(id, db, page, session, theme, false).await
+}
+
+async fn set_pinned(
+    id: String,
+    db: Database,
+    page: Page,
+    session: Option<Session>,
+    theme: Option<Theme>,
+    pinned: bool,
+) -> Result<Redirect, ErrorResponse> {
+    async {
+        let session = admin(session)?;
+        let key: Key = id.parse()?;
+        db.set_pinned(key.id, pinned).await?;
+
+        let action = if pinned { Action::Pin } else { Action::Unpin };
+        db.audit(Some(session.name), action, Some(key.id.to_string()))
+            .await?;
+
+        Ok(Redirect::to("/admin"))
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// POST handler to publish a quarantined paste.
+pub async fn release(
+    Path(id): Path<String>,
+    State(db): State<Database>,
+    State(page): State<Page>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+) -> Result<Redirect, ErrorResponse> {
+    async {
+        let session = admin(session)?;
+        let key: Key = id.parse()?;
+        db.release(key.id).await?;
+        db.audit(
+            Some(session.name),
+            Action::Release,
+            Some(key.id.to_string()),
+        )
+        .await?;
+
+        Ok(Redirect::to("/admin"))
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// POST handler to delete all expired pastes.
+pub async fn purge(
+    State(db): State<Database>,
+    State(cache): State<Cache>,
+    State(page): State<Page>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+) -> Result<Redirect, ErrorResponse> {
+    async {
+        let session = admin(session)?;
+        let ids = db.purge().await?;
+
+        for id in &ids {
+            cache.remove(*id);
+        }
+
+        db.audit(
+            Some(session.name),
+            Action::Purge,
+            Some(ids.len().to_string()),
+        )
+        .await?;
+
+        tracing::info!("purged {} expired pastes", ids.len());
+
+        Ok(Redirect::to(&format!("/admin?purged={}", ids.len())))
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// POST handler to create an invite code.
+pub async fn create_invite(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+    Form(invite): Form<NewInvite>,
+) -> Result<Redirect, ErrorResponse> {
+    async {
+        let session = admin(session)?;
+        let code = accounts::new_invite();
+        db.create_invite(code.clone(), invite.uses.max(1)).await?;
+        db.audit(Some(session.name), Action::CreateInvite, Some(code))
+            .await?;
+
+        Ok(Redirect::to("/admin#invites"))
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// POST handler to revoke an invite code.
+pub async fn delete_invite(
+    Path(code): Path<String>,
+    State(db): State<Database>,
+    State(page): State<Page>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+) -> Result<Redirect, ErrorResponse> {
+    async {
+        let session = admin(session)?;
+        db.delete_invite(code.clone()).await?;
+        db.audit(Some(session.name), Action::RevokeInvite, Some(code))
+            .await?;
+
+        Ok(Redirect::to("/admin#invites"))
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// POST handler to replace the filter of the log output until the next restart.
+pub async fn set_log_filter(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    State(log_filter): State<Option<logging::Filter>>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+    Form(form): Form<LogFilter>,
+) -> Result<Redirect, ErrorResponse> {
+    async {
+        let session = admin(session)?;
+        let filter = form.filter.trim();
+
+        log_filter.ok_or(Error::NotFound)?.set(filter)?;
+        db.audit(
+            Some(session.name),
+            Action::LogFilter,
+            Some(filter.to_string()),
+        )
+        .await?;
+
+        tracing::info!("changed log filter to {filter}");
+
+        Ok(Redirect::to("/admin#log"))
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// GET handler exporting the whole audit log as JSON, most recent entry first.
+pub async fn audit_log(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+) -> Result<Json<Vec<AuditEntry>>, ErrorResponse> {
+    async {
+        admin(session)?;
+
+        Ok(Json(db.audit_log(None).await?))
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{self, Client, StoreCookies, credentials};
+    use reqwest::StatusCode;
+
+    #[test]
+    fn human_readable_size() {
+        assert_eq!(super::size(&512), "512 B");
+        assert_eq!(super::size(&1536), "1.5 KiB");
+        assert_eq!(super::size(&(3 * 1024 * 1024)), "3.0 MiB");
+    }
+
+    #[tokio::test]
+    async fn dashboard() -> Result<(), Box<dyn std::error::Error>> {
+        let page = test_helpers::page().with_admins(vec![String::from("root")]);
+        let client = Client::with_page(StoreCookies(true), page).await;
+
+        let res = client.get("/admin").send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        client
+            .post("/register")
+            .form(&credentials("alice"))
+            .send()
+            .await?;
+
+        let data = Entry {
+            text: String::from("spam"),
+            title: String::from("buy now"),
+            ..Default::default()
+        };
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?.to_string();
+
+        let res = client.get("/admin").send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        client.post("/logout").send().await?;
+        client
+            .post("/register")
+            .form(&credentials("root"))
+            .send()
+            .await?;
+
+        let res = client.get("/admin?q=buy").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let content = res.text().await?;
+        assert!(content.contains("buy now"));
+        assert!(content.contains("alice"));
+
+        let res = client.get("/admin?q=nothing").send().await?;
+        assert!(!res.text().await?.contains("buy now"));
+
+        let res = client.post(&format!("/admin/pin{location}")).send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client.post("/admin/purge").send().await?;
+        assert_eq!(res.headers().get("location").unwrap(), "/admin?purged=0");
+
+        let res = client
+            .post(&format!("/admin/delete{location}"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client.get(&location).send().await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let log: Vec<serde_json::Value> = client.get("/admin/audit").send().await?.json().await?;
+        let actions = log
+            .iter()
+            .map(|entry| entry["action"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(actions, ["delete", "purge", "pin"]);
+        assert_eq!(log[0]["actor"], "root");
+        assert_eq!(log[0]["target"], location.trim_start_matches('/'));
+        assert_eq!(log[1]["target"], "0");
+
+        let res = client.get("/admin").send().await?;
+        assert!(res.text().await?.contains("export as JSON"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stats() -> Result<(), Box<dyn std::error::Error>> {
+        let page = test_helpers::page().with_admins(vec![String::from("root")]);
+        let client = Client::with_page(StoreCookies(true), page).await;
+
+        let res = client.get("/admin/stats?format=json").send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        client
+            .post("/register")
+            .form(&credentials("root"))
+            .send()
+            .await?;
+
+        for extension in ["rs", "rs", "py"] {
+            let data = Entry {
+                text: String::from("fn main() {}"),
+                extension: Some(extension.to_string()),
+                ..Default::default()
+            };
+            let res = client.post_form().form(&data).send().await?;
+            let location = res.headers().get("location").unwrap().to_str()?.to_string();
+            client.get(&location).send().await?;
+            client.get(&location).send().await?;
+        }
+
+        client.post("/admin/purge").send().await?;
+
+        let stats: serde_json::Value = client
+            .get("/admin/stats")
+            .header("accept", "application/json")
+            .send()
+            .await?
+            .json()
+            .await?;
+        assert_eq!(stats["totals"]["pastes"], 3);
+        assert_eq!(stats["languages"][0]["label"], "rs");
+        assert_eq!(stats["languages"][0]["pastes"], 2);
+        assert_eq!(stats["expirations"][0]["label"], "never");
+        assert!(stats["cache"]["hits"].as_u64() >= Some(1));
+        assert_eq!(stats["purges"][0]["actor"], "root");
+        assert_eq!(stats["purges"][0]["pastes"], 0);
+
+        let res = client.get("/admin/stats").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let content = res.text().await?;
+        assert!(content.contains(r#"id="languages""#));
+        assert!(content.contains("cache hits"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn log_filter() -> Result<(), Box<dyn std::error::Error>> {
+        let page = test_helpers::page().with_admins(vec![String::from("root")]);
+        let (filter, _layer) = crate::logging::Filter::new("info".parse()?);
+        let client = Client::with_log_filter(StoreCookies(true), page, filter.clone()).await;
+
+        client
+            .post("/register")
+            .form(&credentials("alice"))
+            .send()
+            .await?;
+
+        let form = [("filter", "wastebin=debug")];
+        let res = client.post("/admin/log").form(&form).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        client.post("/logout").send().await?;
+        client
+            .post("/register")
+            .form(&credentials("root"))
+            .send()
+            .await?;
+
+        let content = client.get("/admin").send().await?.text().await?;
+        assert!(content.contains(r#"id="log""#));
+
+        let res = client.post("/admin/log").form(&form).send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert!(filter.current().eq_ignore_ascii_case("wastebin=debug"));
+
+        let res = client
+            .post("/admin/log")
+            .form(&[("filter", "wastebin=loud")])
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let log: Vec<serde_json::Value> = client.get("/admin/audit").send().await?.json().await?;
+        assert_eq!(log[0]["action"], "log_filter");
+        assert_eq!(log[0]["target"], "wastebin=debug");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn quarantine() -> Result<(), Box<dyn std::error::Error>> {
+        let page = test_helpers::page().with_admins(vec![String::from("root")]);
+        let blocklist = "reject keyword wire transfer\nquarantine regex (?i)verify your account";
+        let client = Client::with_blocklist(StoreCookies(true), page, blocklist).await;
+
+        let data = Entry {
+            text: String::from("urgent wire transfer"),
+            ..Default::default()
+        };
+        let res = client.post_form().form(&data).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let json = serde_json::json!({ "text": "urgent WIRE TRANSFER" });
+        let res = client.post_json().json(&json).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let data = Entry {
+            text: String::from("please verify your account"),
+            title: String::from("phish"),
+            ..Default::default()
+        };
+        let res = client.post_form().form(&data).send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        let location = res.headers().get("location").unwrap().to_str()?.to_string();
+
+        let res = client.get(&location).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        client
+            .post("/register")
+            .form(&credentials("root"))
+            .send()
+            .await?;
+
+        let content = client.get("/admin").send().await?.text().await?;
+        assert!(content.contains(r#"id="quarantine""#));
+        assert!(content.contains("please verify your account"));
+        assert!(content.contains("regex (?i)verify your account"));
+
+        let res = client
+            .post(&format!("/admin/release{location}"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client.get(&location).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = client
+            .post(&format!("/admin/release{location}"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let content = client.get("/admin").send().await?.text().await?;
+        assert!(!content.contains(r#"id="quarantine""#));
+        assert!(content.contains("release"));
+
+        Ok(())
+    }
+}
@@ -1,20 +1,46 @@
-use crate::Database;
-use crate::errors::JsonErrorResponse;
-use crate::handlers::extract::Uid;
+use crate::capability::{self, Right};
+use crate::errors::{Error, JsonErrorResponse};
+use crate::handlers::extract::{Session, Uid};
+use crate::keys::Keys;
+use crate::{Cache, Database};
 use axum::extract::{Path, State};
+use axum::http::HeaderMap;
 
+/// Delete a paste owned by the requesting user, by anyone presenting a delete token in the
+/// [`capability::HEADER`] or by an admin.
 pub async fn delete(
     Path(id): Path<String>,
     State(db): State<Database>,
-    Uid(uid): Uid,
+    State(cache): State<Cache>,
+    State(keys): State<Keys>,
+    uid: Option<Uid>,
+    session: Option<Session>,
+    headers: HeaderMap,
 ) -> Result<(), JsonErrorResponse> {
     let id = id.parse()?;
-    db.delete_for(id, uid).await?;
+    let token = headers
+        .get(capability::HEADER)
+        .and_then(|value| value.to_str().ok());
+
+    if session.is_some_and(|session| session.roles.admin) {
+        db.delete(id).await?;
+    } else if let Some(token) = token {
+        capability::verify(&keys, id, Right::Delete, token)?;
+        db.delete(id).await?;
+    } else {
+        let Uid(uid) = uid.ok_or(Error::Delete)?;
+        db.delete_for(id, uid).await?;
+    }
+
+    cache.remove(id);
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::capability;
+    use crate::db::write;
+    use crate::handlers::insert::api::RedirectResponse;
     use crate::handlers::insert::form::Entry;
     use crate::test_helpers::{Client, StoreCookies};
     use reqwest::StatusCode;
@@ -27,9 +53,51 @@ mod tests {
         assert_eq!(res.status(), StatusCode::SEE_OTHER);
 
         let location = res.headers().get("location").unwrap().to_str()?;
-        let id = location.replace("/", "");
+        let id = location.replace('/', "");
+
+        let res = client.delete(&format!("/{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = client.get(&format!("/{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_with_token() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        let entry = write::Entry {
+            text: String::from("FooBarBaz"),
+            ..Default::default()
+        };
+        let res = client.post_json().json(&entry).send().await?;
+        let payload = res.json::<RedirectResponse>().await?;
+        let id = payload.path.replace('/', "");
 
         let res = client.delete(&format!("/{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client
+            .delete(&format!("/{id}"))
+            .header(capability::HEADER, "1.00")
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client
+            .delete(&format!("/{id}"))
+            .header(capability::HEADER, payload.edit_token.unwrap())
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client
+            .delete(&format!("/{id}"))
+            .header(capability::HEADER, payload.delete_token)
+            .send()
+            .await?;
         assert_eq!(res.status(), StatusCode::OK);
 
         let res = client.get(&format!("/{id}")).send().await?;
@@ -1,19 +1,28 @@
-use crate::handlers::extract::{Theme, Uid};
+use crate::handlers::extract::{Session, Theme, Uid};
 use crate::handlers::html::{ErrorResponse, make_error};
-use crate::{Database, Page};
+use crate::{Cache, Database, Page};
 use axum::extract::{Path, State};
 use axum::response::Redirect;
 
 pub async fn delete(
     Path(id): Path<String>,
     State(db): State<Database>,
+    State(cache): State<Cache>,
     State(page): State<Page>,
     Uid(uid): Uid,
+    session: Option<Session>,
     theme: Option<Theme>,
 ) -> Result<Redirect, ErrorResponse> {
     async {
         let id = id.parse()?;
-        db.delete_for(id, uid).await?;
+
+        if session.is_some_and(|session| session.roles.admin) {
+            db.delete(id).await?;
+        } else {
+            db.delete_for(id, uid).await?;
+        }
+
+        cache.remove(id);
         Ok(Redirect::to("/"))
     }
     .await
@@ -27,16 +36,39 @@ mod tests {
     use reqwest::StatusCode;
 
     #[tokio::test]
-    async fn delete_via_link() -> Result<(), Box<dyn std::error::Error>> {
+    async fn delete_via_form() -> Result<(), Box<dyn std::error::Error>> {
         let client = Client::new(StoreCookies(true)).await;
 
         let res = client.post_form().form(&Entry::default()).send().await?;
         assert_eq!(res.status(), StatusCode::SEE_OTHER);
 
         let location = res.headers().get("location").unwrap().to_str()?;
-        let id = location.replace("/", "");
+        let id = location.replace('/', "");
 
         let res = client.get(&format!("/delete/{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        let res = client.post_forged(&format!("/delete/{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client
+            .post_forged(&format!("/delete/{id}"))
+            .form(&[("csrf", "0123")])
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client.get(&format!("/{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let content = res.text().await?;
+        let start = content.find(r#"name="csrf" value=""#).unwrap() + 19;
+        let token = &content[start..start + 64];
+
+        let res = client
+            .post_forged(&format!("/delete/{id}"))
+            .form(&[("csrf", token)])
+            .send()
+            .await?;
         assert_eq!(res.status(), StatusCode::SEE_OTHER);
 
         let res = client.get(&format!("/{id}")).send().await?;
@@ -1,6 +1,7 @@
 use crate::cache::Key;
-use crate::db::read::{Data, Entry};
-use crate::handlers::extract::{Password, Theme};
+use crate::cache_control::NO_STORE;
+use crate::db::read::Streamed;
+use crate::handlers::extract::{CsrfToken, Password, Theme};
 use crate::handlers::html::{ErrorResponse, PasswordInput, make_error};
 use crate::{Database, Error, Page};
 use axum::extract::{Path, State};
@@ -14,21 +15,28 @@ pub async fn get(
     State(db): State<Database>,
     State(page): State<Page>,
     theme: Option<Theme>,
+    CsrfToken(csrf): CsrfToken,
     password: Option<Password>,
 ) -> Result<Response, ErrorResponse> {
     async {
-        let key: Key = id.parse()?;
+        let mut key: Key = id.parse()?;
         let password = password.map(|Password(password)| password);
 
-        match db.get(key.id, password).await {
-            Ok(Entry::Regular(data) | Entry::Burned(data)) => {
-                Ok(get_download(&key, data).into_response())
+        match db.stream(key.id, key.token.as_deref(), password).await {
+            Ok(streamed) => {
+                if !id.contains('.') {
+                    if let Some(ext) = &streamed.extension {
+                        key.ext.clone_from(ext);
+                    }
+                }
+
+                Ok(get_download(&key, streamed).into_response())
             }
-            Ok(Entry::Expired) => Err(Error::NotFound),
             Err(Error::NoPassword) => Ok(PasswordInput {
                 page: page.clone(),
                 theme: theme.clone(),
-                id: key.id.to_string(),
+                id: key.id_with_token(),
+                csrf,
             }
             .into_response()),
             Err(err) => Err(err),
@@ -38,14 +46,16 @@ pub async fn get(
     .map_err(|err| make_error(err, page, theme))
 }
 
-fn get_download(key: &Key, data: Data) -> impl IntoResponse {
-    let filename = data
-        .title
+fn get_download(key: &Key, streamed: Streamed) -> impl IntoResponse {
+    let filename = streamed
+        .filename
+        .or(streamed.title)
         .unwrap_or_else(|| format!("{}.{}", key.id(), key.ext));
 
     let content_type = "text; charset=utf-8";
+    let cache_control = streamed.burned.then_some((header::CACHE_CONTROL, NO_STORE));
     let content_disposition =
-        HeaderValue::from_str(&format!(r#"attachment; filename="{filename}"#))
+        HeaderValue::from_str(&format!(r#"attachment; filename="{filename}""#))
             .expect("constructing valid header value");
 
     (
@@ -53,7 +63,8 @@ fn get_download(key: &Key, data: Data) -> impl IntoResponse {
             (header::CONTENT_TYPE, HeaderValue::from_static(content_type)),
             (header::CONTENT_DISPOSITION, content_disposition),
         ],
-        data.text,
+        cache_control.map(|header| [header]),
+        streamed.content,
     )
 }
 
@@ -83,4 +94,57 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn download_filename() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let data = Entry {
+            text: String::from("fn main() {}"),
+            title: String::from("Entry point"),
+            filename: String::from("../src/main.rs"),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+        let id = location.strip_suffix(".rs").unwrap();
+        let res = client.get(&format!("/dl{id}")).send().await?;
+        assert_eq!(
+            res.headers()["content-disposition"],
+            r#"attachment; filename="main.rs""#
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn download_large() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let text = "0123456789abcdef\n".repeat(16 * 1024);
+        let data = Entry {
+            text: text.clone(),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let location = res.headers().get("location").unwrap().to_str()?;
+        let res = client.get(&format!("/dl{location}")).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(
+            res.headers()
+                .get("content-disposition")
+                .unwrap()
+                .to_str()?
+                .starts_with("attachment")
+        );
+        assert_eq!(res.text().await?, text);
+
+        let res = client.get(&format!("/raw{location}")).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.text().await?, text);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,319 @@
+use crate::accounts::Quota;
+use crate::cache::Key;
+use crate::capability::{self, Right};
+use crate::db::write;
+use crate::handlers::extract::{CsrfToken, Theme, Uid};
+use crate::handlers::html::{ErrorResponse, make_error};
+use crate::handlers::insert::{Filters, check_blocklist, check_malware, check_secrets};
+use crate::id::Id;
+use crate::keys::Keys;
+use crate::{Cache, Database, Error, Page};
+use askama::Template;
+use axum::extract::{Form, Path, Query, State};
+use axum::response::Redirect;
+use serde::{Deserialize, Serialize};
+
+/// Form with the new content of a paste.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct EditForm {
+    pub text: String,
+    pub title: String,
+}
+
+/// Query of edit links handed out to others than the owner.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct TokenQuery {
+    pub token: Option<String>,
+}
+
+/// Page displaying a form to change text and title of a paste.
+#[derive(Template)]
+#[template(path = "edit.html")]
+pub(crate) struct Edit {
+    page: Page,
+    theme: Option<Theme>,
+    id: String,
+    text: String,
+    title: String,
+    csrf: String,
+    token: Option<String>,
+}
+
+/// Owner to restrict editing paste `id` to, or `None` if a valid edit `token` grants it to anyone.
+pub(crate) fn owner(
+    keys: &Keys,
+    id: Id,
+    token: Option<&str>,
+    uid: Option<Uid>,
+) -> Result<Option<i64>, Error> {
+    if let Some(token) = token {
+        capability::verify(keys, id, Right::Edit, token)?;
+        return Ok(None);
+    }
+
+    let Uid(uid) = uid.ok_or(Error::Edit)?;
+    Ok(Some(uid))
+}
+
+/// GET handler for the edit form of a paste owned by the requesting user or shared with an edit
+/// token.
+#[expect(clippy::too_many_arguments)]
+pub async fn get(
+    Path(id): Path<String>,
+    Query(query): Query<TokenQuery>,
+    State(db): State<Database>,
+    State(page): State<Page>,
+    State(keys): State<Keys>,
+    uid: Option<Uid>,
+    theme: Option<Theme>,
+    CsrfToken(csrf): CsrfToken,
+) -> Result<Edit, ErrorResponse> {
+    async {
+        let key: Key = id.parse()?;
+        let uid = owner(&keys, key.id, query.token.as_deref(), uid)?;
+        let data = db.get_for(key.id, uid).await?;
+
+        Ok(Edit {
+            page: page.clone(),
+            theme: theme.clone(),
+            id: key.id_with_token(),
+            text: data.text,
+            title: data.title.unwrap_or_default(),
+            csrf,
+            token: query.token,
+        })
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// POST handler to replace text and title of a paste owned by the requesting user or shared with
+/// an edit token.
+#[expect(clippy::too_many_arguments)]
+pub async fn post(
+    Path(id): Path<String>,
+    Query(query): Query<TokenQuery>,
+    State(db): State<Database>,
+    State(cache): State<Cache>,
+    State(page): State<Page>,
+    State(keys): State<Keys>,
+    State(filters): State<Filters>,
+    uid: Option<Uid>,
+    theme: Option<Theme>,
+    Form(form): Form<EditForm>,
+) -> Result<Redirect, ErrorResponse> {
+    async {
+        let key: Key = id.parse()?;
+        let uid = owner(&keys, key.id, query.token.as_deref(), uid)?;
+        let mut entry = write::Entry {
+            text: form.text,
+            title: (!form.title.is_empty()).then_some(form.title),
+            ..Default::default()
+        };
+
+        page.quota.check_size(entry.text.len())?;
+
+        if page.quota != Quota::default() {
+            if let Some(usage) = db.usage_besides(key.id).await? {
+                page.quota.check_edit(usage, entry.text.len())?;
+            }
+        }
+
+        check_blocklist(&filters.blocklist, &mut entry)?;
+        check_malware(filters.scanner.as_ref(), &entry).await?;
+        check_secrets(&page, &mut entry)?;
+
+        db.update_for(key.id, uid, entry).await?;
+        cache.remove(key.id);
+
+        Ok(Redirect::to(&format!("/{}", key.id_with_token())))
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::insert::api::RedirectResponse;
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{Client, StoreCookies, credentials};
+    use reqwest::StatusCode;
+
+    #[tokio::test]
+    async fn edit_paste() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(true)).await;
+        let data = Entry {
+            text: String::from("fn first() {}"),
+            extension: Some(String::from("rs")),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+        let id = location.trim_end_matches(".rs").to_string();
+
+        // Render once, so the edit has to invalidate the cached highlight.
+        let res = client.get(location).send().await?;
+        assert!(res.text().await?.contains("first"));
+
+        let res = client.get(&format!("/edit{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.text().await?.contains("fn first() {}"));
+
+        let form = EditForm {
+            text: String::from("fn second() {}"),
+            title: String::from("edited"),
+        };
+        let res = client
+            .post(&format!("/edit{id}"))
+            .form(&form)
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client.get(location).send().await?;
+        let content = res.text().await?;
+        assert!(content.contains("second"));
+        assert!(!content.contains("first"));
+        assert!(content.contains("edited"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn edit_with_token() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(true)).await;
+        let entry = crate::db::write::Entry {
+            text: String::from("first"),
+            ..Default::default()
+        };
+
+        let res = client.post_json().json(&entry).send().await?;
+        let payload = res.json::<RedirectResponse>().await?;
+        let id = payload.path;
+        let token = payload.edit_token.unwrap();
+
+        let res = client.get(&format!("/edit{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client
+            .get(&format!("/edit{id}?token={}", payload.delete_token))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client
+            .get(&format!("/edit{id}?token={token}"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.text().await?.contains(&format!("?token={token}")));
+
+        let form = EditForm {
+            text: String::from("second"),
+            title: String::new(),
+        };
+        let res = client
+            .post(&format!("/edit{id}?token={token}"))
+            .form(&form)
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client.get(&format!("/raw{id}")).send().await?;
+        assert_eq!(res.text().await?, "second");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn edit_within_quota() -> Result<(), Box<dyn std::error::Error>> {
+        let page = crate::test_helpers::page().with_quota(Quota {
+            pastes: None,
+            bytes: Some(100),
+            paste_bytes: None,
+        });
+        let client = Client::with_page(StoreCookies(true), page).await;
+        client
+            .post("/register")
+            .form(&credentials("alice"))
+            .send()
+            .await?;
+
+        let entry = |text: &str| crate::db::write::Entry {
+            text: text.to_string(),
+            ..Default::default()
+        };
+        client.post_json().json(&entry("other")).send().await?;
+        let res = client.post_json().json(&entry("small")).send().await?;
+        let id = res.json::<RedirectResponse>().await?.path;
+
+        // The old size of the edited paste does not count against the new one.
+        let form = |text: String| EditForm {
+            text,
+            title: String::new(),
+        };
+        let res = client
+            .post(&format!("/edit{id}"))
+            .form(&form("x".repeat(70)))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client
+            .post(&format!("/edit{id}"))
+            .form(&form("x".repeat(100)))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client.get(&format!("/raw{id}")).send().await?;
+        assert_eq!(res.text().await?, "x".repeat(70));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn edit_through_filters() -> Result<(), Box<dyn std::error::Error>> {
+        let blocklist = "reject keyword wire transfer\nquarantine regex (?i)verify your account";
+        let client =
+            Client::with_blocklist(StoreCookies(true), crate::test_helpers::page(), blocklist)
+                .await;
+        let entry = crate::db::write::Entry {
+            text: String::from("harmless"),
+            ..Default::default()
+        };
+
+        let res = client.post_json().json(&entry).send().await?;
+        let payload = res.json::<RedirectResponse>().await?;
+        let id = payload.path;
+        let token = payload.edit_token.unwrap();
+
+        let form = |text: &str| EditForm {
+            text: text.to_string(),
+            title: String::new(),
+        };
+        let res = client
+            .post(&format!("/edit{id}?token={token}"))
+            .form(&form("urgent wire transfer"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client.get(&format!("/raw{id}")).send().await?;
+        assert_eq!(res.text().await?, "harmless");
+
+        let res = client
+            .post(&format!("/edit{id}?token={token}"))
+            .form(&form("Verify your account"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client.get(&format!("/raw{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        Ok(())
+    }
+}
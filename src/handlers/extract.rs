@@ -1,56 +1,120 @@
-use crate::crypto;
-use axum::extract::{Form, FromRef, FromRequest, FromRequestParts, Request};
+use crate::db::Database;
+use crate::limits::Limiter;
+use crate::{Page, accounts, crypto};
+use axum::extract::{ConnectInfo, Form, FromRef, FromRequest, FromRequestParts, Request};
+use axum::http::StatusCode;
 use axum::http::request::Parts;
 use axum_extra::extract::cookie::Key;
 use axum_extra::extract::{CookieJar, SignedCookieJar};
 use serde::Deserialize;
+use std::net::{IpAddr, SocketAddr};
 
-/// Theme extractor, extracted from the `pref` cookie.
-#[derive(Debug, Deserialize, Clone)]
-pub(crate) enum Theme {
+/// Dark or light mode preference.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Mode {
     #[serde(rename = "dark")]
     Dark,
     #[serde(rename = "light")]
     Light,
 }
 
-/// Theme preference for use in shared [`axum::extract::Query`]'s.
+/// Theme extractor, extracted from the `pref`, `theme` and `contrast` cookies. A `theme` query
+/// parameter takes precedence over the cookie.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Theme {
+    /// Mode from the `pref` cookie or `None` to follow the system setting.
+    pub mode: Option<Mode>,
+    /// Name of the selected theme or `None` for the instance default.
+    pub name: Option<String>,
+    /// Show pages with high contrast colors on the dark variant of the theme.
+    pub high_contrast: bool,
+}
+
+/// Mode switch requested via the `pref` query parameter.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Switch {
+    Dark,
+    Light,
+    /// Remove the override and follow the system setting again.
+    System,
+}
+
+/// Theme, layout and locale preference for use in shared [`axum::extract::Query`]'s.
 #[derive(Debug, Deserialize)]
 pub(crate) struct Preference {
-    pub pref: Theme,
+    pub pref: Option<Switch>,
+    pub theme: Option<String>,
+    pub locale: Option<String>,
+    pub contrast: Option<bool>,
+    pub wrap: Option<bool>,
+    pub lines: Option<bool>,
+}
+
+/// Layout extractor, extracted from the `wrap` and `lines` cookies.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Layout {
+    /// Wrap long lines instead of scrolling horizontally.
+    pub wrap: bool,
+    /// Show the line number column.
+    pub line_numbers: bool,
 }
 
 /// Password extractor.
 pub(crate) struct Password(pub crypto::Password);
 
-/// Uid cookie value extractor, extracted from the `uid` cookie.
+/// Uid extractor, the uid of the logged in account or else the value of the `uid` cookie.
 pub(crate) struct Uid(pub i64);
 
+/// Account session extractor, extracted from the `session` cookie or else an API token passed as
+/// `Authorization: Bearer` header.
+pub(crate) struct Session {
+    /// Uid of the logged in account.
+    pub uid: i64,
+    /// Name of the logged in account.
+    pub name: String,
+    /// Permissions of the logged in account.
+    pub roles: accounts::Roles,
+    /// Session token as stored in the cookie or the API token.
+    pub token: String,
+    /// Scope of the API token or `None` for a login session.
+    pub scope: Option<accounts::Scope>,
+}
+
+/// Client address extractor, the peer address or the one passed on by a reverse proxy.
+pub(crate) struct ClientIp(pub IpAddr);
+
+/// Token to embed in forms that is checked by [`crate::csrf::verify`].
+pub(crate) struct CsrfToken(pub String);
+
 /// Password header to encrypt a paste.
 pub(crate) const PASSWORD_HEADER_NAME: http::HeaderName =
     http::HeaderName::from_static("wastebin-password");
 
-impl std::fmt::Display for Theme {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Theme::Dark => f.write_str("dark"),
-            Theme::Light => f.write_str("light"),
-        }
-    }
-}
-
-impl std::str::FromStr for Theme {
+impl std::str::FromStr for Mode {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "dark" => Ok(Theme::Dark),
-            "light" => Ok(Theme::Light),
+            "dark" => Ok(Mode::Dark),
+            "light" => Ok(Mode::Light),
             _ => Err(()),
         }
     }
 }
 
+/// Return `true` if the viewer's `theme` preferences ask for high contrast colors.
+pub(crate) fn high_contrast(theme: Option<&Theme>) -> bool {
+    theme.is_some_and(|theme| theme.high_contrast)
+}
+
+/// Return the value of the `theme` query parameter in `uri`.
+pub(crate) fn theme_query(uri: &http::Uri) -> Option<String> {
+    url::form_urlencoded::parse(uri.query()?.as_bytes())
+        .find(|(key, _)| key == "theme")
+        .map(|(_, value)| value.into_owned())
+}
+
 #[axum::async_trait]
 impl<S> FromRequestParts<S> for Theme
 where
@@ -64,10 +128,131 @@ where
             .await
             .map_err(|_| ())?;
 
-        jar.get("pref")
-            .map(|cookie| cookie.value_trimmed().parse())
-            .transpose()?
-            .ok_or(())
+        let mode = jar
+            .get("pref")
+            .and_then(|cookie| cookie.value_trimmed().parse().ok());
+
+        let name = theme_query(&parts.uri).or_else(|| {
+            jar.get("theme")
+                .map(|cookie| cookie.value_trimmed().to_string())
+        });
+
+        let high_contrast = jar
+            .get("contrast")
+            .is_some_and(|cookie| cookie.value_trimmed() == "on");
+
+        if mode.is_none() && name.is_none() && !high_contrast {
+            return Err(());
+        }
+
+        Ok(Theme {
+            mode,
+            name,
+            high_contrast,
+        })
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            wrap: false,
+            line_numbers: true,
+        }
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for Layout
+where
+    S: Send + Sync,
+{
+    // Not extracting the layout is not an issue.
+    type Rejection = ();
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let jar = CookieJar::from_request_parts(parts, state)
+            .await
+            .map_err(|_| ())?;
+
+        Ok(Layout {
+            wrap: jar.get("wrap").is_some_and(|c| c.value_trimmed() == "on"),
+            line_numbers: jar.get("lines").is_none_or(|c| c.value_trimmed() != "off"),
+        })
+    }
+}
+
+/// Session of the API token in the `Authorization: Bearer` header. API tokens never carry admin
+/// rights.
+async fn api_session<S>(parts: &Parts, state: &S) -> Result<Session, ()>
+where
+    Database: FromRef<S>,
+{
+    let token = parts
+        .headers
+        .get(http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| token.starts_with(accounts::API_TOKEN_PREFIX))
+        .ok_or(())?;
+
+    let (uid, name, mut roles, scope) = Database::from_ref(state)
+        .api_token(accounts::token_hash(token))
+        .await
+        .map_err(|_| ())?
+        .ok_or(())?;
+
+    roles.admin = false;
+
+    Ok(Session {
+        uid,
+        name,
+        roles,
+        token: token.to_string(),
+        scope: Some(scope),
+    })
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for Session
+where
+    S: Send + Sync,
+    Key: FromRef<S>,
+    Database: FromRef<S>,
+    Page: FromRef<S>,
+{
+    type Rejection = ();
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let jar: SignedCookieJar<crate::Key> = SignedCookieJar::from_request_parts(parts, state)
+            .await
+            .map_err(|_| ())?;
+
+        let Some(token) = jar
+            .get(accounts::SESSION_COOKIE)
+            .map(|cookie| cookie.value_trimmed().to_string())
+        else {
+            return api_session(parts, state).await;
+        };
+
+        let (uid, name, mut roles, totp) = Database::from_ref(state)
+            .session(accounts::token_hash(&token))
+            .await
+            .map_err(|_| ())?
+            .ok_or(())?;
+
+        let page = Page::from_ref(state);
+        roles.admin |= page.admins.contains(&name);
+        roles.admin &= totp || !page.admin_totp;
+
+        Ok(Session {
+            uid,
+            name,
+            roles,
+            token,
+            scope: None,
+        })
     }
 }
 
@@ -76,10 +261,19 @@ impl<S> FromRequestParts<S> for Uid
 where
     S: Send + Sync,
     Key: FromRef<S>,
+    Database: FromRef<S>,
+    Page: FromRef<S>,
 {
     type Rejection = ();
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        // Tokens scoped to creating pastes do not grant access to existing ones.
+        if let Ok(Session { uid, scope, .. }) = Session::from_request_parts(parts, state).await {
+            if scope != Some(accounts::Scope::Create) {
+                return Ok(Uid(uid));
+            }
+        }
+
         let jar: SignedCookieJar<crate::Key> = SignedCookieJar::from_request_parts(parts, state)
             .await
             .map_err(|_| ())?;
@@ -96,6 +290,43 @@ where
     }
 }
 
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for CsrfToken
+where
+    S: Send + Sync,
+    Key: FromRef<S>,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let jar: SignedCookieJar<Key> = SignedCookieJar::from_request_parts(parts, state).await?;
+
+        Ok(CsrfToken(crate::csrf::token(&Key::from_ref(state), &jar)))
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+    Limiter: FromRef<S>,
+{
+    // The server always provides the peer address, so a missing one is a bug.
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let ConnectInfo(peer) = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .copied()
+            .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let ip = Limiter::from_ref(state).client(&parts.headers, peer.ip());
+
+        Ok(ClientIp(ip))
+    }
+}
+
 #[axum::async_trait]
 impl<S> FromRequest<S> for Password
 where
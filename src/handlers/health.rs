@@ -0,0 +1,133 @@
+use crate::health::Readiness;
+use crate::{Cache, Database};
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde::Serialize;
+
+/// Outcome of the readiness checks.
+#[derive(Serialize)]
+pub(crate) struct Checks {
+    ready: bool,
+    database: Check,
+    migrations: Migrations,
+    cache: CacheCheck,
+    draining: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Check {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Migrations {
+    ok: bool,
+    /// Schema version of the database, if it could be read.
+    version: Option<usize>,
+    /// Schema version this server migrates to.
+    latest: usize,
+}
+
+#[derive(Serialize)]
+pub(crate) struct CacheCheck {
+    ok: bool,
+    /// Number of highlighted pastes held in memory.
+    entries: usize,
+}
+
+/// GET handler reporting that the process is up.
+pub async fn healthz() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+/// GET handler reporting whether the server can serve requests, i.e. the database is reachable
+/// and fully migrated and no shutdown is under way.
+pub async fn readyz(
+    State(db): State<Database>,
+    State(cache): State<Cache>,
+    State(readiness): State<Readiness>,
+) -> (StatusCode, Json<Checks>) {
+    let version = db.schema_version().await;
+    let latest = db.latest_schema_version();
+
+    let database = Check {
+        ok: version.is_ok(),
+        error: version.as_ref().err().map(ToString::to_string),
+    };
+
+    let migrations = Migrations {
+        ok: version.as_ref().is_ok_and(|version| *version == latest),
+        version: version.ok(),
+        latest,
+    };
+
+    let cache = CacheCheck {
+        ok: true,
+        entries: cache.len(),
+    };
+
+    let draining = readiness.draining();
+    let ready = database.ok && migrations.ok && cache.ok && !draining;
+
+    let checks = Checks {
+        ready,
+        database,
+        migrations,
+        cache,
+        draining,
+    };
+
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(checks))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+
+    #[tokio::test]
+    async fn healthz() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        let res = client.get("/healthz").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.json::<serde_json::Value>().await?["status"], "ok");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn readyz() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        let res = client.get("/readyz").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let checks = res.json::<serde_json::Value>().await?;
+        assert_eq!(checks["ready"], true);
+        assert_eq!(checks["database"]["ok"], true);
+        assert_eq!(checks["migrations"]["ok"], true);
+        assert_eq!(
+            checks["migrations"]["version"],
+            checks["migrations"]["latest"]
+        );
+        assert_eq!(checks["draining"], false);
+
+        client.readiness().drain().await;
+
+        let res = client.get("/readyz").send().await?;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(res.json::<serde_json::Value>().await?["draining"], true);
+
+        Ok(())
+    }
+}
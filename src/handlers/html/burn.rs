@@ -48,6 +48,10 @@ impl Burn {
     fn dark_modules(&self) -> Vec<(i32, i32)> {
         dark_modules(&self.code)
     }
+
+    fn share_link(&self) -> String {
+        crate::i18n::link("share-link", &format!("/{}", self.key))
+    }
 }
 
 #[cfg(test)]
@@ -94,6 +98,11 @@ mod tests {
 
     #[tokio::test]
     async fn burn_encrypted() -> Result<(), Box<dyn std::error::Error>> {
+        #[derive(Debug, Serialize)]
+        struct Form {
+            password: String,
+        }
+
         let client = Client::new(StoreCookies(false)).await;
         let password = "asd";
         let data = Entry {
@@ -118,11 +127,6 @@ mod tests {
 
         assert_eq!(res.status(), StatusCode::OK);
 
-        #[derive(Debug, Serialize)]
-        struct Form {
-            password: String,
-        }
-
         let data = Form {
             password: password.to_string(),
         };
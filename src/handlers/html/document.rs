@@ -0,0 +1,138 @@
+use crate::handlers::extract::Theme;
+use crate::handlers::html::{ErrorResponse, make_error};
+use crate::{Error, Page};
+use askama::Template;
+use axum::extract::State;
+use std::sync::Arc;
+
+/// Page showing a document the operator wrote in Markdown.
+#[derive(Template)]
+#[template(path = "document.html")]
+pub(crate) struct Document {
+    page: Page,
+    theme: Option<Theme>,
+    html: Arc<str>,
+}
+
+/// Document page with `html`, not found unless the operator provides it.
+fn document(
+    page: Page,
+    theme: Option<Theme>,
+    html: Option<Arc<str>>,
+) -> Result<Document, ErrorResponse> {
+    match html {
+        Some(html) => Ok(Document { page, theme, html }),
+        None => Err(make_error(Error::NotFound, page, theme)),
+    }
+}
+
+/// GET handler for the terms of service anonymous users accept before creating pastes.
+pub async fn terms(
+    State(page): State<Page>,
+    theme: Option<Theme>,
+) -> Result<Document, ErrorResponse> {
+    let html = page.terms.as_ref().map(|terms| Arc::clone(&terms.html));
+    document(page, theme, html)
+}
+
+/// GET handler for the about page describing the instance.
+pub async fn about(
+    State(page): State<Page>,
+    theme: Option<Theme>,
+) -> Result<Document, ErrorResponse> {
+    let html = page.about.clone();
+    document(page, theme, html)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+
+    fn page() -> crate::page::Page {
+        crate::test_helpers::page().with_terms(crate::terms::Terms::new(
+            "# Terms\n\nNo *illegal* content. <script>",
+        ))
+    }
+
+    #[tokio::test]
+    async fn about_page() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let res = client.get("/about").send().await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let content = client.get("/").send().await?.text().await?;
+        assert!(!content.contains(r#"href="/about""#));
+
+        let page = crate::test_helpers::page()
+            .with_about("Paste with `curl --data-binary @file {base_url}/`.");
+        let client = Client::with_page(StoreCookies(false), page).await;
+        let res = client.get("/about").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let content = res.text().await?;
+        assert!(content.contains("<code>curl --data-binary @file https://localhost:8888/</code>"));
+        assert!(content.contains(r#"href="/about""#));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn terms_page() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let res = client.get("/terms").send().await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let client = Client::with_page(StoreCookies(false), page()).await;
+        let res = client.get("/terms").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let content = res.text().await?;
+        assert!(content.contains("<h1>Terms</h1>"));
+        assert!(content.contains("<em>illegal</em>"));
+        assert!(content.contains("&lt;script&gt;"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn accept_once() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::with_page(StoreCookies(true), page()).await;
+        let content = client.get("/").send().await?.text().await?;
+        assert!(content.contains(r#"name="terms" id="terms" required"#));
+
+        let mut data = Entry {
+            text: String::from("hello"),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        data.terms = Some(String::from("on"));
+        let res = client.post_form().form(&data).send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let content = client.get("/").send().await?.text().await?;
+        assert!(!content.contains(r#"name="terms""#));
+
+        data.terms = None;
+        let res = client.post_form().form(&data).send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let client = Client::with_page(StoreCookies(false), page()).await;
+        let entry = serde_json::json!({ "text": "hello" });
+        let res = client.post_json().json(&entry).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client
+            .post_json()
+            .header("wastebin-accept-terms", "yes")
+            .json(&entry)
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        Ok(())
+    }
+}
@@ -1,18 +1,187 @@
-use crate::{Highlighter, Page, handlers::extract::Theme};
+use crate::cache::Key;
+use crate::captcha::Widget;
+use crate::db::read::Entry;
+use crate::handlers::extract::{CsrfToken, Session, Theme};
+use crate::handlers::html::{ErrorResponse, make_error};
+use crate::spam::{self, FillTime};
+use crate::templates::{self, Overridable, Render};
+use crate::terms;
+use crate::{Database, Error, Highlighter, Page};
 use askama::Template;
-use axum::extract::State;
+use axum::extract::{Multipart, Query, State};
+use axum_extra::extract::cookie::SignedCookieJar;
+use minijinja::{Value, context};
+use serde::Deserialize;
+use syntect::parsing::SyntaxReference;
 
-/// GET handler for the index page.
+#[derive(Deserialize, Debug, Default)]
+pub(crate) struct IndexQuery {
+    /// Paste to fill the form with, identified like in its URL.
+    from: Option<String>,
+}
+
+/// Content of an existing paste or shared by another app the form is filled with, created anew
+/// only when submitted.
+pub(crate) struct Draft {
+    text: String,
+    extension: Option<String>,
+    title: Option<String>,
+    filename: Option<String>,
+}
+
+/// Read the paste identified by `from` to duplicate it. Password protected and browser-encrypted
+/// pastes cannot be read by the server and thus not be duplicated.
+async fn draft(db: &Database, highlighter: &Highlighter, from: &str) -> Result<Draft, Error> {
+    let key: Key = from.parse()?;
+
+    let data = match db.get(key.id, key.token.as_deref(), None).await? {
+        Entry::Regular(data) | Entry::Burned(data) => data,
+        Entry::Expired => return Err(Error::NotFound),
+    };
+
+    if data.ciphertext {
+        return Err(Error::NotFound);
+    }
+
+    Ok(Draft {
+        text: data.text,
+        // Languages are offered by their first extension.
+        extension: data
+            .extension
+            .and_then(|ext| highlighter.extension_for(&ext)),
+        title: data.title,
+        filename: None,
+    })
+}
+
+/// Read the title, text, URL and file another app shares. A shared file replaces the text, which
+/// is otherwise followed by the URL.
+async fn shared(highlighter: &Highlighter, mut multipart: Multipart) -> Result<Draft, Error> {
+    let mut title = None;
+    let mut text = None;
+    let mut url = None;
+    let mut file = None;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|err| Error::Share(err.body_text()))?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+        let filename = field.file_name().map(ToString::to_string);
+        let bytes = field
+            .bytes()
+            .await
+            .map_err(|err| Error::Share(err.body_text()))?;
+
+        let value = || {
+            String::from_utf8(bytes.to_vec())
+                .map_err(|_| Error::Share(format!("{name} is not valid UTF-8")))
+                .map(|value| Some(value).filter(|value| !value.trim().is_empty()))
+        };
+
+        match name.as_str() {
+            "title" => title = value()?,
+            "text" => text = value()?,
+            "url" => url = value()?,
+            "file" if !bytes.is_empty() => file = Some((filename, value()?)),
+            _ => {}
+        }
+    }
+
+    let (filename, text) = match file {
+        Some((filename, text)) => (filename, text.unwrap_or_default()),
+        None => (
+            None,
+            [text, url]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+    };
+
+    Ok(Draft {
+        text,
+        extension: filename.as_deref().and_then(|name| {
+            name.rsplit_once('.')
+                .and_then(|(_, ext)| highlighter.extension_for(ext))
+        }),
+        title,
+        filename,
+    })
+}
+
+/// GET handler for the index page, filled with an existing paste if requested.
+#[expect(clippy::too_many_arguments)]
 pub async fn get(
     State(page): State<Page>,
+    State(db): State<Database>,
     State(highlighter): State<Highlighter>,
+    State(fill_time): State<Option<FillTime>>,
+    session: Option<Session>,
+    jar: SignedCookieJar,
     theme: Option<Theme>,
-) -> Index {
-    Index {
+    CsrfToken(csrf): CsrfToken,
+    query: Option<Query<IndexQuery>>,
+) -> Result<Render<Index>, ErrorResponse> {
+    let query = query.map(|Query(query)| query).unwrap_or_default();
+    let locked = page.private && session.is_none();
+
+    let draft = match query.from.filter(|_| !locked) {
+        Some(from) => Some(
+            draft(&db, &highlighter, &from)
+                .await
+                .map_err(|err| make_error(err, page.clone(), theme.clone()))?,
+        ),
+        None => None,
+    };
+
+    Ok(Render(Index::new(
         page,
+        highlighter,
+        fill_time,
+        session.as_ref(),
+        &jar,
         theme,
+        csrf,
+        draft,
+    )))
+}
+
+/// POST handler of the web share target, filling the form with what another app shares. Pastes
+/// are only created once the form is submitted, so users can review and complete them.
+#[expect(clippy::too_many_arguments)]
+pub async fn share(
+    State(page): State<Page>,
+    State(highlighter): State<Highlighter>,
+    State(fill_time): State<Option<FillTime>>,
+    session: Option<Session>,
+    jar: SignedCookieJar,
+    theme: Option<Theme>,
+    CsrfToken(csrf): CsrfToken,
+    multipart: Multipart,
+) -> Result<Render<Index>, ErrorResponse> {
+    let draft = if page.private && session.is_none() {
+        None
+    } else {
+        Some(
+            shared(&highlighter, multipart)
+                .await
+                .map_err(|err| make_error(err, page.clone(), theme.clone()))?,
+        )
+    };
+
+    Ok(Render(Index::new(
+        page,
         highlighter,
-    }
+        fill_time,
+        session.as_ref(),
+        &jar,
+        theme,
+        csrf,
+        draft,
+    )))
 }
 
 /// Index page displaying a form for paste insertion and a selection box for languages.
@@ -22,4 +191,282 @@ pub(crate) struct Index {
     page: Page,
     theme: Option<Theme>,
     highlighter: Highlighter,
+    /// If the form is replaced by a hint to log in.
+    locked: bool,
+    /// Captcha to solve, only shown to anonymous users.
+    captcha: Option<Widget>,
+    /// If a proof-of-work challenge is solved before submitting the form.
+    pow: bool,
+    /// Signed time of rendering the form.
+    rendered: Option<String>,
+    /// Name of the field hidden from humans.
+    honeypot: &'static str,
+    /// Paste the form is filled with.
+    draft: Option<Draft>,
+    /// If the terms of service are to be accepted with the form.
+    terms: bool,
+    csrf: String,
+}
+
+impl Index {
+    #[expect(clippy::too_many_arguments)]
+    fn new(
+        page: Page,
+        highlighter: Highlighter,
+        fill_time: Option<FillTime>,
+        session: Option<&Session>,
+        jar: &SignedCookieJar,
+        theme: Option<Theme>,
+        csrf: String,
+        draft: Option<Draft>,
+    ) -> Self {
+        let anonymous = session.is_none();
+
+        Self {
+            terms: terms::pending(page.terms.as_ref(), session, jar),
+            locked: page.private && anonymous,
+            captcha: page.captcha.clone().filter(|_| anonymous),
+            pow: page.pow && anonymous,
+            rendered: fill_time.map(|fill_time| fill_time.stamp()),
+            honeypot: spam::HONEYPOT,
+            draft,
+            page,
+            theme,
+            highlighter,
+            csrf,
+        }
+    }
+
+    /// Return `true` if `syntax` is the language of the paste the form is filled with.
+    fn is_draft_language(&self, syntax: &SyntaxReference) -> bool {
+        self.draft
+            .as_ref()
+            .and_then(|draft| draft.extension.as_ref())
+            .is_some_and(|ext| syntax.file_extensions.first() == Some(ext))
+    }
+}
+
+impl Overridable for Index {
+    const NAME: &'static str = "index.html";
+
+    fn page(&self) -> &Page {
+        &self.page
+    }
+
+    fn context(&self) -> Value {
+        let captcha = self.captcha.as_ref().map(|captcha| {
+            context! {
+                class => captcha.provider.class(),
+                script => captcha.provider.script(),
+                site_key => captcha.site_key,
+            }
+        });
+
+        let expirations = self
+            .page
+            .expirations
+            .iter()
+            .map(|expiration| {
+                context! {
+                    seconds => expiration.duration.as_secs(),
+                    default => expiration.default,
+                    label => crate::i18n::expiration(expiration),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        context! {
+            page => templates::page(&self.page, self.theme.as_ref()),
+            csrf => self.csrf,
+            locked => self.locked,
+            pow => self.pow,
+            rendered => self.rendered,
+            honeypot => self.honeypot,
+            terms => self.terms,
+            captcha,
+            expirations,
+            languages => templates::languages(&self.highlighter),
+            draft => self.draft.as_ref().map(|draft| context! {
+                text => draft.text,
+                extension => draft.extension,
+                title => draft.title,
+                filename => draft.filename,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+    use std::fmt::Write;
+
+    #[tokio::test]
+    async fn footer_links() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let content = client.get("/").send().await?.text().await?;
+        assert!(!content.contains("<footer>"));
+
+        let page = crate::test_helpers::page().with_footer(vec![
+            (String::from("Imprint"), String::from("/imprint")),
+            (
+                String::from("Privacy & cookies"),
+                String::from("https://example.com/privacy"),
+            ),
+        ]);
+        let client = Client::with_page(StoreCookies(false), page).await;
+
+        for path in ["/", "/000000"] {
+            let content = client.get(path).send().await?.text().await?;
+            assert!(content.contains(r#"<a href="/imprint">Imprint</a>"#));
+            assert!(
+                content
+                    .contains(r#"<a href="https://example.com/privacy">Privacy &amp; cookies</a>"#)
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn upload_limit() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let content = client.get("/").send().await?.text().await?;
+        assert!(!content.contains("data-max-size"));
+
+        let page = crate::test_helpers::page().with_max_body_size(4096);
+        let client = Client::with_page(StoreCookies(false), page).await;
+        let content = client.get("/").send().await?.text().await?;
+        assert!(content.contains(r#"data-max-size="4096""#));
+        assert!(content.contains("larger than the limit of"));
+        assert!(content.contains(r#"<progress id="progress""#));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn duplicate() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let data = Entry {
+            text: String::from("\n<b>fn main() {}</b>"),
+            extension: Some(String::from("rs")),
+            title: String::from("Entry point"),
+            secret: Some(String::from("on")),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+        let id = location
+            .trim_start_matches('/')
+            .trim_end_matches(".rs")
+            .replace('+', "%2B");
+
+        let content = client.get(location).send().await?.text().await?;
+        assert!(content.contains(&format!(r#"href="/?from={id}""#)));
+
+        let content = client
+            .get(&format!("/?from={id}"))
+            .send()
+            .await?
+            .text()
+            .await?;
+        assert!(content.contains("autofocus>\n\n&lt;b&gt;fn main() {}&lt;/b&gt;</textarea>"));
+        assert!(content.contains(r#"<option value="rs" selected>Rust</option>"#));
+        assert!(content.contains(r#"value="Entry point""#));
+
+        let (bare, _) = id.split_once('_').unwrap_or((&id, ""));
+        let res = client.get(&format!("/?from={bare}")).send().await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn shortcuts() -> Result<(), Box<dyn std::error::Error>> {
+        let page = crate::test_helpers::page().with_shortcuts("editor=i".parse()?);
+        let client = Client::with_page(StoreCookies(false), page).await;
+        let content = client.get("/").send().await?.text().await?;
+
+        assert!(content.contains("&quot;editor&quot;:&quot;i&quot;"));
+        assert!(content.contains("<td><kbd>i</kbd></td>"));
+        assert!(content.contains("Focus editor"));
+        assert!(!content.contains("Copy raw URL"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn editor() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let content = client.get("/").send().await?.text().await?;
+        assert!(content.contains(r#"data-tab-width="4""#));
+        assert!(content.contains(r#"id="draft-notice" class="notice hidden""#));
+
+        let page = crate::test_helpers::page().with_tab_width(0);
+        let client = Client::with_page(StoreCookies(false), page).await;
+        let content = client.get("/").send().await?.text().await?;
+        assert!(content.contains(r#"data-tab-width="0""#));
+
+        Ok(())
+    }
+
+    /// Multipart body of `fields`, named and optionally with a file name.
+    fn multipart(fields: &[(&str, Option<&str>, &str)]) -> String {
+        let mut body = String::new();
+
+        for (name, filename, value) in fields {
+            let filename = filename.map(|name| format!(r#"; filename="{name}""#));
+            let _ = write!(
+                body,
+                "--boundary\r\nContent-Disposition: form-data; name=\"{name}\"{}\r\n\r\n{value}\r\n",
+                filename.unwrap_or_default()
+            );
+        }
+
+        body + "--boundary--\r\n"
+    }
+
+    #[tokio::test]
+    async fn share() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(true)).await;
+        let _ = client.get("/").send().await?;
+
+        let res = client
+            .post("/share")
+            .header("content-type", "multipart/form-data; boundary=boundary")
+            .body(multipart(&[
+                ("title", None, "Shared <note>"),
+                ("text", None, "look at this"),
+                ("url", None, "https://example.com"),
+            ]))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let content = res.text().await?;
+        assert!(content.contains("autofocus>\nlook at this\nhttps://example.com</textarea>"));
+        assert!(content.contains(r#"value="Shared &lt;note&gt;""#));
+
+        let res = client
+            .post("/share")
+            .header("content-type", "multipart/form-data; boundary=boundary")
+            .body(multipart(&[
+                ("text", None, "ignored"),
+                ("file", Some("main.rs"), "fn main() {}"),
+            ]))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let content = res.text().await?;
+        assert!(content.contains("autofocus>\nfn main() {}</textarea>"));
+        assert!(content.contains(r#"<option value="rs" selected>Rust</option>"#));
+        assert!(content.contains(r#"value="main.rs""#));
+        assert!(!content.contains("ignored"));
+
+        Ok(())
+    }
 }
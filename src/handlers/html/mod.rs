@@ -1,12 +1,17 @@
 pub mod burn;
+pub mod document;
 pub mod index;
 pub mod paste;
+pub mod print;
 pub mod qr;
+pub mod zk;
 
 use crate::handlers::extract::Theme;
+use crate::templates::{self, Overridable, Render};
 use crate::{Page, errors};
 use askama::Template;
 use axum::http::StatusCode;
+use minijinja::{Value, context};
 
 /// Error page showing a message.
 #[derive(Template)]
@@ -17,6 +22,21 @@ pub(crate) struct Error {
     pub description: String,
 }
 
+impl Overridable for Error {
+    const NAME: &'static str = "error.html";
+
+    fn page(&self) -> &Page {
+        &self.page
+    }
+
+    fn context(&self) -> Value {
+        context! {
+            page => templates::page(&self.page, self.theme.as_ref()),
+            description => self.description,
+        }
+    }
+}
+
 /// Page showing password input.
 #[derive(Template)]
 #[template(path = "encrypted.html")]
@@ -24,10 +44,11 @@ pub(crate) struct PasswordInput {
     pub page: Page,
     pub theme: Option<Theme>,
     pub id: String,
+    pub csrf: String,
 }
 
 /// Error response carrying a status code and the page itself.
-pub(crate) type ErrorResponse = (StatusCode, Error);
+pub(crate) type ErrorResponse = (StatusCode, Render<Error>);
 
 /// Create an error response from `error` consisting of [`StatusCode`] derive from `error` as well
 /// as a rendered page with a description.
@@ -35,10 +56,24 @@ pub fn make_error(error: errors::Error, page: Page, theme: Option<Theme>) -> Err
     let description = error.to_string();
     (
         error.into(),
-        Error {
+        Render(Error {
             page,
             theme,
             description,
-        },
+        }),
     )
 }
+
+/// Absolute URL of the paste `key`, shown on printouts.
+pub(crate) fn paste_url(page: &Page, key: &crate::cache::Key) -> String {
+    page.base_url
+        .join(&key.to_string())
+        .map_or_else(|_| format!("/{key}"), String::from)
+}
+
+/// Absolute URL of the raw content of `key`, to be copied without JavaScript.
+pub(crate) fn raw_url(page: &Page, key: &crate::cache::Key) -> String {
+    page.base_url
+        .join(&format!("raw/{key}"))
+        .map_or_else(|_| format!("/raw/{key}"), String::from)
+}
@@ -1,35 +1,285 @@
 use crate::cache::Key;
+use crate::cache_control::NO_STORE;
 use crate::crypto::Password;
-use crate::db::read::Entry;
-use crate::handlers::extract::{Theme, Uid};
+use crate::db::read::{Data, Entry};
+use crate::handlers::extract::{CsrfToken, Layout, Session, Theme, Uid};
 use crate::handlers::html::{ErrorResponse, PasswordInput, make_error};
-use crate::highlight::Html;
+use crate::highlight::{self, Html, Rendered, View, json, paging};
+use crate::secrets;
+use crate::templates::{self, Overridable, Render};
 use crate::{Cache, Database, Error, Highlighter, Page};
 use askama::Template;
-use axum::extract::{Form, Path, State};
-use axum::response::{IntoResponse, Response};
+use axum::extract::{Form, Path, Query, State};
+use axum::http::header::CACHE_CONTROL;
+use axum::http::{HeaderName, HeaderValue};
+use axum::response::{IntoResponse, Redirect, Response};
+use minijinja::{Value, context};
 use serde::Deserialize;
+use std::sync::Arc;
+
+/// Header asking search engines not to index a response, in addition to the meta tag of pages.
+const X_ROBOTS_TAG: HeaderName = HeaderName::from_static("x-robots-tag");
+
+const NOINDEX: HeaderValue = HeaderValue::from_static("noindex");
+
+/// Seconds before expiration from which the countdown is shown as a warning.
+pub(crate) const EXPIRES_SOON: u64 = 10 * 60;
 
 #[derive(Deserialize, Debug)]
 pub(crate) struct PasswordForm {
     password: String,
 }
 
+/// Query parameters to select an alternative rendering.
+#[derive(Deserialize, Debug, Default)]
+pub(crate) struct ViewQuery {
+    #[serde(default)]
+    view: View,
+    /// Language name or extension overriding the paste's extension.
+    lang: Option<String>,
+    /// Highlight the paste even if it exceeds the highlighting size limit.
+    #[serde(default)]
+    highlight: bool,
+    /// One-based page of pastes with more than [`paging::PAGE_LINES`] lines.
+    page: Option<usize>,
+    /// Comma-separated names of credentials found when the paste was created.
+    secrets: Option<String>,
+}
+
 /// Paste view showing the formatted paste.
 #[derive(Template)]
 #[template(path = "formatted.html")]
+#[expect(clippy::struct_excessive_bools)]
 pub(crate) struct Paste {
     page: Page,
     key: Key,
     theme: Option<Theme>,
+    layout: Layout,
     can_delete: bool,
+    /// If the owner can edit the paste, which is not possible for encrypted ones.
+    can_edit: bool,
+    /// If the create form can be filled with the paste, which needs no password to read it.
+    can_duplicate: bool,
     /// If the paste still in the database and can be fetched with another request.
     is_available: bool,
     html: String,
     title: Option<String>,
+    /// If pretty and tree views can be offered for this JSON paste.
+    json_views: bool,
+    /// If highlighting was skipped because the paste is too large.
+    highlight_skipped: bool,
+    /// If highlighting exceeded the deadline and is still running in the background.
+    highlight_pending: bool,
+    /// One-based number of the shown page.
+    page_number: usize,
+    /// Number of pages, 1 if the paste is not paged.
+    page_count: usize,
+    /// URLs of the previous and next page.
+    prev_page: Option<String>,
+    next_page: Option<String>,
+    /// Credentials found in the paste, only shown to its owner.
+    secrets: Vec<&'static str>,
+    /// If search engines are asked not to index the paste.
+    noindex: bool,
+    /// Unix time of expiration, `None` if the paste never expires or was burned.
+    expires: Option<i64>,
+    /// Link preview, without content for secret and password protected pastes.
+    preview: Preview,
+    highlighter: Highlighter,
+    csrf: String,
+}
+
+impl Overridable for Paste {
+    const NAME: &'static str = "paste.html";
+
+    fn page(&self) -> &Page {
+        &self.page
+    }
+
+    fn context(&self) -> Value {
+        context! {
+            page => templates::page(&self.page, self.theme.as_ref()),
+            csrf => self.csrf,
+            id => self.key.id(),
+            path => self.key.to_string(),
+            title => self.title,
+            extension => self.key.ext,
+            html => Value::from_safe_string(self.html.clone()),
+            can_edit => self.can_edit,
+            can_duplicate => self.can_duplicate,
+            can_delete => self.can_delete,
+            is_available => self.is_available,
+            highlight_skipped => self.highlight_skipped,
+            highlight_pending => self.highlight_pending,
+            page_number => self.page_number,
+            page_count => self.page_count,
+            prev_page => self.prev_page,
+            next_page => self.next_page,
+            secrets => self.secrets,
+            noindex => self.noindex,
+            expires => self.expires,
+            preview => context! {
+                title => self.preview.title,
+                description => self.preview.description,
+                url => self.preview.url,
+            },
+            wrap => self.layout.wrap,
+            line_numbers => self.layout.line_numbers,
+            languages => templates::languages(&self.highlighter),
+        }
+    }
 }
 
-#[expect(clippy::too_many_arguments)]
+impl Paste {
+    /// Seconds left until the paste expires.
+    fn expires_in(&self) -> u64 {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        self.expires
+            .and_then(|expires| u64::try_from(expires).ok())
+            .map_or(0, |expires| expires.saturating_sub(now))
+    }
+
+    /// Time left until the paste expires, as shown before the script takes over.
+    fn countdown(&self) -> String {
+        crate::expiration::countdown(self.expires_in())
+    }
+}
+
+/// Characters of the text shown in link previews.
+const SNIPPET_CHARS: usize = 200;
+
+/// Title, summary and URL shown when links to a paste unfurl in chat apps and social networks.
+pub(crate) struct Preview {
+    title: String,
+    description: Option<String>,
+    url: Option<String>,
+}
+
+impl Preview {
+    /// Preview of `data` shown as `key`. Secret and `protected` pastes only reveal the site.
+    fn new(
+        page: &Page,
+        highlighter: &Highlighter,
+        key: &Key,
+        data: &Data,
+        protected: bool,
+    ) -> Self {
+        if protected || key.token.is_some() {
+            return Self {
+                title: page.title.clone(),
+                description: None,
+                url: None,
+            };
+        }
+
+        let summary = crate::i18n::t2(
+            "preview-summary",
+            "language",
+            highlighter.syntax_name(&key.ext),
+            "lines",
+            data.text.lines().count(),
+        );
+
+        let mut snippet = data.text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        if let Some((end, _)) = snippet.char_indices().nth(SNIPPET_CHARS) {
+            snippet.truncate(end);
+            snippet.push('…');
+        }
+
+        Self {
+            title: data.title.clone().unwrap_or_else(|| key.id()),
+            description: Some(format!("{summary}: {snippet}")),
+            url: page.base_url.join(&key.to_string()).ok().map(String::from),
+        }
+    }
+}
+
+/// URL of page `number` of `key` that keeps the current view.
+fn page_url(key: &Key, highlight: bool, number: usize) -> String {
+    let view = match key.view {
+        View::Pretty => "view=pretty&",
+        View::Tree => "view=tree&",
+        View::Source | View::Plain | View::Snippet => "",
+    };
+
+    let highlight = if highlight { "highlight=true&" } else { "" };
+
+    format!("/{key}?{view}{highlight}page={number}")
+}
+
+/// Return the cached HTML for `key` or highlight `data`. The second value is `true` if
+/// highlighting exceeded the deadline and the plain text fallback is returned instead. Cacheable
+/// keys are only highlighted once at a time, concurrent requests wait for the same render.
+pub(crate) async fn render(
+    cache: &Cache,
+    highlighter: &Highlighter,
+    key: &Key,
+    data: Data,
+    cacheable: bool,
+) -> Result<(Html, bool), Error> {
+    if let Some(html) = cache.get(key) {
+        tracing::trace!(?key, "found cached item");
+
+        return Ok((html, false));
+    }
+
+    let flight = if cacheable {
+        match cache.start(key) {
+            Ok(flight) => Some(flight),
+            Err(mut pending) => {
+                tracing::trace!(?key, "wait for item in flight");
+
+                let rendered =
+                    tokio::time::timeout(highlighter.deadline(), pending.wait_for(Option::is_some))
+                        .await;
+
+                return Ok(match rendered {
+                    Ok(Ok(html)) => (html.clone().expect("waited for rendered item"), false),
+                    _ => (highlight::fallback(&data.text), true),
+                });
+            }
+        }
+    } else {
+        None
+    };
+
+    match Arc::clone(highlighter)
+        .highlight(data, key.ext.clone(), key.view)
+        .await?
+    {
+        Rendered::Done(html) => {
+            if let Some(flight) = flight {
+                tracing::trace!(?key, "cache item");
+                flight.finish(html.clone());
+            }
+
+            Ok((html, false))
+        }
+        Rendered::Late(fallback, task) => {
+            tracing::debug!(?key, "highlighting exceeded deadline");
+
+            if let Some(flight) = flight {
+                let key = key.clone();
+
+                tokio::spawn(async move {
+                    if let Ok(Ok(html)) = task.await {
+                        tracing::trace!(?key, "cache late item");
+                        flight.finish(html);
+                    }
+                });
+            }
+
+            Ok((fallback, true))
+        }
+    }
+}
+
+#[expect(clippy::too_many_arguments, clippy::too_many_lines)]
 pub async fn get(
     State(cache): State<Cache>,
     State(page): State<Page>,
@@ -37,93 +287,146 @@ pub async fn get(
     State(highlighter): State<Highlighter>,
     Path(id): Path<String>,
     uid: Option<Uid>,
+    session: Option<Session>,
     theme: Option<Theme>,
+    layout: Option<Layout>,
+    CsrfToken(csrf): CsrfToken,
+    query: Option<Query<ViewQuery>>,
     form: Option<Form<PasswordForm>>,
 ) -> Result<Response, ErrorResponse> {
     async {
         let password = form.map(|form| Password::from(form.password.as_bytes().to_vec()));
-        let key: Key = id.parse()?;
-
-        let (data, is_available) = match db.get(key.id, password.clone()).await {
-            Ok(Entry::Regular(data)) => (data, true),
-            Ok(Entry::Burned(data)) => (data, false),
-            Ok(Entry::Expired) => return Err(Error::NotFound),
-            Err(Error::NoPassword) => {
-                return Ok(PasswordInput {
-                    page: page.clone(),
-                    theme: theme.clone(),
-                    id,
+        let query = query.map(|Query(query)| query).unwrap_or_default();
+        let mut key: Key = id.parse()?;
+
+        // Redirect before reading, which would burn the paste before the viewer fetches it.
+        if db.is_ciphertext(key.id, key.token.as_deref()).await? {
+            let url = format!("/zk/{}", key.id_with_token());
+            return Ok(Redirect::to(&url).into_response());
+        }
+
+        let (data, is_available) =
+            match db.get(key.id, key.token.as_deref(), password.clone()).await {
+                Ok(Entry::Regular(data)) => (data, true),
+                Ok(Entry::Burned(data)) => (data, false),
+                Ok(Entry::Expired) => return Err(Error::NotFound),
+                Err(Error::NoPassword) => {
+                    return Ok(PasswordInput {
+                        page: page.clone(),
+                        theme: theme.clone(),
+                        id,
+                        csrf,
+                    }
+                    .into_response());
                 }
-                .into_response());
-            }
-            Err(err) => return Err(err),
-        };
+                Err(err) => return Err(err),
+            };
 
-        let can_be_deleted = uid
+        let is_owner = uid
             .zip(data.uid)
             .is_some_and(|(Uid(user_uid), owner_uid)| user_uid == owner_uid);
+        let is_admin = session.is_some_and(|session| session.roles.admin);
+        let secrets = query
+            .secrets
+            .as_deref()
+            .filter(|_| is_owner)
+            .map(|names| {
+                names
+                    .split(',')
+                    .filter_map(|name| name.parse::<secrets::Kind>().ok())
+                    .map(secrets::Kind::description)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // Fall back to the stored extension if none was requested.
+        if !id.contains('.') {
+            if let Some(ext) = &data.extension {
+                key.ext.clone_from(ext);
+            }
+        }
+
+        if let Some(ext) = query.lang.and_then(|lang| highlighter.extension_for(&lang)) {
+            key.ext = ext;
+        }
 
         let title = data.title.clone();
+        let preview = Preview::new(&page, &highlighter, &key, &data, password.is_some());
+        let noindex = page.noindex || data.noindex;
+        let expires = data.expires.filter(|_| is_available);
+        let json_views =
+            highlighter.resolve(&key.ext) == "json" && data.text.len() <= json::SIZE_LIMIT;
 
-        let html = if let Some(html) = cache.get(&key) {
-            tracing::trace!(?key, "found cached item");
+        if json_views {
+            key.view = query.view;
+        }
 
-            html
-        } else {
-            let html = highlighter.highlight(data, key.ext.clone()).await?;
+        let highlight_skipped =
+            key.view == View::Source && !query.highlight && highlighter.exceeds_limit(&data.text);
 
-            if is_available && password.is_none() {
-                tracing::trace!(?key, "cache item");
-                cache.put(key.clone(), html.clone());
-            }
+        if highlight_skipped {
+            key.view = View::Plain;
+        }
 
-            html
-        };
+        let cacheable = is_available && password.is_none();
+        let (html, highlight_pending) = render(&cache, &highlighter, &key, data, cacheable).await?;
 
-        Ok(Paste::new(
-            key,
-            html,
-            theme.clone(),
-            can_be_deleted,
-            is_available,
-            title,
-            page.clone(),
-        )
-        .into_response())
-    }
-    .await
-    .map_err(|err| make_error(err, page, theme))
-}
+        let (html, page_number, page_count) =
+            match paging::paginate(&html, query.page.unwrap_or(1), paging::PAGE_LINES) {
+                Some(paged) => (paged.html, paged.number, paged.count),
+                None => (html.into_inner(), 1, 1),
+            };
 
-impl Paste {
-    /// Construct new paste view from cache `key` and paste `html`.
-    pub fn new(
-        key: Key,
-        html: Html,
-        theme: Option<Theme>,
-        can_delete: bool,
-        is_available: bool,
-        title: Option<String>,
-        page: Page,
-    ) -> Self {
-        let html = html.into_inner();
+        let prev_page = (page_number > 1).then(|| page_url(&key, query.highlight, page_number - 1));
+        let next_page =
+            (page_number < page_count).then(|| page_url(&key, query.highlight, page_number + 1));
 
-        Self {
-            page,
+        let paste = Paste {
+            page: page.clone(),
             key,
-            theme,
-            can_delete,
+            theme: theme.clone(),
+            layout: layout.unwrap_or_default(),
+            can_delete: is_owner || is_admin,
+            can_edit: is_owner && password.is_none(),
+            can_duplicate: password.is_none(),
             is_available,
             html,
             title,
+            json_views,
+            highlight_skipped,
+            highlight_pending,
+            page_number,
+            page_count,
+            prev_page,
+            next_page,
+            secrets,
+            noindex,
+            expires,
+            preview,
+            highlighter,
+            csrf,
+        };
+
+        let robots = noindex.then_some([(X_ROBOTS_TAG, NOINDEX)]);
+
+        if is_available {
+            db.record_view(paste.key.id);
+            return Ok((robots, Render(paste)).into_response());
         }
+
+        Ok(([(CACHE_CONTROL, NO_STORE)], robots, Render(paste)).into_response())
     }
+    .await
+    .map_err(|err| make_error(err, page, theme))
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::handlers::insert::form::Entry;
+    use crate::highlight::Highlighter;
     use crate::test_helpers::{Client, StoreCookies};
     use reqwest::StatusCode;
+    use std::time::Duration;
 
     #[tokio::test]
     async fn unknown_paste() -> Result<(), Box<dyn std::error::Error>> {
@@ -134,4 +437,309 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn noindex() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        for (noindex, expected) in [(None, false), (Some(String::from("on")), true)] {
+            let data = Entry {
+                text: String::from("hidden"),
+                noindex,
+                ..Default::default()
+            };
+
+            let res = client.post_form().form(&data).send().await?;
+            let location = res.headers().get("location").unwrap().to_str()?;
+
+            let res = client.get(location).send().await?;
+            assert_eq!(res.headers().contains_key("x-robots-tag"), expected);
+            let content = res.text().await?;
+            assert_eq!(
+                content.contains(r#"<meta name="robots" content="noindex">"#),
+                expected
+            );
+        }
+
+        let page = crate::test_helpers::page()
+            .with_noindex()
+            .with_robots_txt(b"User-agent: *\nDisallow: /\n".to_vec());
+        let client = Client::with_page(StoreCookies(false), page).await;
+
+        let content = client.get("/").send().await?.text().await?;
+        assert!(!content.contains(r#"name="noindex""#));
+
+        let data = Entry {
+            text: String::from("hidden"),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+        let res = client.get(location).send().await?;
+        assert_eq!(res.headers()["x-robots-tag"], "noindex");
+
+        let res = client.get("/robots.txt").send().await?;
+        assert_eq!(res.headers()["content-type"], "text/plain; charset=utf-8");
+        assert_eq!(res.text().await?, "User-agent: *\nDisallow: /\n");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn link_preview() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let data = Entry {
+            text: format!(
+                "fn main() {{\n    println!(\"<hi>\");\n}}\n{}",
+                "x".repeat(300)
+            ),
+            extension: Some(String::from("rs")),
+            title: String::from("Hello"),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+        let content = client.get(location).send().await?.text().await?;
+
+        assert!(content.contains(r#"<meta property="og:title" content="Hello">"#));
+        assert!(content.contains(&format!(
+            r#"<meta property="og:description" content="Rust, 4 lines: fn main() {{ println!(&quot;&lt;hi&gt;&quot;); }} {}…">"#,
+            "x".repeat(168)
+        )));
+        assert!(content.contains(&format!(
+            r#"<meta property="og:url" content="https://localhost:8888{location}">"#
+        )));
+
+        for data in [
+            Entry {
+                text: String::from("classified"),
+                title: String::from("Plans"),
+                secret: Some(String::from("on")),
+                ..Default::default()
+            },
+            Entry {
+                text: String::from("classified"),
+                title: String::from("Plans"),
+                password: String::from("hunter2"),
+                ..Default::default()
+            },
+        ] {
+            let password = data.password.clone();
+            let res = client.post_form().form(&data).send().await?;
+            let location = res.headers().get("location").unwrap().to_str()?;
+            let form = std::collections::HashMap::from([("password", password)]);
+            let content = client
+                .post(location)
+                .form(&form)
+                .send()
+                .await?
+                .text()
+                .await?;
+
+            assert!(content.contains(r#"<meta property="og:title" content="test">"#));
+            assert!(!content.contains("og:description"));
+            assert!(!content.contains("og:url"));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn without_javascript() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(true)).await;
+
+        let content = client.get("/").send().await?.text().await?;
+        assert!(content.contains(r#"<div class="nav-item js-only">"#));
+        let (_, rest) = content
+            .split_once(r#"<noscript><link rel="stylesheet" href=""#)
+            .unwrap();
+        let href = rest.split('"').next().unwrap().to_string();
+
+        let res = client.get(&href).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.text().await?.contains(".js-only"));
+
+        let data = Entry {
+            text: String::from("fn main() {}"),
+            extension: Some(String::from("rs")),
+            expires: Some(String::from("3600")),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+
+        let content = client.get(location).send().await?.text().await?;
+        assert!(content.contains(&format!(
+            r#"readonly value="https://localhost:8888/raw{location}""#
+        )));
+        assert!(content.contains(&format!(r#"action="/delete{location}""#).replace(".rs", "")));
+        assert!(content.contains(r#"id="copy-url-button""#));
+        assert!(content.contains(&format!(
+            r#"data-url="https://localhost:8888/raw{location}""#
+        )));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn expiry_countdown() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let mut data = Entry {
+            text: String::from("soon gone"),
+            expires: Some(String::from("60")),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+
+        let content = client.get(location).send().await?.text().await?;
+        assert!(content.contains(r#"class="notice expiry expiry-soon" id="expiry""#));
+        assert!(content.contains("expires in "));
+        assert!(content.contains(r#"data-template="expires in {time}""#));
+
+        data.expires = Some(String::from("86400"));
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+
+        let content = client.get(location).send().await?.text().await?;
+        assert!(content.contains(r#"class="notice expiry" id="expiry""#));
+        assert!(content.contains("expires in 23h 59m") || content.contains("expires in 1d 0h"));
+
+        data.expires = None;
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+
+        let content = client.get(location).send().await?.text().await?;
+        assert!(!content.contains(r#"id="expiry""#));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn json_views() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let data = Entry {
+            text: String::from(r#"{"answer":42}"#),
+            extension: Some(String::from("json")),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+
+        let res = client.get(&format!("{location}?view=tree")).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let content = res.text().await?;
+        assert!(content.contains(r#"<ul class="json-tree">"#));
+        assert!(content.contains("view=pretty"));
+
+        let res = client
+            .get(&format!("{location}?view=pretty"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let content = res.text().await?;
+        assert!(content.contains(r#"id="L3""#));
+        assert!(!content.contains("json-tree"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn skip_highlighting_large_pastes() -> Result<(), Box<dyn std::error::Error>> {
+        let highlighter = Highlighter::default().with_max_bytes(Some(16));
+        let client = Client::with_highlighter(StoreCookies(false), highlighter).await;
+
+        let data = Entry {
+            text: "fn main() { println!(\"<large>\"); }".to_string(),
+            extension: Some("rs".to_string()),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+
+        let res = client.get(location).send().await?;
+        let content = res.text().await?;
+        assert!(content.contains("Highlight anyway"));
+        assert!(content.contains("&lt;large&gt;"));
+        assert!(!content.contains("source rust"));
+
+        let res = client
+            .get(&format!("{location}?highlight=true"))
+            .send()
+            .await?;
+        let content = res.text().await?;
+        assert!(!content.contains("Highlight anyway"));
+        assert!(content.contains("source rust"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fall_back_after_deadline() -> Result<(), Box<dyn std::error::Error>> {
+        let highlighter = Highlighter::default().with_deadline(Duration::ZERO);
+        let client = Client::with_highlighter(StoreCookies(false), highlighter).await;
+
+        let data = Entry {
+            text: "fn main() {}\n".repeat(100),
+            extension: Some("rs".to_string()),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+
+        let res = client.get(location).send().await?;
+        let content = res.text().await?;
+        assert!(content.contains("still being highlighted"));
+        assert!(!content.contains("source rust"));
+
+        // The background task eventually fills the cache.
+        for _ in 0..200 {
+            let content = client.get(location).send().await?.text().await?;
+
+            if content.contains("source rust") {
+                assert!(!content.contains("still being highlighted"));
+                return Ok(());
+            }
+
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        panic!("highlighted paste was never cached");
+    }
+
+    #[tokio::test]
+    async fn paged_paste() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        let data = Entry {
+            text: "line\n".repeat(crate::highlight::paging::PAGE_LINES + 1),
+            extension: Some("txt".to_string()),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+
+        let res = client.get(location).send().await?;
+        let content = res.text().await?;
+        assert!(content.contains("page 1 of 2"));
+        assert!(content.contains(r#"id="L10000""#));
+        assert!(!content.contains(r#"id="L10001""#));
+        assert!(content.contains(&format!(r#"href="{location}?page=2""#)));
+
+        let res = client.get(&format!("{location}?page=2")).send().await?;
+        let content = res.text().await?;
+        assert!(content.contains("page 2 of 2"));
+        assert!(!content.contains(r#"id="L10000""#));
+        assert!(content.contains(r#"id="L10001""#));
+
+        Ok(())
+    }
 }
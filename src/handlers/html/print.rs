@@ -0,0 +1,153 @@
+use crate::cache::Key;
+use crate::cache_control::NO_STORE;
+use crate::db::read::Entry;
+use crate::handlers::extract::{self, CsrfToken, Mode, Password, Theme};
+use crate::handlers::html::{ErrorResponse, PasswordInput, make_error};
+use crate::highlight::{Rendered, View};
+use crate::{Database, Error, Highlighter, Page};
+use askama::Template;
+use axum::extract::{Path, State};
+use axum::http::header::CACHE_CONTROL;
+use axum::response::{IntoResponse, Redirect, Response};
+use std::sync::Arc;
+
+/// Paste without navigation, highlighted in black on white and wrapped to fit on paper.
+#[derive(Template)]
+#[template(path = "print.html")]
+pub(crate) struct Print {
+    page: Page,
+    theme: Option<Theme>,
+    title: String,
+    url: String,
+    language: String,
+    html: String,
+}
+
+/// GET and POST handler for the print view of a paste, with its URL so printouts lead back to it.
+pub async fn get(
+    Path(id): Path<String>,
+    State(db): State<Database>,
+    State(page): State<Page>,
+    State(highlighter): State<Highlighter>,
+    theme: Option<Theme>,
+    CsrfToken(csrf): CsrfToken,
+    password: Option<Password>,
+) -> Result<Response, ErrorResponse> {
+    async {
+        let mut key: Key = id.parse()?;
+        let password = password.map(|Password(password)| password);
+
+        // Redirect before reading, which would burn the paste before the viewer fetches it.
+        if db.is_ciphertext(key.id, key.token.as_deref()).await? {
+            let url = format!("/zk/{}", key.id_with_token());
+            return Ok(Redirect::to(&url).into_response());
+        }
+
+        let (data, is_available) = match db.get(key.id, key.token.as_deref(), password).await {
+            Ok(Entry::Regular(data)) => (data, true),
+            Ok(Entry::Burned(data)) => (data, false),
+            Ok(Entry::Expired) => return Err(Error::NotFound),
+            Err(Error::NoPassword) => {
+                return Ok(PasswordInput {
+                    page: page.clone(),
+                    theme: theme.clone(),
+                    id: format!("{}/print", key.id_with_token()),
+                    csrf,
+                }
+                .into_response());
+            }
+            Err(err) => return Err(err),
+        };
+
+        if !id.contains('.') {
+            if let Some(ext) = &data.extension {
+                key.ext.clone_from(ext);
+            }
+        }
+
+        // Paper is white, so always print with the light variant of the selected theme.
+        let theme = extract::Theme {
+            mode: Some(Mode::Light),
+            name: theme.clone().and_then(|theme| theme.name),
+            ..Default::default()
+        };
+
+        let title = data
+            .title
+            .clone()
+            .unwrap_or_else(|| format!("{}.{}", key.id(), key.ext));
+        let url = page.base_url.join(&key.to_string())?.to_string();
+        let language = highlighter.syntax_name(&key.ext).to_string();
+
+        let view = if highlighter.exceeds_limit(&data.text) {
+            View::Plain
+        } else {
+            View::Source
+        };
+
+        let html = match Arc::clone(&highlighter)
+            .highlight(data, key.ext.clone(), view)
+            .await?
+        {
+            Rendered::Done(html) | Rendered::Late(html, _) => html.into_inner(),
+        };
+
+        let print = Print {
+            page: page.clone(),
+            theme: Some(theme),
+            title,
+            url,
+            language,
+            html,
+        };
+
+        if is_available {
+            return Ok(print.into_response());
+        }
+
+        Ok(([(CACHE_CONTROL, NO_STORE)], print).into_response())
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+
+    #[tokio::test]
+    async fn print_view() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        let data = Entry {
+            text: String::from("fn main() {}\n"),
+            extension: Some(String::from("rs")),
+            title: String::from("review"),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+
+        let res = client.get(&format!("{location}/print")).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let content = res.text().await?;
+        assert!(content.contains("<h1>review</h1>"));
+        assert!(content.contains(&format!("https://localhost:8888{location}")));
+        assert!(content.contains("source rust"));
+        assert!(content.contains("-light."));
+        assert!(!content.contains("-dark."));
+        assert!(!content.contains("<header>"));
+
+        let content = client.get(location).send().await?.text().await?;
+        assert!(content.contains(&format!(r#"href="{location}/print""#).replace(".rs", "")));
+
+        let res = client.get("/aaaaaa/print").send().await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+}
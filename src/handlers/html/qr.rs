@@ -26,7 +26,7 @@ pub async fn get(
         };
 
         let key: Key = id.parse()?;
-        let title = db.get_title(key.id).await?;
+        let title = db.get_title(key.id, key.token.as_deref()).await?;
 
         // TODO: fix the bogus hardcoded can_delete and is_deleted fields.
         Ok(Qr {
@@ -34,7 +34,11 @@ pub async fn get(
             theme: theme.clone(),
             key,
             can_delete: false,
+            can_edit: false,
+            can_duplicate: false,
             is_available: false,
+            // Nothing can be deleted from here, so no form needs a token.
+            csrf: String::new(),
             code,
             title,
         })
@@ -46,12 +50,16 @@ pub async fn get(
 /// Paste view showing the formatted paste as well as a bunch of links.
 #[derive(Template)]
 #[template(path = "qr.html", escape = "none")]
+#[expect(clippy::struct_excessive_bools)]
 pub(crate) struct Qr {
     page: Page,
     theme: Option<Theme>,
     key: Key,
     can_delete: bool,
+    can_edit: bool,
+    can_duplicate: bool,
     is_available: bool,
+    csrf: String,
     code: qrcodegen::QrCode,
     title: Option<String>,
 }
@@ -0,0 +1,186 @@
+use crate::Page;
+use crate::cache::Key;
+use crate::captcha::Widget;
+use crate::handlers::extract::{CsrfToken, Session, Theme};
+use crate::handlers::html::{ErrorResponse, make_error};
+use crate::spam::{self, FillTime};
+use crate::terms;
+use askama::Template;
+use axum::extract::{Path, State};
+use axum_extra::extract::cookie::SignedCookieJar;
+
+/// Page with a form whose text the browser encrypts before submitting it to `/new`. The key is
+/// put into the URL fragment, which browsers never send to the server.
+#[derive(Template)]
+#[template(path = "zk.html")]
+pub(crate) struct Create {
+    page: Page,
+    theme: Option<Theme>,
+    /// If the form is replaced by a hint to log in.
+    locked: bool,
+    /// Captcha to solve, only shown to anonymous users.
+    captcha: Option<Widget>,
+    /// If a proof-of-work challenge is solved before submitting the form.
+    pow: bool,
+    /// Signed time of rendering the form.
+    rendered: Option<String>,
+    /// Name of the field hidden from humans.
+    honeypot: &'static str,
+    /// If the terms of service are to be accepted with the form.
+    terms: bool,
+    csrf: String,
+}
+
+/// Page fetching the raw ciphertext of a paste and decrypting it with the key from the URL
+/// fragment.
+#[derive(Template)]
+#[template(path = "zk_view.html")]
+pub(crate) struct View {
+    page: Page,
+    theme: Option<Theme>,
+    id: String,
+}
+
+/// GET handler for the form creating pastes encrypted by the browser.
+pub async fn get(
+    State(page): State<Page>,
+    State(fill_time): State<Option<FillTime>>,
+    session: Option<Session>,
+    jar: SignedCookieJar,
+    theme: Option<Theme>,
+    CsrfToken(csrf): CsrfToken,
+) -> Create {
+    Create {
+        terms: terms::pending(page.terms.as_ref(), session.as_ref(), &jar),
+        locked: page.private && session.is_none(),
+        captcha: page.captcha.clone().filter(|_| session.is_none()),
+        pow: page.pow && session.is_none(),
+        rendered: fill_time.map(|fill_time| fill_time.stamp()),
+        honeypot: spam::HONEYPOT,
+        page,
+        theme,
+        csrf,
+    }
+}
+
+/// GET handler for the viewer of pastes encrypted by the browser. The paste is not read here, so
+/// burn after reading pastes are only burned once the viewer fetches them.
+pub async fn view(
+    Path(id): Path<String>,
+    State(page): State<Page>,
+    theme: Option<Theme>,
+) -> Result<View, ErrorResponse> {
+    async {
+        let key: Key = id.parse()?;
+
+        Ok(View {
+            page: page.clone(),
+            theme: theme.clone(),
+            id: key.id_with_token(),
+        })
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+
+    #[tokio::test]
+    async fn ciphertext_paste() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(true)).await;
+
+        let res = client.get("/zk").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(
+            res.text()
+                .await?
+                .contains(r#"name="ciphertext" value="on""#)
+        );
+
+        let data = Entry {
+            text: String::from("bm90IHJlYWxseSBlbmNyeXB0ZWQ="),
+            burn_after_reading: Some(String::from("on")),
+            ciphertext: Some(String::from("on")),
+            ..Default::default()
+        };
+        let res = client.post_form().form(&data).send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let location = res.headers().get("location").unwrap().to_str()?;
+        let id = location.strip_prefix("/zk/").unwrap();
+
+        let res = client.get(location).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.text().await?.contains(&format!("/raw/{id}")));
+
+        let res = client.get(&format!("/edit/{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client.get(&format!("/raw/{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.text().await?, data.text);
+
+        let res = client.get(location).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = client.get(&format!("/raw/{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn redirect_to_viewer() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let data = Entry {
+            text: String::from("Y2lwaGVydGV4dA=="),
+            ciphertext: Some(String::from("on")),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+        let id = location.strip_prefix("/zk/").unwrap();
+
+        let res = client.get(&format!("/{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(res.headers().get("location").unwrap(), location);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn redirect_keeps_burned_secret_paste() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let data = Entry {
+            text: String::from("Y2lwaGVydGV4dA=="),
+            burn_after_reading: Some(String::from("on")),
+            secret: Some(String::from("on")),
+            ciphertext: Some(String::from("on")),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+        let id = location.strip_prefix("/zk/").unwrap();
+        assert!(id.contains('_'));
+
+        for path in [format!("/{id}"), format!("/{id}/print")] {
+            let res = client.get(&path).send().await?;
+            assert_eq!(res.status(), StatusCode::SEE_OTHER);
+            assert_eq!(res.headers().get("location").unwrap(), location);
+        }
+
+        let res = client.get(location).send().await?;
+        assert!(res.text().await?.contains(&format!("/raw/{id}")));
+
+        let res = client.get(&format!("/raw/{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.text().await?, data.text);
+
+        Ok(())
+    }
+}
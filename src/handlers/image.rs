@@ -0,0 +1,210 @@
+use crate::cache::Key;
+use crate::db::read::Entry;
+use crate::handlers::extract::{self, Mode, Theme};
+use crate::handlers::html::{ErrorResponse, make_error};
+use crate::highlight::image;
+use crate::{Database, Error, Highlighter, Page};
+use axum::extract::{Path, Query, State};
+use axum::response::{IntoResponse, Response};
+use http::header::CONTENT_TYPE;
+use serde::Deserialize;
+use std::ops::RangeInclusive;
+
+/// Query parameters of image requests.
+#[derive(Deserialize, Debug, Default)]
+pub(crate) struct ImageQuery {
+    /// Single line or range of lines like `10-25`.
+    lines: Option<String>,
+    /// Color mode overriding the `pref` cookie, dark if neither is given.
+    mode: Option<Mode>,
+}
+
+/// Parse a single line number or a range of line numbers.
+fn parse_lines(lines: &str) -> Result<RangeInclusive<usize>, Error> {
+    let (first, last) = lines.split_once('-').unwrap_or((lines, lines));
+    let first = first
+        .trim()
+        .parse::<usize>()
+        .map_err(|_| Error::LineRange)?;
+    let last = last.trim().parse::<usize>().map_err(|_| Error::LineRange)?;
+
+    if first == 0 || last < first {
+        return Err(Error::LineRange);
+    }
+
+    Ok(first..=last)
+}
+
+/// Render the paste `id` as SVG image. Burn after reading pastes are refused, as rendering them
+/// would burn them without anyone having seen them.
+async fn render(
+    db: &Database,
+    page: &Page,
+    highlighter: Highlighter,
+    id: &str,
+    theme: Option<Theme>,
+    query: ImageQuery,
+) -> Result<String, Error> {
+    let mut key: Key = id.parse()?;
+
+    if db
+        .is_burn_after_reading(key.id, key.token.as_deref())
+        .await?
+    {
+        return Err(Error::BurnAfterReading);
+    }
+
+    let data = match db.get(key.id, key.token.as_deref(), None).await? {
+        Entry::Regular(data) => data,
+        Entry::Burned(_) => return Err(Error::BurnAfterReading),
+        Entry::Expired => return Err(Error::NotFound),
+    };
+
+    if !id.contains('.') {
+        if let Some(ext) = &data.extension {
+            key.ext.clone_from(ext);
+        }
+    }
+
+    let lines = query
+        .lines
+        .as_deref()
+        .map(parse_lines)
+        .transpose()?
+        .unwrap_or(1..=image::MAX_LINES);
+
+    let theme = theme.unwrap_or_default();
+    let theme = extract::Theme {
+        mode: query.mode.or(theme.mode),
+        name: theme.name,
+        ..Default::default()
+    };
+
+    let colors = page
+        .assets
+        .css
+        .colors(Some(&theme))
+        .cloned()
+        .ok_or(Error::NotFound)?;
+
+    tokio::task::spawn_blocking(move || highlighter.svg(&data.text, &key.ext, &colors, lines))
+        .await?
+}
+
+/// GET handler for an SVG image of a paste.
+pub async fn svg(
+    Path(id): Path<String>,
+    State(db): State<Database>,
+    State(page): State<Page>,
+    State(highlighter): State<Highlighter>,
+    theme: Option<Theme>,
+    query: Option<Query<ImageQuery>>,
+) -> Result<Response, ErrorResponse> {
+    let query = query.map(|Query(query)| query).unwrap_or_default();
+
+    render(&db, &page, highlighter, &id, theme.clone(), query)
+        .await
+        .map(|svg| ([(CONTENT_TYPE, "image/svg+xml")], svg).into_response())
+        .map_err(|err| make_error(err, page, theme))
+}
+
+/// GET handler for a PNG image of a paste.
+#[cfg(feature = "png")]
+pub async fn png(
+    Path(id): Path<String>,
+    State(db): State<Database>,
+    State(page): State<Page>,
+    State(highlighter): State<Highlighter>,
+    theme: Option<Theme>,
+    query: Option<Query<ImageQuery>>,
+) -> Result<Response, ErrorResponse> {
+    let query = query.map(|Query(query)| query).unwrap_or_default();
+
+    async {
+        let svg = render(&db, &page, highlighter, &id, theme.clone(), query).await?;
+        let png = tokio::task::spawn_blocking(move || image::png(&svg)).await??;
+
+        Ok::<_, Error>(([(CONTENT_TYPE, "image/png")], png).into_response())
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::insert::form;
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::{StatusCode, header};
+
+    #[test]
+    fn line_ranges() {
+        assert_eq!(parse_lines("3").unwrap(), 3..=3);
+        assert_eq!(parse_lines("10-25").unwrap(), 10..=25);
+        assert!(parse_lines("0").is_err());
+        assert!(parse_lines("5-2").is_err());
+        assert!(parse_lines("a-b").is_err());
+    }
+
+    #[tokio::test]
+    async fn svg_image() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        let data = form::Entry {
+            text: "fn main() {}\nfn other() {}\n".to_string(),
+            extension: Some("rs".to_string()),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+
+        let res = client
+            .get(&format!("{location}/image.svg?lines=2&theme=nord"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "image/svg+xml"
+        );
+
+        let content = res.text().await?;
+        assert!(content.starts_with("<svg "));
+        assert!(content.contains("other"));
+        assert!(!content.contains("main"));
+
+        let res = client
+            .get(&format!("{location}/image.svg?lines=5-2"))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn refuse_burn_after_reading() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        let data = form::Entry {
+            text: "fn main() {}\n".to_string(),
+            extension: Some("rs".to_string()),
+            burn_after_reading: Some(String::from("on")),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+        let path = location.trim_start_matches("/burn");
+
+        let res = client.get(&format!("{path}/image.svg")).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client.get(&format!("/raw{path}")).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.text().await?, "fn main() {}\n");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+use crate::db::read::Data;
+use crate::handlers::extract::{Session, Theme};
+use crate::handlers::html::{ErrorResponse, make_error};
+use crate::handlers::insert::{check_creation, detect};
+use crate::highlight::{Rendered, View};
+use crate::{Highlighter, Page};
+use askama::Template;
+use axum::extract::{Form, State};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Fields of the create form the preview depends on, the others are ignored.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Entry {
+    pub text: String,
+    pub extension: Option<String>,
+}
+
+/// Fragment with the text highlighted as its paste would be.
+#[derive(Template)]
+#[template(path = "preview.html")]
+pub(crate) struct Preview {
+    language: String,
+    html: String,
+}
+
+/// POST handler rendering the text of the create form like the paste page would, so the detected
+/// language and the formatting can be checked before submitting.
+pub async fn post(
+    State(page): State<Page>,
+    State(highlighter): State<Highlighter>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+    Form(entry): Form<Entry>,
+) -> Result<Preview, ErrorResponse> {
+    async {
+        check_creation(&page, session.as_ref())?;
+
+        let (text, ext) = match entry.extension.filter(|ext| !ext.is_empty()) {
+            Some(ext) => (entry.text, Some(ext)),
+            None => detect(&highlighter, entry.text).await?,
+        };
+        let ext = ext.unwrap_or_else(|| String::from("txt"));
+
+        let view = if highlighter.exceeds_limit(&text) {
+            View::Plain
+        } else {
+            View::Source
+        };
+
+        let language = highlighter.syntax_name(&ext).to_string();
+        let data = Data {
+            text,
+            uid: None,
+            title: None,
+            extension: Some(ext.clone()),
+            ciphertext: false,
+            noindex: false,
+            expires: None,
+        };
+
+        let html = match Arc::clone(&highlighter).highlight(data, ext, view).await? {
+            Rendered::Done(html) | Rendered::Late(html, _) => html.into_inner(),
+        };
+
+        Ok(Preview { language, html })
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn preview() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        let form = HashMap::from([("text", "fn main() {}"), ("extension", "rs")]);
+        let res = client.post("/preview").form(&form).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let content = res.text().await?;
+        assert!(content.contains("Rust"));
+        assert!(content.contains("source rust"));
+        assert!(content.contains(r#"id="L1""#));
+        assert!(!content.contains("<html"));
+
+        let form = HashMap::from([("text", "#!/usr/bin/env python3\nprint('<b>')")]);
+        let content = client
+            .post("/preview")
+            .form(&form)
+            .send()
+            .await?
+            .text()
+            .await?;
+        assert!(content.contains("Python"));
+        assert!(content.contains("&lt;b&gt;"));
+
+        let page = crate::test_helpers::page().with_private();
+        let client = Client::with_page(StoreCookies(false), page).await;
+        let res = client.post("/preview").form(&form).send().await?;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        Ok(())
+    }
+}
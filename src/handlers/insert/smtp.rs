@@ -0,0 +1,436 @@
+use crate::access;
+use crate::db::write;
+use crate::handlers::insert::{default_expiration, filename, store_anonymous};
+use crate::mail::{Senders, message, relay};
+use crate::{AppState, Error, Page};
+use axum::extract::FromRef;
+use axum::http::StatusCode;
+use std::future::Future;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::net::TcpListener;
+
+/// Longest command line accepted, as the protocol requires.
+const MAX_LINE: usize = 1000;
+
+/// Most recipients accepted per message.
+const MAX_RECIPIENTS: usize = 100;
+
+/// Most pastes created from the parts of one message.
+const MAX_PASTES: usize = 10;
+
+/// Time a client has to send a command or the next line of a message.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Who may send mail and where replies are relayed to.
+#[derive(Clone, Debug)]
+pub(crate) struct Config {
+    pub senders: Arc<Senders>,
+    /// `host:port` of the SMTP server replies with the URLs are sent through
+    pub relay: Option<Arc<str>>,
+}
+
+/// Accept SMTP connections on `listener` until `shutdown` completes and store the text of each
+/// message from the allowed senders as pastes.
+pub(crate) async fn serve(
+    listener: TcpListener,
+    state: AppState,
+    config: Config,
+    max_body_size: usize,
+    shutdown: impl Future<Output = ()>,
+) {
+    tokio::pin!(shutdown);
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            () = &mut shutdown => return,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    tracing::warn!("failed to accept SMTP connection: {err}");
+                    continue;
+                }
+            },
+        };
+
+        let state = state.clone();
+        let config = config.clone();
+
+        tokio::spawn(async move {
+            let ip = peer.ip();
+
+            if let Err(err) = session(stream, ip, &state, &config, max_body_size).await {
+                tracing::debug!("SMTP session with {ip} failed: {err}");
+            }
+        });
+    }
+}
+
+/// Read a line of at most [`MAX_LINE`] bytes into `line`. Returns `false` if the client closed
+/// the connection or took longer than [`COMMAND_TIMEOUT`].
+async fn read_line(
+    reader: &mut (impl AsyncBufRead + Unpin),
+    line: &mut Vec<u8>,
+) -> std::io::Result<bool> {
+    line.clear();
+
+    let limit = u64::try_from(MAX_LINE).unwrap_or(u64::MAX);
+    let mut limited = (&mut *reader).take(limit);
+    let read = tokio::time::timeout(COMMAND_TIMEOUT, limited.read_until(b'\n', line));
+
+    match read.await {
+        Ok(read) => Ok(read? > 0),
+        Err(_) => Ok(false),
+    }
+}
+
+/// Address in the `<path>` after `prefix` of a `MAIL` or `RCPT` argument.
+fn path(argument: &str, prefix: &str) -> Option<String> {
+    let head = argument.get(..prefix.len())?;
+
+    if !head.eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+
+    let rest = argument[prefix.len()..].trim_start().strip_prefix('<')?;
+    let (address, _) = rest.split_once('>')?;
+
+    Some(address.to_string())
+}
+
+/// Talk SMTP with the client at `ip` on `stream` until it quits.
+async fn session(
+    stream: impl AsyncRead + AsyncWrite + Unpin,
+    ip: IpAddr,
+    state: &AppState,
+    config: &Config,
+    max_body_size: usize,
+) -> std::io::Result<()> {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+    let page = Page::from_ref(state);
+    let host = page.base_url.host_str().unwrap_or("localhost").to_string();
+
+    if !access::Filter::from_ref(state).access.create.permits(ip) {
+        writer
+            .write_all(format!("554 5.7.1 {}\r\n", Error::AddressDenied).as_bytes())
+            .await?;
+        return writer.shutdown().await;
+    }
+
+    writer
+        .write_all(format!("220 {host} wastebin ESMTP\r\n").as_bytes())
+        .await?;
+
+    // Base64 encoded attachments take a third more than the pastes they become.
+    let max_message = max_body_size.saturating_mul(2);
+    let mut sender: Option<String> = None;
+    let mut recipients = Vec::new();
+    let mut line = Vec::new();
+
+    while read_line(&mut reader, &mut line).await? {
+        if !line.ends_with(b"\n") {
+            writer.write_all(b"500 5.5.2 line too long\r\n").await?;
+            return writer.shutdown().await;
+        }
+
+        let command = String::from_utf8_lossy(&line).trim_end().to_string();
+        let (verb, argument) = command.split_once(' ').unwrap_or((&command, ""));
+
+        let reply = match verb.to_ascii_uppercase().as_str() {
+            "HELO" => format!("250 {host}"),
+            "EHLO" => format!("250-{host}\r\n250-8BITMIME\r\n250 SIZE {max_message}"),
+            "MAIL" if sender.is_some() => String::from("503 5.5.1 sender already given"),
+            "MAIL" => match path(argument, "FROM:") {
+                Some(address) if config.senders.knows(&address) => {
+                    sender = Some(address);
+                    String::from("250 2.1.0 OK")
+                }
+                Some(address) => {
+                    tracing::debug!("refused mail from {address:?} sent by {ip}");
+                    String::from("550 5.7.1 sender not allowed")
+                }
+                None => String::from("501 5.5.4 expected FROM:<address>"),
+            },
+            "RCPT" if sender.is_none() => String::from("503 5.5.1 sender required first"),
+            "RCPT" if recipients.len() >= MAX_RECIPIENTS => {
+                String::from("452 4.5.3 too many recipients")
+            }
+            "RCPT" => match (path(argument, "TO:"), sender.as_deref()) {
+                (Some(address), Some(sender)) if config.senders.authenticate(sender, &address) => {
+                    recipients.push(address);
+                    String::from("250 2.1.5 OK")
+                }
+                (Some(_), _) => {
+                    tracing::debug!(
+                        "refused recipient without the secret of the sender sent by {ip}"
+                    );
+                    String::from("550 5.7.1 recipient not allowed")
+                }
+                (None, _) => String::from("501 5.5.4 expected TO:<address>"),
+            },
+            "DATA" if recipients.is_empty() => String::from("503 5.5.1 recipient required first"),
+            "DATA" => {
+                writer
+                    .write_all(b"354 end data with <CR><LF>.<CR><LF>\r\n")
+                    .await?;
+
+                let Some(message) = data(&mut reader, &mut line, max_message).await? else {
+                    return Ok(());
+                };
+
+                let sender = sender.take().unwrap_or_default();
+                recipients.clear();
+
+                match message {
+                    Ok(message) => deliver(&message, ip, state, config, &sender, max_body_size)
+                        .await
+                        .unwrap_or_else(refusal),
+                    Err(err) => refusal(err),
+                }
+            }
+            "RSET" => {
+                sender = None;
+                recipients.clear();
+                String::from("250 2.0.0 OK")
+            }
+            "NOOP" => String::from("250 2.0.0 OK"),
+            "VRFY" => String::from("252 2.1.5 cannot verify"),
+            "QUIT" => {
+                writer.write_all(b"221 2.0.0 bye\r\n").await?;
+                return writer.shutdown().await;
+            }
+            _ => String::from("502 5.5.2 command not recognized"),
+        };
+
+        writer.write_all(reply.as_bytes()).await?;
+        writer.write_all(b"\r\n").await?;
+    }
+
+    Ok(())
+}
+
+/// Read the lines of a message up to the terminating dot line. Returns `None` if the client
+/// disappeared and an error if the message exceeds `max` bytes.
+async fn data(
+    reader: &mut (impl AsyncBufRead + Unpin),
+    line: &mut Vec<u8>,
+    max: usize,
+) -> std::io::Result<Option<Result<Vec<u8>, Error>>> {
+    let mut message = Vec::new();
+    let mut too_large = false;
+    // Long lines arrive in several pieces, only the first one may be a dot line.
+    let mut line_start = true;
+
+    loop {
+        if !read_line(reader, line).await? {
+            return Ok(None);
+        }
+
+        let starts_line = std::mem::replace(&mut line_start, line.ends_with(b"\n"));
+
+        if starts_line && matches!(line.as_slice(), b".\r\n" | b".\n") {
+            break;
+        }
+
+        // Drain oversized messages to stay in sync with the client.
+        if too_large {
+            continue;
+        }
+
+        let line = line.as_slice();
+        let unstuffed = if starts_line {
+            line.strip_prefix(b".").unwrap_or(line)
+        } else {
+            line
+        };
+
+        message.extend_from_slice(unstuffed);
+        too_large = message.len() > max;
+    }
+
+    if too_large {
+        return Ok(Some(Err(Error::PasteTooLarge(max))));
+    }
+
+    Ok(Some(Ok(message)))
+}
+
+/// Store the text parts of `message` sent by the authenticated `sender` as pastes and reply with
+/// their URLs.
+async fn deliver(
+    message: &[u8],
+    ip: IpAddr,
+    state: &AppState,
+    config: &Config,
+    sender: &str,
+    max_body_size: usize,
+) -> Result<String, Error> {
+    let page = Page::from_ref(state);
+    let message = message::parse(message);
+    let mut urls = Vec::new();
+
+    if message.parts.is_empty() {
+        return Err(Error::WrongSize);
+    }
+
+    for part in message.parts.into_iter().take(MAX_PASTES) {
+        if part.text.len() > max_body_size {
+            return Err(Error::PasteTooLarge(max_body_size));
+        }
+
+        let entry = write::Entry {
+            text: part.text,
+            expires: default_expiration(&page),
+            title: message.subject.clone(),
+            filename: part.filename.as_deref().and_then(filename),
+            ..Default::default()
+        };
+
+        urls.push(store_anonymous(state, ip, entry).await?);
+    }
+
+    tracing::debug!("stored {} pastes mailed by {sender}", urls.len());
+
+    if let Some(relay) = config.relay.clone().filter(|_| !message.auto_submitted) {
+        let host = page.base_url.host_str().unwrap_or("localhost").to_string();
+        // Not the address the message was sent to, which carries the secret of the sender.
+        let reply = message::reply(
+            &format!("wastebin@{host}"),
+            sender,
+            message.subject.as_deref(),
+            message.id.as_deref(),
+            &urls,
+        );
+        let sender = sender.to_string();
+
+        tokio::spawn(async move {
+            if let Err(err) = relay::send(&relay, &host, &sender, &reply).await {
+                tracing::warn!("failed to reply to {sender}: {err}");
+            }
+        });
+    }
+
+    let last = urls.len() - 1;
+
+    Ok(urls
+        .iter()
+        .enumerate()
+        .map(|(index, url)| {
+            let separator = if index == last { ' ' } else { '-' };
+            format!("250{separator}{url}")
+        })
+        .collect::<Vec<_>>()
+        .join("\r\n"))
+}
+
+/// Reply refusing a message because of `err`.
+fn refusal(err: Error) -> String {
+    let message = err.to_string();
+
+    match StatusCode::from(err) {
+        StatusCode::PAYLOAD_TOO_LARGE => format!("552 5.3.4 {message}"),
+        status if status.is_client_error() => format!("554 5.6.0 {message}"),
+        _ => format!("451 4.3.0 {message}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+    use crate::cache::Key;
+    use crate::db::Database;
+    use crate::db::read::Entry;
+    use crate::highlight::Highlighter;
+    use axum::extract::FromRef;
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::sync::Arc;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    /// Run a session sending `commands` and return the replies.
+    async fn talk(state: &crate::AppState, commands: &str) -> std::io::Result<Vec<String>> {
+        let config = Config {
+            senders: Arc::new("alerts@example.com=0123456789abcdef".parse().unwrap()),
+            relay: None,
+        };
+        let (client, server) = tokio::io::duplex(1 << 16);
+        let (reader, mut writer) = tokio::io::split(client);
+
+        writer.write_all(commands.as_bytes()).await?;
+        super::session(
+            server,
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+            state,
+            &config,
+            1024,
+        )
+        .await?;
+
+        let mut replies = Vec::new();
+        let mut lines = BufReader::new(reader).lines();
+
+        while let Some(line) = lines.next_line().await? {
+            replies.push(line);
+        }
+
+        Ok(replies)
+    }
+
+    #[tokio::test]
+    async fn mail_to_paste() -> Result<(), Box<dyn std::error::Error>> {
+        let state = crate::test_helpers::state(crate::test_helpers::page(), Highlighter::default());
+        let db = Database::from_ref(&state);
+
+        let replies = talk(
+            &state,
+            "EHLO monitoring\r\n\
+             MAIL FROM:<alice@example.com>\r\n\
+             MAIL FROM:<alerts@example.com> SIZE=100\r\n\
+             RCPT TO:<paste@localhost>\r\n\
+             RCPT TO:<paste+0123456789abcdef@localhost>\r\n\
+             DATA\r\n\
+             Subject: disk report\r\n\
+             \r\n\
+             ..hidden\r\n\
+             sda1 95%\r\n\
+             .\r\n\
+             QUIT\r\n",
+        )
+        .await?;
+
+        assert_eq!(replies[0], "220 localhost wastebin ESMTP");
+        assert_eq!(replies[3], "250 SIZE 2048");
+        assert_eq!(replies[4], "550 5.7.1 sender not allowed");
+        assert_eq!(replies[6], "550 5.7.1 recipient not allowed");
+        assert_eq!(replies[7], "250 2.1.5 OK");
+        assert_eq!(replies[8], "354 end data with <CR><LF>.<CR><LF>");
+        assert_eq!(replies[10], "221 2.0.0 bye");
+
+        let path = replies[9]
+            .strip_prefix("250 https://localhost:8888/")
+            .unwrap();
+        let key: Key = path.parse()?;
+        let Entry::Regular(data) = db.get(key.id, None, None).await? else {
+            panic!("paste not stored");
+        };
+        assert_eq!(data.text, ".hidden\nsda1 95%\n");
+        assert_eq!(data.title.as_deref(), Some("disk report"));
+
+        let replies = talk(
+            &state,
+            &format!(
+                "HELO monitoring\r\nMAIL FROM:<alerts@example.com>\r\nRCPT TO:<0123456789abcdef@localhost>\r\nDATA\r\n\r\n{}\r\n.\r\nQUIT\r\n",
+                "a".repeat(2100)
+            ),
+        )
+        .await?;
+
+        assert!(replies[5].starts_with("552 "));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+#[cfg(feature = "captcha")]
+use crate::Captcha;
+use crate::access;
+use crate::db::write;
+#[cfg(feature = "captcha")]
+use crate::handlers::insert::check_captcha_bypass;
+use crate::handlers::insert::{
+    check_creation, check_pow, check_terms, default_expiration, store_anonymous,
+};
+use crate::pow::Pow;
+use crate::{AppState, Error, Page};
+use axum::extract::FromRef;
+use std::future::Future;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::Instant;
+
+/// Time without new input after which the received text is stored, as `nc` does not necessarily
+/// close its side of the connection at the end of stdin.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Time a client has to send its paste.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Accept connections on `listener` until `shutdown` completes and store what each client sends
+/// as an anonymous paste with the default expiration, answering with its URL.
+pub(crate) async fn serve(
+    listener: TcpListener,
+    state: AppState,
+    max_body_size: usize,
+    shutdown: impl Future<Output = ()>,
+) {
+    tokio::pin!(shutdown);
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            () = &mut shutdown => return,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    tracing::warn!("failed to accept TCP connection: {err}");
+                    continue;
+                }
+            },
+        };
+
+        let state = state.clone();
+
+        tokio::spawn(async move {
+            handle(stream, peer.ip(), &state, max_body_size).await;
+        });
+    }
+}
+
+/// Store the text sent on `stream` from `ip` and write back its URL or the reason it was
+/// refused.
+async fn handle(mut stream: TcpStream, ip: IpAddr, state: &AppState, max_body_size: usize) {
+    let reply = match insert(&mut stream, ip, state, max_body_size).await {
+        Ok(url) => format!("{url}\n"),
+        Err(err) => {
+            tracing::debug!("refused TCP paste from {ip}: {err}");
+            format!("error: {err}\n")
+        }
+    };
+
+    if let Err(err) = stream.write_all(reply.as_bytes()).await {
+        tracing::debug!("failed to reply to {ip}: {err}");
+    }
+
+    let _ = stream.shutdown().await;
+}
+
+/// Read bytes from `reader` until it is closed, idles for [`IDLE_TIMEOUT`] after sending
+/// something or exceeds `max` bytes.
+async fn read(reader: &mut (impl AsyncRead + Unpin), max: usize) -> Result<Vec<u8>, Error> {
+    let deadline = Instant::now() + READ_TIMEOUT;
+    let mut data = Vec::new();
+    let mut buf = [0; 8192];
+
+    loop {
+        let idle = if data.is_empty() {
+            deadline
+        } else {
+            deadline.min(Instant::now() + IDLE_TIMEOUT)
+        };
+
+        let Ok(read) = tokio::time::timeout_at(idle, reader.read(&mut buf)).await else {
+            break;
+        };
+
+        let read = read.map_err(|_| Error::WrongSize)?;
+
+        if read == 0 {
+            break;
+        }
+
+        data.extend_from_slice(&buf[..read]);
+
+        if data.len() > max {
+            return Err(Error::PasteTooLarge(max));
+        }
+    }
+
+    if data.is_empty() {
+        return Err(Error::WrongSize);
+    }
+
+    Ok(data)
+}
+
+/// Check that an anonymous client at `ip` may create a paste, read it from `reader` and store it.
+/// Returns the URL of the new paste.
+async fn insert(
+    reader: &mut (impl AsyncRead + Unpin),
+    ip: IpAddr,
+    state: &AppState,
+    max_body_size: usize,
+) -> Result<String, Error> {
+    let page = Page::from_ref(state);
+    let filter = access::Filter::from_ref(state);
+
+    if !filter.access.create.permits(ip) {
+        return Err(Error::AddressDenied);
+    }
+
+    // Anonymous clients cannot accept terms, solve challenges or carry bypass tokens over a
+    // bare socket.
+    check_creation(&page, None)?;
+    check_terms(page.terms.is_some(), false)?;
+    check_pow(Option::<Pow>::from_ref(state).as_ref(), None, None)?;
+    #[cfg(feature = "captcha")]
+    check_captcha_bypass(
+        Option::<Captcha>::from_ref(state).as_ref(),
+        None,
+        &axum::http::HeaderMap::new(),
+    )?;
+
+    let text = String::from_utf8(read(reader, max_body_size).await?)
+        .map_err(|_| Error::IllegalCharacters)?;
+
+    let entry = write::Entry {
+        text,
+        expires: default_expiration(&page),
+        ..Default::default()
+    };
+
+    store_anonymous(state, ip, entry).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::Key;
+    use crate::db::Database;
+    use crate::db::read::Entry;
+    use crate::highlight::Highlighter;
+    use axum::extract::FromRef;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Send `text` to the listener at `addr` and return its reply.
+    async fn paste(addr: std::net::SocketAddr, text: &[u8]) -> std::io::Result<String> {
+        let mut stream = TcpStream::connect(addr).await?;
+        stream.write_all(text).await?;
+        stream.shutdown().await?;
+
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).await?;
+
+        Ok(reply)
+    }
+
+    #[tokio::test]
+    async fn netcat_upload() -> Result<(), Box<dyn std::error::Error>> {
+        let state = crate::test_helpers::state(crate::test_helpers::page(), Highlighter::default());
+        let db = Database::from_ref(&state);
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+
+        tokio::spawn(super::serve(
+            listener,
+            state,
+            16,
+            std::future::pending::<()>(),
+        ));
+
+        let reply = paste(addr, b"fn main() {}\n").await?;
+        let path = reply
+            .trim()
+            .strip_prefix("https://localhost:8888/")
+            .unwrap();
+        assert!(reply.ends_with('\n'));
+
+        let key: Key = path.parse()?;
+        let Entry::Regular(data) = db.get(key.id, None, None).await? else {
+            panic!("paste not stored");
+        };
+        assert_eq!(data.text, "fn main() {}\n");
+
+        let reply = paste(addr, &[b'a'; 17]).await?;
+        assert_eq!(reply, "error: paste exceeds the limit of 16 bytes\n");
+
+        let reply = paste(addr, &[0xff, 0xfe]).await?;
+        assert_eq!(reply, "error: illegal characters\n");
+
+        Ok(())
+    }
+}
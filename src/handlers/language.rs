@@ -0,0 +1,138 @@
+use crate::cache::Key;
+use crate::handlers::edit::{TokenQuery, owner};
+use crate::handlers::extract::{Theme, Uid};
+use crate::handlers::html::{ErrorResponse, make_error};
+use crate::keys::Keys;
+use crate::{Database, Error, Highlighter, Page};
+use axum::extract::{Form, Path, Query, State};
+use axum::response::Redirect;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct LanguageForm {
+    lang: String,
+}
+
+/// POST handler to change the stored language of a paste owned by the requesting user or shared
+/// with an edit token.
+#[expect(clippy::too_many_arguments)]
+pub async fn post(
+    Path(id): Path<String>,
+    Query(query): Query<TokenQuery>,
+    State(db): State<Database>,
+    State(page): State<Page>,
+    State(highlighter): State<Highlighter>,
+    State(keys): State<Keys>,
+    uid: Option<Uid>,
+    theme: Option<Theme>,
+    Form(form): Form<LanguageForm>,
+) -> Result<Redirect, ErrorResponse> {
+    async {
+        let key: Key = id.parse()?;
+        let uid = owner(&keys, key.id, query.token.as_deref(), uid)?;
+        let ext = highlighter
+            .extension_for(&form.lang)
+            .ok_or(Error::UnknownLanguage)?;
+
+        db.set_extension_for(key.id, uid, ext).await?;
+
+        Ok(Redirect::to(&format!("/{}", key.id_with_token())))
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::insert::api::RedirectResponse;
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn change_language() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(true)).await;
+        let data = Entry {
+            text: String::from("fn main() {}"),
+            extension: Some(String::from("txt")),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+        let id = location.trim_end_matches(".txt").to_string();
+
+        let form = HashMap::from([("lang", "Rust")]);
+        let res = client
+            .post(&format!("/lang{id}"))
+            .form(&form)
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client.get(&id).send().await?;
+        assert!(res.text().await?.contains(&format!("/raw{id}.rs")));
+
+        let other = Client::new(StoreCookies(false)).await;
+        let res = other.post(&format!("/lang{id}")).form(&form).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn change_language_with_token() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let entry = crate::db::write::Entry {
+            text: String::from("fn main() {}"),
+            extension: Some(String::from("txt")),
+            ..Default::default()
+        };
+
+        let res = client.post_json().json(&entry).send().await?;
+        let payload = res.json::<RedirectResponse>().await?;
+        let id = payload.path.trim_end_matches(".txt").to_string();
+        let token = payload.edit_token.unwrap();
+
+        let form = HashMap::from([("lang", "Rust")]);
+        let res = client
+            .post(&format!("/lang{id}?token={}", payload.delete_token))
+            .form(&form)
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client
+            .post(&format!("/lang{id}?token={token}"))
+            .form(&form)
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client.get(&id).send().await?;
+        assert!(res.text().await?.contains(&format!("/raw{id}.rs")));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn override_language() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let data = Entry {
+            text: String::from("fn main() {}"),
+            extension: Some(String::from("txt")),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+
+        let res = client.get(&format!("{location}?lang=rust")).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let content = res.text().await?;
+        assert!(content.contains("source rust"));
+
+        Ok(())
+    }
+}
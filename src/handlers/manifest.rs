@@ -0,0 +1,142 @@
+use crate::Page;
+use crate::assets::Asset;
+use axum::Json;
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use serde_json::{Value, json};
+
+/// Manifest entry of `icon`, with its size if it is a PNG image.
+fn icon(icon: &Asset) -> Value {
+    let mut entry = json!({
+        "src": icon.url(),
+        "type": icon.mime().as_ref(),
+    });
+
+    if let Some((width, height)) = icon.png_size() {
+        entry["sizes"] = Value::from(format!("{width}x{height}"));
+    } else if icon.mime().subtype() == mime::SVG {
+        entry["sizes"] = Value::from("any");
+    }
+
+    entry
+}
+
+/// GET handler for the web app manifest, so browsers can install wastebin with its icons and offer
+/// it as target for sharing text and files from other apps.
+pub async fn get(State(page): State<Page>) -> impl IntoResponse {
+    let icons = [Some(&page.assets.favicon), page.assets.touch_icon.as_ref()]
+        .into_iter()
+        .flatten()
+        .map(icon)
+        .collect::<Vec<_>>();
+
+    (
+        [(CONTENT_TYPE, "application/manifest+json")],
+        Json(json!({
+            "name": page.title,
+            "short_name": page.title,
+            "id": "/",
+            "start_url": "/",
+            "scope": "/",
+            "display": "standalone",
+            "icons": icons,
+            "share_target": {
+                "action": crate::csrf::SHARE,
+                "method": "POST",
+                "enctype": "multipart/form-data",
+                "params": {
+                    "title": "title",
+                    "text": "text",
+                    "url": "url",
+                    "files": [{
+                        "name": "file",
+                        "accept": ["text/*", "application/json", "application/xml", "application/x-sh"],
+                    }],
+                },
+            },
+        })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assets::{Asset, Kind};
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+
+    /// Header of a PNG image of `width` by `height` pixels.
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut content = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        content.extend(width.to_be_bytes());
+        content.extend(height.to_be_bytes());
+        content
+    }
+
+    #[tokio::test]
+    async fn icons() -> Result<(), Box<dyn std::error::Error>> {
+        let favicon = Asset::new_hashed("favicon", Kind::Svg, b"<svg/>".to_vec());
+        let touch_icon = Asset::new_hashed("touch-icon", Kind::Png, png(180, 180));
+        let page = crate::test_helpers::page()
+            .with_favicon(favicon)
+            .with_touch_icon(touch_icon);
+        let client = Client::with_page(StoreCookies(false), page).await;
+
+        let res = client.get("/manifest.webmanifest").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["content-type"], "application/manifest+json");
+
+        let manifest = res.json::<serde_json::Value>().await?;
+        let icons = manifest["icons"].as_array().unwrap();
+        assert_eq!(icons[0]["type"], "image/svg+xml");
+        assert_eq!(icons[0]["sizes"], "any");
+        assert_eq!(icons[1]["type"], "image/png");
+        assert_eq!(icons[1]["sizes"], "180x180");
+
+        let res = client.get(icons[1]["src"].as_str().unwrap()).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(
+            res.headers()["cache-control"]
+                .to_str()?
+                .contains("immutable")
+        );
+
+        let res = client.get("/favicon.ico").send().await?;
+        assert_eq!(res.headers()["content-type"], "image/svg+xml");
+        assert_eq!(res.text().await?, "<svg/>");
+
+        let content = client.get("/").send().await?.text().await?;
+        assert!(content.contains(r#"type="image/svg+xml""#));
+        assert!(content.contains(r#"<link rel="apple-touch-icon" href="/touch-icon."#));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn share_target() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        let manifest = client
+            .get("/manifest.webmanifest")
+            .send()
+            .await?
+            .json::<serde_json::Value>()
+            .await?;
+        assert_eq!(manifest["share_target"]["action"], "/share");
+        assert_eq!(manifest["share_target"]["enctype"], "multipart/form-data");
+        assert_eq!(
+            manifest["share_target"]["params"]["files"][0]["name"],
+            "file"
+        );
+
+        let res = client.get("/sw.js").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(
+            res.headers()["content-type"]
+                .to_str()?
+                .starts_with("application/javascript")
+        );
+
+        Ok(())
+    }
+}
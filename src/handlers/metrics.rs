@@ -0,0 +1,79 @@
+use crate::metrics::Registry;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+
+/// GET handler exposing metrics in the Prometheus text format.
+pub async fn get(State(registry): State<Registry>) -> Result<impl IntoResponse, StatusCode> {
+    let body = registry.render().await?;
+
+    Ok(([(CONTENT_TYPE, "text/plain; version=0.0.4")], body))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+
+    /// Value of the unlabeled metric `name`.
+    fn value(metrics: &str, name: &str) -> Option<u64> {
+        metrics
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(' ')?.parse().ok())
+    }
+
+    #[tokio::test]
+    async fn metrics() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::with_metrics(StoreCookies(false)).await;
+
+        let data = Entry {
+            text: String::from("FooBarBaz"),
+            ..Default::default()
+        };
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?.to_string();
+
+        client.get(&location).send().await?;
+        client.get(&location).send().await?;
+
+        let res = client.get("/metrics").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let metrics = res.text().await?;
+
+        assert_eq!(value(&metrics, "wastebin_pastes_created_total"), Some(1));
+        assert_eq!(value(&metrics, "wastebin_pastes"), Some(1));
+        assert!(value(&metrics, "wastebin_cache_hits_total") >= Some(1));
+        assert!(value(&metrics, "wastebin_database_size_bytes") > Some(0));
+        assert!(
+            metrics.contains(
+                r#"wastebin_http_requests_total{method="GET",route="/:id",status="200"} 2"#
+            )
+        );
+        assert!(metrics.contains(
+            r#"wastebin_http_request_duration_seconds_count{method="POST",route="/new"} 1"#
+        ));
+        assert!(
+            metrics.contains(r#"wastebin_http_route_group_duration_seconds_count{group="view"} 2"#)
+        );
+        assert!(
+            metrics
+                .contains(r#"wastebin_http_route_group_duration_seconds_count{group="upload"} 1"#)
+        );
+        assert!(metrics.contains(r#"wastebin_upload_size_bytes_bucket{route="/new",le="1024"} 1"#));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn disabled_by_default() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        let res = client.get("/metrics").send().await?;
+        assert_ne!(res.status(), StatusCode::OK);
+        assert!(!res.text().await?.contains("wastebin_pastes"));
+
+        Ok(())
+    }
+}
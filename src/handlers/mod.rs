@@ -1,7 +1,43 @@
+pub mod account;
+pub mod admin;
 pub mod delete;
 pub mod download;
+pub mod edit;
 pub mod extract;
+pub mod health;
 pub mod html;
+pub mod image;
 pub mod insert;
+pub mod language;
+pub mod manifest;
+pub mod metrics;
+#[cfg(feature = "oidc")]
+pub mod oidc;
+pub mod pdf;
+pub mod pow;
 pub mod raw;
+pub mod settings;
+pub mod sitemap;
+pub mod snippet;
+pub mod stats;
 pub mod theme;
+pub mod time;
+
+use axum::http::HeaderMap;
+
+/// Guess from the `Host` and `Origin` headers whether the request was made via HTTPS, so cookies
+/// can be marked as secure.
+// TODO: think about something more appropriate because those headers might be all messed up and
+// yet we still have a proper TLS connection.
+pub(crate) fn is_https(headers: &HeaderMap) -> bool {
+    headers
+        .get(http::header::HOST)
+        .zip(headers.get(http::header::ORIGIN))
+        .and_then(|(host, origin)| host.to_str().ok().zip(origin.to_str().ok()))
+        .and_then(|(host, origin)| {
+            origin
+                .strip_prefix("https://")
+                .map(|origin| origin.starts_with(host))
+        })
+        .unwrap_or(false)
+}
@@ -0,0 +1,237 @@
+use crate::handlers::account::start_session;
+use crate::handlers::extract::Theme;
+use crate::handlers::html::{ErrorResponse, make_error};
+use crate::handlers::is_https;
+use crate::{Database, Error, Oidc, Page, accounts};
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::Redirect;
+use axum_extra::extract::cookie::{Cookie, SameSite, SignedCookieJar};
+use serde::Deserialize;
+
+/// Name of the signed cookie carrying state and nonce of a pending login.
+const STATE_COOKIE: &str = "oidc";
+
+/// Path below which the state cookie is sent.
+const STATE_PATH: &str = "/login/oidc";
+
+/// Path the provider redirects back to after authentication.
+const CALLBACK_PATH: &str = "/login/oidc/callback";
+
+/// Query parameters the provider redirects back with.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Callback {
+    code: Option<String>,
+    state: String,
+    error: Option<String>,
+}
+
+/// GET handler redirecting to the provider to start a login.
+pub async fn login(
+    State(provider): State<Option<Oidc>>,
+    State(page): State<Page>,
+    jar: SignedCookieJar,
+    headers: HeaderMap,
+    theme: Option<Theme>,
+) -> Result<(SignedCookieJar, Redirect), ErrorResponse> {
+    async {
+        let provider = provider.ok_or(Error::NotFound)?;
+        let state = accounts::new_token();
+        let nonce = accounts::new_token();
+        let redirect_uri = page.base_url.join(CALLBACK_PATH)?;
+        let url = provider
+            .authorize_url(&redirect_uri, &state, &nonce)
+            .await?;
+
+        let cookie = Cookie::build((STATE_COOKIE, format!("{state}:{nonce}")))
+            .path(STATE_PATH)
+            .http_only(true)
+            .secure(is_https(&headers))
+            .same_site(SameSite::Lax)
+            .max_age(time::Duration::minutes(10))
+            .build();
+
+        Ok((jar.add(cookie), Redirect::to(url.as_str())))
+    }
+    .await
+    .map_err(|err| make_error(err, page.clone(), theme))
+}
+
+/// GET handler the provider redirects to, logging into the account linked to the user's subject
+/// and creating one on first login.
+pub async fn callback(
+    State(provider): State<Option<Oidc>>,
+    State(db): State<Database>,
+    State(page): State<Page>,
+    jar: SignedCookieJar,
+    headers: HeaderMap,
+    theme: Option<Theme>,
+    Query(callback): Query<Callback>,
+) -> Result<(SignedCookieJar, Redirect), ErrorResponse> {
+    async {
+        let provider = provider.ok_or(Error::NotFound)?;
+
+        if let Some(error) = callback.error {
+            return Err(Error::Oidc(error));
+        }
+
+        let (state, nonce) = jar
+            .get(STATE_COOKIE)
+            .and_then(|cookie| {
+                cookie
+                    .value_trimmed()
+                    .split_once(':')
+                    .map(|(state, nonce)| (state.to_string(), nonce.to_string()))
+            })
+            .ok_or(Error::OidcState)?;
+
+        if state != callback.state {
+            return Err(Error::OidcState);
+        }
+
+        let code = callback.code.ok_or(Error::OidcState)?;
+        let redirect_uri = page.base_url.join(CALLBACK_PATH)?;
+        let claims = provider.exchange(&code, &redirect_uri, &nonce).await?;
+        let issuer = provider.issuer().to_string();
+
+        let uid = if let Some(uid) = db.identity(issuer.clone(), claims.subject.clone()).await? {
+            uid
+        } else {
+            let uid = db.next_uid().await?;
+            db.create_identity(uid, claims.names, issuer, claims.subject)
+                .await?;
+            uid
+        };
+
+        let jar = jar.remove(Cookie::build(STATE_COOKIE).path(STATE_PATH));
+
+        start_session(&db, jar, &headers, uid).await
+    }
+    .await
+    .map_err(|err| make_error(err, page.clone(), theme))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{Client, StoreCookies};
+    use axum::extract::Form;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use base64::Engine;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use parking_lot::Mutex;
+    use reqwest::StatusCode;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    /// Spawn a provider that issues tokens for subject `1234` and the nonce of the last
+    /// authorization request.
+    async fn provider() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let nonce = Arc::new(Mutex::new(String::new()));
+
+        let discovery = {
+            let issuer = issuer.clone();
+            move || async move {
+                Json(serde_json::json!({
+                    "issuer": issuer,
+                    "authorization_endpoint": format!("{issuer}/authorize"),
+                    "token_endpoint": format!("{issuer}/token"),
+                }))
+            }
+        };
+
+        let token = {
+            let issuer = issuer.clone();
+            let nonce = nonce.clone();
+            move |Form(form): Form<HashMap<String, String>>| async move {
+                assert_eq!(form.get("code").map(String::as_str), Some("secret-code"));
+
+                let claims = serde_json::json!({
+                    "iss": issuer,
+                    "sub": "1234",
+                    "aud": "wastebin",
+                    "exp": time::OffsetDateTime::now_utc().unix_timestamp() + 60,
+                    "nonce": *nonce.lock(),
+                    "preferred_username": "alice",
+                });
+
+                let id_token = format!("e30.{}.", URL_SAFE_NO_PAD.encode(claims.to_string()));
+                Json(serde_json::json!({ "id_token": id_token }))
+            }
+        };
+
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/token", post(token))
+            .route(
+                "/nonce",
+                post(move |nonce_value: String| async move {
+                    *nonce.lock() = nonce_value;
+                }),
+            );
+
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        issuer
+    }
+
+    #[tokio::test]
+    async fn single_sign_on() -> Result<(), Box<dyn std::error::Error>> {
+        let issuer = provider().await;
+        let client = Client::with_oidc(StoreCookies(true), &issuer).await;
+
+        let res = client.get("/login").send().await?;
+        assert!(res.text().await?.contains("/login/oidc"));
+
+        for _ in 0..2 {
+            let res = client.get("/login/oidc").send().await?;
+            assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+            let location = url::Url::parse(res.headers().get("location").unwrap().to_str()?)?;
+            assert!(
+                location
+                    .as_str()
+                    .starts_with(&format!("{issuer}/authorize?"))
+            );
+            let query = location.query_pairs().collect::<HashMap<_, _>>();
+            assert_eq!(query["client_id"], "wastebin");
+
+            reqwest::Client::new()
+                .post(format!("{issuer}/nonce"))
+                .body(query["nonce"].to_string())
+                .send()
+                .await?;
+
+            let res = client
+                .get(&format!(
+                    "/login/oidc/callback?code=secret-code&state={}",
+                    query["state"]
+                ))
+                .send()
+                .await?;
+            assert_eq!(res.status(), StatusCode::SEE_OTHER);
+            assert_eq!(res.headers().get("location").unwrap(), "/pastes");
+
+            let res = client.get("/pastes").send().await?;
+            let content = res.text().await?;
+            assert!(content.contains("alice"));
+            assert!(!content.contains("alice-2"));
+
+            client.post("/logout").send().await?;
+        }
+
+        let res = client.get("/login/oidc").send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client
+            .get("/login/oidc/callback?code=secret-code&state=forged")
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+}
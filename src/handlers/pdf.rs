@@ -0,0 +1,162 @@
+use crate::cache::Key;
+use crate::db::read::Entry;
+use crate::handlers::extract::{self, CsrfToken, Mode, Password, Theme};
+use crate::handlers::html::{ErrorResponse, PasswordInput, make_error};
+use crate::{Database, Error, Highlighter, Page};
+use axum::extract::{Path, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum_extra::headers::HeaderValue;
+
+/// GET handler for a printable PDF document of a paste. Burn after reading pastes are refused,
+/// as exporting them would burn them.
+pub async fn get(
+    Path(id): Path<String>,
+    State(db): State<Database>,
+    State(page): State<Page>,
+    State(highlighter): State<Highlighter>,
+    theme: Option<Theme>,
+    CsrfToken(csrf): CsrfToken,
+    password: Option<Password>,
+) -> Result<Response, ErrorResponse> {
+    async {
+        let mut key: Key = id.parse()?;
+        let password = password.map(|Password(password)| password);
+
+        if db
+            .is_burn_after_reading(key.id, key.token.as_deref())
+            .await?
+        {
+            return Err(Error::BurnAfterReading);
+        }
+
+        let data = match db.get(key.id, key.token.as_deref(), password).await {
+            Ok(Entry::Regular(data)) => data,
+            Ok(Entry::Burned(_)) => return Err(Error::BurnAfterReading),
+            Ok(Entry::Expired) => return Err(Error::NotFound),
+            Err(Error::NoPassword) => {
+                return Ok(PasswordInput {
+                    page: page.clone(),
+                    theme: theme.clone(),
+                    id: key.id_with_token(),
+                    csrf,
+                }
+                .into_response());
+            }
+            Err(err) => return Err(err),
+        };
+
+        if !id.contains('.') {
+            if let Some(ext) = &data.extension {
+                key.ext.clone_from(ext);
+            }
+        }
+
+        // Paper is white, so always print with the light variant of the selected theme.
+        let theme = extract::Theme {
+            mode: Some(Mode::Light),
+            name: theme.clone().and_then(|theme| theme.name),
+            ..Default::default()
+        };
+
+        let colors = page
+            .assets
+            .css
+            .colors(Some(&theme))
+            .cloned()
+            .ok_or(Error::NotFound)?;
+
+        let title = data
+            .title
+            .clone()
+            .unwrap_or_else(|| format!("{}.{}", key.id(), key.ext));
+        let url = page.base_url.join(&id)?;
+        let filename = format!("{}.pdf", key.id());
+
+        let pdf = tokio::task::spawn_blocking(move || {
+            highlighter.pdf(&data.text, &key.ext, &colors, &title, url.as_str())
+        })
+        .await??;
+
+        let content_disposition =
+            HeaderValue::from_str(&format!(r#"inline; filename="{filename}""#))
+                .map_err(axum::http::Error::from)?;
+
+        Ok((
+            [
+                (
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static("application/pdf"),
+                ),
+                (header::CONTENT_DISPOSITION, content_disposition),
+            ],
+            pdf,
+        )
+            .into_response())
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::insert::form;
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::{StatusCode, header};
+
+    #[tokio::test]
+    async fn pdf_export() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        let data = form::Entry {
+            text: "fn main() {}\n".to_string(),
+            extension: Some("rs".to_string()),
+            title: "incident".to_string(),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+
+        let res = client.get(&format!("{location}/pdf")).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/pdf"
+        );
+
+        let content = res.bytes().await?;
+        let content = String::from_utf8_lossy(&content);
+        assert!(content.starts_with("%PDF-"));
+        assert!(content.contains("(incident)"));
+        assert!(content.contains(&format!("{location})")));
+
+        let res = client.get("/aaaaaa/pdf").send().await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn refuse_burn_after_reading() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        let data = form::Entry {
+            text: "fn main() {}\n".to_string(),
+            burn_after_reading: Some(String::from("on")),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+        let path = location.trim_start_matches("/burn");
+
+        let res = client.get(&format!("{path}/pdf")).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client.get(&format!("/raw{path}")).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use crate::errors::{Error, JsonErrorResponse};
+use crate::pow::{Challenge, Pow};
+use axum::Json;
+use axum::extract::State;
+
+/// GET handler issuing a proof-of-work challenge to solve before creating a paste.
+pub async fn get(State(pow): State<Option<Pow>>) -> Result<Json<Challenge>, JsonErrorResponse> {
+    let pow = pow.ok_or(Error::NotFound)?;
+
+    Ok(Json(pow.challenge()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+    use std::collections::HashMap;
+
+    /// Fetch a challenge from the server and solve it.
+    async fn solution(client: &Client) -> Result<String, Box<dyn std::error::Error>> {
+        let challenge = client
+            .get("/pow")
+            .send()
+            .await?
+            .json::<serde_json::Value>()
+            .await?;
+        let challenge = crate::pow::Challenge {
+            challenge: challenge["challenge"].as_str().unwrap().to_string(),
+            difficulty: u8::try_from(challenge["difficulty"].as_u64().unwrap())?,
+        };
+
+        Ok(crate::pow::solve(&challenge))
+    }
+
+    #[tokio::test]
+    async fn insert_with_pow() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::with_pow(StoreCookies(true), 8).await;
+
+        let res = client.get("/").send().await?;
+        let csp = res
+            .headers()
+            .get(reqwest::header::CONTENT_SECURITY_POLICY)
+            .unwrap();
+        assert!(csp.to_str()?.contains("connect-src 'self'"));
+
+        let content = res.text().await?;
+        assert!(content.contains(r#"data-pow="/pow""#));
+
+        let mut data = HashMap::from([("text", "FooBarBaz"), ("password", ""), ("title", "")]);
+        let res = client.post_form().form(&data).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let solved = solution(&client).await?;
+        data.insert("pow", &solved);
+        let res = client.post_form().form(&data).send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client.post_form().form(&data).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let json = serde_json::json!({ "text": "FooBarBaz" });
+        let res = client.post_json().json(&json).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let res = client
+            .post_json()
+            .header(crate::pow::HEADER, solution(&client).await?)
+            .json(&json)
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn no_challenges_by_default() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        let res = client.get("/pow").send().await?;
+        assert_ne!(res.status(), StatusCode::OK);
+
+        let content = client.get("/").send().await?.text().await?;
+        assert!(!content.contains("data-pow"));
+
+        Ok(())
+    }
+}
@@ -1,30 +1,50 @@
 use crate::cache::Key;
-use crate::db::read::Entry;
-use crate::handlers::extract::{Password, Theme};
+use crate::cache_control::NO_STORE;
+use crate::db::read::Content;
+use crate::handlers::extract::{CsrfToken, Password, Theme};
 use crate::handlers::html::{ErrorResponse, PasswordInput, make_error};
 use crate::{Database, Error, Page};
+use axum::body::Body;
 use axum::extract::{Path, State};
+use axum::http::header;
 use axum::response::{IntoResponse, Response};
 
+impl IntoResponse for Content {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Whole(text) => text.into_response(),
+            Self::Chunks(chunks) => (
+                [(header::CONTENT_TYPE, mime::TEXT_PLAIN_UTF_8.as_ref())],
+                Body::from_stream(chunks),
+            )
+                .into_response(),
+        }
+    }
+}
+
 /// GET handler for raw content of a paste.
 pub async fn get(
     Path(id): Path<String>,
     State(db): State<Database>,
     State(page): State<Page>,
     theme: Option<Theme>,
+    CsrfToken(csrf): CsrfToken,
     password: Option<Password>,
 ) -> Result<Response, ErrorResponse> {
     async {
         let password = password.map(|Password(password)| password);
         let key: Key = id.parse()?;
 
-        match db.get(key.id, password).await {
-            Ok(Entry::Regular(data) | Entry::Burned(data)) => Ok(data.text.into_response()),
-            Ok(Entry::Expired) => Err(Error::NotFound),
+        match db.stream(key.id, key.token.as_deref(), password).await {
+            Ok(streamed) if streamed.burned => {
+                Ok(([(header::CACHE_CONTROL, NO_STORE)], streamed.content).into_response())
+            }
+            Ok(streamed) => Ok(streamed.content.into_response()),
             Err(Error::NoPassword) => Ok(PasswordInput {
                 page: page.clone(),
                 theme: theme.clone(),
-                id: key.id.to_string(),
+                id: key.id_with_token(),
+                csrf,
             }
             .into_response()),
             Err(err) => Err(err),
@@ -0,0 +1,533 @@
+use crate::accounts::{self, Scope};
+use crate::db::read::ApiToken;
+use crate::db::write::Action;
+use crate::handlers::account::{SecondFactor, verify_code};
+use crate::handlers::extract::{CsrfToken, Session, Theme};
+use crate::handlers::html::{ErrorResponse, make_error, qr};
+use crate::{Database, Error, Page, totp};
+use askama::Template;
+use axum::extract::{Form, Path, State};
+use axum::response::{IntoResponse, Redirect, Response};
+use qrcodegen::{QrCode, QrCodeEcc};
+use serde::Deserialize;
+
+/// Form to create an API token.
+#[derive(Debug, Deserialize)]
+pub(crate) struct NewToken {
+    pub label: String,
+    pub scope: Scope,
+}
+
+/// Form to confirm setting up two-factor authentication.
+#[derive(Debug, Deserialize)]
+pub(crate) struct EnableTotp {
+    /// Base32 encoded secret shown during setup.
+    pub secret: String,
+    pub code: String,
+}
+
+/// New TOTP secret to add to an authenticator app.
+pub(crate) struct Setup {
+    /// Base32 encoded secret to enter by hand.
+    secret: String,
+    /// QR code of the provisioning URI.
+    code: QrCode,
+}
+
+/// Account settings page with the two-factor authentication status and the API tokens of the
+/// logged in user.
+#[derive(Template)]
+#[template(path = "settings.html")]
+pub(crate) struct Settings {
+    page: Page,
+    theme: Option<Theme>,
+    tokens: Vec<ApiToken>,
+    /// Secret of a token created just now, shown only once.
+    created: Option<String>,
+    /// Number of unused recovery codes if two-factor authentication is enabled.
+    totp: Option<usize>,
+    /// Secret to confirm while setting up two-factor authentication.
+    setup: Option<Setup>,
+    /// Recovery codes generated just now, shown only once.
+    recovery_codes: Vec<String>,
+    csrf: String,
+}
+
+impl Settings {
+    /// Load the settings of account `uid`.
+    async fn load(
+        db: &Database,
+        page: Page,
+        theme: Option<Theme>,
+        csrf: String,
+        uid: i64,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            page,
+            theme,
+            tokens: db.api_tokens(uid).await?,
+            created: None,
+            totp: db.totp(uid).await?.map(|(_, codes)| codes),
+            setup: None,
+            recovery_codes: Vec::new(),
+            csrf,
+        })
+    }
+
+    fn dark_modules(&self) -> Vec<(i32, i32)> {
+        self.setup
+            .as_ref()
+            .map(|setup| qr::dark_modules(&setup.code))
+            .unwrap_or_default()
+    }
+}
+
+/// Return the session if it is a login session, API tokens cannot change settings.
+fn login(session: Option<Session>) -> Result<Session, Error> {
+    session
+        .filter(|session| session.scope.is_none())
+        .ok_or(Error::NoSession)
+}
+
+/// GET handler for the settings page.
+pub async fn get(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+    CsrfToken(csrf): CsrfToken,
+) -> Result<Response, ErrorResponse> {
+    if session.is_none() {
+        return Ok(Redirect::to("/login").into_response());
+    }
+
+    async {
+        let session = login(session)?;
+
+        Ok(
+            Settings::load(&db, page.clone(), theme.clone(), csrf, session.uid)
+                .await?
+                .into_response(),
+        )
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// POST handler to create an API token and show it once.
+pub async fn create_token(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+    CsrfToken(csrf): CsrfToken,
+    Form(form): Form<NewToken>,
+) -> Result<Settings, ErrorResponse> {
+    async {
+        let session = login(session)?;
+        let label = form.label.trim().to_string();
+        accounts::validate_label(&label)?;
+
+        let token = accounts::new_api_token();
+        db.create_api_token(
+            session.uid,
+            accounts::token_hash(&token),
+            label.clone(),
+            form.scope,
+        )
+        .await?;
+        db.audit(Some(session.name), Action::CreateToken, Some(label))
+            .await?;
+
+        Ok(Settings {
+            created: Some(token),
+            ..Settings::load(&db, page.clone(), theme.clone(), csrf, session.uid).await?
+        })
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// POST handler to revoke an API token.
+pub async fn delete_token(
+    Path(id): Path<i64>,
+    State(db): State<Database>,
+    State(page): State<Page>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+) -> Result<Redirect, ErrorResponse> {
+    async {
+        let session = login(session)?;
+        let label = db.delete_api_token(id, session.uid).await?;
+        db.audit(Some(session.name), Action::RevokeToken, Some(label))
+            .await?;
+
+        Ok(Redirect::to("/settings"))
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// GET handler showing a new TOTP secret to set up two-factor authentication with.
+pub async fn totp_setup(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+    CsrfToken(csrf): CsrfToken,
+) -> Result<Response, ErrorResponse> {
+    async {
+        let session = login(session)?;
+        let settings = Settings::load(&db, page.clone(), theme.clone(), csrf, session.uid).await?;
+
+        if settings.totp.is_some() {
+            return Ok(Redirect::to("/settings").into_response());
+        }
+
+        let secret = totp::new_secret();
+        let name = db.user_name(session.uid).await?;
+        let uri = totp::uri(&secret, &page.title, &name);
+        let code = QrCode::encode_text(&uri, QrCodeEcc::Medium)?;
+
+        Ok(Settings {
+            setup: Some(Setup {
+                secret: totp::base32(&secret),
+                code,
+            }),
+            ..settings
+        }
+        .into_response())
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// POST handler to enable two-factor authentication once the first code for the new secret is
+/// entered. The recovery codes are shown once.
+pub async fn enable_totp(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+    CsrfToken(csrf): CsrfToken,
+    Form(form): Form<EnableTotp>,
+) -> Result<Settings, ErrorResponse> {
+    async {
+        let session = login(session)?;
+        let secret = totp::decode_base32(&form.secret).ok_or(Error::SecondFactor)?;
+        let step = totp::verify(&secret, &form.code, totp::now()).ok_or(Error::SecondFactor)?;
+
+        let recovery_codes = (0..totp::RECOVERY_CODES)
+            .map(|_| totp::new_recovery_code())
+            .collect::<Vec<_>>();
+        let hashes = recovery_codes
+            .iter()
+            .map(|code| accounts::token_hash(code))
+            .collect();
+
+        db.enable_totp(session.uid, secret, step, hashes).await?;
+        db.audit(Some(session.name), Action::EnableTotp, None)
+            .await?;
+
+        Ok(Settings {
+            recovery_codes,
+            ..Settings::load(&db, page.clone(), theme.clone(), csrf, session.uid).await?
+        })
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// POST handler to disable two-factor authentication, which requires a current TOTP or recovery
+/// code.
+pub async fn disable_totp(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    session: Option<Session>,
+    theme: Option<Theme>,
+    Form(form): Form<SecondFactor>,
+) -> Result<Redirect, ErrorResponse> {
+    async {
+        let session = login(session)?;
+        let (secret, _) = db.totp(session.uid).await?.ok_or(Error::NotFound)?;
+
+        if !verify_code(&db, session.uid, &secret, &form.code).await? {
+            return Err(Error::SecondFactor);
+        }
+
+        db.disable_totp(session.uid).await?;
+        db.audit(Some(session.name), Action::DisableTotp, None)
+            .await?;
+
+        Ok(Redirect::to("/settings"))
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::write::Entry;
+    use crate::test_helpers::{self, Client, StoreCookies};
+    use crate::totp;
+    use reqwest::StatusCode;
+    use reqwest::header::{AUTHORIZATION, LOCATION};
+
+    /// Create an API token with `scope` on the settings page and return its secret.
+    async fn create_token(
+        client: &Client,
+        scope: &str,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let res = client
+            .post("/settings/tokens")
+            .form(&[("label", "ci"), ("scope", scope)])
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let content = res.text().await?;
+        let start = content.find("<code>wb_").unwrap() + "<code>".len();
+        let end = start + content[start..].find('<').unwrap();
+
+        Ok(content[start..end].to_string())
+    }
+
+    #[tokio::test]
+    async fn api_tokens() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(true)).await;
+        let credentials = test_helpers::credentials("alice");
+
+        let res = client.get("/settings").send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        client.post("/register").form(&credentials).send().await?;
+
+        let manage = create_token(&client, "manage").await?;
+        let create = create_token(&client, "create").await?;
+        assert_ne!(manage, create);
+
+        let content = client.get("/settings").send().await?.text().await?;
+        assert_eq!(content.matches("<td>ci</td>").count(), 2);
+        assert!(!content.contains(&manage));
+
+        client.post("/logout").send().await?;
+
+        let entry = Entry {
+            text: String::from("hello"),
+            ..Default::default()
+        };
+        let bearer = |token: &str| format!("Bearer {token}");
+
+        let res = client
+            .post_json()
+            .header(AUTHORIZATION, bearer(&create))
+            .json(&entry)
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let path = res.json::<serde_json::Value>().await?["path"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        // Creating pastes does not allow deleting them.
+        client
+            .delete(&path)
+            .header(AUTHORIZATION, bearer(&create))
+            .send()
+            .await?;
+        let res = client.get(&format!("/raw{path}")).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let usage = client
+            .get("/usage")
+            .header(AUTHORIZATION, bearer(&manage))
+            .send()
+            .await?
+            .json::<serde_json::Value>()
+            .await?;
+        assert_eq!(usage["pastes"], 1);
+
+        let res = client
+            .get("/settings")
+            .header(AUTHORIZATION, bearer(&manage))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let res = client
+            .delete(&path)
+            .header(AUTHORIZATION, bearer(&manage))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = client.get(&format!("/raw{path}")).send().await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        client.post("/login").form(&credentials).send().await?;
+        let content = client.get("/settings").send().await?.text().await?;
+        assert!(!content.contains("<td>never</td>"));
+        let start = content.find("/settings/tokens/delete/").unwrap();
+        let action = &content[start..start + content[start..].find('"').unwrap()];
+        let res = client.post(action).send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        client.post("/logout").send().await?;
+
+        let res = client
+            .get("/usage")
+            .header(AUTHORIZATION, bearer(&create))
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        Ok(())
+    }
+
+    /// Return the text between `start` and `end` in `content`.
+    fn between<'a>(content: &'a str, start: &str, end: &str) -> &'a str {
+        let content = &content[content.find(start).unwrap() + start.len()..];
+        &content[..content.find(end).unwrap()]
+    }
+
+    /// Set up two-factor authentication on the settings page and return the secret and the
+    /// recovery codes.
+    async fn enable_totp(
+        client: &Client,
+    ) -> Result<(Vec<u8>, Vec<String>), Box<dyn std::error::Error>> {
+        let content = client.get("/settings/totp").send().await?.text().await?;
+        let secret = between(&content, "name=\"secret\" value=\"", "\"").to_string();
+        let key = totp::decode_base32(&secret).unwrap();
+
+        let res = client
+            .post("/settings/totp")
+            .form(&[("secret", secret.as_str()), ("code", "000000")])
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let code = format!("{:06}", totp::code(&key, totp::now()));
+        let res = client
+            .post("/settings/totp")
+            .form(&[("secret", secret.as_str()), ("code", &code)])
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let content = res.text().await?;
+        let recovery = between(&content, "<pre>", "</pre>")
+            .lines()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(recovery.len(), totp::RECOVERY_CODES);
+
+        Ok((key, recovery))
+    }
+
+    #[tokio::test]
+    async fn two_factor() -> Result<(), Box<dyn std::error::Error>> {
+        let page = test_helpers::page()
+            .with_admins(vec![String::from("alice")])
+            .with_admin_totp();
+        let client = Client::with_page(StoreCookies(true), page).await;
+        let credentials = test_helpers::credentials("alice");
+
+        client.post("/register").form(&credentials).send().await?;
+
+        let res = client.get("/admin").send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        let (key, recovery) = enable_totp(&client).await?;
+        let code = |step| format!("{:06}", totp::code(&key, step));
+        let now = totp::now();
+
+        client.post("/logout").send().await?;
+        let res = client.post("/login").form(&credentials).send().await?;
+        assert_eq!(res.headers().get(LOCATION).unwrap(), "/login/totp");
+
+        // The session is not valid before the second factor is given.
+        let res = client.get("/pastes").send().await?;
+        assert_eq!(res.headers().get(LOCATION).unwrap(), "/login");
+
+        let res = client
+            .post("/login/totp")
+            .form(&[("code", code(now + 1))])
+            .send()
+            .await?;
+        assert_eq!(res.headers().get(LOCATION).unwrap(), "/pastes");
+
+        let res = client.get("/admin").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // Codes cannot be replayed.
+        client.post("/logout").send().await?;
+        client.post("/login").form(&credentials).send().await?;
+        let res = client
+            .post("/login/totp")
+            .form(&[("code", code(now + 1))])
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let res = client
+            .post("/login/totp")
+            .form(&[("code", &recovery[0])])
+            .send()
+            .await?;
+        assert_eq!(res.headers().get(LOCATION).unwrap(), "/pastes");
+
+        let content = client.get("/settings").send().await?.text().await?;
+        assert!(content.contains("9 recovery codes left"));
+
+        // Too many wrong codes cancel a pending login.
+        client.post("/logout").send().await?;
+        client.post("/login").form(&credentials).send().await?;
+
+        for _ in 0..crate::accounts::MAX_ATTEMPTS {
+            let res = client
+                .post("/login/totp")
+                .form(&[("code", "wrong")])
+                .send()
+                .await?;
+            assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        let res = client
+            .post("/login/totp")
+            .form(&[("code", &recovery[1])])
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let res = client.get("/settings").send().await?;
+        assert_eq!(res.headers().get(LOCATION).unwrap(), "/login");
+
+        client.post("/login").form(&credentials).send().await?;
+        let res = client
+            .post("/login/totp")
+            .form(&[("code", &recovery[1])])
+            .send()
+            .await?;
+        assert_eq!(res.headers().get(LOCATION).unwrap(), "/pastes");
+
+        let res = client
+            .post("/settings/totp/disable")
+            .form(&[("code", &recovery[0])])
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let res = client
+            .post("/settings/totp/disable")
+            .form(&[("code", &recovery[2])])
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let content = client.get("/settings").send().await?.text().await?;
+        assert!(content.contains("Two-factor authentication is disabled"));
+
+        let res = client.get("/admin").send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        Ok(())
+    }
+}
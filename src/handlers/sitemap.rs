@@ -0,0 +1,258 @@
+use crate::db::read::Listing;
+use crate::handlers::extract::Theme;
+use crate::handlers::html::{ErrorResponse, make_error};
+use crate::{Database, Error, Page};
+use axum::extract::{Path, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::response::{IntoResponse, Response};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Number of paste URLs per sitemap, well below the 50,000 allowed.
+const PAGE_SIZE: usize = 10_000;
+
+/// How long the index and the last, still growing sitemap are served before they are generated
+/// again.
+const TTL: Duration = Duration::from_secs(600);
+
+/// How long full sitemaps are served before they are generated again, to drop deleted and expired
+/// pastes.
+const FULL_TTL: Duration = Duration::from_secs(86400);
+
+/// Sitemap XML and when it was generated.
+struct Generated {
+    at: Instant,
+    ttl: Duration,
+    xml: Arc<str>,
+}
+
+/// Sitemaps of public pastes, each generated at most once per [`TTL`] or [`FULL_TTL`] for full
+/// ones, so that new pastes only regenerate the index and the last sitemap.
+#[derive(Clone, Default)]
+pub(crate) struct Sitemaps {
+    index: Arc<Mutex<Option<Generated>>>,
+    pages: Arc<Mutex<HashMap<usize, Generated>>>,
+}
+
+impl Generated {
+    fn fresh(&self) -> Option<Arc<str>> {
+        (self.at.elapsed() < self.ttl).then(|| Arc::clone(&self.xml))
+    }
+}
+
+impl Sitemaps {
+    /// Index listing one sitemap per [`PAGE_SIZE`] public pastes.
+    async fn index(&self, db: &Database, page: &Page) -> Result<Arc<str>, Error> {
+        if let Some(xml) = self.index.lock().as_ref().and_then(Generated::fresh) {
+            return Ok(xml);
+        }
+
+        let pages = db.public_count().await?.div_ceil(PAGE_SIZE).max(1);
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8"?><sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#,
+        );
+
+        for number in 1..=pages {
+            let _ = write!(
+                xml,
+                "<sitemap><loc>{}</loc></sitemap>",
+                escape(&url(page, &format!("sitemap/{number}.xml")))
+            );
+        }
+
+        xml.push_str("</sitemapindex>");
+
+        let xml: Arc<str> = xml.into();
+        *self.index.lock() = Some(Generated {
+            at: Instant::now(),
+            ttl: TTL,
+            xml: Arc::clone(&xml),
+        });
+
+        Ok(xml)
+    }
+
+    /// Sitemap with the URLs of the `number`th [`PAGE_SIZE`] public pastes, oldest first.
+    async fn page(&self, db: &Database, page: &Page, number: usize) -> Result<Arc<str>, Error> {
+        if let Some(xml) = self.pages.lock().get(&number).and_then(Generated::fresh) {
+            return Ok(xml);
+        }
+
+        let offset = number
+            .checked_sub(1)
+            .and_then(|index| index.checked_mul(PAGE_SIZE))
+            .filter(|offset| i64::try_from(*offset).is_ok())
+            .ok_or(Error::NotFound)?;
+        let listings = db.public(offset, PAGE_SIZE).await?;
+
+        if listings.is_empty() && number > 1 {
+            return Err(Error::NotFound);
+        }
+
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8"?><urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#,
+        );
+
+        for listing in &listings {
+            write_url(&mut xml, page, listing);
+        }
+
+        xml.push_str("</urlset>");
+
+        let xml: Arc<str> = xml.into();
+        let ttl = if listings.len() == PAGE_SIZE {
+            FULL_TTL
+        } else {
+            TTL
+        };
+
+        let mut pages = self.pages.lock();
+        pages.retain(|_, generated| generated.at.elapsed() < generated.ttl);
+        pages.insert(
+            number,
+            Generated {
+                at: Instant::now(),
+                ttl,
+                xml: Arc::clone(&xml),
+            },
+        );
+
+        Ok(xml)
+    }
+}
+
+/// Absolute URL of `path` below the base URL.
+fn url(page: &Page, path: &str) -> String {
+    page.base_url
+        .join(path)
+        .map_or_else(|_| format!("/{path}"), String::from)
+}
+
+/// Append the `<url>` element of `listing`, modified last when it was created.
+fn write_url(xml: &mut String, page: &Page, listing: &Listing) {
+    let _ = write!(
+        xml,
+        "<url><loc>{}</loc>",
+        escape(&url(page, &listing.id.to_string()))
+    );
+
+    if let Some(date) = listing.created.as_deref().and_then(|c| c.get(..10)) {
+        let _ = write!(xml, "<lastmod>{}</lastmod>", escape(date));
+    }
+
+    xml.push_str("</url>");
+}
+
+/// Escape `text` for XML character data.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Response with the sitemap `xml`.
+fn xml(xml: &str) -> Response {
+    ([(CONTENT_TYPE, "application/xml")], xml.to_owned()).into_response()
+}
+
+/// GET handler for the sitemap index. Not found unless enabled and pastes may be indexed.
+pub async fn index(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    State(sitemaps): State<Sitemaps>,
+    theme: Option<Theme>,
+) -> Result<Response, ErrorResponse> {
+    async {
+        if !page.sitemap || page.noindex {
+            return Err(Error::NotFound);
+        }
+
+        Ok(xml(&sitemaps.index(&db, &page).await?))
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+/// GET handler for the sitemap `<number>.xml` listed in the index.
+pub async fn page(
+    Path(name): Path<String>,
+    State(db): State<Database>,
+    State(page): State<Page>,
+    State(sitemaps): State<Sitemaps>,
+    theme: Option<Theme>,
+) -> Result<Response, ErrorResponse> {
+    async {
+        if !page.sitemap || page.noindex {
+            return Err(Error::NotFound);
+        }
+
+        let number = name
+            .strip_suffix(".xml")
+            .and_then(|number| number.parse::<usize>().ok())
+            .filter(|number| *number > 0)
+            .ok_or(Error::NotFound)?;
+
+        Ok(xml(&sitemaps.page(&db, &page, number).await?))
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+
+    #[tokio::test]
+    async fn sitemap() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let res = client.get("/sitemap.xml").send().await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let page = crate::test_helpers::page().with_sitemap();
+        let client = Client::with_page(StoreCookies(false), page).await;
+
+        let data = Entry {
+            text: String::from("public"),
+            ..Default::default()
+        };
+        let res = client.post_form().form(&data).send().await?;
+        let public = res.headers().get("location").unwrap().to_str()?.to_string();
+
+        let data = Entry {
+            text: String::from("secret"),
+            secret: Some(String::from("on")),
+            ..Default::default()
+        };
+        let res = client.post_form().form(&data).send().await?;
+        let secret = res.headers().get("location").unwrap().to_str()?.to_string();
+        let (secret, _) = secret.split_once('_').unwrap();
+
+        let res = client.get("/sitemap.xml").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["content-type"], "application/xml");
+        assert!(res.text().await?.contains("/sitemap/1.xml</loc>"));
+
+        let res = client.get("/sitemap/1.xml").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let content = res.text().await?;
+        assert!(content.contains(&format!("{public}</loc><lastmod>")));
+        assert!(!content.contains(&format!("{secret}</loc>")));
+
+        let res = client.get("/sitemap/2.xml").send().await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        for number in [usize::MAX, usize::MAX / super::PAGE_SIZE] {
+            let res = client.get(&format!("/sitemap/{number}.xml")).send().await?;
+            assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        }
+
+        Ok(())
+    }
+}
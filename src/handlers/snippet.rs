@@ -0,0 +1,104 @@
+use crate::cache::Key;
+use crate::cache_control::NO_STORE;
+use crate::handlers::extract::Session;
+use crate::handlers::html::paste::render;
+use crate::highlight::View;
+use crate::{Cache, Database, Error, Highlighter};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::http::header::CACHE_CONTROL;
+use axum::response::{Html, IntoResponse};
+
+/// GET handler for the first highlighted lines of a paste, fetched by listings once the paste
+/// scrolls into view. Only the owner and admins see it, and burn after reading pastes are not
+/// burned by it.
+pub async fn get(
+    Path(id): Path<String>,
+    State(db): State<Database>,
+    State(cache): State<Cache>,
+    State(highlighter): State<Highlighter>,
+    session: Option<Session>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let session = session.ok_or(Error::LoginRequired)?;
+    let uid = (!session.roles.admin).then_some(session.uid);
+
+    let mut key: Key = id.parse()?;
+    let data = db.get_for(key.id, uid).await?;
+
+    if let Some(ext) = &data.extension {
+        key.ext.clone_from(ext);
+    }
+
+    key.view = View::Snippet;
+
+    let (html, _) = render(&cache, &highlighter, &key, data, true).await?;
+
+    Ok((
+        [(CACHE_CONTROL, NO_STORE)],
+        Html(format!(
+            r#"<div class="paste snippet">{}</div>"#,
+            html.into_inner()
+        )),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{Client, StoreCookies, credentials};
+    use reqwest::StatusCode;
+
+    #[tokio::test]
+    async fn snippet() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(true)).await;
+
+        client
+            .post("/register")
+            .form(&credentials("alice"))
+            .send()
+            .await?;
+
+        let data = Entry {
+            text: "fn main() {}\n".repeat(10),
+            extension: Some(String::from("rs")),
+            burn_after_reading: Some(String::from("on")),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?;
+        let id = location
+            .trim_start_matches("/burn/")
+            .split('.')
+            .next()
+            .unwrap();
+
+        let content = client.get("/pastes").send().await?.text().await?;
+        assert!(content.contains(&format!(r#"data-snippet="/snippet/{id}""#)));
+
+        for _ in 0..2 {
+            let res = client.get(&format!("/snippet/{id}")).send().await?;
+            assert_eq!(res.status(), StatusCode::OK);
+
+            let content = res.text().await?;
+            assert!(content.starts_with(r#"<div class="paste snippet">"#));
+            assert!(content.contains("source rust"));
+            assert!(content.contains(r#"id="L5""#));
+            assert!(!content.contains(r#"id="L6""#));
+        }
+
+        client.post("/logout").send().await?;
+        let res = client.get(&format!("/snippet/{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        client
+            .post("/register")
+            .form(&credentials("bob"))
+            .send()
+            .await?;
+        let res = client.get(&format!("/snippet/{id}")).send().await?;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+use crate::db::read::{Month, Share};
+use crate::handlers::admin::{StatsQuery, wants_json};
+use crate::handlers::extract::Theme;
+use crate::handlers::html::{ErrorResponse, make_error};
+use crate::{Database, Error, Highlighter, Page};
+use askama::Template;
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long statistics are served before they are gathered again.
+const TTL: Duration = Duration::from_secs(600);
+
+/// Number of most common languages shown by month, the others are summed up.
+const TOP: usize = 8;
+
+/// Pastes by language, all time and by month of creation.
+#[derive(Debug, Serialize)]
+pub(crate) struct Report {
+    /// Pastes by extension, most common first
+    languages: Vec<Share>,
+    /// Pastes by month and extension, most recent month first
+    months: Vec<Month>,
+}
+
+/// Report and when it was gathered.
+struct Gathered {
+    at: Instant,
+    report: Arc<Report>,
+}
+
+/// Language statistics gathered at most once per [`TTL`], as they read all stored pastes.
+#[derive(Clone, Default)]
+pub(crate) struct Languages {
+    cached: Arc<Mutex<Option<Gathered>>>,
+}
+
+/// Page showing the language [`Report`].
+#[derive(Template)]
+#[template(path = "stats_languages.html")]
+pub(crate) struct LanguagesPage {
+    page: Page,
+    theme: Option<Theme>,
+    highlighter: Highlighter,
+    report: Arc<Report>,
+}
+
+impl Report {
+    fn new(months: Vec<Month>) -> Self {
+        let mut totals = HashMap::<&str, (usize, usize)>::new();
+
+        for share in months.iter().flat_map(|month| &month.languages) {
+            let total = totals.entry(&share.label).or_default();
+            total.0 += share.pastes;
+            total.1 += share.bytes;
+        }
+
+        let mut languages = totals
+            .into_iter()
+            .map(|(label, (pastes, bytes))| Share {
+                label: label.to_string(),
+                pastes,
+                bytes,
+            })
+            .collect::<Vec<_>>();
+
+        languages.sort_by(|a, b| b.pastes.cmp(&a.pastes).then_with(|| a.label.cmp(&b.label)));
+
+        Self { languages, months }
+    }
+}
+
+impl Languages {
+    /// Current statistics, gathered anew from `db` if the cached ones are older than [`TTL`].
+    async fn get(&self, db: &Database) -> Result<Arc<Report>, Error> {
+        if let Some(gathered) = &*self.cached.lock() {
+            if gathered.at.elapsed() < TTL {
+                return Ok(Arc::clone(&gathered.report));
+            }
+        }
+
+        let report = Arc::new(Report::new(db.language_history().await?));
+        *self.cached.lock() = Some(Gathered {
+            at: Instant::now(),
+            report: Arc::clone(&report),
+        });
+
+        Ok(report)
+    }
+}
+
+impl LanguagesPage {
+    /// Most common languages, shown by month.
+    fn top(&self) -> &[Share] {
+        &self.report.languages[..self.report.languages.len().min(TOP)]
+    }
+
+    /// Name of the language with extension `label`.
+    fn name(&self, label: &str) -> String {
+        if label.is_empty() {
+            return crate::i18n::t("plain-text");
+        }
+
+        self.highlighter.syntax_name(label).to_string()
+    }
+
+    /// Number of pastes created in `month` with extension `label`.
+    fn pastes(month: &Month, label: &str) -> usize {
+        month
+            .languages
+            .iter()
+            .find(|share| share.label == label)
+            .map_or(0, |share| share.pastes)
+    }
+
+    /// Number of pastes created in `month` in languages other than the [`Self::top`] ones.
+    fn other(&self, month: &Month) -> usize {
+        let top = self.top();
+
+        month
+            .languages
+            .iter()
+            .filter(|share| top.iter().all(|top| top.label != share.label))
+            .map(|share| share.pastes)
+            .sum()
+    }
+}
+
+/// GET handler for the language statistics, as JSON if asked for with `Accept: application/json`
+/// or `?format=json`. Not found unless enabled.
+pub async fn languages(
+    State(db): State<Database>,
+    State(page): State<Page>,
+    State(highlighter): State<Highlighter>,
+    State(languages): State<Languages>,
+    theme: Option<Theme>,
+    headers: HeaderMap,
+    Query(query): Query<StatsQuery>,
+) -> Result<Response, ErrorResponse> {
+    async {
+        if !page.language_stats {
+            return Err(Error::NotFound);
+        }
+
+        let report = languages.get(&db).await?;
+
+        if wants_json(&query, &headers) {
+            return Ok(Json(report.as_ref()).into_response());
+        }
+
+        Ok(LanguagesPage {
+            page: page.clone(),
+            theme: theme.clone(),
+            highlighter,
+            report,
+        }
+        .into_response())
+    }
+    .await
+    .map_err(|err| make_error(err, page, theme))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+
+    #[tokio::test]
+    async fn languages() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let res = client.get("/stats/languages").send().await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let page = crate::test_helpers::page().with_language_stats();
+        let client = Client::with_page(StoreCookies(false), page).await;
+
+        for extension in ["rs", "rs", "py"] {
+            let data = Entry {
+                text: String::from("fn main() {}"),
+                extension: Some(String::from(extension)),
+                ..Default::default()
+            };
+            client.post_form().form(&data).send().await?;
+        }
+
+        let report = client
+            .get("/stats/languages?format=json")
+            .send()
+            .await?
+            .json::<serde_json::Value>()
+            .await?;
+        assert_eq!(report["languages"][0]["label"], "rs");
+        assert_eq!(report["languages"][0]["pastes"], 2);
+        assert_eq!(report["months"][0]["languages"][1]["label"], "py");
+
+        let res = client.get("/stats/languages").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        let content = res.text().await?;
+        assert!(content.contains("<th>Rust</th>"));
+        assert!(content.contains("<td>Python</td>"));
+
+        // Served from the cache until it expires.
+        let data = Entry {
+            text: String::from("print()"),
+            extension: Some(String::from("py")),
+            ..Default::default()
+        };
+        client.post_form().form(&data).send().await?;
+
+        let report = client
+            .get("/stats/languages")
+            .header("accept", "application/json")
+            .send()
+            .await?
+            .json::<serde_json::Value>()
+            .await?;
+        assert_eq!(report["languages"][1]["pastes"], 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn languages_without_accounts() -> Result<(), Box<dyn std::error::Error>> {
+        let mut page = crate::test_helpers::page().with_language_stats();
+        page.accounts = false;
+        let client = Client::with_page(StoreCookies(false), page).await;
+
+        let res = client.get("/stats/languages").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        Ok(())
+    }
+}
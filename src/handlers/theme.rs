@@ -1,15 +1,272 @@
-use crate::handlers::extract::Preference;
-use axum::extract::Query;
+use crate::Page;
+use crate::handlers::extract::{Preference, Switch};
+use crate::i18n::{COOKIE, Catalog};
+use axum::extract::{Query, State};
 use axum::http::HeaderMap;
-use axum::response::{IntoResponse, Redirect};
+use axum::response::{AppendHeaders, IntoResponse, Redirect};
 use http::header::{REFERER, SET_COOKIE};
 
-/// GET handler to switch theme by setting the pref cookie and redirecting back to the referer.
-pub async fn get(headers: HeaderMap, Query(pref): Query<Preference>) -> impl IntoResponse {
+/// GET handler to switch the mode, theme, contrast, layout or locale by setting the `pref`,
+/// `theme`, `contrast`, `wrap`, `lines` or `locale` cookie and redirecting back to the referer.
+pub async fn get(
+    State(page): State<Page>,
+    State(catalog): State<Catalog>,
+    headers: HeaderMap,
+    Query(pref): Query<Preference>,
+) -> impl IntoResponse {
     let response = headers
         .get(REFERER)
         .and_then(|referer| referer.to_str().ok())
         .map_or_else(|| Redirect::to("/"), Redirect::to);
 
-    ([(SET_COOKIE, format!("pref={}", pref.pref))], response)
+    let mode = pref.pref.map(|switch| {
+        let cookie = match switch {
+            Switch::Dark => String::from("pref=dark"),
+            Switch::Light => String::from("pref=light"),
+            Switch::System => String::from("pref=; Max-Age=0"),
+        };
+
+        (SET_COOKIE, cookie)
+    });
+
+    let theme = pref
+        .theme
+        .filter(|name| page.assets.css.themes.contains_key(name))
+        .map(|name| (SET_COOKIE, format!("theme={name}; Path=/")));
+
+    let locale = pref
+        .locale
+        .filter(|tag| catalog.get(tag).is_some())
+        .map(|tag| (SET_COOKIE, format!("{COOKIE}={tag}; Path=/")));
+
+    let toggle = |name: &str, value: bool| {
+        let value = if value { "on" } else { "off" };
+        (SET_COOKIE, format!("{name}={value}; Path=/"))
+    };
+
+    let contrast = pref.contrast.map(|contrast| toggle("contrast", contrast));
+    let wrap = pref.wrap.map(|wrap| toggle("wrap", wrap));
+    let lines = pref.lines.map(|lines| toggle("lines", lines));
+
+    (
+        AppendHeaders(
+            mode.into_iter()
+                .chain(theme)
+                .chain(locale)
+                .chain(contrast)
+                .chain(wrap)
+                .chain(lines),
+        ),
+        response,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+
+    #[tokio::test]
+    async fn select_theme() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(true)).await;
+
+        let res = client.get("/").send().await?;
+        assert!(res.text().await?.contains(r#"href="/ayu-dark."#));
+
+        let res = client.get("/theme?theme=nord").send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client.get("/").send().await?;
+        let content = res.text().await?;
+        assert!(content.contains(r#"href="/nord-dark."#));
+        assert!(!content.contains(r#"href="/ayu-dark."#));
+
+        let res = client.get("/?theme=dracula").send().await?;
+        assert!(res.text().await?.contains(r#"href="/dracula-dark."#));
+
+        let res = client.get("/theme?theme=unknown").send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let res = client.get("/").send().await?;
+        assert!(res.text().await?.contains(r#"href="/nord-dark."#));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn external_assets() -> Result<(), Box<dyn std::error::Error>> {
+        let base = url::Url::parse("https://cdn.example.com/static/")?;
+        let page = crate::test_helpers::page().with_asset_url(&base);
+        let client = Client::with_page(StoreCookies(false), page).await;
+
+        let res = client.get("/").send().await?;
+        let csp = res.headers().get("content-security-policy").unwrap();
+        assert!(
+            csp.to_str()?
+                .contains("script-src 'self' https://cdn.example.com;")
+        );
+
+        let content = res.text().await?;
+        assert!(content.contains(r#"href="https://cdn.example.com/static/ayu-dark."#));
+        assert!(content.contains(r#"src="https://cdn.example.com/static/base."#));
+        assert!(!content.contains(r#"src="/base."#));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn branding() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::assets::{Custom, Source};
+
+        let css = Custom::Local(crate::assets::Asset::new_hashed(
+            "custom",
+            crate::assets::Kind::Css,
+            b"header { color: red; }".to_vec(),
+        ));
+        let logo = Custom::load(
+            "logo",
+            Source::Url(url::Url::parse("https://img.example.com/logo.svg")?),
+            &crate::assets::IMAGES,
+        )?;
+
+        let page = crate::test_helpers::page()
+            .with_custom_css(css)
+            .with_logo(logo);
+        let client = Client::with_page(StoreCookies(false), page).await;
+
+        let res = client.get("/").send().await?;
+        let csp = res
+            .headers()
+            .get("content-security-policy")
+            .unwrap()
+            .to_str()?;
+        let directive = |name: &str| {
+            csp.split(';')
+                .map(str::trim)
+                .find(|directive| directive.starts_with(name))
+                .map(|directive| directive.split_whitespace().collect::<Vec<_>>().join(" "))
+        };
+        assert_eq!(
+            directive("img-src").as_deref(),
+            Some("img-src 'self' data: https://img.example.com")
+        );
+        assert_eq!(
+            directive("style-src").as_deref(),
+            Some("style-src 'self' data:")
+        );
+
+        let content = res.text().await?;
+        assert!(content.contains(r#"<img class="logo" src="https://img.example.com/logo.svg""#));
+
+        let href = content
+            .split(r#"<link rel="stylesheet" href=""#)
+            .filter_map(|link| link.split('"').next())
+            .find(|href| href.starts_with("/custom."))
+            .unwrap()
+            .to_string();
+
+        let res = client.get(&href).send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.text().await?, "header { color: red; }");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn high_contrast() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(true)).await;
+
+        let content = client.get("/").send().await?.text().await?;
+        assert!(content.contains(r##"<a href="#content" class="skip-link">"##));
+        assert!(content.contains(r#"<main id="content""#));
+        assert!(content.contains(r#"href="/theme?contrast=true""#));
+        assert!(content.contains(r#"id="expiration-list" aria-label="Expiration""#));
+        assert!(!content.contains("high-contrast"));
+
+        let res = client.get("/theme?pref=light&contrast=true").send().await?;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+
+        let content = client.get("/").send().await?.text().await?;
+        assert!(content.contains(r#"<body class="high-contrast">"#));
+        assert!(content.contains(r#"href="/theme?contrast=false""#));
+        assert!(content.contains(r#"aria-pressed="true""#));
+        assert!(content.contains(r#"href="/ayu-dark."#));
+
+        client.get("/theme?contrast=false").send().await?;
+
+        let content = client.get("/").send().await?.text().await?;
+        assert!(!content.contains("high-contrast"));
+        assert!(!content.contains(r#"href="/ayu-dark."#));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn follow_system_mode() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(true)).await;
+
+        let res = client.get("/").send().await?;
+        let content = res.text().await?;
+        assert!(content.contains(r#"media="screen and (prefers-color-scheme: dark)""#));
+        assert!(!content.contains(r#"id="system-switch""#));
+
+        client.get("/theme?pref=light").send().await?;
+
+        let res = client.get("/").send().await?;
+        let content = res.text().await?;
+        assert!(!content.contains("prefers-color-scheme"));
+        assert!(content.contains(r#"href="/ayu-light."#));
+        assert!(content.contains(r#"id="system-switch""#));
+
+        let res = client.get("/theme?pref=system").send().await?;
+        assert!(
+            res.headers()
+                .get_all("set-cookie")
+                .iter()
+                .any(|cookie| cookie.to_str().is_ok_and(|c| c.contains("Max-Age=0")))
+        );
+
+        let res = client.get("/").send().await?;
+        assert!(res.text().await?.contains("prefers-color-scheme"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn toggle_layout() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(true)).await;
+
+        let data = Entry {
+            text: "FooBarBaz".to_string(),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?.to_string();
+
+        let res = client.get(&location).send().await?;
+        let content = res.text().await?;
+        assert!(!content.contains("wrap-lines"));
+        assert!(!content.contains("hide-line-numbers"));
+        assert!(content.contains(r#"href="/theme?wrap=true""#));
+
+        client.get("/theme?wrap=true&lines=false").send().await?;
+
+        let res = client.get(&location).send().await?;
+        let content = res.text().await?;
+        assert!(content.contains("wrap-lines"));
+        assert!(content.contains("hide-line-numbers"));
+        assert!(content.contains(r#"href="/theme?wrap=false""#));
+        assert!(content.contains(r#"href="/theme?lines=true""#));
+
+        client.get("/theme?wrap=false").send().await?;
+
+        let res = client.get(&location).send().await?;
+        let content = res.text().await?;
+        assert!(!content.contains("wrap-lines"));
+        assert!(content.contains("hide-line-numbers"));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,48 @@
+use crate::cache_control::NO_STORE;
+use axum::Json;
+use axum::http::header::CACHE_CONTROL;
+use axum::response::IntoResponse;
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Current server time.
+#[derive(Serialize)]
+pub(crate) struct Time {
+    /// Milliseconds since the Unix epoch.
+    now: u128,
+}
+
+/// GET handler for the server time, so countdowns to the expiration of pastes do not depend on
+/// the viewer's clock being right.
+pub async fn get() -> impl IntoResponse {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+
+    ([(CACHE_CONTROL, NO_STORE)], Json(Time { now }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[tokio::test]
+    async fn server_time() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+        let before = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+
+        let res = client.get("/api/time").send().await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["cache-control"], "no-store");
+
+        let now = res.json::<serde_json::Value>().await?["now"]
+            .as_u64()
+            .unwrap();
+        assert!(u128::from(now) >= before);
+
+        Ok(())
+    }
+}
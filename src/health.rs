@@ -0,0 +1,36 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Whether the server accepts new traffic. Once a shutdown is requested, the server reports not
+/// to be ready for `period` before it stops accepting connections, giving load balancers time to
+/// route requests elsewhere.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Readiness {
+    draining: Arc<AtomicBool>,
+    period: Duration,
+}
+
+impl Readiness {
+    pub fn new(period: Duration) -> Self {
+        Self {
+            draining: Arc::default(),
+            period,
+        }
+    }
+
+    /// Return `true` if a shutdown was requested.
+    pub fn draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+
+    /// Report not to be ready anymore and wait for the drain period.
+    pub async fn drain(&self) {
+        self.draining.store(true, Ordering::Relaxed);
+
+        if !self.period.is_zero() {
+            tracing::info!("draining for {:?} before shutting down", self.period);
+            tokio::time::sleep(self.period).await;
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use syntect::LoadingError;
+use syntect::dumps;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+
+/// Collect all files with extension `ext` below `dir`, sorted for a stable fingerprint.
+fn files(dir: &Path, ext: &str) -> Result<Vec<PathBuf>, LoadingError> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|found| found == ext) {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+/// Fingerprint of the files based on their paths and contents, hashed with SHA-256 so that it
+/// stays the same across builds and toolchains.
+fn fingerprint(files: &[PathBuf]) -> Result<u64, LoadingError> {
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+
+    for file in files {
+        let content = std::fs::read(file)?;
+        hasher.update(file.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update((content.len() as u64).to_le_bytes());
+        hasher.update(&content);
+    }
+
+    let digest = hasher.finalize();
+    let mut fingerprint = [0; 8];
+    fingerprint.copy_from_slice(&digest[..8]);
+
+    Ok(u64::from_le_bytes(fingerprint))
+}
+
+/// Dump of compiled definitions kept in a directory next to earlier, possibly stale ones.
+struct Dump {
+    dir: PathBuf,
+    /// Extension distinguishing dumps of syntaxes from those of themes.
+    ext: &'static str,
+    path: PathBuf,
+    /// Fingerprint of the definitions the dump is named after.
+    fingerprint: u64,
+}
+
+impl Dump {
+    /// Dump of `files` with extension `ext` in `dir`, named after their fingerprint.
+    fn new(dir: &Path, ext: &'static str, files: &[PathBuf]) -> Result<Self, LoadingError> {
+        let fingerprint = fingerprint(files)?;
+        let path = dir.join(format!(".wastebin-{fingerprint:016x}.{ext}"));
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            ext,
+            path,
+            fingerprint,
+        })
+    }
+
+    /// Read the dump and remove dumps of earlier definitions left behind.
+    fn load<T: serde::de::DeserializeOwned>(&self) -> Option<T> {
+        let loaded = dumps::from_dump_file(&self.path).ok()?;
+        tracing::debug!("loaded compiled definitions from {}", self.path.display());
+        self.remove_stale();
+        Some(loaded)
+    }
+
+    /// Write `value` to the dump and remove dumps of earlier definitions.
+    fn store<T: serde::Serialize>(&self, value: &T) {
+        let stored = std::fs::create_dir_all(&self.dir)
+            .map_err(|err| err.to_string())
+            .and_then(|()| dumps::dump_to_file(value, &self.path).map_err(|err| err.to_string()));
+
+        if let Err(err) = stored {
+            tracing::warn!(
+                "failed to cache compiled definitions in {}: {err}",
+                self.path.display()
+            );
+            return;
+        }
+
+        self.remove_stale();
+    }
+
+    /// Remove all dumps with the same extension but another fingerprint.
+    fn remove_stale(&self) {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+
+        for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+            let stale = path != self.path
+                && path.extension().is_some_and(|ext| ext == self.ext)
+                && path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with(".wastebin-"));
+
+            if stale {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
+/// Extend `base` with the syntax definitions found in `dir` and return it together with the
+/// fingerprint of the definitions. The compiled set is dumped into `dumps`, or `dir` if unset,
+/// and reused on the next start as long as the definitions did not change.
+pub fn load_syntaxes(
+    base: SyntaxSet,
+    dir: &Path,
+    dumps: Option<&Path>,
+) -> Result<(SyntaxSet, u64), LoadingError> {
+    let files = files(dir, "sublime-syntax")?;
+    let dump = Dump::new(dumps.unwrap_or(dir), "packdump", &files)?;
+
+    if let Some(syntax_set) = dump.load() {
+        return Ok((syntax_set, dump.fingerprint));
+    }
+
+    let mut builder = base.into_builder();
+    builder.add_from_folder(dir, true)?;
+    let syntax_set = builder.build();
+
+    tracing::info!(
+        "loaded {} custom syntaxes from {}",
+        files.len(),
+        dir.display()
+    );
+
+    dump.store(&syntax_set);
+
+    Ok((syntax_set, dump.fingerprint))
+}
+
+/// Load the `.tmTheme` files found in `dir`, keyed by file name. Like syntaxes, the parsed
+/// themes are dumped into `dumps`, or `dir` if unset, and reused while they did not change.
+pub fn load_themes(dir: &Path, dumps: Option<&Path>) -> Result<ThemeSet, LoadingError> {
+    let files = files(dir, "tmTheme")?;
+    let dump = Dump::new(dumps.unwrap_or(dir), "themedump", &files)?;
+
+    if let Some(theme_set) = dump.load() {
+        return Ok(theme_set);
+    }
+
+    let theme_set = ThemeSet::load_from_folder(dir)?;
+
+    tracing::info!(
+        "loaded {} custom themes from {}",
+        files.len(),
+        dir.display()
+    );
+
+    dump.store(&theme_set);
+
+    Ok(theme_set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KDL: &str = "%YAML 1.2
+---
+name: KDL
+file_extensions: [kdl]
+scope: source.kdl
+contexts:
+  main:
+    - match: '//.*$'
+      scope: comment.line.kdl
+";
+
+    #[test]
+    fn load_and_cache() {
+        let dir = std::env::temp_dir().join(format!("wastebin-syntaxes-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("nested/kdl.sublime-syntax"), KDL).unwrap();
+
+        let mut builder = SyntaxSet::new().into_builder();
+        builder.add_plain_text_syntax();
+
+        let (syntax_set, fingerprint) = load_syntaxes(builder.build(), &dir, None).unwrap();
+        assert!(syntax_set.find_syntax_by_extension("kdl").is_some());
+        assert!(syntax_set.find_syntax_by_extension("txt").is_some());
+
+        assert_eq!(dumps(&dir, ".packdump").len(), 1);
+
+        let (cached, cached_fingerprint) = load_syntaxes(SyntaxSet::new(), &dir, None).unwrap();
+        assert!(cached.find_syntax_by_extension("kdl").is_some());
+        assert_eq!(cached_fingerprint, fingerprint);
+
+        let stale = dir.join(".wastebin-0000000000000000.packdump");
+        std::fs::write(&stale, b"stale").unwrap();
+        load_syntaxes(SyntaxSet::new(), &dir, None).unwrap();
+        assert!(!stale.exists());
+
+        std::fs::write(
+            dir.join("nested/kdl.sublime-syntax"),
+            KDL.replace("comment.line", "comment.block"),
+        )
+        .unwrap();
+
+        let (_, edited_fingerprint) = load_syntaxes(SyntaxSet::new(), &dir, None).unwrap();
+        assert_ne!(edited_fingerprint, fingerprint);
+        assert_eq!(dumps(&dir, ".packdump").len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// Names of the dumps with extension `ext` in `dir`.
+    fn dumps(dir: &Path, ext: &str) -> Vec<String> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(ext))
+            .collect()
+    }
+
+    #[test]
+    fn theme_dumps_elsewhere() {
+        let root =
+            std::env::temp_dir().join(format!("wastebin-theme-dumps-{}", std::process::id()));
+        let dir = root.join("themes");
+        let dump_dir = root.join("dumps");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let theme = include_str!("../themes/ayu-dark.tmTheme");
+        std::fs::write(dir.join("brand.tmTheme"), theme).unwrap();
+
+        let theme_set = load_themes(&dir, Some(&dump_dir)).unwrap();
+        assert!(theme_set.themes.contains_key("brand"));
+        assert!(dumps(&dir, ".themedump").is_empty());
+
+        let first = dumps(&dump_dir, ".themedump");
+        assert_eq!(first.len(), 1);
+
+        let cached = load_themes(&dir, Some(&dump_dir)).unwrap();
+        assert!(cached.themes.contains_key("brand"));
+
+        std::fs::write(dir.join("other.tmTheme"), theme).unwrap();
+
+        let changed = load_themes(&dir, Some(&dump_dir)).unwrap();
+        assert!(changed.themes.contains_key("other"));
+
+        let second = dumps(&dump_dir, ".themedump");
+        assert_eq!(second.len(), 1);
+        assert_ne!(first, second);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,278 @@
+use crate::highlight::diff;
+use serde::de::IgnoredAny;
+use syntect::parsing::SyntaxSet;
+
+/// Number of bytes inspected for keyword heuristics.
+const SAMPLE_SIZE: usize = 16 * 1024;
+
+/// Minimum number of distinct markers that must be found for a keyword match.
+const MIN_SCORE: usize = 2;
+
+/// Markers that are characteristic for a language and the extension they map to. A language is
+/// only picked if at least [`MIN_SCORE`] distinct markers occur in the sample.
+const MARKERS: &[(&str, &[&str])] = &[
+    (
+        "rs",
+        &[
+            "fn main()",
+            "let mut ",
+            "pub fn ",
+            "impl ",
+            "use std::",
+            "-> Result<",
+            "#[derive(",
+            "println!(",
+            "::new(",
+            "&self",
+        ],
+    ),
+    (
+        "go",
+        &[
+            "package main",
+            "func ",
+            "import (",
+            ":= ",
+            "fmt.",
+            "err != nil",
+            "go func",
+        ],
+    ),
+    (
+        "py",
+        &[
+            "def ",
+            "import ",
+            "from ",
+            "self.",
+            "elif ",
+            "print(",
+            "__init__",
+            "None:",
+            "if __name__",
+        ],
+    ),
+    (
+        "java",
+        &[
+            "public class ",
+            "public static void main",
+            "private ",
+            "System.out.",
+            "import java.",
+            "@Override",
+        ],
+    ),
+    (
+        "cs",
+        &[
+            "using System",
+            "namespace ",
+            "public class ",
+            "Console.Write",
+            "{ get; set; }",
+        ],
+    ),
+    (
+        "cpp",
+        &[
+            "#include <",
+            "std::",
+            "namespace ",
+            "template <",
+            "cout <<",
+            "nullptr",
+        ],
+    ),
+    (
+        "c",
+        &[
+            "#include <",
+            "int main(",
+            "printf(",
+            "malloc(",
+            "#define ",
+            "sizeof(",
+            "NULL",
+        ],
+    ),
+    (
+        "js",
+        &[
+            "function ",
+            "const ",
+            "=> ",
+            "console.log(",
+            "document.",
+            "require(",
+            "let ",
+            "export default",
+        ],
+    ),
+    (
+        "ts",
+        &[
+            "interface ",
+            ": string",
+            ": number",
+            "export type ",
+            "import type ",
+            "readonly ",
+        ],
+    ),
+    (
+        "php",
+        &["<?php", "$this->", "echo ", "function ", "namespace "],
+    ),
+    (
+        "html",
+        &["<!DOCTYPE html", "<html", "<head>", "<body", "<div"],
+    ),
+    (
+        "sql",
+        &[
+            "SELECT ",
+            " FROM ",
+            "WHERE ",
+            "INSERT INTO ",
+            "CREATE TABLE ",
+            "JOIN ",
+            "GROUP BY ",
+        ],
+    ),
+    (
+        "yaml",
+        &[
+            "apiVersion:",
+            "kind:",
+            "metadata:",
+            "spec:",
+            "- name:",
+            "---\n",
+        ],
+    ),
+    (
+        "toml",
+        &["[package]", "[dependencies]", "version = \"", "edition = "],
+    ),
+    (
+        "Dockerfile",
+        &["FROM ", "RUN ", "COPY ", "WORKDIR ", "ENTRYPOINT ", "CMD "],
+    ),
+    (
+        "sh",
+        &["#!/bin/sh", "echo ", "fi\n", "then\n", "done\n", "export "],
+    ),
+];
+
+/// Return a prefix of `text` that is at most [`SAMPLE_SIZE`] bytes and ends on a char boundary.
+fn sample(text: &str) -> &str {
+    let mut end = text.len().min(SAMPLE_SIZE);
+
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    &text[..end]
+}
+
+/// Guess the language of `text` and return an extension that `syntax_set` knows about.
+pub fn detect(syntax_set: &SyntaxSet, text: &str) -> Option<String> {
+    let trimmed = text.trim_start();
+
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    if trimmed.starts_with(['{', '[']) && serde_json::from_str::<IgnoredAny>(text).is_ok() {
+        if trimmed.starts_with('{') && text.contains("\"nbformat\"") && text.contains("\"cells\"") {
+            return Some(String::from("ipynb"));
+        }
+
+        return Some(String::from("json"));
+    }
+
+    if diff::is_diff(text) {
+        return Some(String::from("diff"));
+    }
+
+    let first_line = trimmed.lines().next().unwrap_or_default();
+
+    if let Some(syntax) = syntax_set.find_syntax_by_first_line(first_line) {
+        if let Some(ext) = syntax.file_extensions.first() {
+            if ext != "txt" {
+                return Some(ext.clone());
+            }
+        }
+    }
+
+    let sample = sample(text);
+
+    MARKERS
+        .iter()
+        .map(|(ext, markers)| {
+            let score = markers
+                .iter()
+                .filter(|marker| sample.contains(*marker))
+                .count();
+            (score, *ext)
+        })
+        .filter(|(score, _)| *score >= MIN_SCORE)
+        // Of equally scored languages the one listed first wins.
+        .fold(None, |best: Option<(usize, &str)>, candidate| match best {
+            Some((score, _)) if score >= candidate.0 => best,
+            _ => Some(candidate),
+        })
+        .and_then(|(_, ext)| syntax_set.find_syntax_by_extension(ext).map(|_| ext))
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    fn detect(text: &str) -> Option<String> {
+        super::detect(&two_face::syntax::extra_newlines(), text)
+    }
+
+    #[test]
+    fn detect_by_first_line() {
+        assert_eq!(
+            detect("#!/usr/bin/env python3\nx = 1\n").as_deref(),
+            Some("py")
+        );
+        assert_eq!(
+            detect("<?xml version=\"1.0\"?>\n<a/>").as_deref(),
+            Some("xml")
+        );
+    }
+
+    #[test]
+    fn detect_by_content() {
+        assert_eq!(detect("[1, 2, 3]").as_deref(), Some("json"));
+        assert_eq!(
+            detect(r#"{"nbformat": 4, "cells": []}"#).as_deref(),
+            Some("ipynb")
+        );
+        assert_eq!(
+            detect("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n+b\n").as_deref(),
+            Some("diff")
+        );
+        assert_eq!(
+            detect("use std::io;\n\nfn main() {\n    let mut x = 1;\n}\n").as_deref(),
+            Some("rs")
+        );
+        assert_eq!(
+            detect("package main\n\nfunc main() {\n\tx := 1\n}\n").as_deref(),
+            Some("go")
+        );
+        assert_eq!(
+            detect("SELECT id FROM entries WHERE id = 1;\n").as_deref(),
+            Some("sql")
+        );
+    }
+
+    #[test]
+    fn detect_nothing() {
+        assert_eq!(detect(""), None);
+        assert_eq!(detect("hello world, this is just some text"), None);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::highlight::escape;
+use std::fmt::Write;
+use syntect::util::LinesWithEndings;
+
+/// Number of leading lines inspected to detect a diff without an extension.
+const DETECTION_LINES: usize = 16;
+
+/// Return `true` if `source` looks like a unified diff or a `git format-patch` mail.
+pub fn is_diff(source: &str) -> bool {
+    let mut lines = source.lines().take(DETECTION_LINES).peekable();
+
+    while let Some(line) = lines.next() {
+        if line.starts_with("diff --git ") {
+            return true;
+        }
+
+        if line.starts_with("--- ") && lines.peek().is_some_and(|next| next.starts_with("+++ ")) {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// CSS class for a single diff `line`.
+fn class_of(line: &str) -> &'static str {
+    if line.starts_with("+++ ") || line.starts_with("--- ") {
+        "diff-file"
+    } else if line.starts_with("@@") {
+        "diff-hunk"
+    } else if line.starts_with('+') {
+        "diff-add"
+    } else if line.starts_with('-') {
+        "diff-del"
+    } else if line.starts_with("diff ") || line.starts_with("index ") {
+        "diff-meta"
+    } else {
+        "diff-context"
+    }
+}
+
+/// Render `source` as a table of numbered lines colored by their diff role. Hunk headers carry
+/// `H<n>` anchors.
+pub fn render(source: &str) -> String {
+    let mut html = String::from(r#"<table class="diff"><tbody>"#);
+    let mut hunk = 0;
+
+    for (line_number, line) in LinesWithEndings::from(source).enumerate() {
+        let line_number = line_number + 1;
+        let line = line.trim_end_matches(['\n', '\r']);
+        let class = class_of(line);
+
+        if class == "diff-hunk" {
+            hunk += 1;
+            let _ = write!(
+                html,
+                r##"<tr class="{class}" id="H{hunk}"><td class="line-number" id="L{line_number}"><a href=#L{line_number}>{line_number}</a></td><td class="line"><a class="hunk-anchor" href="#H{hunk}">{}</a></td></tr>"##,
+                escape(line)
+            );
+        } else {
+            let _ = write!(
+                html,
+                r#"<tr class="{class}"><td class="line-number" id="L{line_number}"><a href=#L{line_number}>{line_number}</a></td><td class="line">{}</td></tr>"#,
+                escape(line)
+            );
+        }
+    }
+
+    html.push_str("</tbody></table>");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "diff --git a/foo.rs b/foo.rs
+index 83db48f..bf269f4 100644
+--- a/foo.rs
++++ b/foo.rs
+@@ -1,3 +1,3 @@
+ fn main() {
+-    println!(\"<old>\");
++    println!(\"new\");
+ }
+@@ -10,1 +10,1 @@
+-a
++b
+";
+
+    #[test]
+    fn detect() {
+        assert!(is_diff(DIFF));
+        assert!(is_diff("--- a\n+++ b\n@@ -1 +1 @@\n-a\n+b\n"));
+        assert!(!is_diff("fn main() {}\n"));
+        assert!(!is_diff("--- \nfoo\n"));
+    }
+
+    #[test]
+    fn render_diff() {
+        let html = render(DIFF);
+
+        assert!(html.contains(r#"<tr class="diff-meta"><td class="line-number" id="L1">"#));
+        assert!(html.contains(r#"<tr class="diff-file"><td class="line-number" id="L3">"#));
+        assert!(html.contains(r#"<tr class="diff-hunk" id="H1">"#));
+        assert!(html.contains(r#"<tr class="diff-hunk" id="H2">"#));
+        assert!(html.contains(r#"<tr class="diff-del"><td class="line-number" id="L7">"#));
+        assert!(html.contains("&lt;old&gt;"));
+        assert!(html.contains(r#"<tr class="diff-add"><td class="line-number" id="L8">"#));
+    }
+}
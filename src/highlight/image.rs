@@ -0,0 +1,248 @@
+use crate::errors::Error;
+use crate::highlight::{Highlighter, escape};
+use std::fmt::Write;
+use std::ops::RangeInclusive;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Color, FontStyle, Theme};
+use syntect::util::LinesWithEndings;
+
+/// Maximum number of lines rendered into an image.
+pub const MAX_LINES: usize = 200;
+
+/// Maximum number of characters per line, longer lines are cut off.
+const MAX_COLUMNS: usize = 160;
+
+const FONT_FAMILY: &str = "DejaVu Sans Mono, Menlo, Consolas, monospace";
+const FONT_SIZE: usize = 14;
+const LINE_HEIGHT: usize = 20;
+/// Advance of a monospace glyph at [`FONT_SIZE`] in tenths of a pixel.
+const CHAR_WIDTH: usize = 85;
+const PADDING: usize = 24;
+/// Height of the title bar with the window buttons.
+const TITLE_BAR: usize = 36;
+
+fn rgba(color: Color) -> String {
+    format!(
+        "rgba({},{},{},{:.3})",
+        color.r,
+        color.g,
+        color.b,
+        f64::from(color.a) / 255.0
+    )
+}
+
+/// Expand tabs and cut `text` off at [`MAX_COLUMNS`], `column` being the number of characters
+/// already placed on the line.
+fn clip(text: &str, column: &mut usize) -> String {
+    let mut clipped = String::with_capacity(text.len());
+
+    for c in text.chars().filter(|c| !matches!(c, '\n' | '\r')) {
+        if *column >= MAX_COLUMNS {
+            break;
+        }
+
+        if c == '\t' {
+            let spaces = 4 - *column % 4;
+            clipped.extend(std::iter::repeat_n(' ', spaces));
+            *column += spaces;
+        } else {
+            clipped.push(c);
+            *column += 1;
+        }
+    }
+
+    clipped
+}
+
+impl Highlighter {
+    /// Render the `lines` of `source` as a window-framed SVG image, colored by `theme`. Ranges
+    /// beyond the end of `source` are cut off and at most [`MAX_LINES`] lines are rendered.
+    /// Sources too large to be highlighted are rendered as plain text.
+    pub fn svg(
+        &self,
+        source: &str,
+        ext: &str,
+        theme: &Theme,
+        lines: RangeInclusive<usize>,
+    ) -> Result<String, Error> {
+        let first = (*lines.start()).max(1);
+        let last = (*lines.end()).min(first + MAX_LINES - 1);
+        let ext = if self.exceeds_limit(source) {
+            "txt"
+        } else {
+            ext
+        };
+        let mut highlighter = HighlightLines::new(self.find_syntax(ext), theme);
+        let mut rows = Vec::new();
+
+        for (index, line) in LinesWithEndings::from(source).enumerate().take(last) {
+            // Earlier lines must be parsed nonetheless to keep track of the syntax state.
+            let ranges = highlighter.highlight_line(line, &self.syntax_set)?;
+
+            if index + 1 < first {
+                continue;
+            }
+
+            let mut column = 0;
+            let mut row = String::new();
+
+            for (style, text) in ranges {
+                let text = clip(text, &mut column);
+
+                if text.is_empty() {
+                    continue;
+                }
+
+                let _ = write!(row, r#"<tspan fill="{}""#, rgba(style.foreground));
+
+                if style.font_style.contains(FontStyle::BOLD) {
+                    row.push_str(r#" font-weight="bold""#);
+                }
+
+                if style.font_style.contains(FontStyle::ITALIC) {
+                    row.push_str(r#" font-style="italic""#);
+                }
+
+                let _ = write!(row, ">{}</tspan>", escape(&text));
+            }
+
+            rows.push((index + 1, row, column));
+        }
+
+        let fg = theme.settings.foreground.unwrap_or(Color::WHITE);
+        let bg = theme.settings.background.unwrap_or(Color::BLACK);
+        let gutter = theme
+            .settings
+            .gutter_foreground
+            .unwrap_or(Color { a: 128, ..fg });
+        let digits = rows
+            .last()
+            .map_or(1, |(number, _, _)| number.to_string().len());
+        let columns = rows
+            .iter()
+            .map(|(_, _, columns)| *columns)
+            .max()
+            .unwrap_or(0);
+        let text_x = PADDING + ((digits + 2) * CHAR_WIDTH).div_ceil(10);
+        let width = text_x + (columns * CHAR_WIDTH).div_ceil(10) + PADDING;
+        let height = TITLE_BAR + rows.len().max(1) * LINE_HEIGHT + PADDING;
+
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}"><rect width="{width}" height="{height}" rx="8" fill="{}"/>"#,
+            rgba(bg)
+        );
+
+        for (index, color) in ["#ff5f56", "#ffbd2e", "#27c93f"].iter().enumerate() {
+            let _ = write!(
+                svg,
+                r#"<circle cx="{}" cy="{}" r="6" fill="{color}"/>"#,
+                PADDING + index * 20,
+                TITLE_BAR / 2
+            );
+        }
+
+        let _ = write!(
+            svg,
+            r#"<text font-family="{FONT_FAMILY}" font-size="{FONT_SIZE}" xml:space="preserve">"#
+        );
+
+        for (row, (number, content, _)) in rows.iter().enumerate() {
+            let y = TITLE_BAR + row * LINE_HEIGHT + FONT_SIZE;
+
+            let _ = write!(
+                svg,
+                r#"<tspan x="{PADDING}" y="{y}" fill="{}">{number:>digits$}</tspan><tspan x="{text_x}" y="{y}">{content}</tspan>"#,
+                rgba(gutter),
+            );
+        }
+
+        svg.push_str("</text></svg>");
+
+        Ok(svg)
+    }
+}
+
+/// Rasterize `svg` at twice its size, so the image stays sharp on high density displays.
+#[cfg(feature = "png")]
+pub fn png(svg: &str) -> Result<Vec<u8>, Error> {
+    use resvg::{tiny_skia, usvg};
+    use std::sync::{Arc, LazyLock};
+
+    static FONTS: LazyLock<Arc<usvg::fontdb::Database>> = LazyLock::new(|| {
+        let mut fonts = usvg::fontdb::Database::new();
+        fonts.load_system_fonts();
+        Arc::new(fonts)
+    });
+
+    let options = usvg::Options {
+        fontdb: Arc::clone(&FONTS),
+        ..Default::default()
+    };
+
+    let tree = usvg::Tree::from_str(svg, &options).map_err(|err| Error::Image(err.to_string()))?;
+    let size = tree
+        .size()
+        .to_int_size()
+        .scale_by(2.0)
+        .ok_or_else(|| Error::Image(String::from("invalid image size")))?;
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or_else(|| Error::Image(String::from("invalid image size")))?;
+
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(2.0, 2.0),
+        &mut pixmap.as_mut(),
+    );
+
+    pixmap
+        .encode_png()
+        .map_err(|err| Error::Image(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_svg() {
+        let highlighter = Highlighter::default();
+        let theme = two_face::theme::extra()
+            .get(two_face::theme::EmbeddedThemeName::Nord)
+            .clone();
+
+        let source = "// <comment>\nfn main() {\n\tlet x = 1;\n}\n";
+        let svg = highlighter.svg(source, "rs", &theme, 2..=3).unwrap();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>"));
+        assert!(!svg.contains("comment"));
+        assert!(svg.contains(">fn</tspan>"));
+        assert!(svg.contains(">    </tspan>"));
+        assert!(svg.contains(">2</tspan>"));
+        assert!(svg.contains(">3</tspan>"));
+        assert!(!svg.contains(">4</tspan>"));
+
+        let svg = highlighter.svg(source, "rs", &theme, 1..=1).unwrap();
+        assert!(svg.contains("&lt;comment&gt;"));
+
+        let limited = Highlighter::default().with_max_bytes(Some(8));
+        assert_eq!(
+            limited.svg(source, "rs", &theme, 2..=3).unwrap(),
+            highlighter.svg(source, "txt", &theme, 2..=3).unwrap()
+        );
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn render_png() {
+        let theme = two_face::theme::extra()
+            .get(two_face::theme::EmbeddedThemeName::Nord)
+            .clone();
+
+        let svg = Highlighter::default()
+            .svg("fn main() {}\n", "rs", &theme, 1..=1)
+            .unwrap();
+
+        assert!(png(&svg).unwrap().starts_with(b"\x89PNG"));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::highlight::escape;
+use serde::de::IgnoredAny;
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Maximum size of a JSON document in bytes for which the pretty and tree views are offered.
+pub const SIZE_LIMIT: usize = 4 * 1024 * 1024;
+
+const INDENT: &str = "  ";
+
+fn newline(out: &mut String, level: usize) {
+    out.push('\n');
+
+    for _ in 0..level {
+        out.push_str(INDENT);
+    }
+}
+
+/// Return `true` if `source` is small enough and valid JSON.
+fn is_viewable(source: &str) -> bool {
+    source.len() <= SIZE_LIMIT && serde_json::from_str::<IgnoredAny>(source).is_ok()
+}
+
+/// Re-indent the JSON document `source`. Tokens are copied verbatim, so numbers keep their exact
+/// representation and object keys keep their order.
+pub fn pretty(source: &str) -> Option<String> {
+    if !is_viewable(source) {
+        return None;
+    }
+
+    let mut out = String::with_capacity(source.len() * 2);
+    let mut level = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            '{' | '[' => {
+                out.push(c);
+
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+
+                // Keep empty objects and arrays on a single line.
+                if let Some(close) = chars.next_if(|next| matches!(next, '}' | ']')) {
+                    out.push(close);
+                } else {
+                    level += 1;
+                    newline(&mut out, level);
+                }
+            }
+            '}' | ']' => {
+                level = level.saturating_sub(1);
+                newline(&mut out, level);
+                out.push(c);
+            }
+            ',' => {
+                out.push(c);
+                newline(&mut out, level);
+            }
+            ':' => out.push_str(": "),
+            c if c.is_whitespace() => {}
+            c => out.push(c),
+        }
+    }
+
+    out.push('\n');
+
+    Some(out)
+}
+
+fn render_key(html: &mut String, key: Option<&str>) {
+    if let Some(key) = key {
+        let _ = write!(html, r#"<span class="json-key">"{}"</span>: "#, escape(key));
+    }
+}
+
+fn render_value(html: &mut String, key: Option<&str>, value: &Value) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            html.push_str("<li><details open><summary>");
+            render_key(html, key);
+            let _ = write!(
+                html,
+                r#"{{…}} <span class="json-count">{} {}</span></summary><ul>"#,
+                map.len(),
+                if map.len() == 1 { "key" } else { "keys" }
+            );
+
+            for (key, value) in map {
+                render_value(html, Some(key), value);
+            }
+
+            html.push_str("</ul></details></li>");
+        }
+        Value::Array(values) if !values.is_empty() => {
+            html.push_str("<li><details open><summary>");
+            render_key(html, key);
+            let _ = write!(
+                html,
+                r#"[…] <span class="json-count">{} {}</span></summary><ul>"#,
+                values.len(),
+                if values.len() == 1 { "item" } else { "items" }
+            );
+
+            for value in values {
+                render_value(html, None, value);
+            }
+
+            html.push_str("</ul></details></li>");
+        }
+        leaf => {
+            html.push_str("<li>");
+            render_key(html, key);
+
+            let (class, text) = match leaf {
+                Value::Null => ("json-null", String::from("null")),
+                Value::Bool(b) => ("json-bool", b.to_string()),
+                Value::Number(n) => ("json-number", n.to_string()),
+                Value::String(s) => ("json-string", format!("\"{}\"", escape(s))),
+                Value::Object(_) => ("json-empty", String::from("{}")),
+                Value::Array(_) => ("json-empty", String::from("[]")),
+            };
+
+            let _ = write!(html, r#"<span class="{class}">{text}</span></li>"#);
+        }
+    }
+}
+
+/// Render `source` as a collapsible tree or return `None` if it is not viewable.
+pub fn tree(source: &str) -> Option<String> {
+    if source.len() > SIZE_LIMIT {
+        return None;
+    }
+
+    let value = serde_json::from_str::<Value>(source).ok()?;
+    let mut html = String::from(r#"<ul class="json-tree">"#);
+    render_value(&mut html, None, &value);
+    html.push_str("</ul>");
+
+    Some(html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pretty_print() {
+        let pretty = pretty(r#"{"b":[1,2.50,{}],"a":"x, \"y\": {z}","c":[ ]}"#).unwrap();
+
+        assert_eq!(
+            pretty,
+            r#"{
+  "b": [
+    1,
+    2.50,
+    {}
+  ],
+  "a": "x, \"y\": {z}",
+  "c": []
+}
+"#
+        );
+    }
+
+    #[test]
+    fn pretty_print_invalid() {
+        assert!(pretty("{\"a\": ").is_none());
+        assert!(pretty("hello").is_none());
+    }
+
+    #[test]
+    fn tree_view() {
+        let html = tree(r#"{"name": "<b>", "list": [1, true, null], "empty": {}}"#).unwrap();
+
+        assert!(html.starts_with(r#"<ul class="json-tree">"#));
+        assert!(html.contains(r#"<span class="json-string">"&lt;b&gt;"</span>"#));
+        assert!(html.contains("3 items"));
+        assert!(html.contains(r#"<span class="json-empty">{}</span>"#));
+        assert!(html.find("name").unwrap() < html.find("list").unwrap());
+    }
+}
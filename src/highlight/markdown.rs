@@ -0,0 +1,76 @@
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag};
+
+/// Link schemes that are allowed to end up in `href` and `src` attributes.
+const ALLOWED_SCHEMES: [&str; 4] = ["http:", "https:", "mailto:", "data:image/"];
+
+/// Return `true` if `url` is relative or uses one of the [`ALLOWED_SCHEMES`].
+fn is_safe_url(url: &str) -> bool {
+    let lower = url.trim_start().to_ascii_lowercase();
+
+    match lower.split_once(':') {
+        None => true,
+        Some((scheme, _)) if scheme.contains(['/', '?', '#']) => true,
+        Some(_) => ALLOWED_SCHEMES
+            .iter()
+            .any(|prefix| lower.starts_with(prefix)),
+    }
+}
+
+/// Render Markdown `source` to HTML. Raw HTML is escaped and links with unsafe schemes are
+/// neutralized, so the output can be embedded into a page as-is.
+pub fn render(source: &str) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+
+    let events = Parser::new_ext(source, options).map(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !is_safe_url(&dest_url) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: CowStr::Borrowed("#"),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !is_safe_url(&dest_url) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: CowStr::Borrowed(""),
+            title,
+            id,
+        }),
+        event => event,
+    });
+
+    let mut html = String::with_capacity(source.len() * 3 / 2);
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_raw_html() {
+        let html = render("# Title\n\n<script>alert(1)</script>");
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;"));
+    }
+
+    #[test]
+    fn neutralize_unsafe_links() {
+        let html = render("[click](javascript:alert(1)) [ok](https://example.com) [rel](foo/bar)");
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains(r#"href="https://example.com""#));
+        assert!(html.contains(r#"href="foo/bar""#));
+    }
+}
@@ -0,0 +1,936 @@
+use crate::db::read::Data;
+use crate::deadline::Deadline;
+use crate::errors::Error;
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use syntect::html::{ClassStyle, ClassedHTMLGenerator, line_tokens_to_classed_spans};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+use tokio::task::JoinHandle;
+
+pub mod custom;
+mod detect;
+mod diff;
+pub mod image;
+pub mod json;
+pub(crate) mod markdown;
+mod notebook;
+pub mod paging;
+mod pdf;
+#[cfg(feature = "tree-sitter")]
+mod treesitter;
+
+const HIGHLIGHT_LINE_LENGTH_CUTOFF: usize = 2048;
+
+/// Number of lines highlighted per task when highlighting long pastes in parallel.
+const CHUNK_LINES: usize = 1024;
+
+/// Number of lines before a chunk parsed to recover the parser state at its start.
+const WARM_UP_LINES: usize = 64;
+
+/// Number of lines shown as preview of a paste in listings.
+const SNIPPET_LINES: usize = 5;
+
+/// Default time a request waits for highlighting before falling back to plain text.
+pub const DEFAULT_DEADLINE: Duration = Duration::from_secs(3);
+
+/// Supported themes.
+#[derive(Clone)]
+pub(crate) enum Theme {
+    Ayu,
+    Base16Ocean,
+    Catppuccin,
+    Coldark,
+    Dracula,
+    Gruvbox,
+    Monokai,
+    Nord,
+    Onehalf,
+    Solarized,
+    TokyoNight,
+    /// Theme loaded from a `.tmTheme` file at startup.
+    Custom(Arc<CustomTheme>),
+}
+
+impl Theme {
+    /// All built-in themes.
+    pub fn builtin() -> [Theme; 11] {
+        [
+            Theme::Ayu,
+            Theme::Base16Ocean,
+            Theme::Catppuccin,
+            Theme::Coldark,
+            Theme::Dracula,
+            Theme::Gruvbox,
+            Theme::Monokai,
+            Theme::Nord,
+            Theme::Onehalf,
+            Theme::Solarized,
+            Theme::TokyoNight,
+        ]
+    }
+
+    /// Name used to select the theme.
+    pub fn name(&self) -> &str {
+        match self {
+            Theme::Ayu => "ayu",
+            Theme::Base16Ocean => "base16ocean",
+            Theme::Catppuccin => "catppuccin",
+            Theme::Coldark => "coldark",
+            Theme::Dracula => "dracula",
+            Theme::Gruvbox => "gruvbox",
+            Theme::Monokai => "monokai",
+            Theme::Nord => "nord",
+            Theme::Onehalf => "onehalf",
+            Theme::Solarized => "solarized",
+            Theme::TokyoNight => "tokyonight",
+            Theme::Custom(custom) => &custom.name,
+        }
+    }
+}
+
+/// Light and dark variant of a theme loaded from the theme directory.
+pub(crate) struct CustomTheme {
+    pub name: String,
+    pub light: syntect::highlighting::Theme,
+    pub dark: syntect::highlighting::Theme,
+}
+
+/// Alternative views of a paste's content.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum View {
+    /// Highlighted source as is.
+    #[default]
+    Source,
+    /// Re-indented JSON document.
+    Pretty,
+    /// Collapsible JSON tree.
+    Tree,
+    /// Escaped text without highlighting, used for pastes above the highlighting size limit.
+    Plain,
+    /// First lines highlighted as source, previewing the paste in listings.
+    #[serde(skip_deserializing)]
+    Snippet,
+}
+
+/// Highlighting engine used for languages that more than one engine supports.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Engine {
+    #[default]
+    Syntect,
+    TreeSitter,
+}
+
+/// Alternative highlighting engine for a subset of languages. Its output replaces the syntect
+/// highlighted lines, so cache and templates do not depend on the engine.
+pub(crate) trait Backend: Send + Sync {
+    /// Return `true` if the backend can highlight files with extension `ext`.
+    fn supports(&self, ext: &str) -> bool;
+
+    /// Highlight `source` into one HTML fragment per line or return `None` on failure.
+    fn highlight_lines(&self, source: &str, ext: &str) -> Option<Vec<String>>;
+}
+
+/// Rendered HTML together with the offsets of its table rows, found once per cached rendering
+/// and shared by its clones, so that paging does not scan the whole document on every view.
+#[derive(Clone)]
+pub(crate) struct Html {
+    html: String,
+    rows: Arc<OnceLock<Vec<usize>>>,
+}
+
+/// Result of highlighting with a deadline.
+pub(crate) enum Rendered {
+    /// Highlighting finished in time.
+    Done(Html),
+    /// Highlighting exceeded the deadline. Holds the plain text rendering and the still running
+    /// highlighting task.
+    Late(Html, JoinHandle<Result<Html, Error>>),
+}
+
+#[derive(Clone)]
+pub(crate) struct Highlighter {
+    syntax_set: SyntaxSet,
+    pub syntaxes: Vec<SyntaxReference>,
+    /// Names of the syntaxes used for highlighting, all if unset.
+    languages: Option<HashSet<String>>,
+    /// Custom extensions and names mapped to the canonical extension of a known syntax.
+    aliases: HashMap<String, String>,
+    /// Alternative engine used if preferred or if syntect lacks a syntax.
+    backend: Option<Arc<dyn Backend>>,
+    prefer_backend: bool,
+    /// Size in bytes above which pastes are not highlighted unless explicitly requested.
+    max_bytes: Option<usize>,
+    /// Time to wait for highlighting before falling back to plain text.
+    deadline: Duration,
+    /// Threads shared by all requests to highlight chunks of long pastes, sequential if unset.
+    pool: Option<Arc<rayon::ThreadPool>>,
+    /// Fingerprint of the custom syntax definitions, whose rules may change under the same name.
+    custom_syntaxes: Option<u64>,
+}
+
+/// Errors when setting up the [`Highlighter`] at startup.
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum SetupError {
+    #[error("unknown syntax {0} in the list of languages")]
+    UnknownLanguage(String),
+    #[error("unknown syntax {target} for language alias {alias}")]
+    UnknownAliasTarget { alias: String, target: String },
+    #[error("failed to start highlighting threads: {0}")]
+    ThreadPool(#[from] rayon::ThreadPoolBuildError),
+    #[error("failed to load custom syntaxes: {0}")]
+    LoadSyntaxes(#[from] syntect::LoadingError),
+    #[cfg(feature = "tree-sitter")]
+    #[error("failed to compile tree-sitter queries: {0}")]
+    TreeSitter(#[from] tree_sitter::QueryError),
+    #[cfg(not(feature = "tree-sitter"))]
+    #[error("tree-sitter support is not compiled in, enable the tree-sitter feature")]
+    TreeSitterUnavailable,
+}
+
+/// Syntaxes of `syntax_set` sorted by name for display.
+fn sorted_syntaxes(syntax_set: &SyntaxSet) -> Vec<SyntaxReference> {
+    let mut syntaxes = syntax_set.syntaxes().to_vec();
+    syntaxes.sort_by(|a, b| {
+        a.name
+            .to_lowercase()
+            .partial_cmp(&b.name.to_lowercase())
+            .unwrap_or(Ordering::Less)
+    });
+    syntaxes
+}
+
+impl Default for Highlighter {
+    fn default() -> Self {
+        let syntax_set = two_face::syntax::extra_newlines();
+        let syntaxes = sorted_syntaxes(&syntax_set);
+
+        Self {
+            syntax_set,
+            syntaxes,
+            languages: None,
+            aliases: HashMap::new(),
+            backend: None,
+            prefer_backend: false,
+            max_bytes: None,
+            deadline: DEFAULT_DEADLINE,
+            pool: None,
+            custom_syntaxes: None,
+        }
+    }
+}
+
+/// Escape `text` for safe inclusion in HTML content and attribute values.
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Start a table row with the anchored `line_number` and open the line cell.
+fn push_line_number(html: &mut String, line_number: usize) {
+    let _ = write!(
+        html,
+        r#"<tr><td class="line-number" id="L{line_number}"><a href=#L{line_number}>{line_number}</a></td><td class="line">"#
+    );
+}
+
+/// Plain text rendering of `text` shown while highlighting is late.
+pub(crate) fn fallback(text: &str) -> Html {
+    Html::from(plain_lines(text))
+}
+
+/// Render `source` as a table of numbered, escaped lines.
+fn plain_lines(source: &str) -> String {
+    let mut html = String::from("<table><tbody>");
+
+    for (line_number, line) in source.lines().enumerate() {
+        push_line_number(&mut html, line_number + 1);
+        html.push_str(&escape(line));
+        html.push_str("</td></tr>");
+    }
+
+    html.push_str("</tbody></table>");
+    html
+}
+
+impl Highlighter {
+    /// Skip highlighting of pastes larger than `max_bytes`.
+    pub fn with_max_bytes(mut self, max_bytes: Option<usize>) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Fall back to plain text if highlighting takes longer than `deadline`.
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Time to wait for highlighting before falling back to plain text.
+    pub fn deadline(&self) -> Duration {
+        self.deadline
+    }
+
+    /// Return `true` if `text` is too large to be highlighted by default.
+    /// Highlight pastes longer than a chunk of lines on up to `threads` threads in parallel. The
+    /// calling blocking task waits for the chunks, so the threads add to the blocking pool.
+    pub fn with_threads(mut self, threads: NonZeroUsize) -> Result<Self, SetupError> {
+        self.pool = if threads.get() > 1 {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads.get())
+                .thread_name(|index| format!("highlight-{index}"))
+                .build()?;
+
+            Some(Arc::new(pool))
+        } else {
+            None
+        };
+
+        Ok(self)
+    }
+
+    pub fn exceeds_limit(&self, text: &str) -> bool {
+        self.max_bytes
+            .is_some_and(|max_bytes| text.len() > max_bytes)
+    }
+
+    /// Add the `.sublime-syntax` definitions found in `dir` to the built-in syntaxes, keeping
+    /// the compiled set in `dumps` if given.
+    pub fn with_syntax_dir(mut self, dir: &Path, dumps: Option<&Path>) -> Result<Self, SetupError> {
+        let (syntax_set, fingerprint) = custom::load_syntaxes(self.syntax_set, dir, dumps)?;
+        self.syntax_set = syntax_set;
+        self.custom_syntaxes = Some(fingerprint);
+        self.syntaxes = sorted_syntaxes(&self.syntax_set);
+
+        Ok(self)
+    }
+
+    /// Enable the tree-sitter backend, preferring it over syntect if `engine` says so.
+    #[cfg(feature = "tree-sitter")]
+    pub fn with_engine(mut self, engine: Engine) -> Result<Self, SetupError> {
+        self.backend = Some(Arc::new(treesitter::TreeSitter::new()?));
+        self.prefer_backend = engine == Engine::TreeSitter;
+
+        Ok(self)
+    }
+
+    /// Fail if `engine` requires the tree-sitter backend which is not compiled in.
+    #[cfg(not(feature = "tree-sitter"))]
+    pub fn with_engine(self, engine: Engine) -> Result<Self, SetupError> {
+        match engine {
+            Engine::Syntect => Ok(self),
+            Engine::TreeSitter => Err(SetupError::TreeSitterUnavailable),
+        }
+    }
+
+    /// Keep only the syntaxes of `languages`, given by extension, name or token, and plain text.
+    /// Pastes in other languages are shown as plain text and the contexts of their syntaxes are
+    /// never deserialized, which keeps resident memory low.
+    pub fn with_languages(mut self, languages: &[String]) -> Result<Self, SetupError> {
+        let mut names = HashSet::from([String::from("Plain Text")]);
+
+        for language in languages {
+            let syntax = self
+                .syntax_set
+                .find_syntax_by_extension(language)
+                .or_else(|| self.syntax_set.find_syntax_by_name(language))
+                .or_else(|| self.syntax_set.find_syntax_by_token(language))
+                .ok_or_else(|| SetupError::UnknownLanguage(language.clone()))?;
+
+            names.insert(syntax.name.clone());
+        }
+
+        self.syntaxes.retain(|syntax| names.contains(&syntax.name));
+        self.languages = Some(names);
+
+        Ok(self)
+    }
+
+    /// Return if `syntax` is among the configured languages.
+    fn enabled(&self, syntax: &SyntaxReference) -> bool {
+        self.languages
+            .as_ref()
+            .is_none_or(|names| names.contains(&syntax.name))
+    }
+
+    /// Map each alias to the syntax with the given extension, name or token. Aliases take
+    /// precedence over built-in extensions.
+    pub fn with_aliases(
+        mut self,
+        aliases: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, SetupError> {
+        for (alias, target) in aliases {
+            let ext = self
+                .syntax_set
+                .find_syntax_by_extension(&target)
+                .or_else(|| self.syntax_set.find_syntax_by_name(&target))
+                .or_else(|| self.syntax_set.find_syntax_by_token(&target))
+                .and_then(|syntax| syntax.file_extensions.first().cloned())
+                .ok_or_else(|| SetupError::UnknownAliasTarget {
+                    alias: alias.clone(),
+                    target,
+                })?;
+
+            self.aliases.insert(alias, ext);
+        }
+
+        Ok(self)
+    }
+
+    /// Resolve `token` through the configured aliases.
+    pub fn resolve<'a>(&'a self, token: &'a str) -> &'a str {
+        self.aliases.get(token).map_or(token, String::as_str)
+    }
+
+    /// Name of the syntax for the given extension or name `token`, e.g. `Rust` for `rs`.
+    pub fn syntax_name(&self, token: &str) -> &str {
+        &self.find_syntax(token).name
+    }
+
+    /// Find syntax for the given extension or name `token`, falling back to plain text.
+    fn find_syntax(&self, token: &str) -> &SyntaxReference {
+        let token = self.resolve(token);
+
+        self.syntax_set
+            .find_syntax_by_extension(token)
+            .or_else(|| self.syntax_set.find_syntax_by_token(token))
+            .filter(|syntax| self.enabled(syntax))
+            .unwrap_or_else(|| {
+                self.syntax_set
+                    .find_syntax_by_extension("txt")
+                    .expect("finding txt syntax")
+            })
+    }
+
+    /// Resolve a language name or extension `token` to the canonical extension of its syntax.
+    pub fn extension_for(&self, token: &str) -> Option<String> {
+        if let Some(ext) = self.aliases.get(token) {
+            return Some(ext.clone());
+        }
+
+        self.syntax_set
+            .find_syntax_by_token(token)
+            .filter(|syntax| self.enabled(syntax))
+            .and_then(|syntax| syntax.file_extensions.first().cloned())
+    }
+
+    /// Fingerprint of everything that affects the rendered HTML, used to invalidate persisted
+    /// renderings after upgrades or configuration changes.
+    pub fn version(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        self.backend.is_some().hash(&mut hasher);
+        self.prefer_backend.hash(&mut hasher);
+        self.custom_syntaxes.hash(&mut hasher);
+
+        for syntax in self.syntax_set.syntaxes() {
+            if !self.enabled(syntax) {
+                continue;
+            }
+
+            syntax.name.hash(&mut hasher);
+            syntax.file_extensions.hash(&mut hasher);
+        }
+
+        let mut aliases = self.aliases.iter().collect::<Vec<_>>();
+        aliases.sort();
+        aliases.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    /// Guess the language of `text` and return a matching file extension.
+    pub fn detect(&self, text: &str) -> Option<String> {
+        detect::detect(&self.syntax_set, text).filter(|ext| {
+            self.syntax_set
+                .find_syntax_by_extension(ext)
+                .is_some_and(|syntax| self.enabled(syntax))
+        })
+    }
+
+    /// Highlight `source` with `syntax` into classed spans without any line decoration.
+    fn highlight_block(&self, source: &str, syntax: &SyntaxReference) -> Result<String, Error> {
+        let mut generator = ClassedHTMLGenerator::new_with_class_style(
+            syntax,
+            &self.syntax_set,
+            ClassStyle::Spaced,
+        );
+
+        for line in LinesWithEndings::from(source) {
+            generator.parse_html_for_line_which_includes_newline(line)?;
+        }
+
+        Ok(generator.finalize())
+    }
+
+    fn highlight_inner(
+        &self,
+        source: &str,
+        ext: &str,
+        view: View,
+        deadline: Option<Deadline>,
+    ) -> Result<String, Error> {
+        let ext = self.resolve(ext);
+
+        match view {
+            View::Source => {}
+            View::Plain => return Ok(plain_lines(source)),
+            View::Snippet => {
+                let end = source
+                    .match_indices('\n')
+                    .nth(SNIPPET_LINES - 1)
+                    .map_or(source.len(), |(index, _)| index + 1);
+
+                return self.highlight_lines(&source[..end], ext, deadline);
+            }
+            View::Pretty => {
+                if let Some(pretty) = json::pretty(source) {
+                    return self.highlight_lines(&pretty, "json", deadline);
+                }
+            }
+            View::Tree => {
+                if let Some(html) = json::tree(source) {
+                    return Ok(html);
+                }
+            }
+        }
+
+        if ext == "ipynb" {
+            if let Some(html) = notebook::render(self, source)? {
+                return Ok(html);
+            }
+        }
+
+        if matches!(ext, "diff" | "patch") || (ext == "txt" && diff::is_diff(source)) {
+            return Ok(diff::render(source));
+        }
+
+        self.highlight_lines(source, ext, deadline)
+    }
+
+    /// Return the backend that should highlight `ext` instead of syntect.
+    fn backend_for(&self, ext: &str) -> Option<&dyn Backend> {
+        self.backend.as_deref().filter(|backend| {
+            backend.supports(ext)
+                && (self.prefer_backend
+                    || (self.syntax_set.find_syntax_by_extension(ext).is_none()
+                        && self.syntax_set.find_syntax_by_token(ext).is_none()))
+        })
+    }
+
+    /// Highlight `lines` into table rows numbered from `first`. Parsing `warm_up`, the lines
+    /// preceding a chunk of a longer paste, recovers the parser state at its start in most cases.
+    /// Gives up once `deadline` passes.
+    fn highlight_rows(
+        &self,
+        syntax: &SyntaxReference,
+        warm_up: &[&str],
+        lines: &[&str],
+        first: usize,
+        deadline: Option<Deadline>,
+    ) -> Result<String, Error> {
+        let mut parse_state = ParseState::new(syntax);
+        let mut scope_stack = ScopeStack::new();
+        let mut html = String::new();
+
+        for line in warm_up {
+            if line.len() <= HIGHLIGHT_LINE_LENGTH_CUTOFF {
+                let parsed = parse_state.parse_line(line, &self.syntax_set)?;
+                line_tokens_to_classed_spans(
+                    line,
+                    parsed.as_slice(),
+                    ClassStyle::Spaced,
+                    &mut scope_stack,
+                )?;
+            }
+        }
+
+        for (offset, line) in lines.iter().enumerate() {
+            if deadline.is_some_and(Deadline::passed) {
+                return Err(Error::DeadlineExceeded);
+            }
+
+            let (formatted, delta) = if line.len() > HIGHLIGHT_LINE_LENGTH_CUTOFF {
+                (escape(line), 0)
+            } else {
+                let parsed = parse_state.parse_line(line, &self.syntax_set)?;
+                line_tokens_to_classed_spans(
+                    line,
+                    parsed.as_slice(),
+                    ClassStyle::Spaced,
+                    &mut scope_stack,
+                )?
+            };
+
+            push_line_number(&mut html, first + offset);
+
+            if delta < 0 {
+                html.push_str(&"<span>".repeat(delta.abs().try_into()?));
+            }
+
+            // Strip stray newlines that cause vertically stretched lines.
+            for c in formatted.chars().filter(|c| *c != '\n') {
+                html.push(c);
+            }
+
+            if delta > 0 {
+                html.push_str(&"</span>".repeat(delta.try_into()?));
+            }
+
+            html.push_str("</td></tr>");
+        }
+
+        Ok(html)
+    }
+
+    /// Highlight `source` as a table of numbered lines.
+    fn highlight_lines(
+        &self,
+        source: &str,
+        ext: &str,
+        deadline: Option<Deadline>,
+    ) -> Result<String, Error> {
+        if let Some(lines) = self
+            .backend_for(ext)
+            .and_then(|backend| backend.highlight_lines(source, ext))
+        {
+            let mut html = String::from("<table><tbody>");
+
+            for (line_number, line) in lines.iter().enumerate() {
+                push_line_number(&mut html, line_number + 1);
+                html.push_str(line);
+                html.push_str("</td></tr>");
+            }
+
+            html.push_str("</tbody></table>");
+
+            return Ok(html);
+        }
+
+        let syntax = self.find_syntax(ext);
+        let lines = LinesWithEndings::from(source).collect::<Vec<_>>();
+        let mut html = String::from("<table><tbody>");
+
+        match &self.pool {
+            Some(pool) if lines.len() > CHUNK_LINES => {
+                let chunks = pool.install(|| {
+                    lines
+                        .par_chunks(CHUNK_LINES)
+                        .enumerate()
+                        .map(|(index, chunk)| {
+                            let start = index * CHUNK_LINES;
+                            let warm_up = &lines[start.saturating_sub(WARM_UP_LINES)..start];
+                            self.highlight_rows(syntax, warm_up, chunk, start + 1, deadline)
+                        })
+                        .collect::<Result<Vec<_>, Error>>()
+                })?;
+
+                for chunk in chunks {
+                    html.push_str(&chunk);
+                }
+            }
+            _ => html.push_str(&self.highlight_rows(syntax, &[], &lines, 1, deadline)?),
+        }
+
+        html.push_str("</tbody></table>");
+
+        Ok(html)
+    }
+
+    /// Highlight `data` with the given file extension in the given `view` on the blocking pool.
+    /// If that takes longer than the deadline, the plain text rendering is returned together with
+    /// the task, which keeps running in the background until the deadline of the request passes.
+    #[tracing::instrument(level = "debug", skip_all, fields(%ext, bytes = data.text.len()))]
+    pub async fn highlight(
+        self: Arc<Self>,
+        data: Data,
+        ext: String,
+        view: View,
+    ) -> Result<Rendered, Error> {
+        let deadline = self.deadline;
+        let request_deadline = Deadline::current();
+        let text = Arc::new(data.text);
+        let source = Arc::clone(&text);
+
+        let mut task = tokio::task::spawn_blocking(move || {
+            self.highlight_inner(&source, &ext, view, request_deadline)
+                .map(Html::from)
+        });
+
+        match tokio::time::timeout(deadline, &mut task).await {
+            Ok(html) => Ok(Rendered::Done(html??)),
+            Err(_) => Ok(Rendered::Late(fallback(&text), task)),
+        }
+    }
+}
+
+impl Html {
+    pub fn into_inner(self) -> String {
+        self.html
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.html
+    }
+
+    /// Offsets of the `<tr` tags of a table of lines. Content is escaped, so row tags only
+    /// appear as part of the table structure.
+    pub fn rows(&self) -> &[usize] {
+        self.rows.get_or_init(|| {
+            self.html
+                .match_indices("<tr")
+                .map(|(index, _)| index)
+                .collect()
+        })
+    }
+}
+
+impl From<String> for Html {
+    fn from(html: String) -> Self {
+        Self {
+            html,
+            rows: Arc::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "tree-sitter")]
+    #[test]
+    fn tree_sitter_engine() {
+        let source = "fn main() {}\n";
+        let syntect = Highlighter::default()
+            .with_engine(Engine::Syntect)
+            .unwrap()
+            .highlight_lines(source, "rs", None)
+            .unwrap();
+        let tree_sitter = Highlighter::default()
+            .with_engine(Engine::TreeSitter)
+            .unwrap()
+            .highlight_lines(source, "rs", None)
+            .unwrap();
+
+        assert!(syntect.contains("source rust"));
+        assert!(!tree_sitter.contains("source rust"));
+        assert!(tree_sitter.contains(r#"<span class="keyword">fn</span>"#));
+        assert!(tree_sitter.contains(r#"id="L1""#));
+    }
+
+    #[cfg(not(feature = "tree-sitter"))]
+    #[test]
+    fn tree_sitter_unavailable() {
+        assert!(Highlighter::default().with_engine(Engine::Syntect).is_ok());
+        assert!(
+            Highlighter::default()
+                .with_engine(Engine::TreeSitter)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn aliases() {
+        let highlighter = Highlighter::default()
+            .with_aliases([
+                (String::from("jsonc"), String::from("JSON")),
+                (String::from("tpl"), String::from("rs")),
+            ])
+            .unwrap();
+
+        assert_eq!(highlighter.resolve("jsonc"), "json");
+        assert_eq!(highlighter.resolve("py"), "py");
+        assert_eq!(highlighter.extension_for("tpl").as_deref(), Some("rs"));
+        assert_eq!(highlighter.find_syntax("tpl").name, "Rust");
+
+        assert!(
+            Highlighter::default()
+                .with_aliases([(String::from("foo"), String::from("no-such-language"))])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn languages() {
+        let highlighter = Highlighter::default()
+            .with_languages(&[String::from("rs"), String::from("Python")])
+            .unwrap();
+
+        let names = highlighter
+            .syntaxes
+            .iter()
+            .map(|syntax| syntax.name.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["Plain Text", "Python", "Rust"]);
+        assert_eq!(highlighter.find_syntax("rs").name, "Rust");
+        assert_eq!(highlighter.find_syntax("js").name, "Plain Text");
+        assert!(
+            highlighter
+                .highlight_lines("fn main() {}\n", "rs", None)
+                .unwrap()
+                .contains("source rust")
+        );
+
+        assert!(
+            Highlighter::default()
+                .with_languages(&[String::from("no-such-language")])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parallel_chunks() {
+        let source = "fn main() {\n    let x = \"a\";\n}\n".repeat(CHUNK_LINES);
+        let sequential = Highlighter::default()
+            .highlight_lines(&source, "rs", None)
+            .unwrap();
+        let parallel = Highlighter::default()
+            .with_threads(NonZeroUsize::new(4).unwrap())
+            .unwrap()
+            .highlight_lines(&source, "rs", None)
+            .unwrap();
+
+        assert_eq!(parallel, sequential);
+        assert!(parallel.contains(&format!(r#"id="L{}""#, 3 * CHUNK_LINES)));
+    }
+
+    #[test]
+    fn give_up_after_request_deadline() {
+        let highlighter = Highlighter::default();
+        let source = "fn main() {}\n".repeat(10);
+
+        let result =
+            highlighter.highlight_lines(&source, "rs", Some(Deadline::after(Duration::ZERO)));
+        assert!(matches!(result, Err(Error::DeadlineExceeded)));
+
+        let deadline = Deadline::after(Duration::from_secs(60));
+        assert!(
+            highlighter
+                .highlight_lines(&source, "rs", Some(deadline))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn version_covers_custom_syntaxes() {
+        let dir = std::env::temp_dir().join(format!("wastebin-version-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let syntax = |scope: &str| {
+            format!(
+                "%YAML 1.2\n---\nname: KDL\nfile_extensions: [kdl]\nscope: source.kdl\ncontexts:\n  main:\n    - match: '//.*$'\n      scope: {scope}\n"
+            )
+        };
+
+        // Building upon all built-in syntaxes takes too long for a test.
+        let base = || Highlighter {
+            syntax_set: SyntaxSet::new(),
+            ..Highlighter::default()
+        };
+
+        std::fs::write(dir.join("kdl.sublime-syntax"), syntax("comment.kdl")).unwrap();
+        let first = base().with_syntax_dir(&dir, None).unwrap().version();
+        assert_ne!(first, base().version());
+
+        // Same name and extensions, but different rules.
+        std::fs::write(dir.join("kdl.sublime-syntax"), syntax("comment.line.kdl")).unwrap();
+        let second = base().with_syntax_dir(&dir, None).unwrap().version();
+        assert_ne!(first, second);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn plain_view() {
+        let highlighter = Highlighter::default().with_max_bytes(Some(8));
+        assert!(!highlighter.exceeds_limit("fn main"));
+        assert!(highlighter.exceeds_limit("fn main() {}"));
+
+        let html = highlighter
+            .highlight_inner("<b>\nfn main() {}\n", "rs", View::Plain, None)
+            .unwrap();
+
+        assert!(html.contains(r#"<td class="line">&lt;b&gt;</td>"#));
+        assert!(html.contains(r#"id="L2""#));
+        assert!(!html.contains("source rust"));
+    }
+
+    #[test]
+    fn snippet_view() {
+        let html = Highlighter::default()
+            .highlight_inner(&"fn main() {}\n".repeat(10), "rs", View::Snippet, None)
+            .unwrap();
+
+        assert!(html.contains(r#"id="L5""#));
+        assert!(!html.contains(r#"id="L6""#));
+        assert!(html.contains("source rust"));
+    }
+
+    #[test]
+    fn escape_long_lines() {
+        let line = format!(
+            "<script>{}</script>",
+            "a".repeat(HIGHLIGHT_LINE_LENGTH_CUTOFF)
+        );
+        let html = Highlighter::default()
+            .highlight_lines(&line, "txt", None)
+            .unwrap();
+
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;"));
+    }
+
+    #[tokio::test]
+    async fn fall_back_after_deadline() {
+        let highlighter = Arc::new(Highlighter::default().with_deadline(Duration::ZERO));
+        let data = Data {
+            text: "fn main() {}\n".repeat(100),
+            extension: None,
+            title: None,
+            uid: None,
+            ciphertext: false,
+            noindex: false,
+            expires: None,
+        };
+
+        let Rendered::Late(fallback, task) = highlighter
+            .highlight(data, "rs".into(), View::Source)
+            .await
+            .unwrap()
+        else {
+            panic!("expected highlighting to exceed the deadline");
+        };
+
+        assert!(!fallback.into_inner().contains("source rust"));
+        assert!(
+            task.await
+                .unwrap()
+                .unwrap()
+                .into_inner()
+                .contains("source rust")
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use crate::errors::Error;
+use crate::highlight::{Highlighter, escape, markdown};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Image MIME types that are embedded as `data:` URIs.
+const IMAGE_TYPES: [&str; 3] = ["image/png", "image/jpeg", "image/gif"];
+
+/// Subset of the nbformat 4 notebook schema that is necessary for rendering.
+#[derive(Deserialize)]
+struct Notebook {
+    cells: Vec<Cell>,
+    #[serde(default)]
+    metadata: Metadata,
+}
+
+#[derive(Deserialize, Default)]
+struct Metadata {
+    language_info: Option<LanguageInfo>,
+    kernelspec: Option<KernelSpec>,
+}
+
+#[derive(Deserialize)]
+struct LanguageInfo {
+    name: Option<String>,
+    file_extension: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct KernelSpec {
+    language: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "cell_type", rename_all = "lowercase")]
+enum Cell {
+    Markdown {
+        source: Text,
+    },
+    Code {
+        source: Text,
+        #[serde(default)]
+        outputs: Vec<Output>,
+        execution_count: Option<u64>,
+    },
+    Raw {
+        source: Text,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "output_type", rename_all = "snake_case")]
+enum Output {
+    Stream {
+        text: Text,
+    },
+    ExecuteResult {
+        data: HashMap<String, Value>,
+    },
+    DisplayData {
+        data: HashMap<String, Value>,
+    },
+    Error {
+        ename: String,
+        evalue: String,
+        #[serde(default)]
+        traceback: Vec<String>,
+    },
+}
+
+/// Multi-line strings are either stored as a single string or a list of lines.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Text {
+    Single(String),
+    Lines(Vec<String>),
+}
+
+impl Text {
+    fn into_string(self) -> String {
+        match self {
+            Text::Single(text) => text,
+            Text::Lines(lines) => lines.concat(),
+        }
+    }
+}
+
+/// Join a MIME bundle value that is either a string or a list of strings.
+fn bundle_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Array(lines) => Some(lines.iter().filter_map(Value::as_str).collect()),
+        _ => None,
+    }
+}
+
+/// Remove ANSI escape sequences that are commonly found in tracebacks.
+fn strip_ansi(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+impl Metadata {
+    /// File extension or language name used to look up the syntax of code cells.
+    fn language(&self) -> String {
+        self.language_info
+            .as_ref()
+            .and_then(|info| {
+                info.file_extension
+                    .as_deref()
+                    .map(|ext| ext.trim_start_matches('.').to_string())
+                    .or_else(|| info.name.clone())
+            })
+            .or_else(|| {
+                self.kernelspec
+                    .as_ref()
+                    .and_then(|spec| spec.language.clone())
+            })
+            .unwrap_or_else(|| String::from("py"))
+    }
+}
+
+fn render_output(html: &mut String, output: Output) {
+    match output {
+        Output::Stream { text } => {
+            let _ = write!(
+                html,
+                r#"<pre class="cell-output">{}</pre>"#,
+                escape(&text.into_string())
+            );
+        }
+        Output::ExecuteResult { data } | Output::DisplayData { data } => {
+            let image = IMAGE_TYPES.iter().find_map(|mime| {
+                data.get(*mime)
+                    .and_then(bundle_text)
+                    .map(|content| (mime, content))
+            });
+
+            if let Some((mime, content)) = image {
+                let content: String = content.chars().filter(|c| !c.is_whitespace()).collect();
+
+                if content
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '='))
+                {
+                    let _ = write!(
+                        html,
+                        r#"<div class="cell-output"><img src="data:{mime};base64,{content}" alt="output"></div>"#
+                    );
+                    return;
+                }
+            }
+
+            if let Some(text) = data.get("text/plain").and_then(bundle_text) {
+                let _ = write!(html, r#"<pre class="cell-output">{}</pre>"#, escape(&text));
+            }
+        }
+        Output::Error {
+            ename,
+            evalue,
+            traceback,
+        } => {
+            let text = if traceback.is_empty() {
+                format!("{ename}: {evalue}")
+            } else {
+                strip_ansi(&traceback.join("\n"))
+            };
+
+            let _ = write!(
+                html,
+                r#"<pre class="cell-output cell-error">{}</pre>"#,
+                escape(&text)
+            );
+        }
+    }
+}
+
+/// Render `source` as a Jupyter notebook or return `None` if it cannot be parsed as one.
+pub fn render(highlighter: &Highlighter, source: &str) -> Result<Option<String>, Error> {
+    let Ok(notebook) = serde_json::from_str::<Notebook>(source) else {
+        return Ok(None);
+    };
+
+    let language = notebook.metadata.language();
+    let syntax = highlighter.find_syntax(&language);
+    let mut html = String::from(r#"<div class="notebook">"#);
+
+    for cell in notebook.cells {
+        match cell {
+            Cell::Markdown { source } => {
+                let _ = write!(
+                    html,
+                    r#"<div class="cell cell-markdown">{}</div>"#,
+                    markdown::render(&source.into_string())
+                );
+            }
+            Cell::Raw { source } => {
+                let _ = write!(
+                    html,
+                    r#"<div class="cell"><pre class="cell-raw">{}</pre></div>"#,
+                    escape(&source.into_string())
+                );
+            }
+            Cell::Code {
+                source,
+                outputs,
+                execution_count,
+            } => {
+                let prompt = execution_count.map_or_else(String::new, |n| n.to_string());
+                let code = highlighter.highlight_block(&source.into_string(), syntax)?;
+
+                let _ = write!(
+                    html,
+                    r#"<div class="cell cell-code"><div class="cell-prompt">[{prompt}]</div><div class="cell-body"><pre class="cell-input">{code}</pre>"#
+                );
+
+                for output in outputs {
+                    render_output(&mut html, output);
+                }
+
+                html.push_str("</div></div>");
+            }
+        }
+    }
+
+    html.push_str("</div>");
+
+    Ok(Some(html))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r##"{
+      "metadata": {"language_info": {"name": "python", "file_extension": ".py"}},
+      "nbformat": 4,
+      "nbformat_minor": 5,
+      "cells": [
+        {"cell_type": "markdown", "metadata": {}, "source": ["# Analysis\n", "<b>bold</b>"]},
+        {
+          "cell_type": "code",
+          "execution_count": 3,
+          "metadata": {},
+          "source": "print('hi')",
+          "outputs": [
+            {"output_type": "stream", "name": "stdout", "text": ["hi\n"]},
+            {"output_type": "display_data", "metadata": {}, "data": {"image/png": "iVBORw0KGgo=\n", "text/plain": "<Figure>"}},
+            {"output_type": "error", "ename": "ValueError", "evalue": "oops", "traceback": ["\u001b[0;31mValueError\u001b[0m: oops"]}
+          ]
+        }
+      ]
+    }"##;
+
+    #[test]
+    fn render_notebook() {
+        let highlighter = Highlighter::default();
+        let html = render(&highlighter, NOTEBOOK).unwrap().unwrap();
+
+        assert!(html.contains("<h1>Analysis</h1>"));
+        assert!(html.contains("&lt;b&gt;bold&lt;/b&gt;"));
+        assert!(html.contains("[3]"));
+        assert!(html.contains(r#"<pre class="cell-output">hi"#));
+        assert!(html.contains(r#"src="data:image/png;base64,iVBORw0KGgo=""#));
+        assert!(!html.contains("&lt;Figure&gt;"));
+        assert!(html.contains("ValueError: oops"));
+        assert!(!html.contains('\u{1b}'));
+    }
+
+    #[test]
+    fn reject_non_notebooks() {
+        let highlighter = Highlighter::default();
+        assert!(render(&highlighter, "{}").unwrap().is_none());
+        assert!(render(&highlighter, "not json").unwrap().is_none());
+    }
+
+    #[test]
+    fn reject_bogus_image_data() {
+        let highlighter = Highlighter::default();
+        let source = r#"{"cells": [{"cell_type": "code", "source": "", "outputs": [
+            {"output_type": "execute_result", "data": {"image/png": "\"><script>", "text/plain": "fallback"}}
+        ]}]}"#;
+
+        let html = render(&highlighter, source).unwrap().unwrap();
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("fallback"));
+    }
+}
@@ -0,0 +1,106 @@
+use crate::highlight::Html;
+
+/// Number of lines shown per page for pastes that exceed it.
+pub const PAGE_LINES: usize = 10_000;
+
+/// Page of a paste rendered as a table of lines.
+pub(crate) struct Page {
+    /// HTML of the table containing only the rows of this page.
+    pub html: String,
+    /// One-based number of this page.
+    pub number: usize,
+    /// Total number of pages.
+    pub count: usize,
+}
+
+/// Cut the rows of page `number` out of the table of lines in `html`. Returns `None` if `html` is
+/// not a table of lines or fits on a single page of `lines` rows. Out of range page numbers are
+/// clamped to the last page.
+pub(crate) fn paginate(html: &Html, number: usize, lines: usize) -> Option<Page> {
+    if !html.as_str().starts_with("<table") {
+        return None;
+    }
+
+    let rows = html.rows();
+    let html = html.as_str();
+
+    if rows.len() <= lines {
+        return None;
+    }
+
+    let end = html.rfind("</tbody>")?;
+    let count = rows.len().div_ceil(lines);
+    let number = number.clamp(1, count);
+    let start = rows[(number - 1) * lines];
+    let stop = rows.get(number * lines).copied().unwrap_or(end);
+
+    let mut page = String::with_capacity(stop - start + rows[0] + html.len() - end);
+    page.push_str(&html[..rows[0]]);
+    page.push_str(&html[start..stop]);
+    page.push_str(&html[end..]);
+
+    Some(Page {
+        html: page,
+        number,
+        count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    fn table(rows: usize) -> Html {
+        let mut html = String::from("<table><tbody>");
+
+        for row in 1..=rows {
+            let _ = write!(html, r#"<tr><td id="L{row}">&lt;tr</td></tr>"#);
+        }
+
+        html.push_str("</tbody></table>");
+        Html::from(html)
+    }
+
+    #[test]
+    fn split_into_pages() {
+        let html = table(5);
+
+        let page = paginate(&html, 2, 2).unwrap();
+        assert_eq!(page.count, 3);
+        assert_eq!(page.number, 2);
+        assert_eq!(
+            page.html,
+            r#"<table><tbody><tr><td id="L3">&lt;tr</td></tr><tr><td id="L4">&lt;tr</td></tr></tbody></table>"#
+        );
+
+        let page = paginate(&html, 7, 2).unwrap();
+        assert_eq!(page.number, 3);
+        assert_eq!(
+            page.html,
+            r#"<table><tbody><tr><td id="L5">&lt;tr</td></tr></tbody></table>"#
+        );
+
+        let page = paginate(&html, 0, 2).unwrap();
+        assert_eq!(page.number, 1);
+        assert!(page.html.contains(r#"id="L2""#));
+        assert!(!page.html.contains(r#"id="L3""#));
+    }
+
+    #[test]
+    fn share_row_offsets() {
+        let html = table(5);
+        let cached = html.clone();
+        assert!(paginate(&html, 1, 2).is_some());
+
+        // Clones handed out by the cache find the rows without scanning again.
+        assert_eq!(cached.rows.get().map(Vec::len), Some(5));
+    }
+
+    #[test]
+    fn single_page() {
+        assert!(paginate(&table(2), 1, 2).is_none());
+        let tree = Html::from(String::from(r#"<ul class="json-tree"></ul>"#));
+        assert!(paginate(&tree, 1, 2).is_none());
+    }
+}
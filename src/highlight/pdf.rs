@@ -0,0 +1,430 @@
+use crate::errors::Error;
+use crate::highlight::Highlighter;
+use std::io::Write as _;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Color, FontStyle, Style, Theme};
+use syntect::util::LinesWithEndings;
+
+/// A4 page size in points.
+const PAGE_WIDTH: usize = 595;
+const PAGE_HEIGHT: usize = 842;
+const MARGIN: usize = 42;
+const FONT_SIZE: usize = 8;
+const TITLE_SIZE: usize = 10;
+const FOOTER_SIZE: usize = 7;
+const LEADING: usize = 10;
+/// Space between the title and the first line.
+const HEADER: usize = 22;
+
+/// Advance of a Courier glyph at font `size` in tenths of a point.
+const fn char_width(size: usize) -> usize {
+    size * 6
+}
+
+/// Number of characters at font `size` fitting between the margins.
+const fn columns(size: usize) -> usize {
+    (PAGE_WIDTH - 2 * MARGIN) * 10 / char_width(size)
+}
+
+/// Number of lines fitting between header and footer.
+const ROWS: usize = (PAGE_HEIGHT - 2 * MARGIN - HEADER) / LEADING;
+
+/// Standard fonts that every PDF reader provides, in the order referenced by the content streams.
+const FONTS: [&str; 4] = [
+    "Courier",
+    "Courier-Bold",
+    "Courier-Oblique",
+    "Courier-BoldOblique",
+];
+
+/// Span of text sharing one style.
+type Span = (Style, String);
+
+/// Visual row on a page, the first row of every source line carries its number.
+struct Row {
+    number: Option<usize>,
+    spans: Vec<Span>,
+}
+
+/// Encode `c` in the `WinAnsiEncoding` of the standard fonts, unsupported characters become `?`.
+fn win_ansi(c: char) -> u8 {
+    match c {
+        ' '..='~' => c as u8,
+        '\u{a0}'..='\u{ff}' => u8::try_from(u32::from(c)).unwrap_or(b'?'),
+        '€' => 0x80,
+        '…' => 0x85,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        '™' => 0x99,
+        _ => b'?',
+    }
+}
+
+/// Append `text` as PDF string literal to `buf`.
+fn string(buf: &mut Vec<u8>, text: &str) {
+    buf.push(b'(');
+
+    for byte in text.chars().map(win_ansi) {
+        if matches!(byte, b'(' | b')' | b'\\') {
+            buf.push(b'\\');
+        }
+
+        buf.push(byte);
+    }
+
+    buf.push(b')');
+}
+
+fn font(style: FontStyle) -> usize {
+    let bold = style.contains(FontStyle::BOLD);
+    let italic = style.contains(FontStyle::ITALIC);
+
+    1 + usize::from(bold) + 2 * usize::from(italic)
+}
+
+fn color(buf: &mut Vec<u8>, color: Color) {
+    let _ = write!(
+        buf,
+        "{:.3} {:.3} {:.3} rg ",
+        f64::from(color.r) / 255.0,
+        f64::from(color.g) / 255.0,
+        f64::from(color.b) / 255.0
+    );
+}
+
+/// Cut `text` off at `width` characters, marking the cut with an ellipsis.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+
+    let mut truncated = text.chars().take(width - 1).collect::<String>();
+    truncated.push('…');
+    truncated
+}
+
+/// Break the styled ranges of a single source line into rows of at most `width` characters.
+fn wrap(number: usize, ranges: &[(Style, &str)], width: usize) -> Vec<Row> {
+    let mut rows = vec![Row {
+        number: Some(number),
+        spans: Vec::new(),
+    }];
+    let mut column = 0;
+
+    for (style, text) in ranges {
+        for c in text.chars().filter(|c| !matches!(c, '\n' | '\r')) {
+            let (c, count) = if c == '\t' {
+                (' ', 4 - column % 4)
+            } else {
+                (c, 1)
+            };
+
+            for _ in 0..count {
+                if column == width {
+                    rows.push(Row {
+                        number: None,
+                        spans: Vec::new(),
+                    });
+                    column = 0;
+                }
+
+                let spans = &mut rows.last_mut().expect("at least one row").spans;
+
+                match spans.last_mut() {
+                    Some((last, text)) if last == style => text.push(c),
+                    _ => spans.push((*style, c.to_string())),
+                }
+
+                column += 1;
+            }
+        }
+    }
+
+    rows
+}
+
+/// Content stream of page `number` out of `count` showing `rows` below `title` and above `url`.
+fn content(
+    rows: &[Row],
+    gutter: usize,
+    title: &str,
+    url: &str,
+    number: usize,
+    count: usize,
+) -> Vec<u8> {
+    let mut content = Vec::new();
+    let top = PAGE_HEIGHT - MARGIN;
+    let footer = format!("{number} / {count}");
+
+    let _ = write!(
+        content,
+        "0.8 G 0.5 w {MARGIN} {} m {} {} l S ",
+        top - 6,
+        PAGE_WIDTH - MARGIN,
+        top - 6
+    );
+    let _ = write!(
+        content,
+        "BT 0 g /F2 {TITLE_SIZE} Tf 1 0 0 1 {MARGIN} {top} Tm "
+    );
+    string(&mut content, &truncate(title, columns(TITLE_SIZE)));
+    let _ = write!(
+        content,
+        " Tj 0.5 g /F1 {FOOTER_SIZE} Tf 1 0 0 1 {MARGIN} {} Tm ",
+        MARGIN - 16
+    );
+    string(
+        &mut content,
+        &truncate(url, columns(FOOTER_SIZE) - footer.len() - 2),
+    );
+    // Right-align the page number, positions are given in tenths of a point.
+    let x = (PAGE_WIDTH - MARGIN) * 10 - footer.len() * char_width(FOOTER_SIZE);
+    let _ = write!(
+        content,
+        " Tj 1 0 0 1 {}.{} {} Tm ",
+        x / 10,
+        x % 10,
+        MARGIN - 16
+    );
+    string(&mut content, &footer);
+    content.extend_from_slice(b" Tj ");
+
+    for (index, row) in rows.iter().enumerate() {
+        let y = top - HEADER - index * LEADING;
+        let number = row.number.map(|line| line.to_string()).unwrap_or_default();
+
+        let _ = write!(content, "/F1 {FONT_SIZE} Tf 0.6 g 1 0 0 1 {MARGIN} {y} Tm ");
+        string(&mut content, &format!("{number:>0$} ", gutter - 1));
+        content.extend_from_slice(b" Tj ");
+
+        for (style, text) in &row.spans {
+            let _ = write!(content, "/F{} {FONT_SIZE} Tf ", font(style.font_style));
+            color(&mut content, style.foreground);
+            string(&mut content, text);
+            content.extend_from_slice(b" Tj ");
+        }
+    }
+
+    content.extend_from_slice(b"ET");
+    content
+}
+
+/// Minimal writer of PDF objects that keeps track of their offsets for the cross-reference table.
+struct Document {
+    buf: Vec<u8>,
+    offsets: Vec<usize>,
+}
+
+impl Document {
+    fn new() -> Self {
+        Self {
+            buf: b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec(),
+            offsets: Vec::new(),
+        }
+    }
+
+    /// Append the next object with `body`, objects are numbered consecutively starting at one.
+    fn object(&mut self, body: &[u8]) {
+        self.offsets.push(self.buf.len());
+        let _ = writeln!(self.buf, "{} 0 obj", self.offsets.len());
+        self.buf.extend_from_slice(body);
+        self.buf.extend_from_slice(b"\nendobj\n");
+    }
+
+    fn stream(&mut self, content: &[u8]) {
+        let mut body = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        body.extend_from_slice(content);
+        body.extend_from_slice(b"\nendstream");
+        self.object(&body);
+    }
+
+    /// Write the cross-reference table and trailer pointing at the `root` and `info` objects.
+    fn finish(mut self, root: usize, info: usize) -> Vec<u8> {
+        let xref = self.buf.len();
+        let _ = write!(
+            self.buf,
+            "xref\n0 {}\n0000000000 65535 f \n",
+            self.offsets.len() + 1
+        );
+
+        for offset in &self.offsets {
+            let _ = writeln!(self.buf, "{offset:010} 00000 n ");
+        }
+
+        let _ = write!(
+            self.buf,
+            "trailer\n<< /Size {} /Root {root} 0 R /Info {info} 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            self.offsets.len() + 1
+        );
+
+        self.buf
+    }
+}
+
+impl Highlighter {
+    /// Render `source` as printable A4 PDF document, colored by `theme` and headed by `title`.
+    /// Every page carries `url` and its page number in the footer. Long lines are wrapped and
+    /// characters outside of the Latin-1 range are replaced due to the standard fonts used.
+    pub fn pdf(
+        &self,
+        source: &str,
+        ext: &str,
+        theme: &Theme,
+        title: &str,
+        url: &str,
+    ) -> Result<Vec<u8>, Error> {
+        let count = LinesWithEndings::from(source).count();
+        let gutter = count.max(1).to_string().len() + 1;
+        let width = columns(FONT_SIZE) - gutter;
+        let plain = Style {
+            foreground: theme.settings.foreground.unwrap_or(Color::BLACK),
+            ..Style::default()
+        };
+
+        let skip = self.exceeds_limit(source);
+        let mut highlighter = HighlightLines::new(self.find_syntax(ext), theme);
+        let mut rows = Vec::new();
+
+        for (index, line) in LinesWithEndings::from(source).enumerate() {
+            let ranges = if skip {
+                vec![(plain, line)]
+            } else {
+                highlighter.highlight_line(line, &self.syntax_set)?
+            };
+
+            rows.extend(wrap(index + 1, &ranges, width));
+        }
+
+        let pages = rows.chunks(ROWS).collect::<Vec<_>>();
+        let pages = if pages.is_empty() {
+            vec![&rows[..]]
+        } else {
+            pages
+        };
+
+        // Objects 1 to 3 are catalog, page tree and info, followed by the fonts and then one page
+        // object and content stream per page.
+        let first_page = 4 + FONTS.len();
+        let kids = (0..pages.len())
+            .map(|page| format!("{} 0 R", first_page + 2 * page))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let fonts = (0..FONTS.len())
+            .map(|index| format!("/F{} {} 0 R", index + 1, 4 + index))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut document = Document::new();
+        document.object(b"<< /Type /Catalog /Pages 2 0 R >>");
+        document
+            .object(format!("<< /Type /Pages /Kids [{kids}] /Count {} >>", pages.len()).as_bytes());
+
+        let mut info = b"<< /Producer (wastebin) /Title ".to_vec();
+        string(&mut info, title);
+        info.extend_from_slice(b" >>");
+        document.object(&info);
+
+        for name in FONTS {
+            document.object(
+                format!(
+                    "<< /Type /Font /Subtype /Type1 /BaseFont /{name} /Encoding /WinAnsiEncoding >>"
+                )
+                .as_bytes(),
+            );
+        }
+
+        for (page, rows) in pages.iter().enumerate() {
+            let content = content(rows, gutter, title, url, page + 1, pages.len());
+
+            document.object(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] /Resources << /Font << {fonts} >> >> /Contents {} 0 R >>",
+                    first_page + 2 * page + 1
+                )
+                .as_bytes(),
+            );
+            document.stream(&content);
+        }
+
+        Ok(document.finish(1, 3))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme() -> Theme {
+        two_face::theme::extra()
+            .get(two_face::theme::EmbeddedThemeName::InspiredGithub)
+            .clone()
+    }
+
+    #[test]
+    fn wrap_long_lines() {
+        let style = Style::default();
+        let rows = wrap(7, &[(style, "ab\tc"), (style, "defg\n")], 3);
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].number, Some(7));
+        assert_eq!(rows[0].spans, vec![(style, String::from("ab "))]);
+        assert_eq!(rows[1].number, None);
+        assert_eq!(rows[1].spans, vec![(style, String::from(" cd"))]);
+        assert_eq!(rows[2].spans, vec![(style, String::from("efg"))]);
+    }
+
+    #[test]
+    fn escape_strings() {
+        let mut buf = Vec::new();
+        string(&mut buf, r"f(x) \ é → “");
+        assert_eq!(buf, b"(f\\(x\\) \\\\ \xe9 ? \x93)");
+    }
+
+    #[test]
+    fn render_pdf() {
+        let highlighter = Highlighter::default();
+        let source = "fn main() {}\n".repeat(ROWS + 1);
+        let pdf = highlighter
+            .pdf(
+                &source,
+                "rs",
+                &theme(),
+                "main.rs",
+                "https://example.com/abc",
+            )
+            .unwrap();
+        let content = String::from_utf8_lossy(&pdf);
+
+        assert!(content.starts_with("%PDF-1.4"));
+        assert!(content.ends_with("%%EOF\n"));
+        assert!(content.contains("/Count 2"));
+        assert!(content.contains("(main.rs)"));
+        assert!(content.contains("(https://example.com/abc)"));
+        assert!(content.contains("(2 / 2)"));
+        assert!(content.contains("(fn)"));
+
+        // Every offset in the cross-reference table points at the start of its object.
+        let xref = content.rfind("xref\n").unwrap();
+        for (number, line) in content[xref..].lines().skip(3).take(10).enumerate() {
+            let offset = line[..10].parse::<usize>().unwrap();
+            assert!(content[offset..].starts_with(&format!("{} 0 obj", number + 1)));
+        }
+
+        let pdf = highlighter.pdf("", "txt", &theme(), "empty", "").unwrap();
+        assert!(String::from_utf8_lossy(&pdf).contains("/Count 1"));
+
+        // Sources above the highlight limit are printed as plain text in one span per line.
+        let pdf = Highlighter::default()
+            .with_max_bytes(Some(8))
+            .pdf("fn main() {}\n", "rs", &theme(), "main.rs", "")
+            .unwrap();
+        let content = String::from_utf8_lossy(&pdf);
+        assert!(!content.contains("(fn)"));
+        assert!(content.contains("(fn main\\(\\) {})"));
+    }
+}
@@ -0,0 +1,177 @@
+use crate::highlight::Backend;
+use std::collections::HashMap;
+use tree_sitter::QueryError;
+use tree_sitter_highlight::{HighlightConfiguration, HtmlRenderer};
+
+/// Recognized capture names and the space-separated scope classes they are rendered
+/// with, so the syntect theme stylesheets apply unchanged.
+const CAPTURES: [(&str, &str); 25] = [
+    ("attribute", "entity other attribute-name"),
+    ("comment", "comment"),
+    ("constant", "constant"),
+    ("constant.builtin", "constant language"),
+    ("constructor", "entity name type"),
+    ("escape", "constant character escape"),
+    ("function", "entity name function"),
+    ("function.builtin", "support function"),
+    ("function.macro", "support macro"),
+    ("function.method", "entity name function"),
+    ("keyword", "keyword"),
+    ("label", "entity name label"),
+    ("number", "constant numeric"),
+    ("operator", "keyword operator"),
+    ("property", "variable other member"),
+    ("punctuation", "punctuation"),
+    ("punctuation.bracket", "punctuation section"),
+    ("punctuation.delimiter", "punctuation separator"),
+    ("string", "string"),
+    ("string.special", "string regexp"),
+    ("tag", "entity name tag"),
+    ("type", "entity name type"),
+    ("type.builtin", "storage type"),
+    ("variable.builtin", "variable language"),
+    ("variable.parameter", "variable parameter"),
+];
+
+/// Highlighting backend based on tree-sitter grammars.
+pub(crate) struct TreeSitter {
+    /// Highlight configurations by file extension.
+    configs: HashMap<&'static str, HighlightConfiguration>,
+    /// Pre-rendered `class` attributes indexed like [`CAPTURES`].
+    attributes: Vec<Vec<u8>>,
+}
+
+impl TreeSitter {
+    /// Compile the highlight queries of all bundled grammars.
+    pub fn new() -> Result<Self, QueryError> {
+        let typescript = [
+            tree_sitter_typescript::HIGHLIGHTS_QUERY,
+            tree_sitter_javascript::HIGHLIGHT_QUERY,
+        ]
+        .concat();
+        let tsx = [
+            tree_sitter_typescript::HIGHLIGHTS_QUERY,
+            tree_sitter_javascript::JSX_HIGHLIGHT_QUERY,
+            tree_sitter_javascript::HIGHLIGHT_QUERY,
+        ]
+        .concat();
+        let locals = [
+            tree_sitter_typescript::LOCALS_QUERY,
+            tree_sitter_javascript::LOCALS_QUERY,
+        ]
+        .concat();
+
+        let mut configs = HashMap::from([
+            (
+                "rs",
+                HighlightConfiguration::new(
+                    tree_sitter_rust::LANGUAGE.into(),
+                    "rust",
+                    tree_sitter_rust::HIGHLIGHTS_QUERY,
+                    "",
+                    "",
+                )?,
+            ),
+            (
+                "ts",
+                HighlightConfiguration::new(
+                    tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+                    "typescript",
+                    &typescript,
+                    "",
+                    &locals,
+                )?,
+            ),
+            (
+                "tsx",
+                HighlightConfiguration::new(
+                    tree_sitter_typescript::LANGUAGE_TSX.into(),
+                    "tsx",
+                    &tsx,
+                    "",
+                    &locals,
+                )?,
+            ),
+            (
+                "zig",
+                HighlightConfiguration::new(
+                    tree_sitter_zig::LANGUAGE.into(),
+                    "zig",
+                    tree_sitter_zig::HIGHLIGHTS_QUERY,
+                    "",
+                    "",
+                )?,
+            ),
+        ]);
+
+        let names = CAPTURES.map(|(capture, _)| capture);
+
+        for config in configs.values_mut() {
+            config.configure(&names);
+        }
+
+        let attributes = CAPTURES
+            .iter()
+            .map(|(_, classes)| format!(r#"class="{classes}""#).into_bytes())
+            .collect();
+
+        Ok(Self {
+            configs,
+            attributes,
+        })
+    }
+}
+
+impl Backend for TreeSitter {
+    fn supports(&self, ext: &str) -> bool {
+        self.configs.contains_key(ext)
+    }
+
+    fn highlight_lines(&self, source: &str, ext: &str) -> Option<Vec<String>> {
+        let config = self.configs.get(ext)?;
+        let mut highlighter = tree_sitter_highlight::Highlighter::new();
+        let mut renderer = HtmlRenderer::new();
+
+        let events = highlighter
+            .highlight(config, source.as_bytes(), None, |_| None)
+            .and_then(|events| {
+                renderer.render(events, source.as_bytes(), &|highlight, html| {
+                    if let Some(attribute) = self.attributes.get(highlight.0) {
+                        html.extend_from_slice(attribute);
+                    }
+                })
+            });
+
+        if let Err(err) = events {
+            tracing::warn!("failed to highlight with tree-sitter: {err}");
+            return None;
+        }
+
+        Some(
+            renderer
+                .lines()
+                .map(|line| line.trim_end_matches('\n').to_string())
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlight_rust() {
+        let backend = TreeSitter::new().unwrap();
+        assert!(backend.supports("tsx"));
+        assert!(!backend.supports("py"));
+
+        let lines = backend
+            .highlight_lines("fn main() {\n    // <hi>\n}\n", "rs")
+            .unwrap();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains(r#"<span class="keyword">fn</span>"#));
+        assert!(lines[1].contains(r#"<span class="comment">// &lt;hi&gt;</span>"#));
+    }
+}
@@ -0,0 +1,534 @@
+use crate::expiration::Expiration;
+use crate::highlight::escape;
+use axum::extract::{Request, State};
+use axum::http::HeaderValue;
+use axum::http::header::{ACCEPT_LANGUAGE, VARY};
+use axum::middleware::Next;
+use axum::response::Response;
+use axum_extra::extract::cookie::CookieJar;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, LazyLock};
+use unic_langid::LanguageIdentifier;
+
+/// Locales shipped with the binary.
+const BUILTIN: [(&str, &str); 2] = [
+    ("de", include_str!("locales/de.ftl")),
+    ("en", include_str!("locales/en.ftl")),
+];
+
+/// Locale of requests that accept none of the available ones, and which fills in messages the
+/// others leave out.
+const DEFAULT: &str = "en";
+
+/// Cookie overriding the locale negotiated from `Accept-Language`.
+pub(crate) const COOKIE: &str = "locale";
+
+/// Placeholder substituted with markup after escaping a message.
+const PLACEHOLDER: &str = "\u{e000}";
+
+/// Catalog used outside of requests, e.g. by tests rendering templates directly.
+static FALLBACK: LazyLock<Catalog> = LazyLock::new(Catalog::default);
+
+tokio::task_local! {
+    static CURRENT: Current;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("failed to read {0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("failed to parse {0}: {1}")]
+    Parse(String, String),
+    #[error("invalid language tag {0}: {1}")]
+    Tag(String, unic_langid::LanguageIdentifierError),
+}
+
+/// Messages of a single language.
+pub(crate) struct Locale {
+    tag: String,
+    /// Name of the locale in its own language.
+    name: String,
+    bundle: FluentBundle<Arc<FluentResource>>,
+}
+
+/// All available locales by language tag.
+#[derive(Clone)]
+pub(crate) struct Catalog {
+    locales: Arc<BTreeMap<String, Arc<Locale>>>,
+}
+
+/// Locale of the request handled by the current task and the catalog it was picked from.
+#[derive(Clone)]
+struct Current {
+    locale: Arc<Locale>,
+    catalog: Catalog,
+}
+
+fn parse(name: &str, source: String) -> Result<Arc<FluentResource>, Error> {
+    FluentResource::try_new(source)
+        .map(Arc::new)
+        .map_err(|(_, errors)| Error::Parse(name.to_string(), format!("{errors:?}")))
+}
+
+impl Locale {
+    /// Build the locale `tag` from its `resources`, falling back to `default` for messages they
+    /// do not define.
+    fn new(
+        tag: &str,
+        resources: Vec<Arc<FluentResource>>,
+        default: &[Arc<FluentResource>],
+    ) -> Result<Self, Error> {
+        let id = tag
+            .parse::<LanguageIdentifier>()
+            .map_err(|err| Error::Tag(tag.to_string(), err))?;
+
+        let mut bundle = FluentBundle::new_concurrent(vec![id]);
+        bundle.set_use_isolating(false);
+
+        for resource in resources.into_iter().rev() {
+            // Resources added later override the earlier ones, which are skipped here.
+            let _ = bundle.add_resource(resource);
+        }
+
+        let mut locale = Self {
+            tag: tag.to_string(),
+            name: tag.to_string(),
+            bundle,
+        };
+
+        if locale.bundle.has_message("locale-name") {
+            locale.name = locale.format("locale-name", None);
+        }
+
+        for resource in default {
+            let _ = locale.bundle.add_resource(Arc::clone(resource));
+        }
+
+        Ok(locale)
+    }
+
+    /// Format the value of message `key`, or its attribute if `key` is `message.attribute`.
+    fn format(&self, key: &str, args: Option<&FluentArgs>) -> String {
+        let (id, attribute) = key
+            .split_once('.')
+            .map_or((key, None), |(id, attribute)| (id, Some(attribute)));
+
+        let pattern = self
+            .bundle
+            .get_message(id)
+            .and_then(|message| match attribute {
+                Some(attribute) => message.get_attribute(attribute).map(|attr| attr.value()),
+                None => message.value(),
+            });
+
+        let Some(pattern) = pattern else {
+            tracing::warn!("missing message {key} in locale {}", self.tag);
+            return key.to_string();
+        };
+
+        let mut errors = Vec::new();
+        let formatted = self.bundle.format_pattern(pattern, args, &mut errors);
+
+        if !errors.is_empty() {
+            tracing::warn!("failed to format message {key}: {errors:?}");
+        }
+
+        formatted.into_owned()
+    }
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Self::build(BTreeMap::new()).expect("built-in locales are valid")
+    }
+}
+
+impl Catalog {
+    /// Combine the built-in locales with additional or overriding messages from `resources`.
+    fn build(mut resources: BTreeMap<String, Vec<Arc<FluentResource>>>) -> Result<Self, Error> {
+        for (tag, source) in BUILTIN {
+            let builtin = parse(&format!("{tag}.ftl"), source.to_string())?;
+            resources
+                .entry(tag.to_string())
+                .or_default()
+                .insert(0, builtin);
+        }
+
+        let default = resources.get(DEFAULT).cloned().unwrap_or_default();
+
+        let locales = resources
+            .into_iter()
+            .map(|(tag, resources)| {
+                let locale = Locale::new(&tag, resources, &default)?;
+                Ok((tag, Arc::new(locale)))
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Self {
+            locales: Arc::new(locales),
+        })
+    }
+
+    /// Load the built-in locales together with the `<tag>.ftl` files in `dir`, which add locales
+    /// or override messages of the built-in ones.
+    pub fn load(dir: &Path) -> Result<Self, Error> {
+        let display = dir.display().to_string();
+        let entries = std::fs::read_dir(dir).map_err(|err| Error::Io(display.clone(), err))?;
+        let mut resources = BTreeMap::<String, Vec<_>>::new();
+
+        for entry in entries {
+            let path = entry.map_err(|err| Error::Io(display.clone(), err))?.path();
+
+            if path.extension().is_none_or(|ext| ext != "ftl") {
+                continue;
+            }
+
+            let Some(tag) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+
+            let name = path.display().to_string();
+            let source =
+                std::fs::read_to_string(&path).map_err(|err| Error::Io(name.clone(), err))?;
+
+            resources
+                .entry(tag.to_string())
+                .or_default()
+                .push(parse(&name, source)?);
+        }
+
+        Self::build(resources)
+    }
+
+    pub fn get(&self, tag: &str) -> Option<Arc<Locale>> {
+        self.locales.get(tag).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.locales.len()
+    }
+
+    fn default_locale(&self) -> Arc<Locale> {
+        self.get(DEFAULT)
+            .or_else(|| self.locales.values().next().cloned())
+            .expect("catalog contains the built-in locales")
+    }
+
+    /// Pick the locale preferred by an `Accept-Language` header, matching tags exactly first and
+    /// by their primary language otherwise.
+    pub fn negotiate(&self, accept_language: &str) -> Option<Arc<Locale>> {
+        let mut ranges = accept_language
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+
+                (!tag.is_empty() && quality > 0.0).then_some((tag, quality))
+            })
+            .collect::<Vec<_>>();
+
+        ranges.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        ranges.iter().find_map(|(tag, _)| {
+            self.locales
+                .iter()
+                .find(|(candidate, _)| candidate.eq_ignore_ascii_case(tag))
+                .or_else(|| {
+                    let primary = tag.split('-').next().unwrap_or(tag);
+                    self.locales
+                        .iter()
+                        .find(|(candidate, _)| candidate.eq_ignore_ascii_case(primary))
+                })
+                .map(|(_, locale)| Arc::clone(locale))
+        })
+    }
+}
+
+/// Value passed to a message.
+pub(crate) trait Arg {
+    fn value(&self) -> FluentValue<'_>;
+}
+
+macro_rules! number_arg {
+    ($($ty:ty),*) => {
+        $(impl Arg for $ty {
+            fn value(&self) -> FluentValue<'_> {
+                FluentValue::from(*self)
+            }
+        })*
+    };
+}
+
+number_arg!(i32, i64, u32, u64, usize, f64);
+
+impl Arg for str {
+    fn value(&self) -> FluentValue<'_> {
+        FluentValue::from(self)
+    }
+}
+
+impl Arg for String {
+    fn value(&self) -> FluentValue<'_> {
+        FluentValue::from(self.as_str())
+    }
+}
+
+impl<T: Arg + ?Sized> Arg for &T {
+    fn value(&self) -> FluentValue<'_> {
+        (**self).value()
+    }
+}
+
+fn with_current<T>(f: impl FnOnce(&Current) -> T) -> T {
+    let current = CURRENT
+        .try_with(Current::clone)
+        .unwrap_or_else(|_| Current {
+            locale: FALLBACK.default_locale(),
+            catalog: FALLBACK.clone(),
+        });
+
+    f(&current)
+}
+
+/// Translate `key` with `args` into the locale of the current request.
+pub(crate) fn translate(key: &str, args: &[(&str, &dyn Arg)]) -> String {
+    let args = (!args.is_empty()).then(|| {
+        args.iter()
+            .map(|(name, value)| (*name, value.value()))
+            .collect::<FluentArgs>()
+    });
+
+    with_current(|current| current.locale.format(key, args.as_ref()))
+}
+
+/// Translate `key` into the locale of the current request.
+pub(crate) fn t(key: &str) -> String {
+    translate(key, &[])
+}
+
+/// Translate `key` with argument `name` set to `value`.
+pub(crate) fn t1(key: &str, name: &str, value: impl Arg) -> String {
+    translate(key, &[(name, &value)])
+}
+
+/// Translate `key` with arguments `first` and `second` set to their values.
+pub(crate) fn t2(
+    key: &str,
+    first: &str,
+    first_value: impl Arg,
+    second: &str,
+    second_value: impl Arg,
+) -> String {
+    translate(key, &[(first, &first_value), (second, &second_value)])
+}
+
+/// Escape `message` and replace the placeholder it was formatted with by `markup`.
+fn substitute(message: &str, markup: &str) -> String {
+    escape(message).replacen(PLACEHOLDER, markup, 1)
+}
+
+/// Translate `key` to HTML with `$link` as an anchor to `href`, labelled by the `.link`
+/// attribute of the message.
+pub(crate) fn link(key: &str, href: &str) -> String {
+    let label = t(&format!("{key}.link"));
+    let anchor = format!(
+        r#"<a class="text-link" href="{}">{}</a>"#,
+        escape(href),
+        escape(&label),
+    );
+
+    substitute(&t1(key, "link", PLACEHOLDER), &anchor)
+}
+
+/// Translate `key` to HTML with argument `name` set to `value` as inline code.
+pub(crate) fn code(key: &str, name: &str, value: &str) -> String {
+    let code = format!("<code>{}</code>", escape(value));
+
+    substitute(&t1(key, name, PLACEHOLDER), &code)
+}
+
+/// Language tag of the current request.
+pub(crate) fn lang() -> String {
+    with_current(|current| current.locale.tag.clone())
+}
+
+/// Tags and names of all locales, and if they are the one of the current request.
+pub(crate) fn available() -> Vec<(String, String, bool)> {
+    with_current(|current| {
+        current
+            .catalog
+            .locales
+            .values()
+            .map(|locale| {
+                let selected = locale.tag == current.locale.tag;
+                (locale.tag.clone(), locale.name.clone(), selected)
+            })
+            .collect()
+    })
+}
+
+/// Translate `expiration` into a rough human-readable duration.
+pub(crate) fn expiration(expiration: &Expiration) -> String {
+    let parts = expiration.parts();
+
+    if parts.is_empty() {
+        return t("expires-never");
+    }
+
+    parts
+        .into_iter()
+        .map(|(unit, count)| t1(&format!("expires-{unit}s"), "count", count))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Handle the request in the locale picked by the cookie or negotiated from `Accept-Language`.
+pub(crate) async fn negotiate(
+    State(catalog): State<Catalog>,
+    jar: CookieJar,
+    request: Request,
+    next: Next,
+) -> Response {
+    let locale = jar
+        .get(COOKIE)
+        .and_then(|cookie| catalog.get(cookie.value()))
+        .or_else(|| {
+            request
+                .headers()
+                .get(ACCEPT_LANGUAGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| catalog.negotiate(value))
+        })
+        .unwrap_or_else(|| catalog.default_locale());
+
+    let negotiated = catalog.len() > 1;
+    let current = Current { locale, catalog };
+    let mut response = CURRENT.scope(current, next.run(request)).await;
+
+    if negotiated {
+        response
+            .headers_mut()
+            .append(VARY, HeaderValue::from_static("accept-language"));
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{Client, StoreCookies};
+
+    fn keys(source: &str) -> Vec<&str> {
+        source
+            .lines()
+            .filter(|line| line.starts_with(|c: char| c.is_ascii_lowercase()))
+            .filter_map(|line| line.split_once(" =").map(|(key, _)| key))
+            .collect()
+    }
+
+    #[test]
+    fn builtin_locales_complete() {
+        let english = keys(include_str!("locales/en.ftl"));
+
+        for (tag, source) in BUILTIN {
+            let messages = keys(source);
+
+            for key in &english {
+                assert!(messages.contains(key), "{tag} lacks {key}");
+            }
+        }
+    }
+
+    #[test]
+    fn negotiation() {
+        let catalog = Catalog::default();
+        let tag = |header| catalog.negotiate(header).map(|locale| locale.tag.clone());
+
+        assert_eq!(tag("de-AT, en;q=0.5").as_deref(), Some("de"));
+        assert_eq!(tag("fr, en;q=0.8, de;q=0.9").as_deref(), Some("de"));
+        assert_eq!(tag("fr, de;q=0").as_deref(), None);
+        assert_eq!(tag("EN-us").as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn links_are_escaped() {
+        let html = link("share-link", "/a?b=\"c\"");
+
+        assert!(html.starts_with("Copy and send <a class=\"text-link\""));
+        assert!(html.contains("href=\"/a?b=&quot;c&quot;\">this link</a>"));
+    }
+
+    #[test]
+    fn drop_in_locale() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("fr.ftl"),
+            "locale-name = Français\npaste = Coller\n",
+        )?;
+        std::fs::write(dir.path().join("de.ftl"), "paste = Abschicken\n")?;
+
+        let catalog = Catalog::load(dir.path())?;
+        assert_eq!(catalog.len(), 3);
+
+        let french = catalog.get("fr").unwrap();
+        assert_eq!(french.name, "Français");
+        assert_eq!(french.format("paste", None), "Coller");
+        assert_eq!(french.format("save", None), "Save");
+
+        let german = catalog.get("de").unwrap();
+        assert_eq!(german.name, "Deutsch");
+        assert_eq!(german.format("paste", None), "Abschicken");
+        assert_eq!(german.format("save", None), "Speichern");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn accept_language() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        let res = client.get("/").send().await?;
+        assert_eq!(res.headers().get(VARY).unwrap(), "accept-language");
+        let content = res.text().await?;
+        assert!(content.contains(r#"<html lang="en">"#));
+        assert!(content.contains(">Paste</button>"));
+
+        let res = client
+            .get("/")
+            .header(ACCEPT_LANGUAGE, "de-DE,de;q=0.9,en;q=0.8")
+            .send()
+            .await?;
+        let content = res.text().await?;
+        assert!(content.contains(r#"<html lang="de">"#));
+        assert!(content.contains(">Einfügen</button>"));
+        assert!(content.contains(r#"<option value="de" selected>Deutsch</option>"#));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cookie_override() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(true)).await;
+
+        client.get("/theme?locale=unknown").send().await?;
+        let res = client.get("/").send().await?;
+        assert!(res.text().await?.contains(r#"<html lang="en">"#));
+
+        client.get("/theme?locale=de").send().await?;
+
+        let res = client.get("/").header(ACCEPT_LANGUAGE, "en").send().await?;
+        assert!(res.text().await?.contains(r#"<html lang="de">"#));
+
+        let res = client.get("/does-not-exist").send().await?;
+        assert!(res.text().await?.contains(">zurück</a>"));
+
+        Ok(())
+    }
+}
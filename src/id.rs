@@ -26,6 +26,12 @@ impl Id {
         Self::Id64(rand::rng().random::<i64>())
     }
 
+    /// Reconstruct an identifier read from the database. Values that fit into 32 bits stem from
+    /// the six-character identifiers of earlier versions.
+    pub fn from_i64(n: i64) -> Self {
+        u32::try_from(n).map_or(Self::Id64(n), Self::Id32)
+    }
+
     /// Return i64 representation for database storage purposes.
     pub fn to_i64(self) -> i64 {
         match self {
@@ -34,12 +40,18 @@ impl Id {
         }
     }
 
-    /// Generate a URL path from the string representation and `entry`'s extension.
+    /// Generate a URL path from the string representation, `entry`'s capability token if it is
+    /// secret and its extension.
     pub fn to_url_path(self, entry: &Entry) -> String {
+        let id = match &entry.secret {
+            Some(token) => format!("{self}_{token}"),
+            None => format!("{self}"),
+        };
+
         entry
             .extension
             .as_ref()
-            .map_or_else(|| format!("{self}"), |ext| format!("{self}.{ext}"))
+            .map_or_else(|| id.clone(), |ext| format!("{id}.{ext}"))
     }
 }
 
@@ -122,7 +134,7 @@ impl FromStr for Id {
 
             Ok(Self::Id64(n))
         } else {
-            return Err(Error::WrongSize);
+            Err(Error::WrongSize)
         }
     }
 }
@@ -153,23 +165,23 @@ mod tests {
         assert_eq!(id.to_string(), "aaaaaaaaaaa");
         assert_eq!(id.to_i64(), 0);
 
-        let id = Id::from(0xffffffffu32);
+        let id = Id::from(0xffff_ffffu32);
         assert_eq!(id.to_string(), "+++++d");
-        assert_eq!(id.to_i64(), 0xffffffff);
+        assert_eq!(id.to_i64(), 0xffff_ffff);
 
-        let id = Id::from(0xfffffffffffffffi64);
+        let id = Id::from(0x0fff_ffff_ffff_ffffi64);
         assert_eq!(id.to_string(), "d+++++++++p");
-        assert_eq!(id.to_i64(), 0xfffffffffffffff);
+        assert_eq!(id.to_i64(), 0x0fff_ffff_ffff_ffff);
     }
 
     #[test]
     fn convert_string_to_id_and_back() {
         let id = Id::from_str("bJZCna").unwrap();
-        assert_eq!(id.to_i64(), 104651828);
+        assert_eq!(id.to_i64(), 104_651_828);
         assert_eq!(id.to_string(), "bJZCna");
 
         let id = Id::from_str("eVI4Z48hybf").unwrap();
-        assert_eq!(id.to_i64(), 1367045688504311829);
+        assert_eq!(id.to_i64(), 1_367_045_688_504_311_829);
         assert_eq!(id.to_string(), "eVI4Z48hybf");
     }
 
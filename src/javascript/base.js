@@ -2,7 +2,89 @@ function $(id) {
   return document.getElementById(id);
 }
 
+// Call the function of `handlers` named after the action whose key, as listed on the help
+// overlay, is pressed outside of form fields. The `help` action toggles the overlay.
+function bindShortcuts(handlers) {
+  const overlay = $("overlay");
+
+  if (!overlay) {
+    return;
+  }
+
+  const actions = {};
+
+  for (const [action, key] of Object.entries(JSON.parse(overlay.dataset.shortcuts))) {
+    actions[key] = action;
+  }
+
+  const show = (shown) => overlay.style.display = shown ? "block" : "none";
+  overlay.addEventListener("click", () => show(false));
+
+  document.addEventListener("keydown", (e) => {
+    if (e.key == "Escape") {
+      show(false);
+      return;
+    }
+
+    if (["SELECT", "INPUT", "TEXTAREA"].includes(e.target.tagName) || e.ctrlKey || e.metaKey || e.altKey) {
+      return;
+    }
+
+    const action = actions[e.key];
+
+    if (action == "help") {
+      show(overlay.style.display != "block");
+    }
+    else if (handlers[action]) {
+      e.preventDefault();
+      handlers[action]();
+    }
+  });
+}
+
+// Show timestamps rendered in the instance's time zone in the viewer's one, formatted for the
+// page language in the regional variant the browser prefers, keeping the server's text as title.
+function localizeTimes() {
+  const lang = document.documentElement.lang;
+  const locale = navigator.languages.find(tag => tag.split("-")[0] == lang) || lang;
+  const format = new Intl.DateTimeFormat(locale, { dateStyle: "medium", timeStyle: "short" });
+
+  for (const time of document.querySelectorAll("time.local-time")) {
+    time.title = time.textContent;
+    time.textContent = format.format(new Date(time.dateTime));
+  }
+}
+
+// Fetch the highlighted first lines of listed pastes once they scroll into view.
+function loadSnippets() {
+  const slots = document.querySelectorAll("[data-snippet]");
+
+  if (slots.length == 0 || !window.IntersectionObserver) {
+    return;
+  }
+
+  const observer = new IntersectionObserver((entries) => {
+    for (const entry of entries.filter(entry => entry.isIntersecting)) {
+      const slot = entry.target;
+      observer.unobserve(slot);
+
+      fetch(slot.dataset.snippet)
+        .then(res => res.ok ? res.text() : "")
+        .then(html => slot.innerHTML = html)
+        .catch((err) => console.error("failed to load snippet", err));
+    }
+  }, { rootMargin: "200px" });
+
+  slots.forEach(slot => observer.observe(slot));
+}
+
 window.onload = function() {
+  localizeTimes();
+  loadSnippets();
+  navigator.serviceWorker?.register("/sw.js").catch((err) => console.error("failed to register service worker", err));
+  $("theme-select").addEventListener("change", () => $("theme-form").submit());
+  $("locale-select")?.addEventListener("change", () => $("locale-form").submit());
+
   // Read cookie first and check the preference.
   const value = document.cookie.match('(^|;)\\s*pref\\s*=\\s*([^;]+)')?.pop() || '';
 
@@ -5,55 +5,228 @@ function $(id) {
 function dropHandler(ev) {
   ev.preventDefault();
 
-  if (ev.dataTransfer.items) {
-    const item = ev.dataTransfer.items[0];
+  const files = ev.dataTransfer.items
+    ? [...ev.dataTransfer.items].filter((item) => item.kind === 'file').map((item) => item.getAsFile())
+    : [...ev.dataTransfer.files];
 
-    if (item.kind === 'file') {
-      item.getAsFile().text().then((value) => $("text").value = value);
-    }
-  } else {
-    const item = ev.dataTransfer.files[0];
-    item.text().then((value) => $("text").value = value);
-  }
+  loadFiles(files);
 }
 
 function dragOverHandler(ev) {
   ev.preventDefault();
 }
 
+// Pressing Escape lets the next Tab move the focus out of the editor as usual, so keyboard users
+// are not trapped in it.
+let tabLeaves = false;
+
 function keyDownHandler(ev) {
   if (ev.ctrlKey && ev.key == 's') {
     ev.preventDefault();
 
-    $("text").form.submit();
+    $("text").form.requestSubmit();
+  }
+  else if (ev.key == "Escape") {
+    tabLeaves = true;
+  }
+  else if (ev.key == "Tab" && !tabLeaves && !ev.ctrlKey && !ev.altKey && !ev.metaKey) {
+    ev.preventDefault();
+    indent(ev.target, ev.shiftKey);
+  }
+  else {
+    tabLeaves = false;
   }
 }
 
-function openFile() {
-  let input = document.createElement("input");
-  input.type = "file";
-  input.onchange = ev => {
-    const item = ev.target.files[0];
-    let titleInput = $("title");
-
-    // Iterate through the `langs` <select> and
-    // try to match the value with the extension. If we have one, select it.
-    const extension = item.name.split(".").pop().toLowerCase();
-    const langSelect = $("langs");
-
-    for (i = 0; i < langSelect.length; i++) {
-      if (langSelect[i].value == extension) {
-        langSelect[i].selected = true;
+// Replace the selected text of `text`, keeping the edit on the undo stack where supported.
+function replaceSelection(text, replacement) {
+  if (!document.execCommand("insertText", false, replacement)) {
+    text.setRangeText(replacement, text.selectionStart, text.selectionEnd, "end");
+    text.dispatchEvent(new Event("input"));
+  }
+}
+
+// Indent the cursor position or the selected lines of `text` by one level, or remove one level
+// from the selected lines if `outdent` is set.
+function indent(text, outdent) {
+  const width = Number(text.dataset.tabWidth);
+  const unit = width > 0 ? " ".repeat(width) : "\t";
+  const { selectionStart: start, selectionEnd: end, value } = text;
+
+  if (!outdent && !value.slice(start, end).includes("\n")) {
+    // Align to the next tab stop when indenting with spaces.
+    const column = start - value.lastIndexOf("\n", start - 1) - 1;
+    replaceSelection(text, width > 0 ? " ".repeat(width - column % width) : unit);
+    return;
+  }
+
+  const lineStart = value.lastIndexOf("\n", start - 1) + 1;
+  const lines = value.slice(lineStart, end).split("\n");
+  const changed = lines.map((line) => {
+    if (!outdent) {
+      return line.length > 0 ? unit + line : line;
+    }
+
+    const leading = line.match(width > 0 ? new RegExp(`^ {1,${width}}|^\t`) : /^\t|^ +/);
+    return leading ? line.slice(leading[0].length) : line;
+  }).join("\n");
+
+  text.setSelectionRange(lineStart, end);
+  replaceSelection(text, changed);
+  text.setSelectionRange(lineStart, lineStart + changed.length);
+}
+
+// Grow the editor with its content instead of scrolling inside it.
+function autosize() {
+  const text = $("text");
+  text.style.height = "auto";
+  text.style.height = text.scrollHeight + "px";
+}
+
+// Key of the unsubmitted text and fields kept in the local storage of the browser.
+const DRAFT_KEY = "wastebin-draft";
+
+let saveTimer = null;
+
+function saveDraft() {
+  clearTimeout(saveTimer);
+
+  saveTimer = setTimeout(() => {
+    const text = $("text").value;
+
+    try {
+      if (text.trim()) {
+        localStorage.setItem(DRAFT_KEY, JSON.stringify({
+          text: text,
+          extension: $("langs").value,
+          title: $("title").value,
+          filename: $("filename").value,
+        }));
+      } else {
+        localStorage.removeItem(DRAFT_KEY);
       }
+    } catch (err) {
+      console.error("failed to save draft", err);
     }
+  }, 500);
+}
+
+function discardDraft() {
+  clearTimeout(saveTimer);
+
+  try {
+    localStorage.removeItem(DRAFT_KEY);
+  } catch (err) {
+    console.error("failed to discard draft", err);
+  }
+
+  $("draft-notice").classList.add("hidden");
+}
+
+// Offer to restore the text left behind by navigating away without submitting it, unless the
+// editor was filled by the server, e.g. to duplicate a paste.
+function offerDraft() {
+  let draft = null;
+
+  try {
+    draft = JSON.parse(localStorage.getItem(DRAFT_KEY));
+  } catch (err) {
+    console.error("failed to load draft", err);
+  }
+
+  if (!draft || !draft.text || $("text").value.trim()) {
+    return;
+  }
+
+  const notice = $("draft-notice");
+  notice.classList.remove("hidden");
 
-    // Set title to the filename.
-    titleInput.value = item.name;
+  $("draft-restore").addEventListener("click", () => {
+    $("text").value = draft.text;
+    $("langs").value = draft.extension;
+    $("filename").value = draft.filename;
+    $("title").value = draft.title;
 
-    // Set <textarea> to file content.
-    item.text().then((value) => $("text").value = value);
-  };
+    notice.classList.add("hidden");
+    autosize();
+    $("text").focus();
+  });
+
+  $("draft-discard").addEventListener("click", discardDraft);
+}
+
+function loadFile(file) {
+  const form = $("form");
+
+  if (form.dataset.maxSize && file.size > Number(form.dataset.maxSize)) {
+    alert(form.dataset.tooLarge);
+    return;
+  }
+
+  // Iterate through the `langs` <select> and
+  // try to match the value with the extension. If we have one, select it.
+  const extension = file.name.split(".").pop().toLowerCase();
+  const langSelect = $("langs");
+
+  for (i = 0; i < langSelect.length; i++) {
+    if (langSelect[i].value == extension) {
+      langSelect[i].selected = true;
+    }
+  }
+
+  // Set the file name offered for downloads.
+  $("filename").value = file.name;
+
+  // Set <textarea> to file content.
+  file.text().then((value) => {
+    $("text").value = value;
+    autosize();
+    saveDraft();
+  });
+}
 
+// Pastes hold a single text, so several files are put one after another, each headed by its name
+// like `head` does.
+function loadFiles(files) {
+  if (files.length < 2) {
+    files.forEach(loadFile);
+    return;
+  }
+
+  const form = $("form");
+  const size = files.reduce((size, file) => size + file.size, 0);
+
+  if (form.dataset.maxSize && size > Number(form.dataset.maxSize)) {
+    alert(form.dataset.tooLarge);
+    return;
+  }
+
+  // Select the language only if all files share the extension.
+  const extensions = new Set(files.map((file) => file.name.split(".").pop().toLowerCase()));
+  const langSelect = $("langs");
+
+  for (i = 0; i < langSelect.length; i++) {
+    if (extensions.size == 1 && extensions.has(langSelect[i].value)) {
+      langSelect[i].selected = true;
+    }
+  }
+
+  $("filename").value = "";
+
+  Promise.all(files.map((file) => file.text())).then((values) => {
+    $("text").value = values
+      .map((value, index) => `==> ${files[index].name} <==\n${value}`)
+      .join("\n");
+    autosize();
+    saveDraft();
+  });
+}
+
+function openFile() {
+  let input = document.createElement("input");
+  input.type = "file";
+  input.multiple = true;
+  input.onchange = ev => loadFiles([...ev.target.files]);
   input.click();
 }
 
@@ -76,10 +249,137 @@ function burnCheckboxHandler() {
   $("expiration-list").disabled = $("burn-after-reading").checked;
 }
 
+function togglePreview() {
+  const button = $("preview-toggle");
+  const preview = $("preview");
+  const text = $("text");
+
+  if (!preview.classList.contains("hidden")) {
+    preview.classList.add("hidden");
+    text.classList.remove("hidden");
+    button.textContent = button.dataset.preview;
+    text.focus();
+    return;
+  }
+
+  const form = $("form");
+  const body = new URLSearchParams();
+  body.append("csrf", form.elements["csrf"].value);
+  body.append("text", text.value);
+  body.append("extension", $("langs").value);
+
+  fetch("/preview", {
+    method: "POST",
+    headers: { "X-CSRF-Token": form.elements["csrf"].value },
+    body: body,
+  })
+    .then((response) => response.text())
+    .then((html) => {
+      preview.innerHTML = html;
+      preview.classList.remove("hidden");
+      text.classList.add("hidden");
+      button.textContent = button.dataset.edit;
+    });
+}
+
+function errorMessage(xhr, fallback) {
+  const html = new DOMParser().parseFromString(xhr.responseText, "text/html");
+  const message = html.querySelector(".flex-center p");
+  return message ? message.textContent : fallback;
+}
+
+// Send the form like the browser would, reporting the progress of the upload, and resolve to the
+// URL of the new paste.
+function upload(form) {
+  const body = new URLSearchParams(new FormData(form)).toString();
+
+  if (form.dataset.maxSize && body.length > Number(form.dataset.maxSize)) {
+    return Promise.reject(new Error(form.dataset.tooLarge));
+  }
+
+  const progress = $("progress");
+  progress.value = 0;
+  progress.classList.remove("hidden");
+
+  return new Promise((resolve, reject) => {
+    const xhr = new XMLHttpRequest();
+    xhr.open("POST", form.action);
+    xhr.setRequestHeader("Content-Type", "application/x-www-form-urlencoded");
+    xhr.setRequestHeader("X-CSRF-Token", form.elements["csrf"].value);
+
+    xhr.upload.addEventListener("progress", (ev) => {
+      if (ev.lengthComputable) {
+        progress.value = ev.loaded / ev.total;
+      }
+    });
+
+    xhr.addEventListener("load", () => {
+      if (xhr.status < 400) {
+        resolve(xhr.responseURL);
+      } else {
+        reject(new Error(errorMessage(xhr, form.dataset.failed)));
+      }
+    });
+
+    xhr.addEventListener("error", () => reject(new Error(form.dataset.failed)));
+    xhr.send(body);
+  }).finally(() => progress.classList.add("hidden"));
+}
+
+function submitHandler(ev) {
+  ev.preventDefault();
+
+  const form = ev.target;
+  const button = form.querySelector("button[type=submit]");
+  const label = button.textContent;
+  button.disabled = true;
+
+  let solved = Promise.resolve();
+
+  if (form.dataset.pow && !$("pow").value) {
+    button.textContent = button.dataset.working;
+    solved = solveChallenge(form.dataset.pow).then((solution) => $("pow").value = solution);
+  }
+
+  solved.then(() => upload(form)).then((url) => {
+    discardDraft();
+    window.location.assign(url);
+  }).catch((err) => {
+    // Solutions are only accepted once.
+    if (form.dataset.pow) {
+      $("pow").value = "";
+    }
+
+    if (err.message) {
+      alert(err.message);
+    }
+
+    button.disabled = false;
+    button.textContent = label;
+  });
+}
+
+bindShortcuts({
+  "home": () => window.location.href = "/",
+  "editor": () => $("text").focus(),
+});
+
 $("text").addEventListener("drop", dropHandler);
 $("text").addEventListener("dragover", dragOverHandler);
 $("text").addEventListener("keydown", keyDownHandler);
+$("text").addEventListener("input", autosize);
+$("text").addEventListener("focus", () => tabLeaves = false);
 $("open").addEventListener("click", openFile);
 $("filter").addEventListener("change", filterLangs);
 $("filter").addEventListener("keyup", filterLangs);
 $("burn-after-reading").addEventListener("click", burnCheckboxHandler);
+$("preview-toggle").addEventListener("click", togglePreview);
+$("form").addEventListener("submit", submitHandler);
+$("form").addEventListener("input", saveDraft);
+
+if ($("text").dataset.tabWidth > 0) {
+  $("text").style.tabSize = $("text").dataset.tabWidth;
+}
+
+autosize();
+offerDraft();
@@ -2,70 +2,230 @@ function $(id) {
   return document.getElementById(id);
 }
 
-document.addEventListener('keydown', onKey);
-$("copy-button").addEventListener("click", copy);
+bindShortcuts({
+  "home": () => window.location.href = "/",
+  "here": () => window.location.href = window.location.href.split("?")[0],
+  "raw": () => window.location.href = "/raw" + window.location.pathname,
+  "download": () => window.location.href = "/dl" + window.location.pathname,
+  "qr": () => window.location.href = "/qr" + window.location.pathname,
+  "copy-url": () => $("copy-url-button")?.click(),
+  "copy-raw-url": () => $("copy-raw-url-button")?.click(),
+  "copy-content": () => $("copy-button")?.click(),
+  "wrap": () => $("wrap-toggle")?.click(),
+});
+$("copy-button")?.addEventListener("click", copy);
+$("copy-url-button")?.addEventListener("click", copyUrl);
+$("copy-raw-url-button")?.addEventListener("click", copyUrl);
 
-function copy() {
-  const lines = document.querySelectorAll('td.line');
-  const content = Array.from(lines)
-    .map(line => line.textContent)
-    .join('')
-    .trim();
-
-  navigator.clipboard.writeText(content)
-    .then(() => {
-        let toast = $("toast");
-
-        toast.classList.toggle("hidden");
-        toast.classList.toggle("shown");
-
-        setTimeout(() => {
-          toast.classList.toggle("hidden");
-          toast.classList.toggle("shown");
-        }, 1500);
-    }, function(err) {
-      console.error("failed to copy content", err);
-    });
+if (document.querySelector(".pager")) {
+  $("copy-button")?.addEventListener("pointerenter", fetchContent);
+  $("copy-button")?.addEventListener("focus", fetchContent);
+}
+
+// Format seconds left like `countdown` in expiration.rs, with the two largest units.
+function countdown(secs) {
+  const units = [["d", 86400], ["h", 3600], ["m", 60], ["s", 1]];
+  const start = units.findIndex(([, length]) => secs >= length || length === 1);
+
+  return units.slice(start, start + 2).map(([unit, length]) => {
+    const count = Math.floor(secs / length);
+    secs %= length;
+    return count + unit;
+  }).join(" ");
+}
+
+// Count down to the expiration of the paste, using the offset of the local clock to the server's
+// so wrong local clocks do not show wrong times.
+function startCountdown(expiry) {
+  const expires = Number(expiry.dataset.expires) * 1000;
+  const soon = Number(expiry.dataset.soon);
+  let offset = 0;
+
+  const update = () => {
+    const left = Math.max(0, Math.floor((expires - Date.now() + offset) / 1000));
+
+    expiry.textContent = left > 0
+      ? expiry.dataset.template.replace("{time}", countdown(left))
+      : expiry.dataset.expired;
+    expiry.classList.toggle("expiry-soon", left < soon);
+
+    if (left > 0) {
+      setTimeout(update, 1000);
+    }
+  };
+
+  const started = Date.now();
+
+  fetch("/api/time")
+    .then(response => response.json())
+    .then(time => offset = started + (Date.now() - started) / 2 - time.now)
+    .catch(err => console.error("failed to fetch server time", err))
+    .finally(update);
 }
 
-function onKey(e) {
-  if (e.key == 'n') {
-    window.location.href = "/";
+const expiry = $("expiry");
+
+if (expiry) {
+  startCountdown(expiry);
+}
+
+const langSelect = $("lang-select");
+
+if (langSelect) {
+  langSelect.addEventListener("change", () => $("lang-form").submit());
+}
+
+let anchorLine = null;
+
+document.querySelectorAll("td.line-number > a").forEach(link => {
+  link.addEventListener("click", selectLine);
+});
+
+window.addEventListener("hashchange", () => highlightRange(true));
+highlightRange(true);
+
+// Parse a `#L10` or `#L10-L25` fragment into the first and last line number.
+function parseRange(hash) {
+  const match = hash.match(/^#L(\d+)(?:-L(\d+))?$/);
+
+  if (!match) {
+    return null;
   }
-  else if (e.key == 'r') {
-    window.location.href = "/raw" + window.location.pathname;
+
+  const start = parseInt(match[1]);
+  const end = match[2] ? parseInt(match[2]) : start;
+
+  return [Math.min(start, end), Math.max(start, end)];
+}
+
+function highlightRange(scroll) {
+  document.querySelectorAll("tr.line-selected").forEach(row => {
+    row.classList.remove("line-selected");
+  });
+
+  const range = parseRange(window.location.hash);
+
+  if (!range) {
+    return;
+  }
+
+  const pager = document.querySelector(".pager");
+
+  // Lines of paged pastes might be on another page.
+  if (pager && !$("L" + range[0])) {
+    const page = Math.ceil(range[0] / parseInt(pager.dataset.lines));
+    const params = new URLSearchParams(window.location.search);
+    params.set("page", page);
+    window.location.replace("?" + params + window.location.hash);
+    return;
   }
-  else if (e.key == 'y') {
-    navigator.clipboard.writeText(window.location.href);
+
+  for (let line = range[0]; line <= range[1]; line++) {
+    const cell = $("L" + line);
+
+    if (cell) {
+      cell.parentElement.classList.add("line-selected");
+    }
   }
-  else if (e.key == 'd') {
-    window.location.href = "/dl" + window.location.pathname;
+
+  const first = $("L" + range[0]);
+
+  if (scroll && first) {
+    first.scrollIntoView({ block: "center" });
   }
-  else if (e.key == 'q') {
-    window.location.href = "/qr" + window.location.pathname;
+}
+
+// Select a single line or, with shift pressed, the range from the previously selected line.
+function selectLine(e) {
+  e.preventDefault();
+
+  const line = parseInt(e.currentTarget.parentElement.id.slice(1));
+  let hash = "#L" + line;
+
+  if (e.shiftKey && anchorLine !== null && anchorLine != line) {
+    hash = "#L" + Math.min(anchorLine, line) + "-L" + Math.max(anchorLine, line);
   }
-  else if (e.key == 'p') {
-    window.location.href = window.location.href.split("?")[0];
+  else {
+    anchorLine = line;
   }
-  else if (e.key == 'c') {
-    copy();
+
+  history.replaceState(null, "", hash);
+  highlightRange(false);
+}
+
+// Write `text` to the clipboard. Without the clipboard API, which browsers only offer on pages
+// served over HTTPS or from localhost, the text is selected in a hidden text area and copied
+// from there, which only works while handling the click.
+function writeClipboard(text) {
+  if (navigator.clipboard && window.isSecureContext) {
+    return navigator.clipboard.writeText(text);
   }
-  else if (e.key == '?') {
-    var overlay = document.getElementById("overlay");
-
-    overlay.style.display = overlay.style.display != "block" ? "block" : "none";
-    overlay.onclick = function() {
-      if (overlay.style.display == "block") {
-        overlay.style.display = "none";
-      }
-    };
+
+  const area = document.createElement("textarea");
+  area.value = text;
+  area.readOnly = true;
+  area.style.position = "fixed";
+  area.style.opacity = "0";
+  document.body.appendChild(area);
+  area.select();
+
+  const copied = document.execCommand("copy");
+  area.remove();
+
+  return copied ? Promise.resolve() : Promise.reject(new Error("copy command failed"));
+}
+
+function showToast(message) {
+  const toast = $("toast");
+  toast.textContent = message;
+  toast.classList.remove("hidden");
+  toast.classList.add("shown");
+
+  setTimeout(() => {
+    toast.classList.remove("shown");
+    toast.classList.add("hidden");
+  }, 1500);
+}
+
+function copyText(text, message) {
+  writeClipboard(text).then(() => showToast(message), (err) => {
+    console.error("failed to copy", err);
+    showToast($("toast").dataset.failed);
+  });
+}
+
+// Full text of paged pastes, which only show part of it, fetched once it is likely needed so the
+// copy fallback has it at hand when the button is clicked.
+let content = null;
+let contentText = null;
+
+function fetchContent() {
+  if (!content) {
+    content = fetch(document.querySelector(".pager").dataset.raw)
+      .then(response => response.text())
+      .then(text => contentText = text);
   }
 
-  if (e.keyCode == 27) {
-    var overlay = document.getElementById("overlay");
+  return content;
+}
 
-    if (overlay.style.display == "block") {
-      overlay.style.display = "none";
-    }
+function copy() {
+  const pager = document.querySelector(".pager");
+  const message = $("copy-button").dataset.copied;
+
+  if (!pager) {
+    const lines = document.querySelectorAll('td.line');
+    copyText(Array.from(lines).map(line => line.textContent).join('').trim(), message);
+  }
+  else if (contentText !== null) {
+    copyText(contentText, message);
   }
+  else {
+    fetchContent().then(text => copyText(text, message));
+  }
+}
+
+function copyUrl(e) {
+  const button = e.currentTarget;
+  copyText(button.dataset.url || window.location.href, button.dataset.copied);
 }
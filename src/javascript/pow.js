@@ -0,0 +1,85 @@
+// Round constants and initial hash values of SHA-256, the fractional parts of the cube and
+// square roots of the first primes.
+const primes = [];
+
+for (let n = 2; primes.length < 64; n++) {
+  if (primes.every((p) => n % p != 0)) {
+    primes.push(n);
+  }
+}
+
+const fraction = (x) => ((x - Math.floor(x)) * 0x100000000) | 0;
+const K = primes.map((p) => fraction(Math.cbrt(p)));
+const H = primes.slice(0, 8).map((p) => fraction(Math.sqrt(p)));
+
+// SHA-256 of the ASCII string `message` as eight 32 bit words.
+function sha256(message) {
+  const length = message.length;
+  const words = new Array(((length + 8) >> 6) * 16 + 16).fill(0);
+
+  for (let i = 0; i < length; i++) {
+    words[i >> 2] |= message.charCodeAt(i) << (24 - (i % 4) * 8);
+  }
+
+  words[length >> 2] |= 0x80 << (24 - (length % 4) * 8);
+  words[words.length - 1] = length * 8;
+
+  const hash = H.slice();
+  const w = new Array(64);
+  const rotate = (x, n) => (x >>> n) | (x << (32 - n));
+
+  for (let block = 0; block < words.length; block += 16) {
+    let [a, b, c, d, e, f, g, h] = hash;
+
+    for (let i = 0; i < 64; i++) {
+      if (i < 16) {
+        w[i] = words[block + i];
+      } else {
+        const s0 = rotate(w[i - 15], 7) ^ rotate(w[i - 15], 18) ^ (w[i - 15] >>> 3);
+        const s1 = rotate(w[i - 2], 17) ^ rotate(w[i - 2], 19) ^ (w[i - 2] >>> 10);
+        w[i] = (w[i - 16] + s0 + w[i - 7] + s1) | 0;
+      }
+
+      const t1 = (h + (rotate(e, 6) ^ rotate(e, 11) ^ rotate(e, 25)) + ((e & f) ^ (~e & g)) + K[i] + w[i]) | 0;
+      const t2 = ((rotate(a, 2) ^ rotate(a, 13) ^ rotate(a, 22)) + ((a & b) ^ (a & c) ^ (b & c))) | 0;
+
+      [h, g, f, e, d, c, b, a] = [g, f, e, (d + t1) | 0, c, b, a, (t1 + t2) | 0];
+    }
+
+    [a, b, c, d, e, f, g, h].forEach((x, i) => hash[i] = (hash[i] + x) | 0);
+  }
+
+  return hash;
+}
+
+function hasLeadingZeros(hash, bits) {
+  let zeros = 0;
+
+  for (const word of hash) {
+    const count = Math.clz32(word);
+    zeros += count;
+
+    if (count < 32) {
+      break;
+    }
+  }
+
+  return zeros >= bits;
+}
+
+// Fetch a challenge and search for a nonce in batches, so the page stays responsive.
+async function solveChallenge(url) {
+  const { challenge, difficulty } = await (await fetch(url)).json();
+
+  for (let nonce = 0; ; ) {
+    for (const end = nonce + 10000; nonce < end; nonce++) {
+      const solution = `${challenge}:${nonce}`;
+
+      if (hasLeadingZeros(sha256(solution), difficulty)) {
+        return solution;
+      }
+    }
+
+    await new Promise((resolve) => setTimeout(resolve));
+  }
+}
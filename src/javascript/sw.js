@@ -0,0 +1,7 @@
+// Browsers only offer to install sites with a service worker as web apps, which can then receive
+// text and files shared from other apps. Requests go to the network as usual, nothing is cached.
+self.addEventListener("install", () => self.skipWaiting());
+
+self.addEventListener("activate", (event) => event.waitUntil(self.clients.claim()));
+
+self.addEventListener("fetch", () => {});
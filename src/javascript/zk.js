@@ -0,0 +1,136 @@
+function $(id) {
+  return document.getElementById(id);
+}
+
+// Pastes are encrypted with AES-256-GCM. The stored text is the base64 encoded 12 byte IV
+// followed by the ciphertext, the key is put base64url encoded into the URL fragment.
+function toBase64(bytes) {
+  let binary = "";
+
+  for (const byte of bytes) {
+    binary += String.fromCharCode(byte);
+  }
+
+  return btoa(binary);
+}
+
+function fromBase64(text) {
+  return Uint8Array.from(atob(text), (c) => c.charCodeAt(0));
+}
+
+async function encrypt(text) {
+  const key = await crypto.subtle.generateKey({ name: "AES-GCM", length: 256 }, true, ["encrypt"]);
+  const iv = crypto.getRandomValues(new Uint8Array(12));
+  const ciphertext = await crypto.subtle.encrypt({ name: "AES-GCM", iv }, key, new TextEncoder().encode(text));
+  const raw = new Uint8Array(await crypto.subtle.exportKey("raw", key));
+
+  const payload = new Uint8Array(iv.length + ciphertext.byteLength);
+  payload.set(iv);
+  payload.set(new Uint8Array(ciphertext), iv.length);
+
+  return {
+    payload: toBase64(payload),
+    key: toBase64(raw).replace(/\+/g, "-").replace(/\//g, "_").replace(/=+$/, ""),
+  };
+}
+
+async function decrypt(payload, encodedKey) {
+  const raw = fromBase64(encodedKey.replace(/-/g, "+").replace(/_/g, "/"));
+  const key = await crypto.subtle.importKey("raw", raw, "AES-GCM", false, ["decrypt"]);
+  const data = fromBase64(payload.trim());
+  const plaintext = await crypto.subtle.decrypt({ name: "AES-GCM", iv: data.slice(0, 12) }, key, data.slice(12));
+
+  return new TextDecoder().decode(plaintext);
+}
+
+// Show the message of an error page returned instead of the redirect.
+async function showError(res) {
+  const html = new DOMParser().parseFromString(await res.text(), "text/html");
+  const message = html.querySelector(".flex-center p");
+  alert(message ? message.textContent : `Request failed with status ${res.status}`);
+}
+
+async function submitHandler(ev) {
+  ev.preventDefault();
+
+  const form = ev.target;
+  const button = form.querySelector("button[type=submit]");
+  const label = button.textContent;
+  button.disabled = true;
+
+  try {
+    if (form.dataset.pow && !$("pow").value) {
+      button.textContent = button.dataset.working;
+      $("pow").value = await solveChallenge(form.dataset.pow);
+    }
+
+    button.textContent = button.dataset.encrypting;
+    const { payload, key } = await encrypt($("text").value);
+    const body = new URLSearchParams(new FormData(form));
+    body.set("text", payload);
+
+    // The redirect leads to the viewer, which does not fetch and thus burn the paste.
+    const res = await fetch(form.action, { method: "POST", body });
+
+    if (!res.ok) {
+      await showError(res);
+      return;
+    }
+
+    const url = new URL(res.url);
+    url.hash = key;
+
+    if ($("burn-after-reading").checked) {
+      $("link").querySelector("a").href = url;
+      $("link").classList.remove("hidden");
+      form.classList.add("hidden");
+    } else {
+      window.location.assign(url);
+    }
+  } finally {
+    button.disabled = false;
+    button.textContent = label;
+  }
+}
+
+function status(text) {
+  $("status-text").textContent = text;
+}
+
+async function view(plaintext) {
+  const messages = $("status").dataset;
+  const key = window.location.hash.slice(1);
+
+  if (!key) {
+    status(messages.noKey);
+    return;
+  }
+
+  const res = await fetch(plaintext.dataset.raw);
+
+  if (!res.ok) {
+    status(res.status == 404 ? messages.notFound : `${messages.fetchFailed} (${res.status}).`);
+    return;
+  }
+
+  try {
+    plaintext.textContent = await decrypt(await res.text(), key);
+  } catch {
+    status(messages.decryptFailed);
+    return;
+  }
+
+  $("status").classList.add("hidden");
+  plaintext.classList.remove("hidden");
+}
+
+if ($("form")) {
+  $("form").addEventListener("submit", submitHandler);
+  $("burn-after-reading").addEventListener("click", () => {
+    $("expiration-list").disabled = $("burn-after-reading").checked;
+  });
+}
+
+if ($("plaintext")) {
+  view($("plaintext"));
+}
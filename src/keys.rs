@@ -0,0 +1,117 @@
+use axum::extract::{Request, State};
+use axum::http::{HeaderMap, HeaderValue, header};
+use axum::middleware::Next;
+use axum::response::Response;
+use axum_extra::extract::cookie::{Cookie, Key};
+use cookie::CookieJar;
+use std::sync::Arc;
+
+/// State of the [`resign`] middleware.
+#[derive(Clone)]
+pub(crate) struct Keys {
+    /// Key signing new cookies.
+    pub key: Key,
+    /// Keys of earlier rotations still accepted for verifying cookies.
+    pub previous: Arc<[Key]>,
+}
+
+/// Cookie header with the cookies of `headers` signed by one of the `previous` keys signed with
+/// the current key instead, or `None` if there are no such cookies.
+fn resign_cookies(keys: &Keys, headers: &HeaderMap) -> Option<HeaderValue> {
+    let jar = CookieJar::new();
+    let mut resigned = false;
+
+    let cookies = headers
+        .get_all(header::COOKIE)
+        .into_iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| Cookie::parse_encoded(cookie.trim().to_owned()).ok())
+        .map(|cookie| {
+            if jar.signed(&keys.key).verify(cookie.clone()).is_some() {
+                return cookie;
+            }
+
+            let Some(verified) = keys
+                .previous
+                .iter()
+                .find_map(|key| jar.signed(key).verify(cookie.clone()))
+            else {
+                return cookie;
+            };
+
+            let mut signed = CookieJar::new();
+            signed.signed_mut(&keys.key).add(verified);
+            resigned = true;
+
+            signed
+                .get(cookie.name())
+                .cloned()
+                .expect("cookie was just added")
+        })
+        .map(|cookie| cookie.stripped().encoded().to_string())
+        .collect::<Vec<_>>();
+
+    if !resigned {
+        return None;
+    }
+
+    HeaderValue::from_str(&cookies.join("; ")).ok()
+}
+
+/// Let cookies signed with a previous key pass as if they were signed with the current one, so
+/// that rotating the signing key keeps sessions and paste ownership intact.
+pub(crate) async fn resign(State(keys): State<Keys>, mut request: Request, next: Next) -> Response {
+    if !keys.previous.is_empty() {
+        if let Some(cookies) = resign_cookies(&keys, request.headers()) {
+            request.headers_mut().insert(header::COOKIE, cookies);
+        }
+    }
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed(key: &Key, name: &'static str, value: &'static str) -> String {
+        let mut jar = CookieJar::new();
+        jar.signed_mut(key).add(Cookie::new(name, value));
+        jar.get(name).unwrap().stripped().encoded().to_string()
+    }
+
+    #[test]
+    fn resigns_previous_cookies() {
+        let (current, previous, unknown) = (Key::generate(), Key::generate(), Key::generate());
+        let keys = Keys {
+            key: current.clone(),
+            previous: Arc::new([previous.clone()]),
+        };
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_str(&signed(&current, "uid", "1")).unwrap(),
+        );
+        assert!(resign_cookies(&keys, &headers).is_none());
+
+        let cookies = format!(
+            "{}; {}; theme=dark",
+            signed(&previous, "uid", "1"),
+            signed(&unknown, "session", "abc")
+        );
+        headers.insert(header::COOKIE, HeaderValue::from_str(&cookies).unwrap());
+        headers.insert(
+            header::COOKIE,
+            resign_cookies(&keys, &headers).expect("re-signed cookies"),
+        );
+
+        let jar = axum_extra::extract::cookie::SignedCookieJar::from_headers(&headers, current);
+        assert_eq!(jar.get("uid").unwrap().value(), "1");
+        assert!(jar.get("session").is_none());
+
+        let jar = axum_extra::extract::CookieJar::from_headers(&headers);
+        assert_eq!(jar.get("theme").unwrap().value(), "dark");
+    }
+}
@@ -0,0 +1,145 @@
+#[cfg(feature = "ldap")]
+use crate::accounts::Roles;
+#[cfg(feature = "ldap")]
+use crate::errors::Error;
+#[cfg(feature = "ldap")]
+use std::time::Duration;
+
+/// Placeholder in the bind DN template replaced by the escaped user name.
+pub const USER_PLACEHOLDER: &str = "{user}";
+
+/// Time to connect to the directory and to wait for each response, so that an unreachable or
+/// stuck directory fails logins instead of holding them open.
+#[cfg(feature = "ldap")]
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// LDAP directory that accounts authenticate against by binding as the user.
+#[cfg_attr(
+    not(feature = "ldap"),
+    expect(dead_code, reason = "only used with the `ldap` feature")
+)]
+pub(crate) struct Directory {
+    url: String,
+    bind_dn: String,
+    create_filter: Option<String>,
+    admin_filter: Option<String>,
+}
+
+impl Directory {
+    /// Create a directory at `url` binding with the `bind_dn` template. Users must match the
+    /// optional `create_filter` to create pastes and the optional `admin_filter` to administer
+    /// them.
+    pub fn new(
+        url: String,
+        bind_dn: String,
+        create_filter: Option<String>,
+        admin_filter: Option<String>,
+    ) -> Self {
+        Self {
+            url,
+            bind_dn,
+            create_filter,
+            admin_filter,
+        }
+    }
+
+    /// If only users matching the create filter may create pastes.
+    pub fn restricts_creation(&self) -> bool {
+        self.create_filter.is_some()
+    }
+}
+
+#[cfg(feature = "ldap")]
+impl Directory {
+    /// URL of the directory.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// DN to bind as for `user`.
+    fn dn(&self, user: &str) -> String {
+        self.bind_dn
+            .replace(USER_PLACEHOLDER, &ldap3::dn_escape(user))
+    }
+
+    /// Bind as `user` with `password` and return the bound DN and the roles granted by the
+    /// filters, or `None` if the credentials are wrong.
+    pub async fn authenticate(
+        &self,
+        user: &str,
+        password: &str,
+    ) -> Result<Option<(String, Roles)>, Error> {
+        // An empty password would be an unauthenticated bind that always succeeds.
+        if user.is_empty() || password.is_empty() {
+            return Ok(None);
+        }
+
+        let settings = ldap3::LdapConnSettings::new().set_conn_timeout(TIMEOUT);
+        let (conn, mut ldap) = ldap3::LdapConnAsync::with_settings(settings, &self.url).await?;
+        ldap3::drive!(conn);
+
+        let dn = self.dn(user);
+        let result = ldap
+            .with_timeout(TIMEOUT)
+            .simple_bind(&dn, password)
+            .await?;
+
+        // invalidCredentials
+        if result.rc == 49 {
+            return Ok(None);
+        }
+
+        result.success()?;
+
+        let roles = Roles {
+            create: match &self.create_filter {
+                Some(filter) => matches(&mut ldap, &dn, filter).await?,
+                None => true,
+            },
+            admin: match &self.admin_filter {
+                Some(filter) => matches(&mut ldap, &dn, filter).await?,
+                None => false,
+            },
+        };
+
+        ldap.unbind().await?;
+
+        Ok(Some((dn, roles)))
+    }
+}
+
+/// Check if the entry at `dn` matches `filter`.
+#[cfg(feature = "ldap")]
+async fn matches(ldap: &mut ldap3::Ldap, dn: &str, filter: &str) -> Result<bool, Error> {
+    let (entries, _) = ldap
+        .with_timeout(TIMEOUT)
+        .search(dn, ldap3::Scope::Base, filter, vec!["1.1"])
+        .await?
+        .success()?;
+
+    Ok(!entries.is_empty())
+}
+
+#[cfg(all(test, feature = "ldap"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_bind_dn() {
+        let directory = Directory::new(
+            String::from("ldap://localhost"),
+            String::from("uid={user},ou=people,dc=example,dc=com"),
+            None,
+            None,
+        );
+
+        assert_eq!(
+            directory.dn("alice"),
+            "uid=alice,ou=people,dc=example,dc=com"
+        );
+        assert_eq!(
+            directory.dn("alice,ou=admins"),
+            "uid=alice\\2cou\\3dadmins,ou=people,dc=example,dc=com"
+        );
+    }
+}
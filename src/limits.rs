@@ -0,0 +1,408 @@
+use crate::errors::Error;
+use axum::http::{HeaderMap, HeaderName};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Seconds of a day, counting starts anew at midnight UTC.
+const DAY: u64 = 24 * 60 * 60;
+
+/// Time from the first login attempt of an address in which its attempts are counted together.
+const LOGIN_WINDOW: Duration = Duration::from_secs(60);
+
+/// Login attempts a single address may make per [`LOGIN_WINDOW`], each costing a password hash.
+pub(crate) const MAX_LOGINS: u32 = 20;
+
+/// Login attempts a single account may receive per [`LOGIN_WINDOW`] from all addresses together.
+pub(crate) const MAX_ACCOUNT_LOGINS: u32 = 10;
+
+/// Time from the first registration of an address in which its registrations are counted
+/// together.
+const REGISTRATION_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Registrations a single address may make per [`REGISTRATION_WINDOW`].
+pub(crate) const MAX_REGISTRATIONS: u32 = 5;
+
+/// Address range given as `address/prefix` or a single address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Network {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+/// Limits on what a single anonymous address may create per day, each unlimited if unset.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct DailyLimit {
+    /// Maximum number of pastes.
+    pub pastes: Option<usize>,
+    /// Maximum number of bytes of all pastes.
+    pub bytes: Option<usize>,
+    /// Networks exempt from the limits.
+    pub allow: Vec<Network>,
+}
+
+/// Pastes and bytes created by a single address today.
+#[derive(Debug, Default, Clone, Copy)]
+struct Usage {
+    pastes: usize,
+    bytes: usize,
+}
+
+/// Start of the current window and attempts in it by key.
+type Attempts<K> = Arc<Mutex<HashMap<K, (Instant, u32)>>>;
+
+/// Counts what anonymous addresses created on the current day, how often addresses tried to log
+/// in or register and how often accounts were tried. Counts are kept in memory and restart at
+/// zero with the server.
+#[derive(Clone, Default)]
+pub(crate) struct Limiter {
+    limit: Arc<DailyLimit>,
+    /// Header carrying the client address set by a reverse proxy.
+    header: Option<HeaderName>,
+    usage: Arc<Mutex<(u64, HashMap<IpAddr, Usage>)>>,
+    /// Login attempts by address, counted regardless of the daily limit.
+    logins: Attempts<IpAddr>,
+    /// Login attempts by account name.
+    accounts: Attempts<String>,
+    /// Registrations by address.
+    registrations: Attempts<IpAddr>,
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = s.split_once('/').map_or((s, None), |(a, p)| (a, Some(p)));
+        let addr = addr
+            .trim()
+            .parse::<IpAddr>()
+            .map_err(|err| format!("{s}: {err}"))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max)
+                .ok_or_else(|| format!("{s}: invalid prefix length"))?,
+            None => max,
+        };
+
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl Network {
+    /// Return `true` if `ip` lies within the network.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V6(ip) => ip.to_ipv4_mapped().map_or(IpAddr::V6(ip), IpAddr::V4),
+            IpAddr::V4(_) => ip,
+        };
+
+        let (addr, ip, bits) = match (self.addr, ip) {
+            (IpAddr::V4(addr), IpAddr::V4(ip)) => {
+                (u128::from(addr.to_bits()), u128::from(ip.to_bits()), 32)
+            }
+            (IpAddr::V6(addr), IpAddr::V6(ip)) => (addr.to_bits(), ip.to_bits(), 128),
+            _ => return false,
+        };
+
+        let shift = bits - u32::from(self.prefix);
+        addr.checked_shr(shift).unwrap_or(0) == ip.checked_shr(shift).unwrap_or(0)
+    }
+}
+
+impl Limiter {
+    /// Create a limiter enforcing `limit` on the peer address or, if given, the address in
+    /// `header`.
+    pub fn new(limit: DailyLimit, header: Option<HeaderName>) -> Self {
+        Self {
+            limit: Arc::new(limit),
+            header,
+            usage: Arc::default(),
+            logins: Arc::default(),
+            accounts: Arc::default(),
+            registrations: Arc::default(),
+        }
+    }
+
+    /// Return `true` if any limit is set.
+    pub fn is_enabled(&self) -> bool {
+        self.limit.pastes.is_some() || self.limit.bytes.is_some()
+    }
+
+    /// Client address taken from the configured header, falling back to the `peer` address if
+    /// the header is missing or invalid. Of a list of addresses, the last one was added by the
+    /// closest proxy and is used.
+    pub fn client(&self, headers: &HeaderMap, peer: IpAddr) -> IpAddr {
+        self.header
+            .as_ref()
+            .and_then(|header| headers.get(header))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|addr| addr.trim().parse().ok())
+            .unwrap_or(peer)
+    }
+
+    /// Check if `ip` may create another paste of `size` bytes today and count it if so.
+    pub fn check(&self, ip: IpAddr, size: usize) -> Result<(), Error> {
+        if !self.is_enabled() || self.limit.allow.iter().any(|net| net.contains(ip)) {
+            return Ok(());
+        }
+
+        let today = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() / DAY);
+
+        let mut guard = self.usage.lock();
+        let (day, usage) = &mut *guard;
+
+        if *day != today {
+            *day = today;
+            usage.clear();
+        }
+
+        let usage = usage.entry(ip).or_default();
+
+        if let Some(max) = self.limit.pastes.filter(|max| usage.pastes >= *max) {
+            return Err(Error::DailyPasteLimit(max));
+        }
+
+        if let Some(max) = self.limit.bytes.filter(|max| usage.bytes + size > *max) {
+            return Err(Error::DailyByteLimit(max));
+        }
+
+        usage.pastes += 1;
+        usage.bytes += size;
+
+        Ok(())
+    }
+
+    /// Check if `ip` may attempt another login, to the account `name` if known, and count it if
+    /// so. The account is limited across all addresses, so that spreading attempts over many
+    /// addresses does not help guessing its password.
+    pub fn check_login(&self, ip: IpAddr, name: Option<&str>) -> Result<(), Error> {
+        if self.limit.allow.iter().any(|net| net.contains(ip)) {
+            return Ok(());
+        }
+
+        let mut logins = self.logins.lock();
+        let mut accounts = self.accounts.lock();
+        let address = attempts(&mut logins, bucket(ip), LOGIN_WINDOW);
+        let account =
+            name.map(|name| attempts(&mut accounts, name.to_ascii_lowercase(), LOGIN_WINDOW));
+
+        if *address >= MAX_LOGINS
+            || account
+                .as_ref()
+                .is_some_and(|account| **account >= MAX_ACCOUNT_LOGINS)
+        {
+            return Err(Error::LoginLimit);
+        }
+
+        *address += 1;
+
+        if let Some(account) = account {
+            *account += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Check if `ip` may register another account and count it if so.
+    pub fn check_registration(&self, ip: IpAddr) -> Result<(), Error> {
+        if self.limit.allow.iter().any(|net| net.contains(ip)) {
+            return Ok(());
+        }
+
+        let mut registrations = self.registrations.lock();
+        let count = attempts(&mut registrations, bucket(ip), REGISTRATION_WINDOW);
+
+        if *count >= MAX_REGISTRATIONS {
+            return Err(Error::RegistrationLimit);
+        }
+
+        *count += 1;
+
+        Ok(())
+    }
+}
+
+/// Attempts of `key` in its current window, dropping all windows older than `window`.
+fn attempts<K: std::hash::Hash + Eq>(
+    map: &mut HashMap<K, (Instant, u32)>,
+    key: K,
+    window: Duration,
+) -> &mut u32 {
+    map.retain(|_, (start, _)| start.elapsed() < window);
+    &mut map.entry(key).or_insert_with(|| (Instant::now(), 0)).1
+}
+
+/// Address under which attempts of `ip` are counted. IPv6 clients usually get a whole /64
+/// network, so its addresses are counted together.
+fn bucket(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(ip) => ip.to_ipv4_mapped().map_or_else(
+            || IpAddr::V6((ip.to_bits() & !u128::from(u64::MAX)).into()),
+            IpAddr::V4,
+        ),
+        IpAddr::V4(_) => ip,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn networks() {
+        let net = "10.0.0.0/8".parse::<Network>().unwrap();
+        assert!(net.contains("10.1.2.3".parse().unwrap()));
+        assert!(net.contains("::ffff:10.1.2.3".parse().unwrap()));
+        assert!(!net.contains("11.0.0.1".parse().unwrap()));
+        assert!(!net.contains("::1".parse().unwrap()));
+
+        let net = "2001:db8::/32".parse::<Network>().unwrap();
+        assert!(net.contains("2001:db8::1".parse().unwrap()));
+        assert!(!net.contains("2001:db9::1".parse().unwrap()));
+
+        let net = "127.0.0.1".parse::<Network>().unwrap();
+        assert!(net.contains("127.0.0.1".parse().unwrap()));
+        assert!(!net.contains("127.0.0.2".parse().unwrap()));
+
+        assert!(
+            "0.0.0.0/0"
+                .parse::<Network>()
+                .unwrap()
+                .contains("1.2.3.4".parse().unwrap())
+        );
+        assert!("10.0.0.0/33".parse::<Network>().is_err());
+        assert!("localhost".parse::<Network>().is_err());
+    }
+
+    #[test]
+    fn daily_limit() {
+        let limit = DailyLimit {
+            pastes: Some(2),
+            bytes: Some(10),
+            allow: vec!["192.168.0.0/16".parse().unwrap()],
+        };
+        let limiter = Limiter::new(limit, None);
+        let ip = "10.0.0.1".parse().unwrap();
+
+        assert!(limiter.check(ip, 4).is_ok());
+        assert!(matches!(
+            limiter.check(ip, 7),
+            Err(Error::DailyByteLimit(10))
+        ));
+        assert!(limiter.check(ip, 6).is_ok());
+        assert!(matches!(
+            limiter.check(ip, 0),
+            Err(Error::DailyPasteLimit(2))
+        ));
+        assert!(limiter.check("10.0.0.2".parse().unwrap(), 10).is_ok());
+
+        for _ in 0..3 {
+            assert!(limiter.check("192.168.1.1".parse().unwrap(), 10).is_ok());
+        }
+    }
+
+    #[test]
+    fn login_limit() {
+        let limiter = Limiter::default();
+        let ip = "10.0.0.1".parse().unwrap();
+
+        for _ in 0..MAX_LOGINS {
+            assert!(limiter.check_login(ip, None).is_ok());
+        }
+
+        assert!(matches!(
+            limiter.check_login(ip, None),
+            Err(Error::LoginLimit)
+        ));
+        assert!(
+            limiter
+                .check_login("10.0.0.2".parse().unwrap(), None)
+                .is_ok()
+        );
+
+        let ip = "2001:db8::1".parse().unwrap();
+
+        for _ in 0..MAX_LOGINS {
+            assert!(limiter.check_login(ip, None).is_ok());
+        }
+
+        let neighbour = "2001:db8::ffff:1".parse().unwrap();
+        assert!(matches!(
+            limiter.check_login(neighbour, None),
+            Err(Error::LoginLimit)
+        ));
+        assert!(
+            limiter
+                .check_login("2001:db8:0:1::1".parse().unwrap(), None)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn account_login_limit() {
+        let limiter = Limiter::default();
+
+        for n in 0..MAX_ACCOUNT_LOGINS {
+            let ip = format!("10.0.0.{n}").parse().unwrap();
+            assert!(limiter.check_login(ip, Some("alice")).is_ok());
+        }
+
+        let ip = "10.0.1.1".parse().unwrap();
+        assert!(matches!(
+            limiter.check_login(ip, Some("Alice")),
+            Err(Error::LoginLimit)
+        ));
+        assert!(limiter.check_login(ip, Some("bob")).is_ok());
+    }
+
+    #[test]
+    fn registration_limit() {
+        let limiter = Limiter::default();
+        let ip = "10.0.0.1".parse().unwrap();
+
+        for _ in 0..MAX_REGISTRATIONS {
+            assert!(limiter.check_registration(ip).is_ok());
+        }
+
+        assert!(matches!(
+            limiter.check_registration(ip),
+            Err(Error::RegistrationLimit)
+        ));
+        assert!(
+            limiter
+                .check_registration("10.0.0.2".parse().unwrap())
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn client_address() {
+        let peer = "127.0.0.1".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "1.2.3.4, 5.6.7.8".parse().unwrap());
+
+        let limiter = Limiter::default();
+        assert_eq!(limiter.client(&headers, peer), peer);
+
+        let limiter = Limiter::new(
+            DailyLimit::default(),
+            Some(HeaderName::from_static("x-forwarded-for")),
+        );
+        assert_eq!(
+            limiter.client(&headers, peer),
+            "5.6.7.8".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(limiter.client(&HeaderMap::new(), peer), peer);
+    }
+}
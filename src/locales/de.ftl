@@ -0,0 +1,271 @@
+locale-name = Deutsch
+
+## Navigation
+
+nav-home = Startseite
+nav-pastes = meine Pastes
+nav-about = Über
+nav-theme = Farbschema
+nav-apply-theme = Farbschema anwenden
+nav-locale = Sprache
+nav-apply-locale = Sprache anwenden
+nav-follow-system = Systemeinstellung folgen
+nav-dark = zu dunkel wechseln
+nav-light = zu hell wechseln
+nav-encrypt = im Browser verschlüsseln
+nav-upload = hochladen
+nav-edit = Paste bearbeiten
+nav-duplicate = im Editor duplizieren
+nav-delete = Paste löschen
+nav-download = Datei herunterladen
+nav-raw = Rohdatei anzeigen
+nav-raw-link = Link zur Rohdatei
+nav-pdf = als PDF exportieren
+nav-print = Druckansicht
+nav-copy = in die Zwischenablage kopieren
+nav-copy-url = URL kopieren
+nav-copy-raw-url = Roh-URL kopieren
+nav-source = Quelltextansicht
+nav-pretty = JSON formatieren
+nav-tree = JSON-Baumansicht
+nav-highlight-as = hervorheben als
+nav-apply-language = Sprache anwenden
+nav-save-language = Sprache speichern
+nav-qr = QR-Code
+nav-wrap = Zeilenumbruch umschalten
+nav-line-numbers = Zeilennummern umschalten
+nav-paste-content = Inhalt des Pastes
+nav-admin = Verwaltung
+nav-settings = Einstellungen
+nav-log-out = abmelden
+nav-actions = Seitenaktionen
+nav-skip = zum Inhalt springen
+nav-high-contrast = hohen Kontrast umschalten
+
+## Actions
+
+apply = anwenden
+edit = bearbeiten
+delete = löschen
+revoke = widerrufen
+disable = deaktivieren
+release = freigeben
+pin = anheften
+unpin = lösen
+previous = zurück
+next = weiter
+copied = Inhalt kopiert
+copied-url = URL kopiert
+copied-raw-url = Roh-URL kopiert
+copy-failed = Kopieren in die Zwischenablage fehlgeschlagen
+working = Wird berechnet ...
+go-back = zurück
+
+## Creating pastes
+
+private-instance = Diese Instanz ist privat, { $link }, um Pastes zu erstellen.
+    .link = melde dich an
+honeypot = Dieses Feld leer lassen
+text-placeholder = <Text einfügen oder Datei hierher ziehen>
+filter-placeholder = Filtern ...
+burn-after-reading = 🔥 nach dem Lesen
+noindex = 🙈 vor Suche verbergen
+secret-link = 🔑 geheimer Link
+accept-terms = 📜 Ich akzeptiere die { $link }
+    .link = Nutzungsbedingungen
+password-placeholder = Passwort ...
+title-placeholder = Titel ...
+filename-placeholder = Dateiname ...
+new-paste = Neues Paste
+paste-options = Optionen des Pastes
+text-label = Text des Pastes
+language-label = Sprache
+filter-label = Sprachen filtern
+expiration-label = Ablauf
+password-label = Passwort
+title-label = Titel
+filename-label = Dateiname
+upload-progress = Fortschritt des Hochladens
+challenge-noscript = Anonyme Pastes erfordern das Lösen einer Aufgabe, wofür JavaScript nötig ist.
+preview = Vorschau
+preview-edit = Bearbeiten
+preview-language = Hervorgehoben als { $language }
+upload-too-large = Der Paste ist größer als die Grenze von { $size } Bytes.
+upload-failed = Der Paste konnte nicht hochgeladen werden, bitte erneut versuchen.
+draft-found = Es gibt nicht abgeschickten Text von deinem letzten Besuch.
+draft-restore = Wiederherstellen
+draft-discard = Verwerfen
+paste = Einfügen
+save = Speichern
+decrypt = Entschlüsseln
+share-link = Kopiere { $link } und versende ihn. Nach dem ersten Öffnen wird er gelöscht.
+    .link = diesen Link
+
+## Expirations
+
+expires-never = nie
+expires-years = { $count ->
+    [one] { $count } Jahr
+   *[other] { $count } Jahre
+}
+expires-months = { $count ->
+    [one] { $count } Monat
+   *[other] { $count } Monate
+}
+expires-weeks = { $count ->
+    [one] { $count } Woche
+   *[other] { $count } Wochen
+}
+expires-days = { $count ->
+    [one] { $count } Tag
+   *[other] { $count } Tage
+}
+expires-hours = { $count ->
+    [one] { $count } Stunde
+   *[other] { $count } Stunden
+}
+expires-minutes = { $count ->
+    [one] { $count } Minute
+   *[other] { $count } Minuten
+}
+expires-seconds = { $count } Sekunden
+
+## Encryption in the browser
+
+zk-noscript = Pastes werden im Browser verschlüsselt, wofür JavaScript nötig ist.
+zk-placeholder = <Text zum Verschlüsseln im Browser einfügen>
+zk-key = Der Schlüssel bleibt im Link, der Server speichert nur den Geheimtext.
+zk-submit = Verschlüsseln und einfügen
+zk-encrypting = Wird verschlüsselt ...
+zk-decrypting = Wird entschlüsselt ...
+zk-view-noscript = Im Browser verschlüsselte Pastes lassen sich nur mit JavaScript entschlüsseln.
+zk-no-key = Dem Link fehlt der Schlüssel zum Entschlüsseln dieses Pastes.
+zk-not-found = Paste nicht gefunden, vielleicht ist er abgelaufen oder wurde bereits gelesen.
+zk-fetch-failed = Paste konnte nicht abgerufen werden
+zk-decrypt-failed = Paste konnte nicht entschlüsselt werden, der Schlüssel im Link ist falsch.
+
+## Viewing pastes
+
+help-home = Zur Startseite
+help-here = Hierher
+help-raw = Rohen Paste anzeigen
+help-copy-url = URL kopieren
+help-copy-raw-url = Roh-URL kopieren
+help-copy-content = Inhalt kopieren
+help-download = Herunterladen
+help-qr = QR-Code anzeigen
+help-wrap = Zeilenumbruch umschalten
+help-editor = Editor fokussieren
+help-toggle = Hilfe umschalten
+secrets-notice = Dieser Paste scheint { $secrets } zu enthalten. Jeder mit dem Link kann ihn lesen, lösche ihn besser und widerrufe die Zugangsdaten.
+highlight-skipped = Die Hervorhebung wurde übersprungen, weil dieser Paste groß ist.
+highlight-anyway = Trotzdem hervorheben
+highlight-pending = Dieser Paste wird noch hervorgehoben, lade die Seite gleich neu.
+pager = Seite { $page } von { $pages }
+expires-in = läuft in { $time } ab
+preview-summary = { $language }, { $lines ->
+    [one] { $lines } Zeile
+   *[other] { $lines } Zeilen
+}
+
+## Accounts
+
+user-name-placeholder = Benutzername ...
+invite-placeholder = Einladungscode ...
+register = Registrieren
+log-in = Anmelden
+login-sso = Konto einer Organisation? { $link }
+    .link = mit Single Sign-on anmelden
+login-registered = Schon registriert? { $link }
+    .link = anmelden
+login-no-account = Noch kein Konto? { $link }
+    .link = registrieren
+login-invited = Einladungscode erhalten? { $link }
+    .link = registrieren
+code-placeholder = Authentifizierungs- oder Wiederherstellungscode ...
+verify = Bestätigen
+lost-authenticator = Authenticator verloren? Gib stattdessen einen deiner Wiederherstellungscodes ein.
+no-pastes = Noch keine Pastes, { $link }.
+    .link = erstelle einen
+
+## Listings
+
+column-paste = Paste
+column-language = Sprache
+column-created = Erstellt
+column-expires = Läuft ab
+column-owner = Besitzer
+column-size = Größe
+column-pastes = Pastes
+burned-after-reading = nach dem Lesen gelöscht
+encrypted = verschlüsselt
+pinned = angeheftet
+expired = abgelaufen
+never = nie
+anonymous = anonym
+delete-paste = { $name } löschen
+command-line = Kommandozeile
+plain-text = reiner Text
+export-json = als JSON exportieren
+language-statistics = Sprachstatistik
+column-month = Monat
+column-other = Andere
+
+## Settings
+
+settings = Einstellungen
+recovery-codes = Die Zwei-Faktor-Authentifizierung ist aktiviert. Bewahre diese Wiederherstellungscodes sicher auf. Jeder meldet dich einmal ohne deinen Authenticator an und sie werden nicht noch einmal angezeigt:
+totp-setup = Scanne den Code mit deiner Authenticator-App oder gib das Geheimnis { $secret } von Hand ein und bestätige dann mit dem angezeigten Code.
+totp-code-placeholder = Authentifizierungscode ...
+totp-code = Code
+totp-enable = Aktivieren
+totp-enabled = Die Zwei-Faktor-Authentifizierung ist aktiviert, { $codes } Wiederherstellungscodes übrig.
+totp-disable-placeholder = Code ...
+totp-disabled = Die Zwei-Faktor-Authentifizierung ist deaktiviert, { $link }, um beim Anmelden nach einem Code aus einer Authenticator-App zu fragen.
+    .link = richte sie ein
+token-created = Neuer API-Token, kopiere ihn jetzt, er wird nicht noch einmal angezeigt: { $token }
+column-token = API-Token
+column-scope = Bereich
+column-last-used = Zuletzt verwendet
+token-label-placeholder = Bezeichnung ...
+token-label = Bezeichnung
+token-scope = Bereich
+scope-create = Pastes erstellen
+scope-manage = Pastes verwalten
+create-token = Token erstellen
+no-tokens = Noch keine API-Tokens. Tokens authentifizieren Anfragen an die API über den Header { $header }.
+
+## Administration
+
+admin = Verwaltung
+statistics = Statistik
+dashboard = Übersicht
+stats-pastes = { $count } Pastes ({ $size })
+stats-expired = { $count } abgelaufen
+stats-pinned = { $count } angeheftet
+stats-encrypted = { $count } verschlüsselt
+stats-burn-after-reading = { $count } nach dem Lesen löschen
+stats-accounts = { $count } Konten
+stats-sessions = { $count } Sitzungen
+stats-cached = { $count } zwischengespeichert
+stats-cache-hits = { $ratio } Cache-Treffer
+stats-no-lookups = keine Cache-Abfragen
+purge-title = alle abgelaufenen Pastes löschen
+purge = Abgelaufene löschen
+purged = { $count } abgelaufene Pastes gelöscht.
+no-purges = Noch keine Löschläufe.
+log-filter = Log-Filter
+log-filter-label = Log-Filter
+column-invite = Einladung
+column-uses = Verbleibende Nutzungen
+uses = Nutzungen
+create-invite = Einladung erstellen
+column-quarantined = In Quarantäne
+column-rule = Regel
+column-audit = Audit-Log
+column-actor = Akteur
+column-target = Ziel
+column-purge = Löschlauf
+search-placeholder = Kennung, Titel, Sprache oder Besitzer suchen ...
+search = Suche
+no-pastes-found = Keine Pastes gefunden.
@@ -0,0 +1,274 @@
+# Messages of the web interface. Additional locales are files named after their language tag,
+# e.g. `fr.ftl`, and fall back to these messages for any they leave out.
+
+locale-name = English
+
+## Navigation
+
+nav-home = home
+nav-pastes = my pastes
+nav-about = about
+nav-theme = theme
+nav-apply-theme = apply theme
+nav-locale = language
+nav-apply-locale = apply language
+nav-follow-system = follow system setting
+nav-dark = switch to dark
+nav-light = switch to light
+nav-encrypt = encrypt in browser
+nav-upload = upload
+nav-edit = edit paste
+nav-duplicate = duplicate into editor
+nav-delete = delete paste
+nav-download = download file
+nav-raw = display raw file
+nav-raw-link = link to the raw file
+nav-pdf = export as pdf
+nav-print = print view
+nav-copy = copy to clipboard
+nav-copy-url = copy URL
+nav-copy-raw-url = copy raw URL
+nav-source = source view
+nav-pretty = pretty-print JSON
+nav-tree = JSON tree view
+nav-highlight-as = highlight as
+nav-apply-language = apply language
+nav-save-language = save language
+nav-qr = qr code
+nav-wrap = toggle line wrap
+nav-line-numbers = toggle line numbers
+nav-paste-content = paste content
+nav-admin = admin dashboard
+nav-settings = settings
+nav-log-out = log out
+nav-actions = page actions
+nav-skip = skip to content
+nav-high-contrast = toggle high contrast
+
+## Actions
+
+apply = apply
+edit = edit
+delete = delete
+revoke = revoke
+disable = disable
+release = release
+pin = pin
+unpin = unpin
+previous = previous
+next = next
+copied = Copied content
+copied-url = Copied URL
+copied-raw-url = Copied raw URL
+copy-failed = Copying to the clipboard failed
+working = Working ...
+go-back = go back
+
+## Creating pastes
+
+private-instance = This instance is private, { $link } to create pastes.
+    .link = log in
+honeypot = Leave this field empty
+text-placeholder = <paste text or drop file here>
+filter-placeholder = Filter ...
+burn-after-reading = 🔥 after reading
+noindex = 🙈 hide from search
+secret-link = 🔑 secret link
+accept-terms = 📜 I accept the { $link }
+    .link = terms of service
+password-placeholder = Password ...
+title-placeholder = Title ...
+filename-placeholder = File name ...
+new-paste = New paste
+paste-options = Paste options
+text-label = Text of the paste
+language-label = Language
+filter-label = Filter languages
+expiration-label = Expiration
+password-label = Password
+title-label = Title
+filename-label = File name
+upload-progress = Upload progress
+challenge-noscript = Anonymous pastes require solving a challenge, which needs JavaScript.
+preview = Preview
+preview-edit = Edit
+preview-language = Highlighted as { $language }
+upload-too-large = The paste is larger than the limit of { $size } bytes.
+upload-failed = The paste could not be uploaded, please try again.
+draft-found = There is unsubmitted text from your last visit.
+draft-restore = Restore
+draft-discard = Discard
+paste = Paste
+save = Save
+decrypt = Decrypt
+share-link = Copy and send { $link }. After opening it for the first time, it will be deleted.
+    .link = this link
+
+## Expirations
+
+expires-never = never
+expires-years = { $count ->
+    [one] { $count } year
+   *[other] { $count } years
+}
+expires-months = { $count ->
+    [one] { $count } month
+   *[other] { $count } months
+}
+expires-weeks = { $count ->
+    [one] { $count } week
+   *[other] { $count } weeks
+}
+expires-days = { $count ->
+    [one] { $count } day
+   *[other] { $count } days
+}
+expires-hours = { $count ->
+    [one] { $count } hour
+   *[other] { $count } hours
+}
+expires-minutes = { $count ->
+    [one] { $count } minute
+   *[other] { $count } minutes
+}
+expires-seconds = { $count } seconds
+
+## Encryption in the browser
+
+zk-noscript = Pastes are encrypted in the browser, which requires JavaScript.
+zk-placeholder = <paste text to encrypt in your browser>
+zk-key = The key stays in the link, the server only stores ciphertext.
+zk-submit = Encrypt and paste
+zk-encrypting = Encrypting ...
+zk-decrypting = Decrypting ...
+zk-view-noscript = Pastes encrypted in the browser can only be decrypted with JavaScript.
+zk-no-key = The link lacks the key to decrypt this paste.
+zk-not-found = Paste not found, it may have expired or been burned.
+zk-fetch-failed = Could not fetch paste
+zk-decrypt-failed = Could not decrypt the paste, the key in the link is wrong.
+
+## Viewing pastes
+
+help-home = Go home
+help-here = Go here
+help-raw = View raw paste
+help-copy-url = Copy URL
+help-copy-raw-url = Copy raw URL
+help-copy-content = Copy content
+help-download = Download
+help-qr = Show QR code
+help-wrap = Toggle line wrapping
+help-editor = Focus editor
+help-toggle = Toggle help
+secrets-notice = This paste seems to contain { $secrets }. Anyone with the link can read it, consider deleting it and revoking the credentials.
+highlight-skipped = Highlighting was skipped because this paste is large.
+highlight-anyway = Highlight anyway
+highlight-pending = This paste is still being highlighted, reload the page in a moment.
+pager = page { $page } of { $pages }
+expires-in = expires in { $time }
+preview-summary = { $language }, { $lines ->
+    [one] { $lines } line
+   *[other] { $lines } lines
+}
+
+## Accounts
+
+user-name-placeholder = User name ...
+invite-placeholder = Invite code ...
+register = Register
+log-in = Log in
+login-sso = Have an organization account? { $link }
+    .link = log in with single sign-on
+login-registered = Already registered? { $link }
+    .link = log in
+login-no-account = No account yet? { $link }
+    .link = register
+login-invited = Got an invite code? { $link }
+    .link = register
+code-placeholder = Authentication or recovery code ...
+verify = Verify
+lost-authenticator = Lost your authenticator? Enter one of your recovery codes instead.
+no-pastes = No pastes yet, { $link }.
+    .link = create one
+
+## Listings
+
+column-paste = Paste
+column-language = Language
+column-created = Created
+column-expires = Expires
+column-owner = Owner
+column-size = Size
+column-pastes = Pastes
+burned-after-reading = burned after reading
+encrypted = encrypted
+pinned = pinned
+expired = expired
+never = never
+anonymous = anonymous
+delete-paste = delete { $name }
+command-line = command line
+plain-text = plain text
+export-json = export as JSON
+language-statistics = language statistics
+column-month = Month
+column-other = Other
+
+## Settings
+
+settings = settings
+recovery-codes = Two-factor authentication is enabled. Store these recovery codes in a safe place, each one logs you in once without your authenticator and they will not be shown again:
+totp-setup = Scan the code with your authenticator app or enter the secret { $secret } by hand, then confirm with the code it shows.
+totp-code-placeholder = Authentication code ...
+totp-code = code
+totp-enable = Enable
+totp-enabled = Two-factor authentication is enabled, { $codes } recovery codes left.
+totp-disable-placeholder = Code ...
+totp-disabled = Two-factor authentication is disabled, { $link } to ask for a code from an authenticator app when logging in.
+    .link = set it up
+token-created = New API token, copy it now as it will not be shown again: { $token }
+column-token = API token
+column-scope = Scope
+column-last-used = Last used
+token-label-placeholder = Label ...
+token-label = label
+token-scope = scope
+scope-create = create pastes
+scope-manage = manage pastes
+create-token = create token
+no-tokens = No API tokens yet. Tokens authenticate requests to the API via the { $header } header.
+
+## Administration
+
+admin = admin
+statistics = statistics
+dashboard = dashboard
+stats-pastes = { $count } pastes ({ $size })
+stats-expired = { $count } expired
+stats-pinned = { $count } pinned
+stats-encrypted = { $count } encrypted
+stats-burn-after-reading = { $count } burn after reading
+stats-accounts = { $count } accounts
+stats-sessions = { $count } sessions
+stats-cached = { $count } cached
+stats-cache-hits = { $ratio } cache hits
+stats-no-lookups = no cache lookups
+purge-title = delete all expired pastes
+purge = purge expired
+purged = Purged { $count } expired pastes.
+no-purges = No purges yet.
+log-filter = Log filter
+log-filter-label = log filter
+column-invite = Invite
+column-uses = Uses left
+uses = uses
+create-invite = create invite
+column-quarantined = Quarantined
+column-rule = Rule
+column-audit = Audit log
+column-actor = Actor
+column-target = Target
+column-purge = Purge
+search-placeholder = Search identifier, title, language or owner ...
+search = search
+no-pastes-found = No pastes found.
@@ -0,0 +1,243 @@
+use serde_json::{Map, Value};
+use std::fmt;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::Registry;
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::reload;
+
+/// Output format of log events.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Format {
+    /// Human readable lines.
+    #[default]
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("invalid filter directives: {0}")]
+    Directives(String),
+    #[error("failed to replace filter: {0}")]
+    Reload(#[from] reload::Error),
+}
+
+/// Filter of the log output that can be replaced while the server is running.
+#[derive(Clone)]
+pub(crate) struct Filter {
+    handle: reload::Handle<Targets, Registry>,
+}
+
+impl Filter {
+    /// Create a reloadable filter starting with `targets` and the layer to install it with.
+    pub fn new(targets: Targets) -> (Self, reload::Layer<Targets, Registry>) {
+        let (layer, handle) = reload::Layer::new(targets);
+        (Self { handle }, layer)
+    }
+
+    /// Currently applied directives.
+    pub fn current(&self) -> String {
+        self.handle
+            .with_current(ToString::to_string)
+            .unwrap_or_default()
+    }
+
+    /// Replace the filter with comma-separated `target=level` `directives`.
+    pub fn set(&self, directives: &str) -> Result<(), Error> {
+        let targets = directives
+            .parse::<Targets>()
+            .map_err(|err| Error::Directives(err.to_string()))?;
+
+        Ok(self.handle.reload(targets)?)
+    }
+}
+
+/// Visitor collecting fields into a JSON object.
+#[derive(Default)]
+struct Visitor(Map<String, Value>);
+
+impl Visit for Visitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}").into());
+    }
+}
+
+/// Formatter of span fields as JSON objects, so [`Json`] can merge them into events.
+#[derive(Default)]
+pub(crate) struct JsonFields;
+
+impl<'writer> FormatFields<'writer> for JsonFields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        mut writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        let mut visitor = Visitor::default();
+        fields.record(&mut visitor);
+        write!(writer, "{}", Value::Object(visitor.0))
+    }
+
+    fn add_fields(
+        &self,
+        current: &mut FormattedFields<Self>,
+        fields: &tracing::span::Record<'_>,
+    ) -> fmt::Result {
+        let mut visitor = Visitor(serde_json::from_str(&current.fields).unwrap_or_default());
+        fields.record(&mut visitor);
+        current.fields = Value::Object(visitor.0).to_string();
+        Ok(())
+    }
+}
+
+/// Formatter of events as single line JSON objects with the timestamp, level, target, request id
+/// and the fields of the event and its enclosing spans.
+pub(crate) struct Json;
+
+impl<S> FormatEvent<S, JsonFields> for Json
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, JsonFields>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let timestamp = time::OffsetDateTime::now_utc()
+            .format(&time::format_description::well_known::Rfc3339)
+            .map_err(|_| fmt::Error)?;
+
+        let mut fields = Visitor::default();
+        event.record(&mut fields);
+
+        // Inner spans override fields of outer spans with the same name.
+        let mut spans = Map::new();
+
+        for span in ctx
+            .event_scope()
+            .into_iter()
+            .flat_map(tracing_subscriber::registry::Scope::from_root)
+        {
+            if let Some(Value::Object(span_fields)) = span
+                .extensions()
+                .get::<FormattedFields<JsonFields>>()
+                .and_then(|fields| serde_json::from_str(&fields.fields).ok())
+            {
+                spans.extend(span_fields);
+            }
+        }
+
+        let mut object = Map::new();
+        object.insert(String::from("timestamp"), timestamp.into());
+        object.insert(
+            String::from("level"),
+            event.metadata().level().as_str().into(),
+        );
+        object.insert(String::from("target"), event.metadata().target().into());
+
+        if let Some(id) = spans.remove("request_id") {
+            object.insert(String::from("request_id"), id);
+        }
+
+        if !spans.is_empty() {
+            object.insert(String::from("span"), Value::Object(spans));
+        }
+
+        object.insert(String::from("fields"), Value::Object(fields.0));
+
+        writeln!(writer, "{}", Value::Object(object))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::Layer;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// Writer collecting the log output.
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn json_events() {
+        let output = Output::default();
+        let writer = output.clone();
+        let (filter, reload) = Filter::new("info".parse().unwrap());
+
+        let layer = tracing_subscriber::fmt::layer()
+            .fmt_fields(JsonFields)
+            .event_format(Json)
+            .with_writer(move || writer.clone())
+            .with_filter(reload);
+        let subscriber = tracing_subscriber::registry().with(layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", request_id = "abc", method = "GET");
+            let _span = span.enter();
+            tracing::info!(status = 200, "finished");
+            tracing::debug!("hidden");
+
+            filter.set("debug").unwrap();
+            tracing::debug!("shown");
+
+            assert!(filter.current().eq_ignore_ascii_case("debug"));
+            assert!(filter.set("wastebin=loud").is_err());
+        });
+
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let events = output
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["level"], "INFO");
+        assert_eq!(events[0]["request_id"], "abc");
+        assert_eq!(events[0]["span"]["method"], "GET");
+        assert_eq!(events[0]["fields"]["message"], "finished");
+        assert_eq!(events[0]["fields"]["status"], 200);
+        assert!(events[0]["timestamp"].as_str().is_some());
+        assert_eq!(events[1]["fields"]["message"], "shown");
+    }
+}
@@ -0,0 +1,144 @@
+use std::str::FromStr;
+
+#[cfg(feature = "smtp")]
+pub mod message;
+#[cfg(feature = "smtp")]
+pub mod relay;
+
+/// Shortest secret accepted for a sender.
+const MIN_SECRET: usize = 16;
+
+/// Sender addresses mail is accepted from, exact addresses or whole domains written as
+/// `@example.com`, each with the secret their mail has to be addressed to. Sender addresses are
+/// trivial to forge, so only the secret proves that mail comes from the sender it claims.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Senders(Vec<Sender>);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Sender {
+    /// Address or `@domain`.
+    pattern: String,
+    secret: String,
+}
+
+impl FromStr for Senders {
+    type Err = String;
+
+    /// Parse comma-separated `address=secret` and `@domain=secret` entries. Errors name the
+    /// address of the offending entry, but never its secret.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (pattern, secret) = entry.rsplit_once('=').unwrap_or((entry, ""));
+                let valid_pattern = pattern.contains('@') && !pattern.ends_with('@');
+                let valid_secret = secret.len() >= MIN_SECRET
+                    && secret
+                        .bytes()
+                        .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+
+                if valid_pattern && valid_secret {
+                    Ok(Sender {
+                        pattern: pattern.to_ascii_lowercase(),
+                        secret: secret.to_string(),
+                    })
+                } else {
+                    Err(pattern.to_string())
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+}
+
+impl Sender {
+    /// Return `true` if `address` is the address or in the domain of the sender.
+    fn matches(&self, address: &str) -> bool {
+        if self.pattern.starts_with('@') {
+            address
+                .rsplit_once('@')
+                .is_some_and(|(local, domain)| !local.is_empty() && domain == &self.pattern[1..])
+        } else {
+            self.pattern == address
+        }
+    }
+}
+
+#[cfg_attr(
+    not(any(feature = "smtp", test)),
+    expect(dead_code, reason = "only used with the `smtp` feature")
+)]
+impl Senders {
+    /// Return `true` if mail from `address` may be accepted once its recipient proves it.
+    pub fn knows(&self, address: &str) -> bool {
+        let address = address.to_ascii_lowercase();
+
+        self.0.iter().any(|sender| sender.matches(&address))
+    }
+
+    /// Return `true` if `recipient` carries the secret of `sender`, either as the whole local
+    /// part, as in `<secret>@host`, or after the last plus sign, as in `paste+<secret>@host`.
+    pub fn authenticate(&self, sender: &str, recipient: &str) -> bool {
+        let sender = sender.to_ascii_lowercase();
+        let Some((local, _)) = recipient.rsplit_once('@') else {
+            return false;
+        };
+        let secret = local.rsplit_once('+').map_or(local, |(_, secret)| secret);
+
+        self.0
+            .iter()
+            .filter(|entry| entry.matches(&sender))
+            .any(|entry| equal(entry.secret.as_bytes(), secret.as_bytes()))
+    }
+}
+
+/// Compare `a` and `b` in time independent of where they differ, so that secrets cannot be
+/// guessed byte by byte.
+fn equal(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn senders() -> Result<(), String> {
+        let senders =
+            "alerts@example.com=0123456789abcdef, @monitoring.example.org=fedcba9876543210"
+                .parse::<Senders>()?;
+        assert!(senders.knows("Alerts@Example.com"));
+        assert!(senders.knows("nagios@monitoring.example.org"));
+        assert!(!senders.knows("alice@example.com"));
+        assert!(!senders.knows("nagios@evil.monitoring.example.org"));
+        assert!(!senders.knows(""));
+
+        assert!(senders.authenticate("alerts@example.com", "0123456789abcdef@paste.example.com"));
+        assert!(senders.authenticate(
+            "nagios@monitoring.example.org",
+            "paste+fedcba9876543210@paste.example.com"
+        ));
+        assert!(!senders.authenticate("alerts@example.com", "paste@paste.example.com"));
+        assert!(!senders.authenticate(
+            "alerts@example.com",
+            "paste+fedcba9876543210@paste.example.com"
+        ));
+        assert!(!senders.authenticate("alerts@example.com", "0123456789abcdef"));
+
+        assert_eq!(
+            "example.com=0123456789abcdef".parse::<Senders>(),
+            Err(String::from("example.com"))
+        );
+        assert_eq!(
+            "alerts@example.com".parse::<Senders>(),
+            Err(String::from("alerts@example.com"))
+        );
+        assert_eq!(
+            "alerts@example.com=short".parse::<Senders>(),
+            Err(String::from("alerts@example.com"))
+        );
+
+        Ok(())
+    }
+}
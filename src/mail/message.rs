@@ -0,0 +1,466 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::fmt::Write;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc2822;
+
+/// Largest number of nested multipart bodies that are looked into.
+const MAX_DEPTH: usize = 4;
+
+/// Text of a message body or attachment.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Part {
+    /// File name of attachments
+    pub filename: Option<String>,
+    pub text: String,
+}
+
+/// Parts of a received message that can become pastes.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Message {
+    pub subject: Option<String>,
+    /// Value of the `Message-ID` header
+    pub id: Option<String>,
+    /// The message was sent automatically, e.g. as autoreply, and must not be answered.
+    pub auto_submitted: bool,
+    /// Plain text bodies and text attachments, in the order they appear.
+    pub parts: Vec<Part>,
+}
+
+/// Unfolded header fields with lowercase names.
+struct Headers(Vec<(String, String)>);
+
+impl Headers {
+    fn parse(raw: &[u8]) -> Self {
+        let raw = String::from_utf8_lossy(raw);
+        let mut fields: Vec<(String, String)> = Vec::new();
+
+        for line in raw.lines() {
+            if line.starts_with([' ', '\t']) {
+                if let Some((_, value)) = fields.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+            } else if let Some((name, value)) = line.split_once(':') {
+                fields.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+            }
+        }
+
+        Self(fields)
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Lowercase value and parameters of a structured field like `Content-Type`.
+    fn structured(&self, name: &str) -> Option<(String, Vec<(String, String)>)> {
+        let mut items = self.get(name)?.split(';');
+        let value = items.next()?.trim().to_ascii_lowercase();
+
+        let params = items
+            .filter_map(|param| {
+                let (key, value) = param.split_once('=')?;
+                let key = key.trim().to_ascii_lowercase();
+                let value = value.trim().trim_matches('"');
+
+                // RFC 2231 values look like `utf-8''name%20with%20spaces`.
+                match key.strip_suffix('*') {
+                    Some(key) => {
+                        let value = value.splitn(3, '\'').nth(2).unwrap_or(value);
+                        Some((key.to_string(), percent_decode(value)))
+                    }
+                    None => Some((key, decode_words(value))),
+                }
+            })
+            .collect();
+
+        Some((value, params))
+    }
+}
+
+/// Value of parameter `name` in `params`.
+fn param<'a>(params: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    params
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Split `raw` into its header and body at the first empty line.
+fn split(raw: &[u8]) -> (&[u8], &[u8]) {
+    for (end, skip) in [(b"\r\n\r\n".as_slice(), 4), (b"\n\n".as_slice(), 2)] {
+        if let Some(pos) = raw.windows(end.len()).position(|window| window == end) {
+            return (&raw[..pos], &raw[pos + skip..]);
+        }
+    }
+
+    (raw, &[])
+}
+
+/// Parse the message in `raw` as received via SMTP.
+pub(crate) fn parse(raw: &[u8]) -> Message {
+    let (header, body) = split(raw);
+    let headers = Headers::parse(header);
+    let mut parts = Vec::new();
+
+    collect(&headers, body, 0, &mut parts);
+
+    Message {
+        subject: headers
+            .get("subject")
+            .map(decode_words)
+            .filter(|subject| !subject.is_empty()),
+        id: headers.get("message-id").map(ToString::to_string),
+        auto_submitted: headers
+            .get("auto-submitted")
+            .is_some_and(|value| !value.eq_ignore_ascii_case("no")),
+        parts,
+    }
+}
+
+/// Add the text parts of the entity with `headers` and `body` to `parts`.
+fn collect(headers: &Headers, body: &[u8], depth: usize, parts: &mut Vec<Part>) {
+    let (mime, params) = headers
+        .structured("content-type")
+        .unwrap_or_else(|| (String::from("text/plain"), Vec::new()));
+
+    if let Some(subtype) = mime.strip_prefix("multipart/") {
+        let Some(boundary) = param(&params, "boundary").filter(|_| depth < MAX_DEPTH) else {
+            return;
+        };
+
+        for entity in multipart(body, boundary) {
+            let (header, body) = split(&entity);
+            let before = parts.len();
+            collect(&Headers::parse(header), body, depth + 1, parts);
+
+            // Alternatives show the same content, the plain text one is enough.
+            if subtype == "alternative" && parts.len() > before {
+                break;
+            }
+        }
+
+        return;
+    }
+
+    let filename = headers
+        .structured("content-disposition")
+        .and_then(|(_, params)| param(&params, "filename").map(ToString::to_string))
+        .or_else(|| param(&params, "name").map(ToString::to_string));
+
+    if filename.is_none() && mime != "text/plain" {
+        return;
+    }
+
+    let encoding = headers
+        .get("content-transfer-encoding")
+        .map(str::to_ascii_lowercase);
+
+    let decoded = match encoding.as_deref() {
+        Some("base64") => {
+            let data = body
+                .iter()
+                .copied()
+                .filter(|byte| !byte.is_ascii_whitespace())
+                .collect::<Vec<_>>();
+
+            let Ok(data) = STANDARD.decode(data) else {
+                return;
+            };
+
+            data
+        }
+        Some("quoted-printable") => quoted_printable(body, false),
+        _ => body.to_vec(),
+    };
+
+    // Binary attachments cannot be pasted.
+    let Some(text) = decode_text(&decoded, param(&params, "charset")) else {
+        return;
+    };
+
+    if text.contains('\0') || (filename.is_none() && text.trim().is_empty()) {
+        return;
+    }
+
+    parts.push(Part {
+        filename,
+        text: text.replace("\r\n", "\n"),
+    });
+}
+
+/// Entities of a multipart `body` separated by `boundary`.
+fn multipart(body: &[u8], boundary: &str) -> Vec<Vec<u8>> {
+    let delimiter = format!("--{boundary}");
+    let mut entities = Vec::new();
+    let mut current: Option<Vec<u8>> = None;
+
+    for line in body.split(|&byte| byte == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let trimmed = line.trim_ascii_end();
+
+        if let Some(rest) = trimmed.strip_prefix(delimiter.as_bytes()) {
+            if rest.is_empty() || rest == b"--" {
+                // The line break before the delimiter belongs to it.
+                entities.extend(current.take().map(|mut entity| {
+                    entity.truncate(entity.len().saturating_sub(2));
+                    entity
+                }));
+
+                if rest == b"--" {
+                    break;
+                }
+
+                current = Some(Vec::new());
+                continue;
+            }
+        }
+
+        if let Some(entity) = &mut current {
+            entity.extend_from_slice(line);
+            entity.extend_from_slice(b"\r\n");
+        }
+    }
+
+    entities
+}
+
+/// Text of `data` in `charset`, `None` if it cannot be decoded.
+fn decode_text(data: &[u8], charset: Option<&str>) -> Option<String> {
+    match charset.map(str::to_ascii_lowercase).as_deref() {
+        Some("iso-8859-1" | "latin1" | "us-ascii") => {
+            Some(data.iter().map(|&b| b as char).collect())
+        }
+        _ => String::from_utf8(data.to_vec()).ok(),
+    }
+}
+
+/// Decode quoted-printable `data`, with underscores as spaces for encoded words.
+fn quoted_printable(data: &[u8], underscores: bool) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(data.len());
+    let mut bytes = data.iter().copied().peekable();
+
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'=' => {
+                let high = bytes.next();
+
+                match high {
+                    // Soft line break
+                    Some(b'\r') => {
+                        bytes.next_if_eq(&b'\n');
+                    }
+                    Some(b'\n') | None => {}
+                    Some(high) => {
+                        let low = bytes.next().unwrap_or_default();
+                        let hex = [high, low];
+
+                        match std::str::from_utf8(&hex)
+                            .ok()
+                            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                        {
+                            Some(value) => decoded.push(value),
+                            None => decoded.extend_from_slice(&[b'=', high, low]),
+                        }
+                    }
+                }
+            }
+            b'_' if underscores => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+    }
+
+    decoded
+}
+
+/// Decode `%XX` escapes in `value`.
+fn percent_decode(value: &str) -> String {
+    let mut decoded = Vec::with_capacity(value.len());
+    let mut bytes = value.bytes();
+
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let hex = [
+                bytes.next().unwrap_or_default(),
+                bytes.next().unwrap_or_default(),
+            ];
+
+            if let Some(value) = std::str::from_utf8(&hex)
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(value);
+                continue;
+            }
+
+            decoded.push(b'%');
+            decoded.extend(hex.iter().filter(|&&byte| byte != 0));
+        } else {
+            decoded.push(byte);
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Decode the RFC 2047 encoded words like `=?utf-8?B?...?=` in a header `value`.
+fn decode_words(value: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = value;
+    let mut after_word = false;
+
+    while let Some(start) = rest.find("=?") {
+        let word = rest[start + 2..].splitn(3, '?').collect::<Vec<_>>();
+
+        let [charset, encoding, tail] = word[..] else {
+            break;
+        };
+
+        let Some(end) = tail.find("?=") else {
+            break;
+        };
+
+        let text = &tail[..end];
+        let data = match encoding {
+            "B" | "b" => STANDARD.decode(text).ok(),
+            "Q" | "q" => Some(quoted_printable(text.as_bytes(), true)),
+            _ => None,
+        };
+
+        let between = &rest[..start];
+
+        // Whitespace between adjacent encoded words is dropped.
+        if !(after_word && between.trim().is_empty()) {
+            decoded.push_str(between);
+        }
+
+        match data.and_then(|data| decode_text(&data, Some(charset))) {
+            Some(text) => decoded.push_str(&text),
+            None => {
+                decoded.push_str(&rest[start..start + charset.len() + encoding.len() + end + 6]);
+            }
+        }
+
+        rest = &tail[end + 2..];
+        after_word = true;
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Reply to a message with `subject` and `message_id`, listing the `urls` of its pastes.
+pub(crate) fn reply(
+    from: &str,
+    to: &str,
+    subject: Option<&str>,
+    message_id: Option<&str>,
+    urls: &[String],
+) -> String {
+    let subject = format!("Re: {}", subject.unwrap_or("pastes"));
+    let subject = if subject.is_ascii() {
+        subject
+    } else {
+        format!("=?utf-8?B?{}?=", STANDARD.encode(subject))
+    };
+
+    let mut reply = format!(
+        "From: <{from}>\r\nTo: <{to}>\r\nSubject: {subject}\r\nDate: {}\r\nAuto-Submitted: auto-replied\r\n",
+        OffsetDateTime::now_utc()
+            .format(&Rfc2822)
+            .unwrap_or_default()
+    );
+
+    if let Some(id) = message_id {
+        let _ = write!(reply, "In-Reply-To: {id}\r\nReferences: {id}\r\n");
+    }
+
+    reply.push_str("MIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n");
+
+    for url in urls {
+        reply.push_str(url);
+        reply.push_str("\r\n");
+    }
+
+    reply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain() {
+        let message = parse(
+            b"From: alerts@example.com\r\nSubject: =?utf-8?Q?Disk_=C3=BCber?= 90%\r\n\r\nsda1 full\r\n",
+        );
+
+        assert_eq!(message.subject.as_deref(), Some("Disk über 90%"));
+        assert_eq!(
+            message.parts,
+            [Part {
+                filename: None,
+                text: String::from("sda1 full\n"),
+            }]
+        );
+    }
+
+    #[test]
+    fn multipart() {
+        let message = parse(
+            b"Subject: report\r\n\
+              Message-ID: <1@example.com>\r\n\
+              Content-Type: multipart/mixed; boundary=\"outer\"\r\n\r\n\
+              preamble\r\n\
+              --outer\r\n\
+              Content-Type: multipart/alternative; boundary=inner\r\n\r\n\
+              --inner\r\n\
+              Content-Type: text/plain; charset=iso-8859-1\r\n\
+              Content-Transfer-Encoding: quoted-printable\r\n\r\n\
+              gr=FC=DF=\r\n\
+              e\r\n\
+              --inner\r\n\
+              Content-Type: text/html\r\n\r\n\
+              <p>gruesse</p>\r\n\
+              --inner--\r\n\
+              --outer\r\n\
+              Content-Type: text/x-log; name=\"ignored.log\"\r\n\
+              Content-Disposition: attachment; filename*=utf-8''disk%20usage.log\r\n\
+              Content-Transfer-Encoding: base64\r\n\r\n\
+              L2Rldi9zZGExIDk1JQo=\r\n\
+              --outer\r\n\
+              Content-Type: image/png; name=\"graph.png\"\r\n\
+              Content-Transfer-Encoding: base64\r\n\r\n\
+              iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR4nGNgYGD4DwABBAEAwS2OUAAAAABJRU5ErkJggg==\r\n\
+              --outer--\r\n\
+              epilogue\r\n",
+        );
+
+        assert_eq!(message.subject.as_deref(), Some("report"));
+        assert_eq!(message.id.as_deref(), Some("<1@example.com>"));
+        assert!(!message.auto_submitted);
+        assert_eq!(
+            message.parts,
+            [
+                Part {
+                    filename: None,
+                    text: String::from("grüße"),
+                },
+                Part {
+                    filename: Some(String::from("disk usage.log")),
+                    text: String::from("/dev/sda1 95%\n"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn auto_replies() {
+        assert!(parse(b"Auto-Submitted: auto-replied\r\n\r\nout of office\r\n").auto_submitted);
+        assert!(!parse(b"Auto-Submitted: no\r\n\r\nreport\r\n").auto_submitted);
+    }
+}
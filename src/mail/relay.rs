@@ -0,0 +1,76 @@
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("failed to talk to relay: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("relay refused: {0}")]
+    Refused(String),
+}
+
+/// Read a reply of the relay on `reader` and check that it starts with the `expected` digit.
+async fn expect(
+    reader: &mut BufReader<tokio::net::tcp::ReadHalf<'_>>,
+    expected: u8,
+) -> Result<(), Error> {
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+
+        if reader.read_line(&mut line).await? == 0 {
+            return Err(Error::Refused(String::from("connection closed")));
+        }
+
+        // Continuation lines have a dash after the code.
+        if line.as_bytes().get(3) != Some(&b'-') {
+            break;
+        }
+    }
+
+    if line.as_bytes().first() == Some(&expected) {
+        Ok(())
+    } else {
+        Err(Error::Refused(line.trim_end().to_string()))
+    }
+}
+
+/// Send `message` to `to` through the SMTP server at `relay`, greeting it as `host`. The null
+/// reverse path keeps bounces of the reply from being answered again.
+pub(crate) async fn send(relay: &str, host: &str, to: &str, message: &str) -> Result<(), Error> {
+    let mut stream = TcpStream::connect(relay).await?;
+    let (reader, mut writer) = stream.split();
+    let mut reader = BufReader::new(reader);
+
+    expect(&mut reader, b'2').await?;
+
+    for (command, code) in [
+        (format!("EHLO {host}\r\n"), b'2'),
+        (String::from("MAIL FROM:<>\r\n"), b'2'),
+        (format!("RCPT TO:<{to}>\r\n"), b'2'),
+        (String::from("DATA\r\n"), b'3'),
+    ] {
+        writer.write_all(command.as_bytes()).await?;
+        expect(&mut reader, code).await?;
+    }
+
+    let mut data = String::with_capacity(message.len() + 5);
+
+    for line in message.lines() {
+        if line.starts_with('.') {
+            data.push('.');
+        }
+
+        data.push_str(line);
+        data.push_str("\r\n");
+    }
+
+    data.push_str(".\r\n");
+    writer.write_all(data.as_bytes()).await?;
+    expect(&mut reader, b'2').await?;
+
+    writer.write_all(b"QUIT\r\n").await?;
+
+    Ok(())
+}
@@ -1,17 +1,20 @@
-use crate::cache::Cache;
+use crate::cache::{Cache, DiskCache};
 use crate::db::Database;
 use crate::errors::Error;
 use crate::handlers::extract::Theme;
-use crate::handlers::{delete, download, html, insert, raw, theme};
+use crate::handlers::{
+    account, admin, delete, download, edit, html, image, insert, language, manifest, pdf, raw,
+    settings, theme,
+};
 use axum::extract::{DefaultBodyLimit, FromRef, Request, State};
 use axum::http::{HeaderName, HeaderValue, StatusCode};
-use axum::middleware::{Next, from_fn, from_fn_with_state};
+use axum::middleware::{Next, from_fn_with_state};
 use axum::response::{IntoResponse, Response};
 use axum::routing::{Router, get, post};
 use axum_extra::extract::cookie::Key;
 use http::header::{
-    CONTENT_SECURITY_POLICY, REFERRER_POLICY, SERVER, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
-    X_XSS_PROTECTION,
+    CONTENT_SECURITY_POLICY, InvalidHeaderValue, REFERRER_POLICY, SERVER, X_CONTENT_TYPE_OPTIONS,
+    X_FRAME_OPTIONS, X_XSS_PROTECTION,
 };
 use std::process::ExitCode;
 use std::sync::Arc;
@@ -22,19 +25,58 @@ use tower_http::compression::CompressionLayer;
 use tower_http::timeout::TimeoutLayer;
 use tower_http::trace::TraceLayer;
 
+mod access;
+mod access_log;
+mod accounts;
 mod assets;
+mod blocklist;
 mod cache;
+mod cache_control;
+mod capability;
+mod captcha;
+mod clamav;
+mod cli;
+mod compression;
 mod crypto;
+mod csrf;
 mod db;
+mod deadline;
 mod env;
 mod errors;
 mod expiration;
 mod handlers;
+mod health;
 mod highlight;
+mod i18n;
 mod id;
+mod keys;
+mod ldap;
+mod limits;
+mod logging;
+mod mail;
+mod metrics;
+mod minify;
+mod notify;
+#[cfg(feature = "oidc")]
+mod oidc;
+#[cfg(feature = "otlp")]
+mod otlp;
 mod page;
+mod pow;
+#[cfg(any(feature = "gemini", feature = "gopher"))]
+mod protocols;
+mod secrets;
+#[cfg(feature = "sentry")]
+mod sentry;
+mod shortcuts;
+mod spam;
+mod templates;
+mod terms;
 #[cfg(test)]
 mod test_helpers;
+mod timezone;
+mod totp;
+mod upgrade;
 
 /// Reference counted [`page::Page`] wrapper.
 pub(crate) type Page = Arc<page::Page>;
@@ -42,13 +84,53 @@ pub(crate) type Page = Arc<page::Page>;
 /// Reference counted [`highlight::Highlighter`] wrapper.
 pub(crate) type Highlighter = Arc<highlight::Highlighter>;
 
+/// Reference counted [`ldap::Directory`] wrapper.
+pub(crate) type Ldap = Arc<ldap::Directory>;
+
+/// Reference counted [`oidc::Provider`] wrapper.
+#[cfg(feature = "oidc")]
+pub(crate) type Oidc = Arc<oidc::Provider>;
+
+/// Reference counted [`captcha::Verifier`] wrapper.
+#[cfg(feature = "captcha")]
+pub(crate) type Captcha = Arc<captcha::Verifier>;
+
 #[derive(Clone)]
 pub(crate) struct AppState {
     db: Database,
+    access: Arc<access::Access>,
     cache: Cache,
     key: Key,
+    /// Keys of earlier rotations still accepted for verifying cookies.
+    previous_keys: Arc<[Key]>,
     page: Page,
     highlighter: Highlighter,
+    ldap: Option<Ldap>,
+    #[cfg(feature = "oidc")]
+    oidc: Option<Oidc>,
+    #[cfg(feature = "captcha")]
+    captcha: Option<Captcha>,
+    pow: Option<pow::Pow>,
+    fill_time: Option<spam::FillTime>,
+    blocklist: blocklist::Blocklist,
+    clamav: Option<clamav::Scanner>,
+    notifier: Option<notify::Notifier>,
+    limiter: limits::Limiter,
+    requests: metrics::Requests,
+    /// Where `/metrics` is served, if at all.
+    metrics: Option<metrics::Endpoint>,
+    /// Filter of the log output, if it can be changed at runtime.
+    log_filter: Option<logging::Filter>,
+    readiness: health::Readiness,
+    access_log: Option<access_log::AccessLog>,
+    compression: compression::Filter,
+    cache_control: Arc<cache_control::Policy>,
+    minify: minify::Enabled,
+    locales: i18n::Catalog,
+    language_stats: handlers::stats::Languages,
+    sitemaps: handlers::sitemap::Sitemaps,
+    #[cfg(feature = "sentry")]
+    sentry: Option<sentry::Reporter>,
 }
 
 impl FromRef<AppState> for Key {
@@ -57,6 +139,18 @@ impl FromRef<AppState> for Key {
     }
 }
 
+impl FromRef<AppState> for handlers::stats::Languages {
+    fn from_ref(state: &AppState) -> Self {
+        state.language_stats.clone()
+    }
+}
+
+impl FromRef<AppState> for handlers::sitemap::Sitemaps {
+    fn from_ref(state: &AppState) -> Self {
+        state.sitemaps.clone()
+    }
+}
+
 impl FromRef<AppState> for Highlighter {
     fn from_ref(state: &AppState) -> Self {
         state.highlighter.clone()
@@ -75,21 +169,180 @@ impl FromRef<AppState> for Database {
     }
 }
 
+impl FromRef<AppState> for Option<Ldap> {
+    fn from_ref(state: &AppState) -> Self {
+        state.ldap.clone()
+    }
+}
+
+#[cfg(feature = "oidc")]
+impl FromRef<AppState> for Option<Oidc> {
+    fn from_ref(state: &AppState) -> Self {
+        state.oidc.clone()
+    }
+}
+
+#[cfg(feature = "captcha")]
+impl FromRef<AppState> for Option<Captcha> {
+    fn from_ref(state: &AppState) -> Self {
+        state.captcha.clone()
+    }
+}
+
+#[cfg(feature = "sentry")]
+impl FromRef<AppState> for Option<sentry::Reporter> {
+    fn from_ref(state: &AppState) -> Self {
+        state.sentry.clone()
+    }
+}
+
+impl FromRef<AppState> for Option<pow::Pow> {
+    fn from_ref(state: &AppState) -> Self {
+        state.pow.clone()
+    }
+}
+
+impl FromRef<AppState> for Option<spam::FillTime> {
+    fn from_ref(state: &AppState) -> Self {
+        state.fill_time.clone()
+    }
+}
+
+impl FromRef<AppState> for keys::Keys {
+    fn from_ref(state: &AppState) -> Self {
+        Self {
+            key: state.key.clone(),
+            previous: state.previous_keys.clone(),
+        }
+    }
+}
+
+impl FromRef<AppState> for access::Filter {
+    fn from_ref(state: &AppState) -> Self {
+        Self {
+            access: state.access.clone(),
+            limiter: state.limiter.clone(),
+            page: state.page.clone(),
+        }
+    }
+}
+
+impl FromRef<AppState> for handlers::insert::Filters {
+    fn from_ref(state: &AppState) -> Self {
+        Self {
+            blocklist: state.blocklist.clone(),
+            scanner: state.clamav.clone(),
+            notifier: state.notifier.clone(),
+        }
+    }
+}
+
+impl FromRef<AppState> for limits::Limiter {
+    fn from_ref(state: &AppState) -> Self {
+        state.limiter.clone()
+    }
+}
+
+impl FromRef<AppState> for metrics::Requests {
+    fn from_ref(state: &AppState) -> Self {
+        state.requests.clone()
+    }
+}
+
+impl FromRef<AppState> for metrics::Registry {
+    fn from_ref(state: &AppState) -> Self {
+        Self {
+            db: state.db.clone(),
+            cache: state.cache.clone(),
+            requests: state.requests.clone(),
+        }
+    }
+}
+
+impl FromRef<AppState> for Option<logging::Filter> {
+    fn from_ref(state: &AppState) -> Self {
+        state.log_filter.clone()
+    }
+}
+
+impl FromRef<AppState> for access_log::Logger {
+    fn from_ref(state: &AppState) -> Self {
+        Self {
+            log: state.access_log.clone(),
+            limiter: state.limiter.clone(),
+        }
+    }
+}
+
+impl FromRef<AppState> for health::Readiness {
+    fn from_ref(state: &AppState) -> Self {
+        state.readiness.clone()
+    }
+}
+
+impl FromRef<AppState> for i18n::Catalog {
+    fn from_ref(state: &AppState) -> Self {
+        state.locales.clone()
+    }
+}
+
+impl FromRef<AppState> for minify::Enabled {
+    fn from_ref(state: &AppState) -> Self {
+        state.minify
+    }
+}
+
+impl FromRef<AppState> for cache_control::Headers {
+    fn from_ref(state: &AppState) -> Self {
+        Self {
+            policy: state.cache_control.clone(),
+            page: state.page.clone(),
+        }
+    }
+}
+
 impl FromRef<AppState> for Cache {
     fn from_ref(state: &AppState) -> Self {
         state.cache.clone()
     }
 }
 
-async fn security_headers_layer(req: Request, next: Next) -> impl IntoResponse {
-    const SECURITY_HEADERS: [(HeaderName, HeaderValue); 7] = [
+/// Content security policy allowing the captcha widget, if any, to load from its provider.
+fn content_security_policy(page: &Page) -> Result<HeaderValue, InvalidHeaderValue> {
+    let assets = page.asset_origin.as_deref().unwrap_or_default();
+    let css = page
+        .assets
+        .custom_css
+        .as_ref()
+        .and_then(assets::Custom::origin)
+        .unwrap_or_default();
+    let logo = page
+        .assets
+        .logo
+        .as_ref()
+        .and_then(assets::Custom::origin)
+        .unwrap_or_default();
+
+    let Some(widget) = &page.captcha else {
+        return HeaderValue::try_from(format!(
+            "default-src 'none'; script-src 'self' {assets}; img-src 'self' data: {assets} {css} {logo}; style-src 'self' data: {assets} {css}; font-src 'self' data: {assets} {css}; connect-src 'self' ; manifest-src 'self' ; object-src 'none' ; base-uri 'none' ; frame-ancestors 'none' ; form-action 'self' ;",
+        ));
+    };
+
+    let origins = widget.provider.origins();
+
+    HeaderValue::try_from(format!(
+        "default-src 'none'; script-src 'self' {assets} {origins}; img-src 'self' data: {assets} {css} {logo}; style-src 'self' data: {assets} {css} {origins}; font-src 'self' data: {assets} {css}; frame-src {origins}; connect-src 'self' {origins}; manifest-src 'self' ; object-src 'none' ; base-uri 'none' ; frame-ancestors 'none' ; form-action 'self' ;"
+    ))
+}
+
+async fn security_headers_layer(
+    State(csp): State<HeaderValue>,
+    req: Request,
+    next: Next,
+) -> impl IntoResponse {
+    const SECURITY_HEADERS: [(HeaderName, HeaderValue); 6] = [
         (SERVER, HeaderValue::from_static(env!("CARGO_PKG_NAME"))),
-        (
-            CONTENT_SECURITY_POLICY,
-            HeaderValue::from_static(
-                "default-src 'none'; script-src 'self'; img-src 'self' data: ; style-src 'self' data: ; font-src 'self' data: ; object-src 'none' ; base-uri 'none' ; frame-ancestors 'none' ; form-action 'self' ;",
-            ),
-        ),
         (REFERRER_POLICY, HeaderValue::from_static("same-origin")),
         (X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff")),
         (X_FRAME_OPTIONS, HeaderValue::from_static("SAMEORIGIN")),
@@ -100,7 +353,11 @@ async fn security_headers_layer(req: Request, next: Next) -> impl IntoResponse {
         (X_XSS_PROTECTION, HeaderValue::from_static("1; mode=block")),
     ];
 
-    (SECURITY_HEADERS, next.run(req).await)
+    (
+        SECURITY_HEADERS,
+        [(CONTENT_SECURITY_POLICY, csp)],
+        next.run(req).await,
+    )
 }
 
 async fn handle_service_errors(
@@ -114,20 +371,20 @@ async fn handle_service_errors(
     match response.status() {
         StatusCode::PAYLOAD_TOO_LARGE => (
             StatusCode::PAYLOAD_TOO_LARGE,
-            html::Error {
+            templates::Render(html::Error {
                 page,
                 theme,
                 description: String::from("payload exceeded limit"),
-            },
+            }),
         )
             .into_response(),
         StatusCode::UNSUPPORTED_MEDIA_TYPE => (
             StatusCode::UNSUPPORTED_MEDIA_TYPE,
-            html::Error {
+            templates::Render(html::Error {
                 page,
                 theme,
                 description: String::from("unsupported media type"),
-            },
+            }),
         )
             .into_response(),
         _ => response,
@@ -164,16 +421,24 @@ async fn favicon(State(page): State<Page>) -> impl IntoResponse {
     page.assets.favicon.clone()
 }
 
-async fn style_css(State(page): State<Page>) -> impl IntoResponse {
-    page.assets.css.style.clone()
+async fn favicon_ico(State(page): State<Page>) -> impl IntoResponse {
+    page.assets.favicon_ico.clone()
+}
+
+async fn robots_txt(State(page): State<Page>) -> impl IntoResponse {
+    page.assets.robots_txt.clone()
 }
 
-async fn dark_css(State(page): State<Page>) -> impl IntoResponse {
-    page.assets.css.dark.clone()
+async fn service_worker(State(page): State<Page>) -> impl IntoResponse {
+    page.assets.service_worker.clone()
 }
 
-async fn light_css(State(page): State<Page>) -> impl IntoResponse {
-    page.assets.css.light.clone()
+async fn style_css(State(page): State<Page>) -> impl IntoResponse {
+    page.assets.css.style.clone()
+}
+
+async fn noscript_css(State(page): State<Page>) -> impl IntoResponse {
+    page.assets.css.noscript.clone()
 }
 
 async fn base_js(State(page): State<Page>) -> impl IntoResponse {
@@ -188,44 +453,210 @@ async fn paste_js(State(page): State<Page>) -> impl IntoResponse {
     page.assets.paste_js.clone()
 }
 
+async fn pow_js(State(page): State<Page>) -> impl IntoResponse {
+    page.assets.pow_js.clone()
+}
+
+async fn zk_js(State(page): State<Page>) -> impl IntoResponse {
+    page.assets.zk_js.clone()
+}
+
+/// Add routes of accounts, their settings and the admin dashboard to `app`.
+fn account_routes(app: Router<AppState>) -> Router<AppState> {
+    app.route("/login", get(account::login_form).post(account::login))
+        .route(
+            "/register",
+            get(account::register_form).post(account::register),
+        )
+        .route("/login/totp", get(account::totp_form).post(account::totp))
+        .route("/logout", post(account::logout))
+        .route("/pastes", get(account::pastes))
+        .route("/snippet/:id", get(handlers::snippet::get))
+        .route("/usage", get(account::usage))
+        .route("/settings", get(settings::get))
+        .route(
+            "/settings/totp",
+            get(settings::totp_setup).post(settings::enable_totp),
+        )
+        .route("/settings/totp/disable", post(settings::disable_totp))
+        .route("/settings/tokens", post(settings::create_token))
+        .route("/settings/tokens/delete/:id", post(settings::delete_token))
+        .route("/admin", get(admin::get))
+        .route("/admin/delete/:id", post(admin::delete))
+        .route("/admin/pin/:id", post(admin::pin))
+        .route("/admin/unpin/:id", post(admin::unpin))
+        .route("/admin/release/:id", post(admin::release))
+        .route("/admin/purge", post(admin::purge))
+        .route("/admin/audit", get(admin::audit_log))
+        .route("/admin/stats", get(admin::stats))
+        .route("/admin/log", post(admin::set_log_filter))
+        .route("/admin/invites", post(admin::create_invite))
+        .route("/admin/invites/delete/:code", post(admin::delete_invite))
+}
+
+#[expect(clippy::too_many_lines)]
 async fn serve(
     listener: TcpListener,
     state: AppState,
     timeout: Duration,
     max_body_size: usize,
+    shutdown: impl Future<Output = ()> + Send + 'static,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let app = Router::new()
+    let mut app = Router::new();
+
+    for css in state.page.assets.css.themes.values() {
+        for asset in [&css.light, &css.dark] {
+            let asset = asset.clone();
+            app = app.route(&asset.route.clone(), get(move || async move { asset }));
+        }
+    }
+
+    for asset in state
+        .page
+        .assets
+        .custom()
+        .chain(&state.page.assets.touch_icon)
+    {
+        let asset = asset.clone();
+        app = app.route(&asset.route.clone(), get(move || async move { asset }));
+    }
+
+    #[cfg(feature = "png")]
+    {
+        app = app.route("/:id/image.png", get(image::png));
+    }
+
+    if state.page.accounts {
+        app = account_routes(app);
+    }
+
+    #[cfg(feature = "oidc")]
+    if state.oidc.is_some() {
+        app = app
+            .route("/login/oidc", get(handlers::oidc::login))
+            .route("/login/oidc/callback", get(handlers::oidc::callback));
+    }
+
+    if state.pow.is_some() {
+        app = app.route("/pow", get(handlers::pow::get));
+    }
+
+    if state.metrics == Some(metrics::Endpoint::Main) {
+        app = app.route("/metrics", get(handlers::metrics::get));
+    }
+
+    let csp = content_security_policy(&state.page)?;
+    let readiness = state.readiness.clone();
+    let compression = state.compression.clone();
+
+    let verifier = csrf::Verifier {
+        key: state.key.clone(),
+        page: state.page.clone(),
+    };
+
+    let app = app
         .route(state.page.assets.favicon.route(), get(favicon))
+        .route(state.page.assets.favicon_ico.route(), get(favicon_ico))
+        .route(state.page.assets.robots_txt.route(), get(robots_txt))
         .route(state.page.assets.css.style.route(), get(style_css))
-        .route(state.page.assets.css.dark.route(), get(dark_css))
-        .route(state.page.assets.css.light.route(), get(light_css))
+        .route(state.page.assets.css.noscript.route(), get(noscript_css))
         .route(state.page.assets.base_js.route(), get(base_js))
         .route(state.page.assets.index_js.route(), get(index_js))
         .route(state.page.assets.paste_js.route(), get(paste_js))
+        .route(state.page.assets.pow_js.route(), get(pow_js))
+        .route(state.page.assets.zk_js.route(), get(zk_js))
+        .route(
+            state.page.assets.service_worker.route(),
+            get(service_worker),
+        )
+        .route("/manifest.webmanifest", get(manifest::get))
+        .route("/sitemap.xml", get(handlers::sitemap::index))
+        .route("/sitemap/:page", get(handlers::sitemap::page))
         .route("/", get(html::index::get).post(insert::api::post))
         .route("/new", post(insert::form::post))
+        .route("/preview", post(insert::preview::post))
+        .route("/terms", get(html::document::terms))
+        .route("/about", get(html::document::about))
+        .route("/stats/languages", get(handlers::stats::languages))
+        .route(csrf::SHARE, post(html::index::share))
         .route("/qr/:id", get(html::qr::get))
         .route("/burn/:id", get(html::burn::get))
+        .route("/zk", get(html::zk::get))
+        .route("/zk/:id", get(html::zk::view))
         .route("/theme", get(theme::get))
+        .route("/api/time", get(handlers::time::get))
+        .route("/healthz", get(handlers::health::healthz))
+        .route("/readyz", get(handlers::health::readyz))
         .route(
             "/:id",
             get(html::paste::get)
                 .post(html::paste::get)
                 .delete(delete::api::delete),
         )
+        .route("/:id/image.svg", get(image::svg))
+        .route("/:id/pdf", get(pdf::get))
+        .route("/:id/print", get(html::print::get).post(html::print::get))
         .route("/dl/:id", get(download::get))
         .route("/raw/:id", get(raw::get))
-        .route("/delete/:id", get(delete::form::delete))
+        .route("/delete/:id", post(delete::form::delete))
+        .route("/edit/:id", get(edit::get).post(edit::post))
+        .route("/lang/:id", post(language::post))
         .layer(
             ServiceBuilder::new()
                 .layer(DefaultBodyLimit::max(max_body_size))
-                .layer(CompressionLayer::new())
-                .layer(TraceLayer::new_for_http())
+                .layer(CompressionLayer::new().compress_when(compression))
+                .layer(
+                    TraceLayer::new_for_http().make_span_with(|request: &Request| {
+                        tracing::debug_span!(
+                            "request",
+                            request_id = %hex::encode(rand::random::<[u8; 8]>()),
+                            method = %request.method(),
+                            path = %access_log::redact(request.uri().path()),
+                            version = ?request.version(),
+                        )
+                    }),
+                )
+                .layer(from_fn_with_state(state.clone(), access_log::record))
                 .layer(TimeoutLayer::new(timeout))
+                .layer(from_fn_with_state(timeout, deadline::attach))
+                .layer(from_fn_with_state(state.clone(), i18n::negotiate))
+                .layer(from_fn_with_state(state.clone(), metrics::track))
                 .layer(from_fn_with_state(state.clone(), handle_service_errors))
-                .layer(from_fn(security_headers_layer)),
-        )
-        .with_state(state);
+                .layer(from_fn_with_state(csp, security_headers_layer))
+                .layer(from_fn_with_state(state.clone(), cache_control::apply))
+                .layer(from_fn_with_state(state.clone(), minify::strip))
+                .layer(from_fn_with_state(state.clone(), access::filter))
+                .layer(from_fn_with_state(state.clone(), keys::resign))
+                .layer(from_fn_with_state(verifier, csrf::verify)),
+        );
+
+    // Outermost so that server errors produced by any middleware are reported as well.
+    #[cfg(feature = "sentry")]
+    let app = app.layer(from_fn_with_state(state.clone(), sentry::report));
+
+    let app = app.with_state(state);
+
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        shutdown.await;
+        readiness.drain().await;
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Serve only `/metrics` on `listener`, apart from the main routes.
+async fn serve_metrics(
+    listener: TcpListener,
+    registry: metrics::Registry,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let app = Router::new()
+        .route("/metrics", get(handlers::metrics::get))
+        .with_state(registry);
 
     axum::serve(listener, app)
         .with_graceful_shutdown(shutdown_signal())
@@ -234,47 +665,625 @@ async fn serve(
     Ok(())
 }
 
-async fn start() -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::fmt::init();
+/// Install logging and, if configured, the export of traces. Returns the filter of the log
+/// output to change it at runtime.
+fn tracing() -> Result<logging::Filter, Box<dyn std::error::Error>> {
+    use tracing_subscriber::Layer;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    let otlp = env::otlp()?;
+    let (filter, reload) = logging::Filter::new(env::log_filter()?);
+
+    let output = match env::log_format()? {
+        logging::Format::Text => tracing_subscriber::fmt::layer().with_filter(reload).boxed(),
+        logging::Format::Json => tracing_subscriber::fmt::layer()
+            .fmt_fields(logging::JsonFields)
+            .event_format(logging::Json)
+            .with_filter(reload)
+            .boxed(),
+    };
+
+    let registry = tracing_subscriber::registry().with(output);
+
+    // Spans of the HTTP client would otherwise be exported themselves.
+    #[cfg(feature = "otlp")]
+    let registry = registry.with(otlp.clone().map(|(endpoint, ratio)| {
+        use tracing_subscriber::filter::{LevelFilter, Targets};
+
+        otlp::Layer::new(endpoint, ratio).with_filter(
+            Targets::new()
+                .with_target(env!("CARGO_CRATE_NAME"), LevelFilter::DEBUG)
+                .with_target("tower_http", LevelFilter::DEBUG),
+        )
+    }));
+
+    registry.try_init()?;
+
+    if let Some((endpoint, ratio)) = otlp {
+        tracing::debug!("exporting {ratio} of all traces to {endpoint}");
+    }
+
+    Ok(filter)
+}
+
+/// Highlighter as configured in the environment.
+fn highlighter() -> Result<highlight::Highlighter, Box<dyn std::error::Error>> {
+    let mut highlighter = highlight::Highlighter::default()
+        .with_engine(env::highlighter()?)?
+        .with_max_bytes(env::max_highlight_bytes()?)
+        .with_deadline(env::highlight_timeout()?)
+        .with_threads(env::highlight_threads()?)?;
+
+    if let Some(dir) = env::syntax_dir()? {
+        highlighter = highlighter.with_syntax_dir(&dir, env::dump_dir()?.as_deref())?;
+    }
+
+    if let Some(languages) = env::languages() {
+        tracing::debug!("restricting highlighting to {languages:?}");
+        highlighter = highlighter.with_languages(&languages)?;
+    }
+
+    Ok(highlighter.with_aliases(env::language_aliases()?)?)
+}
 
+/// Cache of highlighted pastes as configured in the environment.
+fn cache(highlighter: &Highlighter) -> Result<Cache, Box<dyn std::error::Error>> {
     let cache_size = env::cache_size()?;
+    let mut cache = Cache::new(cache_size).with_prerender(env::prerender()?);
+
+    tracing::debug!("caching {cache_size} paste highlights");
+
+    if let Some((dir, max_bytes)) = env::disk_cache()? {
+        tracing::debug!("persisting up to {max_bytes} bytes of highlights in {dir:?}");
+        cache = cache.with_disk(DiskCache::new(dir, max_bytes, highlighter.version())?);
+    }
+
+    Ok(cache)
+}
+
+/// Limiter of anonymous pastes as configured in the environment.
+fn limiter() -> Result<limits::Limiter, env::Error> {
+    let limit = env::daily_limit()?;
+    let header = env::client_ip_header()?;
+
+    if limit.pastes.is_some() || limit.bytes.is_some() {
+        tracing::debug!("limiting anonymous addresses per day to {limit:?}");
+    }
+
+    if let Some(header) = &header {
+        tracing::debug!("taking client addresses from the {header} header");
+    }
+
+    Ok(limits::Limiter::new(limit, header))
+}
+
+#[expect(clippy::too_many_lines)]
+async fn start() -> Result<(), Box<dyn std::error::Error>> {
+    let log_filter = tracing()?;
+    let sentry = env::sentry()?;
+
+    if let Some((dsn, environment)) = &sentry {
+        let host = dsn.host_str().unwrap_or_default();
+        tracing::debug!("reporting errors to Sentry at {host} in environment {environment:?}");
+    }
+
     let method = env::database_method()?;
-    let key = env::signing_key()?;
+    let (key, previous_keys) = env::signing_keys()?;
     let addr = env::addr()?;
+    let metrics = env::metrics()?;
+    let tcp_addr = env::tcp_addr()?;
+    #[cfg_attr(
+        not(any(feature = "gemini", feature = "gopher")),
+        expect(
+            unused_variables,
+            reason = "only used with the `gemini` or `gopher` feature"
+        )
+    )]
+    let gemini = env::gemini()?;
+    #[cfg_attr(
+        not(feature = "gopher"),
+        expect(unused_variables, reason = "only used with the `gopher` feature")
+    )]
+    let gopher_addr = env::gopher_addr()?;
+    #[cfg_attr(
+        not(feature = "gopher"),
+        expect(unused_variables, reason = "only used with the `gopher` feature")
+    )]
+    let gopher_listing = env::gopher_listing()?;
+    #[cfg_attr(
+        not(any(feature = "smtp", feature = "gemini", feature = "gopher")),
+        expect(
+            unused_variables,
+            reason = "only used with the `smtp`, `gemini` or `gopher` feature"
+        )
+    )]
+    let smtp = env::smtp()?;
+    #[cfg(any(feature = "gemini", feature = "gopher"))]
+    let smtp_enabled = smtp.is_some();
     let max_body_size = env::max_body_size()?;
     let base_url = env::base_url()?;
+    let asset_url = env::asset_url()?;
     let timeout = env::http_timeout()?;
+    let drain = env::shutdown_drain()?;
+    let compression = env::compression()?;
+    let cache_control = env::cache_control()?;
+    let minify = env::minify()?;
+    let access_log = match env::access_log()? {
+        Some((target, format)) => {
+            tracing::debug!("writing {format:?} access log to {target:?}");
+            Some(access_log::AccessLog::open(&target, format)?)
+        }
+        None => None,
+    };
     let expirations = env::expiration_set()?;
-    let theme = env::theme()?;
+    let custom_themes = match env::theme_dir()? {
+        Some(dir) => assets::load_themes(&dir, env::dump_dir()?.as_deref())?,
+        None => Vec::new(),
+    };
+    let theme = env::theme(&custom_themes)?;
+    let locales = match env::locale_dir()? {
+        Some(dir) => i18n::Catalog::load(&dir)?,
+        None => i18n::Catalog::default(),
+    };
+    tracing::debug!("serving {} locales", locales.len());
     let title = env::title();
+    let accounts = env::accounts()?;
+    let private = env::private()?;
+    let quota = env::quota()?;
+    let limiter = limiter()?;
+    let access = env::access()?;
+    let admins = env::admins();
+    let admin_totp = env::admin_totp()?;
+    let ldap = env::ldap()?;
+    let oidc = env::oidc()?;
+    let captcha = env::captcha()?;
+    let pow_difficulty = env::pow_difficulty()?;
+    let min_fill_time = env::min_fill_time()?;
+    let argon2 = env::argon2()?;
+    let secrets = env::secrets()?;
+    let clamd = env::clamd()?;
+    let blocklist = match env::blocklist()? {
+        Some(path) => blocklist::Blocklist::load(&path)?,
+        None => blocklist::Blocklist::default(),
+    };
+    let notifications = env::notify()?;
 
-    let cache = Cache::new(cache_size);
-    let db = Database::new(method)?;
+    for target in &notifications {
+        tracing::debug!("notifying {} of {}", target.service, target.events);
+    }
 
-    tracing::debug!("serving on {addr}");
-    tracing::debug!("caching {cache_size} paste highlights");
+    let mut db = Database::new(method)?.with_argon2(argon2);
+
+    if let Some((size, ttl)) = env::negative_cache()? {
+        tracing::debug!("remembering up to {size} missing pastes for {ttl:?}");
+        db = db.with_negative_cache(size, ttl);
+    }
+
+    let mut inherited = upgrade::Inherited::from_env();
+    let listener = inherited.tcp(0, addr).await?;
+    #[cfg(unix)]
+    let mut handover = upgrade::Handover::new(&listener)?;
+
+    tracing::debug!("serving on {}", listener.local_addr()?);
+
+    if !previous_keys.is_empty() {
+        tracing::debug!(
+            "accepting cookies signed with {} previous keys",
+            previous_keys.len()
+        );
+    }
+    tracing::debug!("deriving keys of encrypted pastes with {argon2:?}");
     tracing::debug!("restricting maximum body size to {max_body_size} bytes");
     tracing::debug!("enforcing a http timeout of {timeout:#?}");
+    tracing::debug!("compressing responses with {compression:?}");
+
+    if !cache_control.is_empty() {
+        tracing::debug!("overriding cache headers with {cache_control:?}");
+    }
+
+    if blocklist.len() > 0 {
+        tracing::debug!(
+            "checking new pastes against {} blocklist rules",
+            blocklist.len()
+        );
+    }
+
+    let mut page = page::Page::new(title, base_url, &theme, &custom_themes, expirations);
+
+    // External authentication and private mode imply accounts, but without local registration
+    // unless asked for. A directory replaces local accounts entirely.
+    let accounts = if ldap.is_some() {
+        Some(false)
+    } else {
+        accounts.or_else(|| (oidc.is_some() || private).then_some(false))
+    };
+
+    if let Some(registration) = accounts {
+        tracing::debug!("enabling accounts, registration open: {registration}");
+        page = page.with_accounts(registration);
+
+        // Invites open closed registration to selected people, unless accounts come from a
+        // directory.
+        if !registration && ldap.is_none() {
+            page = page.with_invites();
+        }
+    }
+
+    if quota != accounts::Quota::default() {
+        tracing::debug!("limiting accounts to {quota:?}");
+        page = page.with_quota(quota);
+    }
+
+    if !admins.is_empty() {
+        tracing::debug!("granting admin rights to {admins:?}");
+        page = page.with_admins(admins);
+    }
+
+    if admin_totp {
+        tracing::debug!("requiring two-factor authentication for admin rights");
+        page = page.with_admin_totp();
+    }
+
+    if private {
+        tracing::debug!("restricting paste creation to logged in users");
+        page = page.with_private();
+    }
+
+    if ldap
+        .as_ref()
+        .is_some_and(ldap::Directory::restricts_creation)
+    {
+        tracing::debug!("restricting paste creation to directory users matching the filter");
+        page = page.with_restricted_creation();
+    }
+
+    if oidc.is_some() {
+        page = page.with_oidc();
+    }
+
+    if let Some((widget, _, _)) = &captcha {
+        page = page.with_captcha(widget.clone());
+    }
+
+    if let Some(policy) = secrets {
+        tracing::debug!("applying {policy:?} to pastes containing credentials");
+        page = page.with_secrets(policy);
+    }
+
+    if let Some(difficulty) = pow_difficulty {
+        tracing::debug!("asking anonymous users to find {difficulty} bits of proof of work");
+        page = page.with_pow();
+    }
+
+    page = page
+        .with_footer(env::footer_links()?)
+        .with_max_body_size(max_body_size)
+        .with_shortcuts(env::shortcuts()?)
+        .with_timezone(env::timezone()?)
+        .with_tab_width(env::tab_width()?);
+
+    if env::language_stats()? {
+        tracing::debug!("publishing language statistics at /stats/languages");
+        page = page.with_language_stats();
+    }
+
+    if env::noindex()? {
+        tracing::debug!("asking search engines not to index pastes");
+        page = page.with_noindex();
+    }
+
+    if env::sitemap()? {
+        tracing::debug!("listing public pastes at /sitemap.xml");
+        page = page.with_sitemap();
+    }
+
+    if let Some(path) = env::terms()? {
+        tracing::debug!(
+            "asking anonymous users to accept the terms in {}",
+            path.display()
+        );
+        let source = std::fs::read_to_string(&path)
+            .map_err(|err| assets::CustomError::Io(path.display().to_string(), err))?;
+        page = page.with_terms(terms::Terms::new(&source));
+    }
+
+    if let Some(path) = env::about()? {
+        tracing::debug!("serving {} as about page", path.display());
+        let source = std::fs::read_to_string(&path)
+            .map_err(|err| assets::CustomError::Io(path.display().to_string(), err))?;
+        page = page.with_about(&source);
+    }
+
+    if let Some(path) = env::robots_txt()? {
+        tracing::debug!("serving {} as robots.txt", path.display());
+        let rules = std::fs::read(&path)
+            .map_err(|err| assets::CustomError::Io(path.display().to_string(), err))?;
+        page = page.with_robots_txt(rules);
+    }
+
+    if let Some(path) = env::favicon()? {
+        tracing::debug!("using favicon {}", path.display());
+        let kinds = [assets::Kind::Png, assets::Kind::Svg, assets::Kind::Ico];
+        page = page.with_favicon(assets::Asset::load("favicon", &path, &kinds)?);
+    }
+
+    if let Some(path) = env::touch_icon()? {
+        tracing::debug!("using touch icon {}", path.display());
+        page = page.with_touch_icon(assets::Asset::load(
+            "touch-icon",
+            &path,
+            &[assets::Kind::Png],
+        )?);
+    }
+
+    if let Some(source) = env::custom_css()? {
+        tracing::debug!("loading custom stylesheet {source:?}");
+        page = page.with_custom_css(assets::Custom::load(
+            "custom",
+            source,
+            &[assets::Kind::Css],
+        )?);
+    }
+
+    if let Some(source) = env::logo()? {
+        tracing::debug!("showing logo {source:?}");
+        page = page.with_logo(assets::Custom::load("logo", source, &assets::IMAGES)?);
+    }
+
+    if let Some(url) = &asset_url {
+        tracing::debug!("referencing static assets below {url}");
+        page = page.with_asset_url(url);
+    }
+
+    if let Some(dir) = env::template_dir()? {
+        tracing::debug!("overriding templates with the ones in {}", dir.display());
+        page = page.with_templates(templates::Overrides::load(&dir)?);
+    }
+
+    let page = Arc::new(page);
+    let highlighter = Arc::new(highlighter()?);
+    let cache = cache(&highlighter)?;
+
+    let pow = pow_difficulty.map(|difficulty| pow::Pow::new(key.clone(), difficulty));
+    let fill_time = min_fill_time.map(|min| {
+        tracing::debug!("rejecting anonymous pastes submitted within {min:?} of loading the form");
+        spam::FillTime::new(key.clone(), min)
+    });
+
+    if !access.is_empty() {
+        tracing::debug!("restricting access to {access:?}");
+    }
 
-    let page = Arc::new(page::Page::new(title, base_url, theme, expirations));
-    let highlighter = Arc::new(highlight::Highlighter::default());
     let state = AppState {
         db,
+        access: Arc::new(access),
         cache,
         key,
+        previous_keys: previous_keys.into(),
         page,
         highlighter,
+        ldap: ldap.map(Arc::new),
+        #[cfg(feature = "oidc")]
+        oidc: oidc.map(|(issuer, client_id, client_secret)| {
+            tracing::debug!("logging in via single sign-on at {issuer}");
+            Arc::new(oidc::Provider::new(issuer, client_id, client_secret))
+        }),
+        #[cfg(feature = "captcha")]
+        captcha: captcha.map(|(widget, secret, bypass)| {
+            tracing::debug!(
+                "asking anonymous users to solve a {:?} captcha",
+                widget.provider
+            );
+            Arc::new(captcha::Verifier::new(widget.provider, secret, bypass))
+        }),
+        pow,
+        fill_time,
+        blocklist,
+        clamav: clamd.map(|address| {
+            tracing::debug!("scanning new pastes with clamd at {address:?}");
+            clamav::Scanner::new(address)
+        }),
+        #[cfg(feature = "notify")]
+        notifier: (!notifications.is_empty()).then(|| notify::Notifier::new(notifications)),
+        #[cfg(not(feature = "notify"))]
+        notifier: None,
+        limiter,
+        requests: metrics::Requests::default(),
+        metrics,
+        log_filter: Some(log_filter),
+        readiness: health::Readiness::new(drain),
+        access_log,
+        compression,
+        cache_control: Arc::new(cache_control),
+        minify: minify::Enabled(minify),
+        locales,
+        language_stats: handlers::stats::Languages::default(),
+        sitemaps: handlers::sitemap::Sitemaps::default(),
+        #[cfg(feature = "sentry")]
+        sentry: sentry.map(|(dsn, environment)| {
+            let reporter = sentry::Reporter::new(&dsn, environment);
+            reporter.install_panic_hook();
+            reporter
+        }),
+    };
+
+    if let Some(metrics::Endpoint::Separate(addr)) = metrics {
+        tracing::debug!("serving metrics on {addr}");
+
+        let listener = inherited.tcp(1, addr).await?;
+        #[cfg(unix)]
+        handover.add(&listener)?;
+        let registry = metrics::Registry::from_ref(&state);
+
+        tokio::spawn(async move {
+            if let Err(err) = serve_metrics(listener, registry).await {
+                tracing::error!("failed to serve metrics: {err}");
+            }
+        });
+    } else if metrics.is_some() {
+        tracing::debug!("serving metrics at /metrics");
+    }
+
+    if let Some(addr) = tcp_addr {
+        tracing::debug!("storing pastes sent to {addr}");
+
+        // Sockets are passed on in a fixed order, after the one for metrics if there is one.
+        let index = 1 + usize::from(matches!(metrics, Some(metrics::Endpoint::Separate(_))));
+        let listener = inherited.tcp(index, addr).await?;
+        #[cfg(unix)]
+        handover.add(&listener)?;
+
+        tokio::spawn(handlers::insert::tcp::serve(
+            listener,
+            state.clone(),
+            max_body_size,
+            shutdown_signal(),
+        ));
+    }
+
+    #[cfg(feature = "smtp")]
+    if let Some((addr, senders, relay)) = smtp {
+        tracing::debug!("storing mail sent to {addr} from {senders:?}");
+
+        if let Some(relay) = &relay {
+            tracing::debug!("replying to mail through {relay}");
+        }
+
+        // After the socket for plain TCP pastes if there is one.
+        let index = 1
+            + usize::from(matches!(metrics, Some(metrics::Endpoint::Separate(_))))
+            + usize::from(tcp_addr.is_some());
+        let listener = inherited.tcp(index, addr).await?;
+        #[cfg(unix)]
+        handover.add(&listener)?;
+
+        let config = handlers::insert::smtp::Config {
+            senders: Arc::new(senders),
+            relay: relay.map(Arc::from),
+        };
+
+        tokio::spawn(handlers::insert::smtp::serve(
+            listener,
+            state.clone(),
+            config,
+            max_body_size,
+            shutdown_signal(),
+        ));
+    }
+
+    // Sockets of the read-only frontends come last, the Gemini one before the Gopher one.
+    #[cfg(any(feature = "gemini", feature = "gopher"))]
+    let index = 1
+        + usize::from(matches!(metrics, Some(metrics::Endpoint::Separate(_))))
+        + usize::from(tcp_addr.is_some())
+        + usize::from(smtp_enabled);
+    #[cfg(feature = "gopher")]
+    let gopher_index = index + usize::from(gemini.is_some());
+
+    #[cfg(feature = "gemini")]
+    if let Some((addr, cert, key)) = gemini {
+        tracing::debug!("serving pastes over Gemini on {addr}");
+
+        let acceptor = protocols::gemini::acceptor(&cert, &key)?;
+        let listener = inherited.tcp(index, addr).await?;
+        #[cfg(unix)]
+        handover.add(&listener)?;
+
+        tokio::spawn(protocols::gemini::serve(
+            listener,
+            acceptor,
+            state.clone(),
+            shutdown_signal(),
+        ));
+    }
+
+    #[cfg(feature = "gopher")]
+    if let Some(addr) = gopher_addr {
+        tracing::debug!(
+            "serving pastes over Gopher on {addr}, listing recent ones: {gopher_listing}"
+        );
+
+        let listener = inherited.tcp(gopher_index, addr).await?;
+        #[cfg(unix)]
+        handover.add(&listener)?;
+
+        tokio::spawn(protocols::gopher::serve(
+            listener,
+            state.clone(),
+            gopher_listing,
+            shutdown_signal(),
+        ));
+    }
+
+    let warm = env::cache_warm()?;
+
+    if warm > 0 {
+        tracing::debug!("warming the cache with the {warm} most viewed pastes");
+
+        tokio::spawn(cache::warm(
+            state.cache.clone(),
+            state.db.clone(),
+            state.highlighter.clone(),
+            warm,
+        ));
+    }
+
+    #[cfg(unix)]
+    let upgrade = upgrade::requested(handover);
+
+    #[cfg(not(unix))]
+    let upgrade = std::future::pending::<()>();
+
+    let shutdown = async move {
+        tokio::select! {
+            () = shutdown_signal() => upgrade::notify("STOPPING=1"),
+            () = upgrade => {},
+        }
     };
 
-    let listener = TcpListener::bind(&addr).await?;
-    serve(listener, state, timeout, max_body_size).await?;
+    tokio::spawn(state.db.clone().flush_views_every(db::VIEWS_FLUSH_INTERVAL));
+
+    let db = state.db.clone();
+    upgrade::ready();
+    serve(listener, state, timeout, max_body_size, shutdown).await?;
+
+    if let Err(err) = db.flush_views().await {
+        tracing::warn!("failed to write view counts: {err}");
+    }
 
     Ok(())
 }
 
+/// Run the administrative `command` on the configured database.
+async fn administer(command: cli::Admin) -> Result<(), Box<dyn std::error::Error>> {
+    let method = env::database_method()?;
+
+    if matches!(method, db::Open::Memory) {
+        return Err(cli::Error::InMemory.into());
+    }
+
+    let db = Database::new(method)?;
+
+    cli::run(&db, command, &mut std::io::stdout()).await
+}
+
 #[tokio::main]
 async fn main() -> ExitCode {
-    match start().await {
+    let result = match cli::parse(std::env::args().skip(1)) {
+        Ok(cli::Command::Serve) => start().await,
+        Ok(cli::Command::Admin(command)) => administer(command).await,
+        Ok(cli::Command::Help) => {
+            eprintln!("{}", cli::USAGE);
+            Ok(())
+        }
+        Err(err) => {
+            eprintln!("Error: {err}\n\n{}", cli::USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match result {
         Ok(()) => ExitCode::SUCCESS,
         Err(err) => {
             eprintln!("Error: {err}");
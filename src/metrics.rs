@@ -0,0 +1,435 @@
+use crate::cache::Cache;
+use crate::db::Database;
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::Method;
+use axum::http::header::CONTENT_LENGTH;
+use axum::middleware::Next;
+use axum::response::Response;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/// Upper bounds in seconds of the request latency histogram buckets.
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Upper bounds in bytes of the upload size histogram buckets.
+const SIZE_BUCKETS: [f64; 8] = [
+    1024.0,
+    4096.0,
+    16384.0,
+    65536.0,
+    262_144.0,
+    1_048_576.0,
+    4_194_304.0,
+    16_777_216.0,
+];
+
+/// Listener serving the `/metrics` endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Endpoint {
+    /// Next to all other routes.
+    Main,
+    /// On its own address, so it can be kept out of public reach.
+    Separate(SocketAddr),
+}
+
+/// Monotonically increasing counter shared by all clones.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Counter(Arc<AtomicU64>);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Counters of paste lifecycle events.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Pastes {
+    pub created: Counter,
+    pub deleted: Counter,
+    pub expired: Counter,
+    pub burned: Counter,
+}
+
+/// Distribution of observed values, e.g. latencies of requests to a single route.
+#[derive(Debug)]
+struct Histogram {
+    bounds: &'static [f64],
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: vec![0; bounds.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(self.bounds) {
+            if value <= *bound {
+                *bucket += 1;
+            }
+        }
+
+        self.sum += value;
+        self.count += 1;
+    }
+
+    /// Write the buckets, sum and count of metric `name` with extra `labels`.
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        for (count, bound) in self.buckets.iter().zip(self.bounds) {
+            let _ = writeln!(out, r#"{name}_bucket{{{labels},le="{bound}"}} {count}"#);
+        }
+
+        let _ = writeln!(
+            out,
+            "{name}_bucket{{{labels},le=\"+Inf\"}} {count}\n\
+             {name}_sum{{{labels}}} {sum}\n\
+             {name}_count{{{labels}}} {count}",
+            count = self.count,
+            sum = self.sum,
+        );
+    }
+}
+
+/// Coarse group of a route, so that e.g. viewing and uploading pastes can be told apart without
+/// summing up the series of every single route.
+fn group(method: &Method, route: &str) -> &'static str {
+    match (method, route) {
+        (&Method::POST, "/" | "/new") => "upload",
+        (&Method::DELETE, "/:id") | (_, "/delete/:id") => "delete",
+        (_, "/edit/:id" | "/lang/:id") => "edit",
+        (
+            _,
+            "/:id" | "/raw/:id" | "/dl/:id" | "/burn/:id" | "/qr/:id" | "/zk/:id" | "/:id/pdf"
+            | "/:id/image.svg" | "/:id/image.png",
+        ) => "view",
+        (_, route) if route.starts_with("/admin") => "admin",
+        (
+            _,
+            "/login"
+            | "/login/totp"
+            | "/login/oidc"
+            | "/login/oidc/callback"
+            | "/register"
+            | "/logout"
+            | "/pastes"
+            | "/usage",
+        ) => "account",
+        (_, route) if route.starts_with("/settings") => "account",
+        _ => "other",
+    }
+}
+
+/// Label of `method`, with non-standard methods gathered under `other` to keep the number of
+/// series bounded.
+fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::HEAD => "HEAD",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::DELETE => "DELETE",
+        Method::CONNECT => "CONNECT",
+        Method::OPTIONS => "OPTIONS",
+        Method::TRACE => "TRACE",
+        Method::PATCH => "PATCH",
+        _ => "other",
+    }
+}
+
+#[derive(Debug, Default)]
+struct Series {
+    /// Number of requests by method, route and status code.
+    counts: BTreeMap<(String, String, u16), u64>,
+    /// Latencies by method and route.
+    latencies: BTreeMap<(String, String), Histogram>,
+    /// Latencies by route group.
+    groups: BTreeMap<&'static str, Histogram>,
+    /// Request body sizes of uploads by route.
+    uploads: BTreeMap<String, Histogram>,
+}
+
+/// Request counts and latencies per route.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Requests(Arc<Mutex<Series>>);
+
+impl Requests {
+    fn observe(&self, method: &Method, route: &str, status: u16, seconds: f64, size: Option<u64>) {
+        let group = group(method, route);
+        let method = method_label(method);
+        let mut series = self.0.lock();
+
+        *series
+            .counts
+            .entry((method.to_owned(), route.to_owned(), status))
+            .or_default() += 1;
+
+        series
+            .latencies
+            .entry((method.to_owned(), route.to_owned()))
+            .or_insert_with(|| Histogram::new(&BUCKETS))
+            .observe(seconds);
+
+        series
+            .groups
+            .entry(group)
+            .or_insert_with(|| Histogram::new(&BUCKETS))
+            .observe(seconds);
+
+        if let (Some(size), "upload") = (size, group) {
+            #[expect(clippy::cast_precision_loss)]
+            series
+                .uploads
+                .entry(route.to_owned())
+                .or_insert_with(|| Histogram::new(&SIZE_BUCKETS))
+                .observe(size as f64);
+        }
+    }
+}
+
+/// Everything exposed to Prometheus.
+#[derive(Clone)]
+pub(crate) struct Registry {
+    pub db: Database,
+    pub cache: Cache,
+    pub requests: Requests,
+}
+
+/// Count requests and measure their latency by the route they matched and its group. Requests not
+/// matching any route are gathered under `unmatched` to keep the number of series bounded. Upload
+/// sizes are taken from the `Content-Length` header, so chunked uploads are not part of them.
+pub(crate) async fn track(
+    State(requests): State<Requests>,
+    path: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let start = Instant::now();
+    let method = request.method().clone();
+    let route = path.as_ref().map_or("unmatched", MatchedPath::as_str);
+    let route = route.to_owned();
+    let size = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok()?.parse().ok());
+
+    let response = next.run(request).await;
+
+    requests.observe(
+        &method,
+        &route,
+        response.status().as_u16(),
+        start.elapsed().as_secs_f64(),
+        size,
+    );
+
+    response
+}
+
+/// Write a metric header with `help` and `kind` followed by a single unlabeled `value`.
+fn single(out: &mut String, name: &str, kind: &str, help: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(
+        out,
+        "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}"
+    );
+}
+
+impl Series {
+    /// Write request counts, latencies and upload sizes.
+    fn render(&self, out: &mut String) {
+        out.push_str("# HELP wastebin_http_requests_total Number of handled HTTP requests.\n");
+        out.push_str("# TYPE wastebin_http_requests_total counter\n");
+
+        for ((method, route, status), count) in &self.counts {
+            let _ = writeln!(
+                out,
+                r#"wastebin_http_requests_total{{method="{method}",route="{route}",status="{status}"}} {count}"#
+            );
+        }
+
+        out.push_str(
+            "# HELP wastebin_http_request_duration_seconds Latency of handled HTTP requests.\n",
+        );
+        out.push_str("# TYPE wastebin_http_request_duration_seconds histogram\n");
+
+        for ((method, route), histogram) in &self.latencies {
+            histogram.render(
+                out,
+                "wastebin_http_request_duration_seconds",
+                &format!(r#"method="{method}",route="{route}""#),
+            );
+        }
+
+        out.push_str(
+            "# HELP wastebin_http_route_group_duration_seconds Latency of handled HTTP requests by route group.\n",
+        );
+        out.push_str("# TYPE wastebin_http_route_group_duration_seconds histogram\n");
+
+        for (group, histogram) in &self.groups {
+            histogram.render(
+                out,
+                "wastebin_http_route_group_duration_seconds",
+                &format!(r#"group="{group}""#),
+            );
+        }
+
+        out.push_str("# HELP wastebin_upload_size_bytes Request body size of uploads.\n");
+        out.push_str("# TYPE wastebin_upload_size_bytes histogram\n");
+
+        for (route, histogram) in &self.uploads {
+            histogram.render(
+                out,
+                "wastebin_upload_size_bytes",
+                &format!(r#"route="{route}""#),
+            );
+        }
+    }
+}
+
+impl Registry {
+    /// Render all metrics in the Prometheus text exposition format.
+    pub async fn render(&self) -> Result<String, crate::Error> {
+        let stats = self.db.stats().await?;
+        let size = self.db.size().await?;
+        let mut out = String::new();
+
+        self.requests.0.lock().render(&mut out);
+
+        let pastes = self.db.counters();
+
+        for (name, help, counter) in [
+            ("created", "Number of created pastes.", &pastes.created),
+            ("deleted", "Number of deleted pastes.", &pastes.deleted),
+            (
+                "expired",
+                "Number of pastes removed after expiring.",
+                &pastes.expired,
+            ),
+            (
+                "burned",
+                "Number of pastes burned after reading.",
+                &pastes.burned,
+            ),
+        ] {
+            let name = format!("wastebin_pastes_{name}_total");
+            single(&mut out, &name, "counter", help, counter.get());
+        }
+
+        let (hits, misses) = self.cache.counters();
+        single(
+            &mut out,
+            "wastebin_cache_hits_total",
+            "counter",
+            "Number of highlighted pastes served from the cache.",
+            hits.get(),
+        );
+        single(
+            &mut out,
+            "wastebin_cache_misses_total",
+            "counter",
+            "Number of highlighted pastes missing from the cache.",
+            misses.get(),
+        );
+
+        single(
+            &mut out,
+            "wastebin_pastes",
+            "gauge",
+            "Number of stored pastes.",
+            stats.pastes,
+        );
+        single(
+            &mut out,
+            "wastebin_paste_bytes",
+            "gauge",
+            "Size of all stored pastes in bytes.",
+            stats.bytes,
+        );
+        single(
+            &mut out,
+            "wastebin_database_size_bytes",
+            "gauge",
+            "Size of the database in bytes.",
+            size,
+        );
+
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram() {
+        let mut histogram = Histogram::new(&BUCKETS);
+        histogram.observe(0.003);
+        histogram.observe(0.3);
+        histogram.observe(20.0);
+
+        assert_eq!(histogram.buckets[0], 1);
+        assert_eq!(histogram.buckets[5], 1);
+        assert_eq!(histogram.buckets[6], 2);
+        assert_eq!(histogram.buckets[10], 2);
+        assert_eq!(histogram.count, 3);
+    }
+
+    #[test]
+    fn groups() {
+        assert_eq!(group(&Method::POST, "/new"), "upload");
+        assert_eq!(group(&Method::POST, "/"), "upload");
+        assert_eq!(group(&Method::GET, "/"), "other");
+        assert_eq!(group(&Method::GET, "/:id"), "view");
+        assert_eq!(group(&Method::DELETE, "/:id"), "delete");
+        assert_eq!(group(&Method::GET, "/raw/:id"), "view");
+        assert_eq!(group(&Method::POST, "/admin/purge"), "admin");
+        assert_eq!(group(&Method::GET, "/settings/totp"), "account");
+        assert_eq!(group(&Method::GET, "unmatched"), "other");
+    }
+
+    #[test]
+    fn method_labels() {
+        let requests = Requests::default();
+        requests.observe(&Method::GET, "/", 200, 0.1, None);
+
+        for method in ["FOO", "BAR"] {
+            let method = Method::from_bytes(method.as_bytes()).unwrap();
+            requests.observe(&method, "/", 405, 0.1, None);
+        }
+
+        let series = requests.0.lock();
+        let methods = series
+            .counts
+            .keys()
+            .map(|(method, _, _)| method.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(methods, ["GET", "other"]);
+        assert_eq!(
+            series.counts[&(String::from("other"), String::from("/"), 405)],
+            2
+        );
+    }
+}
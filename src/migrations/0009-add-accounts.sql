@@ -0,0 +1,14 @@
+ALTER TABLE entries ADD COLUMN created TEXT;
+
+CREATE TABLE users(
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    password TEXT NOT NULL,
+    created TEXT NOT NULL
+);
+
+CREATE TABLE sessions(
+    token TEXT PRIMARY KEY,
+    uid INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    expires TEXT NOT NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE users ADD COLUMN can_create INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE users ADD COLUMN admin INTEGER NOT NULL DEFAULT 0;
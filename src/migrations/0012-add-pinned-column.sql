@@ -0,0 +1 @@
+ALTER TABLE entries ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;
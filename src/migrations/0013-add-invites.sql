@@ -0,0 +1,5 @@
+CREATE TABLE invites (
+    code TEXT PRIMARY KEY,
+    uses INTEGER NOT NULL,
+    created TEXT NOT NULL
+);
@@ -0,0 +1,9 @@
+CREATE TABLE api_tokens (
+    id INTEGER PRIMARY KEY,
+    uid INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    hash TEXT NOT NULL UNIQUE,
+    label TEXT NOT NULL,
+    scope TEXT NOT NULL,
+    created TEXT NOT NULL,
+    last_used TEXT
+);
@@ -0,0 +1,17 @@
+CREATE TABLE audit_log (
+    id INTEGER PRIMARY KEY,
+    created TEXT NOT NULL,
+    actor TEXT,
+    action TEXT NOT NULL,
+    target TEXT
+);
+
+CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit log is append-only');
+END;
+
+CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'audit log is append-only');
+END;
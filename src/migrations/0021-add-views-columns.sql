@@ -0,0 +1,2 @@
+ALTER TABLE entries ADD COLUMN views INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE entries ADD COLUMN viewed TEXT;
@@ -0,0 +1,5 @@
+-- Metadata of a paste without its data, so that reading it does not walk the overflow pages of
+-- large pastes.
+CREATE INDEX entries_lookup ON entries (id, nonce, burn_after_reading, expires, pinned, quarantine, secret, title, extension);
+
+CREATE INDEX entries_expires ON entries (expires) WHERE expires IS NOT NULL;
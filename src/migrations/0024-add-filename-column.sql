@@ -0,0 +1,5 @@
+ALTER TABLE entries ADD COLUMN filename TEXT;
+
+-- Downloads read the file name along with the other metadata.
+DROP INDEX entries_lookup;
+CREATE INDEX entries_lookup ON entries (id, nonce, burn_after_reading, expires, pinned, quarantine, secret, title, extension, filename);
@@ -0,0 +1,156 @@
+use axum::body::{Body, to_bytes};
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::http::header::{CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+/// Elements whose content is rendered with its whitespace intact.
+const PRESERVED: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+/// If HTML responses are minified, disabled to debug the generated markup.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Enabled(pub bool);
+
+/// Return the preserved element open after scanning `line`, starting inside `open`.
+fn open_after(line: &str, mut open: Option<&'static str>) -> Option<&'static str> {
+    let mut rest = line;
+
+    loop {
+        if let Some(tag) = open {
+            let Some(pos) = rest.find(&format!("</{tag}")) else {
+                return open;
+            };
+
+            rest = &rest[pos + tag.len() + 2..];
+            open = None;
+        } else {
+            let next = PRESERVED
+                .iter()
+                .filter_map(|tag| {
+                    rest.match_indices(&format!("<{tag}"))
+                        .find(|(pos, _)| {
+                            rest[pos + tag.len() + 1..]
+                                .starts_with(|c: char| c == '>' || c.is_ascii_whitespace())
+                        })
+                        .map(|(pos, _)| (pos, *tag))
+                })
+                .min_by_key(|(pos, _)| *pos);
+
+            let (pos, tag) = next?;
+
+            rest = &rest[pos + tag.len() + 1..];
+            open = Some(tag);
+        }
+    }
+}
+
+/// Strip indentation and blank lines from `html` outside of elements that preserve whitespace.
+pub(crate) fn html(html: &str) -> String {
+    let mut minified = String::with_capacity(html.len());
+    let mut open = None;
+
+    for line in html.split_inclusive('\n') {
+        if open.is_some() {
+            minified.push_str(line);
+        } else {
+            let trimmed = line.trim_start();
+
+            if !trimmed.is_empty() {
+                minified.push_str(trimmed);
+            }
+        }
+
+        open = open_after(line, open);
+    }
+
+    minified
+}
+
+/// Minify uncompressed HTML responses if enabled.
+pub(crate) async fn strip(
+    State(Enabled(enabled)): State<Enabled>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let response = next.run(request).await;
+
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"text/html"));
+
+    if !enabled || !is_html || response.headers().contains_key(CONTENT_ENCODING) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            tracing::error!("failed to read HTML response: {err}");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let Ok(text) = std::str::from_utf8(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    parts.headers.remove(CONTENT_LENGTH);
+
+    Response::from_parts(parts, Body::from(html(text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{Client, StoreCookies};
+
+    #[test]
+    fn strip_indentation() {
+        let source = "<html>\n  <body>\n\n    <p>a\n    b</p>\n  </body>\n</html>\n";
+
+        assert_eq!(
+            html(source),
+            "<html>\n<body>\n<p>a\nb</p>\n</body>\n</html>\n"
+        );
+    }
+
+    #[test]
+    fn keep_preserved_elements() {
+        let source = "  <textarea name=\"text\">\n  fn main() {\n\n  }\n</textarea>\n  <pre>a\n  b</pre>\n  <p>\n";
+
+        assert_eq!(
+            html(source),
+            "<textarea name=\"text\">\n  fn main() {\n\n  }\n</textarea>\n<pre>a\n  b</pre>\n<p>\n"
+        );
+
+        let source = "<pre>a</pre><preview>\n  b\n";
+        assert_eq!(html(source), "<pre>a</pre><preview>\nb\n");
+    }
+
+    #[tokio::test]
+    async fn minified_pages() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::new(StoreCookies(false)).await;
+
+        let data = Entry {
+            text: String::from("fn main() {\n    println!(\"hi\");\n}\n"),
+            extension: Some(String::from("rs")),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?.to_string();
+
+        let res = client.get(&location).send().await?;
+        let content = res.text().await?;
+
+        assert!(!content.contains("\n  <"));
+        assert!(content.contains(r#"<a href=#L2>2</a></td><td class="line">    "#));
+
+        Ok(())
+    }
+}
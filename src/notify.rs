@@ -0,0 +1,518 @@
+use crate::highlight::escape;
+use serde_json::{Value, json};
+use std::fmt;
+use std::str::FromStr;
+use tokio::sync::mpsc;
+
+/// Maximum number of announcements waiting to be sent. Further announcements are dropped.
+#[cfg(feature = "notify")]
+const QUEUE_SIZE: usize = 256;
+
+/// Time a chat service has to accept a notification.
+#[cfg(feature = "notify")]
+const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Maximum number of lines shown in previews.
+const PREVIEW_LINES: usize = 10;
+
+/// Maximum number of characters shown in previews.
+const PREVIEW_CHARS: usize = 500;
+
+/// Occasion to notify chat services of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Event {
+    /// A public paste was created.
+    Created,
+    /// A new paste matched a quarantine rule of the blocklist.
+    Quarantined,
+}
+
+/// Events a chat service is notified of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Events {
+    created: bool,
+    quarantined: bool,
+}
+
+impl Default for Events {
+    fn default() -> Self {
+        Self {
+            created: true,
+            quarantined: false,
+        }
+    }
+}
+
+impl FromStr for Events {
+    type Err = String;
+
+    /// Parse comma-separated event names.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut events = Self {
+            created: false,
+            quarantined: false,
+        };
+
+        for name in s.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            match name {
+                "created" => events.created = true,
+                "quarantined" => events.quarantined = true,
+                name => return Err(name.to_string()),
+            }
+        }
+
+        if events.created || events.quarantined {
+            Ok(events)
+        } else {
+            Err(String::from("no events"))
+        }
+    }
+}
+
+impl fmt::Display for Events {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.created, self.quarantined) {
+            (true, true) => f.write_str("created and quarantined pastes"),
+            (true, false) => f.write_str("created pastes"),
+            (false, _) => f.write_str("quarantined pastes"),
+        }
+    }
+}
+
+impl Events {
+    /// Return `true` if `event` is one of the events.
+    #[cfg_attr(
+        not(any(feature = "notify", test)),
+        expect(dead_code, reason = "only used with the `notify` feature")
+    )]
+    pub fn contains(self, event: Event) -> bool {
+        match event {
+            Event::Created => self.created,
+            Event::Quarantined => self.quarantined,
+        }
+    }
+}
+
+/// Chat service notifications are posted to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Service {
+    /// Slack incoming webhook.
+    Slack(url::Url),
+    /// Discord channel webhook.
+    Discord(url::Url),
+    /// Matrix `room` posted to as the user of the access `token` on `homeserver`.
+    Matrix {
+        homeserver: url::Url,
+        room: String,
+        token: String,
+    },
+}
+
+impl fmt::Display for Service {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Slack(_) => f.write_str("Slack"),
+            Self::Discord(_) => f.write_str("Discord"),
+            Self::Matrix { room, .. } => write!(f, "Matrix room {room}"),
+        }
+    }
+}
+
+/// Chat service and the events it is notified of.
+#[derive(Clone, Debug)]
+pub(crate) struct Target {
+    pub service: Service,
+    pub events: Events,
+}
+
+/// Paste chat services are notified of.
+#[derive(Clone, Debug)]
+pub(crate) struct Announcement {
+    pub event: Event,
+    /// Title of the site the paste was created on.
+    pub site: String,
+    pub title: Option<String>,
+    /// Name of the language, e.g. `Rust`.
+    pub language: String,
+    pub extension: String,
+    /// Beginning of the text, see [`preview`].
+    pub preview: Option<String>,
+    pub url: String,
+    /// Blocklist rule a quarantined paste matched.
+    pub rule: Option<String>,
+}
+
+impl Announcement {
+    /// First line of the message, saying what happened.
+    fn headline(&self) -> String {
+        match (&self.event, &self.rule) {
+            (Event::Quarantined, Some(rule)) => {
+                format!("Paste on {} quarantined by rule `{rule}`", self.site)
+            }
+            (Event::Quarantined, None) => format!("Paste on {} quarantined", self.site),
+            (Event::Created, _) => format!("New paste on {}", self.site),
+        }
+    }
+
+    fn title(&self) -> &str {
+        self.title.as_deref().unwrap_or("Untitled")
+    }
+}
+
+/// Sender of announcements to the configured chat services.
+#[derive(Clone)]
+pub(crate) struct Notifier {
+    sender: mpsc::Sender<Announcement>,
+}
+
+impl Notifier {
+    /// Create a notifier and spawn the task sending announcements to `targets`.
+    #[cfg(feature = "notify")]
+    pub fn new(targets: Vec<Target>) -> Self {
+        let (sender, receiver) = mpsc::channel(QUEUE_SIZE);
+
+        tokio::spawn(send(targets, receiver));
+
+        Self { sender }
+    }
+
+    /// Queue `announcement` for the chat services notified of its event.
+    pub fn announce(&self, announcement: Announcement) {
+        // Creating pastes must not wait for chat services, so announcements are dropped when the
+        // queue is full.
+        let _ = self.sender.try_send(announcement);
+    }
+}
+
+#[cfg(test)]
+impl Notifier {
+    /// Notifier queueing announcements for the returned receiver.
+    pub fn channel() -> (Self, mpsc::Receiver<Announcement>) {
+        let (sender, receiver) = mpsc::channel(16);
+
+        (Self { sender }, receiver)
+    }
+}
+
+/// Up to [`PREVIEW_LINES`] lines and [`PREVIEW_CHARS`] characters of `text`, followed by an
+/// ellipsis if cut short, or `None` if there is nothing to show.
+pub(crate) fn preview(text: &str) -> Option<String> {
+    let mut lines = text.lines();
+    let mut preview = lines
+        .by_ref()
+        .take(PREVIEW_LINES)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut cut = lines.any(|line| !line.trim().is_empty());
+
+    if let Some((end, _)) = preview.char_indices().nth(PREVIEW_CHARS) {
+        preview.truncate(end);
+        cut = true;
+    }
+
+    let preview = preview.trim_end();
+
+    if preview.trim_start().is_empty() {
+        None
+    } else if cut {
+        Some(format!("{preview}\n…"))
+    } else {
+        Some(preview.to_string())
+    }
+}
+
+/// `preview` with backtick fences broken up, so it cannot end the code block it is shown in.
+fn fenced(preview: &str) -> String {
+    preview.replace("```", "``\u{200b}`")
+}
+
+/// `text` with the characters Slack treats as markup escaped.
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Message for a Slack incoming webhook.
+#[cfg_attr(
+    not(any(feature = "notify", test)),
+    expect(dead_code, reason = "only used with the `notify` feature")
+)]
+fn slack(announcement: &Announcement) -> Value {
+    let headline = slack_escape(&announcement.headline());
+    let mut blocks = vec![json!({
+        "type": "section",
+        "text": {
+            "type": "mrkdwn",
+            "text": format!(
+                "*{headline}*\n<{}|{}> · {}",
+                announcement.url,
+                slack_escape(announcement.title()),
+                announcement.language
+            ),
+        },
+    })];
+
+    if let Some(preview) = &announcement.preview {
+        blocks.push(json!({
+            "type": "section",
+            "text": {
+                "type": "mrkdwn",
+                "text": format!("```{}```", fenced(&slack_escape(preview))),
+            },
+        }));
+    }
+
+    json!({
+        "text": format!("{headline}: {}", announcement.url),
+        "blocks": blocks,
+        "unfurl_links": false,
+    })
+}
+
+/// Message for a Discord webhook.
+#[cfg_attr(
+    not(any(feature = "notify", test)),
+    expect(dead_code, reason = "only used with the `notify` feature")
+)]
+fn discord(announcement: &Announcement) -> Value {
+    let mut embed = json!({
+        "title": announcement.title().chars().take(256).collect::<String>(),
+        "url": announcement.url,
+        "fields": [{ "name": "Language", "value": announcement.language, "inline": true }],
+    });
+
+    if let Some(preview) = &announcement.preview {
+        embed["description"] = Value::from(format!(
+            "```{}\n{}\n```",
+            announcement.extension,
+            fenced(preview)
+        ));
+    }
+
+    json!({
+        "content": announcement.headline(),
+        "embeds": [embed],
+        // Neither titles nor previews may ping anyone.
+        "allowed_mentions": { "parse": [] },
+    })
+}
+
+/// Message for a Matrix room.
+#[cfg_attr(
+    not(any(feature = "notify", test)),
+    expect(dead_code, reason = "only used with the `notify` feature")
+)]
+fn matrix(announcement: &Announcement) -> Value {
+    let headline = announcement.headline();
+    let title = announcement.title();
+    let mut body = format!(
+        "{headline}\n{title} ({}): {}",
+        announcement.language, announcement.url
+    );
+    let mut formatted = format!(
+        "<p><strong>{}</strong><br><a href=\"{}\">{}</a> ({})</p>",
+        escape(&headline),
+        escape(&announcement.url),
+        escape(title),
+        escape(&announcement.language)
+    );
+
+    if let Some(preview) = &announcement.preview {
+        body = format!("{body}\n\n{preview}");
+        formatted = format!(
+            "{formatted}<pre><code class=\"language-{}\">{}</code></pre>",
+            escape(&announcement.extension),
+            escape(preview)
+        );
+    }
+
+    json!({
+        "msgtype": "m.notice",
+        "body": body,
+        "format": "org.matrix.custom.html",
+        "formatted_body": formatted,
+    })
+}
+
+/// Endpoint sending a message with the transaction id `txn` to `room` on `homeserver`.
+#[cfg_attr(
+    not(any(feature = "notify", test)),
+    expect(dead_code, reason = "only used with the `notify` feature")
+)]
+fn matrix_url(homeserver: &url::Url, room: &str, txn: &str) -> url::Url {
+    let mut url = homeserver.clone();
+
+    if let Ok(mut segments) = url.path_segments_mut() {
+        segments.pop_if_empty().extend([
+            "_matrix",
+            "client",
+            "v3",
+            "rooms",
+            room,
+            "send",
+            "m.room.message",
+            txn,
+        ]);
+    }
+
+    url
+}
+
+/// Send announcements from `receiver` to the `targets` notified of their events until all
+/// senders are gone.
+#[cfg(feature = "notify")]
+async fn send(targets: Vec<Target>, mut receiver: mpsc::Receiver<Announcement>) {
+    let client = match reqwest::Client::builder().timeout(TIMEOUT).build() {
+        Ok(client) => client,
+        Err(err) => {
+            tracing::warn!("failed to create HTTP client, not sending notifications: {err}");
+            return;
+        }
+    };
+
+    while let Some(announcement) = receiver.recv().await {
+        for target in targets
+            .iter()
+            .filter(|target| target.events.contains(announcement.event))
+        {
+            let request = match &target.service {
+                Service::Slack(url) => client.post(url.clone()).json(&slack(&announcement)),
+                Service::Discord(url) => client.post(url.clone()).json(&discord(&announcement)),
+                Service::Matrix {
+                    homeserver,
+                    room,
+                    token,
+                } => {
+                    let txn = hex::encode(rand::random::<[u8; 16]>());
+
+                    client
+                        .put(matrix_url(homeserver, room, &txn))
+                        .bearer_auth(token)
+                        .json(&matrix(&announcement))
+                }
+            };
+
+            let result = request
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status);
+
+            if let Err(err) = result {
+                tracing::warn!("failed to notify {}: {err}", target.service);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn announcement() -> Announcement {
+        Announcement {
+            event: Event::Created,
+            site: String::from("wastebin"),
+            title: Some(String::from("<b>a & b</b>")),
+            language: String::from("Rust"),
+            extension: String::from("rs"),
+            preview: preview("fn main() {\n    println!(\"```\");\n}\n"),
+            url: String::from("https://paste.example.com/abcdef.rs"),
+            rule: None,
+        }
+    }
+
+    #[test]
+    fn events() {
+        assert_eq!("".parse::<Events>(), Err(String::from("no events")));
+        assert_eq!("created,typo".parse::<Events>(), Err(String::from("typo")));
+
+        let events = "quarantined".parse::<Events>().unwrap();
+        assert!(events.contains(Event::Quarantined));
+        assert!(!events.contains(Event::Created));
+        assert!(Events::default().contains(Event::Created));
+    }
+
+    #[test]
+    fn previews() {
+        assert_eq!(preview(" \n\n"), None);
+        assert_eq!(preview("a\nb\n").as_deref(), Some("a\nb"));
+
+        let long = (0..20)
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let cut = preview(&long).unwrap();
+        assert!(cut.starts_with("0\n1\n"));
+        assert!(cut.ends_with("9\n…"));
+
+        let cut = preview(&"ä".repeat(600)).unwrap();
+        assert_eq!(cut.chars().count(), PREVIEW_CHARS + 2);
+    }
+
+    #[test]
+    fn messages() {
+        let announcement = announcement();
+
+        let message = slack(&announcement);
+        assert_eq!(
+            message["text"],
+            "New paste on wastebin: https://paste.example.com/abcdef.rs"
+        );
+        assert_eq!(
+            message["blocks"][0]["text"]["text"],
+            "*New paste on wastebin*\n<https://paste.example.com/abcdef.rs|&lt;b&gt;a &amp; b&lt;/b&gt;> · Rust"
+        );
+        assert_eq!(
+            message["blocks"][1]["text"]["text"],
+            "```fn main() {\n    println!(\"``\u{200b}`\");\n}```"
+        );
+
+        let message = discord(&announcement);
+        assert_eq!(message["content"], "New paste on wastebin");
+        assert_eq!(message["embeds"][0]["title"], "<b>a & b</b>");
+        assert_eq!(message["embeds"][0]["fields"][0]["value"], "Rust");
+        assert!(
+            message["embeds"][0]["description"]
+                .as_str()
+                .unwrap()
+                .starts_with("```rs\nfn main() {\n")
+        );
+        assert_eq!(message["allowed_mentions"]["parse"], json!([]));
+
+        let message = matrix(&announcement);
+        assert_eq!(message["msgtype"], "m.notice");
+        assert!(
+            message["body"]
+                .as_str()
+                .unwrap()
+                .starts_with("New paste on wastebin\n<b>a & b</b> (Rust): https://")
+        );
+        assert!(message["formatted_body"].as_str().unwrap().contains(
+            "&lt;b&gt;a &amp; b&lt;/b&gt;</a> (Rust)</p><pre><code class=\"language-rs\">"
+        ));
+
+        let quarantined = Announcement {
+            event: Event::Quarantined,
+            rule: Some(String::from("spam")),
+            preview: None,
+            ..announcement
+        };
+        let message = discord(&quarantined);
+        assert_eq!(
+            message["content"],
+            "Paste on wastebin quarantined by rule `spam`"
+        );
+        assert!(message["embeds"][0].get("description").is_none());
+    }
+
+    #[test]
+    fn matrix_endpoint() {
+        let homeserver = url::Url::parse("https://matrix.example.com/").unwrap();
+
+        assert_eq!(
+            matrix_url(&homeserver, "!room:example.com", "42").as_str(),
+            "https://matrix.example.com/_matrix/client/v3/rooms/!room:example.com/send/m.room.message/42"
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use crate::accounts;
+use crate::errors::Error;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+use url::Url;
+
+/// Endpoints announced by the provider's discovery document.
+#[derive(Deserialize)]
+struct Metadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// Single or multiple audiences of an ID token.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Deserialize)]
+struct IdToken {
+    iss: String,
+    sub: String,
+    aud: Audience,
+    exp: i64,
+    nonce: Option<String>,
+    preferred_username: Option<String>,
+    email: Option<String>,
+}
+
+/// Verified identity of a user.
+#[derive(Debug)]
+pub(crate) struct Claims {
+    /// Stable identifier of the user at the provider.
+    pub subject: String,
+    /// Account names to try in order of preference when creating an account.
+    pub names: Vec<String>,
+}
+
+/// OIDC provider using the authorization code flow.
+pub(crate) struct Provider {
+    issuer: String,
+    client_id: String,
+    client_secret: String,
+    client: reqwest::Client,
+    metadata: OnceCell<Metadata>,
+}
+
+impl Provider {
+    /// Create a provider for `issuer` which is discovered on first use.
+    pub fn new(issuer: String, client_id: String, client_secret: String) -> Self {
+        Self {
+            issuer,
+            client_id,
+            client_secret,
+            client: reqwest::Client::new(),
+            metadata: OnceCell::new(),
+        }
+    }
+
+    /// Issuer identifier as configured.
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    async fn metadata(&self) -> Result<&Metadata, Error> {
+        self.metadata
+            .get_or_try_init(|| async {
+                let url = format!(
+                    "{}/.well-known/openid-configuration",
+                    self.issuer.trim_end_matches('/')
+                );
+
+                let metadata = self
+                    .client
+                    .get(url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<Metadata>()
+                    .await?;
+
+                if metadata.issuer.trim_end_matches('/') != self.issuer.trim_end_matches('/') {
+                    return Err(Error::Oidc(format!(
+                        "discovered issuer {} does not match",
+                        metadata.issuer
+                    )));
+                }
+
+                Ok(metadata)
+            })
+            .await
+    }
+
+    /// URL to send the user to for authentication.
+    pub async fn authorize_url(
+        &self,
+        redirect_uri: &Url,
+        state: &str,
+        nonce: &str,
+    ) -> Result<Url, Error> {
+        let mut url = Url::parse(&self.metadata().await?.authorization_endpoint)?;
+
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", redirect_uri.as_str())
+            .append_pair("scope", "openid profile email")
+            .append_pair("state", state)
+            .append_pair("nonce", nonce);
+
+        Ok(url)
+    }
+
+    /// Exchange the authorization `code` for the identity of the user.
+    pub async fn exchange(
+        &self,
+        code: &str,
+        redirect_uri: &Url,
+        nonce: &str,
+    ) -> Result<Claims, Error> {
+        let metadata = self.metadata().await?;
+
+        // Credentials are form-encoded before being used for basic authentication.
+        let encode =
+            |s: &str| url::form_urlencoded::byte_serialize(s.as_bytes()).collect::<String>();
+
+        let response = self
+            .client
+            .post(&metadata.token_endpoint)
+            .basic_auth(encode(&self.client_id), Some(encode(&self.client_secret)))
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", redirect_uri.as_str()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<TokenResponse>()
+            .await?;
+
+        let now = time::OffsetDateTime::now_utc().unix_timestamp();
+
+        decode(
+            &response.id_token,
+            &metadata.issuer,
+            &self.client_id,
+            nonce,
+            now,
+        )
+    }
+}
+
+/// Decode and validate the claims of `token`.
+///
+/// The signature is not checked because the token is received directly from the token endpoint
+/// over TLS, which authenticates the issuer as permitted by section 3.1.3.7 of the OIDC core specification.
+fn decode(
+    token: &str,
+    issuer: &str,
+    client_id: &str,
+    nonce: &str,
+    now: i64,
+) -> Result<Claims, Error> {
+    let payload = token
+        .split('.')
+        .nth(1)
+        .ok_or_else(|| Error::Oidc(String::from("malformed ID token")))?;
+
+    let payload = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|err| Error::Oidc(err.to_string()))?;
+
+    let token: IdToken =
+        serde_json::from_slice(&payload).map_err(|err| Error::Oidc(err.to_string()))?;
+
+    let audience = match &token.aud {
+        Audience::One(aud) => aud == client_id,
+        Audience::Many(aud) => aud.iter().any(|aud| aud == client_id),
+    };
+
+    if token.iss != issuer {
+        return Err(Error::Oidc(String::from("ID token from wrong issuer")));
+    }
+
+    if !audience {
+        return Err(Error::Oidc(String::from("ID token for wrong audience")));
+    }
+
+    if token.exp <= now {
+        return Err(Error::Oidc(String::from("ID token expired")));
+    }
+
+    if token.nonce.as_deref() != Some(nonce) {
+        return Err(Error::Oidc(String::from("ID token with wrong nonce")));
+    }
+
+    let name = token
+        .preferred_username
+        .or_else(|| {
+            token
+                .email
+                .and_then(|email| email.split('@').next().map(ToString::to_string))
+        })
+        .unwrap_or_default();
+
+    Ok(Claims {
+        subject: token.sub,
+        names: accounts::names(&name),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(claims: &serde_json::Value) -> String {
+        format!(
+            "eyJhbGciOiJub25lIn0.{}.",
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        )
+    }
+
+    #[test]
+    fn decode_claims() {
+        let claims = serde_json::json!({
+            "iss": "https://sso.example.com",
+            "sub": "1234",
+            "aud": ["other", "wastebin"],
+            "exp": 2000,
+            "nonce": "abc",
+            "preferred_username": "Alice Smith",
+        });
+
+        let decoded = decode(
+            &token(&claims),
+            "https://sso.example.com",
+            "wastebin",
+            "abc",
+            1000,
+        );
+        let decoded = decoded.unwrap();
+        assert_eq!(decoded.subject, "1234");
+        assert_eq!(decoded.names[0], "AliceSmith");
+
+        let token = token(&claims);
+        assert!(decode(&token, "https://evil.example.com", "wastebin", "abc", 1000).is_err());
+        assert!(decode(&token, "https://sso.example.com", "other-app", "abc", 1000).is_err());
+        assert!(decode(&token, "https://sso.example.com", "wastebin", "xyz", 1000).is_err());
+        assert!(decode(&token, "https://sso.example.com", "wastebin", "abc", 3000).is_err());
+        assert!(
+            decode(
+                "garbage",
+                "https://sso.example.com",
+                "wastebin",
+                "abc",
+                1000
+            )
+            .is_err()
+        );
+    }
+}
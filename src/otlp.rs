@@ -0,0 +1,332 @@
+use serde_json::{Value, json};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Maximum number of spans sent in one request.
+const BATCH_SIZE: usize = 512;
+
+/// Maximum number of finished spans waiting for export. Further spans are dropped.
+const QUEUE_SIZE: usize = 4096;
+
+/// Interval after which incomplete batches are sent anyway.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// OTLP span kinds.
+const KIND_INTERNAL: u8 = 1;
+const KIND_SERVER: u8 = 2;
+
+/// Layer exporting spans to an OpenTelemetry collector once they are closed. Whether a trace is
+/// sampled is decided at its root span and inherited by all spans below it.
+pub(crate) struct Layer {
+    ratio: f64,
+    sender: mpsc::Sender<Value>,
+}
+
+/// Span state kept in the span extensions until the span is closed.
+struct Data {
+    trace_id: [u8; 16],
+    span_id: [u8; 8],
+    parent_id: Option<[u8; 8]>,
+    sampled: bool,
+    start: u64,
+    attributes: Vec<Value>,
+    events: Vec<Value>,
+}
+
+/// Visitor collecting fields as OTLP attributes, except for the message of events.
+#[derive(Default)]
+struct Fields {
+    attributes: Vec<Value>,
+    message: Option<String>,
+}
+
+impl Fields {
+    fn push(&mut self, field: &Field, value: Value) {
+        let attribute = [("key", Value::from(field.name())), ("value", value)]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value));
+
+        self.attributes.push(Value::Object(attribute.collect()));
+    }
+}
+
+impl Visit for Fields {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.push(field, json!({ "doubleValue": value }));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.push(field, json!({ "intValue": value.to_string() }));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.push(field, json!({ "intValue": value.to_string() }));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.push(field, json!({ "boolValue": value }));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = Some(value.to_string());
+        } else {
+            self.push(field, json!({ "stringValue": value }));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.record_str(field, &format!("{value:?}"));
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| {
+            u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX)
+        })
+}
+
+impl Layer {
+    /// Create a layer sampling `ratio` of all traces and spawn the task sending them to
+    /// `endpoint`.
+    pub fn new(endpoint: url::Url, ratio: f64) -> Self {
+        let (sender, receiver) = mpsc::channel(QUEUE_SIZE);
+
+        tokio::spawn(export(endpoint, receiver));
+
+        Self { ratio, sender }
+    }
+}
+
+impl<S> tracing_subscriber::Layer<S> for Layer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let parent = span.parent().and_then(|parent| {
+            parent
+                .extensions()
+                .get::<Data>()
+                .map(|data| (data.trace_id, data.span_id, data.sampled))
+        });
+
+        let (trace_id, parent_id, sampled) = match parent {
+            Some((trace_id, span_id, sampled)) => (trace_id, Some(span_id), sampled),
+            None => (rand::random(), None, rand::random::<f64>() < self.ratio),
+        };
+
+        let mut fields = Fields::default();
+
+        if sampled {
+            attrs.record(&mut fields);
+        }
+
+        span.extensions_mut().insert(Data {
+            trace_id,
+            span_id: rand::random(),
+            parent_id,
+            sampled,
+            start: now(),
+            attributes: fields.attributes,
+            events: Vec::new(),
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        if let Some(data) = span
+            .extensions_mut()
+            .get_mut::<Data>()
+            .filter(|data| data.sampled)
+        {
+            let mut fields = Fields::default();
+            values.record(&mut fields);
+            data.attributes.extend(fields.attributes);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.event_span(event) else {
+            return;
+        };
+
+        if let Some(data) = span
+            .extensions_mut()
+            .get_mut::<Data>()
+            .filter(|data| data.sampled)
+        {
+            let mut fields = Fields::default();
+            event.record(&mut fields);
+            fields.attributes.push(json!({
+                "key": "level",
+                "value": { "stringValue": event.metadata().level().as_str() },
+            }));
+
+            data.events.push(json!({
+                "timeUnixNano": now().to_string(),
+                "name": fields.message.unwrap_or_else(|| event.metadata().name().to_string()),
+                "attributes": fields.attributes,
+            }));
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+
+        let Some(data) = span
+            .extensions_mut()
+            .remove::<Data>()
+            .filter(|data| data.sampled)
+        else {
+            return;
+        };
+
+        let mut otlp = json!({
+            "traceId": hex::encode(data.trace_id),
+            "spanId": hex::encode(data.span_id),
+            "name": span.name(),
+            "kind": if data.parent_id.is_some() { KIND_INTERNAL } else { KIND_SERVER },
+            "startTimeUnixNano": data.start.to_string(),
+            "endTimeUnixNano": now().to_string(),
+            "attributes": data.attributes,
+            "events": data.events,
+        });
+
+        if let Some(parent_id) = data.parent_id {
+            otlp["parentSpanId"] = Value::String(hex::encode(parent_id));
+        }
+
+        // Requests must not wait for a slow collector, so spans are dropped when the queue is
+        // full.
+        let _ = self.sender.try_send(otlp);
+    }
+}
+
+/// Request body carrying `spans`.
+fn payload(spans: &[Value]) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [{
+                    "key": "service.name",
+                    "value": { "stringValue": env!("CARGO_PKG_NAME") },
+                }],
+            },
+            "scopeSpans": [{
+                "scope": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+/// Send batches of spans from `receiver` to `endpoint` until all senders are gone.
+async fn export(endpoint: url::Url, mut receiver: mpsc::Receiver<Value>) {
+    let client = reqwest::Client::new();
+    let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+    let mut batch = Vec::new();
+
+    loop {
+        let closed = tokio::select! {
+            span = receiver.recv() => match span {
+                Some(span) => {
+                    batch.push(span);
+
+                    if batch.len() < BATCH_SIZE {
+                        continue;
+                    }
+
+                    false
+                }
+                None => true,
+            },
+            _ = interval.tick() => false,
+        };
+
+        if !batch.is_empty() {
+            let body = payload(&std::mem::take(&mut batch));
+            let result = client
+                .post(endpoint.clone())
+                .json(&body)
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status);
+
+            if let Err(err) = result {
+                tracing::warn!("failed to export traces to {endpoint}: {err}");
+            }
+        }
+
+        if closed {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[tokio::test]
+    async fn sampled_traces() {
+        let (sender, mut receiver) = mpsc::channel(16);
+        let layer = Layer { ratio: 1.0, sender };
+        let subscriber = tracing_subscriber::registry().with(layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let root = tracing::info_span!("request", method = "GET");
+            let _root = root.enter();
+            let child = tracing::info_span!("query", id = 42);
+            let _child = child.enter();
+            tracing::info!(rows = 1, "done");
+        });
+
+        let child = receiver.try_recv().unwrap();
+        let root = receiver.try_recv().unwrap();
+
+        assert_eq!(child["name"], "query");
+        assert_eq!(child["traceId"], root["traceId"]);
+        assert_eq!(child["parentSpanId"], root["spanId"]);
+        assert_eq!(child["kind"], KIND_INTERNAL);
+        assert_eq!(child["attributes"][0]["value"]["intValue"], "42");
+        assert_eq!(child["events"][0]["name"], "done");
+        assert_eq!(root["kind"], KIND_SERVER);
+        assert_eq!(root["attributes"][0]["value"]["stringValue"], "GET");
+        assert!(root.get("parentSpanId").is_none());
+    }
+
+    #[tokio::test]
+    async fn unsampled_traces() {
+        let (sender, mut receiver) = mpsc::channel(16);
+        let layer = Layer { ratio: 0.0, sender };
+        let subscriber = tracing_subscriber::registry().with(layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let root = tracing::info_span!("request");
+            let _root = root.enter();
+            let _child = tracing::info_span!("query").entered();
+        });
+
+        assert!(receiver.try_recv().is_err());
+    }
+}
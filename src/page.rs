@@ -1,30 +1,112 @@
-use crate::assets::{Asset, Css, Kind};
+use crate::accounts::Quota;
+use crate::assets::{Asset, Css, Custom, Kind};
+use crate::captcha::Widget;
 use crate::expiration::{Expiration, ExpirationSet};
-use crate::highlight::Theme;
+use crate::highlight::{Theme, markdown};
+use crate::secrets::Policy;
+use crate::shortcuts::Shortcuts;
+use crate::templates::Overrides;
+use crate::terms::Terms;
+use crate::timezone::Timezone;
+use std::sync::Arc;
 use url::Url;
 
+/// Number of spaces the Tab key indents by in the editor unless configured otherwise.
+pub(crate) const DEFAULT_TAB_WIDTH: u8 = 4;
+
 /// Static page assets.
 pub(crate) struct Assets {
     pub favicon: Asset,
+    /// Favicon under the fixed route browsers request if a page does not link one.
+    pub favicon_ico: Asset,
+    /// Rules for crawlers under the fixed route `/robots.txt`.
+    pub robots_txt: Asset,
+    /// Icon home screens and installed web apps show.
+    pub touch_icon: Option<Asset>,
     pub css: Css,
     pub base_js: Asset,
     pub index_js: Asset,
     pub paste_js: Asset,
+    pub pow_js: Asset,
+    pub zk_js: Asset,
+    /// Service worker making wastebin installable, served by this host under a fixed route so it
+    /// controls all pages.
+    pub service_worker: Asset,
+    /// Stylesheet loaded after the theme's.
+    pub custom_css: Option<Custom>,
+    /// Image shown in the header instead of the title.
+    pub logo: Option<Custom>,
 }
 
+#[expect(clippy::struct_excessive_bools)]
 pub(crate) struct Page {
     pub version: &'static str,
     pub title: String,
     pub assets: Assets,
     pub base_url: Url,
     pub expirations: Vec<Expiration>,
+    /// If users can log into accounts.
+    pub accounts: bool,
+    /// If new accounts can be registered.
+    pub registration: bool,
+    /// If new accounts can be registered with an invite code while registration is closed.
+    pub invites: bool,
+    /// If users can log in via single sign-on.
+    pub oidc: bool,
+    /// If only logged in users may create pastes.
+    pub private: bool,
+    /// If only accounts with the create role may create pastes.
+    pub restrict_creation: bool,
+    /// Limits on what each account may store.
+    pub quota: Quota,
+    /// Names of accounts with admin rights in addition to the ones granted by a directory.
+    pub admins: Vec<String>,
+    /// If admin rights require two-factor authentication.
+    pub admin_totp: bool,
+    /// Captcha anonymous users solve before creating a paste.
+    pub captcha: Option<Widget>,
+    /// If anonymous users must solve a proof-of-work challenge before creating a paste.
+    pub pow: bool,
+    /// What happens to pastes containing credentials, nothing if unset.
+    pub secrets: Option<Policy>,
+    /// Origin of the host serving static assets, if not this one.
+    pub asset_origin: Option<String>,
+    /// Templates replacing the embedded ones.
+    pub templates: Option<Overrides>,
+    /// If search engines are asked not to index any paste, not only the ones created so.
+    pub noindex: bool,
+    /// If public pastes are listed in a sitemap for search engines.
+    pub sitemap: bool,
+    /// Labels and targets of the links shown at the bottom of every page.
+    pub footer: Vec<(String, String)>,
+    /// Maximum size of request bodies, checked by the browser before uploading.
+    pub max_body_size: Option<usize>,
+    /// Keys bound to the actions of the help overlay.
+    pub shortcuts: Shortcuts,
+    /// Time zone of timestamps until the browser shows them in the viewer's one.
+    pub timezone: Timezone,
+    /// Number of spaces the Tab key indents by in the editor, 0 to insert tab characters.
+    pub tab_width: u8,
+    /// Terms of service anonymous users accept before creating pastes.
+    pub terms: Option<Terms>,
+    /// About page describing the instance.
+    pub about: Option<Arc<str>>,
+    /// If anyone can see how many pastes are created in which language.
+    pub language_stats: bool,
 }
 
 impl Page {
     /// Create new page meta data from generated  `assets`, `title` and optional `base_url`.
+    /// The `custom` themes are selectable in addition to the built-in ones.
     #[must_use]
-    pub fn new(title: String, base_url: Url, theme: Theme, expirations: ExpirationSet) -> Self {
-        let assets = Assets::new(theme);
+    pub fn new(
+        title: String,
+        base_url: Url,
+        theme: &Theme,
+        custom: &[Theme],
+        expirations: ExpirationSet,
+    ) -> Self {
+        let assets = Assets::new(theme, custom);
         let expirations = expirations.into_inner();
 
         Self {
@@ -33,20 +115,305 @@ impl Page {
             assets,
             base_url,
             expirations,
+            accounts: false,
+            registration: false,
+            invites: false,
+            oidc: false,
+            private: false,
+            restrict_creation: false,
+            quota: Quota::default(),
+            admins: Vec::new(),
+            admin_totp: false,
+            captcha: None,
+            pow: false,
+            secrets: None,
+            asset_origin: None,
+            templates: None,
+            noindex: false,
+            sitemap: false,
+            footer: Vec::new(),
+            max_body_size: None,
+            shortcuts: Shortcuts::default(),
+            timezone: Timezone::default(),
+            tab_width: DEFAULT_TAB_WIDTH,
+            terms: None,
+            about: None,
+            language_stats: false,
         }
     }
+
+    /// Enable accounts and allow registering new ones if `registration` is set.
+    #[must_use]
+    pub fn with_accounts(mut self, registration: bool) -> Self {
+        self.accounts = true;
+        self.registration = registration;
+        self
+    }
+
+    /// Allow registering new accounts with an invite code.
+    #[must_use]
+    pub fn with_invites(mut self) -> Self {
+        self.invites = true;
+        self
+    }
+
+    /// Limit what each account may store to `quota`.
+    #[must_use]
+    pub fn with_quota(mut self, quota: Quota) -> Self {
+        self.quota = quota;
+        self
+    }
+
+    /// Grant admin rights to the accounts named `admins`.
+    #[must_use]
+    pub fn with_admins(mut self, admins: Vec<String>) -> Self {
+        self.admins = admins;
+        self
+    }
+
+    /// Only grant admin rights to accounts with two-factor authentication enabled.
+    #[must_use]
+    pub fn with_admin_totp(mut self) -> Self {
+        self.admin_totp = true;
+        self
+    }
+
+    /// Only allow logged in users to create pastes.
+    #[must_use]
+    pub fn with_private(mut self) -> Self {
+        self.private = true;
+        self
+    }
+
+    /// Only allow accounts with the create role to create pastes.
+    #[must_use]
+    pub fn with_restricted_creation(mut self) -> Self {
+        self.restrict_creation = true;
+        self
+    }
+
+    /// Offer logging in via single sign-on.
+    #[must_use]
+    pub fn with_oidc(mut self) -> Self {
+        self.oidc = true;
+        self
+    }
+
+    /// Ask anonymous users to solve the captcha `widget` before creating a paste.
+    #[must_use]
+    pub fn with_captcha(mut self, widget: Widget) -> Self {
+        self.captcha = Some(widget);
+        self
+    }
+
+    /// Ask anonymous users to solve a proof-of-work challenge before creating a paste.
+    #[must_use]
+    pub fn with_pow(mut self) -> Self {
+        self.pow = true;
+        self
+    }
+
+    /// Apply `policy` to pastes containing credentials.
+    #[must_use]
+    pub fn with_secrets(mut self, policy: Policy) -> Self {
+        self.secrets = Some(policy);
+        self
+    }
+
+    /// Ask search engines not to index any paste.
+    #[must_use]
+    pub fn with_noindex(mut self) -> Self {
+        self.noindex = true;
+        self
+    }
+
+    /// List public pastes in a sitemap.
+    #[must_use]
+    pub fn with_sitemap(mut self) -> Self {
+        self.sitemap = true;
+        self
+    }
+
+    /// Serve `rules` as `/robots.txt` instead of the built-in ones.
+    #[must_use]
+    pub fn with_robots_txt(mut self, rules: Vec<u8>) -> Self {
+        self.assets.robots_txt = Asset::new("robots.txt", mime::TEXT_PLAIN_UTF_8, rules);
+        self
+    }
+
+    /// Show `favicon` instead of the built-in one, also under `/favicon.ico`.
+    #[must_use]
+    pub fn with_favicon(mut self, favicon: Asset) -> Self {
+        self.assets.favicon_ico = favicon.fixed("favicon.ico");
+        self.assets.favicon = favicon;
+        self
+    }
+
+    /// Link the `icon` for home screens and installed web apps.
+    #[must_use]
+    pub fn with_touch_icon(mut self, icon: Asset) -> Self {
+        self.assets.touch_icon = Some(icon);
+        self
+    }
+
+    /// Load the `stylesheet` after the theme's on every page.
+    #[must_use]
+    pub fn with_custom_css(mut self, stylesheet: Custom) -> Self {
+        self.assets.custom_css = Some(stylesheet);
+        self
+    }
+
+    /// Show the `logo` in the header of every page.
+    #[must_use]
+    pub fn with_logo(mut self, logo: Custom) -> Self {
+        self.assets.logo = Some(logo);
+        self
+    }
+
+    /// Show the `links`, given by label and target, at the bottom of every page.
+    #[must_use]
+    pub fn with_footer(mut self, links: Vec<(String, String)>) -> Self {
+        self.footer = links;
+        self
+    }
+
+    /// Bind keyboard shortcuts as given by `shortcuts` instead of the default keys.
+    #[must_use]
+    pub fn with_shortcuts(mut self, shortcuts: Shortcuts) -> Self {
+        self.shortcuts = shortcuts;
+        self
+    }
+
+    /// Show timestamps in `timezone` to viewers without JavaScript instead of UTC.
+    #[must_use]
+    pub fn with_timezone(mut self, timezone: Timezone) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// Indent by `width` spaces when pressing Tab in the editor, or insert tabs if 0.
+    #[must_use]
+    pub fn with_tab_width(mut self, width: u8) -> Self {
+        self.tab_width = width;
+        self
+    }
+
+    /// Require anonymous users to accept the `terms` of service before creating pastes.
+    #[must_use]
+    pub fn with_terms(mut self, terms: Terms) -> Self {
+        self.terms = Some(terms);
+        self
+    }
+
+    /// Show an about page rendered from Markdown `source`, with `{base_url}` replaced by the base
+    /// URL of this instance.
+    #[must_use]
+    pub fn with_about(mut self, source: &str) -> Self {
+        let source = source.replace("{base_url}", self.base_url.as_str().trim_end_matches('/'));
+        self.about = Some(markdown::render(&source).into());
+        self
+    }
+
+    /// Publish statistics of the languages pastes are created in.
+    #[must_use]
+    pub fn with_language_stats(mut self) -> Self {
+        self.language_stats = true;
+        self
+    }
+
+    /// Let the browser refuse files and pastes larger than `size` bytes before uploading them.
+    #[must_use]
+    pub fn with_max_body_size(mut self, size: usize) -> Self {
+        self.max_body_size = Some(size);
+        self
+    }
+
+    /// Reference static assets below `base`, e.g. on a CDN, instead of this host.
+    #[must_use]
+    pub fn with_asset_url(mut self, base: &Url) -> Self {
+        self.assets.rebase(base);
+        self.asset_origin = Some(base.origin().ascii_serialization());
+        self
+    }
+
+    /// Render pages with the `overrides` instead of the embedded templates.
+    #[must_use]
+    pub fn with_templates(mut self, overrides: Overrides) -> Self {
+        self.templates = Some(overrides);
+        self
+    }
 }
 
 impl Assets {
-    /// Create page [`Assets`] for the given `theme`.
-    fn new(theme: Theme) -> Self {
+    /// Return `true` if `route` serves one of the assets.
+    pub fn contains(&self, route: &str) -> bool {
+        [
+            &self.favicon,
+            &self.favicon_ico,
+            &self.robots_txt,
+            &self.base_js,
+            &self.index_js,
+            &self.paste_js,
+            &self.pow_js,
+            &self.zk_js,
+            &self.service_worker,
+            &self.css.style,
+            &self.css.noscript,
+        ]
+        .into_iter()
+        .chain(
+            self.css
+                .themes
+                .values()
+                .flat_map(|css| [&css.light, &css.dark]),
+        )
+        .chain(self.custom())
+        .chain(&self.touch_icon)
+        .any(|asset| asset.route() == route)
+    }
+
+    /// Custom stylesheet and logo served by this host.
+    pub fn custom(&self) -> impl Iterator<Item = &Asset> {
+        [&self.custom_css, &self.logo]
+            .into_iter()
+            .filter_map(|custom| custom.as_ref()?.local())
+    }
+
+    /// Reference all assets below `base`.
+    fn rebase(&mut self, base: &Url) {
+        for asset in [
+            &mut self.favicon,
+            &mut self.favicon_ico,
+            &mut self.base_js,
+            &mut self.index_js,
+            &mut self.paste_js,
+            &mut self.pow_js,
+            &mut self.zk_js,
+        ] {
+            asset.rebase(base);
+        }
+
+        if let Some(icon) = &mut self.touch_icon {
+            icon.rebase(base);
+        }
+
+        for custom in [&mut self.custom_css, &mut self.logo].into_iter().flatten() {
+            if let Custom::Local(asset) = custom {
+                asset.rebase(base);
+            }
+        }
+
+        self.css.rebase(base);
+    }
+
+    /// Create page [`Assets`] for the given default `theme` and `custom` themes.
+    fn new(theme: &Theme, custom: &[Theme]) -> Self {
+        let favicon = include_bytes!("../assets/favicon.png").to_vec();
+
         Self {
-            favicon: Asset::new(
-                "favicon.ico",
-                mime::IMAGE_PNG,
-                include_bytes!("../assets/favicon.png").to_vec(),
-            ),
-            css: Css::new(theme),
+            favicon: Asset::new_hashed("favicon", Kind::Png, favicon.clone()),
+            favicon_ico: Asset::new("favicon.ico", mime::IMAGE_PNG, favicon),
+            css: Css::new(theme, custom),
             base_js: Asset::new_hashed(
                 "base",
                 Kind::Js,
@@ -62,6 +429,25 @@ impl Assets {
                 Kind::Js,
                 include_bytes!("javascript/paste.js").to_vec(),
             ),
+            pow_js: Asset::new_hashed(
+                "pow",
+                Kind::Js,
+                include_bytes!("javascript/pow.js").to_vec(),
+            ),
+            zk_js: Asset::new_hashed("zk", Kind::Js, include_bytes!("javascript/zk.js").to_vec()),
+            service_worker: Asset::new(
+                "sw.js",
+                mime::APPLICATION_JAVASCRIPT_UTF_8,
+                include_bytes!("javascript/sw.js").to_vec(),
+            ),
+            robots_txt: Asset::new(
+                "robots.txt",
+                mime::TEXT_PLAIN_UTF_8,
+                include_bytes!("../assets/robots.txt").to_vec(),
+            ),
+            touch_icon: None,
+            custom_css: None,
+            logo: None,
         }
     }
 }
@@ -0,0 +1,164 @@
+use crate::errors::Error;
+use axum::http::HeaderName;
+use axum_extra::extract::cookie::Key;
+use hmac::{Hmac, Mac};
+use parking_lot::Mutex;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Header carrying the solution of anonymous API requests.
+pub(crate) const HEADER: HeaderName = HeaderName::from_static("wastebin-pow");
+
+/// Seconds a challenge can be solved and submitted in.
+const VALIDITY: u64 = 10 * 60;
+
+/// Challenge handed out to clients. A solution is `<challenge>:<nonce>` whose SHA-256 digest
+/// starts with `difficulty` zero bits.
+#[derive(Debug, Serialize)]
+pub(crate) struct Challenge {
+    pub challenge: String,
+    pub difficulty: u8,
+}
+
+/// Issues and verifies proof-of-work challenges. Challenges are signed and thus need no
+/// storage, only solutions already used are remembered until their challenge expires.
+#[derive(Clone)]
+pub(crate) struct Pow {
+    key: Key,
+    difficulty: u8,
+    used: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Return `true` if `digest` starts with at least `bits` zero bits.
+fn has_leading_zeros(digest: &[u8], bits: u8) -> bool {
+    let mut zeros = 0;
+
+    for byte in digest {
+        zeros += byte.leading_zeros();
+
+        if *byte != 0 {
+            break;
+        }
+    }
+
+    zeros >= u32::from(bits)
+}
+
+impl Pow {
+    /// Create challenges of `difficulty` leading zero bits signed with `key`.
+    pub fn new(key: Key, difficulty: u8) -> Self {
+        Self {
+            key,
+            difficulty,
+            used: Arc::default(),
+        }
+    }
+
+    fn mac(&self, payload: &str) -> Option<Hmac<Sha256>> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.key.signing()).ok()?;
+        mac.update(b"pow");
+        mac.update(payload.as_bytes());
+        Some(mac)
+    }
+
+    /// Issue a new challenge.
+    pub fn challenge(&self) -> Result<Challenge, Error> {
+        let payload = format!(
+            "{}.{}",
+            now() + VALIDITY,
+            hex::encode(rand::random::<[u8; 16]>())
+        );
+        let mac = self.mac(&payload).ok_or(Error::ProofOfWork)?;
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        Ok(Challenge {
+            challenge: format!("{payload}.{signature}"),
+            difficulty: self.difficulty,
+        })
+    }
+
+    /// Check that `solution` solves an unexpired challenge issued by us that was not solved
+    /// before.
+    pub fn verify(&self, solution: &str) -> Result<(), Error> {
+        let (challenge, _nonce) = solution.rsplit_once(':').ok_or(Error::ProofOfWork)?;
+        let (payload, signature) = challenge.rsplit_once('.').ok_or(Error::ProofOfWork)?;
+        let signature = hex::decode(signature).map_err(|_| Error::ProofOfWork)?;
+
+        if self
+            .mac(payload)
+            .is_none_or(|mac| mac.verify_slice(&signature).is_err())
+        {
+            return Err(Error::ProofOfWork);
+        }
+
+        let expires = payload
+            .split_once('.')
+            .and_then(|(expires, _)| expires.parse::<u64>().ok())
+            .ok_or(Error::ProofOfWork)?;
+        let now = now();
+
+        if expires < now || !has_leading_zeros(&Sha256::digest(solution), self.difficulty) {
+            return Err(Error::ProofOfWork);
+        }
+
+        let mut used = self.used.lock();
+        used.retain(|_, expires| *expires >= now);
+
+        if used.insert(challenge.to_string(), expires).is_some() {
+            return Err(Error::ProofOfWork);
+        }
+
+        Ok(())
+    }
+}
+
+/// Find a solution of `challenge` the way the browser does.
+#[cfg(test)]
+pub(crate) fn solve(challenge: &Challenge) -> String {
+    (0..u64::MAX)
+        .map(|nonce| format!("{}:{nonce}", challenge.challenge))
+        .find(|solution| has_leading_zeros(&Sha256::digest(solution), challenge.difficulty))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leading_zeros() {
+        assert!(has_leading_zeros(&[0, 0x0f], 12));
+        assert!(!has_leading_zeros(&[0, 0x1f], 12));
+        assert!(has_leading_zeros(&[0x80], 0));
+        assert!(!has_leading_zeros(&[0, 0], 17));
+    }
+
+    #[test]
+    fn challenges() {
+        let pow = Pow::new(Key::generate(), 8);
+        let challenge = pow.challenge().unwrap();
+        let solution = solve(&challenge);
+
+        assert!(pow.verify(&solution).is_ok());
+        assert!(matches!(pow.verify(&solution), Err(Error::ProofOfWork)));
+
+        let challenge = pow.challenge().unwrap();
+        let solution = solve(&challenge);
+        let other = Pow::new(Key::generate(), 8);
+        assert!(matches!(other.verify(&solution), Err(Error::ProofOfWork)));
+
+        let tampered = solution.replacen('.', "0.", 1);
+        assert!(matches!(pow.verify(&tampered), Err(Error::ProofOfWork)));
+        assert!(matches!(pow.verify("garbage"), Err(Error::ProofOfWork)));
+        assert!(pow.verify(&solution).is_ok());
+    }
+}
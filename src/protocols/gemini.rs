@@ -0,0 +1,190 @@
+use super::Failure;
+use crate::{AppState, Page};
+use axum::extract::FromRef;
+use std::future::Future;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::{self, ServerConfig};
+
+/// Longest request URL the protocol allows, without the terminating CRLF.
+const MAX_REQUEST: usize = 1024;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("failed to read {0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("no private key in {0}")]
+    NoKey(String),
+    #[error("invalid certificate or key: {0}")]
+    Tls(#[from] rustls::Error),
+}
+
+/// TLS acceptor presenting the PEM encoded certificate chain in `cert` with the private key in
+/// `key`.
+pub(crate) fn acceptor(cert: &Path, key: &Path) -> Result<TlsAcceptor, Error> {
+    let open =
+        |path: &Path| std::fs::read(path).map_err(|err| Error::Io(path.display().to_string(), err));
+
+    let certs = rustls_pemfile::certs(&mut open(cert)?.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| Error::Io(cert.display().to_string(), err))?;
+
+    let key = rustls_pemfile::private_key(&mut open(key)?.as_slice())
+        .map_err(|err| Error::Io(key.display().to_string(), err))?
+        .ok_or_else(|| Error::NoKey(key.display().to_string()))?;
+
+    let config =
+        ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)?;
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Accept Gemini connections on `listener` until `shutdown` completes and answer each request
+/// with the index or the plain text of a paste.
+pub(crate) async fn serve(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    state: AppState,
+    shutdown: impl Future<Output = ()>,
+) {
+    super::serve(listener, "Gemini", shutdown, |stream, ip| {
+        let acceptor = acceptor.clone();
+        let state = state.clone();
+
+        async move {
+            let handshake =
+                tokio::time::timeout(super::REQUEST_TIMEOUT, acceptor.accept(stream)).await;
+
+            match handshake {
+                Ok(Ok(mut stream)) => handle(&mut stream, ip, &state).await,
+                Ok(Err(err)) => tracing::debug!("failed TLS handshake with {ip}: {err}"),
+                Err(_) => tracing::debug!("TLS handshake with {ip} timed out"),
+            }
+        }
+    })
+    .await;
+}
+
+/// Read a request from `stream` sent by `ip` and write back the response.
+async fn handle(stream: &mut (impl AsyncRead + AsyncWrite + Unpin), ip: IpAddr, state: &AppState) {
+    let response = match super::read_line(stream, MAX_REQUEST).await {
+        Some(request) => respond(&request, ip, state).await,
+        None => Err(Failure::BadRequest),
+    };
+
+    let reply = match response {
+        Ok((mime, body)) => format!("20 {mime}; charset=utf-8\r\n{body}"),
+        Err(failure) => {
+            let status = match failure {
+                Failure::BadRequest => 59,
+                Failure::NotFound(_) => 51,
+                Failure::Gone => 52,
+                Failure::Refused(_) => 50,
+                Failure::Unavailable(_) => 40,
+            };
+
+            format!("{status} {failure}\r\n")
+        }
+    };
+
+    super::reply(stream, ip, reply.as_bytes()).await;
+}
+
+/// MIME type and body answering the `request` URL sent by a client at `ip`.
+async fn respond(
+    request: &str,
+    ip: IpAddr,
+    state: &AppState,
+) -> Result<(&'static str, String), Failure> {
+    let url = url::Url::parse(request).map_err(|_| Failure::BadRequest)?;
+
+    if url.scheme() != "gemini" || !url.username().is_empty() || url.password().is_some() {
+        return Err(Failure::BadRequest);
+    }
+
+    super::check_access(state, ip)?;
+
+    match url.path().trim_start_matches('/') {
+        "" => Ok(("text/gemini", index(&Page::from_ref(state)))),
+        path => Ok(("text/plain", super::paste(state, path).await?)),
+    }
+}
+
+/// Gemtext page describing the capsule and linking to the web interface.
+fn index(page: &Page) -> String {
+    format!(
+        "# {}\n\nPastes can be read here as plain text by their ID, like on the web. New pastes \
+         are created on the web.\n\n=> {} {}\n",
+        page.title, page.base_url, page.title
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::Database;
+    use crate::db::write::Entry;
+    use crate::highlight::Highlighter;
+    use crate::id::Id;
+    use axum::extract::FromRef;
+    use std::net::{IpAddr, Ipv4Addr};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Send `request` and return the response.
+    async fn request(state: &crate::AppState, request: &[u8]) -> std::io::Result<String> {
+        let (mut client, mut server) = tokio::io::duplex(4096);
+        client.write_all(request).await?;
+
+        super::handle(&mut server, IpAddr::V4(Ipv4Addr::LOCALHOST), state).await;
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).await?;
+
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn serve_pastes() -> Result<(), Box<dyn std::error::Error>> {
+        let state = crate::test_helpers::state(crate::test_helpers::page(), Highlighter::default());
+        let db = Database::from_ref(&state);
+
+        let id = Id::new();
+        db.insert(
+            id,
+            Entry {
+                text: String::from("fn main() {}\n"),
+                extension: Some(String::from("rs")),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let response = request(&state, b"gemini://localhost/\r\n").await?;
+        assert!(response.starts_with("20 text/gemini; charset=utf-8\r\n# test\n"));
+        assert!(response.contains("=> https://localhost:8888/ test\n"));
+
+        let response =
+            request(&state, format!("gemini://localhost/{id}.rs\r\n").as_bytes()).await?;
+        assert_eq!(response, "20 text/plain; charset=utf-8\r\nfn main() {}\n");
+
+        let response = request(&state, b"gemini://localhost/000000\r\n").await?;
+        assert!(response.starts_with("51 "));
+
+        let response = request(&state, b"https://localhost/\r\n").await?;
+        assert_eq!(response, "59 bad request\r\n");
+
+        let mut long = b"gemini://localhost/".to_vec();
+        long.extend_from_slice(&[b'a'; 1024]);
+        long.extend_from_slice(b"\r\n");
+        let response = request(&state, &long).await?;
+        assert_eq!(response, "59 bad request\r\n");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,238 @@
+use super::Failure;
+use crate::db::read::Listing;
+use crate::{AppState, Database, Page};
+use axum::extract::FromRef;
+use std::fmt::Write;
+use std::future::Future;
+use std::net::IpAddr;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+
+/// Longest selector accepted, as recommended by the protocol.
+const MAX_SELECTOR: usize = 255;
+
+/// Number of pastes listed in the menu.
+const RECENT: usize = 20;
+
+/// Accept Gopher connections on `listener` until `shutdown` completes and answer each request
+/// with the root menu, listing recent public pastes if `listing`, or the plain text of a paste.
+pub(crate) async fn serve(
+    listener: TcpListener,
+    state: AppState,
+    listing: bool,
+    shutdown: impl Future<Output = ()>,
+) {
+    // Menu items point back at this listener under the host name of the web interface.
+    let port = listener.local_addr().map_or(70, |addr| addr.port());
+
+    super::serve(listener, "Gopher", shutdown, |mut stream, ip| {
+        let state = state.clone();
+
+        async move { handle(&mut stream, ip, &state, listing, port).await }
+    })
+    .await;
+}
+
+/// Read a selector from `stream` sent by `ip` and write back the response.
+async fn handle(
+    stream: &mut (impl AsyncRead + AsyncWrite + Unpin),
+    ip: IpAddr,
+    state: &AppState,
+    listing: bool,
+    port: u16,
+) {
+    let response = match super::read_line(stream, MAX_SELECTOR).await {
+        Some(selector) => respond(&selector, ip, state, listing, port).await,
+        None => Err(Failure::BadRequest),
+    };
+
+    let reply = response.unwrap_or_else(|failure| {
+        format!(
+            "3{}\terror\terror.host\t1\r\n.\r\n",
+            clean(&failure.to_string())
+        )
+    });
+
+    super::reply(stream, ip, reply.as_bytes()).await;
+}
+
+/// Menu or text answering `selector` sent by a client at `ip`.
+async fn respond(
+    selector: &str,
+    ip: IpAddr,
+    state: &AppState,
+    listing: bool,
+    port: u16,
+) -> Result<String, Failure> {
+    super::check_access(state, ip)?;
+
+    // Gopher+ clients append attributes after a tab.
+    let selector = selector.split('\t').next().unwrap_or_default();
+
+    match selector.trim_start_matches('/') {
+        "" if listing => Ok(menu(
+            &Page::from_ref(state),
+            Some(&Database::from_ref(state).recent(RECENT).await?),
+            port,
+        )),
+        "" => Ok(menu(&Page::from_ref(state), None, port)),
+        path => Ok(text(&super::paste(state, path).await?)),
+    }
+}
+
+/// Menu linking to the web interface and listing the most recent public `pastes`, if given.
+fn menu(page: &Page, pastes: Option<&[Listing]>, port: u16) -> String {
+    let host = page.base_url.host_str().unwrap_or("localhost");
+    let mut menu = String::new();
+
+    let _ = write!(menu, "i{}\t\t(NULL)\t0\r\n", clean(&page.title));
+    menu.push_str("i\t\t(NULL)\t0\r\n");
+
+    match pastes {
+        Some([]) => menu.push_str("iNo pastes yet.\t\t(NULL)\t0\r\n"),
+        Some(pastes) => {
+            for paste in pastes {
+                let _ = write!(
+                    menu,
+                    "0{}\t/{}\t{host}\t{port}\r\n",
+                    clean(&paste.name()),
+                    paste.path()
+                );
+            }
+        }
+        None => menu.push_str("iRequest /<id> for the text of a paste.\t\t(NULL)\t0\r\n"),
+    }
+
+    menu.push_str("i\t\t(NULL)\t0\r\n");
+    let _ = write!(
+        menu,
+        "hCreate pastes on the web\tURL:{}\t{host}\t{port}\r\n",
+        page.base_url
+    );
+    menu.push_str(".\r\n");
+
+    menu
+}
+
+/// `body` as a text document, with CRLF line endings, lines starting with a dot escaped and the
+/// terminating dot line.
+fn text(body: &str) -> String {
+    let mut text = String::with_capacity(body.len() + 3);
+
+    for line in body.lines() {
+        if line.starts_with('.') {
+            text.push('.');
+        }
+
+        text.push_str(line);
+        text.push_str("\r\n");
+    }
+
+    text.push_str(".\r\n");
+    text
+}
+
+/// `display` with tabs and line breaks, which would break the menu, replaced by spaces.
+fn clean(display: &str) -> String {
+    display
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::Database;
+    use crate::db::write::Entry;
+    use crate::highlight::Highlighter;
+    use crate::id::Id;
+    use axum::extract::FromRef;
+    use std::net::{IpAddr, Ipv4Addr};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Send `selector` and return the response.
+    async fn request(
+        state: &crate::AppState,
+        listing: bool,
+        selector: &[u8],
+    ) -> std::io::Result<String> {
+        let (mut client, mut server) = tokio::io::duplex(4096);
+        client.write_all(selector).await?;
+
+        super::handle(
+            &mut server,
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+            state,
+            listing,
+            7070,
+        )
+        .await;
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).await?;
+
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn serve_pastes() -> Result<(), Box<dyn std::error::Error>> {
+        let state = crate::test_helpers::state(crate::test_helpers::page(), Highlighter::default());
+        let db = Database::from_ref(&state);
+
+        let public = Id::new();
+        db.insert(
+            public,
+            Entry {
+                text: String::from(".hidden\nfn main() {}\n"),
+                title: Some(String::from("main\tfunction")),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let secret = Id::new();
+        db.insert(
+            secret,
+            Entry {
+                text: String::from("secret"),
+                secret: Some(String::from("0123abcd")),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let burned = Id::new();
+        db.insert(
+            burned,
+            Entry {
+                text: String::from("burn"),
+                burn_after_reading: Some(true),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let menu = request(&state, true, b"\r\n").await?;
+        assert!(menu.starts_with("itest\t\t(NULL)\t0\r\n"));
+        assert!(menu.contains(&format!("0main function\t/{public}\tlocalhost\t7070\r\n")));
+        assert!(!menu.contains(&secret.to_string()));
+        assert!(!menu.contains(&burned.to_string()));
+        assert!(menu.contains("hCreate pastes on the web\tURL:https://localhost:8888/\t"));
+        assert!(menu.ends_with(".\r\n"));
+
+        let menu = request(&state, false, b"\r\n").await?;
+        assert!(!menu.contains(&public.to_string()));
+        assert!(menu.contains("hCreate pastes on the web\t"));
+
+        let text = request(&state, false, format!("/{public}\r\n").as_bytes()).await?;
+        assert_eq!(text, "..hidden\r\nfn main() {}\r\n.\r\n");
+
+        let text = request(&state, false, format!("{secret}_0123abcd\n").as_bytes()).await?;
+        assert_eq!(text, "secret\r\n.\r\n");
+
+        let text = request(&state, false, b"/000000\r\n").await?;
+        assert!(text.starts_with("3entry not found\t"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+//! Read-only frontends serving pastes over protocols other than HTTP.
+
+use crate::cache::Key;
+use crate::db::read::Entry;
+use crate::{AppState, Database, access, errors};
+use axum::extract::FromRef;
+use axum::http::StatusCode;
+use std::fmt;
+use std::future::Future;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+#[cfg(feature = "gemini")]
+pub mod gemini;
+#[cfg(feature = "gopher")]
+pub mod gopher;
+
+/// Time a client has to send its request, including a TLS handshake.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Reason a request cannot be answered with a paste.
+#[derive(Debug, PartialEq, Eq)]
+enum Failure {
+    /// The request cannot be parsed.
+    BadRequest,
+    NotFound(String),
+    /// The paste existed but has expired.
+    Gone,
+    /// The paste or the client is refused, for good.
+    Refused(String),
+    /// The request failed on the server and may be retried.
+    Unavailable(String),
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadRequest => f.write_str("bad request"),
+            Self::Gone => f.write_str("paste has expired"),
+            Self::NotFound(message) | Self::Refused(message) | Self::Unavailable(message) => {
+                f.write_str(message)
+            }
+        }
+    }
+}
+
+impl From<errors::Error> for Failure {
+    fn from(err: errors::Error) -> Self {
+        let message = err.to_string();
+
+        match StatusCode::from(err) {
+            StatusCode::NOT_FOUND => Self::NotFound(message),
+            status if status.is_client_error() => Self::Refused(message),
+            _ => Self::Unavailable(message),
+        }
+    }
+}
+
+/// Accept connections on `listener` until `shutdown` completes and pass each one with the
+/// client's address to `handle`. `protocol` names the frontend in log messages.
+async fn serve<F, Fut>(
+    listener: TcpListener,
+    protocol: &'static str,
+    shutdown: impl Future<Output = ()>,
+    handle: F,
+) where
+    F: Fn(TcpStream, IpAddr) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    tokio::pin!(shutdown);
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            () = &mut shutdown => return,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    tracing::warn!("failed to accept {protocol} connection: {err}");
+                    continue;
+                }
+            },
+        };
+
+        tokio::spawn(handle(stream, peer.ip()));
+    }
+}
+
+/// Read a line of at most `max` bytes from `reader`, without the terminating LF or CRLF. Returns
+/// `None` if the line is too long, not UTF-8, incomplete or not sent within [`REQUEST_TIMEOUT`].
+async fn read_line(reader: &mut (impl AsyncRead + Unpin), max: usize) -> Option<String> {
+    let read = async {
+        let mut data = Vec::new();
+        let mut buf = [0; 1024];
+
+        loop {
+            if let Some(end) = data.iter().position(|&byte| byte == b'\n') {
+                data.truncate(end);
+
+                if data.last() == Some(&b'\r') {
+                    data.pop();
+                }
+
+                return (data.len() <= max)
+                    .then(|| String::from_utf8(data).ok())
+                    .flatten();
+            }
+
+            // Leave room for the CRLF.
+            if data.len() > max + 1 {
+                return None;
+            }
+
+            let read = reader.read(&mut buf).await.ok()?;
+
+            if read == 0 {
+                return None;
+            }
+
+            data.extend_from_slice(&buf[..read]);
+        }
+    };
+
+    tokio::time::timeout(REQUEST_TIMEOUT, read)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Write `reply` to the client at `ip` and close the connection.
+async fn reply(stream: &mut (impl AsyncWrite + Unpin), ip: IpAddr, reply: &[u8]) {
+    if let Err(err) = stream.write_all(reply).await {
+        tracing::debug!("failed to reply to {ip}: {err}");
+    }
+
+    let _ = stream.shutdown().await;
+}
+
+/// Check that the client at `ip` may read pastes.
+fn check_access(state: &AppState, ip: IpAddr) -> Result<(), Failure> {
+    if access::Filter::from_ref(state).access.read.permits(ip) {
+        Ok(())
+    } else {
+        Err(errors::Error::AddressDenied.into())
+    }
+}
+
+/// Text of the paste at `path`, an identifier with an optional token and extension as in web
+/// URLs. Password protected and end-to-end encrypted pastes are refused.
+async fn paste(state: &AppState, path: &str) -> Result<String, Failure> {
+    let key: Key = path.parse().map_err(|_| errors::Error::NotFound)?;
+
+    let data = match Database::from_ref(state)
+        .get(key.id, key.token.as_deref(), None)
+        .await?
+    {
+        Entry::Regular(data) | Entry::Burned(data) => data,
+        Entry::Expired => return Err(Failure::Gone),
+    };
+
+    if data.ciphertext {
+        return Err(Failure::Refused(String::from(
+            "paste is encrypted in the browser and cannot be shown",
+        )));
+    }
+
+    Ok(data.text)
+}
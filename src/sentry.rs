@@ -0,0 +1,311 @@
+use crate::access_log;
+use axum::extract::{Request, State};
+use axum::http::header::USER_AGENT;
+use axum::middleware::Next;
+use axum::response::Response;
+use serde_json::{Value, json};
+use std::cell::RefCell;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Maximum number of events waiting to be sent. Further events are dropped.
+const QUEUE_SIZE: usize = 256;
+
+/// Release the events are tagged with.
+const RELEASE: &str = concat!(env!("CARGO_PKG_NAME"), "@", env!("CARGO_PKG_VERSION"));
+
+tokio::task_local! {
+    /// Context of the request handled by the current task.
+    static SCOPE: RefCell<Scope>;
+}
+
+struct Scope {
+    request: Value,
+    /// Message of the error that caused a server error response.
+    error: Option<String>,
+}
+
+/// Reporter of panics and server errors to Sentry.
+#[derive(Clone)]
+pub(crate) struct Reporter {
+    environment: Option<Arc<str>>,
+    sender: mpsc::Sender<Value>,
+}
+
+/// Remember `message` as the cause of the server error response to the current request.
+pub(crate) fn record_error(message: &str) {
+    let _ = SCOPE.try_with(|scope| {
+        if let Ok(mut scope) = scope.try_borrow_mut() {
+            scope.error = Some(message.to_string());
+        }
+    });
+}
+
+/// Request context of the current task, if any.
+fn current_request() -> Option<Value> {
+    SCOPE
+        .try_with(|scope| scope.try_borrow().ok().map(|scope| scope.request.clone()))
+        .ok()
+        .flatten()
+}
+
+impl Reporter {
+    /// Create a reporter tagging events with `environment` and spawn the task sending them to
+    /// the project of `dsn`.
+    pub fn new(dsn: &url::Url, environment: Option<String>) -> Self {
+        let (sender, receiver) = mpsc::channel(QUEUE_SIZE);
+
+        tokio::spawn(send(dsn.clone(), receiver));
+
+        Self {
+            environment: environment.map(Arc::from),
+            sender,
+        }
+    }
+
+    /// Report panics before passing them on to the previously installed hook.
+    pub fn install_panic_hook(&self) {
+        let reporter = self.clone();
+        let previous = std::panic::take_hook();
+
+        std::panic::set_hook(Box::new(move |info| {
+            let payload = info.payload();
+            let message = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("Box<dyn Any>");
+            let message = match info.location() {
+                Some(location) => format!("{message} at {location}"),
+                None => message.to_string(),
+            };
+
+            reporter.capture("fatal", "panic", &message, current_request());
+            previous(info);
+        }));
+    }
+
+    fn capture(&self, level: &str, kind: &str, message: &str, request: Option<Value>) {
+        let timestamp = time::OffsetDateTime::now_utc()
+            .format(&time::format_description::well_known::Rfc3339)
+            .unwrap_or_default();
+
+        let mut event = json!({
+            "event_id": hex::encode(rand::random::<[u8; 16]>()),
+            "timestamp": timestamp,
+            "platform": "other",
+            "level": level,
+            "release": RELEASE,
+            "exception": {
+                "values": [{
+                    "type": kind,
+                    "value": message,
+                    "mechanism": { "type": kind, "handled": kind != "panic" },
+                }],
+            },
+        });
+
+        if let Some(environment) = &self.environment {
+            event["environment"] = Value::from(environment.as_ref());
+        }
+
+        if let Some(request) = request {
+            event["request"] = request;
+        }
+
+        // Requests must not wait for Sentry, so events are dropped when the queue is full.
+        let _ = self.sender.try_send(event);
+    }
+}
+
+/// Report server error responses together with the method, path and user agent of the request.
+/// The query is left out and capability tokens in the path are redacted because they grant access.
+pub(crate) async fn report(
+    State(reporter): State<Option<Reporter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(reporter) = reporter else {
+        return next.run(request).await;
+    };
+
+    let context = json!({
+        "method": request.method().as_str(),
+        "url": access_log::redact(request.uri().path()),
+        "headers": request
+            .headers()
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map_or_else(|| json!({}), |agent| json!({ "User-Agent": agent })),
+    });
+
+    let scope = RefCell::new(Scope {
+        request: context.clone(),
+        error: None,
+    });
+
+    let (response, error) = SCOPE
+        .scope(scope, async move {
+            let response = next.run(request).await;
+            let error = SCOPE.with(|scope| scope.borrow_mut().error.take());
+            (response, error)
+        })
+        .await;
+
+    let status = response.status();
+
+    if status.is_server_error() {
+        let kind = status.canonical_reason().unwrap_or("Server Error");
+        let message = error.unwrap_or_else(|| status.to_string());
+        reporter.capture("error", kind, &message, Some(context));
+    }
+
+    response
+}
+
+/// Envelope endpoint and authentication header of the project of `dsn`, which looks like
+/// `https://<key>@<host>/<project>`.
+fn endpoint(dsn: &url::Url) -> Option<(url::Url, String)> {
+    let key = dsn.username();
+    let (prefix, project) = dsn.path().rsplit_once('/')?;
+
+    if key.is_empty() || project.is_empty() {
+        return None;
+    }
+
+    let mut endpoint = dsn.clone();
+    endpoint.set_username("").ok()?;
+    endpoint.set_password(None).ok()?;
+    endpoint.set_path(&format!("{prefix}/api/{project}/envelope/"));
+
+    let auth = format!(
+        "Sentry sentry_version=7, sentry_client={}/{}, sentry_key={key}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    );
+
+    Some((endpoint, auth))
+}
+
+/// Envelope containing the single `event`.
+fn envelope(dsn: &url::Url, event: &Value) -> String {
+    let header = json!({ "event_id": event["event_id"], "dsn": dsn.as_str() });
+    let event = event.to_string();
+    let item = json!({ "type": "event", "length": event.len() });
+
+    format!("{header}\n{item}\n{event}\n")
+}
+
+/// Send events from `receiver` to the project of `dsn` until all senders are gone.
+async fn send(dsn: url::Url, mut receiver: mpsc::Receiver<Value>) {
+    let Some((endpoint, auth)) = endpoint(&dsn) else {
+        tracing::warn!("invalid Sentry DSN, not reporting errors");
+        return;
+    };
+
+    let client = reqwest::Client::new();
+
+    while let Some(event) = receiver.recv().await {
+        let result = client
+            .post(endpoint.clone())
+            .header("X-Sentry-Auth", &auth)
+            .header("Content-Type", "application/x-sentry-envelope")
+            .body(envelope(&dsn, &event))
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+
+        if let Err(err) = result {
+            tracing::warn!("failed to report error to Sentry: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::http::StatusCode;
+    use axum::middleware::from_fn_with_state;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    fn reporter() -> (Reporter, mpsc::Receiver<Value>) {
+        let (sender, receiver) = mpsc::channel(16);
+        let reporter = Reporter {
+            environment: Some(Arc::from("test")),
+            sender,
+        };
+
+        (reporter, receiver)
+    }
+
+    #[test]
+    fn dsn() {
+        let dsn = url::Url::parse("https://abc@sentry.example.com/prefix/42").unwrap();
+        let (url, auth) = endpoint(&dsn).unwrap();
+
+        assert_eq!(
+            url.as_str(),
+            "https://sentry.example.com/prefix/api/42/envelope/"
+        );
+        assert!(auth.ends_with("sentry_key=abc"));
+
+        let dsn = url::Url::parse("https://sentry.example.com/42").unwrap();
+        assert!(endpoint(&dsn).is_none());
+    }
+
+    #[tokio::test]
+    async fn server_errors() -> Result<(), Box<dyn std::error::Error>> {
+        let (reporter, mut receiver) = reporter();
+
+        let broken =
+            || async { StatusCode::from(crate::Error::Compression(String::from("zstd exploded"))) };
+        let app = Router::new()
+            .route("/broken", get(broken))
+            .route("/broken/:id", get(broken))
+            .route("/missing", get(|| async { StatusCode::NOT_FOUND }))
+            .layer(from_fn_with_state(Some(reporter), report));
+
+        let request = Request::builder()
+            .uri("/broken?token=secret")
+            .header(USER_AGENT, "curl")
+            .body(axum::body::Body::empty())?;
+        let response = app.clone().oneshot(request).await?;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let request = Request::builder()
+            .uri("/missing")
+            .body(axum::body::Body::empty())?;
+        app.clone().oneshot(request).await?;
+
+        let event = receiver.try_recv()?;
+        assert!(receiver.try_recv().is_err());
+
+        assert_eq!(event["level"], "error");
+        assert_eq!(event["release"], RELEASE);
+        assert_eq!(event["environment"], "test");
+        assert_eq!(
+            event["exception"]["values"][0]["value"],
+            "compression error: zstd exploded"
+        );
+        assert_eq!(event["request"]["method"], "GET");
+        assert_eq!(event["request"]["url"], "/broken");
+        assert_eq!(event["request"]["headers"]["User-Agent"], "curl");
+
+        let envelope = envelope(&url::Url::parse("https://abc@localhost/1")?, &event);
+        let lines = envelope.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains(event["event_id"].as_str().unwrap()));
+
+        let request = Request::builder()
+            .uri("/broken/sIiFec_0123abcd")
+            .body(axum::body::Body::empty())?;
+        app.oneshot(request).await?;
+
+        let event = receiver.try_recv()?;
+        assert_eq!(event["request"]["url"], "/broken/sIiFec_redacted");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// Action triggered by a keyboard shortcut.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Action {
+    Home,
+    Here,
+    Raw,
+    Download,
+    Qr,
+    CopyUrl,
+    CopyRawUrl,
+    CopyContent,
+    Wrap,
+    Editor,
+    Help,
+}
+
+/// Pages with keyboard shortcuts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Scope {
+    Index,
+    Paste,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("expected action=key, got {0}")]
+    Format(String),
+    #[error("unknown action {0}")]
+    Action(String),
+    #[error("{0} is not a single character")]
+    Key(String),
+    #[error("{0} is bound to more than one action")]
+    Duplicate(char),
+}
+
+/// Keys bound to the actions, in the order of the help overlay.
+#[derive(Clone, Debug)]
+pub(crate) struct Shortcuts(Vec<(Action, char)>);
+
+impl Action {
+    const ALL: [Self; 11] = [
+        Self::Home,
+        Self::Here,
+        Self::Raw,
+        Self::Download,
+        Self::Qr,
+        Self::CopyUrl,
+        Self::CopyRawUrl,
+        Self::CopyContent,
+        Self::Wrap,
+        Self::Editor,
+        Self::Help,
+    ];
+
+    /// Name used in `WASTEBIN_SHORTCUTS` and by the scripts.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Home => "home",
+            Self::Here => "here",
+            Self::Raw => "raw",
+            Self::Download => "download",
+            Self::Qr => "qr",
+            Self::CopyUrl => "copy-url",
+            Self::CopyRawUrl => "copy-raw-url",
+            Self::CopyContent => "copy-content",
+            Self::Wrap => "wrap",
+            Self::Editor => "editor",
+            Self::Help => "help",
+        }
+    }
+
+    /// Message describing the action in the help overlay.
+    pub fn help(self) -> &'static str {
+        match self {
+            Self::Home => "help-home",
+            Self::Here => "help-here",
+            Self::Raw => "help-raw",
+            Self::Download => "help-download",
+            Self::Qr => "help-qr",
+            Self::CopyUrl => "help-copy-url",
+            Self::CopyRawUrl => "help-copy-raw-url",
+            Self::CopyContent => "help-copy-content",
+            Self::Wrap => "help-wrap",
+            Self::Editor => "help-editor",
+            Self::Help => "help-toggle",
+        }
+    }
+
+    fn default_key(self) -> char {
+        match self {
+            Self::Home => 'n',
+            Self::Here => 'p',
+            Self::Raw => 'r',
+            Self::Download => 'd',
+            Self::Qr => 'q',
+            Self::CopyUrl => 'y',
+            Self::CopyRawUrl => 'u',
+            Self::CopyContent => 'c',
+            Self::Wrap => 'w',
+            Self::Editor => 'e',
+            Self::Help => '?',
+        }
+    }
+
+    /// Return `true` if the action is available on pages of `scope`.
+    fn applies_to(self, scope: Scope) -> bool {
+        match self {
+            Self::Home | Self::Help => true,
+            Self::Editor => scope == Scope::Index,
+            _ => scope == Scope::Paste,
+        }
+    }
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        Self(
+            Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_key()))
+                .collect(),
+        )
+    }
+}
+
+impl Shortcuts {
+    /// Shortcuts available on pages of `scope`.
+    pub fn on(&self, scope: &Scope) -> impl Iterator<Item = (Action, char)> + '_ {
+        let scope = *scope;
+
+        self.0
+            .iter()
+            .copied()
+            .filter(move |(action, _)| action.applies_to(scope))
+    }
+
+    /// JSON object mapping the names of the actions available on pages of `scope` to their keys.
+    #[expect(clippy::trivially_copy_pass_by_ref)]
+    pub fn json(&self, scope: &Scope) -> String {
+        let map = self
+            .on(scope)
+            .map(|(action, key)| (action.name().to_string(), key.to_string().into()))
+            .collect::<serde_json::Map<_, _>>();
+
+        serde_json::Value::Object(map).to_string()
+    }
+}
+
+impl FromStr for Shortcuts {
+    type Err = Error;
+
+    /// Parse comma separated `action=key` pairs, which rebind the default keys of the actions.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut shortcuts = Self::default();
+
+        for pair in s.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (name, key) = pair
+                .split_once('=')
+                .ok_or_else(|| Error::Format(pair.to_string()))?;
+
+            let action = Action::ALL
+                .into_iter()
+                .find(|action| action.name() == name.trim())
+                .ok_or_else(|| Error::Action(name.trim().to_string()))?;
+
+            let mut chars = key.trim().chars();
+
+            let (Some(key), None) = (chars.next(), chars.next()) else {
+                return Err(Error::Key(key.to_string()));
+            };
+
+            for (bound, old) in &mut shortcuts.0 {
+                if *bound == action {
+                    *old = key;
+                }
+            }
+        }
+
+        for scope in [Scope::Index, Scope::Paste] {
+            let mut seen = HashSet::new();
+
+            if let Some((_, key)) = shortcuts.on(&scope).find(|(_, key)| !seen.insert(*key)) {
+                return Err(Error::Duplicate(key));
+            }
+        }
+
+        Ok(shortcuts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebind() -> Result<(), Box<dyn std::error::Error>> {
+        let shortcuts = "home=h, wrap=W,help==".parse::<Shortcuts>()?;
+        let keys = shortcuts.on(&Scope::Paste).collect::<Vec<_>>();
+
+        assert!(keys.contains(&(Action::Home, 'h')));
+        assert!(keys.contains(&(Action::Wrap, 'W')));
+        assert!(keys.contains(&(Action::Help, '=')));
+        assert!(keys.contains(&(Action::Raw, 'r')));
+        assert!(!keys.iter().any(|(action, _)| *action == Action::Editor));
+
+        // The editor is only focused on the index page, where raw is not bound.
+        assert!("editor=r".parse::<Shortcuts>().is_ok());
+
+        assert!(matches!("home".parse::<Shortcuts>(), Err(Error::Format(_))));
+        assert!(matches!(
+            "back=b".parse::<Shortcuts>(),
+            Err(Error::Action(_))
+        ));
+        assert!(matches!("home=ab".parse::<Shortcuts>(), Err(Error::Key(_))));
+        assert!(matches!(
+            "home=r".parse::<Shortcuts>(),
+            Err(Error::Duplicate('r'))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn json() {
+        let json = Shortcuts::default().json(&Scope::Index);
+        assert_eq!(json, r#"{"home":"n","editor":"e","help":"?"}"#);
+    }
+}
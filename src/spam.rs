@@ -0,0 +1,113 @@
+use crate::errors::Error;
+use axum_extra::extract::cookie::Key;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Name of the form field hidden from humans that bots tend to fill in.
+pub(crate) const HONEYPOT: &str = "website";
+
+/// Signs the time a form was rendered and rejects submissions that come back sooner than a
+/// person could have filled it in.
+#[derive(Clone)]
+pub(crate) struct FillTime {
+    key: Key,
+    min: Duration,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Reject submissions with a non-empty `honeypot` field.
+pub(crate) fn check_honeypot(honeypot: Option<&str>) -> Result<(), Error> {
+    if honeypot.is_some_and(|value| !value.is_empty()) {
+        return Err(Error::Spam);
+    }
+
+    Ok(())
+}
+
+impl FillTime {
+    /// Require at least `min` between rendering and submitting a form signed with `key`.
+    pub fn new(key: Key, min: Duration) -> Self {
+        Self { key, min }
+    }
+
+    fn mac(&self, rendered: &str) -> Option<Hmac<Sha256>> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.key.signing()).ok()?;
+        mac.update(b"rendered");
+        mac.update(rendered.as_bytes());
+        Some(mac)
+    }
+
+    /// Signed stamp of the current time to embed into a form.
+    pub fn stamp(&self) -> String {
+        let rendered = now().to_string();
+
+        self.mac(&rendered).map_or_else(String::new, |mac| {
+            format!("{rendered}.{}", hex::encode(mac.finalize().into_bytes()))
+        })
+    }
+
+    /// Check that the form with the `stamp` was rendered at least the minimum fill time ago.
+    pub fn check(&self, stamp: Option<&str>) -> Result<(), Error> {
+        let (rendered, signature) = stamp
+            .and_then(|stamp| stamp.split_once('.'))
+            .ok_or(Error::Spam)?;
+        let signature = hex::decode(signature).map_err(|_| Error::Spam)?;
+
+        if self
+            .mac(rendered)
+            .is_none_or(|mac| mac.verify_slice(&signature).is_err())
+        {
+            return Err(Error::Spam);
+        }
+
+        let rendered = rendered.parse::<u64>().map_err(|_| Error::Spam)?;
+
+        if now().saturating_sub(rendered) < self.min.as_secs() {
+            return Err(Error::TooFast(self.min.as_secs()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn honeypot() {
+        assert!(check_honeypot(None).is_ok());
+        assert!(check_honeypot(Some("")).is_ok());
+        assert!(matches!(
+            check_honeypot(Some("https://spam.example")),
+            Err(Error::Spam)
+        ));
+    }
+
+    #[test]
+    fn fill_time() {
+        let key = Key::generate();
+
+        let fill_time = FillTime::new(key.clone(), Duration::from_secs(3));
+        let stamp = fill_time.stamp();
+        assert!(matches!(
+            fill_time.check(Some(&stamp)),
+            Err(Error::TooFast(3))
+        ));
+        assert!(matches!(fill_time.check(None), Err(Error::Spam)));
+        assert!(matches!(fill_time.check(Some("1.00")), Err(Error::Spam)));
+
+        let (rendered, signature) = stamp.split_once('.').unwrap();
+        let earlier = format!("{}.{signature}", rendered.parse::<u64>().unwrap() - 10);
+        assert!(matches!(fill_time.check(Some(&earlier)), Err(Error::Spam)));
+
+        let fill_time = FillTime::new(key, Duration::ZERO);
+        assert!(fill_time.check(Some(&stamp)).is_ok());
+    }
+}
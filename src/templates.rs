@@ -0,0 +1,368 @@
+use crate::Page;
+use crate::assets::{Asset, Custom};
+use crate::handlers::extract::Theme;
+use crate::i18n::{self, Arg};
+use crate::shortcuts::Scope;
+use askama::Template;
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Response};
+use fluent_bundle::FluentValue;
+use minijinja::value::Kwargs;
+use minijinja::{Environment, Value, context};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Pages operators may replace by a template of the same name, with the variables passed to it.
+const OVERRIDABLE: [(&str, &[&str]); 3] = [
+    ("error.html", &["page", "description"]),
+    (
+        "index.html",
+        &[
+            "page",
+            "csrf",
+            "locked",
+            "pow",
+            "rendered",
+            "honeypot",
+            "terms",
+            "captcha",
+            "expirations",
+            "languages",
+            "draft",
+        ],
+    ),
+    (
+        "paste.html",
+        &[
+            "page",
+            "csrf",
+            "id",
+            "path",
+            "title",
+            "extension",
+            "html",
+            "can_edit",
+            "can_duplicate",
+            "can_delete",
+            "is_available",
+            "highlight_skipped",
+            "highlight_pending",
+            "page_number",
+            "page_count",
+            "prev_page",
+            "next_page",
+            "secrets",
+            "noindex",
+            "expires",
+            "preview",
+            "wrap",
+            "line_numbers",
+            "languages",
+        ],
+    ),
+];
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("failed to read {0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("unknown template {0}, partials must start with `_`")]
+    Unknown(String),
+    #[error("{0} uses unknown variables: {1}")]
+    Undeclared(String, String),
+    #[error("{0}")]
+    Template(#[from] minijinja::Error),
+}
+
+/// Templates overriding the embedded ones.
+pub(crate) struct Overrides {
+    env: Environment<'static>,
+}
+
+/// Page with an embedded template that may be overridden.
+pub(crate) trait Overridable: Template {
+    /// Name of the overriding template.
+    const NAME: &'static str;
+
+    fn page(&self) -> &Page;
+
+    /// Variables passed to the overriding template.
+    fn context(&self) -> Value;
+}
+
+/// Response rendering the overriding template if there is one, the embedded one otherwise.
+pub(crate) struct Render<T>(pub T);
+
+impl Arg for Value {
+    fn value(&self) -> FluentValue<'_> {
+        match i64::try_from(self.clone()) {
+            Ok(number) => FluentValue::from(number),
+            Err(_) => FluentValue::from(self.to_string()),
+        }
+    }
+}
+
+/// Translate `key` with the keyword arguments as message arguments.
+#[expect(
+    clippy::needless_pass_by_value,
+    reason = "template functions own their arguments"
+)]
+fn translate(key: &str, kwargs: Kwargs) -> Result<String, minijinja::Error> {
+    let args = kwargs
+        .args()
+        .map(|name| Ok((name, kwargs.get::<Value>(name)?)))
+        .collect::<Result<Vec<_>, minijinja::Error>>()?;
+
+    let args = args
+        .iter()
+        .map(|(name, value)| (*name, value as &dyn Arg))
+        .collect::<Vec<_>>();
+
+    Ok(i18n::translate(key, &args))
+}
+
+impl Overrides {
+    /// Load the `.html` templates in `dir`, which must be named after the pages they override or
+    /// start with `_` to be included or extended by them, and check that they only use known
+    /// variables.
+    pub fn load(dir: &Path) -> Result<Self, Error> {
+        let display = dir.display().to_string();
+        let entries = std::fs::read_dir(dir).map_err(|err| Error::Io(display.clone(), err))?;
+        let mut env = Environment::new();
+
+        env.add_function("t", translate);
+        env.add_function("lang", i18n::lang);
+
+        for entry in entries {
+            let path = entry.map_err(|err| Error::Io(display.clone(), err))?.path();
+
+            if path.extension().is_none_or(|ext| ext != "html") {
+                continue;
+            }
+
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+
+            if !name.starts_with('_') && OVERRIDABLE.iter().all(|(known, _)| *known != name) {
+                return Err(Error::Unknown(name.to_string()));
+            }
+
+            let source = std::fs::read_to_string(&path)
+                .map_err(|err| Error::Io(path.display().to_string(), err))?;
+
+            env.add_template_owned(name.to_string(), source)?;
+        }
+
+        let globals = env
+            .globals()
+            .map(|(name, _)| name.to_string())
+            .collect::<HashSet<_>>();
+
+        for (name, variables) in OVERRIDABLE {
+            let Ok(template) = env.get_template(name) else {
+                continue;
+            };
+
+            let mut unknown = template
+                .undeclared_variables(false)
+                .into_iter()
+                .filter(|var| !variables.contains(&var.as_str()) && !globals.contains(var))
+                .collect::<Vec<_>>();
+
+            if !unknown.is_empty() {
+                unknown.sort();
+                return Err(Error::Undeclared(name.to_string(), unknown.join(", ")));
+            }
+
+            tracing::debug!("overriding {name}");
+        }
+
+        Ok(Self { env })
+    }
+
+    /// Render the template overriding `name` with the variables returned by `context`, if there
+    /// is one.
+    fn render(
+        &self,
+        name: &str,
+        context: impl FnOnce() -> Value,
+    ) -> Option<Result<String, minijinja::Error>> {
+        let template = self.env.get_template(name).ok()?;
+        Some(template.render(context()))
+    }
+}
+
+/// Variables describing the site and its assets, the same for all pages.
+pub(crate) fn page(page: &Page, theme: Option<&Theme>) -> Value {
+    let stylesheets = page
+        .assets
+        .css
+        .stylesheets(theme)
+        .into_iter()
+        .map(|(url, media)| context! { url, media })
+        .collect::<Vec<_>>();
+
+    context! {
+        title => page.title,
+        version => page.version,
+        accounts => page.accounts,
+        noindex => page.noindex,
+        about => page.about.is_some(),
+        max_body_size => page.max_body_size,
+        tab_width => page.tab_width,
+        shortcuts => context! {
+            index => page.shortcuts.json(&Scope::Index),
+            paste => page.shortcuts.json(&Scope::Paste),
+        },
+        stylesheets,
+        style => page.assets.css.style.url(),
+        custom_css => page.assets.custom_css.as_ref().map(Custom::url),
+        logo => page.assets.logo.as_ref().map(Custom::url),
+        footer => page
+            .footer
+            .iter()
+            .map(|(label, url)| context! { label, url })
+            .collect::<Vec<_>>(),
+        favicon => page.assets.favicon.url(),
+        touch_icon => page.assets.touch_icon.as_ref().map(Asset::url),
+        base_js => page.assets.base_js.url(),
+        index_js => page.assets.index_js.url(),
+        paste_js => page.assets.paste_js.url(),
+        pow_js => page.assets.pow_js.url(),
+    }
+}
+
+/// Extensions and names of the languages pastes can be highlighted as.
+pub(crate) fn languages(highlighter: &crate::highlight::Highlighter) -> Vec<Value> {
+    highlighter
+        .syntaxes
+        .iter()
+        .filter_map(|syntax| {
+            let extension = syntax.file_extensions.first()?;
+            Some(context! { extension, name => syntax.name })
+        })
+        .collect()
+}
+
+impl<T: Overridable> IntoResponse for Render<T> {
+    fn into_response(self) -> Response {
+        let rendered = self.0.page().templates.as_ref().and_then(|overrides| {
+            overrides
+                .render(T::NAME, || self.0.context())
+                .map(|rendered| rendered.map_err(|err| format!("{err:#}")))
+        });
+
+        let rendered = rendered.unwrap_or_else(|| self.0.render().map_err(|err| err.to_string()));
+
+        match rendered {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => {
+                tracing::error!("failed to render {}: {err}", T::NAME);
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::insert::form::Entry;
+    use crate::test_helpers::{Client, StoreCookies};
+    use reqwest::StatusCode;
+
+    fn overrides(files: &[(&str, &str)]) -> Result<Overrides, Error> {
+        let dir = tempfile::tempdir().map_err(|err| Error::Io(String::new(), err))?;
+
+        for (name, source) in files {
+            std::fs::write(dir.path().join(name), source)
+                .map_err(|err| Error::Io(String::new(), err))?;
+        }
+
+        Overrides::load(dir.path())
+    }
+
+    #[test]
+    fn validate_at_startup() {
+        assert!(matches!(
+            overrides(&[("indx.html", "")]),
+            Err(Error::Unknown(_))
+        ));
+
+        assert!(matches!(
+            overrides(&[("error.html", "{{ descripton }} {{ range(3)|length }}")]),
+            Err(Error::Undeclared(name, vars)) if name == "error.html" && vars == "descripton"
+        ));
+
+        assert!(matches!(
+            overrides(&[("paste.html", "{% if %}")]),
+            Err(Error::Template(_))
+        ));
+
+        assert!(
+            overrides(&[
+                (
+                    "_layout.html",
+                    "<main>{% block main %}{% endblock %}</main>"
+                ),
+                (
+                    "error.html",
+                    r#"{% extends "_layout.html" %}{% block main %}{{ description }}{% endblock %}"#
+                ),
+            ])
+            .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn override_pages() -> Result<(), Box<dyn std::error::Error>> {
+        let templates = overrides(&[
+            (
+                "_layout.html",
+                r#"<html lang="{{ lang() }}"><title>{{ page.title }}</title>{% block main %}{% endblock %}</html>"#,
+            ),
+            (
+                "index.html",
+                r#"{% extends "_layout.html" %}{% block main %}<form><input name="csrf" value="{{ csrf }}">{% for exp in expirations %}<option>{{ exp.label }}</option>{% endfor %}<button>{{ t("paste") }}</button></form>{% endblock %}"#,
+            ),
+            (
+                "paste.html",
+                r#"{% extends "_layout.html" %}{% block main %}<p>{{ t("pager", page=page_number, pages=page_count) }}</p>{{ html }}{% endblock %}"#,
+            ),
+            (
+                "error.html",
+                r#"{% extends "_layout.html" %}{% block main %}<h1>{{ description }}</h1>{% endblock %}"#,
+            ),
+        ])?;
+
+        let page = crate::test_helpers::page().with_templates(templates);
+        let client = Client::with_page(StoreCookies(false), page).await;
+
+        let res = client.get("/").send().await?;
+        let content = res.text().await?;
+        assert!(content.starts_with(r#"<html lang="en"><title>"#));
+        assert!(content.contains("<option>never</option>"));
+        assert!(content.contains("<button>Paste</button>"));
+
+        let data = Entry {
+            text: String::from("<script>"),
+            ..Default::default()
+        };
+
+        let res = client.post_form().form(&data).send().await?;
+        let location = res.headers().get("location").unwrap().to_str()?.to_string();
+
+        let res = client.get(&location).send().await?;
+        let content = res.text().await?;
+        assert!(content.contains("<p>page 1 of 1</p>"));
+        assert!(content.contains("&lt;script&gt;"));
+        assert!(!content.contains("<script>"));
+
+        let res = client.get("/000000").send().await?;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert!(res.text().await?.contains("<h1>"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use crate::handlers::extract::Session;
+use crate::highlight::markdown;
+use axum::http::HeaderName;
+use axum_extra::extract::cookie::{Cookie, SameSite, SignedCookieJar};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+/// Cookie recording which version of the terms a browser accepted.
+const COOKIE: &str = "terms";
+
+/// Days until browsers ask to accept the terms again.
+const COOKIE_DAYS: i64 = 365;
+
+/// Header anonymous API clients accept the terms with.
+pub(crate) const HEADER: HeaderName = HeaderName::from_static("wastebin-accept-terms");
+
+/// Terms of service anonymous users accept before creating pastes.
+#[derive(Clone, Debug)]
+pub(crate) struct Terms {
+    /// Terms rendered from the operator's Markdown.
+    pub html: Arc<str>,
+    /// Digest of the Markdown, so browsers accept changed terms anew.
+    version: String,
+}
+
+impl Terms {
+    /// Render the terms written in Markdown `source`.
+    pub fn new(source: &str) -> Self {
+        let version = hex::encode(Sha256::digest(source.as_bytes()))
+            .get(0..16)
+            .expect("at least 16 characters")
+            .to_string();
+
+        Self {
+            html: markdown::render(source).into(),
+            version,
+        }
+    }
+
+    /// Return `true` if the cookies in `jar` show that the current terms were accepted.
+    pub fn is_accepted(&self, jar: &SignedCookieJar) -> bool {
+        jar.get(COOKIE)
+            .is_some_and(|cookie| cookie.value_trimmed() == self.version)
+    }
+
+    /// Cookie recording the acceptance of the current terms.
+    pub fn cookie(&self, secure: bool) -> Cookie<'static> {
+        Cookie::build((COOKIE, self.version.clone()))
+            .path("/")
+            .http_only(true)
+            .secure(secure)
+            .same_site(SameSite::Strict)
+            .max_age(time::Duration::days(COOKIE_DAYS))
+            .build()
+    }
+}
+
+/// Return `true` if a request with the cookies in `jar` comes from an anonymous user yet to accept
+/// the terms of service.
+pub(crate) fn pending(
+    terms: Option<&Terms>,
+    session: Option<&Session>,
+    jar: &SignedCookieJar,
+) -> bool {
+    session.is_none() && terms.is_some_and(|terms| !terms.is_accepted(jar))
+}
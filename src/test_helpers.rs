@@ -3,17 +3,80 @@ use crate::db::{self, Database};
 use crate::expiration::ExpirationSet;
 use crate::highlight::{Highlighter, Theme};
 use crate::page;
+use axum_extra::extract::SignedCookieJar;
 use axum_extra::extract::cookie::Key;
 use reqwest::RequestBuilder;
+use reqwest::cookie::{CookieStore, Jar};
 use std::net::SocketAddr;
 use std::num::NonZeroUsize;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::net::TcpListener;
 
+/// Page of the test server with accounts and registration enabled.
+pub(crate) fn page() -> page::Page {
+    let expirations = "0".parse::<ExpirationSet>().unwrap();
+
+    page::Page::new(
+        String::from("test"),
+        url::Url::parse("https://localhost:8888").unwrap(),
+        &Theme::Ayu,
+        &[],
+        expirations,
+    )
+    .with_accounts(true)
+}
+
+/// Credentials of the account `name` with a password long enough to register it.
+pub(crate) fn credentials(name: &str) -> crate::handlers::account::Credentials {
+    crate::handlers::account::Credentials {
+        name: name.to_string(),
+        password: String::from("correct horse"),
+    }
+}
+
+pub(crate) fn state(page: page::Page, highlighter: Highlighter) -> crate::AppState {
+    crate::AppState {
+        db: Database::new(db::Open::Memory).expect("open memory database"),
+        access: Arc::default(),
+        cache: Cache::new(NonZeroUsize::new(128).unwrap()),
+        key: Key::generate(),
+        previous_keys: Arc::new([]),
+        page: Arc::new(page),
+        highlighter: Arc::new(highlighter),
+        ldap: None,
+        #[cfg(feature = "oidc")]
+        oidc: None,
+        #[cfg(feature = "captcha")]
+        captcha: None,
+        pow: None,
+        fill_time: None,
+        blocklist: crate::blocklist::Blocklist::default(),
+        clamav: None,
+        notifier: None,
+        limiter: crate::limits::Limiter::default(),
+        requests: crate::metrics::Requests::default(),
+        metrics: None,
+        log_filter: None,
+        readiness: crate::health::Readiness::default(),
+        access_log: None,
+        compression: crate::compression::Filter::default(),
+        cache_control: Arc::default(),
+        minify: crate::minify::Enabled(true),
+        locales: crate::i18n::Catalog::default(),
+        language_stats: crate::handlers::stats::Languages::default(),
+        sitemaps: crate::handlers::sitemap::Sitemaps::default(),
+        #[cfg(feature = "sentry")]
+        sentry: None,
+    }
+}
+
 pub(crate) struct Client {
-    client: reqwest::Client,
+    inner: reqwest::Client,
     addr: SocketAddr,
+    key: Key,
+    cookies: Option<Arc<Jar>>,
+    readiness: crate::health::Readiness,
 }
 
 /// Determine if the client should store cookies.
@@ -21,62 +84,261 @@ pub(crate) struct StoreCookies(pub bool);
 
 impl Client {
     pub(crate) async fn new(store_cookies: StoreCookies) -> Self {
-        let db = Database::new(db::Open::Memory).expect("open memory database");
-        let cache = Cache::new(NonZeroUsize::new(128).unwrap());
-        let key = Key::generate();
-        let expirations = "0".parse::<ExpirationSet>().unwrap();
-        let page = Arc::new(page::Page::new(
-            String::from("test"),
-            url::Url::parse("https://localhost:8888").unwrap(),
-            Theme::Ayu,
-            expirations,
-        ));
-        let state = crate::AppState {
-            db,
-            cache,
-            key,
-            page,
-            highlighter: Arc::new(Highlighter::default()),
+        Self::with_highlighter(store_cookies, Highlighter::default()).await
+    }
+
+    /// Create a client for a server that uses `highlighter`.
+    pub(crate) async fn with_highlighter(
+        store_cookies: StoreCookies,
+        highlighter: Highlighter,
+    ) -> Self {
+        Self::with_state(store_cookies, state(page(), highlighter)).await
+    }
+
+    /// Create a client for a server that serves `page`.
+    pub(crate) async fn with_page(store_cookies: StoreCookies, page: page::Page) -> Self {
+        Self::with_state(store_cookies, state(page, Highlighter::default())).await
+    }
+
+    /// Create a client for a server that serves `page` with the data in `db`.
+    pub(crate) async fn with_database(
+        store_cookies: StoreCookies,
+        page: page::Page,
+        db: Database,
+    ) -> Self {
+        let mut state = state(page, Highlighter::default());
+        state.db = db;
+
+        Self::with_state(store_cookies, state).await
+    }
+
+    /// Create a client for a server that enforces `limit` on anonymous addresses.
+    pub(crate) async fn with_daily_limit(
+        store_cookies: StoreCookies,
+        limit: crate::limits::DailyLimit,
+    ) -> Self {
+        let mut state = state(page(), Highlighter::default());
+        state.limiter = crate::limits::Limiter::new(limit, None);
+
+        Self::with_state(store_cookies, state).await
+    }
+
+    /// Create a client for a server that logs in via the OIDC provider at `issuer`
+    /// with client id `wastebin`.
+    #[cfg(feature = "oidc")]
+    pub(crate) async fn with_oidc(store_cookies: StoreCookies, issuer: &str) -> Self {
+        let mut state = state(page().with_oidc(), Highlighter::default());
+
+        state.oidc = Some(Arc::new(crate::oidc::Provider::new(
+            issuer.to_string(),
+            String::from("wastebin"),
+            String::from("secret"),
+        )));
+
+        Self::with_state(store_cookies, state).await
+    }
+
+    /// Create a client for a server that asks anonymous users to solve a Turnstile captcha
+    /// verified at `url`, letting API requests bypass it with `bypass`.
+    #[cfg(feature = "captcha")]
+    pub(crate) async fn with_captcha(
+        store_cookies: StoreCookies,
+        url: &str,
+        bypass: Option<&str>,
+    ) -> Self {
+        use crate::captcha::{Provider, Verifier, Widget};
+
+        let widget = Widget {
+            provider: Provider::Turnstile,
+            site_key: String::from("site-key"),
         };
+        let mut state = state(page().with_captcha(widget), Highlighter::default());
+
+        state.captcha = Some(Arc::new(
+            Verifier::new(
+                Provider::Turnstile,
+                String::from("secret"),
+                bypass.map(String::from),
+            )
+            .with_url(url.to_string()),
+        ));
+
+        Self::with_state(store_cookies, state).await
+    }
+
+    /// Create a client for a server that asks anonymous users for proof of work of
+    /// `difficulty` bits.
+    pub(crate) async fn with_pow(store_cookies: StoreCookies, difficulty: u8) -> Self {
+        let mut state = state(page().with_pow(), Highlighter::default());
+        state.pow = Some(crate::pow::Pow::new(state.key.clone(), difficulty));
+
+        Self::with_state(store_cookies, state).await
+    }
+
+    /// Create a client for a server that rejects anonymous forms submitted within `min` of
+    /// loading them.
+    pub(crate) async fn with_min_fill_time(store_cookies: StoreCookies, min: Duration) -> Self {
+        let mut state = state(page(), Highlighter::default());
+        state.fill_time = Some(crate::spam::FillTime::new(state.key.clone(), min));
 
+        Self::with_state(store_cookies, state).await
+    }
+
+    /// Create a client for a server that serves `page` and checks new pastes against the
+    /// `blocklist` rules.
+    pub(crate) async fn with_blocklist(
+        store_cookies: StoreCookies,
+        page: page::Page,
+        blocklist: &str,
+    ) -> Self {
+        let mut state = state(page, Highlighter::default());
+        state.blocklist = blocklist.parse().unwrap();
+
+        Self::with_state(store_cookies, state).await
+    }
+
+    /// Create a client for a server that scans new pastes with clamd at `address`.
+    pub(crate) async fn with_clamav(
+        store_cookies: StoreCookies,
+        address: crate::clamav::Address,
+    ) -> Self {
+        let mut state = state(page(), Highlighter::default());
+        state.clamav = Some(crate::clamav::Scanner::new(address));
+
+        Self::with_state(store_cookies, state).await
+    }
+
+    /// Create a client for a server restricting client addresses by `access` rules.
+    pub(crate) async fn with_access(
+        store_cookies: StoreCookies,
+        access: crate::access::Access,
+    ) -> Self {
+        let mut state = state(page(), Highlighter::default());
+        state.access = Arc::new(access);
+
+        Self::with_state(store_cookies, state).await
+    }
+
+    /// Create a client for a server that serves `page` and lets admins change `log_filter`.
+    pub(crate) async fn with_log_filter(
+        store_cookies: StoreCookies,
+        page: page::Page,
+        log_filter: crate::logging::Filter,
+    ) -> Self {
+        let mut state = state(page, Highlighter::default());
+        state.log_filter = Some(log_filter);
+
+        Self::with_state(store_cookies, state).await
+    }
+
+    /// Create a client for a server writing requests to `log`.
+    pub(crate) async fn with_access_log(
+        store_cookies: StoreCookies,
+        log: crate::access_log::AccessLog,
+    ) -> Self {
+        let mut state = state(page(), Highlighter::default());
+        state.access_log = Some(log);
+
+        Self::with_state(store_cookies, state).await
+    }
+
+    /// Create a client for a server that overrides cache headers according to `policy`.
+    pub(crate) async fn with_cache_control(
+        store_cookies: StoreCookies,
+        policy: crate::cache_control::Policy,
+    ) -> Self {
+        let mut state = state(page(), Highlighter::default());
+        state.cache_control = Arc::new(policy);
+
+        Self::with_state(store_cookies, state).await
+    }
+
+    /// Create a client for a server exposing metrics next to all other routes.
+    pub(crate) async fn with_metrics(store_cookies: StoreCookies) -> Self {
+        let mut state = state(page(), Highlighter::default());
+        state.metrics = Some(crate::metrics::Endpoint::Main);
+
+        Self::with_state(store_cookies, state).await
+    }
+
+    async fn with_state(store_cookies: StoreCookies, state: crate::AppState) -> Self {
         let listener = TcpListener::bind("127.0.0.1:0")
             .await
             .expect("Could not bind ephemeral socket");
 
         let addr = listener.local_addr().unwrap();
+        let key = state.key.clone();
+        let readiness = state.readiness.clone();
 
         tokio::spawn(async move {
-            crate::serve(listener, state, Duration::new(30, 0), 1024 * 1024)
-                .await
-                .unwrap();
+            crate::serve(
+                listener,
+                state,
+                Duration::new(30, 0),
+                1024 * 1024,
+                crate::shutdown_signal(),
+            )
+            .await
+            .unwrap();
         });
 
-        let client = reqwest::Client::builder()
-            .redirect(reqwest::redirect::Policy::none())
-            .cookie_store(store_cookies.0)
-            .build()
-            .unwrap();
+        let cookies = store_cookies.0.then(|| Arc::new(Jar::default()));
+        let mut builder = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+
+        if let Some(cookies) = &cookies {
+            builder = builder.cookie_provider(Arc::clone(cookies));
+        }
 
-        Self { client, addr }
+        Self {
+            inner: builder.build().unwrap(),
+            addr,
+            key,
+            cookies,
+            readiness,
+        }
+    }
+
+    /// Readiness of the server, to simulate a shutdown.
+    pub(crate) fn readiness(&self) -> &crate::health::Readiness {
+        &self.readiness
+    }
+
+    /// CSRF token for the cookies currently stored, sent along like a browser submitting a form.
+    fn csrf_token(&self) -> String {
+        let url = format!("http://{}/", self.addr).parse().unwrap();
+        let mut headers = http::HeaderMap::new();
+
+        if let Some(cookies) = self.cookies.as_ref().and_then(|jar| jar.cookies(&url)) {
+            headers.insert(http::header::COOKIE, cookies);
+        }
+
+        let jar = SignedCookieJar::from_headers(&headers, self.key.clone());
+        crate::csrf::token(&self.key, &jar)
     }
 
     pub(crate) fn get(&self, url: &str) -> RequestBuilder {
-        self.client.get(format!("http://{}{}", self.addr, url))
+        self.inner.get(format!("http://{}{}", self.addr, url))
     }
 
     pub(crate) fn post(&self, url: &str) -> RequestBuilder {
-        self.client.post(format!("http://{}{}", self.addr, url))
+        self.post_forged(url)
+            .header(crate::csrf::HEADER, self.csrf_token())
+    }
+
+    /// POST request to `url` without the CSRF token, as if sent from another site.
+    pub(crate) fn post_forged(&self, url: &str) -> RequestBuilder {
+        self.inner.post(format!("http://{}{}", self.addr, url))
     }
 
     pub(crate) fn post_form(&self) -> RequestBuilder {
-        self.client.post(format!("http://{}/new", self.addr))
+        self.post("/new")
     }
 
     pub(crate) fn post_json(&self) -> RequestBuilder {
-        self.client.post(format!("http://{}/", self.addr))
+        self.inner.post(format!("http://{}/", self.addr))
     }
 
     pub(crate) fn delete(&self, url: &str) -> RequestBuilder {
-        self.client.delete(format!("http://{}{}", self.addr, url))
+        self.inner.delete(format!("http://{}{}", self.addr, url))
     }
 }
@@ -0,0 +1,113 @@
+use crate::highlight::escape;
+use std::str::FromStr;
+use time::format_description::FormatItem;
+use time::macros::format_description;
+use time::{PrimitiveDateTime, UtcOffset};
+
+/// Format of timestamps stored by the database, always in UTC.
+const STORED: &[FormatItem<'_>] =
+    format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
+
+const SHOWN: &[FormatItem<'_>] = format_description!("[year]-[month]-[day] [hour]:[minute]");
+
+const ISO: &[FormatItem<'_>] =
+    format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]Z");
+
+const OFFSET: &[FormatItem<'_>] =
+    format_description!("[offset_hour sign:mandatory]:[offset_minute]");
+
+const OFFSET_HOURS: &[FormatItem<'_>] = format_description!("[offset_hour sign:mandatory]");
+
+/// Time zone timestamps are shown in until the browser replaces them with the viewer's local
+/// time, a fixed offset from UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Timezone(UtcOffset);
+
+impl Default for Timezone {
+    fn default() -> Self {
+        Self(UtcOffset::UTC)
+    }
+}
+
+impl FromStr for Timezone {
+    type Err = time::error::Parse;
+
+    /// Parse `UTC` or an offset like `+02:00` or `-05`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if s.eq_ignore_ascii_case("utc") || s == "Z" {
+            return Ok(Self(UtcOffset::UTC));
+        }
+
+        UtcOffset::parse(s, OFFSET)
+            .or_else(|_| UtcOffset::parse(s, OFFSET_HOURS))
+            .map(Self)
+    }
+}
+
+impl Timezone {
+    /// `<time>` element showing the stored UTC `timestamp` in this time zone, which the page
+    /// script formats for the viewer's locale and time zone. Timestamps that cannot be parsed are
+    /// shown as they are.
+    pub fn time(self, timestamp: &str) -> String {
+        let Ok(utc) =
+            PrimitiveDateTime::parse(timestamp, STORED).map(PrimitiveDateTime::assume_utc)
+        else {
+            return escape(timestamp);
+        };
+
+        let local = utc.to_offset(self.0);
+        let shown = local.format(SHOWN).unwrap_or_default();
+        let zone = if self.0.is_utc() {
+            String::from("UTC")
+        } else {
+            local.format(OFFSET).unwrap_or_default()
+        };
+
+        format!(
+            r#"<time datetime="{}" class="local-time">{shown} {zone}</time>"#,
+            utc.format(ISO).unwrap_or_default()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!("UTC".parse::<Timezone>()?, Timezone::default());
+        assert_eq!(
+            "+02:00".parse::<Timezone>()?,
+            Timezone(UtcOffset::from_hms(2, 0, 0)?)
+        );
+        assert_eq!(
+            "-05:30".parse::<Timezone>()?,
+            Timezone(UtcOffset::from_hms(-5, -30, 0)?)
+        );
+        assert_eq!(
+            "+09".parse::<Timezone>()?,
+            Timezone(UtcOffset::from_hms(9, 0, 0)?)
+        );
+        assert!("Europe/Berlin".parse::<Timezone>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn format() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(
+            Timezone::default().time("2024-05-01 23:30:00"),
+            r#"<time datetime="2024-05-01T23:30:00Z" class="local-time">2024-05-01 23:30 UTC</time>"#
+        );
+        assert_eq!(
+            "+02:00".parse::<Timezone>()?.time("2024-05-01 23:30:00"),
+            r#"<time datetime="2024-05-01T23:30:00Z" class="local-time">2024-05-02 01:30 +02:00</time>"#
+        );
+        assert_eq!(Timezone::default().time("<soon>"), "&lt;soon&gt;");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha1::Sha1;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds each code is valid. Together with HMAC-SHA1 and six digits, these are the RFC 6238
+/// defaults authenticator apps expect.
+const STEP: u64 = 30;
+
+/// Number of digits of a code.
+const DIGITS: u32 = 6;
+
+/// Number of recovery codes generated when enabling two-factor authentication.
+pub const RECOVERY_CODES: usize = 10;
+
+/// Generate a new random 160 bit secret.
+pub fn new_secret() -> Vec<u8> {
+    rand::rng().random::<[u8; 20]>().to_vec()
+}
+
+/// Generate a new random recovery code like `1a2b3-c4d5e`.
+pub fn new_recovery_code() -> String {
+    let code = hex::encode(rand::rng().random::<[u8; 5]>());
+    format!("{}-{}", &code[..5], &code[5..])
+}
+
+/// Encode `bytes` as unpadded RFC 4648 base32 as expected by authenticator apps.
+pub fn base32(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+
+    for byte in bytes {
+        buffer = (buffer << 8) | u16::from(*byte);
+        bits += 8;
+
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(char::from(ALPHABET[usize::from((buffer >> bits) & 31)]));
+        }
+    }
+
+    if bits > 0 {
+        encoded.push(char::from(
+            ALPHABET[usize::from((buffer << (5 - bits)) & 31)],
+        ));
+    }
+
+    encoded
+}
+
+/// Decode unpadded base32 `encoded`, ignoring case and whitespace.
+pub fn decode_base32(encoded: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(encoded.len() * 5 / 8);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+
+    for c in encoded.chars().filter(|c| !c.is_whitespace()) {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u16 - u16::from(b'A'),
+            c @ '2'..='7' => c as u16 - u16::from(b'2') + 26,
+            _ => return None,
+        };
+
+        buffer = (buffer << 5) | value;
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push(((buffer >> bits) & 0xff) as u8);
+        }
+    }
+
+    Some(decoded)
+}
+
+/// Percent-encode `s` for use in an URI.
+fn encode(s: &str) -> String {
+    // Literal plus signs are encoded, so the remaining ones are spaces.
+    url::form_urlencoded::byte_serialize(s.as_bytes())
+        .collect::<String>()
+        .replace('+', "%20")
+}
+
+/// Provisioning URI for authenticator apps identifying `account` at `issuer`.
+pub fn uri(secret: &[u8], issuer: &str, account: &str) -> String {
+    let label = encode(&format!("{issuer}:{account}"));
+    let issuer = encode(issuer);
+
+    format!(
+        "otpauth://totp/{label}?secret={}&issuer={issuer}",
+        base32(secret)
+    )
+}
+
+/// Code for time `step` derived from `secret`.
+pub fn code(secret: &[u8], step: u64) -> u32 {
+    // HMAC accepts keys of any length.
+    let Ok(mut mac) = Hmac::<Sha1>::new_from_slice(secret) else {
+        return 0;
+    };
+
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = usize::from(hash[19] & 0xf);
+    let truncated = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+
+    truncated % 10u32.pow(DIGITS)
+}
+
+/// Current time step.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() / STEP)
+}
+
+/// Return the time step `input` is valid for if it matches the code of `secret` for step `now`
+/// or one of its neighbours, allowing for clock drift.
+pub fn verify(secret: &[u8], input: &str, now: u64) -> Option<u64> {
+    let input = input.trim().replace(' ', "");
+
+    if input.len() != DIGITS as usize {
+        return None;
+    }
+
+    let input = input.parse::<u32>().ok()?;
+
+    [now.saturating_sub(1), now, now + 1]
+        .into_iter()
+        .find(|step| code(secret, *step) == input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Secret used in the test vectors of RFC 6238.
+    const SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn rfc_test_vectors() {
+        for (time, expected) in [
+            (59, 287_082),
+            (1_111_111_109, 81_804),
+            (1_234_567_890, 5_924),
+            (20_000_000_000, 353_130),
+        ] {
+            assert_eq!(code(SECRET, time / STEP), expected);
+        }
+    }
+
+    #[test]
+    fn verify_with_drift() {
+        let step = 1_234_567_890 / STEP;
+
+        assert_eq!(verify(SECRET, "005924", step), Some(step));
+        assert_eq!(verify(SECRET, "005 924", step + 1), Some(step));
+        assert_eq!(verify(SECRET, "005924", step + 2), None);
+        assert_eq!(verify(SECRET, "5924", step), None);
+        assert_eq!(verify(SECRET, "abcdef", step), None);
+    }
+
+    #[test]
+    fn base32_round_trip() {
+        assert_eq!(base32(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(decode_base32("mzxw 6ytb oi"), Some(b"foobar".to_vec()));
+        assert_eq!(decode_base32("MZXW1"), None);
+
+        let secret = new_secret();
+        assert_eq!(decode_base32(&base32(&secret)), Some(secret));
+    }
+
+    #[test]
+    fn provisioning_uri() {
+        assert_eq!(
+            uri(b"foobar", "my bin", "alice"),
+            "otpauth://totp/my%20bin%3Aalice?secret=MZXW6YTBOI&issuer=my%20bin"
+        );
+    }
+}
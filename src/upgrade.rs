@@ -0,0 +1,238 @@
+use std::io;
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+
+#[cfg(unix)]
+use std::ffi::OsStr;
+#[cfg(unix)]
+use std::os::fd::{AsFd, OwnedFd};
+#[cfg(unix)]
+use std::process::ExitStatus;
+#[cfg(unix)]
+use std::time::Duration;
+
+/// Variable with the socket on which a new process reports readiness to the one it replaces.
+#[cfg(unix)]
+const VAR_UPGRADE_SOCKET: &str = "WASTEBIN_UPGRADE_SOCKET";
+
+/// Time a new process has to start serving before the upgrade is abandoned.
+#[cfg(unix)]
+const READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Reasons a new process could not take over the listening sockets.
+#[cfg(unix)]
+#[derive(thiserror::Error, Debug)]
+enum Error {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("failed to pass sockets: {0}")]
+    Mapping(#[from] command_fds::FdMappingCollision),
+    #[error("unknown program name")]
+    Program,
+    #[error("new process exited with {0}")]
+    Exited(ExitStatus),
+    #[error("new process was not ready within {READY_TIMEOUT:?}")]
+    Timeout,
+}
+
+/// Listening sockets passed by systemd socket activation or by the process this one replaces.
+pub(crate) struct Inherited(listenfd::ListenFd);
+
+impl Inherited {
+    pub fn from_env() -> Self {
+        Self(listenfd::ListenFd::from_env())
+    }
+
+    /// Take the inherited listener at `index` or bind a new one to `addr`.
+    pub async fn tcp(&mut self, index: usize, addr: SocketAddr) -> io::Result<TcpListener> {
+        let Some(listener) = self.0.take_tcp_listener(index)? else {
+            return TcpListener::bind(addr).await;
+        };
+
+        listener.set_nonblocking(true)?;
+        let listener = TcpListener::from_std(listener)?;
+        tracing::debug!("serving on inherited socket {}", listener.local_addr()?);
+
+        Ok(listener)
+    }
+}
+
+/// Send `state` to the datagram socket at `path`, abstract if it starts with `@`.
+#[cfg(unix)]
+fn send(path: &OsStr, state: &str) -> io::Result<()> {
+    let socket = std::os::unix::net::UnixDatagram::unbound()?;
+
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::linux::net::SocketAddrExt;
+        use std::os::unix::ffi::OsStrExt;
+
+        if let Some(name) = path.as_bytes().strip_prefix(b"@") {
+            let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            socket.send_to_addr(state.as_bytes(), &addr)?;
+            return Ok(());
+        }
+    }
+
+    socket.send_to(state.as_bytes(), path)?;
+
+    Ok(())
+}
+
+/// Tell the service manager about `state`, e.g. `STOPPING=1`, if started with `NOTIFY_SOCKET`.
+pub(crate) fn notify(state: &str) {
+    #[cfg(unix)]
+    if let Some(path) = std::env::var_os("NOTIFY_SOCKET") {
+        if let Err(err) = send(&path, state) {
+            tracing::warn!("failed to notify service manager: {err}");
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = state;
+}
+
+/// Report readiness to the service manager and to the process this one replaces, if any.
+pub(crate) fn ready() {
+    notify("READY=1");
+
+    #[cfg(unix)]
+    if let Some(path) = std::env::var_os(VAR_UPGRADE_SOCKET) {
+        if let Err(err) = send(&path, "READY=1") {
+            tracing::warn!("failed to report readiness to previous process: {err}");
+        }
+    }
+}
+
+/// Duplicates of the listening sockets to pass on to a new process.
+#[cfg(unix)]
+pub(crate) struct Handover {
+    fds: Vec<OwnedFd>,
+}
+
+#[cfg(unix)]
+impl Handover {
+    pub fn new(listener: &TcpListener) -> io::Result<Self> {
+        let mut handover = Self { fds: Vec::new() };
+        handover.add(listener)?;
+        Ok(handover)
+    }
+
+    /// Pass `listener` as the next socket, for the new process to take by its index.
+    pub fn add(&mut self, listener: &TcpListener) -> io::Result<()> {
+        self.fds.push(listener.as_fd().try_clone_to_owned()?);
+        Ok(())
+    }
+
+    /// Start the binary again with the same arguments, picking up a replaced executable, pass it
+    /// the listening sockets like systemd socket activation does and wait until it is ready.
+    /// Returns the process ID of the new process.
+    async fn spawn(&self) -> Result<u32, Error> {
+        use command_fds::{CommandFdExt, FdMapping};
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("ready");
+        let socket = tokio::net::UnixDatagram::bind(&path)?;
+
+        let mappings = self
+            .fds
+            .iter()
+            .zip(3..)
+            .map(|(fd, child_fd)| {
+                Ok(FdMapping {
+                    parent_fd: fd.try_clone()?,
+                    child_fd,
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let mut args = std::env::args_os().collect::<Vec<_>>().into_iter();
+        let program = args.next().ok_or(Error::Program)?;
+
+        let mut child = tokio::process::Command::new(program)
+            .args(args)
+            .env("LISTEN_FDS", self.fds.len().to_string())
+            .env_remove("LISTEN_PID")
+            .env_remove("LISTEN_FDNAMES")
+            .env_remove("LISTEN_FDS_FIRST_FD")
+            .env(VAR_UPGRADE_SOCKET, &path)
+            .fd_mappings(mappings)?
+            .spawn()?;
+
+        let pid = child.id().unwrap_or_default();
+        let mut buf = [0; 64];
+
+        tokio::select! {
+            status = child.wait() => Err(Error::Exited(status?)),
+            received = tokio::time::timeout(READY_TIMEOUT, socket.recv(&mut buf)) => match received {
+                Ok(Ok(_)) => Ok(pid),
+                Ok(Err(err)) => Err(err.into()),
+                Err(_) => {
+                    child.start_kill()?;
+                    Err(Error::Timeout)
+                }
+            },
+        }
+    }
+}
+
+/// Wait for `SIGUSR2` and hand the listening sockets over to a new process, returning once it
+/// serves. After a failed attempt the current process keeps serving and waits for the next signal.
+#[cfg(unix)]
+pub(crate) async fn requested(handover: Handover) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut upgrade = match signal(SignalKind::user_defined2()) {
+        Ok(upgrade) => upgrade,
+        Err(err) => {
+            tracing::warn!("failed to install upgrade signal handler: {err}");
+            return std::future::pending().await;
+        }
+    };
+
+    while upgrade.recv().await.is_some() {
+        tracing::info!("received SIGUSR2, starting new process ...");
+
+        match handover.spawn().await {
+            Ok(pid) => {
+                notify(&format!("MAINPID={pid}"));
+                tracing::info!("process {pid} took over, exiting ...");
+                return;
+            }
+            Err(err) => tracing::error!("failed to upgrade: {err}"),
+        }
+    }
+
+    std::future::pending::<()>().await;
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notify_socket() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("notify");
+        let socket = std::os::unix::net::UnixDatagram::bind(&path)?;
+
+        send(path.as_os_str(), "READY=1")?;
+
+        let mut buf = [0; 16];
+        let len = socket.recv(&mut buf)?;
+        assert_eq!(&buf[..len], b"READY=1");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn bind_without_inherited() -> Result<(), Box<dyn std::error::Error>> {
+        let mut inherited = Inherited(listenfd::ListenFd::empty());
+        let listener = inherited.tcp(0, "127.0.0.1:0".parse()?).await?;
+        let handover = Handover::new(&listener)?;
+
+        assert_eq!(handover.fds.len(), 1);
+
+        Ok(())
+    }
+}